@@ -20,6 +20,16 @@
 
 #define IPC_CHANNEL_ERROR 4
 
+/**
+ * `IpcFrame::tag` for payloads owned by the frame; release with `ipc_frame_free`.
+ */
+#define IPC_FRAME_TAG_HEAP 0
+
+/**
+ * `IpcFrame::tag` for payloads owned by an arena; never pass to `ipc_frame_free`.
+ */
+#define IPC_FRAME_TAG_ARENA 165
+
 enum IpcResult {
     IPC_RESULT_OK = 0,
     IPC_RESULT_INVALID_ARGUMENT = 1,
@@ -36,12 +46,31 @@ enum IpcResult {
 };
 typedef int32_t IpcResult;
 
+/**
+ * Received frame. `tag` records who owns `data` and sits in what was
+ * padding, so the layout is unchanged; zero-initialize new frames.
+ */
 typedef struct IpcFrame {
     uint16_t channel;
+    uint8_t tag;
     uint8_t *data;
     uintptr_t len;
 } IpcFrame;
 
+/**
+ * Listener counters filled by `ipc_listener_metrics`.
+ *
+ * `last_accept_at_ms` is Unix milliseconds, or 0 before the first accept.
+ * The per-kind failure breakdown is only available from Rust and the CLI.
+ */
+typedef struct IpcListenerMetrics {
+    uint64_t accepted;
+    uint64_t handshake_failures;
+    uint64_t last_accept_at_ms;
+} IpcListenerMetrics;
+
+typedef void *IpcArenaHandle;
+
 typedef void *IpcListenerHandle;
 
 typedef void *IpcPeerHandle;
@@ -78,9 +107,45 @@ void ipc_cleanup(void);
 
 const char *ipc_last_error(void);
 
+/**
+ * Stable frame error code (see `FrameErrorCode`) behind the last
+ * `IPC_ERR_FRAME` on this thread, or 0 if the last error was not a frame error.
+ */
+uint16_t ipc_last_frame_error_code(void);
+
+/**
+ * Create a payload arena for `ipc_peer_recv_arena`.
+ *
+ * Payloads are packed into slabs of `slab_size` bytes; larger payloads get
+ * their own allocation. Returns null if `slab_size` is zero.
+ */
+IpcArenaHandle ipc_arena_new(uintptr_t slab_size);
+
+/**
+ * Release every payload allocated from `arena`, keeping its slabs for reuse.
+ *
+ * # Safety
+ * `arena` must be a valid handle returned by `ipc_arena_new`. Every frame
+ * filled from this arena is invalidated: its `data` pointer must not be read
+ * afterwards.
+ */
+IpcResult ipc_arena_reset(IpcArenaHandle arena);
+
+/**
+ * Free an arena and every payload allocated from it.
+ *
+ * # Safety
+ * `arena` must be null or a handle returned by `ipc_arena_new`. Frames filled
+ * from this arena are invalidated, as with `ipc_arena_reset`.
+ */
+void ipc_arena_free(IpcArenaHandle arena);
+
 /**
  * Free payload memory held by an [`IpcFrame`] populated by recv APIs.
  *
+ * Frames filled by `ipc_peer_recv_arena` are left untouched and an error is
+ * recorded for `ipc_last_error`; their payloads belong to the arena.
+ *
  * # Safety
  * `frame` must be either null or a valid pointer to an `IpcFrame` created by caller code.
  * If `frame->data` is non-null, it must have originated from this library.
@@ -103,6 +168,15 @@ IpcListenerHandle ipc_listener_bind(const char *path);
  */
 IpcPeerHandle ipc_listener_accept(IpcListenerHandle listener);
 
+/**
+ * Copy the listener's accept and handshake-failure counters into `out_metrics`.
+ *
+ * # Safety
+ * `listener` must be a valid listener handle and `out_metrics` must be a
+ * non-null writable pointer.
+ */
+IpcResult ipc_listener_metrics(IpcListenerHandle listener, struct IpcListenerMetrics *out_metrics);
+
 /**
  * Free a listener handle.
  *
@@ -147,6 +221,19 @@ IpcResult ipc_peer_recv(IpcPeerHandle peer, struct IpcFrame *out_frame);
  */
 IpcResult ipc_peer_recv_on(IpcPeerHandle peer, uint16_t channel, struct IpcFrame *out_frame);
 
+/**
+ * Receive the next non-control frame into `arena` instead of a per-frame allocation.
+ *
+ * The payload stays valid until `ipc_arena_reset` or `ipc_arena_free` is called on
+ * `arena`. Frames filled this way must not be passed to `ipc_frame_free`.
+ *
+ * # Safety
+ * `peer` must be a valid peer handle, `arena` a valid handle from `ipc_arena_new`, and
+ * `out_frame` a valid writable pointer. If `out_frame->data` holds a prior heap payload
+ * from this library, it is freed first; a prior arena payload is left to its arena.
+ */
+IpcResult ipc_peer_recv_arena(IpcPeerHandle peer, IpcArenaHandle arena, struct IpcFrame *out_frame);
+
 /**
  * Send a control ping and return round-trip time in nanoseconds.
  *
@@ -163,6 +250,18 @@ IpcResult ipc_peer_ping(IpcPeerHandle peer, uint64_t *out_rtt_ns);
  */
 IpcResult ipc_peer_shutdown(IpcPeerHandle peer);
 
+/**
+ * ipcprims version the remote side reported during the handshake.
+ *
+ * Returns null if the remote did not report one (older peers, or
+ * `send_library_version` disabled). The string is owned by `peer` and stays
+ * valid until `ipc_peer_free`.
+ *
+ * # Safety
+ * `peer` must be a valid peer handle.
+ */
+const char *ipc_peer_remote_library_version(IpcPeerHandle peer);
+
 /**
  * Free a peer handle.
  *
@@ -95,6 +95,7 @@ impl Peer {
             .take()
             .ok_or_else(|| invalid_state("peer is closed"))?;
         peer.shutdown()
+            .map(|_| ())
             .map_err(|err| to_napi_error("shutdown failed", err))
     }
 
@@ -0,0 +1,221 @@
+use std::ptr;
+
+use crate::error;
+use crate::types::{IpcArenaHandle, IpcResult};
+
+/// Bump allocator for received payloads.
+///
+/// Payloads are copied into fixed-size slabs; one larger than a slab gets a
+/// dedicated allocation. Nothing is freed individually: `reset` rewinds to the
+/// first slab (keeping slab memory for reuse) and drop releases everything.
+/// Slabs are held as raw pointers because C keeps reading earlier payloads
+/// while later ones are written.
+pub(crate) struct Arena {
+    slab_size: usize,
+    slabs: Vec<*mut u8>,
+    current: usize,
+    offset: usize,
+    oversized: Vec<(*mut u8, usize)>,
+}
+
+impl Arena {
+    pub(crate) fn new(slab_size: usize) -> Self {
+        Self {
+            slab_size,
+            slabs: Vec::new(),
+            current: 0,
+            offset: 0,
+            oversized: Vec::new(),
+        }
+    }
+
+    /// Copy `payload` into the arena. Returns null for an empty payload.
+    pub(crate) fn alloc(&mut self, payload: &[u8]) -> *mut u8 {
+        let len = payload.len();
+        if len == 0 {
+            return ptr::null_mut();
+        }
+
+        let dst = if len > self.slab_size {
+            let dst = Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8;
+            self.oversized.push((dst, len));
+            dst
+        } else {
+            if self.current == self.slabs.len() || self.offset + len > self.slab_size {
+                if self.current < self.slabs.len() {
+                    self.current += 1;
+                }
+                if self.current == self.slabs.len() {
+                    let slab = vec![0u8; self.slab_size].into_boxed_slice();
+                    self.slabs.push(Box::into_raw(slab) as *mut u8);
+                }
+                self.offset = 0;
+            }
+            // SAFETY: `offset + len <= slab_size`, so the range is inside the slab.
+            let dst = unsafe { self.slabs[self.current].add(self.offset) };
+            self.offset += len;
+            dst
+        };
+
+        // SAFETY: `dst` points to at least `len` writable bytes owned by this
+        // arena that no earlier allocation handed out.
+        unsafe { ptr::copy_nonoverlapping(payload.as_ptr(), dst, len) };
+        dst
+    }
+
+    /// Invalidate every payload handed out so far.
+    pub(crate) fn reset(&mut self) {
+        for (data, len) in self.oversized.drain(..) {
+            // SAFETY: Allocated as a boxed slice of `len` bytes in `alloc`.
+            unsafe { drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len))) };
+        }
+        self.current = 0;
+        self.offset = 0;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn slab_count(&self) -> usize {
+        self.slabs.len()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        self.reset();
+        for slab in self.slabs.drain(..) {
+            // SAFETY: Allocated as a boxed slice of `slab_size` bytes in `alloc`.
+            unsafe {
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    slab,
+                    self.slab_size,
+                )))
+            };
+        }
+    }
+}
+
+/// Create a payload arena for `ipc_peer_recv_arena`.
+///
+/// Payloads are packed into slabs of `slab_size` bytes; larger payloads get
+/// their own allocation. Returns null if `slab_size` is zero.
+#[no_mangle]
+pub extern "C" fn ipc_arena_new(slab_size: usize) -> IpcArenaHandle {
+    crate::ffi_boundary(ptr::null_mut(), || {
+        error::clear_error_state();
+
+        if slab_size == 0 {
+            let _ = error::set_invalid_argument("slab_size must be greater than zero");
+            return ptr::null_mut();
+        }
+
+        Box::into_raw(Box::new(Arena::new(slab_size))) as IpcArenaHandle
+    })
+}
+
+/// Release every payload allocated from `arena`, keeping its slabs for reuse.
+///
+/// # Safety
+/// `arena` must be a valid handle returned by `ipc_arena_new`. Every frame
+/// filled from this arena is invalidated: its `data` pointer must not be read
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ipc_arena_reset(arena: IpcArenaHandle) -> IpcResult {
+    crate::ffi_boundary(IpcResult::Internal, || {
+        error::clear_error_state();
+
+        if arena.is_null() {
+            return error::set_invalid_argument("arena handle cannot be null");
+        }
+
+        // SAFETY: Caller guarantees this handle was allocated by ipc_arena_new.
+        unsafe { &mut *(arena as *mut Arena) }.reset();
+        IpcResult::Ok
+    })
+}
+
+/// Free an arena and every payload allocated from it.
+///
+/// # Safety
+/// `arena` must be null or a handle returned by `ipc_arena_new`. Frames filled
+/// from this arena are invalidated, as with `ipc_arena_reset`.
+#[no_mangle]
+pub unsafe extern "C" fn ipc_arena_free(arena: IpcArenaHandle) {
+    crate::ffi_boundary((), || {
+        if arena.is_null() {
+            return;
+        }
+
+        // SAFETY: Caller guarantees this handle was allocated by ipc_arena_new.
+        unsafe {
+            drop(Box::from_raw(arena as *mut Arena));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+
+    use super::*;
+
+    fn read(data: *mut u8, len: usize) -> Vec<u8> {
+        // SAFETY: Test callers pass pointers returned by `Arena::alloc`.
+        unsafe { slice::from_raw_parts(data, len) }.to_vec()
+    }
+
+    #[test]
+    fn payloads_are_packed_into_slabs() {
+        let mut arena = Arena::new(16);
+        let a = arena.alloc(b"aaaaaa");
+        let b = arena.alloc(b"bbbbbb");
+        let c = arena.alloc(b"cccccc");
+
+        assert_eq!(arena.slab_count(), 2);
+        // SAFETY: `a` and `b` are in the same slab.
+        assert_eq!(unsafe { b.offset_from(a) }, 6);
+        assert_eq!(read(a, 6), b"aaaaaa");
+        assert_eq!(read(b, 6), b"bbbbbb");
+        assert_eq!(read(c, 6), b"cccccc");
+        assert!(arena.alloc(b"").is_null());
+    }
+
+    #[test]
+    fn oversized_payload_gets_own_allocation() {
+        let mut arena = Arena::new(4);
+        let big = arena.alloc(b"larger than a slab");
+        let small = arena.alloc(b"ok");
+
+        assert_eq!(arena.slab_count(), 1);
+        assert_eq!(read(big, 18), b"larger than a slab");
+        assert_eq!(read(small, 2), b"ok");
+    }
+
+    #[test]
+    fn reset_reuses_slabs() {
+        let mut arena = Arena::new(8);
+        let first = arena.alloc(b"12345678");
+        arena.alloc(b"abcdefgh");
+        assert_eq!(arena.slab_count(), 2);
+
+        arena.reset();
+        let again = arena.alloc(b"xyz");
+        assert_eq!(again, first);
+        arena.alloc(b"12345678");
+        assert_eq!(arena.slab_count(), 2);
+    }
+
+    #[test]
+    fn ffi_handles_validate_arguments() {
+        assert!(ipc_arena_new(0).is_null());
+
+        let arena = ipc_arena_new(1024);
+        assert!(!arena.is_null());
+        // SAFETY: `arena` was returned by ipc_arena_new.
+        unsafe {
+            assert_eq!(ipc_arena_reset(arena), IpcResult::Ok);
+            assert_eq!(ipc_arena_reset(ptr::null_mut()), IpcResult::InvalidArgument);
+            ipc_arena_free(arena);
+            ipc_arena_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ffi::CString;
 use std::os::raw::c_char;
 
@@ -8,12 +8,14 @@ use crate::types::IpcResult;
 
 thread_local! {
     static LAST_ERROR: RefCell<CString> = RefCell::new(CString::new("").expect("empty CString should be valid"));
+    static LAST_FRAME_ERROR_CODE: Cell<u16> = const { Cell::new(0) };
 }
 
 pub(crate) fn clear_error_state() {
     LAST_ERROR.with(|state| {
         *state.borrow_mut() = CString::new("").expect("empty CString should be valid");
     });
+    LAST_FRAME_ERROR_CODE.with(|code| code.set(0));
 }
 
 pub(crate) fn set_error_message(message: impl Into<String>) {
@@ -38,7 +40,10 @@ pub(crate) fn map_peer_error(err: &PeerError) -> IpcResult {
     set_error_message(err.to_string());
     match err {
         PeerError::Transport(_) => IpcResult::TransportError,
-        PeerError::Frame(_) => IpcResult::FrameError,
+        PeerError::Frame(err) => {
+            LAST_FRAME_ERROR_CODE.with(|code| code.set(err.code().as_u16()));
+            IpcResult::FrameError
+        }
         PeerError::HandshakeFailed(_) => IpcResult::HandshakeFailed,
         PeerError::Disconnected(_) => IpcResult::Disconnected,
         PeerError::UnsupportedChannel(_) => IpcResult::UnsupportedChannel,
@@ -46,8 +51,15 @@ pub(crate) fn map_peer_error(err: &PeerError) -> IpcResult {
         PeerError::Json(_) => IpcResult::InvalidArgument,
         #[cfg(feature = "schema")]
         PeerError::Schema(_) => IpcResult::SchemaError,
-        PeerError::Timeout(_) => IpcResult::Timeout,
+        PeerError::Timeout { .. } => IpcResult::Timeout,
         PeerError::ShutdownFailed(_) => IpcResult::ShutdownFailed,
+        PeerError::Desynchronized(_) => IpcResult::FrameError,
+        PeerError::AuthRejected(_) => IpcResult::HandshakeFailed,
+        PeerError::TransactionFailed { source, .. } => {
+            let code = map_peer_error(source);
+            set_error_message(err.to_string());
+            code
+        }
         // Catch any variants activated by workspace feature unification
         // (e.g. Schema when another crate enables ipcprims-peer/schema)
         #[allow(unreachable_patterns)]
@@ -64,3 +76,7 @@ pub(crate) fn map_schema_error(err: &ipcprims_schema::SchemaError) -> IpcResult
 pub(crate) fn last_error_ptr() -> *const c_char {
     LAST_ERROR.with(|state| state.borrow().as_ptr())
 }
+
+pub(crate) fn last_frame_error_code() -> u16 {
+    LAST_FRAME_ERROR_CODE.with(Cell::get)
+}
@@ -1,9 +1,13 @@
 use std::ptr;
 
+use crate::error;
 use crate::types::IpcFrame;
 
 /// Free payload memory held by an [`IpcFrame`] populated by recv APIs.
 ///
+/// Frames filled by `ipc_peer_recv_arena` are left untouched and an error is
+/// recorded for `ipc_last_error`; their payloads belong to the arena.
+///
 /// # Safety
 /// `frame` must be either null or a valid pointer to an `IpcFrame` created by caller code.
 /// If `frame->data` is non-null, it must have originated from this library.
@@ -19,6 +23,13 @@ pub unsafe extern "C" fn ipc_frame_free(frame: *mut IpcFrame) {
             unsafe { &mut *frame }
         };
 
+        if frame_ref.is_arena() {
+            error::set_error_message(
+                "frame payload belongs to an arena; release it with ipc_arena_reset or ipc_arena_free",
+            );
+            return;
+        }
+
         if !frame_ref.data.is_null() {
             let slice_ptr = ptr::slice_from_raw_parts_mut(frame_ref.data, frame_ref.len);
             // SAFETY: `data` was allocated by `Box<[u8]>` in FFI receive functions.
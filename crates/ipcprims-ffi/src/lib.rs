@@ -1,5 +1,6 @@
 //! ipcprims-ffi: C-ABI exports for ipcprims peer-level APIs.
 
+mod arena;
 mod error;
 mod frame;
 mod peer;
@@ -9,21 +10,24 @@ mod types;
 
 use std::panic::AssertUnwindSafe;
 
+pub use arena::{ipc_arena_free, ipc_arena_new, ipc_arena_reset};
 pub use frame::ipc_frame_free;
 pub use peer::{
-    ipc_connect, ipc_listener_accept, ipc_listener_bind, ipc_listener_free, ipc_peer_free,
-    ipc_peer_ping, ipc_peer_recv, ipc_peer_recv_on, ipc_peer_send, ipc_peer_shutdown,
+    ipc_connect, ipc_listener_accept, ipc_listener_bind, ipc_listener_free, ipc_listener_metrics,
+    ipc_peer_free, ipc_peer_ping, ipc_peer_recv, ipc_peer_recv_arena, ipc_peer_recv_on,
+    ipc_peer_remote_library_version, ipc_peer_send, ipc_peer_shutdown,
 };
 pub use schema::{
     ipc_schema_registry_free, ipc_schema_registry_from_directory, ipc_schema_registry_validate,
 };
 pub use types::IpcSchemaRegistryHandle;
 pub use types::{
-    IpcFrame, IpcListenerHandle, IpcPeerHandle, IpcResult, IPC_CHANNEL_COMMAND,
-    IPC_CHANNEL_CONTROL, IPC_CHANNEL_DATA, IPC_CHANNEL_ERROR, IPC_CHANNEL_TELEMETRY,
-    IPC_ERR_BUFFER_FULL, IPC_ERR_DISCONNECTED, IPC_ERR_FRAME, IPC_ERR_HANDSHAKE_FAILED,
-    IPC_ERR_INTERNAL, IPC_ERR_INVALID_ARGUMENT, IPC_ERR_SCHEMA, IPC_ERR_SHUTDOWN_FAILED,
-    IPC_ERR_TIMEOUT, IPC_ERR_TRANSPORT, IPC_ERR_UNSUPPORTED_CHANNEL, IPC_OK,
+    IpcArenaHandle, IpcFrame, IpcListenerHandle, IpcListenerMetrics, IpcPeerHandle, IpcResult,
+    IPC_CHANNEL_COMMAND, IPC_CHANNEL_CONTROL, IPC_CHANNEL_DATA, IPC_CHANNEL_ERROR,
+    IPC_CHANNEL_TELEMETRY, IPC_ERR_BUFFER_FULL, IPC_ERR_DISCONNECTED, IPC_ERR_FRAME,
+    IPC_ERR_HANDSHAKE_FAILED, IPC_ERR_INTERNAL, IPC_ERR_INVALID_ARGUMENT, IPC_ERR_SCHEMA,
+    IPC_ERR_SHUTDOWN_FAILED, IPC_ERR_TIMEOUT, IPC_ERR_TRANSPORT, IPC_ERR_UNSUPPORTED_CHANNEL,
+    IPC_FRAME_TAG_ARENA, IPC_FRAME_TAG_HEAP, IPC_OK,
 };
 
 fn ffi_boundary<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
@@ -56,6 +60,13 @@ pub extern "C" fn ipc_last_error() -> *const std::os::raw::c_char {
     ffi_boundary(std::ptr::null(), error::last_error_ptr)
 }
 
+/// Stable frame error code (see `FrameErrorCode`) behind the last
+/// `IPC_ERR_FRAME` on this thread, or 0 if the last error was not a frame error.
+#[no_mangle]
+pub extern "C" fn ipc_last_frame_error_code() -> u16 {
+    ffi_boundary(0, error::last_frame_error_code)
+}
+
 #[cfg(test)]
 mod tests {
     use std::ffi::CStr;
@@ -78,4 +89,27 @@ mod tests {
         let text = unsafe { CStr::from_ptr(ptr).to_str().unwrap() };
         assert!(text.is_empty());
     }
+
+    #[test]
+    fn timeout_error_message_names_the_operation() {
+        let err = ipcprims_peer::PeerError::Timeout {
+            operation: ipcprims_peer::TimeoutOp::Ping,
+            waited: std::time::Duration::from_secs(5),
+        };
+        assert_eq!(error::map_peer_error(&err), IpcResult::Timeout);
+
+        // SAFETY: ipc_last_error returns a pointer to a thread-local CString.
+        let text = unsafe { CStr::from_ptr(ipc_last_error()).to_str().unwrap() };
+        assert_eq!(text, "ping timed out after 5s");
+    }
+
+    #[test]
+    fn frame_error_code_is_recorded() {
+        let err = ipcprims_peer::PeerError::Frame(ipcprims_frame::FrameError::InvalidMagic);
+        assert_eq!(error::map_peer_error(&err), IpcResult::FrameError);
+        assert_eq!(ipc_last_frame_error_code(), 1);
+
+        ipc_cleanup();
+        assert_eq!(ipc_last_frame_error_code(), 0);
+    }
 }
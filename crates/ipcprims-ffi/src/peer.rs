@@ -1,7 +1,9 @@
+use crate::arena::Arena;
 use crate::error;
 use crate::transport;
 use crate::types::{
-    IpcFrame, IpcListenerHandle, IpcPeerHandle, IpcResult, ListenerHandle, PeerHandle,
+    IpcArenaHandle, IpcFrame, IpcListenerHandle, IpcListenerMetrics, IpcPeerHandle, IpcResult,
+    ListenerHandle, PeerHandle, IPC_FRAME_TAG_ARENA, IPC_FRAME_TAG_HEAP,
 };
 
 fn with_peer_mut<T>(handle: IpcPeerHandle, on_error: T, f: impl FnOnce(&mut PeerHandle) -> T) -> T {
@@ -36,9 +38,11 @@ fn with_listener<T>(
     f(listener_handle)
 }
 
-fn write_frame_out(out_frame: *mut IpcFrame, channel: u16, payload: &[u8]) -> IpcResult {
+/// Borrow `out_frame`, freeing a heap payload left from an earlier recv.
+fn take_frame_out<'a>(out_frame: *mut IpcFrame) -> Option<&'a mut IpcFrame> {
     if out_frame.is_null() {
-        return error::set_invalid_argument("out_frame cannot be null");
+        let _ = error::set_invalid_argument("out_frame cannot be null");
+        return None;
     }
 
     let frame_ref = {
@@ -46,15 +50,23 @@ fn write_frame_out(out_frame: *mut IpcFrame, channel: u16, payload: &[u8]) -> Ip
         unsafe { &mut *out_frame }
     };
 
-    if !frame_ref.data.is_null() {
+    // Arena payloads are released with their arena, not per frame.
+    if !frame_ref.data.is_null() && !frame_ref.is_arena() {
         let slice_ptr = std::ptr::slice_from_raw_parts_mut(frame_ref.data, frame_ref.len);
         // SAFETY: Existing payload pointers are allocated by this library.
         unsafe {
             drop(Box::from_raw(slice_ptr));
         }
-        frame_ref.data = std::ptr::null_mut();
-        frame_ref.len = 0;
     }
+    *frame_ref = IpcFrame::default();
+
+    Some(frame_ref)
+}
+
+fn write_frame_out(out_frame: *mut IpcFrame, channel: u16, payload: &[u8]) -> IpcResult {
+    let Some(frame_ref) = take_frame_out(out_frame) else {
+        return IpcResult::InvalidArgument;
+    };
 
     let boxed_payload: Box<[u8]> = payload.to_vec().into_boxed_slice();
     let len = boxed_payload.len();
@@ -65,12 +77,31 @@ fn write_frame_out(out_frame: *mut IpcFrame, channel: u16, payload: &[u8]) -> Ip
     };
 
     frame_ref.channel = channel;
+    frame_ref.tag = IPC_FRAME_TAG_HEAP;
     frame_ref.data = ptr;
     frame_ref.len = len;
 
     IpcResult::Ok
 }
 
+fn write_arena_frame_out(
+    out_frame: *mut IpcFrame,
+    arena: &mut Arena,
+    channel: u16,
+    payload: &[u8],
+) -> IpcResult {
+    let Some(frame_ref) = take_frame_out(out_frame) else {
+        return IpcResult::InvalidArgument;
+    };
+
+    frame_ref.channel = channel;
+    frame_ref.tag = IPC_FRAME_TAG_ARENA;
+    frame_ref.data = arena.alloc(payload);
+    frame_ref.len = payload.len();
+
+    IpcResult::Ok
+}
+
 /// Bind a peer listener at `path`.
 ///
 /// # Safety
@@ -115,7 +146,7 @@ pub unsafe extern "C" fn ipc_listener_accept(listener: IpcListenerHandle) -> Ipc
             std::ptr::null_mut(),
             |listener_handle| match listener_handle.listener.accept() {
                 Ok(peer) => {
-                    let handle = PeerHandle { peer: Some(peer) };
+                    let handle = PeerHandle::new(peer);
                     Box::into_raw(Box::new(handle)) as IpcPeerHandle
                 }
                 Err(err) => {
@@ -127,6 +158,45 @@ pub unsafe extern "C" fn ipc_listener_accept(listener: IpcListenerHandle) -> Ipc
     })
 }
 
+/// Copy the listener's accept and handshake-failure counters into `out_metrics`.
+///
+/// # Safety
+/// `listener` must be a valid listener handle and `out_metrics` must be a
+/// non-null writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ipc_listener_metrics(
+    listener: IpcListenerHandle,
+    out_metrics: *mut IpcListenerMetrics,
+) -> IpcResult {
+    crate::ffi_boundary(IpcResult::Internal, || {
+        error::clear_error_state();
+
+        if out_metrics.is_null() {
+            return error::set_invalid_argument("out_metrics cannot be null");
+        }
+
+        with_listener(listener, IpcResult::InvalidArgument, |listener_handle| {
+            let metrics = listener_handle.listener.metrics();
+            let last_accept_at_ms = metrics
+                .last_accept_at
+                .and_then(|at| at.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |since| {
+                    u64::try_from(since.as_millis()).unwrap_or(u64::MAX)
+                });
+
+            // SAFETY: Pointer was checked for null above.
+            unsafe {
+                *out_metrics = IpcListenerMetrics {
+                    accepted: metrics.accepted,
+                    handshake_failures: metrics.handshake_failures,
+                    last_accept_at_ms,
+                };
+            }
+            IpcResult::Ok
+        })
+    })
+}
+
 /// Free a listener handle.
 ///
 /// # Safety
@@ -177,7 +247,7 @@ pub unsafe extern "C" fn ipc_connect(
 
         match ipcprims_peer::connect(path, channels) {
             Ok(peer) => {
-                let handle = PeerHandle { peer: Some(peer) };
+                let handle = PeerHandle::new(peer);
                 Box::into_raw(Box::new(handle)) as IpcPeerHandle
             }
             Err(err) => {
@@ -277,6 +347,48 @@ pub unsafe extern "C" fn ipc_peer_recv_on(
     })
 }
 
+/// Receive the next non-control frame into `arena` instead of a per-frame allocation.
+///
+/// The payload stays valid until `ipc_arena_reset` or `ipc_arena_free` is called on
+/// `arena`. Frames filled this way must not be passed to `ipc_frame_free`.
+///
+/// # Safety
+/// `peer` must be a valid peer handle, `arena` a valid handle from `ipc_arena_new`, and
+/// `out_frame` a valid writable pointer. If `out_frame->data` holds a prior heap payload
+/// from this library, it is freed first; a prior arena payload is left to its arena.
+#[no_mangle]
+pub unsafe extern "C" fn ipc_peer_recv_arena(
+    peer: IpcPeerHandle,
+    arena: IpcArenaHandle,
+    out_frame: *mut IpcFrame,
+) -> IpcResult {
+    crate::ffi_boundary(IpcResult::Internal, || {
+        error::clear_error_state();
+
+        if arena.is_null() {
+            return error::set_invalid_argument("arena handle cannot be null");
+        }
+        let arena = {
+            // SAFETY: Caller guarantees this handle was allocated by ipc_arena_new.
+            unsafe { &mut *(arena as *mut Arena) }
+        };
+
+        with_peer_mut(peer, IpcResult::InvalidArgument, |peer_handle| {
+            let peer = match peer_handle.peer.as_mut() {
+                Some(peer) => peer,
+                None => return error::set_invalid_argument("peer handle has been closed"),
+            };
+
+            match peer.recv() {
+                Ok(frame) => {
+                    write_arena_frame_out(out_frame, arena, frame.channel, frame.payload.as_ref())
+                }
+                Err(err) => error::map_peer_error(&err),
+            }
+        })
+    })
+}
+
 /// Send a control ping and return round-trip time in nanoseconds.
 ///
 /// # Safety
@@ -329,13 +441,37 @@ pub unsafe extern "C" fn ipc_peer_shutdown(peer: IpcPeerHandle) -> IpcResult {
             };
 
             match peer.shutdown() {
-                Ok(()) => IpcResult::Ok,
+                Ok(_) => IpcResult::Ok,
                 Err(err) => error::map_peer_error(&err),
             }
         })
     })
 }
 
+/// ipcprims version the remote side reported during the handshake.
+///
+/// Returns null if the remote did not report one (older peers, or
+/// `send_library_version` disabled). The string is owned by `peer` and stays
+/// valid until `ipc_peer_free`.
+///
+/// # Safety
+/// `peer` must be a valid peer handle.
+#[no_mangle]
+pub unsafe extern "C" fn ipc_peer_remote_library_version(
+    peer: IpcPeerHandle,
+) -> *const std::os::raw::c_char {
+    crate::ffi_boundary(std::ptr::null(), || {
+        error::clear_error_state();
+
+        with_peer_mut(peer, std::ptr::null(), |peer_handle| {
+            peer_handle
+                .remote_library_version
+                .as_ref()
+                .map_or(std::ptr::null(), |version| version.as_ptr())
+        })
+    })
+}
+
 /// Free a peer handle.
 ///
 /// # Safety
@@ -356,8 +492,41 @@ pub unsafe extern "C" fn ipc_peer_free(peer: IpcPeerHandle) {
 
 #[cfg(test)]
 mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
     use super::*;
 
+    /// Counts allocations made by the current thread.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // SAFETY: Delegates to the system allocator; the counter does not allocate.
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            // SAFETY: Forwarded unchanged from the caller.
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            // SAFETY: Forwarded unchanged from the caller.
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
     #[test]
     fn write_frame_out_populates_output() {
         let mut frame = IpcFrame::default();
@@ -388,4 +557,205 @@ mod tests {
         // SAFETY: `frame` was populated by `write_frame_out`.
         unsafe { crate::ipc_frame_free(&mut frame as *mut IpcFrame) };
     }
+
+    #[test]
+    fn ipc_frame_layout_matches_pre_tag_abi() {
+        #[repr(C)]
+        struct UntaggedFrame {
+            channel: u16,
+            data: *mut u8,
+            len: usize,
+        }
+
+        assert_eq!(
+            std::mem::size_of::<IpcFrame>(),
+            std::mem::size_of::<UntaggedFrame>()
+        );
+        assert_eq!(
+            std::mem::offset_of!(IpcFrame, data),
+            std::mem::offset_of!(UntaggedFrame, data)
+        );
+        assert_eq!(
+            std::mem::offset_of!(IpcFrame, len),
+            std::mem::offset_of!(UntaggedFrame, len)
+        );
+    }
+
+    #[test]
+    fn arena_frames_are_not_freed_per_frame() {
+        let mut arena = Arena::new(64);
+        let mut frame = IpcFrame::default();
+        assert_eq!(
+            write_arena_frame_out(&mut frame as *mut IpcFrame, &mut arena, 3, b"pooled"),
+            IpcResult::Ok
+        );
+        assert_eq!(frame.tag, IPC_FRAME_TAG_ARENA);
+        let data = frame.data;
+
+        // SAFETY: `frame` is a valid IpcFrame.
+        unsafe { crate::ipc_frame_free(&mut frame as *mut IpcFrame) };
+        assert_eq!(frame.data, data, "arena payload must be left in place");
+        // SAFETY: ipc_last_error returns a pointer to a thread-local CString.
+        let message = unsafe { std::ffi::CStr::from_ptr(crate::ipc_last_error()) };
+        assert!(message.to_str().unwrap().contains("arena"));
+
+        // Reusing the frame for a heap recv must not free the arena payload.
+        assert_eq!(
+            write_frame_out(&mut frame as *mut IpcFrame, 4, b"heap"),
+            IpcResult::Ok
+        );
+        assert_eq!(frame.tag, IPC_FRAME_TAG_HEAP);
+        // SAFETY: `data` is still owned by the live arena.
+        assert_eq!(unsafe { std::slice::from_raw_parts(data, 6) }, b"pooled");
+
+        // SAFETY: `frame` now holds a heap payload from `write_frame_out`.
+        unsafe { crate::ipc_frame_free(&mut frame as *mut IpcFrame) };
+        assert!(frame.data.is_null());
+    }
+
+    #[test]
+    fn arena_path_allocates_per_slab_not_per_frame() {
+        const FRAMES: usize = 1000;
+        let payload = [7u8; 48];
+
+        let heap = allocations_during(|| {
+            let mut frame = IpcFrame::default();
+            for _ in 0..FRAMES {
+                write_frame_out(&mut frame as *mut IpcFrame, 2, &payload);
+            }
+            // SAFETY: `frame` holds a heap payload from `write_frame_out`.
+            unsafe { crate::ipc_frame_free(&mut frame as *mut IpcFrame) };
+        });
+
+        let mut arena = Arena::new(64 * 1024);
+        let pooled = allocations_during(|| {
+            let mut frame = IpcFrame::default();
+            for _ in 0..FRAMES {
+                write_arena_frame_out(&mut frame as *mut IpcFrame, &mut arena, 2, &payload);
+            }
+        });
+
+        assert!(heap >= FRAMES, "heap path made {heap} allocations");
+        // 48 KB of payload fits one slab, plus the slab list itself.
+        assert!(pooled <= 2, "arena path made {pooled} allocations");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recv_arena_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-ffi-arena-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("arena.sock");
+        let listener = ipcprims_peer::PeerListener::bind(&path).unwrap();
+        let client_path = path.clone();
+        let client = std::thread::spawn(move || {
+            let mut peer = ipcprims_peer::connect(&client_path, &[1, 2]).unwrap();
+            peer.send(2, b"first").unwrap();
+            peer.send(2, b"second").unwrap();
+            peer
+        });
+        let server = listener.accept().unwrap();
+        let _client = client.join().unwrap();
+
+        let handle = Box::into_raw(Box::new(PeerHandle::new(server))) as IpcPeerHandle;
+        let arena = crate::ipc_arena_new(4096);
+        let mut first = IpcFrame::default();
+        let mut second = IpcFrame::default();
+
+        // SAFETY: Handles come from this test and frames are valid and writable.
+        unsafe {
+            assert_eq!(
+                ipc_peer_recv_arena(handle, arena, &mut first as *mut IpcFrame),
+                IpcResult::Ok
+            );
+            assert_eq!(
+                ipc_peer_recv_arena(handle, arena, &mut second as *mut IpcFrame),
+                IpcResult::Ok
+            );
+            assert_eq!(std::slice::from_raw_parts(first.data, first.len), b"first");
+            assert_eq!(
+                std::slice::from_raw_parts(second.data, second.len),
+                b"second"
+            );
+            assert_eq!(first.data.add(first.len), second.data);
+            assert_eq!(
+                ipc_peer_recv_arena(handle, std::ptr::null_mut(), &mut first as *mut IpcFrame),
+                IpcResult::InvalidArgument
+            );
+
+            crate::ipc_arena_free(arena);
+            ipc_peer_free(handle);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_library_version_accessor() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-ffi-libver-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("libver.sock");
+        let listener = ipcprims_peer::PeerListener::bind(&path).unwrap();
+        let client_path = path.clone();
+        let client =
+            std::thread::spawn(move || ipcprims_peer::connect(&client_path, &[1]).unwrap());
+        let server = listener.accept().unwrap();
+        let _client = client.join().unwrap();
+
+        let handle = Box::into_raw(Box::new(PeerHandle::new(server))) as IpcPeerHandle;
+        // SAFETY: `handle` comes from this test; the string lives as long as it.
+        unsafe {
+            let version = ipc_peer_remote_library_version(handle);
+            assert!(!version.is_null());
+            assert_eq!(
+                std::ffi::CStr::from_ptr(version).to_str().unwrap(),
+                env!("CARGO_PKG_VERSION")
+            );
+            assert!(ipc_peer_remote_library_version(std::ptr::null_mut()).is_null());
+            ipc_peer_free(handle);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listener_metrics_reports_accepts() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-ffi-metrics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.sock");
+        let listener = ipcprims_peer::PeerListener::bind(&path).unwrap();
+        let handle = Box::into_raw(Box::new(ListenerHandle { listener })) as IpcListenerHandle;
+        let mut metrics = IpcListenerMetrics::default();
+
+        let client_path = path.clone();
+        let client =
+            std::thread::spawn(move || ipcprims_peer::connect(&client_path, &[1]).unwrap());
+        // SAFETY: `handle` comes from this test and `metrics` is writable.
+        unsafe {
+            assert_eq!(
+                ipc_listener_metrics(handle, &mut metrics as *mut IpcListenerMetrics),
+                IpcResult::Ok
+            );
+            assert_eq!(metrics, IpcListenerMetrics::default());
+
+            let peer = ipc_listener_accept(handle);
+            assert!(!peer.is_null());
+            let _client = client.join().unwrap();
+            assert_eq!(
+                ipc_listener_metrics(handle, &mut metrics as *mut IpcListenerMetrics),
+                IpcResult::Ok
+            );
+            assert_eq!(metrics.accepted, 1);
+            assert_eq!(metrics.handshake_failures, 0);
+            assert!(metrics.last_accept_at_ms > 0);
+            assert_eq!(
+                ipc_listener_metrics(handle, std::ptr::null_mut()),
+                IpcResult::InvalidArgument
+            );
+
+            ipc_peer_free(peer);
+            ipc_listener_free(handle);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
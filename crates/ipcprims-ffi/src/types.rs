@@ -1,4 +1,4 @@
-use std::ffi::c_void;
+use std::ffi::{c_void, CString};
 
 use ipcprims_peer::{Peer, PeerListener};
 
@@ -58,30 +58,76 @@ pub const IPC_CHANNEL_TELEMETRY: u16 = 3;
 #[allow(dead_code)]
 pub const IPC_CHANNEL_ERROR: u16 = 4;
 
+/// `IpcFrame::tag` for payloads owned by the frame; release with `ipc_frame_free`.
+#[allow(dead_code)]
+pub const IPC_FRAME_TAG_HEAP: u8 = 0;
+/// `IpcFrame::tag` for payloads owned by an arena; never pass to `ipc_frame_free`.
+#[allow(dead_code)]
+pub const IPC_FRAME_TAG_ARENA: u8 = 0xA5;
+
+/// Received frame. `tag` records who owns `data` and sits in what was
+/// padding, so the layout is unchanged; zero-initialize new frames.
 #[repr(C)]
 #[derive(Debug)]
 pub struct IpcFrame {
     pub channel: u16,
+    pub tag: u8,
     pub data: *mut u8,
     pub len: usize,
 }
 
+impl IpcFrame {
+    pub(crate) fn is_arena(&self) -> bool {
+        self.tag == IPC_FRAME_TAG_ARENA
+    }
+}
+
 impl Default for IpcFrame {
     fn default() -> Self {
         Self {
             channel: 0,
+            tag: IPC_FRAME_TAG_HEAP,
             data: std::ptr::null_mut(),
             len: 0,
         }
     }
 }
 
+/// Listener counters filled by `ipc_listener_metrics`.
+///
+/// `last_accept_at_ms` is Unix milliseconds, or 0 before the first accept.
+/// The per-kind failure breakdown is only available from Rust and the CLI.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IpcListenerMetrics {
+    pub accepted: u64,
+    pub handshake_failures: u64,
+    pub last_accept_at_ms: u64,
+}
+
+pub type IpcArenaHandle = *mut c_void;
 pub type IpcPeerHandle = *mut c_void;
 pub type IpcListenerHandle = *mut c_void;
 pub type IpcSchemaRegistryHandle = *mut c_void;
 
 pub(crate) struct PeerHandle {
     pub(crate) peer: Option<Peer>,
+    /// Kept for the handle's lifetime so C can hold the returned pointer.
+    pub(crate) remote_library_version: Option<CString>,
+}
+
+impl PeerHandle {
+    pub(crate) fn new(peer: Peer) -> Self {
+        let remote_library_version = peer
+            .handshake_result()
+            .remote_library_version
+            .as_deref()
+            .and_then(|version| CString::new(version).ok());
+        Self {
+            peer: Some(peer),
+            remote_library_version,
+        }
+    }
 }
 
 pub(crate) struct ListenerHandle {
@@ -0,0 +1,169 @@
+//! Frame codec benchmarks.
+//!
+//! Run with `cargo bench -p ipcprims-frame`. `cargo test --benches` runs each
+//! benchmark once as a smoke test; the round-trip assertions below make that
+//! run fail loudly if the codec or the fixtures regress.
+
+use std::io::Cursor;
+
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ipcprims_frame::{
+    decode_frame, encode_frame, FrameError, FrameReader, DEFAULT_MAX_PAYLOAD, HEADER_SIZE,
+    USER_CHANNEL_START,
+};
+
+/// 64 B control-sized, 4 KiB page-sized, 1 MiB bulk.
+const PAYLOAD_SIZES: [usize; 3] = [64, 4 * 1024, 1024 * 1024];
+
+/// Frames per in-memory stream in the reader benchmark.
+const STREAM_FRAMES: usize = 64;
+
+const CHANNEL: u16 = USER_CHANNEL_START;
+
+fn payload(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn encoded(payload: &[u8]) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(HEADER_SIZE + payload.len());
+    encode_frame(CHANNEL, payload, &mut buf).expect("payload within default limit");
+    buf
+}
+
+/// Concatenate `count` encoded frames into one buffer.
+fn encoded_stream(payload: &[u8], count: usize) -> Vec<u8> {
+    let mut buf = BytesMut::with_capacity(count * (HEADER_SIZE + payload.len()));
+    for _ in 0..count {
+        encode_frame(CHANNEL, payload, &mut buf).expect("payload within default limit");
+    }
+    buf.to_vec()
+}
+
+/// Guard: the fixtures must decode back to exactly what was encoded.
+fn assert_roundtrip(payload: &[u8]) {
+    let mut buf = encoded(payload);
+    assert_eq!(buf.len(), HEADER_SIZE + payload.len());
+    let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+        .expect("valid frame")
+        .expect("complete frame");
+    assert_eq!(frame.channel, CHANNEL);
+    assert_eq!(&frame.payload[..], payload);
+    assert!(buf.is_empty(), "decode must consume the whole frame");
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_frame");
+    for size in PAYLOAD_SIZES {
+        let payload = payload(size);
+        assert_roundtrip(&payload);
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE + size);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+            b.iter(|| {
+                buf.clear();
+                encode_frame(CHANNEL, black_box(payload), &mut buf).unwrap();
+                black_box(&buf);
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_frame");
+    for size in PAYLOAD_SIZES {
+        let payload = payload(size);
+        assert_roundtrip(&payload);
+        let wire = encoded(&payload);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &wire, |b, wire| {
+            b.iter_batched_ref(
+                || wire.clone(),
+                |buf| black_box(decode_frame(buf, DEFAULT_MAX_PAYLOAD).unwrap()),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn bench_reader(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_reader_in_memory");
+    for size in PAYLOAD_SIZES {
+        let payload = payload(size);
+        let count = if size >= 1024 * 1024 {
+            8
+        } else {
+            STREAM_FRAMES
+        };
+        let stream = encoded_stream(&payload, count);
+
+        // Guard: the reader yields every frame intact, then a clean close.
+        let mut reader = FrameReader::new(Cursor::new(stream.as_slice()));
+        for _ in 0..count {
+            assert_eq!(&reader.read_frame().unwrap().payload[..], &payload[..]);
+        }
+        assert!(matches!(
+            reader.read_frame(),
+            Err(FrameError::ConnectionClosed)
+        ));
+
+        group.throughput(Throughput::Bytes((count * size) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &stream, |b, stream| {
+            b.iter(|| {
+                let mut reader = FrameReader::new(Cursor::new(stream.as_slice()));
+                for _ in 0..count {
+                    black_box(reader.read_frame().unwrap());
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_reader_into(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_reader_into_in_memory");
+    for size in PAYLOAD_SIZES {
+        let payload = payload(size);
+        let count = if size >= 1024 * 1024 {
+            8
+        } else {
+            STREAM_FRAMES
+        };
+        let stream = encoded_stream(&payload, count);
+
+        // Guard: every frame lands in the same buffer without reallocating.
+        let mut reader = FrameReader::new(Cursor::new(stream.as_slice()));
+        let mut buf = Vec::with_capacity(size);
+        let ptr = buf.as_ptr();
+        for _ in 0..count {
+            assert_eq!(reader.read_frame_into(&mut buf).unwrap(), CHANNEL);
+            assert_eq!(buf, payload);
+            assert_eq!(buf.as_ptr(), ptr);
+        }
+
+        group.throughput(Throughput::Bytes((count * size) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &stream, |b, stream| {
+            let mut buf = Vec::with_capacity(size);
+            b.iter(|| {
+                let mut reader = FrameReader::new(Cursor::new(stream.as_slice()));
+                for _ in 0..count {
+                    black_box(reader.read_frame_into(&mut buf).unwrap());
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_encode,
+    bench_decode,
+    bench_reader,
+    bench_reader_into
+);
+criterion_main!(benches);
@@ -7,13 +7,23 @@
 use bytes::BytesMut;
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::codec::{decode_frame, encode_frame, Frame, FrameConfig};
+use crate::codec::{check_buffer_limit, decode_frame_copying, encode_message, Frame, FrameConfig};
 use crate::error::FrameError;
+use crate::fragment::Reassembler;
+use crate::sequence::{SequenceTracker, Sequencer};
 
 /// Tokio codec for the ipcprims wire format.
+///
+/// Reassembles fragmented messages and checks sequence numbers like
+/// [`FrameReader`](crate::FrameReader). A message behind a
+/// `SequenceGap` error is returned by the next `decode` call.
 #[derive(Debug, Clone)]
 pub struct IpcCodec {
     config: FrameConfig,
+    reassembly: Reassembler,
+    sequencer: Sequencer,
+    sequences: SequenceTracker,
+    held: Option<Frame>,
 }
 
 impl IpcCodec {
@@ -24,7 +34,35 @@ impl IpcCodec {
 
     /// Create a codec with explicit configuration.
     pub fn with_config(config: FrameConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            reassembly: Reassembler::default(),
+            sequencer: Sequencer::default(),
+            sequences: SequenceTracker::default(),
+            held: None,
+        }
+    }
+
+    /// Last sequence number decoded on `channel`, if its messages carry them.
+    pub fn last_sequence(&self, channel: u16) -> Option<u32> {
+        self.sequences.last(channel)
+    }
+
+    /// Check a complete message's sequence number, holding the message back
+    /// for the next `decode` call when it reports a gap.
+    fn checked(&mut self, frame: Frame) -> Result<Option<Frame>, FrameError> {
+        match self.sequences.observe(&frame) {
+            Ok(()) => Ok(Some(frame)),
+            Err(err) => {
+                self.held = Some(frame);
+                Err(err)
+            }
+        }
+    }
+
+    /// Bytes held by a partly reassembled message, if one is in progress.
+    pub(crate) fn reassembly_buffered(&self) -> Option<usize> {
+        self.reassembly.buffered()
     }
 
     /// Update maximum payload size for subsequent frame decoding/encoding.
@@ -49,7 +87,23 @@ impl Decoder for IpcCodec {
     type Error = FrameError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        decode_frame(src, self.config.max_payload_size)
+        if let Some(frame) = self.held.take() {
+            return Ok(Some(frame));
+        }
+        if let Some(frame) = self.reassembly.take_pending() {
+            return self.checked(frame);
+        }
+        loop {
+            check_buffer_limit(src, self.config.max_buffer_bytes)?;
+            let Some((frame, fragment)) =
+                decode_frame_copying(src, self.config.max_payload_size, 0)?
+            else {
+                return Ok(None);
+            };
+            if let Some(frame) = self.reassembly.push(frame, fragment, &self.config)? {
+                return self.checked(frame);
+            }
+        }
     }
 }
 
@@ -57,7 +111,22 @@ impl Encoder<Frame> for IpcCodec {
     type Error = FrameError;
 
     fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        encode_frame(item.channel, item.payload.as_ref(), dst)
+        let sequence = self
+            .config
+            .sequence_numbers
+            .then(|| self.sequencer.peek(item.channel));
+        encode_message(
+            &self.config,
+            item.channel,
+            item.payload.as_ref(),
+            item.priority,
+            sequence,
+            dst,
+        )?;
+        if sequence.is_some() {
+            self.sequencer.next(item.channel);
+        }
+        Ok(())
     }
 }
 
@@ -66,7 +135,7 @@ mod tests {
     use bytes::Bytes;
 
     use super::*;
-    use crate::codec::DEFAULT_MAX_PAYLOAD;
+    use crate::codec::{encode_frame, DEFAULT_MAX_PAYLOAD};
 
     #[test]
     fn encode_decode_roundtrip() {
@@ -83,6 +152,65 @@ mod tests {
         assert!(buf.is_empty());
     }
 
+    #[test]
+    fn reassembles_fragments_across_partial_buffers() {
+        let config = FrameConfig {
+            max_fragment_size: Some(3),
+            ..FrameConfig::default()
+        };
+        let mut codec = IpcCodec::with_config(config);
+        let mut wire = BytesMut::new();
+        codec
+            .encode(Frame::new(2, Bytes::from_static(b"fragmented")), &mut wire)
+            .unwrap();
+
+        let mut buf = BytesMut::new();
+        let mut decoded = None;
+        for byte in wire.iter() {
+            assert!(decoded.is_none());
+            buf.extend_from_slice(&[*byte]);
+            decoded = codec.decode(&mut buf).unwrap();
+        }
+        assert_eq!(decoded.unwrap().payload.as_ref(), b"fragmented");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn stamps_and_checks_sequence_numbers() {
+        let config = FrameConfig {
+            sequence_numbers: true,
+            ..FrameConfig::default()
+        };
+        let mut sender = IpcCodec::with_config(config);
+        let mut wire = BytesMut::new();
+        for payload in [b"0", b"1", b"2"] {
+            sender
+                .encode(Frame::new(3, Bytes::from_static(payload)), &mut wire)
+                .unwrap();
+        }
+        // Lose the second frame: header, sequence number, 1 byte.
+        let frame_len = crate::VERSIONED_HEADER_SIZE + 4 + 1;
+        let mut buf = BytesMut::from(&wire[..frame_len]);
+        buf.extend_from_slice(&wire[2 * frame_len..]);
+
+        let mut receiver = IpcCodec::new();
+        assert_eq!(
+            receiver.decode(&mut buf).unwrap().unwrap().sequence,
+            Some(0)
+        );
+        assert!(matches!(
+            receiver.decode(&mut buf),
+            Err(FrameError::SequenceGap {
+                channel: 3,
+                expected: 1,
+                got: 2
+            })
+        ));
+        let frame = receiver.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"2");
+        assert_eq!(receiver.last_sequence(3), Some(2));
+    }
+
     #[test]
     fn decode_enforces_max_payload() {
         let mut codec = IpcCodec::with_config(FrameConfig {
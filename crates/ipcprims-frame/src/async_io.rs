@@ -0,0 +1,435 @@
+//! Async frame reader and writer over tokio streams.
+//!
+//! [`AsyncFrameReader`] and [`AsyncFrameWriter`] are the `async fn`
+//! counterparts of [`FrameReader`](crate::FrameReader) and
+//! [`FrameWriter`](crate::FrameWriter). They decode and encode through the
+//! same code as [`IpcCodec`], so both sides speak exactly the same wire
+//! format, and report the same errors: `ConnectionClosed` on EOF between
+//! frames, `Truncated` on EOF inside one, `InvalidMagic` and
+//! `PayloadTooLarge` on bad headers.
+
+use std::future::Future;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::Decoder;
+
+use crate::async_codec::IpcCodec;
+use crate::codec::{encode_message, Frame, FrameConfig, Priority};
+use crate::error::{is_disconnect, FrameError, Result};
+use crate::sequence::Sequencer;
+
+const INITIAL_BUFFER_CAPACITY: usize = 8 * 1024;
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reads complete frames from an async byte stream.
+pub struct AsyncFrameReader<T> {
+    inner: T,
+    buf: BytesMut,
+    codec: IpcCodec,
+}
+
+impl<T: AsyncRead + Unpin> AsyncFrameReader<T> {
+    /// Create a reader with default configuration.
+    pub fn new(inner: T) -> Self {
+        Self::with_config(inner, FrameConfig::default())
+    }
+
+    /// Create a reader with explicit configuration.
+    pub fn with_config(inner: T, config: FrameConfig) -> Self {
+        Self {
+            inner,
+            buf: BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY),
+            codec: IpcCodec::with_config(config),
+        }
+    }
+
+    /// Read the next complete frame.
+    ///
+    /// Returns `FrameError::ConnectionClosed` on EOF between frames and
+    /// `FrameError::Truncated` on EOF inside one. With `read_timeout` set, a
+    /// read that waits longer fails with `FrameError::Timeout`.
+    ///
+    /// Cancel-safe: bytes read before the future is dropped stay buffered
+    /// for the next call.
+    pub async fn read_frame(&mut self) -> Result<Frame> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.buf)? {
+                return Ok(frame);
+            }
+            self.read_more().await?;
+        }
+    }
+
+    async fn read_more(&mut self) -> Result<()> {
+        loop {
+            self.buf.reserve(READ_CHUNK_SIZE);
+            let timeout = self.codec.config().read_timeout;
+            let read = match with_timeout(timeout, self.inner.read_buf(&mut self.buf)).await? {
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                // Same event as EOF, reported differently by some transports.
+                Err(err) if is_disconnect(err.kind()) => 0,
+                Err(err) => return Err(FrameError::Io(err)),
+            };
+
+            if read == 0 {
+                let reassembled = self.codec.reassembly_buffered();
+                if self.buf.is_empty() && reassembled.is_none() {
+                    return Err(FrameError::ConnectionClosed);
+                }
+                return Err(FrameError::Truncated {
+                    buffered: self.buf.len() + reassembled.unwrap_or(0),
+                });
+            }
+            return Ok(());
+        }
+    }
+
+    /// Last sequence number read on `channel`, if its messages carry them.
+    pub fn last_sequence(&self, channel: u16) -> Option<u32> {
+        self.codec.last_sequence(channel)
+    }
+
+    /// Current reader configuration.
+    pub fn config(&self) -> &FrameConfig {
+        self.codec.config()
+    }
+
+    /// Get a reference to the underlying stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the reader and return the underlying stream.
+    ///
+    /// Bytes buffered past the last returned frame are dropped.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Writes frames to an async byte stream.
+pub struct AsyncFrameWriter<T> {
+    inner: T,
+    buf: BytesMut,
+    config: FrameConfig,
+    sequencer: Sequencer,
+    poisoned: bool,
+}
+
+impl<T: AsyncWrite + Unpin> AsyncFrameWriter<T> {
+    /// Create a writer with default configuration.
+    pub fn new(inner: T) -> Self {
+        Self::with_config(inner, FrameConfig::default())
+    }
+
+    /// Create a writer with explicit configuration.
+    pub fn with_config(inner: T, config: FrameConfig) -> Self {
+        Self {
+            inner,
+            buf: BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY),
+            config,
+            sequencer: Sequencer::default(),
+            poisoned: false,
+        }
+    }
+
+    /// Write a frame and flush.
+    pub async fn write_frame(&mut self, frame: &Frame) -> Result<()> {
+        self.send_with_priority(frame.channel, &frame.payload, frame.priority)
+            .await
+    }
+
+    /// Send a payload on `channel` and flush.
+    pub async fn send(&mut self, channel: u16, payload: &[u8]) -> Result<()> {
+        self.send_with_priority(channel, payload, Priority::NORMAL)
+            .await
+    }
+
+    /// Send a payload on `channel` with a priority and flush.
+    ///
+    /// A write cut off part-way through the frame poisons the writer: later
+    /// sends fail with `FrameError::Poisoned`, since the peer can no longer
+    /// find the next frame boundary. Dropping the future before the frame is
+    /// fully written counts as cut off. With `write_timeout` set, a write or flush that
+    /// waits longer fails with `FrameError::Timeout`.
+    pub async fn send_with_priority(
+        &mut self,
+        channel: u16,
+        payload: &[u8],
+        priority: Priority,
+    ) -> Result<()> {
+        if self.poisoned {
+            return Err(FrameError::Poisoned);
+        }
+        let max = self.config.send_limit();
+        if payload.len() > max {
+            return Err(FrameError::PayloadTooLarge {
+                size: payload.len(),
+                max,
+            });
+        }
+        let sequence = self
+            .config
+            .sequence_numbers
+            .then(|| self.sequencer.peek(channel));
+
+        self.buf.clear();
+        encode_message(
+            &self.config,
+            channel,
+            payload,
+            priority,
+            sequence,
+            &mut self.buf,
+        )?;
+        self.write_encoded().await?;
+        if sequence.is_some() {
+            self.sequencer.next(channel);
+        }
+        self.flush().await
+    }
+
+    async fn write_encoded(&mut self) -> Result<()> {
+        // Set while the frame is going out, so a cancelled send leaves the
+        // writer poisoned rather than silently misaligned.
+        self.poisoned = true;
+        let mut written = 0;
+        while written < self.buf.len() {
+            let write = self.inner.write(&self.buf[written..]);
+            let err = match with_timeout(self.config.write_timeout, write).await {
+                Ok(Ok(0)) => FrameError::ConnectionClosed,
+                Ok(Ok(n)) => {
+                    written += n;
+                    continue;
+                }
+                Ok(Err(err)) if err.kind() == ErrorKind::Interrupted => continue,
+                Ok(Err(err)) => io_error(err),
+                Err(err) => err,
+            };
+            self.poisoned = written > 0;
+            return Err(err);
+        }
+        self.poisoned = false;
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// Flush the underlying stream.
+    pub async fn flush(&mut self) -> Result<()> {
+        with_timeout(self.config.write_timeout, self.inner.flush())
+            .await?
+            .map_err(io_error)
+    }
+
+    /// Whether an earlier send was cut off mid-frame.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Current writer configuration.
+    pub fn config(&self) -> &FrameConfig {
+        &self.config
+    }
+
+    /// Get a reference to the underlying stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the writer and return the underlying stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Await `op`, failing with `FrameError::Timeout` if it takes longer than
+/// `timeout`.
+async fn with_timeout<F: Future>(timeout: Option<Duration>, op: F) -> Result<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, op)
+            .await
+            .map_err(|_| FrameError::Timeout(timeout)),
+        None => Ok(op.await),
+    }
+}
+
+fn io_error(err: std::io::Error) -> FrameError {
+    if is_disconnect(err.kind()) {
+        FrameError::ConnectionClosed
+    } else {
+        FrameError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{encode_frame, HEADER_SIZE, MAGIC};
+    use crate::writer::FrameWriter;
+    use tokio_test::io::Builder;
+
+    fn wire(frames: &[(u16, &str)]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        for (channel, payload) in frames {
+            encode_frame(*channel, payload.as_bytes(), &mut buf).unwrap();
+        }
+        buf.to_vec()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn roundtrip_over_unix_stream() {
+        let (client, server) = tokio::net::UnixStream::pair().unwrap();
+        let config = FrameConfig {
+            sequence_numbers: true,
+            ..FrameConfig::default()
+        };
+        let mut writer = AsyncFrameWriter::with_config(client, config.clone());
+        let mut reader = AsyncFrameReader::with_config(server, config);
+
+        let big = vec![0xab; 100_000];
+        writer.send(1, b"hello").await.unwrap();
+        writer
+            .send_with_priority(256, &big, Priority(7))
+            .await
+            .unwrap();
+        writer.write_frame(&Frame::new(1, "again")).await.unwrap();
+
+        let first = reader.read_frame().await.unwrap();
+        assert_eq!((first.channel, &first.payload[..]), (1, &b"hello"[..]));
+        let second = reader.read_frame().await.unwrap();
+        assert_eq!((second.channel, second.priority), (256, Priority(7)));
+        assert_eq!(second.payload, big);
+        assert_eq!(reader.read_frame().await.unwrap().payload, "again");
+        assert_eq!(reader.last_sequence(1), Some(1));
+
+        drop(writer);
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(FrameError::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reassembles_frames_split_across_reads() {
+        let bytes = wire(&[(1, "split"), (2, "next")]);
+        let mock = Builder::new()
+            .read(&bytes[..3])
+            .read(&bytes[3..HEADER_SIZE + 2])
+            .read(&bytes[HEADER_SIZE + 2..])
+            .build();
+        let mut reader = AsyncFrameReader::new(mock);
+
+        assert_eq!(reader.read_frame().await.unwrap().payload, "split");
+        assert_eq!(reader.read_frame().await.unwrap().payload, "next");
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(FrameError::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn eof_inside_a_frame_is_truncated() {
+        let bytes = wire(&[(1, "cut short")]);
+        let mock = Builder::new().read(&bytes[..HEADER_SIZE + 3]).build();
+        let mut reader = AsyncFrameReader::new(mock);
+
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(FrameError::Truncated { buffered }) if buffered == HEADER_SIZE + 3
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_magic_and_oversized_payloads() {
+        let mock = Builder::new().read(b"XXjunkjunk").build();
+        let mut reader = AsyncFrameReader::new(mock);
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(FrameError::InvalidMagic)
+        ));
+
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&1024u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        let mock = Builder::new().read(&header).build();
+        let config = FrameConfig {
+            max_payload_size: 16,
+            ..FrameConfig::default()
+        };
+        let mut reader = AsyncFrameReader::with_config(mock, config.clone());
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(FrameError::PayloadTooLarge {
+                size: 1024,
+                max: 16
+            })
+        ));
+
+        let mut writer = AsyncFrameWriter::with_config(Vec::new(), config);
+        assert!(matches!(
+            writer.send(1, &[0; 17]).await,
+            Err(FrameError::PayloadTooLarge { size: 17, max: 16 })
+        ));
+        assert!(writer.get_ref().is_empty());
+    }
+
+    #[tokio::test]
+    async fn writes_the_same_bytes_as_frame_writer() {
+        let config = FrameConfig {
+            checksum: true,
+            sequence_numbers: true,
+            ..FrameConfig::default()
+        };
+        let mut blocking = FrameWriter::with_config(Vec::new(), config.clone());
+        let mut writer = AsyncFrameWriter::with_config(Vec::new(), config);
+        for (channel, payload, priority) in [
+            (1, &b"a"[..], Priority::NORMAL),
+            (2, b"bb", Priority(3)),
+            (1, b"", Priority::NORMAL),
+        ] {
+            blocking
+                .send_with_priority(channel, payload, priority)
+                .unwrap();
+            writer
+                .send_with_priority(channel, payload, priority)
+                .await
+                .unwrap();
+        }
+        assert_eq!(writer.get_ref(), blocking.get_ref());
+    }
+
+    #[tokio::test]
+    async fn partial_write_poisons_the_writer() {
+        let bytes = wire(&[(1, "payload")]);
+        let mock = Builder::new()
+            .write(&bytes[..4])
+            .write_error(std::io::Error::from(ErrorKind::BrokenPipe))
+            .build();
+        let mut writer = AsyncFrameWriter::new(mock);
+
+        assert!(matches!(
+            writer.send(1, b"payload").await,
+            Err(FrameError::ConnectionClosed)
+        ));
+        assert!(writer.is_poisoned());
+        assert!(matches!(
+            writer.send(1, b"payload").await,
+            Err(FrameError::Poisoned)
+        ));
+    }
+}
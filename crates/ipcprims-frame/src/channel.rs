@@ -2,6 +2,14 @@
 //!
 //! Channels 0-255 are reserved for built-in use.
 //! Channels 256-65535 are available for application-defined use.
+//! [`ChannelMap`] gives them names.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::{FrameError, Result};
 
 /// Connection management (handshake, ping/pong, shutdown).
 pub const CONTROL: u16 = 0;
@@ -18,9 +26,63 @@ pub const TELEMETRY: u16 = 3;
 /// Error notifications.
 pub const ERROR: u16 = 4;
 
+/// Frame-layer heartbeats, absorbed by readers that ask for it (see
+/// [`heartbeat`](crate::heartbeat)). Not negotiated like other channels.
+pub const HEARTBEAT: u16 = 255;
+
 /// First user-defined channel ID.
 pub const USER_CHANNEL_START: u16 = 256;
 
+/// Metadata describing a built-in channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelInfo {
+    /// Channel ID as carried in the frame header.
+    pub id: u16,
+    /// Name of the exported constant for this channel.
+    pub name: &'static str,
+    /// Intended purpose of the channel.
+    pub purpose: &'static str,
+}
+
+/// Registry of built-in channels, ordered by ID.
+pub const BUILTIN_CHANNELS: &[ChannelInfo] = &[
+    ChannelInfo {
+        id: CONTROL,
+        name: "CONTROL",
+        purpose: "Connection management (handshake, ping/pong, shutdown)",
+    },
+    ChannelInfo {
+        id: COMMAND,
+        name: "COMMAND",
+        purpose: "Structured commands (request/response)",
+    },
+    ChannelInfo {
+        id: DATA,
+        name: "DATA",
+        purpose: "Bulk data transfer",
+    },
+    ChannelInfo {
+        id: TELEMETRY,
+        name: "TELEMETRY",
+        purpose: "Metrics, logs, health signals",
+    },
+    ChannelInfo {
+        id: ERROR,
+        name: "ERROR",
+        purpose: "Error notifications",
+    },
+    ChannelInfo {
+        id: HEARTBEAT,
+        name: "HEARTBEAT",
+        purpose: "Frame-layer heartbeats (not negotiated)",
+    },
+];
+
+/// Returns metadata for a built-in channel, or `None` for other IDs.
+pub fn builtin_channel(id: u16) -> Option<&'static ChannelInfo> {
+    BUILTIN_CHANNELS.iter().find(|info| info.id == id)
+}
+
 /// Returns a human-readable name for a channel ID.
 pub fn channel_name(id: u16) -> &'static str {
     match id {
@@ -29,6 +91,7 @@ pub fn channel_name(id: u16) -> &'static str {
         DATA => "DATA",
         TELEMETRY => "TELEMETRY",
         ERROR => "ERROR",
+        HEARTBEAT => "HEARTBEAT",
         5..=255 => "RESERVED",
         _ => "USER",
     }
@@ -41,5 +104,237 @@ pub fn is_reserved(id: u16) -> bool {
 
 /// Returns true if the channel ID is a built-in channel.
 pub fn is_builtin(id: u16) -> bool {
-    id <= ERROR
+    builtin_channel(id).is_some()
+}
+
+/// Names for application channels, shared by both ends of a connection.
+///
+/// Maps names to IDs at or above [`USER_CHANNEL_START`]. Lookups also accept
+/// the built-in names (`"command"`, `"DATA"`, ...; case-insensitive), which
+/// cannot be reused. With the `json` feature the map serializes as a JSON
+/// object of name to ID, and deserializing applies the same checks as
+/// [`insert`](Self::insert).
+///
+/// ```
+/// # use ipcprims_frame::channel::{ChannelMap, DATA};
+/// let mut channels = ChannelMap::new();
+/// channels.insert("metrics", 256)?;
+/// assert_eq!(channels.get("metrics"), Some(256));
+/// assert_eq!(channels.get("data"), Some(DATA));
+/// assert_eq!(channels.name(256), Some("metrics"));
+/// # Ok::<(), ipcprims_frame::FrameError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "BTreeMap<String, u16>", into = "BTreeMap<String, u16>")
+)]
+pub struct ChannelMap {
+    by_name: BTreeMap<String, u16>,
+    by_id: BTreeMap<u16, String>,
+}
+
+impl ChannelMap {
+    /// An empty map; built-in names still resolve.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name channel `id`.
+    ///
+    /// Fails with [`FrameError::InvalidConfig`] if `id` is reserved, the name
+    /// is empty or a built-in name, or either is already mapped.
+    pub fn insert(&mut self, name: impl Into<String>, id: u16) -> Result<()> {
+        let name = name.into();
+        let invalid = |msg: String| Err(FrameError::InvalidConfig(msg));
+        if name.is_empty() {
+            return invalid("channel name must not be empty".to_string());
+        }
+        if is_reserved(id) {
+            return invalid(format!(
+                "channel '{name}' uses reserved id {id} (user channels start at {USER_CHANNEL_START})"
+            ));
+        }
+        if builtin_id(&name).is_some() {
+            return invalid(format!("channel name '{name}' is a built-in channel"));
+        }
+        if let Some(existing) = self.by_name.get(&name) {
+            return invalid(format!("channel '{name}' is already mapped to {existing}"));
+        }
+        if let Some(existing) = self.by_id.get(&id) {
+            return invalid(format!("channel {id} is already named '{existing}'"));
+        }
+        self.by_id.insert(id, name.clone());
+        self.by_name.insert(name, id);
+        Ok(())
+    }
+
+    /// Channel ID for `name`: a mapped name, or a built-in one in any case.
+    pub fn get(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).copied().or_else(|| builtin_id(name))
+    }
+
+    /// Name of channel `id`: its mapped name, or the built-in constant name.
+    pub fn name(&self, id: u16) -> Option<&str> {
+        match self.by_id.get(&id) {
+            Some(name) => Some(name),
+            None => builtin_channel(id).map(|info| info.name),
+        }
+    }
+
+    /// IDs for `names`, in order; fails with [`FrameError::InvalidConfig`]
+    /// naming the first unknown one.
+    pub fn resolve<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<u16>> {
+        names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref();
+                self.get(name)
+                    .ok_or_else(|| FrameError::InvalidConfig(format!("unknown channel '{name}'")))
+            })
+            .collect()
+    }
+
+    /// Mapped `(name, id)` pairs in name order; built-ins are not included.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.by_name.iter().map(|(name, &id)| (name.as_str(), id))
+    }
+
+    /// Mapped IDs in ascending order.
+    pub fn ids(&self) -> Vec<u16> {
+        self.by_id.keys().copied().collect()
+    }
+
+    /// Number of mapped channels.
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    /// True if no channels are mapped.
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+impl TryFrom<BTreeMap<String, u16>> for ChannelMap {
+    type Error = FrameError;
+
+    fn try_from(names: BTreeMap<String, u16>) -> Result<Self> {
+        let mut map = ChannelMap::new();
+        for (name, id) in names {
+            map.insert(name, id)?;
+        }
+        Ok(map)
+    }
+}
+
+impl From<ChannelMap> for BTreeMap<String, u16> {
+    fn from(map: ChannelMap) -> Self {
+        map.by_name
+    }
+}
+
+/// ID of the built-in channel called `name`, ignoring case.
+fn builtin_id(name: &str) -> Option<u16> {
+    BUILTIN_CHANNELS
+        .iter()
+        .find(|info| info.name.eq_ignore_ascii_case(name))
+        .map(|info| info.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_registry_matches_constants() {
+        // Every channel constant this module exports, read from its source
+        // so a new constant without a registry entry fails here.
+        let constants: Vec<(&str, u16)> = include_str!("channel.rs")
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const ")?.strip_suffix(';'))
+            .filter_map(|decl| {
+                let (name, value) = decl.split_once(": u16 = ")?;
+                Some((name, value.parse().ok()?))
+            })
+            .filter(|&(name, _)| name != "USER_CHANNEL_START")
+            .collect();
+        assert!(constants.contains(&("HEARTBEAT", HEARTBEAT)));
+        let registry: Vec<(&str, u16)> = BUILTIN_CHANNELS
+            .iter()
+            .map(|info| (info.name, info.id))
+            .collect();
+        assert_eq!(registry, constants);
+        assert!(registry.windows(2).all(|pair| pair[0].1 < pair[1].1));
+
+        for id in 0..USER_CHANNEL_START {
+            let named = !matches!(channel_name(id), "RESERVED");
+            assert_eq!(named, builtin_channel(id).is_some(), "channel {id}");
+        }
+        for info in BUILTIN_CHANNELS {
+            assert!(is_builtin(info.id));
+            assert_eq!(channel_name(info.id), info.name);
+            assert!(!info.purpose.is_empty());
+        }
+    }
+
+    #[test]
+    fn builtin_channel_lookup() {
+        assert_eq!(builtin_channel(DATA).map(|info| info.name), Some("DATA"));
+        assert!(builtin_channel(5).is_none());
+        assert!(builtin_channel(USER_CHANNEL_START).is_none());
+    }
+
+    #[test]
+    fn channel_map_lookups() {
+        let mut map = ChannelMap::new();
+        map.insert("metrics", 256).unwrap();
+        map.insert(String::from("audit"), 300).unwrap();
+
+        assert_eq!(map.get("metrics"), Some(256));
+        assert_eq!(map.get("Command"), Some(COMMAND));
+        assert_eq!(map.get("nope"), None);
+        assert_eq!(map.name(300), Some("audit"));
+        assert_eq!(map.name(ERROR), Some("ERROR"));
+        assert_eq!(map.name(301), None);
+        assert_eq!(map.ids(), vec![256, 300]);
+        assert_eq!(map.resolve(&["data", "metrics"]).unwrap(), vec![DATA, 256]);
+        assert!(matches!(
+            map.resolve(&["metrics", "nope"]),
+            Err(FrameError::InvalidConfig(msg)) if msg.contains("'nope'")
+        ));
+    }
+
+    #[test]
+    fn channel_map_rejects_collisions() {
+        let mut map = ChannelMap::new();
+        map.insert("metrics", 256).unwrap();
+        for (name, id) in [
+            ("metrics", 257),
+            ("other", 256),
+            ("low", 5),
+            ("data", 400),
+            ("", 401),
+        ] {
+            assert!(
+                matches!(map.insert(name, id), Err(FrameError::InvalidConfig(_))),
+                "{name} = {id}"
+            );
+        }
+        assert_eq!(map.len(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn channel_map_serde_roundtrip() {
+        let mut map = ChannelMap::new();
+        map.insert("metrics", 256).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"metrics":256}"#);
+        assert_eq!(serde_json::from_str::<ChannelMap>(&json).unwrap(), map);
+
+        assert!(serde_json::from_str::<ChannelMap>(r#"{"metrics":2}"#).is_err());
+        assert!(serde_json::from_str::<ChannelMap>(r#"{"a":256,"b":256}"#).is_err());
+    }
 }
@@ -1,16 +1,161 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
+use crate::compression::{self, Compression, DEFAULT_COMPRESSION_THRESHOLD};
+use crate::crc32;
 use crate::error::{FrameError, Result};
 
 /// Frame header: magic (2) + length (4) + channel (2) = 8 bytes.
 pub const HEADER_SIZE: usize = 8;
 
 /// Magic bytes: "IP" (0x49 0x50).
+///
+/// The basic header, and the only [`WireVersion::V1`] form.
 pub const MAGIC: [u8; 2] = [0x49, 0x50];
 
+/// Magic bytes of the versioned header: "IV" (0x49 0x56).
+///
+/// Followed by a version byte, a flags byte, the length and channel fields
+/// of the basic header, and then one extension field per flag that has one,
+/// in this order: priority ([`FLAG_PRIORITY`], 1 byte), CRC-32
+/// ([`FLAG_CHECKSUM`]), decoded length ([`FLAG_COMPRESSED`]), fragment index
+/// ([`FLAG_FRAGMENT`]) and sequence number ([`FLAG_SEQUENCE`]), each 4 bytes
+/// LE. Later layouts bump the version byte rather than adding a magic.
+pub const VERSIONED_MAGIC: [u8; 2] = [0x49, 0x56];
+
+/// Versioned header without extension fields: magic (2) + version (1) +
+/// flags (1) + length (4) + channel (2) = 10 bytes.
+pub const VERSIONED_HEADER_SIZE: usize = HEADER_SIZE + 2;
+
+/// Versioned header with every extension field: versioned header (10) +
+/// priority (1) + four 4-byte fields = 27 bytes.
+pub const MAX_HEADER_SIZE: usize = VERSIONED_HEADER_SIZE + 1 + 4 * 4;
+
+/// Flags bit: the payload is zstd-compressed, and a decoded length field
+/// follows the header.
+pub const FLAG_COMPRESSED: u8 = 0x01;
+
+/// Flags bit: a CRC-32 of the payload as sent follows the header.
+pub const FLAG_CHECKSUM: u8 = 0x02;
+
+/// Flags bit: the payload is one fragment of a larger message, and its
+/// 0-based index follows the header. Never combined with [`FLAG_COMPRESSED`].
+pub const FLAG_FRAGMENT: u8 = 0x04;
+
+/// Flags bit: the last fragment of a message. Only set with [`FLAG_FRAGMENT`],
+/// and adds no field.
+pub const FLAG_LAST_FRAGMENT: u8 = 0x08;
+
+/// Flags bit: a per-channel sequence number follows the header. See
+/// [`FrameConfig::sequence_numbers`].
+pub const FLAG_SEQUENCE: u8 = 0x10;
+
+/// Flags bit: a nonzero priority byte follows the header. Frames without it
+/// are priority 0.
+pub const FLAG_PRIORITY: u8 = 0x20;
+
+const KNOWN_FLAGS: u8 = FLAG_COMPRESSED
+    | FLAG_CHECKSUM
+    | FLAG_FRAGMENT
+    | FLAG_LAST_FRAGMENT
+    | FLAG_SEQUENCE
+    | FLAG_PRIORITY;
+
+/// Flags whose extension field is 4 bytes long.
+const WORD_FIELD_FLAGS: u8 = FLAG_CHECKSUM | FLAG_COMPRESSED | FLAG_FRAGMENT | FLAG_SEQUENCE;
+
+/// Header layout writers use for frames that need no extension fields.
+///
+/// Readers accept every version: the magic tells the basic header from the
+/// versioned one, whose version byte tells its layouts apart, so a stream
+/// may mix them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum WireVersion {
+    /// The basic header ([`MAGIC`]). Frames that need extension fields
+    /// (priority, checksum, ...) use the version 2 header regardless; peers
+    /// only send those once the handshake shows the remote decodes it.
+    #[default]
+    V1 = 1,
+    /// The versioned header ([`VERSIONED_MAGIC`]) with version byte 2 for
+    /// every frame, so new flag bits can be added without another header.
+    /// Readers that predate a flag reject it with
+    /// [`FrameError::UnsupportedFlags`], and a version byte with
+    /// [`FrameError::UnsupportedVersion`].
+    V2 = 2,
+}
+
+impl WireVersion {
+    /// The newest version this crate reads and writes.
+    pub const LATEST: WireVersion = WireVersion::V2;
+
+    /// The version number exchanged during handshakes and carried in the
+    /// versioned header.
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// The newest version known here that is not newer than `version`.
+    /// Numbers below 2 (including unknown ones) fall back to
+    /// [`WireVersion::V1`].
+    pub fn at_most(version: u8) -> WireVersion {
+        if version >= WireVersion::V2.as_u8() {
+            WireVersion::V2
+        } else {
+            WireVersion::V1
+        }
+    }
+}
+
 /// Default maximum payload size: 16 MiB.
 pub const DEFAULT_MAX_PAYLOAD: usize = 16 * 1024 * 1024;
 
+/// Default [`FrameConfig::copy_out_threshold`]: 4 KiB.
+pub const DEFAULT_COPY_OUT_THRESHOLD: usize = 4 * 1024;
+
+/// Default [`FrameConfig::buffer_shrink_threshold`]: 64 KiB.
+pub const DEFAULT_BUFFER_SHRINK_THRESHOLD: usize = 64 * 1024;
+
+/// Default [`FrameConfig::reassembly_timeout`]: 30 seconds.
+pub const DEFAULT_REASSEMBLY_TIMEOUT: core::time::Duration = core::time::Duration::from_secs(30);
+
+/// Delivery priority of a message: [`Priority::NORMAL`], or higher for more
+/// urgent.
+///
+/// Travels as the priority field of the versioned header, flagged with
+/// [`FLAG_PRIORITY`]; normal-priority frames carry no field, so they keep
+/// the basic header under [`WireVersion::V1`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub u8);
+
+impl Priority {
+    /// Priority 0, the default.
+    pub const NORMAL: Priority = Priority(0);
+
+    /// The most urgent priority.
+    pub const HIGHEST: Priority = Priority(u8::MAX);
+
+    /// The value carried on the wire.
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<u8> for Priority {
+    fn from(priority: u8) -> Self {
+        Priority(priority)
+    }
+}
+
+impl From<Priority> for u8 {
+    fn from(priority: Priority) -> Self {
+        priority.0
+    }
+}
+
 /// A framed message with channel routing.
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -18,23 +163,282 @@ pub struct Frame {
     pub channel: u16,
     /// The message payload.
     pub payload: Bytes,
+    /// Delivery priority; [`Priority::NORMAL`] unless set.
+    pub priority: Priority,
+    /// Per-channel sequence number, when the sender stamps them (see
+    /// [`FrameConfig::sequence_numbers`]). Writers assign their own and
+    /// ignore this field.
+    pub sequence: Option<u32>,
 }
 
 impl Frame {
-    /// Create a new frame.
+    /// Create a new frame at [`Priority::NORMAL`].
     pub fn new(channel: u16, payload: impl Into<Bytes>) -> Self {
         Self {
             channel,
             payload: payload.into(),
+            priority: Priority::NORMAL,
+            sequence: None,
         }
     }
 
+    /// Set the delivery priority.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     /// The total wire size of this frame (header + payload).
     pub fn wire_size(&self) -> usize {
-        HEADER_SIZE + self.payload.len()
+        let fields = Extensions {
+            priority: self.priority,
+            sequence: self.sequence,
+            ..Extensions::default()
+        };
+        fields.header_size(false) + self.payload.len()
+    }
+
+    /// Encode this frame into a new `Vec`; see [`Frame::encode_into`].
+    pub fn encode_to_vec(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(self.wire_size());
+        self.encode_into(&mut dst);
+        dst
+    }
+
+    /// Append this frame's wire bytes to `dst`.
+    ///
+    /// Uses the header a default [`FrameWriter`](crate::FrameWriter) would:
+    /// basic for normal priority without a [`Frame::sequence`], versioned
+    /// otherwise. Payloads are never compressed or fragmented.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the payload is longer than `u32::MAX` bytes.
+    pub fn encode_into(&self, dst: &mut Vec<u8>) {
+        let header = encode_header(
+            &FrameConfig::default(),
+            self.channel,
+            &[&self.payload],
+            self.priority,
+            self.sequence,
+        )
+        .expect("payload length exceeds the u32 length field")
+        .expect("the default config neither compresses nor fragments");
+        dst.reserve(header.as_bytes().len() + self.payload.len());
+        dst.extend_from_slice(header.as_bytes());
+        dst.extend_from_slice(&self.payload);
+    }
+
+    /// Decode the frame at the start of `src`, returning it and the number of
+    /// bytes it took up. Bytes after the frame are ignored; use
+    /// [`Frame::decode_from_slice_exact`] to reject them.
+    ///
+    /// Payloads are limited to [`DEFAULT_MAX_PAYLOAD`]. A slice that ends
+    /// partway through the frame fails with [`FrameError::Truncated`]; other
+    /// errors match [`decode_frame`], and fragments are rejected the same way.
+    pub fn decode_from_slice(src: &[u8]) -> Result<(Frame, usize)> {
+        let Some((_, total)) = buffered_frame(src, DEFAULT_MAX_PAYLOAD)? else {
+            return Err(FrameError::Truncated {
+                buffered: src.len(),
+            });
+        };
+        let mut buf = BytesMut::from(&src[..total]);
+        let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)?
+            .expect("buffered_frame found a complete frame");
+        Ok((frame, total))
+    }
+
+    /// Decode `src` as exactly one frame, failing with
+    /// [`FrameError::TrailingBytes`] if anything follows it.
+    pub fn decode_from_slice_exact(src: &[u8]) -> Result<Frame> {
+        let (frame, consumed) = Frame::decode_from_slice(src)?;
+        if consumed < src.len() {
+            return Err(FrameError::TrailingBytes {
+                extra: src.len() - consumed,
+            });
+        }
+        Ok(frame)
+    }
+}
+
+/// The fixed part of a frame header: everything up to the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HeaderPrefix {
+    /// Size of the whole header, extension fields included.
+    pub(crate) size: usize,
+    pub(crate) payload_len: usize,
+    pub(crate) channel: u16,
+    /// Flags of a versioned header; 0 for the basic one.
+    pub(crate) flags: u8,
+}
+
+/// Parse the fixed part of the header at the front of `src`, dispatching on
+/// the magic and then on the version byte.
+///
+/// Returns `Ok(None)` until it is buffered. An unknown magic, version or
+/// flag bit leaves the frame's size unknown, so nothing can be skipped and
+/// the stream has to be resynchronized.
+pub(crate) fn header_prefix(src: &[u8]) -> Result<Option<HeaderPrefix>> {
+    if src.len() < HEADER_SIZE {
+        return Ok(None);
+    }
+    if src[..2] == MAGIC {
+        return Ok(Some(HeaderPrefix {
+            size: HEADER_SIZE,
+            payload_len: u32::from_le_bytes(src[2..6].try_into().unwrap()) as usize,
+            channel: u16::from_le_bytes([src[6], src[7]]),
+            flags: 0,
+        }));
+    }
+    if src[..2] != VERSIONED_MAGIC {
+        return Err(FrameError::InvalidMagic);
+    }
+    match src[2] {
+        2 => {
+            let flags = src[3];
+            let unknown = flags & !KNOWN_FLAGS;
+            if unknown != 0 {
+                return Err(FrameError::UnsupportedFlags(unknown));
+            }
+            if src.len() < VERSIONED_HEADER_SIZE {
+                return Ok(None);
+            }
+            Ok(Some(HeaderPrefix {
+                size: versioned_header_size(flags),
+                payload_len: u32::from_le_bytes(src[4..8].try_into().unwrap()) as usize,
+                channel: u16::from_le_bytes([src[8], src[9]]),
+                flags,
+            }))
+        }
+        version => Err(FrameError::UnsupportedVersion(version)),
     }
 }
 
+/// Size of a version 2 header with these flags.
+fn versioned_header_size(flags: u8) -> usize {
+    let priority = usize::from(flags & FLAG_PRIORITY != 0);
+    VERSIONED_HEADER_SIZE + priority + 4 * (flags & WORD_FIELD_FLAGS).count_ones() as usize
+}
+
+/// The extension fields of a versioned header.
+#[derive(Debug, Default)]
+struct Extensions {
+    priority: Priority,
+    crc: Option<u32>,
+    decoded_len: Option<u32>,
+    fragment: Option<Fragment>,
+    sequence: Option<u32>,
+}
+
+impl Extensions {
+    /// Flags announcing these fields.
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.priority != Priority::NORMAL {
+            flags |= FLAG_PRIORITY;
+        }
+        if self.crc.is_some() {
+            flags |= FLAG_CHECKSUM;
+        }
+        if self.decoded_len.is_some() {
+            flags |= FLAG_COMPRESSED;
+        }
+        if let Some(fragment) = self.fragment {
+            flags |= FLAG_FRAGMENT;
+            if fragment.last {
+                flags |= FLAG_LAST_FRAGMENT;
+            }
+        }
+        if self.sequence.is_some() {
+            flags |= FLAG_SEQUENCE;
+        }
+        flags
+    }
+
+    /// Size of the header [`Extensions::put_header`] writes.
+    fn header_size(&self, versioned: bool) -> usize {
+        match self.flags() {
+            0 if !versioned => HEADER_SIZE,
+            flags => versioned_header_size(flags),
+        }
+    }
+
+    /// Write the header for a payload of `len` bytes: the basic one when
+    /// there are no fields and `versioned` is false, version 2 otherwise.
+    fn put_header(&self, dst: &mut impl BufMut, len: usize, channel: u16, versioned: bool) {
+        let flags = self.flags();
+        if flags == 0 && !versioned {
+            dst.put_slice(&MAGIC);
+            dst.put_u32_le(len as u32);
+            dst.put_u16_le(channel);
+            return;
+        }
+        dst.put_slice(&VERSIONED_MAGIC);
+        dst.put_u8(WireVersion::V2.as_u8());
+        dst.put_u8(flags);
+        dst.put_u32_le(len as u32);
+        dst.put_u16_le(channel);
+        if self.priority != Priority::NORMAL {
+            dst.put_u8(self.priority.get());
+        }
+        for field in [
+            self.crc,
+            self.decoded_len,
+            self.fragment.map(|fragment| fragment.index),
+            self.sequence,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            dst.put_u32_le(field);
+        }
+    }
+
+    /// Read the fields `flags` announces from `fields`, the header bytes
+    /// after the channel.
+    fn parse(mut fields: &[u8], flags: u8) -> Self {
+        let priority = if flags & FLAG_PRIORITY != 0 {
+            Priority(fields.get_u8())
+        } else {
+            Priority::NORMAL
+        };
+        let crc = (flags & FLAG_CHECKSUM != 0).then(|| fields.get_u32_le());
+        let decoded_len = (flags & FLAG_COMPRESSED != 0).then(|| fields.get_u32_le());
+        let fragment = (flags & FLAG_FRAGMENT != 0).then(|| Fragment {
+            index: fields.get_u32_le(),
+            last: flags & FLAG_LAST_FRAGMENT != 0,
+        });
+        let sequence = (flags & FLAG_SEQUENCE != 0).then(|| fields.get_u32_le());
+        Self {
+            priority,
+            crc,
+            decoded_len,
+            fragment,
+            sequence,
+        }
+    }
+}
+
+/// Fail with [`FrameError::BufferLimitExceeded`] when the frame whose header
+/// starts `src` needs more than `max` bytes, header included. Bytes that do
+/// not start with a known header are left for decoding to reject.
+pub(crate) fn check_buffer_limit(src: &[u8], max: Option<usize>) -> Result<()> {
+    let Some(max) = max else {
+        return Ok(());
+    };
+    let Ok(Some(header)) = header_prefix(src) else {
+        return Ok(());
+    };
+    let needed = header.size + header.payload_len;
+    if needed > max {
+        return Err(FrameError::BufferLimitExceeded {
+            buffered: src.len().min(needed),
+            max,
+        });
+    }
+    Ok(())
+}
+
 /// Encode a frame into the wire format.
 ///
 /// Wire format:
@@ -60,39 +464,452 @@ pub fn encode_frame(channel: u16, payload: &[u8], dst: &mut BytesMut) -> Result<
     Ok(())
 }
 
+/// Encode a frame with a delivery priority.
+///
+/// [`Priority::NORMAL`] produces exactly the [`encode_frame`] output; anything else
+/// uses the versioned header ([`VERSIONED_MAGIC`]) with [`FLAG_PRIORITY`],
+/// which peers older than that header cannot decode.
+pub fn encode_frame_with_priority(
+    channel: u16,
+    payload: &[u8],
+    priority: Priority,
+    dst: &mut BytesMut,
+) -> Result<()> {
+    let fields = Extensions {
+        priority,
+        ..Extensions::default()
+    };
+    encode_with_fields(channel, payload, &fields, false, dst)
+}
+
+/// Encode a frame with a CRC-32 of the payload in the versioned header
+/// ([`FLAG_CHECKSUM`]).
+///
+/// Decoders verify the checksum and reject a corrupted payload with
+/// [`FrameError::ChecksumMismatch`]. Peers older than the versioned header
+/// cannot decode it.
+pub fn encode_frame_with_checksum(
+    channel: u16,
+    payload: &[u8],
+    priority: Priority,
+    dst: &mut BytesMut,
+) -> Result<()> {
+    let fields = Extensions {
+        priority,
+        crc: Some(crc32::checksum(payload)),
+        ..Extensions::default()
+    };
+    encode_with_fields(channel, payload, &fields, false, dst)
+}
+
+/// Encode `payload` behind a header carrying `fields`; see
+/// [`Extensions::put_header`].
+fn encode_with_fields(
+    channel: u16,
+    payload: &[u8],
+    fields: &Extensions,
+    versioned: bool,
+    dst: &mut BytesMut,
+) -> Result<()> {
+    if payload.len() > u32::MAX as usize {
+        return Err(FrameError::PayloadTooLarge {
+            size: payload.len(),
+            max: u32::MAX as usize,
+        });
+    }
+    dst.reserve(fields.header_size(versioned) + payload.len());
+    fields.put_header(dst, payload.len(), channel, versioned);
+    dst.put_slice(payload);
+    Ok(())
+}
+
+/// Encode a whole message: fragmented when [`FrameConfig::max_fragment_size`]
+/// is set and the payload is larger, otherwise as one frame via
+/// [`encode_frame_configured`]. Fragments are never compressed, and every
+/// fragment carries the message's sequence number.
+pub(crate) fn encode_message(
+    config: &FrameConfig,
+    channel: u16,
+    payload: &[u8],
+    priority: Priority,
+    sequence: Option<u32>,
+    dst: &mut BytesMut,
+) -> Result<()> {
+    let Some(fragment_size) = config.fragment_size() else {
+        return encode_frame_configured(config, channel, payload, priority, sequence, dst);
+    };
+    if payload.len() <= fragment_size {
+        return encode_frame_configured(config, channel, payload, priority, sequence, dst);
+    }
+    let count = payload.len().div_ceil(fragment_size);
+    if count > u32::MAX as usize {
+        return Err(FrameError::PayloadTooLarge {
+            size: payload.len(),
+            max: fragment_size * u32::MAX as usize,
+        });
+    }
+    for (index, data) in payload.chunks(fragment_size).enumerate() {
+        let fields = Extensions {
+            fragment: Some(Fragment {
+                index: index as u32,
+                last: index + 1 == count,
+            }),
+            ..plain_extensions(config, &[data], priority, sequence)
+        };
+        encode_with_fields(channel, data, &fields, true, dst)?;
+    }
+    Ok(())
+}
+
+/// The encoded header of a frame whose payload goes on the wire as is.
+pub(crate) struct EncodedHeader {
+    bytes: [u8; MAX_HEADER_SIZE],
+    len: usize,
+}
+
+impl EncodedHeader {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Encode just the header [`encode_frame_configured`] would put in front of
+/// a payload made of `parts` joined end to end, so the payload can be
+/// written without copying it.
+///
+/// Returns `Ok(None)` when `config` may compress or fragment the payload;
+/// those messages still need [`encode_message`].
+pub(crate) fn encode_header(
+    config: &FrameConfig,
+    channel: u16,
+    parts: &[&[u8]],
+    priority: Priority,
+    sequence: Option<u32>,
+) -> Result<Option<EncodedHeader>> {
+    let len: usize = parts.iter().map(|part| part.len()).sum();
+    let may_compress =
+        config.compression != Compression::None && len >= config.compression_threshold;
+    let fragments = config.fragment_size().is_some_and(|size| len > size);
+    if may_compress || fragments {
+        return Ok(None);
+    }
+    if len > u32::MAX as usize {
+        return Err(FrameError::PayloadTooLarge {
+            size: len,
+            max: u32::MAX as usize,
+        });
+    }
+
+    let mut bytes = [0u8; MAX_HEADER_SIZE];
+    let mut dst = &mut bytes[..];
+    plain_extensions(config, parts, priority, sequence).put_header(
+        &mut dst,
+        len,
+        channel,
+        config.flags_every_frame(),
+    );
+    let len = MAX_HEADER_SIZE - dst.len();
+    Ok(Some(EncodedHeader { bytes, len }))
+}
+
+/// Extension fields for an uncompressed, unfragmented payload of `parts`.
+fn plain_extensions(
+    config: &FrameConfig,
+    parts: &[&[u8]],
+    priority: Priority,
+    sequence: Option<u32>,
+) -> Extensions {
+    Extensions {
+        priority,
+        crc: config.checksum.then(|| crc32::checksum_parts(parts)),
+        sequence,
+        ..Extensions::default()
+    }
+}
+
+/// Encode with the header `config` asks for: compressed when
+/// [`FrameConfig::compression`] applies and actually shrinks the payload,
+/// checksummed when [`FrameConfig::checksum`] is set, and versioned under
+/// [`WireVersion::V2`] or whenever the frame has an extension field.
+pub(crate) fn encode_frame_configured(
+    config: &FrameConfig,
+    channel: u16,
+    payload: &[u8],
+    priority: Priority,
+    sequence: Option<u32>,
+    dst: &mut BytesMut,
+) -> Result<()> {
+    if payload.len() >= config.compression_threshold {
+        if let Some(compressed) = config.compression.compress(payload)? {
+            if compressed.len() < payload.len() {
+                if payload.len() > u32::MAX as usize {
+                    return Err(FrameError::PayloadTooLarge {
+                        size: payload.len(),
+                        max: u32::MAX as usize,
+                    });
+                }
+                let fields = Extensions {
+                    decoded_len: Some(payload.len() as u32),
+                    ..plain_extensions(config, &[compressed.as_slice()], priority, sequence)
+                };
+                return encode_with_fields(channel, &compressed, &fields, true, dst);
+            }
+        }
+    }
+    let fields = plain_extensions(config, &[payload], priority, sequence);
+    encode_with_fields(channel, payload, &fields, config.flags_every_frame(), dst)
+}
+
+/// Position of one wire frame within a fragmented message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fragment {
+    pub(crate) index: u32,
+    pub(crate) last: bool,
+}
+
 /// Decode a frame from a buffer.
 ///
 /// Returns `Ok(None)` if the buffer doesn't contain a complete frame yet.
-/// On success, consumes the frame bytes from the buffer.
+/// On success, consumes the frame bytes from the buffer. A checksummed frame
+/// whose payload does not match is consumed as well, so the buffer stays
+/// aligned on the next frame, and reported as
+/// [`FrameError::ChecksumMismatch`]. Compressed payloads are inflated; one
+/// that would exceed `max_payload` once decompressed is consumed and
+/// reported as [`FrameError::PayloadTooLarge`] without being decompressed.
+///
+/// The magic and the version byte of a versioned header pick the layout; an
+/// unknown one fails with [`FrameError::InvalidMagic`] or
+/// [`FrameError::UnsupportedVersion`], and unknown flag bits with
+/// [`FrameError::UnsupportedFlags`], all without consuming anything.
+///
+/// Fragments of a larger message are consumed and rejected with
+/// [`FrameError::Fragmentation`]; [`FrameReader`](crate::FrameReader) and
+/// `IpcCodec` reassemble them.
 pub fn decode_frame(src: &mut BytesMut, max_payload: usize) -> Result<Option<Frame>> {
-    if src.len() < HEADER_SIZE {
-        return Ok(None); // Need more data
+    match decode_frame_copying(src, max_payload, 0)? {
+        Some((_, Some(fragment))) => Err(FrameError::Fragmentation(format!(
+            "fragment {} of a larger message needs reassembly",
+            fragment.index
+        ))),
+        decoded => Ok(decoded.map(|(frame, _)| frame)),
     }
+}
 
-    // Check magic
-    if src[0..2] != MAGIC {
-        return Err(FrameError::InvalidMagic);
-    }
+/// Channel and payload length of a frame, read from its header alone.
+///
+/// Returned by [`FrameReader::peek_header`](crate::FrameReader::peek_header).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Channel ID.
+    pub channel: u16,
+    /// Payload length as the reader will return it: the decoded length of a
+    /// compressed payload, and the length of this fragment alone for a
+    /// fragment of a larger message.
+    pub payload_len: usize,
+}
 
-    let payload_len = u32::from_le_bytes(src[2..6].try_into().unwrap()) as usize;
-    let channel = u16::from_le_bytes(src[6..8].try_into().unwrap());
+impl FrameHeader {
+    pub(crate) fn of(frame: &Frame) -> Self {
+        Self {
+            channel: frame.channel,
+            payload_len: frame.payload.len(),
+        }
+    }
+}
 
+/// Parse the header at the front of `src` without consuming anything.
+///
+/// Returns `Ok(None)` until the whole header is buffered. Rejects a bad
+/// magic, version or flags and a payload (or decoded payload) larger than
+/// `max_payload`, as decoding would.
+pub(crate) fn peek_header(src: &[u8], max_payload: usize) -> Result<Option<FrameHeader>> {
+    let Some(header) = header_prefix(src)? else {
+        return Ok(None);
+    };
+    let Some(header_bytes) = src.get(..header.size) else {
+        return Ok(None);
+    };
+    let fields = extensions(header_bytes, &header);
+    let payload_len = fields
+        .decoded_len
+        .map_or(header.payload_len, |len| len as usize);
     if payload_len > max_payload {
         return Err(FrameError::PayloadTooLarge {
             size: payload_len,
             max: max_payload,
         });
     }
+    Ok(Some(FrameHeader {
+        channel: header.channel,
+        payload_len,
+    }))
+}
 
-    let total = HEADER_SIZE + payload_len;
+/// Header and total length of the frame at the front of `src`, once it is
+/// completely buffered.
+fn buffered_frame(src: &[u8], max_payload: usize) -> Result<Option<(HeaderPrefix, usize)>> {
+    let Some(header) = header_prefix(src)? else {
+        return Ok(None); // Need more data
+    };
+    if header.payload_len > max_payload {
+        return Err(FrameError::PayloadTooLarge {
+            size: header.payload_len,
+            max: max_payload,
+        });
+    }
+
+    let total = header.size + header.payload_len;
     if src.len() < total {
         return Ok(None); // Need more data
     }
+    Ok(Some((header, total)))
+}
 
-    src.advance(HEADER_SIZE);
-    let payload = src.split_to(payload_len).freeze();
+/// Extension fields of the complete header `header_bytes`.
+fn extensions(header_bytes: &[u8], header: &HeaderPrefix) -> Extensions {
+    match header_bytes.get(VERSIONED_HEADER_SIZE..header.size) {
+        Some(fields) if header.flags != 0 => Extensions::parse(fields, header.flags),
+        _ => Extensions::default(),
+    }
+}
 
-    Ok(Some(Frame { channel, payload }))
+/// Extension fields of a complete frame, after checking them against each
+/// other, the payload and `max_payload`.
+fn checked_extensions(
+    frame: &[u8],
+    header: &HeaderPrefix,
+    max_payload: usize,
+) -> Result<Extensions> {
+    let flags = header.flags;
+    if flags & FLAG_LAST_FRAGMENT != 0 && flags & FLAG_FRAGMENT == 0 {
+        return Err(FrameError::Fragmentation(
+            "last-fragment flag on a frame that is not a fragment".to_string(),
+        ));
+    }
+    if flags & FLAG_FRAGMENT != 0 && flags & FLAG_COMPRESSED != 0 {
+        return Err(FrameError::Fragmentation(
+            "compressed fragments are not supported".to_string(),
+        ));
+    }
+    let fields = extensions(frame, header);
+    if let Some(expected) = fields.crc {
+        let actual = crc32::checksum(&frame[header.size..]);
+        if actual != expected {
+            return Err(FrameError::ChecksumMismatch { expected, actual });
+        }
+    }
+    if let Some(decoded_len) = fields.decoded_len {
+        if decoded_len as usize > max_payload {
+            return Err(FrameError::PayloadTooLarge {
+                size: decoded_len as usize,
+                max: max_payload,
+            });
+        }
+    }
+    Ok(fields)
+}
+
+/// Decode one wire frame, which may be a fragment of a larger message.
+///
+/// Payloads of at most `copy_out_threshold` bytes are copied into their own
+/// allocation instead of sharing `src`'s.
+pub(crate) fn decode_frame_copying(
+    src: &mut BytesMut,
+    max_payload: usize,
+    copy_out_threshold: usize,
+) -> Result<Option<(Frame, Option<Fragment>)>> {
+    let Some((header, total)) = buffered_frame(src, max_payload)? else {
+        return Ok(None);
+    };
+    let fields = match checked_extensions(&src[..total], &header, max_payload) {
+        Ok(fields) => fields,
+        Err(err) => {
+            src.advance(total);
+            return Err(err);
+        }
+    };
+
+    let payload = if let Some(decoded_len) = fields.decoded_len {
+        let payload = compression::decompress(&src[header.size..total], decoded_len as usize);
+        src.advance(total);
+        Bytes::from(payload?)
+    } else {
+        src.advance(header.size);
+        if header.payload_len <= copy_out_threshold {
+            let payload = Bytes::copy_from_slice(&src[..header.payload_len]);
+            src.advance(header.payload_len);
+            payload
+        } else {
+            src.split_to(header.payload_len).freeze()
+        }
+    };
+
+    Ok(Some((
+        Frame {
+            channel: header.channel,
+            payload,
+            priority: fields.priority,
+            sequence: fields.sequence,
+        },
+        fields.fragment,
+    )))
+}
+
+/// A growable buffer a received payload can be copied into.
+pub(crate) trait PayloadBuf {
+    /// Replace the contents with `payload`, reusing the capacity.
+    fn replace_with(&mut self, payload: &[u8]);
+
+    fn len(&self) -> usize;
+}
+
+impl PayloadBuf for Vec<u8> {
+    fn replace_with(&mut self, payload: &[u8]) {
+        self.clear();
+        self.extend_from_slice(payload);
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+impl PayloadBuf for BytesMut {
+    fn replace_with(&mut self, payload: &[u8]) {
+        self.clear();
+        self.extend_from_slice(payload);
+    }
+
+    fn len(&self) -> usize {
+        BytesMut::len(self)
+    }
+}
+
+/// Copy the payload of the frame at the front of `src` into `dst`, replacing
+/// its contents, and return the channel.
+///
+/// Returns `Ok(None)` without consuming anything when the frame is incomplete
+/// or has a flag other than [`FLAG_PRIORITY`] and [`FLAG_CHECKSUM`], which
+/// needs [`decode_frame_copying`]. Errors match `decode_frame_copying`, and
+/// leave `dst` untouched.
+pub(crate) fn decode_plain_frame_into(
+    src: &mut BytesMut,
+    max_payload: usize,
+    dst: &mut impl PayloadBuf,
+) -> Result<Option<u16>> {
+    let Some((header, total)) = buffered_frame(src, max_payload)? else {
+        return Ok(None);
+    };
+    if header.flags & !(FLAG_PRIORITY | FLAG_CHECKSUM) != 0 {
+        return Ok(None);
+    }
+    if let Err(err) = checked_extensions(&src[..total], &header, max_payload) {
+        src.advance(total);
+        return Err(err);
+    }
+    dst.replace_with(&src[header.size..total]);
+    src.advance(total);
+    Ok(Some(header.channel))
 }
 
 /// Configuration for the frame codec.
@@ -101,9 +918,145 @@ pub struct FrameConfig {
     /// Maximum payload size in bytes. Default: 16 MiB.
     pub max_payload_size: usize,
     /// Read timeout for blocking operations.
-    pub read_timeout: Option<std::time::Duration>,
+    pub read_timeout: Option<core::time::Duration>,
     /// Write timeout for blocking operations.
-    pub write_timeout: Option<std::time::Duration>,
+    pub write_timeout: Option<core::time::Duration>,
+    /// Received payloads up to this size are copied out of the read buffer
+    /// rather than sharing it, so small frames held by the caller do not pin
+    /// a buffer that grew for a large one. Default: 4 KiB.
+    pub copy_out_threshold: usize,
+    /// Writers send every frame with a CRC-32 of its payload
+    /// ([`FLAG_CHECKSUM`]). Readers verify checksummed frames regardless of
+    /// this setting. Default: `false`.
+    pub checksum: bool,
+    /// How writers compress payloads ([`FLAG_COMPRESSED`]). Readers decompress
+    /// whenever they were built with the `compression` feature, and check
+    /// `max_payload_size` against the decompressed size. Default:
+    /// [`Compression::None`].
+    pub compression: Compression,
+    /// Payloads shorter than this are sent uncompressed. Default: 1 KiB.
+    pub compression_threshold: usize,
+    /// Writers split payloads larger than this into fragments
+    /// ([`FLAG_FRAGMENT`]) of at most this many bytes, capped at
+    /// `max_payload_size`. `None` (the default) never fragments, and a
+    /// payload over `max_payload_size` is an error.
+    pub max_fragment_size: Option<usize>,
+    /// Largest logical message: the limit on a payload a writer fragments
+    /// and on a message a reader reassembles. Default: 16 MiB.
+    pub max_message_size: usize,
+    /// Fragments of one message must all arrive within this long of the
+    /// first. Default: 30 seconds.
+    pub reassembly_timeout: core::time::Duration,
+    /// Writers stamp every message with a sequence number counting up from 0
+    /// per channel and wrapping after `u32::MAX`, sent in the versioned
+    /// header ([`FLAG_SEQUENCE`]). Readers check stamped messages for gaps
+    /// regardless of this setting. Default: `false`.
+    pub sequence_numbers: bool,
+    /// Header layout for frames that need no extension fields; see
+    /// [`WireVersion`].
+    /// Readers accept every version regardless of this setting. Default:
+    /// [`WireVersion::V1`].
+    pub wire_version: WireVersion,
+    /// Most bytes a reader buffers toward one frame, header included. A
+    /// frame whose header announces more fails with
+    /// [`FrameError::BufferLimitExceeded`] before its payload is buffered.
+    /// Fragments count one at a time; reassembled messages are bounded by
+    /// `max_message_size`. `None` (the default) allows any frame within
+    /// `max_payload_size`.
+    pub max_buffer_bytes: Option<usize>,
+    /// Once a frame is read (or written), a buffer whose capacity grew past
+    /// this is swapped for a fresh one at its initial capacity, unless it
+    /// still holds more than this many unread bytes. Pooled buffers use the
+    /// pool's `max_buffer_capacity` if it is lower. `usize::MAX` never
+    /// shrinks. Default: 64 KiB.
+    pub buffer_shrink_threshold: usize,
+    /// Readers and writers count messages and payload bytes per channel,
+    /// exposed as [`FrameStats`](crate::FrameStats). Off (the default), no
+    /// counters are allocated or updated.
+    pub collect_stats: bool,
+}
+
+impl FrameConfig {
+    /// Start from the defaults and set fields one at a time; `build()`
+    /// validates the result.
+    pub fn builder() -> FrameConfigBuilder {
+        FrameConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable.
+    ///
+    /// Rejects a `max_payload_size` that cannot hold more than a header or
+    /// does not fit the 32-bit length field, zero timeouts (which the OS
+    /// rejects for sockets), a buffer limit that cannot hold more than a
+    /// header, a zero fragment or message size, and a fragment size larger
+    /// than the message size.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(FrameError::InvalidConfig(msg));
+        if self.max_payload_size <= HEADER_SIZE {
+            return invalid(format!(
+                "max_payload_size must be greater than {HEADER_SIZE}, got {}",
+                self.max_payload_size
+            ));
+        }
+        if self.max_payload_size > u32::MAX as usize {
+            return invalid(format!(
+                "max_payload_size must be at most {}, got {}",
+                u32::MAX,
+                self.max_payload_size
+            ));
+        }
+        for (name, timeout) in [
+            ("read_timeout", self.read_timeout),
+            ("write_timeout", self.write_timeout),
+            ("reassembly_timeout", Some(self.reassembly_timeout)),
+        ] {
+            if timeout == Some(core::time::Duration::ZERO) {
+                return invalid(format!("{name} must be nonzero"));
+            }
+        }
+        if let Some(max) = self.max_buffer_bytes {
+            if max <= HEADER_SIZE {
+                return invalid(format!(
+                    "max_buffer_bytes must be greater than {HEADER_SIZE}, got {max}"
+                ));
+            }
+        }
+        if self.max_message_size == 0 {
+            return invalid("max_message_size must be nonzero".to_string());
+        }
+        if let Some(fragment) = self.max_fragment_size {
+            if fragment == 0 {
+                return invalid("max_fragment_size must be nonzero".to_string());
+            }
+            if fragment > self.max_message_size {
+                return invalid(format!(
+                    "max_fragment_size ({fragment}) exceeds max_message_size ({})",
+                    self.max_message_size
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fragment size writers use, if they fragment at all.
+    pub(crate) fn fragment_size(&self) -> Option<usize> {
+        self.max_fragment_size
+            .map(|size| size.min(self.max_payload_size).max(1))
+    }
+
+    /// Whether writers use the versioned header even when no flag is set.
+    pub(crate) fn flags_every_frame(&self) -> bool {
+        self.wire_version >= WireVersion::V2
+    }
+
+    /// Largest payload a writer accepts in one send.
+    pub(crate) fn send_limit(&self) -> usize {
+        if self.max_fragment_size.is_some() {
+            self.max_message_size
+        } else {
+            self.max_payload_size
+        }
+    }
 }
 
 impl Default for FrameConfig {
@@ -112,14 +1065,187 @@ impl Default for FrameConfig {
             max_payload_size: DEFAULT_MAX_PAYLOAD,
             read_timeout: None,
             write_timeout: None,
+            copy_out_threshold: DEFAULT_COPY_OUT_THRESHOLD,
+            checksum: false,
+            compression: Compression::None,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            max_fragment_size: None,
+            max_message_size: DEFAULT_MAX_PAYLOAD,
+            reassembly_timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+            sequence_numbers: false,
+            wire_version: WireVersion::V1,
+            max_buffer_bytes: None,
+            buffer_shrink_threshold: DEFAULT_BUFFER_SHRINK_THRESHOLD,
+            collect_stats: false,
         }
     }
 }
 
+/// Builds a validated [`FrameConfig`]; see [`FrameConfig::builder`].
+///
+/// Unset fields keep their [`FrameConfig::default`] values.
+#[derive(Debug, Clone, Default)]
+pub struct FrameConfigBuilder {
+    config: FrameConfig,
+}
+
+impl FrameConfigBuilder {
+    /// See [`FrameConfig::max_payload_size`].
+    pub fn max_payload_size(mut self, max_payload_size: usize) -> Self {
+        self.config.max_payload_size = max_payload_size;
+        self
+    }
+
+    /// See [`FrameConfig::read_timeout`].
+    pub fn read_timeout(mut self, timeout: core::time::Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`FrameConfig::write_timeout`].
+    pub fn write_timeout(mut self, timeout: core::time::Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`FrameConfig::copy_out_threshold`].
+    pub fn copy_out_threshold(mut self, threshold: usize) -> Self {
+        self.config.copy_out_threshold = threshold;
+        self
+    }
+
+    /// See [`FrameConfig::checksum`].
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.config.checksum = checksum;
+        self
+    }
+
+    /// See [`FrameConfig::compression`].
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// See [`FrameConfig::compression_threshold`].
+    pub fn compression_threshold(mut self, threshold: usize) -> Self {
+        self.config.compression_threshold = threshold;
+        self
+    }
+
+    /// See [`FrameConfig::max_fragment_size`].
+    pub fn max_fragment_size(mut self, size: usize) -> Self {
+        self.config.max_fragment_size = Some(size);
+        self
+    }
+
+    /// See [`FrameConfig::max_message_size`].
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    /// See [`FrameConfig::reassembly_timeout`].
+    pub fn reassembly_timeout(mut self, timeout: core::time::Duration) -> Self {
+        self.config.reassembly_timeout = timeout;
+        self
+    }
+
+    /// See [`FrameConfig::sequence_numbers`].
+    pub fn sequence_numbers(mut self, sequence_numbers: bool) -> Self {
+        self.config.sequence_numbers = sequence_numbers;
+        self
+    }
+
+    /// See [`FrameConfig::wire_version`].
+    pub fn wire_version(mut self, wire_version: WireVersion) -> Self {
+        self.config.wire_version = wire_version;
+        self
+    }
+
+    /// See [`FrameConfig::max_buffer_bytes`].
+    pub fn max_buffer_bytes(mut self, max: usize) -> Self {
+        self.config.max_buffer_bytes = Some(max);
+        self
+    }
+
+    /// See [`FrameConfig::buffer_shrink_threshold`].
+    pub fn buffer_shrink_threshold(mut self, threshold: usize) -> Self {
+        self.config.buffer_shrink_threshold = threshold;
+        self
+    }
+
+    /// See [`FrameConfig::collect_stats`].
+    pub fn collect_stats(mut self, collect_stats: bool) -> Self {
+        self.config.collect_stats = collect_stats;
+        self
+    }
+
+    /// Validate and return the config; fails with
+    /// [`FrameError::InvalidConfig`] as described in
+    /// [`FrameConfig::validate`].
+    pub fn build(self) -> Result<FrameConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_builder_sets_fields_and_keeps_defaults() {
+        let config = FrameConfig::builder()
+            .max_payload_size(1024)
+            .read_timeout(core::time::Duration::from_secs(2))
+            .write_timeout(core::time::Duration::from_secs(3))
+            .checksum(true)
+            .max_fragment_size(256)
+            .build()
+            .unwrap();
+        assert_eq!(config.max_payload_size, 1024);
+        assert_eq!(
+            config.read_timeout,
+            Some(core::time::Duration::from_secs(2))
+        );
+        assert_eq!(
+            config.write_timeout,
+            Some(core::time::Duration::from_secs(3))
+        );
+        assert!(config.checksum);
+        assert_eq!(config.max_fragment_size, Some(256));
+        assert_eq!(config.max_message_size, DEFAULT_MAX_PAYLOAD);
+        assert_eq!(config.copy_out_threshold, DEFAULT_COPY_OUT_THRESHOLD);
+
+        assert!(FrameConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_settings() {
+        let zero = core::time::Duration::ZERO;
+        let invalid = [
+            FrameConfig::builder().max_payload_size(0),
+            FrameConfig::builder().max_payload_size(HEADER_SIZE),
+            FrameConfig::builder().read_timeout(zero),
+            FrameConfig::builder().write_timeout(zero),
+            FrameConfig::builder().reassembly_timeout(zero),
+            FrameConfig::builder().max_message_size(0),
+            FrameConfig::builder().max_buffer_bytes(HEADER_SIZE),
+            FrameConfig::builder().max_fragment_size(0),
+            FrameConfig::builder()
+                .max_message_size(1024)
+                .max_fragment_size(2048),
+        ];
+        for builder in invalid {
+            let err = builder.clone().build().unwrap_err();
+            assert!(matches!(err, FrameError::InvalidConfig(_)), "{builder:?}");
+        }
+        assert!(FrameConfig::builder()
+            .max_payload_size(HEADER_SIZE + 1)
+            .build()
+            .is_ok());
+    }
+
     #[test]
     fn test_encode_decode_roundtrip() {
         let mut buf = BytesMut::new();
@@ -211,5 +1337,512 @@ mod tests {
     fn test_frame_wire_size() {
         let frame = Frame::new(1, Bytes::from_static(b"test"));
         assert_eq!(frame.wire_size(), HEADER_SIZE + 4);
+        assert_eq!(
+            frame.with_priority(Priority(2)).wire_size(),
+            VERSIONED_HEADER_SIZE + 1 + 4
+        );
+    }
+
+    #[test]
+    fn test_priority_roundtrip() {
+        let mut buf = BytesMut::new();
+        encode_frame_with_priority(3, b"urgent", Priority(7), &mut buf).unwrap();
+        assert_eq!(buf[..4], [0x49, 0x56, 2, FLAG_PRIORITY]);
+        assert_eq!(buf.len(), VERSIONED_HEADER_SIZE + 1 + 6);
+        encode_frame_with_priority(3, b"normal", Priority::NORMAL, &mut buf).unwrap();
+
+        let urgent = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!((urgent.channel, urgent.priority), (3, Priority(7)));
+        assert_eq!(urgent.payload.as_ref(), b"urgent");
+
+        let normal = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!(normal.priority, Priority::NORMAL);
+        assert_eq!(normal.payload.as_ref(), b"normal");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_checksum_roundtrip() {
+        let mut buf = BytesMut::new();
+        encode_frame_with_checksum(4, b"checked", Priority(2), &mut buf).unwrap();
+        assert_eq!(buf[..2], VERSIONED_MAGIC);
+        assert_eq!(buf[3], FLAG_PRIORITY | FLAG_CHECKSUM);
+        assert_eq!(buf.len(), VERSIONED_HEADER_SIZE + 1 + 4 + 7);
+
+        let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!((frame.channel, frame.priority), (4, Priority(2)));
+        assert_eq!(frame.payload.as_ref(), b"checked");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_checksum_rejects_flipped_payload_byte() {
+        let mut buf = BytesMut::new();
+        encode_frame_with_checksum(1, b"payload", Priority::NORMAL, &mut buf).unwrap();
+        encode_frame(1, b"next", &mut buf).unwrap();
+        buf[VERSIONED_HEADER_SIZE + 4 + 3] ^= 0x01;
+
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        let FrameError::ChecksumMismatch { expected, actual } = err else {
+            panic!("expected checksum mismatch, got {err}");
+        };
+        assert_eq!(expected, crc32::checksum(b"payload"));
+        assert_ne!(actual, expected);
+
+        // The corrupted frame is consumed; the stream stays in sync.
+        let next = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.payload.as_ref(), b"next");
+    }
+
+    #[test]
+    fn test_checksum_rejects_flipped_checksum_byte() {
+        let mut buf = BytesMut::new();
+        encode_frame_with_checksum(1, b"payload", Priority::NORMAL, &mut buf).unwrap();
+        buf[VERSIONED_HEADER_SIZE] ^= 0x80;
+
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        let FrameError::ChecksumMismatch { expected, actual } = err else {
+            panic!("expected checksum mismatch, got {err}");
+        };
+        assert_eq!(actual, crc32::checksum(b"payload"));
+        assert_eq!(expected, actual ^ 0x80);
+        assert!(buf.is_empty());
+    }
+
+    /// A versioned frame carrying `data` with a checksum field and, if
+    /// compressed, a claim that it decodes to `decoded_len`.
+    fn versioned_frame(flags: u8, data: &[u8], decoded_len: u32) -> BytesMut {
+        let flags = flags | FLAG_CHECKSUM;
+        let mut buf = BytesMut::new();
+        buf.put_slice(&VERSIONED_MAGIC);
+        buf.put_u8(2);
+        buf.put_u8(flags);
+        buf.put_u32_le(data.len() as u32);
+        buf.put_u16_le(2);
+        buf.put_u32_le(crc32::checksum(data));
+        if flags & FLAG_COMPRESSED != 0 {
+            buf.put_u32_le(decoded_len);
+        }
+        buf.put_slice(data);
+        buf
+    }
+
+    #[test]
+    fn test_undecodable_compressed_frame_is_consumed() {
+        let mut buf = versioned_frame(FLAG_COMPRESSED, b"not zstd at all", 64);
+        encode_frame(1, b"next", &mut buf).unwrap();
+
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        assert!(matches!(err, FrameError::Compression(_)), "{err}");
+        let next = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.payload.as_ref(), b"next");
+    }
+
+    #[test]
+    fn test_unknown_frame_flags_are_rejected() {
+        let mut buf = versioned_frame(0x80, b"data", 4);
+        let len = buf.len();
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        assert!(matches!(err, FrameError::UnsupportedFlags(0x80)), "{err}");
+        // The unknown bit may announce a field, so nothing can be skipped.
+        assert_eq!(buf.len(), len);
+    }
+
+    #[test]
+    fn test_unknown_header_versions_are_rejected() {
+        for version in [1, 3, 0xFF] {
+            let mut buf = versioned_frame(0, b"data", 4);
+            buf[2] = version;
+            let len = buf.len();
+            let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+            assert!(
+                matches!(err, FrameError::UnsupportedVersion(v) if v == version),
+                "{err}"
+            );
+            assert_eq!(buf.len(), len);
+        }
+    }
+
+    #[test]
+    fn test_extension_fields_follow_the_flag_order() {
+        let fields = Extensions {
+            priority: Priority(7),
+            crc: Some(0x0403_0201),
+            decoded_len: None,
+            fragment: Some(Fragment {
+                index: 5,
+                last: true,
+            }),
+            sequence: Some(9),
+        };
+        let mut header = Vec::new();
+        fields.put_header(&mut header, 3, 0x0102, false);
+        let flags =
+            FLAG_PRIORITY | FLAG_CHECKSUM | FLAG_FRAGMENT | FLAG_LAST_FRAGMENT | FLAG_SEQUENCE;
+        assert_eq!(
+            header,
+            [
+                0x49, 0x56, 2, flags, 3, 0, 0, 0, 0x02, 0x01, // fixed part
+                7, 1, 2, 3, 4, 5, 0, 0, 0, 9, 0, 0, 0, // extension fields
+            ]
+        );
+        assert_eq!(header.len(), fields.header_size(false));
+
+        let prefix = header_prefix(&header).unwrap().unwrap();
+        assert_eq!(prefix.size, header.len());
+        let parsed = extensions(&header, &prefix);
+        assert_eq!(parsed.priority, Priority(7));
+        assert_eq!(parsed.crc, fields.crc);
+        assert_eq!(parsed.fragment, fields.fragment);
+        assert_eq!(parsed.sequence, Some(9));
+        assert!(MAX_HEADER_SIZE >= header.len() + 4);
+    }
+
+    #[test]
+    fn test_decompressed_size_is_checked_before_decompressing() {
+        let mut buf = versioned_frame(FLAG_COMPRESSED, b"tiny", 1 << 30);
+        let err = decode_frame(&mut buf, 1024).unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::PayloadTooLarge { size, max: 1024 } if size == 1 << 30
+        ));
+        assert!(buf.is_empty());
+    }
+
+    #[cfg(feature = "compression")]
+    fn zstd_config(checksum: bool) -> FrameConfig {
+        FrameConfig {
+            checksum,
+            compression: Compression::Zstd { level: 0 },
+            compression_threshold: 16,
+            ..FrameConfig::default()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compressed_roundtrip() {
+        let payload = b"abcdefgh".repeat(512);
+        for checksum in [false, true] {
+            let mut buf = BytesMut::new();
+            encode_frame_configured(
+                &zstd_config(checksum),
+                5,
+                &payload,
+                Priority(4),
+                None,
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf[..2], VERSIONED_MAGIC);
+            assert_eq!(buf[3] & FLAG_CHECKSUM != 0, checksum);
+            assert!(buf.len() < payload.len() / 4);
+
+            let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+                .unwrap()
+                .unwrap();
+            assert_eq!((frame.channel, frame.priority), (5, Priority(4)));
+            assert_eq!(frame.payload.as_ref(), payload);
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_small_or_incompressible_payloads_are_sent_plain() {
+        let mut buf = BytesMut::new();
+        encode_frame_configured(
+            &zstd_config(false),
+            1,
+            b"short",
+            Priority::NORMAL,
+            None,
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(buf[..2], MAGIC);
+
+        let noise: Vec<u8> = (0..256u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        buf.clear();
+        encode_frame_configured(
+            &zstd_config(true),
+            1,
+            &noise,
+            Priority::NORMAL,
+            None,
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(buf[..2], VERSIONED_MAGIC);
+        assert_eq!(buf[3], FLAG_CHECKSUM);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_decompressed_size_limit_applies() {
+        let payload = vec![0u8; 64 * 1024];
+        let mut buf = BytesMut::new();
+        encode_frame_configured(
+            &zstd_config(false),
+            1,
+            &payload,
+            Priority::NORMAL,
+            None,
+            &mut buf,
+        )
+        .unwrap();
+        assert!(buf.len() < 1024);
+
+        let err = decode_frame(&mut buf, 1024).unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTooLarge { max: 1024, .. }));
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_payload_inflating_past_its_header_length_is_rejected() {
+        let compressed = zstd::bulk::compress(&[7u8; 4096], 0).unwrap();
+        let mut buf = versioned_frame(FLAG_COMPRESSED, &compressed, 100);
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        assert!(matches!(err, FrameError::Compression(_)), "{err}");
+    }
+
+    #[test]
+    fn test_stateless_decode_rejects_fragments() {
+        let config = FrameConfig {
+            max_fragment_size: Some(2),
+            ..FrameConfig::default()
+        };
+        let mut buf = BytesMut::new();
+        encode_message(&config, 1, b"abc", Priority::NORMAL, None, &mut buf).unwrap();
+        let fragment_header = VERSIONED_HEADER_SIZE + 4;
+        assert_eq!(buf.len(), 2 * fragment_header + 3);
+        assert_eq!(buf[3], FLAG_FRAGMENT);
+
+        let err = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD).unwrap_err();
+        assert!(matches!(err, FrameError::Fragmentation(_)), "{err}");
+        assert_eq!(buf.len(), fragment_header + 1);
+        assert_eq!(buf[3], FLAG_FRAGMENT | FLAG_LAST_FRAGMENT);
+    }
+
+    #[test]
+    fn test_header_matches_full_encoding() {
+        let payload = b"header only";
+        for (checksum, priority) in [
+            (false, Priority::NORMAL),
+            (false, Priority(5)),
+            (true, Priority::NORMAL),
+            (true, Priority(5)),
+        ] {
+            let config = FrameConfig {
+                checksum,
+                ..FrameConfig::default()
+            };
+            let mut full = BytesMut::new();
+            encode_frame_configured(&config, 3, payload, priority, None, &mut full).unwrap();
+
+            let header = encode_header(&config, 3, &[payload], priority, None)
+                .unwrap()
+                .unwrap();
+            assert_eq!([header.as_bytes(), payload].concat(), full.as_ref());
+        }
+
+        let fragmenting = FrameConfig {
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        assert!(
+            encode_header(&fragmenting, 3, &[payload], Priority::NORMAL, None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            encode_header(&fragmenting, 3, &[b"tiny"], Priority::NORMAL, None)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_sequence_roundtrip() {
+        for checksum in [false, true] {
+            let config = FrameConfig {
+                checksum,
+                ..FrameConfig::default()
+            };
+            // Priority byte, the checksum if any, then the sequence number.
+            let header_size = VERSIONED_HEADER_SIZE + 1 + 4 * (1 + usize::from(checksum));
+            let mut buf = BytesMut::new();
+            encode_frame_configured(
+                &config,
+                4,
+                b"counted",
+                Priority(2),
+                Some(u32::MAX),
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf.len(), header_size + 7);
+            assert_eq!(buf[3] & FLAG_SEQUENCE, FLAG_SEQUENCE);
+
+            let header = encode_header(&config, 4, &[b"counted"], Priority(2), Some(u32::MAX))
+                .unwrap()
+                .unwrap();
+            assert_eq!(header.as_bytes(), &buf[..header_size]);
+
+            let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.sequence, Some(u32::MAX));
+            assert_eq!((frame.channel, frame.priority), (4, Priority(2)));
+            assert_eq!(frame.payload.as_ref(), b"counted");
+            assert_eq!(frame.wire_size(), VERSIONED_HEADER_SIZE + 1 + 4 + 7);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_sequence_roundtrip() {
+        let payload = vec![b'z'; 4096];
+        let mut buf = BytesMut::new();
+        encode_frame_configured(
+            &zstd_config(true),
+            5,
+            &payload,
+            Priority::NORMAL,
+            Some(9),
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(buf[3], FLAG_COMPRESSED | FLAG_CHECKSUM | FLAG_SEQUENCE);
+
+        let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.sequence, Some(9));
+        assert_eq!(frame.payload.as_ref(), payload.as_slice());
+    }
+
+    #[test]
+    fn test_v2_flags_every_frame() {
+        for (checksum, priority) in [
+            (false, Priority::NORMAL),
+            (false, Priority(5)),
+            (true, Priority::NORMAL),
+        ] {
+            let config = FrameConfig {
+                checksum,
+                wire_version: WireVersion::V2,
+                ..FrameConfig::default()
+            };
+            let mut buf = BytesMut::new();
+            encode_frame_configured(&config, 6, b"v2", priority, None, &mut buf).unwrap();
+            let mut expected_flags = if checksum { FLAG_CHECKSUM } else { 0 };
+            if priority != Priority::NORMAL {
+                expected_flags |= FLAG_PRIORITY;
+            }
+            let header_size = versioned_header_size(expected_flags);
+            assert_eq!(buf[..4], [0x49, 0x56, 2, expected_flags]);
+            assert_eq!(buf.len(), header_size + 2);
+
+            let header = encode_header(&config, 6, &[b"v2"], priority, None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(header.as_bytes(), &buf[..header_size]);
+
+            let frame = decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD)
+                .unwrap()
+                .unwrap();
+            assert_eq!((frame.channel, frame.priority), (6, priority));
+            assert_eq!(frame.payload.as_ref(), b"v2");
+        }
+    }
+
+    #[test]
+    fn test_wire_version_at_most() {
+        assert_eq!(WireVersion::at_most(0), WireVersion::V1);
+        assert_eq!(WireVersion::at_most(1), WireVersion::V1);
+        assert_eq!(WireVersion::at_most(2), WireVersion::V2);
+        assert_eq!(WireVersion::at_most(200), WireVersion::LATEST);
+        assert_eq!(FrameConfig::default().wire_version.as_u8(), 1);
+    }
+
+    #[test]
+    fn test_priority_zero_matches_basic_encoding() {
+        let mut basic = BytesMut::new();
+        encode_frame(1, b"same", &mut basic).unwrap();
+        let mut prioritized = BytesMut::new();
+        encode_frame_with_priority(1, b"same", Priority::NORMAL, &mut prioritized).unwrap();
+        assert_eq!(basic, prioritized);
+    }
+
+    #[test]
+    fn test_slice_roundtrip() {
+        let mut sequenced = Frame::new(5, &b"sequenced"[..]);
+        sequenced.sequence = Some(7);
+        let frames = [
+            Frame::new(1, &b"plain"[..]),
+            Frame::new(2, &b"urgent"[..]).with_priority(Priority(3)),
+            sequenced,
+            Frame::new(3, Bytes::new()),
+        ];
+        for frame in frames {
+            let bytes = frame.encode_to_vec();
+            assert_eq!(bytes.len(), frame.wire_size());
+
+            let (decoded, consumed) = Frame::decode_from_slice(&bytes).unwrap();
+            assert_eq!(consumed, bytes.len());
+            assert_eq!(decoded.channel, frame.channel);
+            assert_eq!(decoded.payload, frame.payload);
+            assert_eq!(decoded.priority, frame.priority);
+            assert_eq!(decoded.sequence, frame.sequence);
+        }
+
+        let mut expected = BytesMut::new();
+        encode_frame(1, b"plain", &mut expected).unwrap();
+        let mut appended = b"prefix".to_vec();
+        Frame::new(1, &b"plain"[..]).encode_into(&mut appended);
+        assert_eq!(&appended[6..], &expected[..]);
+    }
+
+    #[test]
+    fn test_decode_from_slice_with_trailing_bytes() {
+        let mut bytes = Frame::new(4, &b"one"[..]).encode_to_vec();
+        let frame_len = bytes.len();
+        bytes.extend_from_slice(b"extra");
+
+        let (frame, consumed) = Frame::decode_from_slice(&bytes).unwrap();
+        assert_eq!(consumed, frame_len);
+        assert_eq!(frame.payload.as_ref(), b"one");
+
+        let err = Frame::decode_from_slice_exact(&bytes).unwrap_err();
+        assert!(
+            matches!(err, FrameError::TrailingBytes { extra: 5 }),
+            "{err}"
+        );
+        assert!(Frame::decode_from_slice_exact(&bytes[..frame_len]).is_ok());
+    }
+
+    #[test]
+    fn test_decode_from_slice_truncated() {
+        let bytes = Frame::new(4, &b"cut short"[..]).encode_to_vec();
+        let err = Frame::decode_from_slice(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(
+            matches!(err, FrameError::Truncated { buffered } if buffered == bytes.len() - 1),
+            "{err}"
+        );
+        let err = Frame::decode_from_slice(&[0xFF; HEADER_SIZE]).unwrap_err();
+        assert!(matches!(err, FrameError::InvalidMagic), "{err}");
     }
 }
@@ -0,0 +1,64 @@
+//! Optional payload compression for the versioned frame header.
+
+#[cfg(feature = "compression")]
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::error::{FrameError, Result};
+
+/// Default [`FrameConfig::compression_threshold`](crate::FrameConfig::compression_threshold): 1 KiB.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// How a writer compresses payloads.
+///
+/// Codecs other than `None` need the `compression` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Send payloads as they are.
+    #[default]
+    None,
+    /// zstd at `level` (1-22; 0 picks zstd's default, currently 3).
+    #[cfg(feature = "compression")]
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// Compress `payload`, or `None` when this setting sends it as is.
+    #[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+    pub(crate) fn compress(self, payload: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self {
+            Compression::None => Ok(None),
+            #[cfg(feature = "compression")]
+            Compression::Zstd { level } => zstd::bulk::compress(payload, level)
+                .map(Some)
+                .map_err(|err| FrameError::Compression(err.to_string())),
+        }
+    }
+}
+
+/// Decompress a payload sent with the compressed flag, producing exactly
+/// `decoded_len` bytes.
+///
+/// `decoded_len` must already be checked against the payload limit; it
+/// caps the output buffer, so a payload that inflates further fails.
+#[cfg(feature = "compression")]
+pub(crate) fn decompress(data: &[u8], decoded_len: usize) -> Result<Vec<u8>> {
+    let payload = zstd::bulk::decompress(data, decoded_len)
+        .map_err(|err| FrameError::Compression(err.to_string()))?;
+    if payload.len() != decoded_len {
+        return Err(FrameError::Compression(format!(
+            "decompressed to {} bytes, header says {decoded_len}",
+            payload.len()
+        )));
+    }
+    Ok(payload)
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress(_data: &[u8], _decoded_len: usize) -> Result<Vec<u8>> {
+    Err(FrameError::Compression(
+        "compressed frame received but the `compression` feature is disabled".to_string(),
+    ))
+}
@@ -0,0 +1,58 @@
+//! CRC-32 (IEEE 802.3, as used by zlib and Ethernet) for frame checksums.
+
+const POLY: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = build_table();
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 of `data`.
+pub(crate) fn checksum(data: &[u8]) -> u32 {
+    checksum_parts(&[data])
+}
+
+/// CRC-32 of `parts` joined end to end.
+pub(crate) fn checksum_parts(parts: &[&[u8]]) -> u32 {
+    !parts.iter().fold(!0u32, |crc, part| {
+        part.iter().fold(crc, |crc, &byte| {
+            TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reference_values() {
+        assert_eq!(checksum(b""), 0);
+        assert_eq!(checksum(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            checksum(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+        assert_eq!(
+            checksum_parts(&[b"1234", b"", b"56789"]),
+            checksum(b"123456789")
+        );
+    }
+}
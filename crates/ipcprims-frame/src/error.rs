@@ -1,21 +1,284 @@
+use alloc::string::String;
+
+/// Stable numeric identifier for a [`FrameError`] variant.
+///
+/// These values are part of the public contract for bindings, logs, and any
+/// other consumer that cannot match on Rust variants: an assigned number is
+/// never reused or changed, and new variants take the next free value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+#[non_exhaustive]
+pub enum FrameErrorCode {
+    /// [`FrameError::InvalidMagic`].
+    InvalidMagic = 1,
+    /// [`FrameError::PayloadTooLarge`].
+    PayloadTooLarge = 2,
+    /// [`FrameError::ConnectionClosed`].
+    ConnectionClosed = 3,
+    /// `FrameError::Io` (`std` feature).
+    Io = 4,
+    /// [`FrameError::Truncated`].
+    Truncated = 5,
+    /// [`FrameError::Timeout`].
+    Timeout = 6,
+    /// [`FrameError::Poisoned`].
+    Poisoned = 7,
+    /// [`FrameError::ChecksumMismatch`].
+    ChecksumMismatch = 8,
+    /// [`FrameError::Compression`].
+    Compression = 9,
+    /// [`FrameError::Fragmentation`].
+    Fragmentation = 10,
+    /// [`FrameError::InvalidConfig`].
+    InvalidConfig = 11,
+    /// [`FrameError::SequenceGap`].
+    SequenceGap = 12,
+    /// [`FrameError::UnsupportedFlags`].
+    UnsupportedFlags = 13,
+    /// [`FrameError::BufferLimitExceeded`].
+    BufferLimitExceeded = 14,
+    /// [`FrameError::TrailingBytes`].
+    TrailingBytes = 15,
+    /// `FrameError::Json` (`json` feature).
+    Json = 16,
+    /// [`FrameError::PayloadTransform`].
+    PayloadTransform = 17,
+    /// [`FrameError::UnsupportedVersion`].
+    UnsupportedVersion = 18,
+}
+
+impl FrameErrorCode {
+    /// The numeric value of this code.
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+}
+
 /// Errors that can occur during frame encoding/decoding.
+///
+/// Messages end with the variant's [`FrameErrorCode`], e.g. `(code 1)`.
 #[derive(Debug, thiserror::Error)]
 pub enum FrameError {
     /// The frame header contains an invalid magic number.
-    #[error("invalid frame magic (expected 0x4950 \"IP\")")]
+    #[error("invalid frame magic (expected 0x4950 \"IP\") (code 1)")]
     InvalidMagic,
 
     /// The payload exceeds the configured maximum size.
-    #[error("payload too large ({size} bytes, max {max})")]
+    #[error("payload too large ({size} bytes, max {max}) (code 2)")]
     PayloadTooLarge { size: usize, max: usize },
 
-    /// An I/O error occurred while reading or writing frames.
-    #[error("frame I/O error: {0}")]
+    /// An I/O error occurred while reading or writing frames (`std` only).
+    #[cfg(feature = "std")]
+    #[error("frame I/O error: {0} (code 4)")]
     Io(#[from] std::io::Error),
 
-    /// The connection was closed before a complete frame was received.
-    #[error("connection closed (incomplete frame)")]
+    /// The connection was closed cleanly between frames.
+    #[error("connection closed (code 3)")]
     ConnectionClosed,
+
+    /// The connection was closed partway through a frame.
+    #[error("connection closed mid-frame ({buffered} bytes of an incomplete frame) (code 5)")]
+    Truncated { buffered: usize },
+
+    /// A write made no progress within the configured write timeout.
+    ///
+    /// Part of the frame may already have been written, so the stream should
+    /// not be reused for framing.
+    #[error("write timed out after {0:?} (code 6)")]
+    Timeout(core::time::Duration),
+
+    /// An earlier write failed partway through, so the remote has a partial
+    /// frame and the writer refuses to send more.
+    #[error("writer poisoned by an interrupted write (code 7)")]
+    Poisoned,
+
+    /// A checksummed frame's payload does not match its CRC-32.
+    ///
+    /// The frame has been consumed, so the stream is still aligned on the
+    /// next frame.
+    #[error("frame checksum mismatch (expected {expected:#010x}, got {actual:#010x}) (code 8)")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    /// A payload could not be compressed, or a compressed one could not be
+    /// decoded (corrupt data, unknown flags, or no `compression` feature).
+    ///
+    /// A received frame has been consumed, as with `ChecksumMismatch`.
+    #[error("payload compression error: {0} (code 9)")]
+    Compression(String),
+
+    /// Fragments of a message arrived out of order, interleaved with other
+    /// frames, or too slowly, so the message was dropped.
+    ///
+    /// The stream is still aligned; the next read continues after it.
+    #[error("fragment reassembly failed: {0} (code 10)")]
+    Fragmentation(String),
+
+    /// A [`FrameConfig`](crate::FrameConfig) setting is out of range.
+    #[error("invalid frame config: {0} (code 11)")]
+    InvalidConfig(String),
+
+    /// A message's sequence number is not the one after the last seen on its
+    /// channel: messages were lost (`got` is ahead) or reordered.
+    ///
+    /// Not fatal: the message itself is returned by the next read.
+    #[error("sequence gap on channel {channel}: expected {expected}, got {got} (code 12)")]
+    SequenceGap {
+        channel: u16,
+        expected: u32,
+        got: u32,
+    },
+
+    /// A versioned header sets flag bits this reader does not know, most
+    /// likely from a newer writer, rather than being misread.
+    ///
+    /// The unknown bits may announce extension fields, so the frame's size is
+    /// unknown and nothing is consumed; use
+    /// [`FrameReader::resync`](crate::FrameReader::resync) to skip it.
+    #[error("unsupported frame flags {0:#04x} (code 13)")]
+    UnsupportedFlags(u8),
+
+    /// The frame being read needs more than
+    /// [`FrameConfig::max_buffer_bytes`](crate::FrameConfig::max_buffer_bytes)
+    /// of buffer. Nothing is consumed, so the stream stays on that frame;
+    /// use [`FrameReader::resync`](crate::FrameReader::resync) to skip it.
+    #[error("frame buffer limit exceeded ({buffered} bytes buffered, max {max}) (code 14)")]
+    BufferLimitExceeded { buffered: usize, max: usize },
+
+    /// [`Frame::decode_from_slice_exact`](crate::Frame::decode_from_slice_exact)
+    /// found bytes after the frame.
+    #[error("{extra} trailing bytes after the frame (code 15)")]
+    TrailingBytes { extra: usize },
+
+    /// A value could not be serialized to JSON, or a payload could not be
+    /// deserialized from it.
+    #[cfg(feature = "json")]
+    #[error("frame json error: {0} (code 16)")]
+    Json(#[from] serde_json::Error),
+
+    /// A `PayloadCodec` (`std` feature) could not seal an outgoing
+    /// payload or open a received one (wrong key, tampering, or a remote
+    /// without the codec).
+    ///
+    /// A received frame has been consumed, so the stream is still aligned.
+    #[error("payload transform failed: {0} (code 17)")]
+    PayloadTransform(String),
+
+    /// A versioned header carries a version byte this reader does not know,
+    /// most likely from a newer writer.
+    ///
+    /// Its layout, and so the frame's size, is unknown, so nothing is
+    /// consumed; use [`FrameReader::resync`](crate::FrameReader::resync) to
+    /// skip it.
+    #[error("unsupported frame header version {0} (code 18)")]
+    UnsupportedVersion(u8),
+}
+
+impl FrameError {
+    /// Stable numeric code for this error; see [`FrameErrorCode`].
+    pub fn code(&self) -> FrameErrorCode {
+        match self {
+            FrameError::InvalidMagic => FrameErrorCode::InvalidMagic,
+            FrameError::PayloadTooLarge { .. } => FrameErrorCode::PayloadTooLarge,
+            #[cfg(feature = "std")]
+            FrameError::Io(_) => FrameErrorCode::Io,
+            FrameError::ConnectionClosed => FrameErrorCode::ConnectionClosed,
+            FrameError::Truncated { .. } => FrameErrorCode::Truncated,
+            FrameError::Timeout(_) => FrameErrorCode::Timeout,
+            FrameError::Poisoned => FrameErrorCode::Poisoned,
+            FrameError::ChecksumMismatch { .. } => FrameErrorCode::ChecksumMismatch,
+            FrameError::Compression(_) => FrameErrorCode::Compression,
+            FrameError::Fragmentation(_) => FrameErrorCode::Fragmentation,
+            FrameError::InvalidConfig(_) => FrameErrorCode::InvalidConfig,
+            FrameError::SequenceGap { .. } => FrameErrorCode::SequenceGap,
+            FrameError::UnsupportedFlags(_) => FrameErrorCode::UnsupportedFlags,
+            FrameError::BufferLimitExceeded { .. } => FrameErrorCode::BufferLimitExceeded,
+            FrameError::TrailingBytes { .. } => FrameErrorCode::TrailingBytes,
+            #[cfg(feature = "json")]
+            FrameError::Json(_) => FrameErrorCode::Json,
+            FrameError::PayloadTransform(_) => FrameErrorCode::PayloadTransform,
+            FrameError::UnsupportedVersion(_) => FrameErrorCode::UnsupportedVersion,
+        }
+    }
+}
+
+pub type Result<T> = core::result::Result<T, FrameError>;
+
+/// I/O error kinds that mean the remote end went away.
+///
+/// Writers see these instead of EOF, and Windows named pipes report them on
+/// read as well; both sides map them to [`FrameError::ConnectionClosed`].
+#[cfg(feature = "std")]
+pub(crate) fn is_disconnect(kind: std::io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+    )
 }
 
-pub type Result<T> = std::result::Result<T, FrameError>;
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn every_variant() -> Vec<FrameError> {
+        vec![
+            FrameError::InvalidMagic,
+            FrameError::PayloadTooLarge { size: 2, max: 1 },
+            FrameError::ConnectionClosed,
+            #[cfg(feature = "std")]
+            FrameError::Io(std::io::Error::other("boom")),
+            FrameError::Truncated { buffered: 3 },
+            FrameError::Timeout(Duration::from_secs(1)),
+            FrameError::Poisoned,
+            FrameError::ChecksumMismatch {
+                expected: 1,
+                actual: 2,
+            },
+            FrameError::Compression("bad data".to_string()),
+            FrameError::Fragmentation("gap".to_string()),
+            FrameError::InvalidConfig("zero".to_string()),
+            FrameError::SequenceGap {
+                channel: 1,
+                expected: 2,
+                got: 4,
+            },
+            FrameError::UnsupportedFlags(0x80),
+            FrameError::BufferLimitExceeded {
+                buffered: 8,
+                max: 4,
+            },
+            FrameError::TrailingBytes { extra: 2 },
+            #[cfg(feature = "json")]
+            FrameError::Json(serde_json::from_str::<u8>("x").unwrap_err()),
+            FrameError::PayloadTransform("bad tag".to_string()),
+            FrameError::UnsupportedVersion(3),
+        ]
+    }
+
+    #[test]
+    fn codes_are_frozen() {
+        let codes: Vec<u16> = every_variant()
+            .iter()
+            .map(|err| err.code().as_u16())
+            .collect();
+        let mut expected: Vec<u16> = (1..=15)
+            .filter(|&code| code != 4 || cfg!(feature = "std"))
+            .collect();
+        if cfg!(feature = "json") {
+            expected.push(16);
+        }
+        expected.extend([17, 18]);
+        assert_eq!(codes, expected);
+    }
+
+    #[test]
+    fn display_ends_with_code() {
+        for err in every_variant() {
+            let suffix = format!("(code {})", err.code().as_u16());
+            assert!(err.to_string().ends_with(&suffix), "{err}");
+        }
+    }
+}
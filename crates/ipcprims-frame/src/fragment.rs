@@ -0,0 +1,279 @@
+//! Reassembly of messages split across several wire frames.
+
+use std::time::Instant;
+
+use bytes::BytesMut;
+
+use crate::codec::{Fragment, Frame, FrameConfig, Priority};
+use crate::error::{FrameError, Result};
+
+/// Collects fragments into whole messages.
+///
+/// Fragments of one message must arrive back to back on one channel, in
+/// index order. Anything else drops the message with
+/// [`FrameError::Fragmentation`]; the rest of its fragments are then skipped
+/// without further errors.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Reassembler {
+    partial: Option<Partial>,
+    /// A whole frame that interrupted a message, returned after the error.
+    pending: Option<Frame>,
+    /// Bytes a payload codec adds to a message, allowed on top of
+    /// `max_message_size`.
+    overhead: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Partial {
+    channel: u16,
+    priority: Priority,
+    sequence: Option<u32>,
+    next_index: u32,
+    data: BytesMut,
+    started: Instant,
+    /// The message already failed; drop its remaining fragments.
+    discarding: bool,
+}
+
+impl Reassembler {
+    /// A frame held back by an earlier error, to be returned next.
+    pub(crate) fn take_pending(&mut self) -> Option<Frame> {
+        self.pending.take()
+    }
+
+    /// The frame [`take_pending`](Self::take_pending) would return.
+    pub(crate) fn pending(&self) -> Option<&Frame> {
+        self.pending.as_ref()
+    }
+
+    /// Accept messages up to `overhead` bytes over `max_message_size`.
+    pub(crate) fn allow_overhead(&mut self, overhead: usize) {
+        self.overhead = overhead;
+    }
+
+    /// True while fragments of a message (even a failed one) are expected.
+    pub(crate) fn in_progress(&self) -> bool {
+        self.partial.is_some()
+    }
+
+    /// Payload bytes of a message still waiting for fragments.
+    pub(crate) fn buffered(&self) -> Option<usize> {
+        self.partial
+            .as_ref()
+            .filter(|partial| !partial.discarding)
+            .map(|partial| partial.data.len())
+    }
+
+    /// Feed one decoded wire frame; returns a message once one is complete.
+    pub(crate) fn push(
+        &mut self,
+        frame: Frame,
+        fragment: Option<Fragment>,
+        config: &FrameConfig,
+    ) -> Result<Option<Frame>> {
+        let interrupted = match &self.partial {
+            Some(partial) => {
+                fragment.is_none_or(|f| f.index == 0) || partial.channel != frame.channel
+            }
+            None => false,
+        };
+        if interrupted {
+            let partial = self.partial.take().expect("checked above");
+            let result = match fragment {
+                Some(fragment) => self.start(frame, fragment, config),
+                None => Ok(Some(frame)),
+            };
+            if partial.discarding {
+                return result;
+            }
+            if let Ok(Some(frame)) = result {
+                self.pending = Some(frame);
+            }
+            return Err(FrameError::Fragmentation(format!(
+                "message on channel {} interrupted after {} fragments",
+                partial.channel, partial.next_index
+            )));
+        }
+
+        match fragment {
+            None => Ok(Some(frame)),
+            Some(fragment) if self.partial.is_none() => self.start(frame, fragment, config),
+            Some(fragment) => self.append(frame, fragment, config),
+        }
+    }
+
+    fn start(
+        &mut self,
+        frame: Frame,
+        fragment: Fragment,
+        config: &FrameConfig,
+    ) -> Result<Option<Frame>> {
+        let mut partial = Partial {
+            channel: frame.channel,
+            priority: frame.priority,
+            sequence: frame.sequence,
+            next_index: 0,
+            data: BytesMut::new(),
+            started: Instant::now(),
+            discarding: false,
+        };
+        if fragment.index != 0 {
+            partial.discarding = true;
+            partial.next_index = fragment.index.wrapping_add(1);
+            if !fragment.last {
+                self.partial = Some(partial);
+            }
+            return Err(FrameError::Fragmentation(format!(
+                "fragment {} on channel {} arrived without fragment 0",
+                fragment.index, frame.channel
+            )));
+        }
+        self.partial = Some(partial);
+        self.append(frame, fragment, config)
+    }
+
+    fn append(
+        &mut self,
+        frame: Frame,
+        fragment: Fragment,
+        config: &FrameConfig,
+    ) -> Result<Option<Frame>> {
+        let partial = self.partial.as_mut().expect("reassembly in progress");
+        let mut error = None;
+        if !partial.discarding {
+            let size = partial.data.len() + frame.payload.len();
+            error = if fragment.index != partial.next_index {
+                Some(FrameError::Fragmentation(format!(
+                    "expected fragment {} on channel {}, got {}",
+                    partial.next_index, partial.channel, fragment.index
+                )))
+            } else if fragment.index > 0 && partial.started.elapsed() > config.reassembly_timeout {
+                Some(FrameError::Fragmentation(format!(
+                    "message on channel {} not complete within {:?}",
+                    partial.channel, config.reassembly_timeout
+                )))
+            } else if size > config.max_message_size.saturating_add(self.overhead) {
+                Some(FrameError::PayloadTooLarge {
+                    size,
+                    max: config.max_message_size.saturating_add(self.overhead),
+                })
+            } else {
+                partial.data.extend_from_slice(&frame.payload);
+                None
+            };
+            if error.is_some() {
+                partial.discarding = true;
+                partial.data = BytesMut::new();
+            }
+        }
+        partial.next_index = fragment.index.wrapping_add(1);
+
+        if fragment.last {
+            let partial = self.partial.take().expect("reassembly in progress");
+            if error.is_none() && !partial.discarding {
+                return Ok(Some(Frame {
+                    channel: partial.channel,
+                    payload: partial.data.freeze(),
+                    priority: partial.priority,
+                    sequence: partial.sequence,
+                }));
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    fn piece(channel: u16, data: &'static [u8]) -> Frame {
+        Frame::new(channel, Bytes::from_static(data))
+    }
+
+    fn frag(index: u32, last: bool) -> Option<Fragment> {
+        Some(Fragment { index, last })
+    }
+
+    #[test]
+    fn joins_fragments_in_order() {
+        let config = FrameConfig::default();
+        let mut r = Reassembler::default();
+        assert!(r
+            .push(piece(2, b"ab"), frag(0, false), &config)
+            .unwrap()
+            .is_none());
+        assert!(r
+            .push(piece(2, b"cd"), frag(1, false), &config)
+            .unwrap()
+            .is_none());
+        assert_eq!(r.buffered(), Some(4));
+        let message = r
+            .push(piece(2, b"e"), frag(2, true), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (message.channel, message.payload.as_ref()),
+            (2, b"abcde".as_ref())
+        );
+        assert_eq!(r.buffered(), None);
+    }
+
+    #[test]
+    fn missing_fragment_fails_once_and_skips_the_rest() {
+        let config = FrameConfig::default();
+        let mut r = Reassembler::default();
+        r.push(piece(2, b"ab"), frag(0, false), &config).unwrap();
+        let err = r
+            .push(piece(2, b"ef"), frag(2, false), &config)
+            .unwrap_err();
+        assert!(matches!(err, FrameError::Fragmentation(_)), "{err}");
+        assert!(r
+            .push(piece(2, b"gh"), frag(3, true), &config)
+            .unwrap()
+            .is_none());
+
+        let whole = r.push(piece(2, b"next"), None, &config).unwrap().unwrap();
+        assert_eq!(whole.payload.as_ref(), b"next");
+    }
+
+    #[test]
+    fn interleaved_frame_is_returned_after_the_error() {
+        let config = FrameConfig::default();
+        let mut r = Reassembler::default();
+        r.push(piece(2, b"ab"), frag(0, false), &config).unwrap();
+        let err = r.push(piece(1, b"cmd"), None, &config).unwrap_err();
+        assert!(matches!(err, FrameError::Fragmentation(_)), "{err}");
+        assert_eq!(r.take_pending().unwrap().payload.as_ref(), b"cmd");
+        assert_eq!(r.buffered(), None);
+    }
+
+    #[test]
+    fn enforces_message_size_and_timeout() {
+        let config = FrameConfig {
+            max_message_size: 3,
+            ..FrameConfig::default()
+        };
+        let mut r = Reassembler::default();
+        r.push(piece(2, b"ab"), frag(0, false), &config).unwrap();
+        let err = r.push(piece(2, b"cd"), frag(1, true), &config).unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::PayloadTooLarge { size: 4, max: 3 }
+        ));
+        assert_eq!(r.buffered(), None);
+
+        let config = FrameConfig {
+            reassembly_timeout: Duration::ZERO,
+            ..FrameConfig::default()
+        };
+        r.push(piece(2, b"ab"), frag(0, false), &config).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        let err = r.push(piece(2, b"cd"), frag(1, true), &config).unwrap_err();
+        assert!(matches!(err, FrameError::Fragmentation(_)), "{err}");
+    }
+}
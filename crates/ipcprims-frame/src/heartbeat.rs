@@ -0,0 +1,157 @@
+//! Frame-layer heartbeats for connections that use [`FrameReader`] and
+//! [`FrameWriter`] directly.
+//!
+//! A heartbeat is an empty frame on the reserved [`HEARTBEAT`] channel, sent
+//! with [`FrameWriter::send_heartbeat`]. Readers set to absorb them (see
+//! [`FrameReader::absorb_heartbeats`]) never return them to the caller, and
+//! they take no part in sequence numbers, stats or schema checks.
+//!
+//! [`FrameReader`]: crate::FrameReader
+//! [`FrameWriter`]: crate::FrameWriter
+//! [`FrameWriter::send_heartbeat`]: crate::FrameWriter::send_heartbeat
+//! [`FrameReader::absorb_heartbeats`]: crate::FrameReader::absorb_heartbeats
+//! [`HEARTBEAT`]: crate::channel::HEARTBEAT
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Called for every heartbeat a reader absorbs.
+pub type HeartbeatCallback = Box<dyn FnMut() + Send>;
+
+/// Tracks when a connection last showed signs of life.
+///
+/// Clones share the same timestamp, so one clone can be handed to a
+/// [`FrameReader`](crate::FrameReader) with
+/// [`set_heartbeat_monitor`](crate::FrameReader::set_heartbeat_monitor) while
+/// another is polled from a watchdog thread. The reader records every frame
+/// it reads, heartbeat or not.
+#[derive(Clone)]
+pub struct HeartbeatMonitor {
+    last: Arc<Mutex<Instant>>,
+}
+
+impl HeartbeatMonitor {
+    /// A monitor whose last activity is now.
+    pub fn new() -> Self {
+        Self {
+            last: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Note activity now.
+    pub fn record(&self) {
+        *self.lock() = Instant::now();
+    }
+
+    /// When activity was last recorded.
+    pub fn last_received(&self) -> Instant {
+        *self.lock()
+    }
+
+    /// Time since activity was last recorded.
+    pub fn elapsed(&self) -> Duration {
+        self.last_received().elapsed()
+    }
+
+    /// True if nothing has been recorded for longer than `max_silence`.
+    pub fn is_stale(&self, max_silence: Duration) -> bool {
+        self.elapsed() > max_silence
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.last
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for HeartbeatMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for HeartbeatMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeartbeatMonitor")
+            .field("elapsed", &self.elapsed())
+            .finish()
+    }
+}
+
+/// A reader's heartbeat handling: whether heartbeats are absorbed, and who
+/// hears about them.
+#[derive(Default)]
+pub(crate) struct HeartbeatHandling {
+    pub(crate) absorb: bool,
+    pub(crate) callback: Option<HeartbeatCallback>,
+    pub(crate) monitor: Option<HeartbeatMonitor>,
+}
+
+impl HeartbeatHandling {
+    /// Record a frame on `channel`; returns true if it is a heartbeat to
+    /// drop.
+    pub(crate) fn observe(&mut self, channel: u16) -> bool {
+        if let Some(monitor) = &self.monitor {
+            monitor.record();
+        }
+        if !self.absorb || channel != crate::channel::HEARTBEAT {
+            return false;
+        }
+        if let Some(callback) = &mut self.callback {
+            callback();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{FrameReader, FrameWriter, COMMAND, HEARTBEAT};
+
+    #[test]
+    fn absorbed_heartbeats_are_not_returned() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send_heartbeat().unwrap();
+        writer.send(COMMAND, b"one").unwrap();
+        writer.send_heartbeat().unwrap();
+        writer.send_heartbeat().unwrap();
+        writer.send(COMMAND, b"two").unwrap();
+        let bytes = writer.into_inner();
+
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let mut reader = FrameReader::new(Cursor::new(bytes.clone()));
+        reader.on_heartbeat(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"one");
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), COMMAND);
+        assert_eq!(buf, b"two");
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+
+        let mut plain = FrameReader::new(Cursor::new(bytes));
+        assert_eq!(plain.read_frame().unwrap().channel, HEARTBEAT);
+    }
+
+    #[test]
+    fn monitor_tracks_received_frames() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send_heartbeat().unwrap();
+        let monitor = HeartbeatMonitor::new();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(monitor.is_stale(Duration::from_millis(10)));
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        reader.set_heartbeat_monitor(monitor.clone());
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, crate::FrameError::ConnectionClosed), "{err}");
+        assert!(!monitor.is_stale(Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,110 @@
+//! JSON payload helpers (`json` feature).
+
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::codec::Frame;
+use crate::error::Result;
+use crate::reader::FrameReader;
+use crate::writer::FrameWriter;
+
+impl Frame {
+    /// Deserialize the payload as JSON.
+    ///
+    /// Fails with [`FrameError::Json`](crate::FrameError::Json) if the
+    /// payload is not valid JSON for `T`.
+    pub fn parse_json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.payload)?)
+    }
+}
+
+impl<T: Write> FrameWriter<T> {
+    /// Serialize `value` as JSON and [`send`](Self::send) it on `channel`.
+    ///
+    /// Serialization errors are returned as
+    /// [`FrameError::Json`](crate::FrameError::Json) before anything is
+    /// written; the payload size limit applies to the serialized bytes.
+    pub fn send_json<V: Serialize + ?Sized>(&mut self, channel: u16, value: &V) -> Result<()> {
+        let payload = serde_json::to_vec(value)?;
+        self.send(channel, &payload)
+    }
+}
+
+impl<T: Read> FrameReader<T> {
+    /// [`read_frame`](Self::read_frame) and deserialize its payload as JSON,
+    /// returning the channel and the value.
+    ///
+    /// The frame is read and size-checked against `max_payload_size` before
+    /// anything is parsed. A payload that is not valid JSON for `V` fails
+    /// with [`FrameError::Json`](crate::FrameError::Json); the frame is
+    /// consumed, so the next read continues after it.
+    pub fn read_json<V: DeserializeOwned>(&mut self) -> Result<(u16, V)> {
+        let frame = self.read_frame()?;
+        let value = frame.parse_json()?;
+        Ok((frame.channel, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::error::FrameError;
+    use crate::{FrameConfig, COMMAND};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Ping {
+        action: String,
+        seq: u32,
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let mut writer = FrameWriter::new(Vec::new());
+        let ping = Ping {
+            action: "ping".to_string(),
+            seq: 3,
+        };
+        writer.send_json(COMMAND, &ping).unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        let (channel, decoded): (u16, Ping) = reader.read_json().unwrap();
+        assert_eq!(channel, COMMAND);
+        assert_eq!(decoded, ping);
+    }
+
+    #[test]
+    fn invalid_json_is_consumed() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send(COMMAND, b"{not-json").unwrap();
+        writer.send_json(COMMAND, &[1, 2]).unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        let err = reader.read_json::<Ping>().unwrap_err();
+        assert!(matches!(err, FrameError::Json(_)), "{err}");
+        let (_, next): (u16, Vec<u8>) = reader.read_json().unwrap();
+        assert_eq!(next, [1, 2]);
+    }
+
+    #[test]
+    fn size_limit_applies_before_parsing() {
+        let config = FrameConfig {
+            max_payload_size: 16,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        let err = writer.send_json(COMMAND, &"x".repeat(32)).unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTooLarge { .. }), "{err}");
+
+        let mut unlimited = FrameWriter::new(Vec::new());
+        unlimited.send_json(COMMAND, &"x".repeat(32)).unwrap();
+        let mut reader = FrameReader::with_config(Cursor::new(unlimited.into_inner()), config);
+        let err = reader.read_json::<String>().unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTooLarge { .. }), "{err}");
+    }
+}
@@ -5,20 +5,97 @@
 //! - A 4-byte little-endian payload length
 //! - A 2-byte little-endian channel ID for multiplexing
 //!
+//! Frames that need more use the versioned header instead: magic "IV", a
+//! version byte and a flags byte ahead of the same length and channel, then
+//! an extension field per flag: a priority byte, a CRC-32 of the payload (see
+//! [`FrameConfig::checksum`]), the decoded length of a compressed payload
+//! (see [`FrameConfig::compression`], `compression` feature), the index of a
+//! fragment of a message larger than one frame (see
+//! [`FrameConfig::max_fragment_size`]) and a per-channel sequence number
+//! (see [`FrameConfig::sequence_numbers`]).
+//!
 //! No partial reads, no buffer management in user code.
+//!
+//! Without the default `std` feature the crate is `no_std`: the `alloc`
+//! feature keeps the codec ([`encode_frame`], [`decode_frame`], [`Frame`],
+//! [`FrameConfig`]), the channel constants and [`FrameError`], so the same
+//! wire format can be produced where there is an allocator but no operating
+//! system. Readers, writers and the rest need `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// Codec helpers shared with the readers and writers go unused without them.
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("ipcprims-frame needs the `alloc` feature (`std`, the default, implies it)");
+
+extern crate alloc;
 
 #[cfg(feature = "async")]
 pub mod async_codec;
+#[cfg(feature = "async")]
+pub mod async_io;
 pub mod channel;
 pub mod codec;
+pub mod compression;
+mod crc32;
 pub mod error;
+#[cfg(feature = "std")]
+mod fragment;
+#[cfg(feature = "std")]
+pub mod heartbeat;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod priority;
+#[cfg(feature = "std")]
 pub mod reader;
+#[cfg(feature = "std")]
+mod sequence;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "std")]
 pub mod writer;
 
 #[cfg(feature = "async")]
 pub use async_codec::IpcCodec;
-pub use channel::{COMMAND, CONTROL, DATA, ERROR, TELEMETRY, USER_CHANNEL_START};
-pub use codec::{decode_frame, encode_frame, Frame, FrameConfig, DEFAULT_MAX_PAYLOAD, HEADER_SIZE};
-pub use error::{FrameError, Result};
-pub use reader::FrameReader;
-pub use writer::FrameWriter;
+#[cfg(feature = "async")]
+pub use async_io::{AsyncFrameReader, AsyncFrameWriter};
+pub use channel::{
+    builtin_channel, ChannelInfo, ChannelMap, BUILTIN_CHANNELS, COMMAND, CONTROL, DATA, ERROR,
+    HEARTBEAT, TELEMETRY, USER_CHANNEL_START,
+};
+pub use codec::{
+    decode_frame, encode_frame, encode_frame_with_checksum, encode_frame_with_priority, Frame,
+    FrameConfig, FrameConfigBuilder, FrameHeader, Priority, WireVersion,
+    DEFAULT_BUFFER_SHRINK_THRESHOLD, DEFAULT_COPY_OUT_THRESHOLD, DEFAULT_MAX_PAYLOAD,
+    DEFAULT_REASSEMBLY_TIMEOUT, FLAG_CHECKSUM, FLAG_COMPRESSED, FLAG_FRAGMENT, FLAG_LAST_FRAGMENT,
+    FLAG_PRIORITY, FLAG_SEQUENCE, HEADER_SIZE, MAX_HEADER_SIZE, VERSIONED_HEADER_SIZE,
+    VERSIONED_MAGIC,
+};
+pub use compression::{Compression, DEFAULT_COMPRESSION_THRESHOLD};
+pub use error::{FrameError, FrameErrorCode, Result};
+#[cfg(feature = "std")]
+pub use heartbeat::{HeartbeatCallback, HeartbeatMonitor};
+#[cfg(feature = "std")]
+pub use observer::FrameObserver;
+#[cfg(feature = "std")]
+pub use pool::{BufferPool, BufferPoolConfig, BufferPoolStats, PooledFrame};
+#[cfg(feature = "std")]
+pub use priority::PrioritizedWriter;
+#[cfg(feature = "std")]
+pub use reader::{FrameReader, Frames};
+#[cfg(feature = "std")]
+pub use stats::{ChannelStats, FrameStats};
+#[cfg(feature = "crypto")]
+pub use transform::ChaCha20Poly1305Codec;
+#[cfg(feature = "std")]
+pub use transform::PayloadCodec;
+#[cfg(feature = "std")]
+pub use writer::{BatchError, FrameWriter};
@@ -0,0 +1,145 @@
+//! Hooks for tracing and metrics on every frame a reader or writer moves.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use crate::error::FrameError;
+
+/// Receives an event for every message a [`FrameReader`](crate::FrameReader)
+/// or [`FrameWriter`](crate::FrameWriter) handles, once set with their
+/// `set_observer`.
+///
+/// Events fire after the fact: `on_send` once a message is written in full,
+/// `on_recv` once one is decoded (and reassembled, if fragmented). Lengths
+/// are payload bytes as the caller sees them, as in
+/// [`FrameStats`](crate::FrameStats). Observers only get the channel and the
+/// length, so they cannot change or drop frames.
+///
+/// Methods run on the reading or writing thread and should return quickly;
+/// slow work belongs on another thread. A panic inside a method is caught
+/// and discarded, so it cannot take the connection down with it.
+pub trait FrameObserver: Send + Sync {
+    /// A message of `len` payload bytes was written on `channel`.
+    fn on_send(&self, channel: u16, len: usize) {
+        let _ = (channel, len);
+    }
+
+    /// A message of `len` payload bytes was read on `channel`.
+    fn on_recv(&self, channel: u16, len: usize) {
+        let _ = (channel, len);
+    }
+
+    /// A read or send failed. A `WouldBlock` from a non-blocking stream is
+    /// not reported.
+    fn on_error(&self, error: &FrameError) {
+        let _ = error;
+    }
+}
+
+/// Run `event` on the observer, if there is one, swallowing any panic.
+pub(crate) fn notify(
+    observer: Option<&Arc<dyn FrameObserver>>,
+    event: impl FnOnce(&dyn FrameObserver),
+) {
+    if let Some(observer) = observer {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| event(observer.as_ref())));
+    }
+}
+
+/// Report `result`'s error, if any, to the observer.
+pub(crate) fn notify_error<R>(
+    observer: Option<&Arc<dyn FrameObserver>>,
+    result: &Result<R, FrameError>,
+) {
+    match result {
+        Err(FrameError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+        Err(err) => notify(observer, |observer| observer.on_error(err)),
+        Ok(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{FrameConfig, FrameReader, FrameWriter, COMMAND, DATA};
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Send(u16, usize),
+        Recv(u16, usize),
+        Error(String),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<Event>>);
+
+    impl FrameObserver for Recorder {
+        fn on_send(&self, channel: u16, len: usize) {
+            self.0.lock().unwrap().push(Event::Send(channel, len));
+        }
+
+        fn on_recv(&self, channel: u16, len: usize) {
+            self.0.lock().unwrap().push(Event::Recv(channel, len));
+        }
+
+        fn on_error(&self, error: &FrameError) {
+            self.0.lock().unwrap().push(Event::Error(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn events_follow_frames_in_order() {
+        let recorder = Arc::new(Recorder::default());
+        let config = FrameConfig {
+            max_payload_size: 64,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.set_observer(recorder.clone());
+        writer.send(COMMAND, b"ping").unwrap();
+        writer.send(DATA, &[0u8; 100]).unwrap_err();
+        writer.send_batch(&[(DATA, b"ab"), (DATA, b"c")]).unwrap();
+
+        let mut reader = FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        reader.set_observer(recorder.clone());
+        reader.read_frame().unwrap();
+        let mut buf = Vec::new();
+        reader.read_frame_into(&mut buf).unwrap();
+        reader.read_frame().unwrap();
+        reader.read_frame().unwrap_err();
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(
+            events[..],
+            [
+                Event::Send(COMMAND, 4),
+                Event::Error(FrameError::PayloadTooLarge { size: 100, max: 64 }.to_string()),
+                Event::Send(DATA, 2),
+                Event::Send(DATA, 1),
+                Event::Recv(COMMAND, 4),
+                Event::Recv(DATA, 2),
+                Event::Recv(DATA, 1),
+                Event::Error(FrameError::ConnectionClosed.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn panicking_observer_does_not_break_frames() {
+        struct Panics;
+        impl FrameObserver for Panics {
+            fn on_send(&self, _: u16, _: usize) {
+                panic!("observer bug");
+            }
+        }
+
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.set_observer(Arc::new(Panics));
+        writer.send(COMMAND, b"still sent").unwrap();
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"still sent");
+    }
+}
@@ -0,0 +1,477 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bytes::BytesMut;
+
+/// Configuration for a [`BufferPool`].
+#[derive(Debug, Clone)]
+pub struct BufferPoolConfig {
+    /// Capacity of freshly allocated buffers. Default: 8 KiB.
+    pub buffer_capacity: usize,
+    /// Buffers that grew past this capacity are dropped instead of pooled.
+    /// Default: 64 KiB.
+    pub max_buffer_capacity: usize,
+    /// Maximum number of idle buffers kept. Default: 1024.
+    pub max_pooled: usize,
+}
+
+impl Default for BufferPoolConfig {
+    fn default() -> Self {
+        Self {
+            buffer_capacity: 8 * 1024,
+            max_buffer_capacity: 64 * 1024,
+            max_pooled: 1024,
+        }
+    }
+}
+
+/// Counters describing pool effectiveness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPoolStats {
+    /// Acquisitions served from an idle buffer.
+    pub hits: u64,
+    /// Acquisitions that had to allocate.
+    pub misses: u64,
+    /// Idle buffers currently held.
+    pub pooled: usize,
+}
+
+/// Shared pool of frame buffers for readers and writers.
+///
+/// Cloning is cheap and every clone shares the same buffers. Idle memory is
+/// bounded by `max_pooled * max_buffer_capacity`.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    config: BufferPoolConfig,
+    idle: Mutex<Vec<BytesMut>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BufferPool {
+    /// Create a pool with explicit configuration.
+    pub fn new(config: BufferPoolConfig) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                config,
+                idle: Mutex::new(Vec::new()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Take an empty buffer, reusing an idle one when available.
+    pub fn acquire(&self) -> BytesMut {
+        let reused = self.lock_idle().pop();
+        match reused {
+            Some(mut buf) => {
+                self.inner.hits.fetch_add(1, Ordering::Relaxed);
+                buf.reserve(self.inner.config.buffer_capacity);
+                buf
+            }
+            None => {
+                self.inner.misses.fetch_add(1, Ordering::Relaxed);
+                BytesMut::with_capacity(self.inner.config.buffer_capacity)
+            }
+        }
+    }
+
+    /// Return a buffer. Its contents are discarded; oversized buffers and
+    /// buffers beyond `max_pooled` are dropped.
+    pub fn release(&self, mut buf: BytesMut) {
+        if buf.capacity() > self.inner.config.max_buffer_capacity {
+            return;
+        }
+        buf.clear();
+        let mut idle = self.lock_idle();
+        if idle.len() < self.inner.config.max_pooled {
+            idle.push(buf);
+        }
+    }
+
+    /// Current hit/miss counters and idle buffer count.
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            pooled: self.lock_idle().len(),
+        }
+    }
+
+    /// Pool configuration.
+    pub fn config(&self) -> &BufferPoolConfig {
+        &self.inner.config
+    }
+
+    fn lock_idle(&self) -> std::sync::MutexGuard<'_, Vec<BytesMut>> {
+        // A panic while holding the lock cannot leave the Vec inconsistent.
+        self.inner
+            .idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(BufferPoolConfig::default())
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("config", &self.inner.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// A received payload whose storage goes back to a [`BufferPool`] when
+/// dropped, returned by
+/// [`FrameReader::read_frame_pooled`](crate::FrameReader::read_frame_pooled).
+///
+/// Derefs to the payload bytes. Like
+/// [`read_frame_into`](crate::FrameReader::read_frame_into), it does not
+/// report the priority or sequence number.
+#[derive(Debug)]
+pub struct PooledFrame {
+    channel: u16,
+    payload: BytesMut,
+    pool: Option<BufferPool>,
+}
+
+impl PooledFrame {
+    pub(crate) fn new(channel: u16, payload: BytesMut, pool: Option<BufferPool>) -> Self {
+        Self {
+            channel,
+            payload,
+            pool,
+        }
+    }
+
+    /// Channel the frame arrived on.
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Payload bytes.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Convert into a plain [`Frame`](crate::Frame) at priority 0. The
+    /// storage moves into the frame and never returns to the pool.
+    pub fn into_frame(mut self) -> crate::Frame {
+        let payload = std::mem::take(&mut self.payload).freeze();
+        self.pool = None;
+        crate::Frame::new(self.channel, payload)
+    }
+}
+
+impl Deref for PooledFrame {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+impl Drop for PooledFrame {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.release(std::mem::take(&mut self.payload));
+        }
+    }
+}
+
+/// A reader/writer buffer that returns itself to its pool on drop.
+pub(crate) struct PooledBuffer {
+    buf: BytesMut,
+    pool: Option<BufferPool>,
+    /// Capacity of replacement buffers when there is no pool.
+    initial_capacity: usize,
+}
+
+impl PooledBuffer {
+    /// A plain buffer with no pool attached.
+    pub(crate) fn unpooled(capacity: usize) -> Self {
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+            pool: None,
+            initial_capacity: capacity,
+        }
+    }
+
+    pub(crate) fn from_pool(pool: BufferPool) -> Self {
+        Self {
+            buf: pool.acquire(),
+            initial_capacity: pool.config().buffer_capacity,
+            pool: Some(pool),
+        }
+    }
+
+    /// Move any buffered bytes into a buffer taken from `pool`.
+    pub(crate) fn attach(&mut self, pool: BufferPool) {
+        let mut pooled = pool.acquire();
+        pooled.extend_from_slice(&self.buf);
+        let previous = std::mem::replace(&mut self.buf, pooled);
+        if let Some(old_pool) = self.pool.replace(pool) {
+            old_pool.release(previous);
+        }
+    }
+
+    /// Swap a buffer that grew past `threshold` (or the pool's limit, if
+    /// lower) for a fresh one, carrying over unread bytes, so one large frame
+    /// does not pin memory for the connection. A buffer holding more than
+    /// the limit in unread bytes is kept.
+    pub(crate) fn recycle_if_oversized(&mut self, threshold: usize) {
+        let limit = self.pool.as_ref().map_or(threshold, |pool| {
+            pool.config().max_buffer_capacity.min(threshold)
+        });
+        if self.buf.capacity() <= limit.max(self.initial_capacity) || self.buf.len() > limit {
+            return;
+        }
+
+        let mut fresh = match &self.pool {
+            Some(pool) => pool.acquire(),
+            None => BytesMut::with_capacity(self.initial_capacity),
+        };
+        fresh.extend_from_slice(&self.buf);
+        let oversized = std::mem::replace(&mut self.buf, fresh);
+        if let Some(pool) = &self.pool {
+            pool.release(oversized);
+        }
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &BytesMut {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.release(std::mem::take(&mut self.buf));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::codec::encode_frame;
+    use crate::reader::FrameReader;
+    use crate::writer::FrameWriter;
+    use crate::FrameConfig;
+
+    #[test]
+    fn released_buffers_are_reused() {
+        let pool = BufferPool::default();
+
+        let buf = pool.acquire();
+        pool.release(buf);
+        let _buf = pool.acquire();
+
+        let stats = pool.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.pooled, 0);
+    }
+
+    #[test]
+    fn writers_reuse_buffers_after_drop() {
+        let pool = BufferPool::default();
+
+        for _ in 0..5 {
+            let mut writer =
+                FrameWriter::with_pool(Vec::new(), FrameConfig::default(), pool.clone());
+            writer.send(1, b"hello").unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 4);
+        assert_eq!(stats.pooled, 1);
+    }
+
+    #[test]
+    fn readers_return_buffers_on_drop() {
+        let pool = BufferPool::default();
+        let mut wire = BytesMut::new();
+        encode_frame(2, b"pooled", &mut wire).unwrap();
+
+        for _ in 0..3 {
+            let mut reader = FrameReader::with_pool(
+                Cursor::new(wire.to_vec()),
+                FrameConfig::default(),
+                pool.clone(),
+            );
+            assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"pooled");
+        }
+
+        assert_eq!(pool.stats().hits, 2);
+    }
+
+    #[test]
+    fn oversized_and_excess_buffers_are_dropped() {
+        let pool = BufferPool::new(BufferPoolConfig {
+            buffer_capacity: 16,
+            max_buffer_capacity: 64,
+            max_pooled: 2,
+        });
+
+        pool.release(BytesMut::with_capacity(1024));
+        assert_eq!(pool.stats().pooled, 0);
+
+        for _ in 0..4 {
+            pool.release(BytesMut::with_capacity(16));
+        }
+        assert_eq!(pool.stats().pooled, 2);
+    }
+
+    #[test]
+    fn writer_swaps_out_buffer_after_large_frame() {
+        let pool = BufferPool::new(BufferPoolConfig {
+            buffer_capacity: 64,
+            max_buffer_capacity: 256,
+            max_pooled: 8,
+        });
+        let mut writer = FrameWriter::with_pool(Vec::new(), FrameConfig::default(), pool.clone());
+
+        // Plain sends skip the encode buffer; a batch still fills it.
+        writer.send_batch(&[(1, &[0u8; 4096])]).unwrap();
+        // The grown buffer was discarded and replaced with a fresh one.
+        assert_eq!(pool.stats().misses, 2);
+        assert_eq!(pool.stats().pooled, 0);
+        writer.send(1, b"small").unwrap();
+
+        let wire = writer.into_inner();
+        assert_eq!(wire.len(), 2 * crate::HEADER_SIZE + 4096 + 5);
+        assert_eq!(pool.stats().pooled, 1);
+    }
+
+    #[test]
+    fn pooled_frames_return_payload_storage() {
+        let pool = BufferPool::default();
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send(1, b"first").unwrap();
+        writer.send(2, b"second").unwrap();
+        writer.send(3, b"third").unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        reader.set_payload_pool(pool.clone());
+        let first = reader.read_frame_pooled().unwrap();
+        assert_eq!((first.channel(), first.payload()), (1, &b"first"[..]));
+        drop(first);
+        assert_eq!(pool.stats().pooled, 1);
+
+        let second = reader.read_frame_pooled().unwrap();
+        assert_eq!(&second[..], b"second");
+        assert_eq!(pool.stats().hits, 1);
+        let frame = second.into_frame();
+        assert_eq!((frame.channel, frame.payload.as_ref()), (2, &b"second"[..]));
+        assert_eq!(pool.stats().pooled, 0);
+
+        reader.read_frame_pooled().unwrap();
+        let err = reader.read_frame_pooled().unwrap_err();
+        assert!(matches!(err, crate::FrameError::ConnectionClosed));
+        assert_eq!(pool.stats().pooled, 1);
+
+        let mut unpooled = FrameReader::new(Cursor::new(Vec::new()));
+        assert!(unpooled.read_frame_pooled().is_err());
+    }
+
+    #[test]
+    fn pooled_reads_keep_allocations_bounded() {
+        const FRAMES: usize = 20_000;
+        const IN_FLIGHT: usize = 8;
+        let pool = BufferPool::new(BufferPoolConfig {
+            buffer_capacity: 256,
+            max_buffer_capacity: 4096,
+            max_pooled: 16,
+        });
+        let mut writer = FrameWriter::new(Vec::new());
+        for i in 0..FRAMES {
+            writer.send(1, &[i as u8; 100]).unwrap();
+        }
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        reader.set_payload_pool(pool.clone());
+        let mut held = std::collections::VecDeque::new();
+        for i in 0..FRAMES {
+            let frame = reader.read_frame_pooled().unwrap();
+            assert_eq!(frame[0], i as u8);
+            held.push_back(frame);
+            if held.len() > IN_FLIGHT {
+                held.pop_front();
+            }
+        }
+        drop(held);
+
+        // Allocations never exceed the frames alive at once.
+        let stats = pool.stats();
+        assert!(stats.misses <= IN_FLIGHT as u64 + 1, "{stats:?}");
+        assert_eq!(stats.hits + stats.misses, FRAMES as u64);
+        assert!(stats.pooled <= 16);
+    }
+
+    #[test]
+    fn concurrent_use_is_safe() {
+        let pool = BufferPool::new(BufferPoolConfig {
+            max_pooled: 4,
+            ..BufferPoolConfig::default()
+        });
+
+        let threads: Vec<_> = (0..16)
+            .map(|t| {
+                let pool = pool.clone();
+                std::thread::spawn(move || {
+                    for i in 0..200u32 {
+                        let payload = (t * 1000 + i).to_le_bytes();
+                        let mut writer = FrameWriter::with_pool(
+                            Vec::new(),
+                            FrameConfig::default(),
+                            pool.clone(),
+                        );
+                        writer.send(1, &payload).unwrap();
+
+                        let mut reader = FrameReader::with_pool(
+                            Cursor::new(writer.into_inner()),
+                            FrameConfig::default(),
+                            pool.clone(),
+                        );
+                        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), &payload);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.hits + stats.misses, 16 * 200 * 2);
+        assert!(stats.hits > 0);
+        assert!(stats.pooled <= 4);
+    }
+}
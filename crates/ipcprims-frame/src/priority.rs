@@ -0,0 +1,319 @@
+//! Priority queue in front of a [`FrameWriter`].
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::Write;
+
+use bytes::Bytes;
+
+use crate::codec::{Frame, Priority};
+use crate::error::{FrameError, Result};
+use crate::writer::FrameWriter;
+
+/// Queues outgoing messages and writes the most urgent first.
+///
+/// Sends only queue the message; [`write_next`](Self::write_next) and
+/// [`flush`](Self::flush) write it. A queued message with a higher
+/// [`Frame::priority`] is always written before any lower-priority message
+/// that has not started yet, and messages of equal priority keep their
+/// order, so a CONTROL message queued behind bulk DATA goes out next.
+///
+/// A message already on the wire is never interrupted. In particular,
+/// high-priority frames are not interleaved between the fragments of a
+/// message that has started: with fragmentation enabled its fragments still
+/// go out back to back, since readers drop a message whose fragments are
+/// interleaved with other frames. An urgent message queued behind a large
+/// fragmented one waits for all of its fragments.
+pub struct PrioritizedWriter<T> {
+    writer: FrameWriter<T>,
+    queue: BinaryHeap<Queued>,
+    next_order: u64,
+}
+
+/// A queued message, ordered by priority and then by arrival.
+struct Queued {
+    order: u64,
+    frame: Frame,
+}
+
+impl Queued {
+    fn key(&self) -> (Priority, Reverse<u64>) {
+        (self.frame.priority, Reverse(self.order))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl<T: Write> PrioritizedWriter<T> {
+    /// Queue in front of `writer`, which keeps its configuration.
+    pub fn new(writer: FrameWriter<T>) -> Self {
+        Self {
+            writer,
+            queue: BinaryHeap::new(),
+            next_order: 0,
+        }
+    }
+
+    /// Queue a payload on `channel` at [`Priority::NORMAL`].
+    pub fn send(&mut self, channel: u16, payload: &[u8]) -> Result<()> {
+        self.send_with_priority(channel, payload, Priority::NORMAL)
+    }
+
+    /// Queue a payload on `channel` with a priority; higher is more urgent.
+    pub fn send_with_priority(
+        &mut self,
+        channel: u16,
+        payload: &[u8],
+        priority: Priority,
+    ) -> Result<()> {
+        let frame = Frame::new(channel, Bytes::copy_from_slice(payload)).with_priority(priority);
+        self.enqueue(frame)
+    }
+
+    /// Queue a frame at its own priority without copying the payload.
+    ///
+    /// A payload over the writer's size limit is rejected here with
+    /// `FrameError::PayloadTooLarge` rather than when it is written.
+    pub fn enqueue(&mut self, frame: Frame) -> Result<()> {
+        let max = self.writer.config().send_limit();
+        if frame.payload.len() > max {
+            return Err(FrameError::PayloadTooLarge {
+                size: frame.payload.len(),
+                max,
+            });
+        }
+        self.queue.push(Queued {
+            order: self.next_order,
+            frame,
+        });
+        self.next_order += 1;
+        Ok(())
+    }
+
+    /// Write the most urgent queued message. Returns `false` if the queue
+    /// was empty.
+    ///
+    /// If the write fails the message stays queued, at the head of its
+    /// priority, unless the writer is poisoned and can send nothing more.
+    pub fn write_next(&mut self) -> Result<bool> {
+        let Some(next) = self.queue.pop() else {
+            return Ok(false);
+        };
+        if let Err(err) = self.writer.write_frame(&next.frame) {
+            if !self.writer.is_poisoned() {
+                self.queue.push(next);
+            }
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    /// Write every queued message, most urgent first.
+    ///
+    /// Stops at the first error, leaving the rest queued.
+    pub fn flush(&mut self) -> Result<()> {
+        while self.write_next()? {}
+        Ok(())
+    }
+
+    /// Number of messages waiting to be written.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &FrameWriter<T> {
+        &self.writer
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    ///
+    /// Frames sent through it directly bypass the queue.
+    pub fn get_mut(&mut self) -> &mut FrameWriter<T> {
+        &mut self.writer
+    }
+
+    /// Return the wrapped writer, dropping any messages still queued.
+    pub fn into_inner(self) -> FrameWriter<T> {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use super::*;
+    use crate::codec::FrameConfig;
+    use crate::reader::FrameReader;
+    use crate::{COMMAND, CONTROL, DATA};
+
+    fn on_the_wire(bytes: &[u8]) -> Vec<(u16, Priority, Bytes)> {
+        FrameReader::new(Cursor::new(bytes.to_vec()))
+            .frames()
+            .map(|frame| {
+                let frame = frame.unwrap();
+                (frame.channel, frame.priority, frame.payload)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn urgent_messages_overtake_queued_ones() {
+        let mut writer = PrioritizedWriter::new(FrameWriter::new(Vec::new()));
+        writer.send(DATA, b"bulk-1").unwrap();
+        writer.send(DATA, b"bulk-2").unwrap();
+        writer
+            .send_with_priority(COMMAND, b"cmd", Priority(1))
+            .unwrap();
+        writer
+            .send_with_priority(CONTROL, b"ctl", Priority(5))
+            .unwrap();
+        writer.send(DATA, b"bulk-3").unwrap();
+        writer
+            .send_with_priority(COMMAND, b"cmd-2", Priority(1))
+            .unwrap();
+        assert_eq!(writer.queued(), 6);
+
+        assert!(writer.write_next().unwrap());
+        writer
+            .send_with_priority(CONTROL, b"late-ctl", Priority::HIGHEST)
+            .unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.queued(), 0);
+        assert!(!writer.write_next().unwrap());
+
+        let wire = on_the_wire(writer.get_ref().get_ref());
+        let order: Vec<_> = wire.iter().map(|(_, _, p)| &p[..]).collect();
+        assert_eq!(
+            order,
+            [
+                &b"ctl"[..],
+                b"late-ctl",
+                b"cmd",
+                b"cmd-2",
+                b"bulk-1",
+                b"bulk-2",
+                b"bulk-3"
+            ]
+        );
+        assert_eq!(
+            wire[1],
+            (CONTROL, Priority::HIGHEST, Bytes::from_static(b"late-ctl"))
+        );
+    }
+
+    #[test]
+    fn priorities_travel_in_the_versioned_header() {
+        let mut writer = PrioritizedWriter::new(FrameWriter::new(Vec::new()));
+        writer.send(DATA, b"normal").unwrap();
+        writer
+            .send_with_priority(COMMAND, b"urgent", Priority(4))
+            .unwrap();
+        writer.flush().unwrap();
+
+        let wire = writer.get_ref().get_ref();
+        let urgent_len = crate::VERSIONED_HEADER_SIZE + 1 + 6;
+        assert_eq!(wire[..4], [0x49, 0x56, 2, crate::FLAG_PRIORITY]);
+        assert_eq!(wire[crate::VERSIONED_HEADER_SIZE], 4);
+        assert_eq!(wire[urgent_len..urgent_len + 2], crate::codec::MAGIC);
+    }
+
+    #[test]
+    fn fragments_of_a_started_message_stay_together() {
+        let config = FrameConfig {
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        let mut writer = PrioritizedWriter::new(FrameWriter::with_config(Vec::new(), config));
+        writer.send(DATA, b"0123456789").unwrap();
+        writer.send(DATA, b"abcdefgh").unwrap();
+        assert!(writer.write_next().unwrap());
+        writer
+            .send_with_priority(CONTROL, b"stop", Priority(3))
+            .unwrap();
+        writer.flush().unwrap();
+
+        let order: Vec<_> = on_the_wire(writer.get_ref().get_ref())
+            .into_iter()
+            .map(|(_, _, payload)| payload)
+            .collect();
+        assert_eq!(order, ["0123456789", "stop", "abcdefgh"]);
+    }
+
+    #[test]
+    fn oversized_payloads_are_rejected_when_queued() {
+        let config = FrameConfig {
+            max_payload_size: 8,
+            ..FrameConfig::default()
+        };
+        let mut writer = PrioritizedWriter::new(FrameWriter::with_config(Vec::new(), config));
+        assert!(matches!(
+            writer.send(DATA, &[0; 9]),
+            Err(FrameError::PayloadTooLarge { size: 9, max: 8 })
+        ));
+        assert_eq!(writer.queued(), 0);
+    }
+
+    /// Fails the first write before accepting any bytes.
+    struct FailsOnce {
+        failed: bool,
+        data: Vec<u8>,
+    }
+
+    impl Write for FailsOnce {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if !self.failed {
+                self.failed = true;
+                return Err(std::io::Error::from(ErrorKind::PermissionDenied));
+            }
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_write_keeps_the_message_queued() {
+        let inner = FailsOnce {
+            failed: false,
+            data: Vec::new(),
+        };
+        let mut writer = PrioritizedWriter::new(FrameWriter::new(inner));
+        writer.send(DATA, b"low").unwrap();
+        writer
+            .send_with_priority(CONTROL, b"high", Priority(2))
+            .unwrap();
+
+        assert!(matches!(writer.flush(), Err(FrameError::Io(_))));
+        assert_eq!(writer.queued(), 2);
+        writer.flush().unwrap();
+
+        let order: Vec<_> = on_the_wire(&writer.get_ref().get_ref().data)
+            .into_iter()
+            .map(|(_, _, payload)| payload)
+            .collect();
+        assert_eq!(order, ["high", "low"]);
+    }
+}
@@ -1,21 +1,47 @@
 use std::io::{ErrorKind, Read};
+use std::iter::FusedIterator;
+use std::sync::Arc;
 
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 use ipcprims_transport::IpcStream;
 
-use crate::codec::{decode_frame, Frame, FrameConfig};
-use crate::error::{FrameError, Result};
+use crate::channel::HEARTBEAT;
+use crate::codec::{
+    check_buffer_limit, decode_frame_copying, decode_plain_frame_into, header_prefix, peek_header,
+    Frame, FrameConfig, FrameHeader, PayloadBuf, MAGIC, VERSIONED_MAGIC,
+};
+use crate::error::{is_disconnect, FrameError, Result};
+use crate::fragment::Reassembler;
+use crate::heartbeat::{HeartbeatHandling, HeartbeatMonitor};
+use crate::observer::{notify, notify_error, FrameObserver};
+use crate::pool::{BufferPool, PooledBuffer, PooledFrame};
+use crate::sequence::SequenceTracker;
+use crate::stats::FrameStats;
+use crate::transform::{codec_for, open_frame, PayloadCodec};
 
 const INITIAL_BUFFER_CAPACITY: usize = 8 * 1024;
 const READ_CHUNK_SIZE: usize = 8 * 1024;
+/// Largest single read while a big frame is pending. Bounds how far the
+/// buffer grows ahead of bytes actually received from the peer.
+const MAX_READ_CHUNK_SIZE: usize = 64 * 1024;
 
 /// Reads complete frames from any `Read` stream.
 ///
-/// Handles partial reads internally — callers always get complete frames.
+/// Handles partial reads internally — callers always get complete frames,
+/// with fragmented messages reassembled.
 pub struct FrameReader<T> {
     inner: T,
-    buf: BytesMut,
+    buf: PooledBuffer,
     config: FrameConfig,
+    reassembly: Reassembler,
+    sequences: SequenceTracker,
+    /// A message held back by a sequence gap error, returned next.
+    held: Option<Frame>,
+    stats: Option<FrameStats>,
+    heartbeats: HeartbeatHandling,
+    observer: Option<Arc<dyn FrameObserver>>,
+    payload_pool: Option<BufferPool>,
+    payload_codec: Option<Arc<dyn PayloadCodec>>,
 }
 
 impl<T: Read> FrameReader<T> {
@@ -28,35 +54,543 @@ impl<T: Read> FrameReader<T> {
     pub fn with_config(inner: T, config: FrameConfig) -> Self {
         Self {
             inner,
-            buf: BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY),
+            buf: PooledBuffer::unpooled(INITIAL_BUFFER_CAPACITY),
+            stats: config.collect_stats.then(FrameStats::default),
             config,
+            reassembly: Reassembler::default(),
+            sequences: SequenceTracker::default(),
+            held: None,
+            heartbeats: HeartbeatHandling::default(),
+            observer: None,
+            payload_pool: None,
+            payload_codec: None,
         }
     }
 
+    /// Create a frame reader whose read buffer is borrowed from `pool` and
+    /// returned when the reader is dropped.
+    pub fn with_pool(inner: T, config: FrameConfig, pool: BufferPool) -> Self {
+        Self {
+            inner,
+            buf: PooledBuffer::from_pool(pool),
+            stats: config.collect_stats.then(FrameStats::default),
+            config,
+            reassembly: Reassembler::default(),
+            sequences: SequenceTracker::default(),
+            held: None,
+            heartbeats: HeartbeatHandling::default(),
+            observer: None,
+            payload_pool: None,
+            payload_codec: None,
+        }
+    }
+
+    /// Move this reader's buffer, including any unread bytes, into `pool`.
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.buf.attach(pool);
+    }
+
+    /// Take payload storage for [`read_frame_pooled`](Self::read_frame_pooled)
+    /// from `pool`. Can be the reader's own buffer pool or a separate one.
+    pub fn set_payload_pool(&mut self, pool: BufferPool) {
+        self.payload_pool = Some(pool);
+    }
+
     /// Read the next complete frame (blocking).
     ///
-    /// Returns `Err(FrameError::ConnectionClosed)` when EOF is reached.
+    /// Returns `Err(FrameError::ConnectionClosed)` when EOF is reached between
+    /// frames, or `Err(FrameError::Truncated)` when it cuts a frame short. A
+    /// reset or broken connection is treated the same as EOF. Checksummed
+    /// frames are verified; a corrupted one is dropped with
+    /// `Err(FrameError::ChecksumMismatch)` and the next call reads on.
+    /// Compressed frames are decompressed, subject to `max_payload_size`.
+    /// Fragments are joined into one frame of up to `max_message_size`
+    /// bytes; a message whose fragments are missing, out of order or
+    /// interleaved with other frames fails with
+    /// `Err(FrameError::Fragmentation)` and is dropped. A frame larger than
+    /// [`FrameConfig::max_buffer_bytes`] fails with
+    /// `Err(FrameError::BufferLimitExceeded)` as soon as its header arrives.
+    /// A message whose
+    /// sequence number does not follow the last one on its channel fails
+    /// with `Err(FrameError::SequenceGap)` and is returned by the next call.
+    /// On a non-blocking stream without a complete frame it fails with
+    /// `Err(FrameError::Io)` of kind `WouldBlock`, keeping what it read for
+    /// the next call; [`try_read_frame`](Self::try_read_frame) returns
+    /// `Ok(None)` instead.
     pub fn read_frame(&mut self) -> Result<Frame> {
+        let result = self.next_frame();
+        notify_error(self.observer.as_ref(), &result);
+        result
+    }
+
+    fn next_frame(&mut self) -> Result<Frame> {
+        if let Some(ready) = self.take_ready() {
+            return ready;
+        }
+        let max_payload = self.wire_payload_limit();
+        loop {
+            check_buffer_limit(&self.buf, self.config.max_buffer_bytes)?;
+            if let Some((frame, fragment)) =
+                decode_frame_copying(&mut self.buf, max_payload, self.config.copy_out_threshold)?
+            {
+                self.buf
+                    .recycle_if_oversized(self.config.buffer_shrink_threshold);
+                match self.reassembly.push(frame, fragment, &self.config)? {
+                    Some(frame) if self.heartbeats.observe(frame.channel) => continue,
+                    Some(frame) => return self.checked(frame),
+                    None => continue,
+                }
+            }
+            self.read_more()?;
+        }
+    }
+
+    /// Return the next frame if one can be completed without blocking.
+    ///
+    /// Frames already buffered are returned without touching the stream.
+    /// Otherwise the reader reads until a frame completes or the stream
+    /// reports `WouldBlock`, keeping what it read, and returns `Ok(None)` in
+    /// the latter case. Meant for streams in non-blocking mode (see
+    /// `IpcStream::set_nonblocking`) driven by a readiness loop; on a
+    /// blocking stream it blocks like [`read_frame`](Self::read_frame).
+    ///
+    /// EOF and errors are reported as by `read_frame`: EOF between frames
+    /// fails with `Err(FrameError::ConnectionClosed)`, but EOF partway
+    /// through a frame fails with `Err(FrameError::Truncated)`, not
+    /// `ConnectionClosed`, so callers can tell a cut-off frame from a clean
+    /// close. Treat both as the end of the stream.
+    pub fn try_read_frame(&mut self) -> Result<Option<Frame>> {
+        match self.read_frame() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(FrameError::Io(err)) if err.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Iterate over incoming frames until the stream ends.
+    ///
+    /// Each item is what [`read_frame`](Self::read_frame) returned, except
+    /// that EOF between frames (`FrameError::ConnectionClosed`) ends the
+    /// iteration with `None` instead of appearing as an error. Any other
+    /// error, including `FrameError::Truncated` when EOF cuts a frame short,
+    /// is yielded once as `Some(Err(..))` and then the iteration ends too,
+    /// so a reader stuck on bad input (`InvalidMagic`, `PayloadTooLarge`)
+    /// cannot spin a `for` loop forever. The reader is left as `read_frame`
+    /// left it: call [`resync`](Self::resync) or simply `frames()` again to
+    /// carry on after a recoverable error such as a checksum mismatch.
+    ///
+    /// ```
+    /// # use ipcprims_frame::FrameReader;
+    /// # fn run(stream: impl std::io::Read) -> ipcprims_frame::Result<()> {
+    /// let mut reader = FrameReader::new(stream);
+    /// for frame in reader.frames().take(10) {
+    ///     let frame = frame?;
+    ///     println!("channel {}: {} bytes", frame.channel, frame.payload.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn frames(&mut self) -> Frames<'_, T> {
+        Frames {
+            reader: self,
+            done: false,
+        }
+    }
+
+    /// Read the next frame's payload into `buf`, returning its channel.
+    ///
+    /// `buf` is cleared and refilled, reusing its capacity, so a loop that
+    /// passes the same buffer allocates nothing per frame once the buffer
+    /// has grown to the largest payload. The priority is not reported.
+    /// Partial reads, EOF and errors behave exactly as in
+    /// [`read_frame`](Self::read_frame); on error `buf` is left unchanged.
+    /// Compressed and fragmented messages are still decoded into a `Frame`
+    /// first and then copied.
+    pub fn read_frame_into(&mut self, buf: &mut Vec<u8>) -> Result<u16> {
+        self.read_frame_into_buf(buf)
+    }
+
+    fn read_frame_into_buf(&mut self, buf: &mut impl PayloadBuf) -> Result<u16> {
+        let result = self.next_frame_into(buf);
+        notify_error(self.observer.as_ref(), &result);
+        result
+    }
+
+    fn next_frame_into(&mut self, buf: &mut impl PayloadBuf) -> Result<u16> {
+        if let Some(ready) = self.take_ready() {
+            return ready.map(|frame| copy_frame_into(frame, buf));
+        }
+        let max_payload = self.wire_payload_limit();
         loop {
-            if let Some(frame) = decode_frame(&mut self.buf, self.config.max_payload_size)? {
-                return Ok(frame);
+            check_buffer_limit(&self.buf, self.config.max_buffer_bytes)?;
+            if !self.reassembly.in_progress()
+                && !self.heartbeat_buffered()
+                && self.payload_codec.is_none()
+            {
+                if let Some(channel) = decode_plain_frame_into(&mut self.buf, max_payload, buf)? {
+                    self.buf
+                        .recycle_if_oversized(self.config.buffer_shrink_threshold);
+                    self.heartbeats.observe(channel);
+                    self.record_received(channel, buf.len());
+                    return Ok(channel);
+                }
+            }
+            if let Some((frame, fragment)) =
+                decode_frame_copying(&mut self.buf, max_payload, self.config.copy_out_threshold)?
+            {
+                self.buf
+                    .recycle_if_oversized(self.config.buffer_shrink_threshold);
+                match self.reassembly.push(frame, fragment, &self.config)? {
+                    Some(frame) if self.heartbeats.observe(frame.channel) => continue,
+                    Some(frame) => {
+                        return self.checked(frame).map(|frame| copy_frame_into(frame, buf))
+                    }
+                    None => continue,
+                }
+            }
+            self.read_more()?;
+        }
+    }
+
+    /// [`read_frame_into`](Self::read_frame_into) a buffer taken from the
+    /// payload pool (see [`set_payload_pool`](Self::set_payload_pool)),
+    /// which goes back to the pool when the returned frame is dropped.
+    ///
+    /// Once the pool has warmed up, a steady stream of frames that are
+    /// dropped after use allocates nothing. An empty pool hands out a fresh
+    /// buffer, and without a payload pool every call allocates, as
+    /// [`read_frame`](Self::read_frame) does. Payloads larger than the pool's
+    /// `max_buffer_capacity` are not pooled. Errors match `read_frame_into`,
+    /// and return the buffer to the pool.
+    pub fn read_frame_pooled(&mut self) -> Result<PooledFrame> {
+        let mut payload = match &self.payload_pool {
+            Some(pool) => pool.acquire(),
+            None => BytesMut::new(),
+        };
+        match self.read_frame_into_buf(&mut payload) {
+            Ok(channel) => Ok(PooledFrame::new(
+                channel,
+                payload,
+                self.payload_pool.clone(),
+            )),
+            Err(err) => {
+                if let Some(pool) = &self.payload_pool {
+                    pool.release(payload);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Channel and payload length of the next frame, without consuming it.
+    ///
+    /// Reads just until the header is buffered; the next
+    /// [`read_frame`](Self::read_frame) or
+    /// [`read_frame_into`](Self::read_frame_into) returns the same frame.
+    /// A bad magic or a payload over `max_payload_size` fails as it would
+    /// there, and EOF before a complete header is
+    /// `Err(FrameError::ConnectionClosed)`. Returns `Ok(None)` when the
+    /// stream reports `WouldBlock` first, as
+    /// [`try_read_frame`](Self::try_read_frame) does. While a fragmented
+    /// message is arriving, the header is that of its next fragment.
+    /// Absorbed heartbeats (see [`absorb_heartbeats`](Self::absorb_heartbeats))
+    /// are consumed on the way.
+    /// With a [payload codec](Self::set_payload_codec), the length is that of
+    /// the sealed payload.
+    pub fn peek_header(&mut self) -> Result<Option<FrameHeader>> {
+        if let Some(frame) = self.held.as_ref().or_else(|| self.reassembly.pending()) {
+            return Ok(Some(FrameHeader::of(frame)));
+        }
+        let max_payload = self.wire_payload_limit();
+        loop {
+            check_buffer_limit(&self.buf, self.config.max_buffer_bytes)?;
+            if self.heartbeat_buffered() {
+                if let Some((frame, _)) = decode_frame_copying(
+                    &mut self.buf,
+                    max_payload,
+                    self.config.copy_out_threshold,
+                )? {
+                    self.heartbeats.observe(frame.channel);
+                    continue;
+                }
+            } else if let Some(header) = peek_header(&self.buf, max_payload)? {
+                return Ok(Some(header));
+            }
+            match self.read_more() {
+                Ok(()) => {}
+                Err(FrameError::Io(err)) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(FrameError::Truncated { .. }) if !self.reassembly.in_progress() => {
+                    return Err(FrameError::ConnectionClosed)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Last sequence number received on `channel`, if its messages carry
+    /// them (see [`FrameConfig::sequence_numbers`]).
+    pub fn last_sequence(&self, channel: u16) -> Option<u32> {
+        self.sequences.last(channel)
+    }
+
+    /// A message ready without decoding: one held back by a sequence gap,
+    /// or one that interrupted a fragmented message.
+    fn take_ready(&mut self) -> Option<Result<Frame>> {
+        if let Some(frame) = self.held.take() {
+            return Some(Ok(frame));
+        }
+        let frame = self.reassembly.take_pending()?;
+        if self.heartbeats.observe(frame.channel) {
+            return None;
+        }
+        Some(self.checked(frame))
+    }
+
+    /// True if heartbeats are absorbed and the next buffered frame is one,
+    /// so it must take the path that can drop it.
+    fn heartbeat_buffered(&self) -> bool {
+        self.heartbeats.absorb
+            && matches!(header_prefix(&self.buf), Ok(Some(header)) if header.channel == HEARTBEAT)
+    }
+
+    /// Check a complete message's sequence number, holding the message back
+    /// for the next read when it reports a gap.
+    ///
+    /// A payload codec opens it first; if that fails, the message is dropped.
+    fn checked(&mut self, frame: Frame) -> Result<Frame> {
+        let sequence = self.sequences.observe(&frame);
+        let frame = match codec_for(self.payload_codec.as_ref(), frame.channel) {
+            Some(codec) => open_frame(codec, frame, self.plaintext_limit())?,
+            None => frame,
+        };
+        self.record_received(frame.channel, frame.payload.len());
+        match sequence {
+            Ok(()) => Ok(frame),
+            Err(err) => {
+                self.held = Some(frame);
+                Err(err)
             }
+        }
+    }
+
+    /// Largest payload accepted on the wire: `max_payload_size`, plus what
+    /// the payload codec may add.
+    fn wire_payload_limit(&self) -> usize {
+        let overhead = self
+            .payload_codec
+            .as_ref()
+            .map_or(0, |codec| codec.overhead());
+        self.config.max_payload_size.saturating_add(overhead)
+    }
+
+    /// Largest message a payload codec may open to.
+    fn plaintext_limit(&self) -> usize {
+        self.config
+            .max_payload_size
+            .max(self.config.max_message_size)
+    }
 
-            let mut chunk = [0u8; READ_CHUNK_SIZE];
-            let read = match self.inner.read(&mut chunk) {
+    /// Count a decoded message in the stats and tell the observer.
+    fn record_received(&mut self, channel: u16, len: usize) {
+        if let Some(stats) = &mut self.stats {
+            stats.record_received(channel, len);
+        }
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_recv(channel, len)
+        });
+    }
+
+    /// Read more bytes toward the next frame, failing on EOF or a read error.
+    fn read_more(&mut self) -> Result<()> {
+        loop {
+            let chunk = self
+                .pending_frame_remaining()
+                .clamp(READ_CHUNK_SIZE, MAX_READ_CHUNK_SIZE);
+            let read = match self.fill(chunk) {
                 Ok(n) => n,
                 Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                // Same event as EOF, reported differently by some transports.
+                Err(err) if is_disconnect(err.kind()) => 0,
                 Err(err) => return Err(FrameError::Io(err)),
             };
 
+            if read == 0 {
+                let reassembled = self.reassembly.buffered();
+                if self.buf.is_empty() && reassembled.is_none() {
+                    return Err(FrameError::ConnectionClosed);
+                }
+                return Err(FrameError::Truncated {
+                    buffered: self.buf.len() + reassembled.unwrap_or(0),
+                });
+            }
+            return Ok(());
+        }
+    }
+
+    /// Discard bytes up to the next plausible frame header.
+    ///
+    /// Recovery after [`FrameError::InvalidMagic`],
+    /// [`FrameError::UnsupportedVersion`], [`FrameError::UnsupportedFlags`],
+    /// [`FrameError::PayloadTooLarge`] or
+    /// [`FrameError::BufferLimitExceeded`]: scans forward for a header this
+    /// reader understands ([`MAGIC`], or [`VERSIONED_MAGIC`] with a known
+    /// version and flags) announcing a length within `max_payload_size` and
+    /// `max_buffer_bytes`, reading from the stream as needed, and returns how
+    /// many bytes were dropped. The next
+    /// [`read_frame`](Self::read_frame) starts at that header. Garbage that
+    /// happens to look like a header is indistinguishable from a real one.
+    ///
+    /// On a read error or timeout the bytes scanned so far stay discarded and
+    /// the call can be repeated. EOF returns `FrameError::ConnectionClosed`.
+    pub fn resync(&mut self) -> Result<usize> {
+        let mut discarded = 0;
+        loop {
+            let (offset, found) = self.scan_for_header();
+            self.buf.advance(offset);
+            discarded += offset;
+            if found {
+                return Ok(discarded);
+            }
+
+            let read = match self.fill(READ_CHUNK_SIZE) {
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) if is_disconnect(err.kind()) => 0,
+                Err(err) => return Err(FrameError::Io(err)),
+            };
             if read == 0 {
                 return Err(FrameError::ConnectionClosed);
             }
+        }
+    }
+
+    /// Offset of the first plausible header in the buffer, and whether one
+    /// was found. When not found, the offset is where scanning must resume
+    /// once more bytes arrive.
+    fn scan_for_header(&self) -> (usize, bool) {
+        let buf = &self.buf[..];
+        for start in 0..buf.len() {
+            if buf[start] != MAGIC[0] {
+                continue;
+            }
+            let candidate = &buf[start..];
+            if candidate.len() < 2 {
+                // Can't judge a partial header yet; keep it for the next read.
+                return (start, false);
+            }
+            if candidate[..2] != MAGIC && candidate[..2] != VERSIONED_MAGIC {
+                continue;
+            }
+            let header = match header_prefix(candidate) {
+                Ok(Some(header)) => header,
+                Ok(None) => return (start, false),
+                Err(_) => continue,
+            };
+            let fits_buffer = self
+                .config
+                .max_buffer_bytes
+                .is_none_or(|max| header.size + header.payload_len <= max);
+            if header.payload_len <= self.wire_payload_limit() && fits_buffer {
+                return (start, true);
+            }
+        }
+        (buf.len(), false)
+    }
+
+    /// Append up to `chunk` bytes from the stream to the buffer.
+    fn fill(&mut self, chunk: usize) -> std::io::Result<usize> {
+        // Read straight into the buffer's tail. `Read` impls may inspect
+        // the slice they are given, so the tail is zeroed rather than left
+        // uninitialized; that still saves a stack chunk and a copy.
+        let filled = self.buf.len();
+        self.buf.resize(filled + chunk, 0);
+        let result = self.inner.read(&mut self.buf[filled..]);
+        let read = *result.as_ref().unwrap_or(&0);
+        self.buf.truncate(filled + read.min(chunk));
+        result
+    }
+
+    /// Bytes still missing from the frame whose header is buffered, or 0.
+    ///
+    /// Only called after `decode_frame` accepted the header, so the length is
+    /// already bounded by `max_payload_size`.
+    fn pending_frame_remaining(&self) -> usize {
+        match header_prefix(&self.buf) {
+            Ok(Some(header)) => (header.size + header.payload_len).saturating_sub(self.buf.len()),
+            _ => 0,
+        }
+    }
+
+    /// Counters for the messages read so far, if
+    /// [`FrameConfig::collect_stats`] is set.
+    pub fn stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+
+    /// Drop frames on the [`HEARTBEAT`] channel instead of returning them.
+    ///
+    /// Absorbed heartbeats skip sequence checks and stats. Off by default,
+    /// so heartbeats reach callers that do not know about them as ordinary
+    /// empty frames.
+    pub fn absorb_heartbeats(&mut self, absorb: bool) {
+        self.heartbeats.absorb = absorb;
+    }
+
+    /// Absorb heartbeats and call `callback` for each one, e.g. to send a
+    /// reply from the other half of the connection.
+    pub fn on_heartbeat(&mut self, callback: impl FnMut() + Send + 'static) {
+        self.heartbeats.absorb = true;
+        self.heartbeats.callback = Some(Box::new(callback));
+    }
+
+    /// Absorb heartbeats and record every frame read, heartbeat or not, in
+    /// `monitor`.
+    pub fn set_heartbeat_monitor(&mut self, monitor: HeartbeatMonitor) {
+        self.heartbeats.absorb = true;
+        self.heartbeats.monitor = Some(monitor);
+    }
+
+    /// Report every message read, and every read error, to `observer`.
+    pub fn set_observer(&mut self, observer: Arc<dyn FrameObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Open every payload with `codec` once its message is complete, on the
+    /// channels it [applies to](PayloadCodec::applies_to).
+    ///
+    /// Frames may then exceed `max_payload_size` by the codec's
+    /// [`overhead`](PayloadCodec::overhead) on the wire. A payload that fails
+    /// to open is dropped with `Err(FrameError::PayloadTransform)`, and the
+    /// next read continues after it.
+    pub fn set_payload_codec(&mut self, codec: Arc<dyn PayloadCodec>) {
+        self.reassembly.allow_overhead(codec.overhead());
+        self.payload_codec = Some(codec);
+    }
+
+    /// Zero the counters returned by [`stats`](Self::stats).
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            stats.reset();
+        }
+    }
 
-            self.buf.extend_from_slice(&chunk[..read]);
+    /// Start or stop counting; see [`FrameConfig::collect_stats`]. Turning
+    /// it off drops the counters.
+    pub fn set_collect_stats(&mut self, collect_stats: bool) {
+        self.config.collect_stats = collect_stats;
+        if !collect_stats {
+            self.stats = None;
+        } else if self.stats.is_none() {
+            self.stats = Some(FrameStats::default());
         }
     }
 
+    /// Current capacity of the read buffer, for observing memory use; see
+    /// [`FrameConfig::buffer_shrink_threshold`].
+    pub fn buffer_capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
     /// Borrow the underlying stream.
     pub fn get_ref(&self) -> &T {
         &self.inner
@@ -93,12 +627,57 @@ impl FrameReader<IpcStream> {
     }
 }
 
+/// Iterator over a [`FrameReader`]'s frames, returned by
+/// [`FrameReader::frames`].
+pub struct Frames<'a, T> {
+    reader: &'a mut FrameReader<T>,
+    done: bool,
+}
+
+impl<T: Read> Iterator for Frames<'_, T> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.reader.read_frame() {
+            Ok(frame) => Some(Ok(frame)),
+            Err(FrameError::ConnectionClosed) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<T: Read> FusedIterator for Frames<'_, T> {}
+
+/// Replace `buf`'s contents with `frame`'s payload; returns the channel.
+fn copy_frame_into(frame: Frame, buf: &mut impl PayloadBuf) -> u16 {
+    buf.replace_with(&frame.payload);
+    frame.channel
+}
+
 fn transport_to_frame_error(err: ipcprims_transport::TransportError) -> FrameError {
     match err {
         ipcprims_transport::TransportError::Io(io)
         | ipcprims_transport::TransportError::Accept(io) => FrameError::Io(io),
         ipcprims_transport::TransportError::Bind { source, .. }
         | ipcprims_transport::TransportError::Connect { source, .. } => FrameError::Io(source),
+        ipcprims_transport::TransportError::ConnectionReset
+        | ipcprims_transport::TransportError::BrokenPipe => FrameError::ConnectionClosed,
+        ipcprims_transport::TransportError::NotFound { .. } => FrameError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            err.to_string(),
+        )),
+        ipcprims_transport::TransportError::PermissionDenied { .. } => FrameError::Io(
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, err.to_string()),
+        ),
         other => FrameError::Io(std::io::Error::other(other.to_string())),
     }
 }
@@ -112,7 +691,10 @@ mod tests {
     use bytes::{BufMut, BytesMut};
 
     use super::*;
-    use crate::codec::{encode_frame, MAGIC};
+    use crate::codec::{
+        decode_frame, encode_frame, encode_frame_with_checksum, Priority, HEADER_SIZE, MAGIC,
+        VERSIONED_HEADER_SIZE,
+    };
 
     #[test]
     fn read_single_frame() {
@@ -126,6 +708,70 @@ mod tests {
         assert_eq!(frame.payload.as_ref(), b"hello");
     }
 
+    #[test]
+    fn frames_ends_at_eof_without_an_error() {
+        let mut wire = BytesMut::new();
+        for payload in [&b"one"[..], b"two", b"three"] {
+            encode_frame(1, payload, &mut wire).unwrap();
+        }
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+
+        let payloads: Vec<_> = reader
+            .frames()
+            .map(|frame| frame.unwrap().payload)
+            .collect();
+        assert_eq!(payloads, ["one", "two", "three"]);
+
+        // `read_frame` itself still reports the EOF.
+        assert!(matches!(
+            reader.read_frame(),
+            Err(FrameError::ConnectionClosed)
+        ));
+        assert_eq!(reader.frames().count(), 0);
+    }
+
+    #[test]
+    fn frames_composes_with_take() {
+        let mut wire = BytesMut::new();
+        for channel in 1..=5 {
+            encode_frame(channel, b"x", &mut wire).unwrap();
+        }
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+
+        let channels: Vec<_> = reader
+            .frames()
+            .take(2)
+            .map(|frame| frame.unwrap().channel)
+            .collect();
+        assert_eq!(channels, [1, 2]);
+        assert_eq!(reader.read_frame().unwrap().channel, 3);
+    }
+
+    #[test]
+    fn frames_yields_real_errors_once_then_ends() {
+        let mut wire = BytesMut::new();
+        encode_frame(1, b"good", &mut wire).unwrap();
+        wire.extend_from_slice(b"XXjunkjunk");
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+
+        let mut frames = reader.frames();
+        assert_eq!(frames.next().unwrap().unwrap().payload, "good");
+        assert!(matches!(frames.next(), Some(Err(FrameError::InvalidMagic))));
+        assert!(frames.next().is_none());
+        assert!(frames.next().is_none());
+
+        // EOF inside a frame is an error, not a clean end.
+        let mut wire = BytesMut::new();
+        encode_frame(1, b"cut short", &mut wire).unwrap();
+        wire.truncate(HEADER_SIZE + 2);
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+        let results: Vec<_> = reader.frames().collect();
+        assert!(matches!(
+            results[..],
+            [Err(FrameError::Truncated { buffered })] if buffered == HEADER_SIZE + 2
+        ));
+    }
+
     #[test]
     fn read_multiple_frames() {
         let mut wire = BytesMut::new();
@@ -190,7 +836,7 @@ mod tests {
 
         let mut reader = FrameReader::new(Cursor::new(partial.to_vec()));
         let err = reader.read_frame().unwrap_err();
-        assert!(matches!(err, FrameError::ConnectionClosed));
+        assert!(matches!(err, FrameError::Truncated { buffered } if buffered == partial.len()));
     }
 
     #[test]
@@ -217,6 +863,167 @@ mod tests {
         assert!(matches!(err, FrameError::PayloadTooLarge { .. }));
     }
 
+    #[test]
+    fn resync_skips_garbage_before_next_frame() {
+        let mut wire = BytesMut::new();
+        encode_frame(1, b"before", &mut wire).unwrap();
+        // Includes lone magic bytes that must not be mistaken for a header.
+        wire.put_slice(b"xxIyyPI");
+        encode_frame(2, b"after", &mut wire).unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"before");
+        assert!(matches!(
+            reader.read_frame().unwrap_err(),
+            FrameError::InvalidMagic
+        ));
+
+        assert_eq!(reader.resync().unwrap(), 7);
+        let frame = reader.read_frame().unwrap();
+        assert_eq!(
+            (frame.channel, frame.payload.as_ref()),
+            (2, b"after".as_ref())
+        );
+    }
+
+    #[test]
+    fn resync_skips_oversized_header() {
+        let mut wire = BytesMut::new();
+        wire.put_slice(&MAGIC);
+        wire.put_u32_le(1024);
+        wire.put_u16_le(1);
+        encode_frame(3, b"ok", &mut wire).unwrap();
+
+        let cfg = FrameConfig {
+            max_payload_size: 16,
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(wire.to_vec()), cfg);
+        assert!(matches!(
+            reader.read_frame().unwrap_err(),
+            FrameError::PayloadTooLarge { .. }
+        ));
+
+        assert_eq!(reader.resync().unwrap(), HEADER_SIZE);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"ok");
+    }
+
+    #[test]
+    fn resync_skips_frame_with_unknown_header_version() {
+        let mut wire = BytesMut::new();
+        wire.put_slice(&VERSIONED_MAGIC);
+        wire.put_u8(9);
+        wire.put_u8(0);
+        wire.put_u32_le(4);
+        wire.put_u16_le(1);
+        wire.put_slice(b"newr");
+        encode_frame(3, b"ok", &mut wire).unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+        assert!(matches!(
+            reader.read_frame().unwrap_err(),
+            FrameError::UnsupportedVersion(9)
+        ));
+
+        assert_eq!(reader.resync().unwrap(), VERSIONED_HEADER_SIZE + 4);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"ok");
+    }
+
+    /// Sends `prefix`, then one filler byte per read, forever.
+    struct EndlessTrickle {
+        prefix: Cursor<Vec<u8>>,
+    }
+
+    impl Read for EndlessTrickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.prefix.read(buf)? {
+                0 => {
+                    buf[0] = 0;
+                    Ok(1)
+                }
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn buffer_limit_stops_a_frame_that_never_completes() {
+        let mut header = BytesMut::new();
+        header.put_slice(&MAGIC);
+        header.put_u32_le(1024 * 1024);
+        header.put_u16_le(1);
+        let stream = EndlessTrickle {
+            prefix: Cursor::new(header.to_vec()),
+        };
+
+        let config = FrameConfig {
+            max_buffer_bytes: Some(64 * 1024),
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(stream, config);
+        let err = reader.read_frame().unwrap_err();
+        assert!(
+            matches!(err, FrameError::BufferLimitExceeded { max: 65536, .. }),
+            "{err}"
+        );
+        assert!(reader.buf.len() <= 64 * 1024);
+        // The frame is not consumed: reading again fails the same way.
+        assert!(matches!(
+            reader.read_frame_into(&mut Vec::new()),
+            Err(FrameError::BufferLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn resync_skips_frame_over_buffer_limit() {
+        let mut wire = BytesMut::new();
+        encode_frame(1, &[b'x'; 200], &mut wire).unwrap();
+        encode_frame(2, b"fits", &mut wire).unwrap();
+
+        let config = FrameConfig {
+            max_buffer_bytes: Some(64),
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(wire.to_vec()), config);
+        let err = reader.read_frame().unwrap_err();
+        assert!(
+            matches!(
+                err,
+                FrameError::BufferLimitExceeded {
+                    buffered: 208,
+                    max: 64
+                }
+            ),
+            "{err}"
+        );
+
+        assert_eq!(reader.resync().unwrap(), HEADER_SIZE + 200);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"fits");
+    }
+
+    #[test]
+    fn resync_reads_until_a_header_arrives() {
+        let mut wire = BytesMut::new();
+        wire.put_slice(b"garbage");
+        encode_frame(4, b"late", &mut wire).unwrap();
+
+        let mut reader = FrameReader::new(ByteByByteReader {
+            bytes: wire.to_vec(),
+            pos: 0,
+        });
+        assert_eq!(reader.resync().unwrap(), 7);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"late");
+    }
+
+    #[test]
+    fn resync_without_a_header_reports_connection_closed() {
+        let mut reader = FrameReader::new(Cursor::new(b"no frames here, just I".to_vec()));
+        assert!(matches!(
+            reader.resync().unwrap_err(),
+            FrameError::ConnectionClosed
+        ));
+    }
+
     #[derive(Debug)]
     struct ByteByByteReader {
         bytes: Vec<u8>,
@@ -252,6 +1059,382 @@ mod tests {
         assert_eq!(frame.payload.as_ref(), b"ping");
     }
 
+    #[test]
+    fn checksummed_frames_are_verified() {
+        let mut writer = crate::writer::FrameWriter::with_config(
+            Vec::new(),
+            FrameConfig {
+                checksum: true,
+                ..FrameConfig::default()
+            },
+        );
+        writer.send(1, b"corrupt me").unwrap();
+        writer.send(2, b"intact").unwrap();
+        let mut wire = writer.into_inner();
+        wire[VERSIONED_HEADER_SIZE] ^= 0xFF;
+
+        let mut reader = FrameReader::new(Cursor::new(wire));
+
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, FrameError::ChecksumMismatch { .. }), "{err}");
+        let frame = reader.read_frame().unwrap();
+        assert_eq!(
+            (frame.channel, frame.payload.as_ref()),
+            (2, b"intact".as_ref())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compressed_frames_roundtrip_through_writer_and_reader() {
+        let config = FrameConfig {
+            compression: crate::Compression::Zstd { level: 3 },
+            ..FrameConfig::default()
+        };
+        let blob = br#"{"key":"value","items":[1,2,3]}"#.repeat(4096);
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), config);
+        writer.send(2, &blob).unwrap();
+        writer.send(2, b"tiny").unwrap();
+        let wire = writer.into_inner();
+        assert_eq!(wire[..2], VERSIONED_MAGIC);
+        assert!(wire.len() < blob.len() / 10, "wire is {} bytes", wire.len());
+
+        let mut reader = FrameReader::new(Cursor::new(wire));
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), blob);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"tiny");
+    }
+
+    fn fragmenting_config() -> FrameConfig {
+        FrameConfig {
+            max_payload_size: 1024 * 1024,
+            max_fragment_size: Some(1024 * 1024),
+            max_message_size: 128 * 1024 * 1024,
+            ..FrameConfig::default()
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn fragmented_100_mib_payload_is_reassembled() {
+        let (left, right) = std::os::unix::net::UnixStream::pair().unwrap();
+        let payload: Vec<u8> = (0..100 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let payload = std::sync::Arc::new(payload);
+
+        let sent = payload.clone();
+        let sender = std::thread::spawn(move || {
+            let mut writer = crate::writer::FrameWriter::with_config(left, fragmenting_config());
+            writer.send(2, &sent).unwrap();
+            writer.send(1, b"after").unwrap();
+        });
+
+        let mut reader = FrameReader::with_config(right, fragmenting_config());
+        let frame = reader.read_frame().unwrap();
+        assert_eq!(frame.channel, 2);
+        assert!(frame.payload.as_ref() == payload.as_slice());
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"after");
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn reassembly_enforces_max_message_size() {
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), fragmenting_config());
+        writer.send(2, &vec![7u8; 3 * 1024 * 1024]).unwrap();
+        writer.send(2, b"small").unwrap();
+
+        let config = FrameConfig {
+            max_message_size: 2 * 1024 * 1024,
+            ..fragmenting_config()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTooLarge { .. }), "{err}");
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"small");
+    }
+
+    #[test]
+    fn missing_fragment_is_reported_and_skipped() {
+        let config = FrameConfig {
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(2, b"abcdefghijkl").unwrap();
+        writer.send(2, b"next").unwrap();
+        let mut wire = writer.into_inner();
+        // Cut out the middle fragment: header, fragment index, 4 bytes.
+        let fragment_len = VERSIONED_HEADER_SIZE + 4 + 4;
+        wire.drain(fragment_len..2 * fragment_len);
+
+        let mut reader = FrameReader::with_config(Cursor::new(wire), config);
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, FrameError::Fragmentation(_)), "{err}");
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"next");
+    }
+
+    #[test]
+    fn eof_inside_fragmented_message_is_truncation() {
+        let config = FrameConfig {
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(2, b"abcdefgh").unwrap();
+        let mut wire = writer.into_inner();
+        wire.truncate(VERSIONED_HEADER_SIZE + 4 + 4);
+
+        let mut reader = FrameReader::with_config(Cursor::new(wire), config);
+        let err = reader.read_frame().unwrap_err();
+        assert!(
+            matches!(err, FrameError::Truncated { buffered: 4 }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn sequence_gap_is_reported_and_the_frame_returned_next() {
+        let config = FrameConfig {
+            sequence_numbers: true,
+            ..FrameConfig::default()
+        };
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), config);
+        for payload in [b"aaaa", b"bbbb", b"cccc"] {
+            writer.send(1, payload).unwrap();
+        }
+        writer.send(2, b"dddd").unwrap();
+        let mut wire = writer.into_inner();
+        // Lose the second message on channel 1: header, sequence, 4 bytes.
+        let frame_len = VERSIONED_HEADER_SIZE + 4 + 4;
+        wire.drain(frame_len..2 * frame_len);
+
+        let mut reader = FrameReader::new(Cursor::new(wire));
+        assert_eq!(reader.last_sequence(1), None);
+        assert_eq!(reader.read_frame().unwrap().sequence, Some(0));
+        assert_eq!(reader.last_sequence(1), Some(0));
+
+        let err = reader.read_frame().unwrap_err();
+        assert!(
+            matches!(
+                err,
+                FrameError::SequenceGap {
+                    channel: 1,
+                    expected: 1,
+                    got: 2
+                }
+            ),
+            "{err}"
+        );
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 1);
+        assert_eq!(buf, b"cccc");
+        assert_eq!(reader.last_sequence(1), Some(2));
+
+        let frame = reader.read_frame().unwrap();
+        assert_eq!((frame.channel, frame.sequence), (2, Some(0)));
+    }
+
+    #[test]
+    fn peek_header_leaves_the_frame_unread() {
+        let mut wire = BytesMut::new();
+        encode_frame(7, b"first", &mut wire).unwrap();
+        encode_frame_with_checksum(9, &[1u8; 300], Priority(2), &mut wire).unwrap();
+
+        let mut reader = FrameReader::new(ByteByByteReader {
+            bytes: wire.to_vec(),
+            pos: 0,
+        });
+        let header = reader.peek_header().unwrap().unwrap();
+        assert_eq!(
+            header,
+            FrameHeader {
+                channel: 7,
+                payload_len: 5
+            }
+        );
+        assert_eq!(reader.buf.len(), HEADER_SIZE);
+        assert_eq!(reader.peek_header().unwrap(), Some(header));
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"first");
+
+        let header = reader.peek_header().unwrap().unwrap();
+        assert_eq!((header.channel, header.payload_len), (9, 300));
+        let mut buf = Vec::with_capacity(header.payload_len);
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 9);
+        assert_eq!(buf.len(), 300);
+
+        let err = reader.peek_header().unwrap_err();
+        assert!(matches!(err, FrameError::ConnectionClosed), "{err}");
+    }
+
+    #[test]
+    fn peek_header_validates_like_read_frame() {
+        let mut wire = BytesMut::new();
+        encode_frame(1, &[0u8; 64], &mut wire).unwrap();
+        let config = FrameConfig {
+            max_payload_size: 16,
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(wire.to_vec()), config);
+        let err = reader.peek_header().unwrap_err();
+        assert!(
+            matches!(err, FrameError::PayloadTooLarge { size: 64, .. }),
+            "{err}"
+        );
+
+        let mut reader = FrameReader::new(Cursor::new(b"XXXXXXXXXX".to_vec()));
+        let err = reader.peek_header().unwrap_err();
+        assert!(matches!(err, FrameError::InvalidMagic), "{err}");
+
+        let mut reader = FrameReader::new(Cursor::new(wire[..5].to_vec()));
+        let err = reader.peek_header().unwrap_err();
+        assert!(matches!(err, FrameError::ConnectionClosed), "{err}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn try_read_frame_drains_a_nonblocking_stream() {
+        use std::io::Write;
+
+        let dir =
+            std::env::temp_dir().join(format!("ipcprims-frame-try-read-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("test.sock");
+        let listener = ipcprims_transport::UnixDomainSocket::bind(&sock_path).unwrap();
+        let mut client = ipcprims_transport::UnixDomainSocket::connect(&sock_path).unwrap();
+        let server = listener.accept().unwrap();
+        server.set_nonblocking(true).unwrap();
+        let mut reader = FrameReader::new(server);
+
+        assert!(reader.try_read_frame().unwrap().is_none());
+
+        // Two frames from one write come back one per call.
+        let mut wire = BytesMut::new();
+        encode_frame(1, b"first", &mut wire).unwrap();
+        encode_frame(2, b"second", &mut wire).unwrap();
+        client.write_all(&wire).unwrap();
+        assert_eq!(reader.try_read_frame().unwrap().unwrap().channel, 1);
+        assert_eq!(reader.try_read_frame().unwrap().unwrap().channel, 2);
+        assert!(reader.try_read_frame().unwrap().is_none());
+
+        // A partial frame is kept until the rest arrives.
+        let mut wire = BytesMut::new();
+        encode_frame(3, b"split", &mut wire).unwrap();
+        client.write_all(&wire[..6]).unwrap();
+        assert!(reader.try_read_frame().unwrap().is_none());
+        client.write_all(&wire[6..]).unwrap();
+        let frame = reader.try_read_frame().unwrap().unwrap();
+        assert_eq!(
+            (frame.channel, frame.payload.as_ref()),
+            (3, b"split".as_ref())
+        );
+
+        // EOF mid-frame is an error, not "nothing yet", and reports the
+        // cut-off frame as read_frame does rather than ConnectionClosed.
+        client.write_all(&wire[..6]).unwrap();
+        drop(client);
+        assert!(matches!(
+            reader.try_read_frame().unwrap_err(),
+            FrameError::Truncated { buffered: 6 }
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn try_read_frame_returns_buffered_frames_without_reading() {
+        let mut wire = BytesMut::new();
+        encode_frame(1, b"a", &mut wire).unwrap();
+        encode_frame(2, b"b", &mut wire).unwrap();
+        let mut reader = FrameReader::new(ScriptedReader::new(wire.to_vec(), usize::MAX));
+
+        assert_eq!(reader.try_read_frame().unwrap().unwrap().channel, 1);
+        let reads = reader.get_ref().offered.len();
+        assert_eq!(reader.try_read_frame().unwrap().unwrap().channel, 2);
+        assert_eq!(reader.get_ref().offered.len(), reads);
+        assert!(matches!(
+            reader.try_read_frame().unwrap_err(),
+            FrameError::ConnectionClosed
+        ));
+    }
+
+    #[test]
+    fn read_frame_into_reuses_the_callers_buffer() {
+        let mut wire = BytesMut::new();
+        for i in 0..32u16 {
+            encode_frame(i, &vec![i as u8; 100 + usize::from(i)], &mut wire).unwrap();
+        }
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+
+        let mut buf = Vec::with_capacity(256);
+        let (ptr, capacity) = (buf.as_ptr(), buf.capacity());
+        for i in 0..32u16 {
+            assert_eq!(reader.read_frame_into(&mut buf).unwrap(), i);
+            assert_eq!(buf, vec![i as u8; 100 + usize::from(i)]);
+            assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, capacity));
+        }
+        assert!(matches!(
+            reader.read_frame_into(&mut buf).unwrap_err(),
+            FrameError::ConnectionClosed
+        ));
+    }
+
+    #[test]
+    fn read_frame_into_matches_read_frame() {
+        let mut wire = BytesMut::new();
+        encode_frame(4, b"slow", &mut wire).unwrap();
+        encode_frame_with_checksum(5, b"checked", Priority::NORMAL, &mut wire).unwrap();
+        let mut reader = FrameReader::new(ByteByByteReader {
+            bytes: wire.to_vec(),
+            pos: 0,
+        });
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 4);
+        assert_eq!(buf, b"slow");
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 5);
+        assert_eq!(buf, b"checked");
+
+        let mut partial = BytesMut::new();
+        partial.put_slice(&MAGIC);
+        partial.put_u32_le(16);
+        partial.put_u16_le(2);
+        partial.put_slice(b"only-part");
+        let mut reader = FrameReader::new(Cursor::new(partial.to_vec()));
+        let err = reader.read_frame_into(&mut buf).unwrap_err();
+        assert!(matches!(err, FrameError::Truncated { buffered } if buffered == partial.len()));
+        assert_eq!(buf, b"checked", "buffer is untouched on error");
+
+        let mut reader = FrameReader::new(Cursor::new(vec![0u8; 8]));
+        let err = reader.read_frame_into(&mut buf).unwrap_err();
+        assert!(matches!(err, FrameError::InvalidMagic));
+
+        let mut oversized = BytesMut::new();
+        oversized.put_slice(&MAGIC);
+        oversized.put_u32_le(1024);
+        oversized.put_u16_le(1);
+        let cfg = FrameConfig {
+            max_payload_size: 16,
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(oversized.to_vec()), cfg);
+        let err = reader.read_frame_into(&mut buf).unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTooLarge { .. }));
+    }
+
+    #[test]
+    fn read_frame_into_reassembles_fragments() {
+        let config = FrameConfig {
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        let mut writer = crate::writer::FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(2, b"abcdefghij").unwrap();
+        writer.send(3, b"next").unwrap();
+
+        let mut reader = FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 2);
+        assert_eq!(buf, b"abcdefghij");
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 3);
+        assert_eq!(buf, b"next");
+    }
+
     #[test]
     #[cfg(unix)]
     fn multi_channel_roundtrip() {
@@ -390,6 +1573,272 @@ mod tests {
         }
     }
 
+    /// Serves `bytes` in reads of at most `max_read` bytes, failing once with
+    /// `fail_kind` after `fail_at` bytes, and records each offered slice length.
+    struct ScriptedReader {
+        bytes: Vec<u8>,
+        pos: usize,
+        max_read: usize,
+        fail_at: Option<(usize, ErrorKind)>,
+        offered: Vec<usize>,
+    }
+
+    impl ScriptedReader {
+        fn new(bytes: Vec<u8>, max_read: usize) -> Self {
+            Self {
+                bytes,
+                pos: 0,
+                max_read,
+                fail_at: None,
+                offered: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for ScriptedReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.offered.push(buf.len());
+            if let Some((at, kind)) = self.fail_at {
+                if self.pos >= at {
+                    self.fail_at = None;
+                    return Err(std::io::Error::from(kind));
+                }
+            }
+            let limit = self.fail_at.map_or(self.bytes.len(), |(at, _)| at);
+            let n = (limit - self.pos).min(buf.len()).min(self.max_read);
+            buf[..n].copy_from_slice(&self.bytes[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn error_mid_frame_keeps_buffered_bytes() {
+        let payload: Vec<u8> = (0..5000u32).map(|i| i as u8).collect();
+        let mut wire = BytesMut::new();
+        encode_frame(6, &payload, &mut wire).unwrap();
+
+        for kind in [ErrorKind::WouldBlock, ErrorKind::Interrupted] {
+            let mut source = ScriptedReader::new(wire.to_vec(), usize::MAX);
+            source.fail_at = Some((100, kind));
+            let mut reader = FrameReader::new(source);
+
+            if kind == ErrorKind::WouldBlock {
+                let err = reader.read_frame().unwrap_err();
+                assert!(matches!(err, FrameError::Io(e) if e.kind() == kind));
+                assert_eq!(reader.buf.len(), 100);
+            }
+            let frame = reader.read_frame().unwrap();
+            assert_eq!(frame.channel, 6);
+            assert_eq!(frame.payload.as_ref(), payload.as_slice());
+        }
+    }
+
+    #[test]
+    fn reset_is_reported_like_eof() {
+        let mut wire = BytesMut::new();
+        encode_frame(2, b"first", &mut wire).unwrap();
+        let frame_len = wire.len();
+        encode_frame(2, b"second", &mut wire).unwrap();
+
+        for kind in [
+            ErrorKind::BrokenPipe,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+        ] {
+            let mut source = ScriptedReader::new(wire.to_vec(), usize::MAX);
+            source.fail_at = Some((frame_len, kind));
+            let mut reader = FrameReader::new(source);
+            assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"first");
+            assert!(matches!(
+                reader.read_frame(),
+                Err(FrameError::ConnectionClosed)
+            ));
+
+            let mut source = ScriptedReader::new(wire.to_vec(), usize::MAX);
+            source.fail_at = Some((frame_len + 3, kind));
+            let mut reader = FrameReader::new(source);
+            reader.read_frame().unwrap();
+            assert!(matches!(
+                reader.read_frame(),
+                Err(FrameError::Truncated { buffered: 3 })
+            ));
+        }
+    }
+
+    #[test]
+    fn large_frame_over_short_reads() {
+        let big: Vec<u8> = (0..200 * 1024u32).map(|i| (i % 253) as u8).collect();
+        let mut wire = BytesMut::new();
+        encode_frame(2, &big, &mut wire).unwrap();
+        encode_frame(3, b"tail", &mut wire).unwrap();
+
+        let mut reader = FrameReader::new(ScriptedReader::new(wire.to_vec(), 1000));
+        assert_eq!(
+            reader.read_frame().unwrap().payload.as_ref(),
+            big.as_slice()
+        );
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"tail");
+        assert!(matches!(
+            reader.read_frame(),
+            Err(FrameError::ConnectionClosed)
+        ));
+    }
+
+    #[test]
+    fn read_size_bounded_by_received_bytes() {
+        // A header announcing a huge payload must not make the reader
+        // allocate it up front.
+        let mut wire = BytesMut::new();
+        wire.put_slice(&MAGIC);
+        wire.put_u32_le(8 * 1024 * 1024);
+        wire.put_u16_le(1);
+        wire.put_slice(&[7u8; 1000]);
+
+        let mut reader = FrameReader::new(ScriptedReader::new(wire.to_vec(), usize::MAX));
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, FrameError::Truncated { buffered } if buffered == wire.len()));
+        assert!(reader.buf.capacity() <= wire.len() + MAX_READ_CHUNK_SIZE);
+
+        let offered = &reader.get_ref().offered;
+        assert_eq!(offered[0], READ_CHUNK_SIZE);
+        assert!(offered
+            .iter()
+            .all(|&len| (READ_CHUNK_SIZE..=MAX_READ_CHUNK_SIZE).contains(&len)));
+    }
+
+    /// One 8 MiB frame inflates the buffer; the 10k tiny frames after it are
+    /// copied out, so none of them pins that allocation, and the reader drops
+    /// back to a small buffer instead of slicing them from the big one.
+    #[test]
+    fn buffer_shrinks_after_large_frame() {
+        const TINY_FRAMES: u32 = 10_000;
+        let mut wire = BytesMut::new();
+        encode_frame(2, &vec![1u8; 8 * 1024 * 1024], &mut wire).unwrap();
+        for i in 0..TINY_FRAMES {
+            encode_frame(3, &i.to_le_bytes(), &mut wire).unwrap();
+        }
+
+        let mut reader = FrameReader::new(Cursor::new(wire.to_vec()));
+        let big = reader.read_frame().unwrap();
+        assert_eq!(big.payload.len(), 8 * 1024 * 1024);
+        assert!(reader.buf.capacity() <= MAX_READ_CHUNK_SIZE);
+
+        let mut held = Vec::new();
+        let mut peak = 0;
+        for i in 0..TINY_FRAMES {
+            let frame = reader.read_frame().unwrap();
+            assert_eq!(frame.payload.as_ref(), i.to_le_bytes());
+            peak = peak.max(reader.buf.capacity());
+            held.push(frame);
+        }
+        assert!(peak <= MAX_READ_CHUNK_SIZE, "peak capacity {peak}");
+        assert!(reader.buf.capacity() <= 2 * INITIAL_BUFFER_CAPACITY);
+        assert!(held.iter().all(|frame| frame.payload.is_unique()));
+        assert!(big.payload.is_unique());
+    }
+
+    #[test]
+    fn buffer_shrink_threshold_reclaims_capacity() {
+        let mut wire = BytesMut::new();
+        encode_frame(2, &vec![1u8; 2 * 1024 * 1024], &mut wire).unwrap();
+        for i in 0..3u32 {
+            encode_frame(3, &i.to_le_bytes(), &mut wire).unwrap();
+        }
+        let capacity_after = |buffer_shrink_threshold| {
+            let config = FrameConfig {
+                buffer_shrink_threshold,
+                ..FrameConfig::default()
+            };
+            let mut reader = FrameReader::with_config(Cursor::new(wire.to_vec()), config);
+            let mut buf = Vec::new();
+            assert_eq!(reader.read_frame_into(&mut buf).unwrap(), 2);
+            for _ in 0..3 {
+                reader.read_frame().unwrap();
+            }
+            reader.buffer_capacity()
+        };
+
+        let kept = capacity_after(usize::MAX);
+        assert!(kept > crate::DEFAULT_BUFFER_SHRINK_THRESHOLD, "kept {kept}");
+        let shrunk = capacity_after(crate::DEFAULT_BUFFER_SHRINK_THRESHOLD);
+        assert!(shrunk <= INITIAL_BUFFER_CAPACITY, "shrunk to {shrunk}");
+    }
+
+    #[test]
+    fn payloads_above_threshold_share_the_buffer() {
+        let mut wire = BytesMut::new();
+        encode_frame(2, &[9u8; 64], &mut wire).unwrap();
+        encode_frame(2, &[9u8; 64], &mut wire).unwrap();
+
+        let config = FrameConfig {
+            copy_out_threshold: 16,
+            ..FrameConfig::default()
+        };
+        let mut reader = FrameReader::with_config(Cursor::new(wire.to_vec()), config);
+        let first = reader.read_frame().unwrap();
+        assert!(!first.payload.is_unique());
+        drop(reader);
+        assert!(first.payload.is_unique());
+    }
+
+    /// Compare against the previous copy-through-a-stack-chunk loop.
+    /// Run with `cargo test -p ipcprims-frame --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing comparison; run manually in release mode"]
+    fn large_payload_throughput_vs_copying_reader() {
+        const FRAMES: usize = 64;
+        let payload = vec![0x5A; 1024 * 1024];
+        let mut wire = BytesMut::new();
+        for _ in 0..FRAMES {
+            encode_frame(2, &payload, &mut wire).unwrap();
+        }
+        let wire = wire.to_vec();
+
+        fn copying_read_frame(src: &mut Cursor<&[u8]>, buf: &mut BytesMut) -> Frame {
+            loop {
+                if let Some(frame) = decode_frame(buf, usize::MAX).unwrap() {
+                    return frame;
+                }
+                let mut chunk = [0u8; READ_CHUNK_SIZE];
+                let read = src.read(&mut chunk).unwrap();
+                assert!(read > 0);
+                buf.extend_from_slice(&chunk[..read]);
+            }
+        }
+
+        let best_of = |run: &dyn Fn()| {
+            (0..5)
+                .map(|_| {
+                    let start = std::time::Instant::now();
+                    run();
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+
+        let copying = best_of(&|| {
+            let mut src = Cursor::new(wire.as_slice());
+            let mut buf = BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY);
+            for _ in 0..FRAMES {
+                std::hint::black_box(copying_read_frame(&mut src, &mut buf));
+            }
+        });
+        let in_place = best_of(&|| {
+            let mut reader = FrameReader::new(Cursor::new(wire.as_slice()));
+            for _ in 0..FRAMES {
+                std::hint::black_box(reader.read_frame().unwrap());
+            }
+        });
+
+        println!("copying reader: {copying:?}, in-place reader: {in_place:?}");
+        assert!(
+            in_place <= copying,
+            "in-place reads should not be slower ({in_place:?} vs {copying:?})"
+        );
+    }
+
     #[test]
     #[cfg(unix)]
     fn applies_read_timeout_for_ipc_stream() {
@@ -445,4 +1894,39 @@ mod tests {
         server.join().unwrap();
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn roundtrip_over_rewrapped_accepted_fd() {
+        use std::os::fd::AsFd;
+
+        let dir =
+            std::env::temp_dir().join(format!("ipcprims-frame-uds-rewrap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("test.sock");
+        let listener = ipcprims_transport::UnixDomainSocket::bind(&sock_path).unwrap();
+
+        let path_clone = sock_path.clone();
+        let server = std::thread::spawn(move || {
+            let accepted = listener.accept().unwrap();
+            let fd = accepted.as_fd().try_clone_to_owned().unwrap();
+            drop(accepted);
+            let stream = IpcStream::from_owned_fd(fd).unwrap();
+            let mut writer = crate::writer::FrameWriter::new(stream.try_clone().unwrap());
+            let mut reader = FrameReader::new(stream);
+            let frame = reader.read_frame().unwrap();
+            writer.send(frame.channel, &frame.payload).unwrap();
+        });
+
+        let stream = ipcprims_transport::UnixDomainSocket::connect(&path_clone).unwrap();
+        let mut writer = crate::writer::FrameWriter::new(stream.try_clone().unwrap());
+        let mut reader = FrameReader::new(stream);
+        writer.send(12, b"rewrapped").unwrap();
+        let echoed = reader.read_frame().unwrap();
+        assert_eq!(echoed.channel, 12);
+        assert_eq!(echoed.payload.as_ref(), b"rewrapped");
+
+        server.join().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,110 @@
+//! Per-channel message sequence numbers and gap detection.
+
+use std::collections::HashMap;
+
+use crate::codec::Frame;
+use crate::error::{FrameError, Result};
+
+/// Hands out the next sequence number for each channel, starting at 0 and
+/// wrapping after `u32::MAX`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Sequencer {
+    next: HashMap<u16, u32>,
+}
+
+impl Sequencer {
+    /// The number the next message on `channel` gets.
+    pub(crate) fn peek(&self, channel: u16) -> u32 {
+        self.next.get(&channel).copied().unwrap_or(0)
+    }
+
+    /// Use up `channel`'s next number and return it.
+    pub(crate) fn next(&mut self, channel: u16) -> u32 {
+        let next = self.next.entry(channel).or_insert(0);
+        let sequence = *next;
+        *next = sequence.wrapping_add(1);
+        sequence
+    }
+}
+
+/// Remembers the last sequence number seen on each channel.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SequenceTracker {
+    last: HashMap<u16, u32>,
+}
+
+impl SequenceTracker {
+    pub(crate) fn last(&self, channel: u16) -> Option<u32> {
+        self.last.get(&channel).copied()
+    }
+
+    /// Record `frame`'s sequence number, if it has one.
+    ///
+    /// Fails with [`FrameError::SequenceGap`] when it does not follow the
+    /// last one seen on the channel; the new number is recorded either way,
+    /// so one lost message is reported once.
+    pub(crate) fn observe(&mut self, frame: &Frame) -> Result<()> {
+        let Some(got) = frame.sequence else {
+            return Ok(());
+        };
+        match self.last.insert(frame.channel, got) {
+            Some(last) if got != last.wrapping_add(1) => Err(FrameError::SequenceGap {
+                channel: frame.channel,
+                expected: last.wrapping_add(1),
+                got,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamped(channel: u16, sequence: u32) -> Frame {
+        Frame {
+            sequence: Some(sequence),
+            ..Frame::new(channel, "x")
+        }
+    }
+
+    #[test]
+    fn counts_per_channel_and_wraps() {
+        let mut sequencer = Sequencer::default();
+        assert_eq!(
+            (sequencer.next(1), sequencer.next(1), sequencer.next(2)),
+            (0, 1, 0)
+        );
+        assert_eq!(sequencer.peek(1), 2);
+
+        sequencer.next.insert(3, u32::MAX);
+        assert_eq!((sequencer.next(3), sequencer.next(3)), (u32::MAX, 0));
+    }
+
+    #[test]
+    fn reports_gaps_once_and_accepts_wraparound() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(&stamped(1, 7)).unwrap();
+        tracker.observe(&stamped(1, 8)).unwrap();
+        tracker.observe(&stamped(2, 0)).unwrap();
+        tracker.observe(&Frame::new(1, "unstamped")).unwrap();
+
+        let err = tracker.observe(&stamped(1, 11)).unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::SequenceGap {
+                channel: 1,
+                expected: 9,
+                got: 11
+            }
+        ));
+        tracker.observe(&stamped(1, 12)).unwrap();
+        assert_eq!(tracker.last(1), Some(12));
+
+        tracker.observe(&stamped(2, u32::MAX)).unwrap_err();
+        tracker.observe(&stamped(2, 0)).unwrap();
+        assert_eq!(tracker.last(2), Some(0));
+        assert_eq!(tracker.last(3), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! Frame and byte counters kept by readers and writers.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Frames and payload bytes moved by a [`FrameReader`](crate::FrameReader)
+/// or [`FrameWriter`](crate::FrameWriter), kept when
+/// [`FrameConfig::collect_stats`](crate::FrameConfig::collect_stats) is set.
+///
+/// A frame is counted once per message: a fragmented message counts as one
+/// frame of its full length. Bytes are payload bytes as the caller sees
+/// them, before compression and without headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Messages written.
+    pub frames_sent: u64,
+    /// Messages read.
+    pub frames_received: u64,
+    /// Payload bytes written.
+    pub bytes_sent: u64,
+    /// Payload bytes read.
+    pub bytes_received: u64,
+    /// The same counters split by channel.
+    pub channels: HashMap<u16, ChannelStats>,
+    /// When a message was last read or written.
+    pub last_activity: Option<Instant>,
+}
+
+/// Per-channel counters in [`FrameStats::channels`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// Messages written on the channel.
+    pub frames_sent: u64,
+    /// Messages read on the channel.
+    pub frames_received: u64,
+    /// Payload bytes written on the channel.
+    pub bytes_sent: u64,
+    /// Payload bytes read on the channel.
+    pub bytes_received: u64,
+}
+
+impl FrameStats {
+    /// Zero every counter and forget the last activity.
+    pub fn reset(&mut self) {
+        *self = FrameStats::default();
+    }
+
+    /// Add `other`'s counters to these, keeping the later activity time.
+    pub fn merge(&mut self, other: &FrameStats) {
+        self.frames_sent += other.frames_sent;
+        self.frames_received += other.frames_received;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        for (&channel, theirs) in &other.channels {
+            let ours = self.channels.entry(channel).or_default();
+            ours.frames_sent += theirs.frames_sent;
+            ours.frames_received += theirs.frames_received;
+            ours.bytes_sent += theirs.bytes_sent;
+            ours.bytes_received += theirs.bytes_received;
+        }
+        self.last_activity = self.last_activity.max(other.last_activity);
+    }
+
+    pub(crate) fn record_sent(&mut self, channel: u16, len: usize) {
+        self.frames_sent += 1;
+        self.bytes_sent += len as u64;
+        let channel = self.channels.entry(channel).or_default();
+        channel.frames_sent += 1;
+        channel.bytes_sent += len as u64;
+        self.last_activity = Some(Instant::now());
+    }
+
+    pub(crate) fn record_received(&mut self, channel: u16, len: usize) {
+        self.frames_received += 1;
+        self.bytes_received += len as u64;
+        let channel = self.channels.entry(channel).or_default();
+        channel.frames_received += 1;
+        channel.bytes_received += len as u64;
+        self.last_activity = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{FrameConfig, FrameReader, FrameWriter, COMMAND, DATA, TELEMETRY};
+
+    #[test]
+    fn counts_frames_and_bytes_per_channel() {
+        let config = FrameConfig {
+            collect_stats: true,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(COMMAND, b"ping").unwrap();
+        writer.send(DATA, &[0u8; 100]).unwrap();
+        writer.send(DATA, &[0u8; 50]).unwrap();
+        writer
+            .send_batch(&[(TELEMETRY, b"t1"), (TELEMETRY, b"t22")])
+            .unwrap();
+
+        let sent = writer.stats().unwrap().clone();
+        assert_eq!((sent.frames_sent, sent.bytes_sent), (5, 159));
+        assert_eq!((sent.frames_received, sent.bytes_received), (0, 0));
+        assert_eq!(sent.channels.len(), 3);
+        assert_eq!(sent.channels[&DATA].frames_sent, 2);
+        assert_eq!(sent.channels[&DATA].bytes_sent, 150);
+        assert_eq!(sent.channels[&TELEMETRY].bytes_sent, 5);
+        assert!(sent.last_activity.is_some());
+
+        let bytes = writer.into_inner();
+        let mut reader = FrameReader::with_config(Cursor::new(bytes.clone()), config);
+        for _ in 0..4 {
+            reader.read_frame().unwrap();
+        }
+        let mut buf = Vec::new();
+        reader.read_frame_into(&mut buf).unwrap();
+
+        let received = reader.stats().unwrap();
+        assert_eq!(
+            (received.frames_received, received.bytes_received),
+            (5, 159)
+        );
+        assert_eq!(received.channels[&COMMAND].frames_received, 1);
+        assert_eq!(received.channels[&COMMAND].bytes_received, 4);
+        assert_eq!(received.channels[&DATA].bytes_received, 150);
+
+        let mut merged = sent.clone();
+        merged.merge(received);
+        assert_eq!(merged.channels[&DATA].frames_sent, 2);
+        assert_eq!(merged.channels[&DATA].frames_received, 2);
+
+        reader.reset_stats();
+        assert_eq!(reader.stats().unwrap().frames_received, 0);
+        assert!(reader.stats().unwrap().channels.is_empty());
+
+        let mut plain = FrameReader::new(Cursor::new(bytes));
+        plain.read_frame().unwrap();
+        assert!(plain.stats().is_none());
+    }
+
+    #[test]
+    fn fragmented_message_counts_once() {
+        let config = FrameConfig {
+            collect_stats: true,
+            max_fragment_size: Some(16),
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(DATA, &[7u8; 40]).unwrap();
+        assert_eq!(writer.stats().unwrap().frames_sent, 1);
+
+        let mut reader = FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        reader.read_frame().unwrap();
+        let stats = reader.stats().unwrap();
+        assert_eq!((stats.frames_received, stats.bytes_received), (1, 40));
+    }
+}
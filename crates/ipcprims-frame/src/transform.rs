@@ -0,0 +1,284 @@
+//! Payload transforms (encryption, signing, ...) applied around the wire.
+
+use std::sync::Arc;
+
+use crate::channel::{CONTROL, HEARTBEAT};
+use crate::codec::Frame;
+use crate::error::{FrameError, Result};
+
+/// Transforms payloads symmetrically: a [`FrameWriter`](crate::FrameWriter)
+/// seals each message before it is compressed or fragmented, and a
+/// [`FrameReader`](crate::FrameReader) opens it once it is whole again.
+/// Set one on both ends with their `set_payload_codec`.
+///
+/// Size limits apply to the plaintext: writers check `max_payload_size` (or
+/// `max_message_size`) before sealing, and readers accept up to
+/// [`overhead`](Self::overhead) more bytes on the wire. Stats and
+/// [`FrameObserver`](crate::FrameObserver)s see plaintext lengths.
+///
+/// Heartbeats are never transformed. Other channels are unless
+/// [`applies_to`](Self::applies_to) says otherwise; by default CONTROL is
+/// left alone, so the handshake and control messages stay readable to a
+/// remote that has not set up its keys yet.
+pub trait PayloadCodec: Send + Sync {
+    /// Transform an outgoing `payload` on `channel`.
+    fn seal(&self, channel: u16, payload: &[u8]) -> Result<Vec<u8>>;
+
+    /// Undo [`seal`](Self::seal) on a received payload. Failures should be
+    /// [`FrameError::PayloadTransform`].
+    fn open(&self, channel: u16, sealed: &[u8]) -> Result<Vec<u8>>;
+
+    /// Most bytes `seal` adds to a payload (default 0).
+    fn overhead(&self) -> usize {
+        0
+    }
+
+    /// Whether payloads on `channel` go through this codec (default: every
+    /// channel but [`CONTROL`]).
+    fn applies_to(&self, channel: u16) -> bool {
+        channel != CONTROL
+    }
+}
+
+/// The codec, if any, that transforms payloads on `channel`.
+pub(crate) fn codec_for(
+    codec: Option<&Arc<dyn PayloadCodec>>,
+    channel: u16,
+) -> Option<&dyn PayloadCodec> {
+    codec
+        .map(|codec| codec.as_ref())
+        .filter(|codec| channel != HEARTBEAT && codec.applies_to(channel))
+}
+
+/// Open `frame`'s payload with `codec`, rejecting plaintext over `max`.
+pub(crate) fn open_frame(codec: &dyn PayloadCodec, frame: Frame, max: usize) -> Result<Frame> {
+    let payload = codec.open(frame.channel, &frame.payload)?;
+    if payload.len() > max {
+        return Err(FrameError::PayloadTransform(format!(
+            "opened payload of {} bytes exceeds the limit of {max}",
+            payload.len()
+        )));
+    }
+    Ok(Frame {
+        payload: payload.into(),
+        ..frame
+    })
+}
+
+#[cfg(feature = "crypto")]
+pub use self::crypto::ChaCha20Poly1305Codec;
+
+#[cfg(feature = "crypto")]
+mod crypto {
+    use std::fmt;
+
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+    use super::PayloadCodec;
+    use crate::error::{FrameError, Result};
+
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    /// ChaCha20-Poly1305 with a 256-bit key shared out of band (`crypto`
+    /// feature).
+    ///
+    /// Each sealed payload is a random 96-bit nonce, then the ciphertext and
+    /// its 16-byte tag; the channel ID is authenticated as associated data,
+    /// so a payload moved to another channel fails to open. Random nonces
+    /// are safe for about 2^32 messages per key, so rotate keys well before
+    /// that.
+    ///
+    /// Only the payload and channel ID are authenticated. Replay is not
+    /// protected against: a recorded payload opens again on the same
+    /// channel, and frame sequence numbers do not help, since they travel in
+    /// the unauthenticated header and can be rewritten. Neither is
+    /// reflection: with one key for both directions, a payload can be sent
+    /// back to the peer that sealed it.
+    #[derive(Clone)]
+    pub struct ChaCha20Poly1305Codec {
+        cipher: ChaCha20Poly1305,
+    }
+
+    impl ChaCha20Poly1305Codec {
+        /// Codec keyed with `key`.
+        pub fn new(key: &[u8; 32]) -> Self {
+            Self {
+                cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            }
+        }
+    }
+
+    impl fmt::Debug for ChaCha20Poly1305Codec {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("ChaCha20Poly1305Codec")
+                .field("key", &"<redacted>")
+                .finish()
+        }
+    }
+
+    impl PayloadCodec for ChaCha20Poly1305Codec {
+        fn seal(&self, channel: u16, payload: &[u8]) -> Result<Vec<u8>> {
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let aad = channel.to_le_bytes();
+            let ciphertext = self
+                .cipher
+                .encrypt(
+                    &nonce,
+                    Payload {
+                        msg: payload,
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| FrameError::PayloadTransform("encryption failed".to_string()))?;
+            let mut sealed = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+            sealed.extend_from_slice(&nonce);
+            sealed.extend_from_slice(&ciphertext);
+            Ok(sealed)
+        }
+
+        fn open(&self, channel: u16, sealed: &[u8]) -> Result<Vec<u8>> {
+            if sealed.len() < NONCE_SIZE + TAG_SIZE {
+                return Err(FrameError::PayloadTransform(format!(
+                    "sealed payload of {} bytes is shorter than nonce and tag",
+                    sealed.len()
+                )));
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+            let aad = channel.to_le_bytes();
+            self.cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| {
+                    FrameError::PayloadTransform(format!(
+                        "payload on channel {channel} failed authentication"
+                    ))
+                })
+        }
+
+        fn overhead(&self) -> usize {
+            NONCE_SIZE + TAG_SIZE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{FrameConfig, FrameReader, FrameWriter, COMMAND, DATA};
+
+    /// Reverses payloads and appends a marker byte.
+    struct Reverse;
+
+    impl PayloadCodec for Reverse {
+        fn seal(&self, _channel: u16, payload: &[u8]) -> Result<Vec<u8>> {
+            let mut sealed: Vec<u8> = payload.iter().rev().copied().collect();
+            sealed.push(0xAA);
+            Ok(sealed)
+        }
+
+        fn open(&self, _channel: u16, sealed: &[u8]) -> Result<Vec<u8>> {
+            match sealed.split_last() {
+                Some((0xAA, rest)) => Ok(rest.iter().rev().copied().collect()),
+                _ => Err(FrameError::PayloadTransform("missing marker".to_string())),
+            }
+        }
+
+        fn overhead(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn payloads_are_sealed_on_the_wire_and_opened_on_read() {
+        let config = FrameConfig {
+            max_payload_size: 4,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.set_payload_codec(Arc::new(Reverse));
+        writer.send(DATA, b"abcd").unwrap();
+        writer.send(CONTROL, b"ctl").unwrap();
+        writer.send_heartbeat().unwrap();
+        let err = writer.send(DATA, b"abcde").unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::PayloadTooLarge { size: 5, max: 4 }
+        ));
+
+        let wire = writer.into_inner();
+        assert_eq!(&wire[8..13], b"dcba\xAA");
+        assert_eq!(&wire[21..24], b"ctl");
+
+        let mut reader = FrameReader::with_config(Cursor::new(wire), config);
+        reader.set_payload_codec(Arc::new(Reverse));
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"abcd");
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_frame_into(&mut buf).unwrap(), CONTROL);
+        assert_eq!(buf, b"ctl");
+        let heartbeat = reader.read_frame().unwrap();
+        assert!(heartbeat.payload.is_empty());
+    }
+
+    #[test]
+    fn open_failure_drops_only_that_frame() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send(COMMAND, b"plain").unwrap();
+        writer.set_payload_codec(Arc::new(Reverse));
+        writer.send(COMMAND, b"sealed").unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(writer.into_inner()));
+        reader.set_payload_codec(Arc::new(Reverse));
+        let err = reader.read_frame().unwrap_err();
+        assert!(matches!(err, FrameError::PayloadTransform(_)), "{err}");
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"sealed");
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn chacha20poly1305_roundtrips_and_authenticates() {
+        let codec = ChaCha20Poly1305Codec::new(&[7; 32]);
+        let sealed = codec.seal(DATA, b"secret").unwrap();
+        assert_eq!(sealed.len(), b"secret".len() + codec.overhead());
+        assert!(!sealed.windows(6).any(|window| window == b"secret"));
+        assert_eq!(codec.open(DATA, &sealed).unwrap(), b"secret");
+        assert_ne!(codec.seal(DATA, b"secret").unwrap(), sealed);
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(codec.open(DATA, &tampered).is_err());
+        assert!(codec.open(COMMAND, &sealed).is_err());
+        assert!(ChaCha20Poly1305Codec::new(&[8; 32])
+            .open(DATA, &sealed)
+            .is_err());
+        assert!(codec.open(DATA, &sealed[..20]).is_err());
+        assert!(!format!("{codec:?}").contains('7'));
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn chacha20poly1305_over_fragmented_frames() {
+        let config = FrameConfig {
+            max_fragment_size: Some(64),
+            max_message_size: 1024,
+            ..FrameConfig::default()
+        };
+        let codec = Arc::new(ChaCha20Poly1305Codec::new(&[1; 32]));
+        let payload = vec![0x5A; 1024];
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.set_payload_codec(codec.clone());
+        writer.send(DATA, &payload).unwrap();
+
+        let mut reader = FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        reader.set_payload_codec(codec);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), &payload[..]);
+    }
+}
@@ -1,18 +1,114 @@
-use std::io::{ErrorKind, Write};
+use std::io::{ErrorKind, IoSlice, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use bytes::BytesMut;
 use ipcprims_transport::IpcStream;
 
-use crate::codec::{encode_frame, Frame, FrameConfig};
-use crate::error::{FrameError, Result};
+use crate::channel::HEARTBEAT;
+use crate::codec::{
+    encode_frame, encode_header, encode_message, Frame, FrameConfig, Priority, WireVersion,
+};
+use crate::error::{is_disconnect, FrameError, Result};
+use crate::observer::{notify, notify_error, FrameObserver};
+use crate::pool::{BufferPool, PooledBuffer};
+use crate::sequence::Sequencer;
+use crate::stats::FrameStats;
+use crate::transform::{codec_for, PayloadCodec};
 
 const INITIAL_BUFFER_CAPACITY: usize = 8 * 1024;
 
+/// Most slices handed to one vectored write; the rest follow in later ones.
+const MAX_WRITE_PARTS: usize = 64;
+
+/// First and largest sleep between `WouldBlock` retries on generic sinks.
+const MIN_BACKOFF: Duration = Duration::from_micros(50);
+const MAX_BACKOFF: Duration = Duration::from_millis(10);
+
 /// Writes complete frames to any `Write` stream.
+///
+/// When the stream reports `WouldBlock`, the writer waits rather than
+/// retrying immediately: `IpcStream` writers created with
+/// [`with_config_ipc`](FrameWriter::with_config_ipc) poll for writability on
+/// Unix, and other sinks sleep with a capped exponential backoff. If
+/// `write_timeout` is set and no bytes are accepted for that long, the write
+/// fails with [`FrameError::Timeout`].
+///
+/// A write that fails after part of a frame reached the stream poisons the
+/// writer: later sends fail with [`FrameError::Poisoned`].
 pub struct FrameWriter<T> {
     inner: T,
-    buf: BytesMut,
+    buf: PooledBuffer,
     config: FrameConfig,
+    wait: WriteWait<T>,
+    poisoned: bool,
+    sequencer: Sequencer,
+    stats: Option<FrameStats>,
+    observer: Option<Arc<dyn FrameObserver>>,
+    payload_codec: Option<Arc<dyn PayloadCodec>>,
+}
+
+/// A [`FrameWriter::send_batch`] failure: the frame it stopped at and why.
+#[derive(Debug, thiserror::Error)]
+#[error("frame {index} of batch: {source}")]
+pub struct BatchError {
+    /// Index into the batch of the frame that failed.
+    pub index: usize,
+    /// The underlying error.
+    #[source]
+    pub source: FrameError,
+}
+
+/// How the writer waits for a stalled stream.
+#[cfg_attr(not(unix), allow(dead_code))]
+enum WriteWait<T> {
+    Backoff,
+    /// Block until writable or the timeout elapses.
+    Ready(fn(&T, Option<Duration>) -> std::io::Result<bool>),
+}
+
+/// One stretch of `WouldBlock` retries without forward progress.
+struct Stall {
+    since: Instant,
+    backoff: Duration,
+}
+
+impl Stall {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            backoff: MIN_BACKOFF,
+        }
+    }
+}
+
+impl<T> WriteWait<T> {
+    /// Wait after `WouldBlock`, or fail once the stall outlasts `write_timeout`.
+    fn wait(&self, inner: &T, write_timeout: Option<Duration>, stall: &mut Stall) -> Result<()> {
+        let remaining = match write_timeout {
+            Some(timeout) => {
+                let elapsed = stall.since.elapsed();
+                if elapsed >= timeout {
+                    return Err(FrameError::Timeout(timeout));
+                }
+                Some(timeout - elapsed)
+            }
+            None => None,
+        };
+
+        match self {
+            // A timed-out poll falls through; the retried write hits the
+            // deadline check above.
+            WriteWait::Ready(wait) => {
+                wait(inner, remaining)?;
+            }
+            WriteWait::Backoff => {
+                let delay = remaining.map_or(stall.backoff, |left| left.min(stall.backoff));
+                std::thread::sleep(delay);
+                stall.backoff = (stall.backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<T: Write> FrameWriter<T> {
@@ -25,49 +121,337 @@ impl<T: Write> FrameWriter<T> {
     pub fn with_config(inner: T, config: FrameConfig) -> Self {
         Self {
             inner,
-            buf: BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY),
+            buf: PooledBuffer::unpooled(INITIAL_BUFFER_CAPACITY),
+            stats: config.collect_stats.then(FrameStats::default),
+            config,
+            wait: WriteWait::Backoff,
+            poisoned: false,
+            sequencer: Sequencer::default(),
+            observer: None,
+            payload_codec: None,
+        }
+    }
+
+    /// Create a frame writer whose encode buffer is borrowed from `pool` and
+    /// returned when the writer is dropped.
+    pub fn with_pool(inner: T, config: FrameConfig, pool: BufferPool) -> Self {
+        Self {
+            inner,
+            buf: PooledBuffer::from_pool(pool),
+            stats: config.collect_stats.then(FrameStats::default),
             config,
+            wait: WriteWait::Backoff,
+            poisoned: false,
+            sequencer: Sequencer::default(),
+            observer: None,
+            payload_codec: None,
         }
     }
 
+    /// Move this writer's buffer into `pool` (e.g. after a handshake).
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.buf.attach(pool);
+    }
+
     /// Write a complete frame (blocking).
     pub fn write_frame(&mut self, frame: &Frame) -> Result<()> {
-        self.send(frame.channel, frame.payload.as_ref())
+        self.send_with_priority(frame.channel, frame.payload.as_ref(), frame.priority)
     }
 
     /// Encode and send a payload on a channel.
+    ///
+    /// Returns `Err(FrameError::ConnectionClosed)` if the remote end has gone
+    /// away (broken pipe, reset or aborted connection).
     pub fn send(&mut self, channel: u16, payload: &[u8]) -> Result<()> {
-        if payload.len() > self.config.max_payload_size {
-            return Err(FrameError::PayloadTooLarge {
-                size: payload.len(),
-                max: self.config.max_payload_size,
+        self.send_with_priority(channel, payload, Priority::NORMAL)
+    }
+
+    /// [`send`](Self::send) with a delivery priority.
+    ///
+    /// Any priority above [`Priority::NORMAL`] uses the versioned header; only send one to peers
+    /// known to decode it. With [`FrameConfig::checksum`] set, every frame
+    /// carries a CRC-32 of its payload in that header. With [`FrameConfig::compression`]
+    /// set, payloads of at least `compression_threshold` bytes that shrink
+    /// are sent compressed. With [`FrameConfig::max_fragment_size`] set,
+    /// payloads up to `max_message_size` are accepted and larger ones go out
+    /// as several fragments, written back to back with a single flush. With
+    /// [`FrameConfig::sequence_numbers`] set, each message takes the next
+    /// sequence number on its channel, shared by all of its fragments.
+    /// With a [payload codec](Self::set_payload_codec) set, the payload is
+    /// sealed before any of that, and size limits apply to the plaintext.
+    ///
+    /// Payloads sent as they are skip the encode buffer: the header and the
+    /// payload go out together with vectored writes.
+    pub fn send_with_priority(
+        &mut self,
+        channel: u16,
+        payload: &[u8],
+        priority: Priority,
+    ) -> Result<()> {
+        let result = self.send_message(channel, &[payload], priority);
+        notify_error(self.observer.as_ref(), &result);
+        result
+    }
+
+    /// [`send`](Self::send) a payload made of `parts` joined end to end,
+    /// without joining them first.
+    ///
+    /// The total length is checked against `max_payload_size`, then the
+    /// header and every part go out with vectored writes; the receiver sees
+    /// one contiguous payload. No parts, or only empty ones, send an empty
+    /// payload. A payload that may be compressed or fragmented (see
+    /// [`send_with_priority`](Self::send_with_priority)) is still joined
+    /// into the encode buffer first.
+    pub fn send_vectored(&mut self, channel: u16, parts: &[&[u8]]) -> Result<()> {
+        let result = self.send_message(channel, parts, Priority::NORMAL);
+        notify_error(self.observer.as_ref(), &result);
+        result
+    }
+
+    fn send_message(&mut self, channel: u16, parts: &[&[u8]], priority: Priority) -> Result<()> {
+        if self.poisoned {
+            return Err(FrameError::Poisoned);
+        }
+        let len = parts.iter().map(|part| part.len()).sum();
+        self.check_payload_size(len)?;
+        let (sealed, sealed_parts);
+        let parts = match codec_for(self.payload_codec.as_ref(), channel) {
+            Some(codec) => {
+                sealed = match parts {
+                    [payload] => codec.seal(channel, payload)?,
+                    _ => codec.seal(channel, &parts.concat())?,
+                };
+                sealed_parts = [sealed.as_slice()];
+                &sealed_parts[..]
+            }
+            None => parts,
+        };
+        let sequence = self
+            .config
+            .sequence_numbers
+            .then(|| self.sequencer.peek(channel));
+
+        if let Some(header) = encode_header(&self.config, channel, parts, priority, sequence)? {
+            let written = write_all_parts(
+                &mut self.inner,
+                &self.wait,
+                self.config.write_timeout,
+                header.as_bytes(),
+                parts,
+            );
+            self.poison_if_partial(written).map_err(|(_, err)| err)?;
+        } else {
+            let joined;
+            let payload = match parts {
+                [payload] => *payload,
+                _ => {
+                    joined = parts.concat();
+                    &joined
+                }
+            };
+            self.buf.clear();
+            encode_message(
+                &self.config,
+                channel,
+                payload,
+                priority,
+                sequence,
+                &mut self.buf,
+            )?;
+            self.write_encoded().map_err(|(_, err)| err)?;
+        }
+        if sequence.is_some() {
+            self.sequencer.next(channel);
+        }
+        self.record_sent(channel, len);
+        self.flush()
+    }
+
+    /// Send an empty frame on the [`HEARTBEAT`] channel and flush.
+    ///
+    /// Heartbeats always use the plain header, whatever the configuration,
+    /// and take no sequence number and no place in stats. Readers drop them
+    /// when set to (see
+    /// [`FrameReader::absorb_heartbeats`](crate::FrameReader::absorb_heartbeats)).
+    pub fn send_heartbeat(&mut self) -> Result<()> {
+        let result = self.write_heartbeat();
+        notify_error(self.observer.as_ref(), &result);
+        result
+    }
+
+    fn write_heartbeat(&mut self) -> Result<()> {
+        if self.poisoned {
+            return Err(FrameError::Poisoned);
+        }
+        self.buf.clear();
+        encode_frame(HEARTBEAT, &[], &mut self.buf)?;
+        self.write_encoded().map_err(|(_, err)| err)?;
+        self.flush()
+    }
+
+    /// Send several frames back to back with a single flush.
+    ///
+    /// Every payload is size-checked and encoded before any byte is written,
+    /// so a rejected frame sends nothing. Frames go out at [`Priority::NORMAL`]. If the
+    /// write fails after part of the batch reached the stream, the writer is
+    /// poisoned and the error names the frame that was cut off.
+    pub fn send_batch(&mut self, frames: &[(u16, &[u8])]) -> std::result::Result<(), BatchError> {
+        let result = self.write_batch(frames.len(), |index| {
+            let (channel, payload) = frames[index];
+            (channel, payload, Priority::NORMAL)
+        });
+        self.notify_batch_error(result)
+    }
+
+    /// [`send_batch`](Self::send_batch) for whole frames, each sent at its
+    /// own priority.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> std::result::Result<(), BatchError> {
+        let result = self.write_batch(frames.len(), |index| {
+            let frame = &frames[index];
+            (frame.channel, frame.payload.as_ref(), frame.priority)
+        });
+        self.notify_batch_error(result)
+    }
+
+    /// Encode `len` frames, given as `(channel, payload, priority)` by index,
+    /// and write them with one write loop and one flush.
+    fn write_batch<'a>(
+        &mut self,
+        len: usize,
+        frame: impl Fn(usize) -> (u16, &'a [u8], Priority),
+    ) -> std::result::Result<(), BatchError> {
+        if self.poisoned {
+            return Err(BatchError {
+                index: 0,
+                source: FrameError::Poisoned,
             });
         }
+        for index in 0..len {
+            self.check_payload_size(frame(index).1.len())
+                .map_err(|source| BatchError { index, source })?;
+        }
+        if len == 0 {
+            return Ok(());
+        }
 
         self.buf.clear();
-        encode_frame(channel, payload, &mut self.buf)?;
+        let mut frame_ends = Vec::with_capacity(len);
+        let mut sequencer = self.sequencer.clone();
+        for index in 0..len {
+            let (channel, payload, priority) = frame(index);
+            let sealed = codec_for(self.payload_codec.as_ref(), channel)
+                .map(|codec| codec.seal(channel, payload))
+                .transpose()
+                .map_err(|source| BatchError { index, source })?;
+            let payload = sealed.as_deref().unwrap_or(payload);
+            let sequence = self
+                .config
+                .sequence_numbers
+                .then(|| sequencer.next(channel));
+            encode_message(
+                &self.config,
+                channel,
+                payload,
+                priority,
+                sequence,
+                &mut self.buf,
+            )
+            .map_err(|source| BatchError { index, source })?;
+            frame_ends.push(self.buf.len());
+        }
+        self.write_encoded()
+            .map_err(|(written, source)| BatchError {
+                index: frame_ends
+                    .iter()
+                    .position(|&end| end > written)
+                    .unwrap_or(len - 1),
+                source,
+            })?;
+        self.sequencer = sequencer;
+        for index in 0..len {
+            let (channel, payload, _) = frame(index);
+            self.record_sent(channel, payload.len());
+        }
+        self.flush().map_err(|source| BatchError {
+            index: len - 1,
+            source,
+        })
+    }
 
-        let mut offset = 0usize;
-        while offset < self.buf.len() {
-            match self.inner.write(&self.buf[offset..]) {
-                Ok(0) => return Err(FrameError::ConnectionClosed),
-                Ok(n) => offset += n,
-                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
-                Err(err) if err.kind() == ErrorKind::WouldBlock => continue,
-                Err(err) => return Err(FrameError::Io(err)),
-            }
+    fn notify_batch_error(
+        &self,
+        result: std::result::Result<(), BatchError>,
+    ) -> std::result::Result<(), BatchError> {
+        if let Err(err) = &result {
+            notify(self.observer.as_ref(), |observer| {
+                observer.on_error(&err.source)
+            });
         }
+        result
+    }
 
-        self.flush()
+    /// Count a written message in the stats and tell the observer.
+    fn record_sent(&mut self, channel: u16, len: usize) {
+        if let Some(stats) = &mut self.stats {
+            stats.record_sent(channel, len);
+        }
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_send(channel, len)
+        });
+    }
+
+    /// True once a write failed partway through a frame or batch.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    fn check_payload_size(&self, len: usize) -> Result<()> {
+        let max = self.config.send_limit();
+        if len > max {
+            return Err(FrameError::PayloadTooLarge { size: len, max });
+        }
+        Ok(())
+    }
+
+    /// Write the encode buffer out in full. On failure returns how many bytes
+    /// made it to the stream, poisoning the writer if that was any.
+    fn write_encoded(&mut self) -> std::result::Result<(), (usize, FrameError)> {
+        let written = write_all_parts(
+            &mut self.inner,
+            &self.wait,
+            self.config.write_timeout,
+            &self.buf[..],
+            &[],
+        );
+        self.poison_if_partial(written)?;
+        self.buf.clear();
+        self.buf
+            .recycle_if_oversized(self.config.buffer_shrink_threshold);
+        Ok(())
+    }
+
+    fn poison_if_partial(
+        &mut self,
+        written: std::result::Result<(), (usize, FrameError)>,
+    ) -> std::result::Result<(), (usize, FrameError)> {
+        if matches!(written, Err((offset, _)) if offset > 0) {
+            self.poisoned = true;
+        }
+        written
     }
 
     /// Flush the underlying stream.
     pub fn flush(&mut self) -> Result<()> {
+        let mut stall = Stall::new();
         loop {
             match self.inner.flush() {
                 Ok(()) => return Ok(()),
                 Err(err) if err.kind() == ErrorKind::Interrupted => continue,
-                Err(err) if err.kind() == ErrorKind::WouldBlock => continue,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.wait
+                        .wait(&self.inner, self.config.write_timeout, &mut stall)?
+                }
+                Err(err) if is_disconnect(err.kind()) => return Err(FrameError::ConnectionClosed),
                 Err(err) => return Err(FrameError::Io(err)),
             }
         }
@@ -93,6 +477,59 @@ impl<T: Write> FrameWriter<T> {
         self.config.max_payload_size = max_payload_size;
     }
 
+    /// Update how long a write may stall before failing with
+    /// [`FrameError::Timeout`]. The stream's own timeout is left unchanged.
+    pub fn set_write_timeout(&mut self, write_timeout: Option<Duration>) {
+        self.config.write_timeout = write_timeout;
+    }
+
+    /// Set the header layout for subsequent frames; see
+    /// [`FrameConfig::wire_version`].
+    pub fn set_wire_version(&mut self, wire_version: WireVersion) {
+        self.config.wire_version = wire_version;
+    }
+
+    /// Turn payload checksums on or off for subsequent frames.
+    pub fn set_checksum(&mut self, checksum: bool) {
+        self.config.checksum = checksum;
+    }
+
+    /// Counters for the messages written so far, if
+    /// [`FrameConfig::collect_stats`] is set.
+    pub fn stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+
+    /// Report every message written, and every failed send, to `observer`.
+    pub fn set_observer(&mut self, observer: Arc<dyn FrameObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Seal every payload with `codec` before it is written, on the
+    /// channels it [applies to](PayloadCodec::applies_to). The remote's
+    /// reader needs the same codec.
+    pub fn set_payload_codec(&mut self, codec: Arc<dyn PayloadCodec>) {
+        self.payload_codec = Some(codec);
+    }
+
+    /// Zero the counters returned by [`stats`](Self::stats).
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            stats.reset();
+        }
+    }
+
+    /// Start or stop counting; see [`FrameConfig::collect_stats`]. Turning
+    /// it off drops the counters.
+    pub fn set_collect_stats(&mut self, collect_stats: bool) {
+        self.config.collect_stats = collect_stats;
+        if !collect_stats {
+            self.stats = None;
+        } else if self.stats.is_none() {
+            self.stats = Some(FrameStats::default());
+        }
+    }
+
     /// Current frame writer configuration.
     pub fn config(&self) -> &FrameConfig {
         &self.config
@@ -101,12 +538,87 @@ impl<T: Write> FrameWriter<T> {
 
 impl FrameWriter<IpcStream> {
     /// Create a frame writer for `IpcStream` and apply write timeout from config.
+    ///
+    /// On Unix the writer waits for writability with `poll(2)` instead of
+    /// backing off when the socket is full.
     pub fn with_config_ipc(inner: IpcStream, config: FrameConfig) -> Result<Self> {
         inner
             .set_write_timeout(config.write_timeout)
             .map_err(transport_to_frame_error)?;
-        Ok(Self::with_config(inner, config))
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut writer = Self::with_config(inner, config);
+        #[cfg(unix)]
+        {
+            writer.wait = WriteWait::Ready(IpcStream::wait_writable);
+        }
+        Ok(writer)
+    }
+}
+
+/// Write `head` and then `parts` to `inner` in full, in order. On failure
+/// returns how many bytes made it to the stream.
+///
+/// Uses `write_vectored`; streams without vectored support write the first
+/// non-empty part per call, and short writes resume mid-part.
+fn write_all_parts<T: Write>(
+    inner: &mut T,
+    wait: &WriteWait<T>,
+    write_timeout: Option<Duration>,
+    head: &[u8],
+    parts: &[&[u8]],
+) -> std::result::Result<(), (usize, FrameError)> {
+    let total = head.len() + parts.iter().map(|part| part.len()).sum::<usize>();
+    let mut offset = 0usize;
+    let mut stall = Stall::new();
+    while offset < total {
+        let mut slices = [IoSlice::new(&[]); MAX_WRITE_PARTS];
+        let count = remaining_parts(head, parts, offset, &mut slices);
+        let err = match inner.write_vectored(&slices[..count]) {
+            Ok(0) => FrameError::ConnectionClosed,
+            Ok(n) => {
+                offset += n;
+                if offset < total {
+                    stall = Stall::new();
+                }
+                continue;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                match wait.wait(inner, write_timeout, &mut stall) {
+                    Ok(()) => continue,
+                    Err(err) => err,
+                }
+            }
+            Err(err) if is_disconnect(err.kind()) => FrameError::ConnectionClosed,
+            Err(err) => FrameError::Io(err),
+        };
+        return Err((offset, err));
+    }
+    Ok(())
+}
+
+/// Fill `slices` with what is left of `head` and then `parts` after
+/// `offset` bytes, skipping empty parts; returns how many were filled.
+fn remaining_parts<'a>(
+    head: &'a [u8],
+    parts: &[&'a [u8]],
+    mut offset: usize,
+    slices: &mut [IoSlice<'a>; MAX_WRITE_PARTS],
+) -> usize {
+    let mut count = 0;
+    for part in std::iter::once(head).chain(parts.iter().copied()) {
+        if offset >= part.len() {
+            offset -= part.len();
+            continue;
+        }
+        slices[count] = IoSlice::new(&part[offset..]);
+        offset = 0;
+        count += 1;
+        if count == MAX_WRITE_PARTS {
+            break;
+        }
     }
+    count
 }
 
 fn transport_to_frame_error(err: ipcprims_transport::TransportError) -> FrameError {
@@ -115,6 +627,15 @@ fn transport_to_frame_error(err: ipcprims_transport::TransportError) -> FrameErr
         | ipcprims_transport::TransportError::Accept(io) => FrameError::Io(io),
         ipcprims_transport::TransportError::Bind { source, .. }
         | ipcprims_transport::TransportError::Connect { source, .. } => FrameError::Io(source),
+        ipcprims_transport::TransportError::ConnectionReset
+        | ipcprims_transport::TransportError::BrokenPipe => FrameError::ConnectionClosed,
+        ipcprims_transport::TransportError::NotFound { .. } => FrameError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            err.to_string(),
+        )),
+        ipcprims_transport::TransportError::PermissionDenied { .. } => FrameError::Io(
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, err.to_string()),
+        ),
         other => FrameError::Io(std::io::Error::other(other.to_string())),
     }
 }
@@ -178,6 +699,121 @@ mod tests {
         assert!(matches!(err, FrameError::PayloadTooLarge { .. }));
     }
 
+    #[test]
+    fn send_batch_writes_frames_in_order() {
+        let mut writer = FrameWriter::new(Cursor::new(Vec::<u8>::new()));
+
+        writer
+            .send_batch(&[(1, b"cmd".as_ref()), (2, b"a"), (2, b"b")])
+            .unwrap();
+        writer.send_batch(&[]).unwrap();
+
+        let mut wire = BytesMut::from(writer.into_inner().into_inner().as_slice());
+        for (channel, payload) in [(1, b"cmd".as_ref()), (2, b"a"), (2, b"b")] {
+            let frame = decode_frame(&mut wire, usize::MAX).unwrap().unwrap();
+            assert_eq!((frame.channel, frame.payload.as_ref()), (channel, payload));
+        }
+        assert!(wire.is_empty());
+    }
+
+    #[test]
+    fn send_batch_rejects_oversized_frame_before_writing() {
+        let cfg = FrameConfig {
+            max_payload_size: 4,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Cursor::new(Vec::<u8>::new()), cfg);
+
+        let err = writer
+            .send_batch(&[(1, b"ok".as_ref()), (2, b"oversized")])
+            .unwrap_err();
+        assert_eq!(err.index, 1);
+        assert!(matches!(err.source, FrameError::PayloadTooLarge { .. }));
+        assert!(!writer.is_poisoned());
+        assert!(writer.into_inner().into_inner().is_empty());
+    }
+
+    #[test]
+    fn send_batch_cut_off_reports_index_and_poisons() {
+        // Room for the first frame and two bytes of the second.
+        let mut writer = FrameWriter::new(CappedWriter {
+            remaining: crate::HEADER_SIZE + 3 + 2,
+        });
+
+        let err = writer
+            .send_batch(&[(1, b"abc".as_ref()), (2, b"def"), (2, b"ghi")])
+            .unwrap_err();
+        assert_eq!(err.index, 1);
+        assert!(matches!(err.source, FrameError::ConnectionClosed));
+        assert!(writer.is_poisoned());
+        assert!(matches!(
+            writer.send(1, b"more").unwrap_err(),
+            FrameError::Poisoned
+        ));
+    }
+
+    #[test]
+    fn batch_of_100_frames_is_one_write_and_one_flush() {
+        let payloads: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 40]).collect();
+        let batch: Vec<(u16, &[u8])> = payloads
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i as u16, p.as_slice()))
+            .collect();
+        let mut writer = FrameWriter::new(CountingWriter::default());
+        writer.send_batch(&batch).unwrap();
+
+        let frames: Vec<Frame> = (0..100u8)
+            .map(|i| Frame::new(i.into(), vec![i; 40]).with_priority(Priority(i % 3)))
+            .collect();
+        writer.write_frames(&frames).unwrap();
+
+        let sink = writer.into_inner();
+        assert_eq!((sink.writes, sink.flushes), (2, 2));
+        let mut wire = BytesMut::from(sink.data.as_slice());
+        for (channel, payload) in &batch {
+            let frame = decode_frame(&mut wire, usize::MAX).unwrap().unwrap();
+            assert_eq!(
+                (frame.channel, frame.payload.as_ref()),
+                (*channel, *payload)
+            );
+        }
+        for expected in &frames {
+            let frame = decode_frame(&mut wire, usize::MAX).unwrap().unwrap();
+            assert_eq!(
+                (frame.channel, frame.priority, &frame.payload),
+                (expected.channel, expected.priority, &expected.payload)
+            );
+        }
+        assert!(wire.is_empty());
+    }
+
+    #[test]
+    fn write_frames_rejects_oversized_frame_before_writing() {
+        let cfg = FrameConfig {
+            max_payload_size: 4,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(CountingWriter::default(), cfg);
+        let err = writer
+            .write_frames(&[
+                Frame::new(1, "ok"),
+                Frame::new(1, "fine"),
+                Frame::new(2, "oversized"),
+            ])
+            .unwrap_err();
+        assert_eq!(err.index, 2);
+        assert!(matches!(err.source, FrameError::PayloadTooLarge { .. }));
+        assert_eq!(writer.into_inner().writes, 0);
+    }
+
+    #[test]
+    fn failure_before_any_byte_does_not_poison() {
+        let mut writer = FrameWriter::new(CappedWriter { remaining: 0 });
+        assert!(writer.send(1, b"x").is_err());
+        assert!(!writer.is_poisoned());
+    }
+
     #[test]
     fn send_convenience_method() {
         let cursor = Cursor::new(Vec::<u8>::new());
@@ -260,6 +896,79 @@ mod tests {
         assert!(!inner.data.is_empty());
     }
 
+    #[test]
+    fn short_writes_resume_inside_header_and_payload() {
+        let payload: Vec<u8> = (0..50u8).collect();
+        let mut writer = FrameWriter::new(TrickleWriter::default());
+        writer.send(7, &payload).unwrap();
+        writer
+            .send_with_priority(8, b"urgent", Priority(3))
+            .unwrap();
+        writer.set_checksum(true);
+        writer.send(9, b"checked").unwrap();
+
+        let sink = writer.into_inner();
+        assert!(sink.calls.iter().all(|&n| n <= 3));
+        assert!(sink.retries >= 2, "retries: {}", sink.retries);
+        let mut wire = BytesMut::from(sink.data.as_slice());
+        for (channel, expected) in [(7, payload.as_slice()), (8, b"urgent"), (9, b"checked")] {
+            let frame = decode_frame(&mut wire, usize::MAX).unwrap().unwrap();
+            assert_eq!((frame.channel, frame.payload.as_ref()), (channel, expected));
+        }
+        assert!(wire.is_empty());
+    }
+
+    #[test]
+    fn payload_is_written_from_the_callers_slice() {
+        let payload = vec![0xAB; 64 * 1024];
+        let mut writer = FrameWriter::new(VectoredSink::default());
+        writer.send(2, &payload).unwrap();
+
+        let sink = writer.into_inner();
+        assert_eq!(sink.data.len(), crate::HEADER_SIZE + payload.len());
+        assert!(sink
+            .slices
+            .contains(&(payload.as_ptr() as usize, payload.len())));
+    }
+
+    #[test]
+    fn send_vectored_writes_parts_in_place() {
+        let header = *b"hdr:";
+        let body = vec![0xCD; 32 * 1024];
+        let mut writer = FrameWriter::new(VectoredSink::default());
+        writer.send_vectored(3, &[&header, &[], &body]).unwrap();
+        writer.send_vectored(3, &[]).unwrap();
+        writer.send_vectored(3, &[&[], &[]]).unwrap();
+
+        let sink = writer.into_inner();
+        assert!(sink.slices.contains(&(body.as_ptr() as usize, body.len())));
+        let mut reader = crate::FrameReader::new(Cursor::new(sink.data));
+        let frame = reader.read_frame().unwrap();
+        assert_eq!(frame.channel, 3);
+        assert_eq!(frame.payload.as_ref(), [&header[..], &body].concat());
+        assert!(reader.read_frame().unwrap().payload.is_empty());
+        assert!(reader.read_frame().unwrap().payload.is_empty());
+    }
+
+    #[test]
+    fn send_vectored_checks_the_total_length() {
+        let config = FrameConfig {
+            max_payload_size: 8,
+            checksum: true,
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        let err = writer.send_vectored(1, &[b"12345", b"6789"]).unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::PayloadTooLarge { size: 9, max: 8 }
+        ));
+        writer.send_vectored(1, &[b"1234", b"5678"]).unwrap();
+
+        let mut reader = crate::FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        assert_eq!(reader.read_frame().unwrap().payload.as_ref(), b"12345678");
+    }
+
     #[test]
     fn connection_closed_when_write_returns_zero() {
         let mut writer = FrameWriter::new(ZeroWriter);
@@ -267,6 +976,149 @@ mod tests {
         assert!(matches!(err, FrameError::ConnectionClosed));
     }
 
+    #[test]
+    fn would_block_backs_off_until_write_timeout() {
+        let cfg = FrameConfig {
+            write_timeout: Some(Duration::from_millis(200)),
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(AlwaysWouldBlock::default(), cfg);
+
+        let started = Instant::now();
+        let err = writer.send(1, b"stuck").unwrap_err();
+        assert!(matches!(err, FrameError::Timeout(t) if t == Duration::from_millis(200)));
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        // A hot loop would retry millions of times; backoff caps it near 30.
+        let attempts = writer.get_ref().attempts;
+        assert!(attempts < 100, "{attempts} write attempts");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn stalled_socket_polls_without_spinning_then_times_out() {
+        use std::os::fd::AsRawFd;
+
+        let dir = std::env::temp_dir().join(format!(
+            "ipcprims-frame-stalled-writer-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("test.sock");
+        let listener = ipcprims_transport::UnixDomainSocket::bind(&sock_path).unwrap();
+        let path_clone = sock_path.clone();
+        let connector = std::thread::spawn(move || {
+            ipcprims_transport::UnixDomainSocket::connect(path_clone).unwrap()
+        });
+        let stream = listener.accept().unwrap();
+        // Connected but never reads.
+        let _client = connector.join().unwrap();
+
+        let fd = stream.as_raw_fd();
+        let sndbuf: libc::c_int = 4096;
+        // SAFETY: `fd` is an open socket and the option value outlives the calls.
+        unsafe {
+            assert_eq!(
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_SNDBUF,
+                    (&sndbuf as *const libc::c_int).cast(),
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                ),
+                0
+            );
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            assert_eq!(libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK), 0);
+        }
+
+        let timeout = Duration::from_millis(300);
+        let cfg = FrameConfig {
+            write_timeout: Some(timeout),
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config_ipc(stream, cfg).unwrap();
+
+        let cpu_before = thread_cpu_time();
+        let started = Instant::now();
+        let err = writer.send(1, &vec![0u8; 1024 * 1024]).unwrap_err();
+        let wall = started.elapsed();
+        let cpu = thread_cpu_time() - cpu_before;
+
+        assert!(matches!(err, FrameError::Timeout(t) if t == timeout));
+        assert!(wall >= timeout);
+        assert!(cpu < wall / 4, "used {cpu:?} CPU over {wall:?}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    fn thread_cpu_time() -> Duration {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `ts` is a valid, writable timespec.
+        let rc = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+        assert_eq!(rc, 0);
+        Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+    }
+
+    #[test]
+    fn disconnect_errors_map_to_connection_closed() {
+        for kind in [
+            ErrorKind::BrokenPipe,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+        ] {
+            let mut writer = FrameWriter::new(FailingWriter {
+                write_kind: Some(kind),
+                flush_kind: None,
+            });
+            assert!(matches!(
+                writer.send(1, b"x"),
+                Err(FrameError::ConnectionClosed)
+            ));
+
+            let mut writer = FrameWriter::new(FailingWriter {
+                write_kind: None,
+                flush_kind: Some(kind),
+            });
+            assert!(matches!(
+                writer.send(1, b"x"),
+                Err(FrameError::ConnectionClosed)
+            ));
+        }
+
+        let mut writer = FrameWriter::new(FailingWriter {
+            write_kind: Some(ErrorKind::PermissionDenied),
+            flush_kind: None,
+        });
+        assert!(matches!(
+            writer.send(1, b"x"),
+            Err(FrameError::Io(e)) if e.kind() == ErrorKind::PermissionDenied
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn closed_socketpair_reports_connection_closed_on_both_sides() {
+        use std::os::unix::net::UnixStream;
+
+        let (local, remote) = UnixStream::pair().unwrap();
+        drop(remote);
+
+        let mut writer = FrameWriter::new(local.try_clone().unwrap());
+        assert!(matches!(
+            writer.send(1, b"after close"),
+            Err(FrameError::ConnectionClosed)
+        ));
+
+        let mut reader = crate::reader::FrameReader::new(local);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(FrameError::ConnectionClosed)
+        ));
+    }
+
     #[test]
     #[cfg(unix)]
     fn applies_write_timeout_for_ipc_stream() {
@@ -363,6 +1215,105 @@ mod tests {
         }
     }
 
+    /// Accepts `remaining` bytes, then reports a broken pipe.
+    struct CappedWriter {
+        remaining: usize,
+    }
+
+    impl Write for CappedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(std::io::Error::from(ErrorKind::BrokenPipe));
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Accepts at most 3 bytes per call, failing every fourth call with
+    /// `Interrupted` or `WouldBlock`.
+    #[derive(Default)]
+    struct TrickleWriter {
+        data: Vec<u8>,
+        calls: Vec<usize>,
+        retries: usize,
+    }
+
+    impl Write for TrickleWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.calls.len() % 4 == 3 && self.calls.last() != Some(&0) {
+                self.calls.push(0);
+                self.retries += 1;
+                let kind = if self.retries % 2 == 0 {
+                    ErrorKind::WouldBlock
+                } else {
+                    ErrorKind::Interrupted
+                };
+                return Err(std::io::Error::from(kind));
+            }
+            let n = buf.len().min(3);
+            self.data.extend_from_slice(&buf[..n]);
+            self.calls.push(n);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Records the address and length of every slice it is handed.
+    #[derive(Default)]
+    struct VectoredSink {
+        data: Vec<u8>,
+        slices: Vec<(usize, usize)>,
+    }
+
+    impl Write for VectoredSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+            let mut n = 0;
+            for buf in bufs {
+                self.slices.push((buf.as_ptr() as usize, buf.len()));
+                self.data.extend_from_slice(buf);
+                n += buf.len();
+            }
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingWriter {
+        data: Vec<u8>,
+        writes: usize,
+        flushes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
     struct ZeroWriter;
 
     impl Write for ZeroWriter {
@@ -375,6 +1326,72 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct AlwaysWouldBlock {
+        attempts: usize,
+    }
+
+    impl Write for AlwaysWouldBlock {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            self.attempts += 1;
+            Err(std::io::Error::from(ErrorKind::WouldBlock))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct FailingWriter {
+        write_kind: Option<ErrorKind>,
+        flush_kind: Option<ErrorKind>,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.write_kind {
+                Some(kind) => Err(std::io::Error::from(kind)),
+                None => Ok(buf.len()),
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            match self.flush_kind {
+                Some(kind) => Err(std::io::Error::from(kind)),
+                None => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_numbers_count_per_channel_and_message() {
+        let config = FrameConfig {
+            sequence_numbers: true,
+            max_fragment_size: Some(4),
+            ..FrameConfig::default()
+        };
+        let mut writer = FrameWriter::with_config(Vec::new(), config.clone());
+        writer.send(1, b"a").unwrap();
+        writer.send(2, b"b").unwrap();
+        writer.send(1, b"split into fragments").unwrap();
+        writer
+            .send_batch(&[(2, b"c".as_slice()), (1, b"d".as_slice())])
+            .unwrap();
+        let oversized = vec![0u8; config.max_message_size + 1];
+        writer.send(1, &oversized).unwrap_err();
+        writer.send(1, b"e").unwrap();
+
+        let mut reader =
+            crate::reader::FrameReader::with_config(Cursor::new(writer.into_inner()), config);
+        let stamped: Vec<_> = (0..6)
+            .map(|_| {
+                let frame = reader.read_frame().unwrap();
+                (frame.channel, frame.sequence.unwrap())
+            })
+            .collect();
+        assert_eq!(stamped, [(1, 0), (2, 0), (1, 1), (2, 1), (1, 2), (1, 3)]);
+    }
+
     #[test]
     fn written_bytes_decode() {
         let cursor = Cursor::new(Vec::<u8>::new());
@@ -0,0 +1,206 @@
+//! Peer round-trip and streaming benchmarks over Unix domain sockets.
+//!
+//! Run with `cargo bench -p ipcprims-peer`. `uds_pair_frame_rtt` uses a
+//! `socketpair` and never touches the filesystem; it is the baseline for the
+//! `peer_*` benchmarks, which need a listener path and bind it under
+//! `CARGO_TARGET_TMPDIR` rather than `/tmp`.
+
+#[cfg(unix)]
+mod unix {
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread::JoinHandle;
+
+    use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+    use ipcprims_frame::{FrameReader, FrameWriter, COMMAND, DATA};
+    use ipcprims_peer::{connect, Peer, PeerError, PeerListener};
+
+    const RTT_SIZES: [usize; 3] = [64, 4 * 1024, 64 * 1024];
+    const STREAM_SIZES: [usize; 2] = [4 * 1024, 64 * 1024];
+
+    /// DATA frames sent per COMMAND sync in the streaming benchmark.
+    const STREAM_BATCH: usize = 64;
+
+    fn payload(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Framed echo over a socketpair, bypassing handshake and peer bookkeeping.
+    struct SocketPairEcho {
+        reader: FrameReader<UnixStream>,
+        writer: FrameWriter<UnixStream>,
+        server: Option<JoinHandle<()>>,
+    }
+
+    impl SocketPairEcho {
+        fn start() -> Self {
+            let (client, server) = UnixStream::pair().expect("socketpair");
+            let server_reader = server.try_clone().expect("clone server stream");
+            let server = std::thread::spawn(move || {
+                let mut reader = FrameReader::new(server_reader);
+                let mut writer = FrameWriter::new(server);
+                while let Ok(frame) = reader.read_frame() {
+                    if writer.write_frame(&frame).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Self {
+                reader: FrameReader::new(client.try_clone().expect("clone client stream")),
+                writer: FrameWriter::new(client),
+                server: Some(server),
+            }
+        }
+
+        fn roundtrip(&mut self, payload: &[u8]) -> usize {
+            self.writer.send(COMMAND, payload).unwrap();
+            self.reader.read_frame().unwrap().payload.len()
+        }
+    }
+
+    impl Drop for SocketPairEcho {
+        fn drop(&mut self) {
+            let _ = self.writer.get_ref().shutdown(std::net::Shutdown::Both);
+            if let Some(server) = self.server.take() {
+                let _ = server.join();
+            }
+        }
+    }
+
+    /// Connected client plus a server thread that echoes COMMAND frames and
+    /// discards everything else.
+    struct PeerEcho {
+        client: Option<Peer>,
+        server: Option<JoinHandle<()>>,
+        dir: PathBuf,
+    }
+
+    impl PeerEcho {
+        fn start() -> Self {
+            static NEXT: AtomicU64 = AtomicU64::new(0);
+            let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+                "ipcprims-bench-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&dir).expect("create bench socket dir");
+            let path = dir.join("peer.sock");
+
+            let listener = PeerListener::bind(&path).expect("bind bench listener");
+            let server = std::thread::spawn(move || {
+                let mut peer = listener.accept().expect("accept bench client");
+                loop {
+                    match peer.recv() {
+                        Ok(frame) if frame.channel == COMMAND => {
+                            if peer.send(COMMAND, &frame.payload).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(_) => {}
+                        // Criterion pauses between phases; idle is not a hang-up.
+                        Err(PeerError::Timeout { .. }) => {}
+                        Err(_) => break,
+                    }
+                }
+            });
+
+            let client = connect(&path, &[COMMAND, DATA]).expect("connect bench client");
+            Self {
+                client: Some(client),
+                server: Some(server),
+                dir,
+            }
+        }
+
+        fn client(&mut self) -> &mut Peer {
+            self.client.as_mut().expect("client is live until drop")
+        }
+    }
+
+    impl Drop for PeerEcho {
+        fn drop(&mut self) {
+            drop(self.client.take());
+            if let Some(server) = self.server.take() {
+                let _ = server.join();
+            }
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    pub fn bench_socketpair_rtt(c: &mut Criterion) {
+        let mut group = c.benchmark_group("uds_pair_frame_rtt");
+        let mut echo = SocketPairEcho::start();
+        for size in RTT_SIZES {
+            let payload = payload(size);
+            assert_eq!(
+                echo.roundtrip(&payload),
+                size,
+                "echo must return the payload"
+            );
+
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+                b.iter(|| black_box(echo.roundtrip(payload)));
+            });
+        }
+        group.finish();
+    }
+
+    pub fn bench_peer_rtt(c: &mut Criterion) {
+        let mut group = c.benchmark_group("peer_request_rtt");
+        let mut echo = PeerEcho::start();
+        for size in RTT_SIZES {
+            let payload = payload(size);
+            let reply = echo.client().request(&payload).unwrap();
+            assert_eq!(
+                &reply.payload[..],
+                &payload[..],
+                "echo must return the payload"
+            );
+
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+                b.iter(|| black_box(echo.client().request(payload).unwrap()));
+            });
+        }
+        group.finish();
+    }
+
+    pub fn bench_peer_stream(c: &mut Criterion) {
+        let mut group = c.benchmark_group("peer_stream_throughput");
+        let mut echo = PeerEcho::start();
+        for size in STREAM_SIZES {
+            let payload = payload(size);
+
+            group.throughput(Throughput::Bytes((STREAM_BATCH * size) as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+                b.iter(|| {
+                    let client = echo.client();
+                    for _ in 0..STREAM_BATCH {
+                        client.send(DATA, payload).unwrap();
+                    }
+                    // Sync so each iteration measures delivery, not buffering.
+                    black_box(client.request(b"sync").unwrap())
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+#[cfg(unix)]
+criterion::criterion_group!(
+    benches,
+    unix::bench_socketpair_rtt,
+    unix::bench_peer_rtt,
+    unix::bench_peer_stream
+);
+#[cfg(unix)]
+criterion::criterion_main!(benches);
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("ipcprims-peer benchmarks require Unix domain sockets; skipping");
+}
@@ -6,7 +6,7 @@ use ipcprims_transport::AsyncNamedPipeSocket as AsyncTransportSocket;
 use ipcprims_transport::AsyncUnixDomainSocket as AsyncTransportSocket;
 use tokio_util::sync::CancellationToken;
 
-use crate::async_peer::{build_async_peer_with_cancel, AsyncPeer};
+use crate::async_peer::{build_async_peer_with_cancel, check_async_config, AsyncPeer};
 use crate::error::Result;
 use crate::handshake::{async_handshake_client_with_config, HandshakeConfig};
 use crate::peer::{PeerConfig, SchemaRegistryHandle};
@@ -25,6 +25,9 @@ pub async fn async_connect(path: impl AsRef<Path>, channels: &[u16]) -> Result<A
 }
 
 /// Connect with explicit configuration (async).
+///
+/// Fails with `FrameError::InvalidConfig` before connecting if
+/// `peer_config` sets a `payload_codec`, which async peers do not support.
 pub async fn async_connect_with_config(
     path: impl AsRef<Path>,
     channels: &[u16],
@@ -33,6 +36,8 @@ pub async fn async_connect_with_config(
     peer_config: Option<PeerConfig>,
     cancel: Option<CancellationToken>,
 ) -> Result<AsyncPeer> {
+    let peer_config = peer_config.unwrap_or_default();
+    check_async_config(&peer_config)?;
     let stream = AsyncTransportSocket::connect(path).await?;
     let (mut reader, mut writer) = stream.into_split();
 
@@ -46,7 +51,7 @@ pub async fn async_connect_with_config(
         writer,
         handshake,
         schema_registry,
-        peer_config.unwrap_or_default(),
+        peer_config,
         cancel,
     ))
 }
@@ -8,7 +8,7 @@ use ipcprims_transport::AsyncNamedPipeSocket as AsyncTransportSocket;
 use ipcprims_transport::AsyncUnixDomainSocket as AsyncTransportSocket;
 use tokio_util::sync::CancellationToken;
 
-use crate::async_peer::{build_async_peer_with_cancel, AsyncPeer};
+use crate::async_peer::{build_async_peer_with_cancel, check_async_config, AsyncPeer};
 use crate::error::Result;
 use crate::handshake::{async_handshake_server_with_config, HandshakeConfig};
 use crate::peer::{PeerConfig, SchemaRegistryHandle};
@@ -63,7 +63,22 @@ impl AsyncPeerListener {
         self
     }
 
+    /// Accept only clients whose auth token satisfies `validator`.
+    ///
+    /// Rejected clients fail the handshake. Call after `with_handshake_config`,
+    /// which replaces the whole handshake config.
+    pub fn with_auth_validator(
+        mut self,
+        validator: impl Fn(Option<&str>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.handshake_config.auth_validator = Some(std::sync::Arc::new(validator));
+        self
+    }
+
     /// Override peer behavior config.
+    ///
+    /// A config with a `payload_codec` makes every accept fail with
+    /// `FrameError::InvalidConfig`; async peers do not support one.
     pub fn with_peer_config(mut self, config: PeerConfig) -> Self {
         self.peer_config = config;
         self
@@ -86,6 +101,7 @@ impl AsyncPeerListener {
 
     /// Accept next connection and use explicit peer id.
     pub async fn accept_with_id(&self, peer_id: &str) -> Result<AsyncPeer> {
+        check_async_config(&self.peer_config)?;
         let stream = self.socket.accept().await?;
         let (mut reader, mut writer) = stream.into_split();
 
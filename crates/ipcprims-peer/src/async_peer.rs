@@ -12,7 +12,7 @@
 //! - With the `schema` feature enabled, inbound schema validation failures currently cause a hard
 //!   disconnect and are surfaced to receivers as `PeerError::Disconnected(String)` (error text).
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, Weak};
@@ -21,22 +21,20 @@ use std::time::{Duration, Instant};
 
 use bytes::BytesMut;
 use futures_core::Stream;
-use ipcprims_frame::{
-    decode_frame, encode_frame, Frame, FrameError, CONTROL, DEFAULT_MAX_PAYLOAD, HEADER_SIZE,
-};
+use ipcprims_frame::{decode_frame, encode_frame, Frame, FrameError, COMMAND, CONTROL};
 use ipcprims_transport::AsyncIpcStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::{mpsc, oneshot, watch, Semaphore};
 use tokio_util::sync::CancellationToken;
-use tracing::debug;
 
 use crate::control::{
     ControlMessage, CONTROL_PING, CONTROL_PONG, CONTROL_SHUTDOWN_ACK, CONTROL_SHUTDOWN_FORCE,
-    CONTROL_SHUTDOWN_REQUEST,
+    CONTROL_SHUTDOWN_REQUEST, FORCED_SHUTDOWN_REASON,
 };
-use crate::error::{PeerError, Result};
+use crate::error::{PeerError, Result, TimeoutOp};
 use crate::handshake::HandshakeResult;
 use crate::peer::{PeerConfig, SchemaRegistryHandle};
+use crate::trace::{self, debug, Span};
 
 const READ_CHUNK_SIZE: usize = 8 * 1024;
 
@@ -65,9 +63,15 @@ fn disconnect_error(disconnect: &watch::Receiver<Option<PeerError>>) -> PeerErro
     match disconnect.borrow().as_ref() {
         Some(PeerError::BufferFull(ch)) => PeerError::BufferFull(*ch),
         Some(PeerError::UnsupportedChannel(ch)) => PeerError::UnsupportedChannel(*ch),
-        Some(PeerError::Timeout(d)) => PeerError::Timeout(*d),
+        Some(PeerError::Timeout { operation, waited }) => PeerError::Timeout {
+            operation: *operation,
+            waited: *waited,
+        },
         Some(PeerError::HandshakeFailed(s)) => PeerError::HandshakeFailed(s.clone()),
         Some(PeerError::ShutdownFailed(s)) => PeerError::ShutdownFailed(s.clone()),
+        Some(PeerError::Desynchronized(s)) => PeerError::Desynchronized(s.clone()),
+        Some(PeerError::AuthRejected(s)) => PeerError::AuthRejected(s.clone()),
+        Some(err @ PeerError::TransactionFailed { .. }) => PeerError::Disconnected(err.to_string()),
         Some(PeerError::Disconnected(s)) => PeerError::Disconnected(s.clone()),
         Some(PeerError::Frame(e)) => PeerError::Disconnected(e.to_string()),
         Some(PeerError::Transport(e)) => PeerError::Disconnected(e.to_string()),
@@ -200,6 +204,10 @@ struct Shared {
 
     // Optional external structured cancellation token (brief D8).
     external_cancel: Option<CancellationToken>,
+
+    // Connection span; the reader task runs inside it and callers enter it
+    // briefly (never across an await) to emit events.
+    span: Span,
 }
 
 #[derive(Debug)]
@@ -273,10 +281,11 @@ impl AsyncPeerTx {
             return Err(PeerError::UnsupportedChannel(channel));
         }
 
-        if payload.len() > DEFAULT_MAX_PAYLOAD {
+        let max_payload = self.shared.config.max_payload_size;
+        if payload.len() > max_payload {
             return Err(PeerError::Frame(FrameError::PayloadTooLarge {
                 size: payload.len(),
-                max: DEFAULT_MAX_PAYLOAD,
+                max: max_payload,
             }));
         }
 
@@ -294,6 +303,9 @@ impl AsyncPeerTx {
             .flush()
             .await
             .map_err(|e| PeerError::Frame(FrameError::Io(e)))?;
+        self.shared
+            .span
+            .in_scope(|| trace::frame_sent(channel, payload.len()));
         Ok(())
     }
 
@@ -341,7 +353,10 @@ impl AsyncPeerTx {
         match res {
             Ok(Ok(())) => Ok(start.elapsed()),
             Ok(Err(_)) => Err(PeerError::Disconnected("ping waiter dropped".to_string())),
-            Err(_) => Err(PeerError::Timeout(self.shared.config.shutdown_timeout)),
+            Err(_) => Err(PeerError::Timeout {
+                operation: TimeoutOp::Ping,
+                waited: self.shared.config.shutdown_timeout,
+            }),
         }
     }
 
@@ -373,6 +388,9 @@ impl AsyncPeerTx {
             }
             return Err(e);
         }
+        self.shared
+            .span
+            .in_scope(|| trace::shutdown_phase("requested"));
 
         let res = tokio::time::timeout(self.shared.config.shutdown_timeout, rx).await;
         // Clear the in-flight waiter on all paths. If the peer responds after a timeout, we
@@ -383,7 +401,7 @@ impl AsyncPeerTx {
                 let _ = guard.take();
             }
         }
-        match res {
+        let result = match res {
             Ok(Ok(())) => Ok(()),
             Ok(Err(_)) => Err(PeerError::ShutdownFailed(
                 "shutdown waiter dropped".to_string(),
@@ -391,13 +409,25 @@ impl AsyncPeerTx {
             Err(_) => Err(PeerError::ShutdownFailed(
                 "timed out waiting for shutdown acknowledgement".to_string(),
             )),
-        }
+        };
+        self.shared.span.in_scope(|| {
+            trace::shutdown_phase(if result.is_ok() {
+                "acknowledged"
+            } else {
+                "failed"
+            })
+        });
+        result
     }
 
     #[cfg(feature = "schema")]
     fn validate_send(&self, channel: u16, payload: &[u8]) -> Result<()> {
         if let Some(registry) = &self.shared.schema_registry {
-            registry.validate(channel, payload)?;
+            registry.validate(channel, payload).inspect_err(|err| {
+                self.shared
+                    .span
+                    .in_scope(|| trace::schema_rejected("send", channel, err))
+            })?;
         }
         Ok(())
     }
@@ -412,7 +442,9 @@ impl AsyncPeerTx {
 #[cfg(feature = "schema")]
 fn validate_recv(shared: &Shared, frame: &Frame) -> Result<()> {
     if let Some(registry) = &shared.schema_registry {
-        registry.validate_frame(frame)?;
+        registry
+            .validate_frame(frame)
+            .inspect_err(|err| trace::schema_rejected("recv", frame.channel, err))?;
     }
     Ok(())
 }
@@ -426,6 +458,9 @@ fn validate_recv(shared: &Shared, _frame: &Frame) -> Result<()> {
 pub struct AsyncPeerRx {
     shared: Arc<Shared>,
     any_rx: Option<mpsc::Receiver<QueuedFrame>>,
+    /// Frames `recv_on` took from `any_rx` for other channels, in arrival
+    /// order; they keep their share of the byte budget until returned.
+    pending: VecDeque<QueuedFrame>,
     subscriptions: Arc<Mutex<HashMap<u16, mpsc::Sender<QueuedFrame>>>>,
     disconnect: watch::Receiver<Option<PeerError>>,
 }
@@ -443,7 +478,15 @@ impl AsyncPeerRx {
         self.shared.negotiated_set.contains(&channel)
     }
 
+    /// Receive the next frame from any channel, in arrival order.
+    ///
+    /// Frames [`recv_on`](Self::recv_on) set aside for other channels come
+    /// first. Cancellation-safe: the reader task assembles frames, so a
+    /// dropped future loses nothing.
     pub async fn recv(&mut self) -> Result<Frame> {
+        if let Some(queued) = self.pending.pop_front() {
+            return Ok(queued.frame);
+        }
         let Some(any_rx) = &mut self.any_rx else {
             return Err(PeerError::Disconnected(
                 "any receiver disabled; use a channel receiver".to_string(),
@@ -455,12 +498,57 @@ impl AsyncPeerRx {
         }
     }
 
+    /// Receive the next frame on `channel`, setting frames for other
+    /// channels aside for [`recv`](Self::recv) and later `recv_on` calls.
+    ///
+    /// More than `max_buffer_per_channel` frames set aside for one channel
+    /// fail with [`PeerError::BufferFull`]. Cancellation-safe, like
+    /// [`recv`](Self::recv).
+    pub async fn recv_on(&mut self, channel: u16) -> Result<Frame> {
+        if channel != CONTROL && !self.supports_channel(channel) {
+            return Err(PeerError::UnsupportedChannel(channel));
+        }
+        if let Some(index) = self.pending.iter().position(|q| q.frame.channel == channel) {
+            if let Some(queued) = self.pending.remove(index) {
+                return Ok(queued.frame);
+            }
+        }
+        loop {
+            let queued = {
+                let Some(any_rx) = &mut self.any_rx else {
+                    return Err(PeerError::Disconnected(
+                        "any receiver disabled; use a channel receiver".to_string(),
+                    ));
+                };
+                match any_rx.recv().await {
+                    Some(queued) => queued,
+                    None => return Err(disconnect_error(&self.disconnect)),
+                }
+            };
+            if queued.frame.channel == channel {
+                return Ok(queued.frame);
+            }
+            let other = queued.frame.channel;
+            let set_aside = self
+                .pending
+                .iter()
+                .filter(|q| q.frame.channel == other)
+                .count();
+            if set_aside >= self.shared.config.max_buffer_per_channel {
+                return Err(PeerError::BufferFull(other));
+            }
+            self.pending.push_back(queued);
+        }
+    }
+
     /// Cancel the background reader task (local cancellation).
     pub fn cancel(&self) {
         self.shared.cancel.cancel();
     }
 
     /// Take ownership of the arrival-ordered receiver.
+    ///
+    /// Frames [`recv_on`](Self::recv_on) set aside stay with this handle.
     pub fn take_any_receiver(&mut self) -> AnyReceiver {
         let rx = self.any_rx.take().unwrap_or_else(closed_receiver);
         AnyReceiver {
@@ -538,12 +626,64 @@ impl AsyncPeer {
         (self.tx, self.rx)
     }
 
+    /// Send `payload` on a negotiated channel; see [`AsyncPeerTx::send`].
+    pub async fn send(&self, channel: u16, payload: &[u8]) -> Result<()> {
+        self.tx.send(channel, payload).await
+    }
+
+    /// Serialize `value` as JSON and send it on `channel`.
+    pub async fn send_json<T: serde::Serialize>(&self, channel: u16, value: &T) -> Result<()> {
+        self.tx.send_json(channel, value).await
+    }
+
+    /// Receive the next frame from any channel; see [`AsyncPeerRx::recv`].
+    pub async fn recv(&mut self) -> Result<Frame> {
+        self.rx.recv().await
+    }
+
+    /// Receive the next frame on `channel`; see [`AsyncPeerRx::recv_on`].
+    pub async fn recv_on(&mut self, channel: u16) -> Result<Frame> {
+        self.rx.recv_on(channel).await
+    }
+
+    /// Send a COMMAND request and wait for the COMMAND response.
+    ///
+    /// Like [`Peer::request`](crate::Peer::request), meant for one request
+    /// in flight at a time; correlation is up to the payloads.
+    pub async fn request(&mut self, payload: &[u8]) -> Result<Frame> {
+        self.tx.send(COMMAND, payload).await?;
+        self.rx.recv_on(COMMAND).await
+    }
+
+    /// Round-trip a CONTROL ping; see [`AsyncPeerTx::ping`].
+    pub async fn ping(&self) -> Result<Duration> {
+        self.tx.ping().await
+    }
+
+    /// Ask the remote to shut down and wait for its acknowledgement; see
+    /// [`AsyncPeerTx::shutdown`].
+    pub async fn shutdown(&self) -> Result<()> {
+        self.tx.shutdown().await
+    }
+
     /// Cancel the background reader task (local cancellation).
     pub fn cancel(&self) {
         self.rx.cancel();
     }
 }
 
+/// Refuse a config async peers cannot honor: they have no payload codec
+/// path, and sending in the clear what was meant to be sealed is worse than
+/// not connecting.
+pub(crate) fn check_async_config(config: &PeerConfig) -> Result<()> {
+    if config.payload_codec.is_some() {
+        return Err(PeerError::Frame(FrameError::InvalidConfig(
+            "payload_codec is not supported by async peers".to_string(),
+        )));
+    }
+    Ok(())
+}
+
 pub(crate) fn build_async_peer_with_cancel(
     id: String,
     read_half: tokio::io::ReadHalf<AsyncIpcStream>,
@@ -567,6 +707,7 @@ pub(crate) fn build_async_peer_with_cancel(
         shutdown_waiter: tokio::sync::Mutex::new(None),
         cancel: CancellationToken::new(),
         external_cancel,
+        span: trace::connection_span(Some(&id)),
     });
 
     let (disconnect_tx, disconnect_rx) = watch::channel::<Option<PeerError>>(None);
@@ -591,6 +732,8 @@ pub(crate) fn build_async_peer_with_cancel(
             subscriptions: Arc::clone(&subscriptions),
             disconnect_tx,
             budget,
+            max_payload: config.max_payload_size,
+            span: shared.span.clone(),
         },
         read_half,
     );
@@ -602,6 +745,7 @@ pub(crate) fn build_async_peer_with_cancel(
         rx: AsyncPeerRx {
             shared,
             any_rx,
+            pending: VecDeque::new(),
             subscriptions,
             disconnect: disconnect_rx,
         },
@@ -632,7 +776,7 @@ fn deliver_frame(
         return false;
     }
 
-    let frame_bytes = decoded.payload.len().saturating_add(HEADER_SIZE);
+    let frame_bytes = decoded.wire_size();
     let permits: u32 = match frame_bytes.try_into() {
         Ok(v) => v,
         Err(_) => {
@@ -676,7 +820,7 @@ fn deliver_frame(
     }
 
     if !delivered {
-        debug!(peer_id=%shared.id, "frame dropped (no active receivers)");
+        debug!(channel, "frame dropped (no active receivers)");
     }
 
     true
@@ -691,12 +835,14 @@ fn spawn_reader_task(ctx: ReaderTaskCtx, mut reader: tokio::io::ReadHalf<AsyncIp
         subscriptions,
         disconnect_tx,
         budget,
+        max_payload,
+        span,
     } = ctx;
 
     let has_external = external_cancel.is_some();
     let external_token = external_cancel.unwrap_or_default();
 
-    tokio::spawn(async move {
+    let task = async move {
         let mut buf = BytesMut::with_capacity(8 * 1024);
         let mut chunk = [0u8; READ_CHUNK_SIZE];
 
@@ -721,9 +867,14 @@ fn spawn_reader_task(ctx: ReaderTaskCtx, mut reader: tokio::io::ReadHalf<AsyncIp
 
             let n = match read_res {
                 Ok(0) => {
-                    let _ = disconnect_tx.send_replace(Some(PeerError::Disconnected(
-                        "connection closed".to_string(),
-                    )));
+                    let err = if buf.is_empty() {
+                        PeerError::Disconnected("connection closed".to_string())
+                    } else {
+                        PeerError::Frame(FrameError::Truncated {
+                            buffered: buf.len(),
+                        })
+                    };
+                    let _ = disconnect_tx.send_replace(Some(err));
                     break;
                 }
                 Ok(n) => n,
@@ -735,7 +886,7 @@ fn spawn_reader_task(ctx: ReaderTaskCtx, mut reader: tokio::io::ReadHalf<AsyncIp
             buf.extend_from_slice(&chunk[..n]);
 
             loop {
-                let decoded = match decode_frame(&mut buf, DEFAULT_MAX_PAYLOAD) {
+                let decoded = match decode_frame(&mut buf, max_payload) {
                     Ok(Some(frame)) => frame,
                     Ok(None) => break,
                     Err(err) => {
@@ -743,6 +894,7 @@ fn spawn_reader_task(ctx: ReaderTaskCtx, mut reader: tokio::io::ReadHalf<AsyncIp
                         return;
                     }
                 };
+                trace::frame_received(decoded.channel, decoded.payload.len());
 
                 if decoded.channel == CONTROL {
                     let Some(shared) = shared.upgrade() else {
@@ -801,7 +953,8 @@ fn spawn_reader_task(ctx: ReaderTaskCtx, mut reader: tokio::io::ReadHalf<AsyncIp
                 }
             }
         }
-    });
+    };
+    tokio::spawn(trace::instrument(task, span));
 }
 
 struct ReaderTaskCtx {
@@ -812,6 +965,8 @@ struct ReaderTaskCtx {
     subscriptions: Arc<Mutex<HashMap<u16, mpsc::Sender<QueuedFrame>>>>,
     disconnect_tx: watch::Sender<Option<PeerError>>,
     budget: Arc<Semaphore>,
+    max_payload: usize,
+    span: Span,
 }
 
 async fn handle_control(
@@ -822,11 +977,13 @@ async fn handle_control(
     let message = match serde_json::from_slice::<ControlMessage>(frame.payload.as_ref()) {
         Ok(m) => m,
         Err(_) => {
+            trace::control_rejected(None, "invalid CONTROL JSON payload");
             return Err(PeerError::Disconnected(
                 "invalid CONTROL JSON payload".to_string(),
             ));
         }
     };
+    trace::control_received(&message.msg_type);
 
     match message.msg_type.as_str() {
         CONTROL_PING => {
@@ -847,25 +1004,34 @@ async fn handle_control(
         }
         CONTROL_SHUTDOWN_REQUEST => {
             send_control(shared, &ControlMessage::shutdown_ack()).await?;
+            trace::shutdown_phase("remote_requested");
             let msg = "shutdown requested".to_string();
             let _ = disconnect_tx.send_replace(Some(PeerError::Disconnected(msg.clone())));
             Err(PeerError::Disconnected(msg))
         }
         CONTROL_SHUTDOWN_FORCE => {
             if shared.config.allow_shutdown_force {
-                let msg = "force shutdown".to_string();
+                trace::shutdown_phase("remote_forced");
+                let msg = FORCED_SHUTDOWN_REASON.to_string();
                 let _ = disconnect_tx.send_replace(Some(PeerError::Disconnected(msg.clone())));
                 Err(PeerError::Disconnected(msg))
             } else {
+                trace::control_rejected(
+                    Some(CONTROL_SHUTDOWN_FORCE),
+                    "received disallowed SHUTDOWN_FORCE",
+                );
                 Err(PeerError::Disconnected(
                     "received disallowed SHUTDOWN_FORCE".to_string(),
                 ))
             }
         }
         _ if shared.config.allow_unknown_control_messages => Ok(Some(frame)),
-        _ => Err(PeerError::Disconnected(
-            "unknown CONTROL message type".to_string(),
-        )),
+        msg_type => {
+            trace::control_rejected(Some(msg_type), "unknown CONTROL message type");
+            Err(PeerError::Disconnected(
+                "unknown CONTROL message type".to_string(),
+            ))
+        }
     }
 }
 
@@ -1164,7 +1330,8 @@ mod tests {
 
         let _server = server_task.await.unwrap();
 
-        let _ = tx.ping().await.unwrap_err();
+        let err = tx.ping().await.unwrap_err();
+        assert_eq!(err.to_string(), "ping timed out after 25ms");
 
         // Regression: an error/timeout used to leave the waiter stuck, causing "already in flight".
         let err2 = tx.ping().await.unwrap_err();
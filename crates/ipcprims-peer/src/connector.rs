@@ -1,24 +1,55 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[cfg_attr(not(unix), allow(unused_imports))]
-use ipcprims_frame::{FrameConfig, FrameReader, FrameWriter, DEFAULT_MAX_PAYLOAD};
+use ipcprims_frame::{ChannelMap, FrameConfig, FrameReader, FrameWriter};
 #[cfg(windows)]
 use ipcprims_transport::NamedPipeStream;
+#[cfg(feature = "tcp")]
+use ipcprims_transport::TcpTransport;
+use ipcprims_transport::TransportError;
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+use ipcprims_transport::VsockTransport;
 #[cfg(unix)]
-use ipcprims_transport::UnixDomainSocket;
+use ipcprims_transport::{ConnectOptions, UnixDomainSocket};
+use ipcprims_transport::{IpcAddr, IpcStream, RetryPolicy, TransportStream};
 
 use crate::error::Result;
-#[cfg_attr(not(unix), allow(unused_imports))]
-use crate::handshake::handshake_client_with_config;
-use crate::handshake::HandshakeConfig;
+#[cfg(unix)]
+use crate::error::{PeerError, TimeoutOp};
+use crate::handshake::{handshake_client_with_config, HandshakeConfig};
 use crate::peer::{Peer, PeerConfig, SchemaRegistryHandle};
+use crate::trace;
 
 /// Connect to a listening peer as a client.
+///
+/// On Linux, `@name` connects to `name` in the abstract socket namespace.
+/// With the `tcp` feature, `tcp://127.0.0.1:9000` connects over TCP, and
+/// with the `vsock` feature (Linux), `vsock://2:5000` connects to port 5000
+/// on the host from inside a VM.
 pub fn connect(path: impl AsRef<Path>, channels: &[u16]) -> Result<Peer> {
     connect_with_config(path, channels, &HandshakeConfig::default(), None, None)
 }
 
+/// [`connect`] requesting channels by name, resolved through `map` (built-in
+/// names such as `"command"` always resolve).
+///
+/// An unknown name fails with `FrameError::InvalidConfig` before connecting.
+pub fn connect_named<S: AsRef<str>>(
+    path: impl AsRef<Path>,
+    map: &ChannelMap,
+    names: &[S],
+) -> Result<Peer> {
+    let channels = map.resolve(names)?;
+    connect(path, &channels)
+}
+
 /// Connect with explicit configuration.
+///
+/// Connecting to a Unix socket path gives up after
+/// `handshake_config.connect_timeout` (default: its `timeout`) with
+/// `PeerError::Timeout` for `TimeoutOp::Connect`. With
+/// `handshake_config.require_server_uid`, the endpoint is vetted before
+/// the handshake starts.
 pub fn connect_with_config(
     path: impl AsRef<Path>,
     channels: &[u16],
@@ -26,66 +57,217 @@ pub fn connect_with_config(
     schema_registry: Option<SchemaRegistryHandle>,
     peer_config: Option<PeerConfig>,
 ) -> Result<Peer> {
-    #[cfg(unix)]
-    {
-        let stream = UnixDomainSocket::connect(path)?;
-        let reader_stream = stream.try_clone()?;
+    let connect_timeout = handshake_config
+        .connect_timeout
+        .unwrap_or(handshake_config.timeout);
+    let stream = connect_stream(path.as_ref(), connect_timeout)?;
+    check_server_uid(path.as_ref(), &stream, handshake_config)?;
+    establish_client(
+        stream,
+        channels,
+        handshake_config,
+        schema_registry,
+        peer_config.unwrap_or_default(),
+    )
+}
+
+/// [`connect_with_config`] that first retries the connect per `policy`
+/// while the listener is not up yet.
+///
+/// `policy` alone bounds the connect phase (`handshake_config.connect_timeout`
+/// is not consulted); running out of time fails with
+/// `TransportError::ConnectTimeout` carrying the last connect error. The
+/// handshake is not retried and stays bounded by `handshake_config.timeout`.
+pub fn connect_with_retry(
+    path: impl AsRef<Path>,
+    channels: &[u16],
+    handshake_config: &HandshakeConfig,
+    policy: &RetryPolicy,
+    peer_config: Option<PeerConfig>,
+) -> Result<Peer> {
+    let stream = ipcprims_transport::connect_with_retry(&path, policy)?;
+    check_server_uid(path.as_ref(), &stream, handshake_config)?;
+    establish_client(
+        stream,
+        channels,
+        handshake_config,
+        None,
+        peer_config.unwrap_or_default(),
+    )
+}
 
-        let frame_config = FrameConfig {
-            max_payload_size: handshake_config.max_handshake_payload,
-            read_timeout: Some(handshake_config.timeout),
-            write_timeout: Some(handshake_config.timeout),
+/// Run the client handshake over a stream connected by other means: a
+/// transport from outside this crate, or a socket set up by hand.
+///
+/// `handshake_config.connect_timeout` does not apply, since the stream is
+/// already connected. `handshake_config.require_server_uid` checks only the
+/// peer's credentials, so streams that carry none fail it.
+pub fn connect_over(
+    stream: impl TransportStream,
+    channels: &[u16],
+    handshake_config: &HandshakeConfig,
+    schema_registry: Option<SchemaRegistryHandle>,
+    peer_config: Option<PeerConfig>,
+) -> Result<Peer> {
+    let stream = stream.into_ipc_stream();
+    if handshake_config.require_server_uid.is_some() {
+        let endpoint = match stream.peer_addr() {
+            Ok(IpcAddr::Path(path)) => path,
+            Ok(addr) => PathBuf::from(addr.to_string()),
+            Err(_) => PathBuf::from(IpcAddr::Unnamed.to_string()),
         };
+        check_server_peer_uid(&endpoint, &stream, handshake_config)?;
+    }
+    establish_client(
+        stream,
+        channels,
+        handshake_config,
+        schema_registry,
+        peer_config.unwrap_or_default(),
+    )
+}
 
-        let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
-        let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
-
-        let handshake =
-            handshake_client_with_config(&mut reader, &mut writer, channels, handshake_config)?;
-        // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
-        reader.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-        writer.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-        let id = handshake.peer_id.clone();
-
-        Ok(Peer::from_parts(
-            id,
-            reader,
-            writer,
-            handshake,
-            schema_registry,
-            peer_config.unwrap_or_default(),
-        ))
+/// Run the client handshake on a connected stream.
+pub(crate) fn establish_client(
+    stream: IpcStream,
+    channels: &[u16],
+    handshake_config: &HandshakeConfig,
+    schema_registry: Option<SchemaRegistryHandle>,
+    peer_config: PeerConfig,
+) -> Result<Peer> {
+    peer_config.apply_buffer_sizes(&stream)?;
+    let reader_stream = stream.try_clone()?;
+
+    let frame_config = FrameConfig {
+        max_payload_size: handshake_config.max_handshake_payload,
+        read_timeout: Some(handshake_config.timeout),
+        write_timeout: Some(handshake_config.timeout),
+        ..FrameConfig::default()
+    };
+
+    let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
+    let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
+
+    let span = trace::connection_span(None);
+    let _entered = span.clone().entered();
+    let handshake =
+        handshake_client_with_config(&mut reader, &mut writer, channels, handshake_config)?;
+    trace::record_peer_id(&span, &handshake.peer_id);
+    // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
+    reader.set_max_payload_size(peer_config.max_payload_size);
+    writer.set_max_payload_size(peer_config.max_payload_size);
+    let id = handshake.peer_id.clone();
+
+    Ok(Peer::from_parts(
+        id,
+        reader,
+        writer,
+        handshake,
+        schema_registry,
+        peer_config,
+        span,
+    ))
+}
+
+/// Enforce `handshake_config.require_server_uid` on a stream just
+/// connected to `path`.
+fn check_server_uid(
+    path: &Path,
+    stream: &IpcStream,
+    handshake_config: &HandshakeConfig,
+) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(uid) = handshake_config.require_server_uid {
+        if is_socket_path(path) {
+            ConnectOptions::new()
+                .require_owner_uid(uid)
+                .check_path(path)?;
+        }
     }
+    check_server_peer_uid(path, stream, handshake_config)
+}
 
-    #[cfg(windows)]
+/// The credential half of [`check_server_uid`], for streams with no socket
+/// path to vet.
+fn check_server_peer_uid(
+    path: &Path,
+    stream: &IpcStream,
+    handshake_config: &HandshakeConfig,
+) -> Result<()> {
+    let Some(uid) = handshake_config.require_server_uid else {
+        return Ok(());
+    };
+
+    #[cfg(unix)]
     {
-        let stream = NamedPipeStream::connect(path)?;
-        let reader_stream = stream.try_clone()?;
+        ConnectOptions::new()
+            .require_peer_uid(uid)
+            .check_peer(path, stream)?;
+        Ok(())
+    }
 
-        let frame_config = FrameConfig {
-            max_payload_size: handshake_config.max_handshake_payload,
-            read_timeout: Some(handshake_config.timeout),
-            write_timeout: Some(handshake_config.timeout),
+    #[cfg(not(unix))]
+    {
+        let _ = stream;
+        Err(TransportError::UntrustedEndpoint {
+            path: path.to_path_buf(),
+            detail: format!("cannot verify that the server runs as uid {uid}"),
+        }
+        .into())
+    }
+}
+
+/// Whether `path` names a filesystem Unix socket rather than an abstract,
+/// TCP or vsock endpoint.
+#[cfg(unix)]
+fn is_socket_path(path: &Path) -> bool {
+    #[cfg(feature = "tcp")]
+    if TcpTransport::endpoint_addr(path).is_some() {
+        return false;
+    }
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    if VsockTransport::endpoint_addr(path).is_some() {
+        return false;
+    }
+    UnixDomainSocket::abstract_name(path).is_none()
+}
+
+/// Open the transport stream `path` names, bounding a Unix socket path
+/// connect by `timeout`.
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn connect_stream(path: &Path, timeout: Duration) -> Result<IpcStream> {
+    #[cfg(feature = "tcp")]
+    if let Some(addr) = TcpTransport::endpoint_addr(path) {
+        return Ok(TcpTransport::connect(addr)?);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    if let Some((cid, port)) = VsockTransport::endpoint_addr(path) {
+        return Ok(VsockTransport::connect(cid, port)?);
+    }
+
+    #[cfg(unix)]
+    {
+        let stream = match UnixDomainSocket::abstract_name(path) {
+            Some(name) => UnixDomainSocket::connect_abstract(name)?,
+            None => UnixDomainSocket::connect_timeout(path, timeout).map_err(|err| match err {
+                TransportError::Connect { source, .. }
+                    if source.kind() == std::io::ErrorKind::TimedOut =>
+                {
+                    PeerError::Timeout {
+                        operation: TimeoutOp::Connect,
+                        waited: timeout,
+                    }
+                }
+                err => err.into(),
+            })?,
         };
+        Ok(stream)
+    }
 
-        let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
-        let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
-
-        let handshake =
-            handshake_client_with_config(&mut reader, &mut writer, channels, handshake_config)?;
-        // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
-        reader.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-        writer.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-        let id = handshake.peer_id.clone();
-
-        Ok(Peer::from_parts(
-            id,
-            reader,
-            writer,
-            handshake,
-            schema_registry,
-            peer_config.unwrap_or_default(),
-        ))
+    #[cfg(windows)]
+    {
+        Ok(NamedPipeStream::connect(path)?)
     }
 }
 
@@ -164,6 +346,131 @@ mod tests {
         server.join().expect("server thread should complete");
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn connect_with_retry_handshakes_with_a_late_listener() {
+        let dir = std::env::temp_dir().join(format!("ipcc-retry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        let sock_path = dir.join("listener.sock");
+
+        let bind_path = sock_path.clone();
+        let server = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let listener = PeerListener::bind(&bind_path).expect("listener should bind");
+            let mut peer = listener.accept().expect("listener should accept");
+            let frame = peer.recv_on(COMMAND).expect("should receive command frame");
+            peer.send(COMMAND, frame.payload.as_ref())
+                .expect("should echo command");
+        });
+
+        let mut client = connect_with_retry(
+            &sock_path,
+            &[COMMAND],
+            &HandshakeConfig::default(),
+            &RetryPolicy::default(),
+            None,
+        )
+        .expect("client should connect once the listener is up");
+        let response = client.request(b"late").expect("request should succeed");
+        assert_eq!(response.payload.as_ref(), b"late");
+
+        server.join().expect("server thread should complete");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn connect_rejects_overlong_path() {
+        let long_path = "/tmp/".to_string() + &"a".repeat(200) + ".sock";
+        assert!(matches!(
+            connect(&long_path, &[COMMAND]),
+            Err(crate::PeerError::Transport(
+                ipcprims_transport::TransportError::PathTooLong { .. }
+            ))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn connect_times_out_on_a_full_backlog() {
+        use ipcprims_transport::BindOptions;
+
+        let dir = std::env::temp_dir().join(format!("ipcc-backlog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        let sock_path = dir.join("listener.sock");
+        // Never accepts, so the queue fills after a connection or two.
+        let _socket = UnixDomainSocket::bind_with(&sock_path, BindOptions::new().backlog(0))
+            .expect("socket should bind");
+        let config = HandshakeConfig {
+            connect_timeout: Some(Duration::from_millis(100)),
+            ..HandshakeConfig::default()
+        };
+
+        let mut queued = Vec::new();
+        let err = loop {
+            assert!(queued.len() < 16, "backlog never filled");
+            match UnixDomainSocket::connect_timeout(&sock_path, Duration::from_millis(100)) {
+                Ok(stream) => queued.push(stream),
+                Err(_) => break connect_with_config(&sock_path, &[COMMAND], &config, None, None),
+            }
+        }
+        .err()
+        .expect("connect should time out");
+        assert!(
+            matches!(
+                err,
+                crate::PeerError::Timeout {
+                    operation: TimeoutOp::Connect,
+                    waited,
+                } if waited == Duration::from_millis(100)
+            ),
+            "{err:?}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn require_server_uid_refuses_before_the_handshake() {
+        let dir = std::env::temp_dir().join(format!(
+            "ipcc-uid-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        let sock_path = dir.join("listener.sock");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+        let server = thread::spawn(move || (listener.accept(), listener.accept()));
+        let me = std::os::unix::fs::MetadataExt::uid(
+            &std::fs::metadata(&sock_path).expect("socket should exist"),
+        );
+
+        let handshake = |uid: u32| HandshakeConfig {
+            require_server_uid: Some(uid),
+            auth_token: Some("secret".to_string()),
+            ..HandshakeConfig::default()
+        };
+        let refused = connect_with_config(&sock_path, &[COMMAND], &handshake(me + 1), None, None);
+        assert!(
+            matches!(
+                refused,
+                Err(PeerError::Transport(
+                    TransportError::UntrustedEndpoint { .. }
+                ))
+            ),
+            "a server running as another uid should be refused"
+        );
+        connect_with_config(&sock_path, &[COMMAND], &handshake(me), None, None)
+            .expect("a server running as this uid should be accepted");
+
+        // The refused client hung up without sending a handshake request.
+        let (refused, accepted) = server.join().expect("server thread should complete");
+        assert!(matches!(refused, Err(PeerError::Disconnected(_))));
+        assert!(accepted.is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
 
 #[cfg(all(test, windows))]
@@ -1,5 +1,9 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use crate::trace::Redacted;
+
 /// CONTROL message type: ping request.
 pub const CONTROL_PING: &str = "ping";
 /// CONTROL message type: ping response.
@@ -11,8 +15,23 @@ pub const CONTROL_SHUTDOWN_ACK: &str = "shutdown_ack";
 /// CONTROL message type: force-close request.
 pub const CONTROL_SHUTDOWN_FORCE: &str = "shutdown_force";
 
+/// CONTROL message type: present a new auth token on a live connection.
+pub const CONTROL_AUTH_REFRESH: &str = "auth_refresh";
+/// CONTROL message type: accept/reject verdict for an auth refresh.
+pub const CONTROL_AUTH_RESULT: &str = "auth_result";
+
+/// CONTROL message type: file descriptors sent with this frame belong to
+/// the next frame on the named channel.
+pub const CONTROL_FDS: &str = "fds";
+
+/// `PeerError::Disconnected` reason when an honored SHUTDOWN_FORCE closes
+/// the connection.
+pub const FORCED_SHUTDOWN_REASON: &str = "shutdown forced by remote";
+
 /// CONTROL channel message payload.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+///
+/// Debug output redacts the token carried by [`CONTROL_AUTH_REFRESH`].
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct ControlMessage {
     #[serde(rename = "type")]
     pub msg_type: String,
@@ -68,4 +87,90 @@ impl ControlMessage {
             timestamp: None,
         }
     }
+
+    /// Create an auth refresh carrying a new token.
+    pub fn auth_refresh(token: &str) -> Self {
+        Self {
+            msg_type: CONTROL_AUTH_REFRESH.to_string(),
+            payload: Some(serde_json::json!({ "token": token })),
+            timestamp: None,
+        }
+    }
+
+    /// Create an auth refresh verdict; `reason` explains a rejection.
+    pub fn auth_result(accepted: bool, reason: Option<&str>) -> Self {
+        let mut payload = serde_json::json!({ "accepted": accepted });
+        if let Some(reason) = reason {
+            payload["reason"] = reason.into();
+        }
+        Self {
+            msg_type: CONTROL_AUTH_RESULT.to_string(),
+            payload: Some(payload),
+            timestamp: None,
+        }
+    }
+
+    /// Announce `count` file descriptors for the next frame on `channel`.
+    pub fn fds(channel: u16, count: usize) -> Self {
+        Self {
+            msg_type: CONTROL_FDS.to_string(),
+            payload: Some(serde_json::json!({ "channel": channel, "count": count })),
+            timestamp: None,
+        }
+    }
+
+    /// The channel and descriptor count of a [`CONTROL_FDS`] message.
+    pub fn fds_announcement(&self) -> Option<(u16, usize)> {
+        if self.msg_type != CONTROL_FDS {
+            return None;
+        }
+        let payload = self.payload.as_ref()?;
+        let channel = u16::try_from(payload.get("channel")?.as_u64()?).ok()?;
+        let count = usize::try_from(payload.get("count")?.as_u64()?).ok()?;
+        Some((channel, count))
+    }
+
+    /// The token of an [`CONTROL_AUTH_REFRESH`] message.
+    pub fn auth_token(&self) -> Option<&str> {
+        if self.msg_type != CONTROL_AUTH_REFRESH {
+            return None;
+        }
+        self.payload.as_ref()?.get("token")?.as_str()
+    }
+}
+
+impl fmt::Debug for ControlMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut dbg = f.debug_struct("ControlMessage");
+        dbg.field("msg_type", &self.msg_type);
+        if self.msg_type == CONTROL_AUTH_REFRESH {
+            dbg.field(
+                "payload",
+                &format_args!("{{token: {}}}", Redacted(self.auth_token())),
+            );
+        } else {
+            dbg.field("payload", &self.payload);
+        }
+        dbg.field("timestamp", &self.timestamp);
+        dbg.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_refresh_debug_redacts_token() {
+        let message = ControlMessage::auth_refresh("super-secret");
+        assert_eq!(message.auth_token(), Some("super-secret"));
+
+        let debug = format!("{message:?}");
+        assert!(debug.contains("<redacted:12 bytes>"));
+        assert!(!debug.contains("super-secret"));
+
+        let result = ControlMessage::auth_result(false, Some("expired"));
+        assert_eq!(result.auth_token(), None);
+        assert!(format!("{result:?}").contains("expired"));
+    }
 }
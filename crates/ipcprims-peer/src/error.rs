@@ -1,13 +1,70 @@
+use std::fmt;
+use std::time::Duration;
+
+/// The operation a [`PeerError::Timeout`] interrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutOp {
+    /// Connecting to a listener that is not accepting yet.
+    Connect,
+    /// Sending the handshake request or response.
+    HandshakeWrite,
+    /// Waiting for the handshake request or response.
+    HandshakeRead,
+    /// Writing a frame; `channel` is `None` when the frame layer timed out
+    /// outside a channel-specific send.
+    Send { channel: Option<u16> },
+    /// Waiting for a frame; `channel` is `None` for a receive on any channel.
+    Recv { channel: Option<u16> },
+    /// Waiting for the pong to a ping.
+    Ping,
+    /// Waiting for the peer to acknowledge a shutdown request.
+    ShutdownAck,
+    /// Waiting for the verdict on an auth refresh.
+    AuthRefresh,
+}
+
+impl fmt::Display for TimeoutOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutOp::Connect => f.write_str("connect"),
+            TimeoutOp::HandshakeWrite => f.write_str("handshake write"),
+            TimeoutOp::HandshakeRead => f.write_str("handshake read"),
+            TimeoutOp::Send { channel: None } => f.write_str("send"),
+            TimeoutOp::Send {
+                channel: Some(channel),
+            } => write!(f, "send on channel {channel}"),
+            TimeoutOp::Recv { channel: None } => f.write_str("recv"),
+            TimeoutOp::Recv {
+                channel: Some(channel),
+            } => write!(f, "recv on channel {channel}"),
+            TimeoutOp::Ping => f.write_str("ping"),
+            TimeoutOp::ShutdownAck => f.write_str("shutdown ack"),
+            TimeoutOp::AuthRefresh => f.write_str("auth refresh"),
+        }
+    }
+}
+
 /// Errors that can occur in peer operations.
 #[derive(Debug, thiserror::Error)]
 pub enum PeerError {
     /// Transport-level error.
+    ///
+    /// [`TransportError::ConnectionReset`](ipcprims_transport::TransportError::ConnectionReset)
+    /// and [`TransportError::BrokenPipe`](ipcprims_transport::TransportError::BrokenPipe)
+    /// convert to [`PeerError::Disconnected`] instead.
     #[error("transport error: {0}")]
-    Transport(#[from] ipcprims_transport::TransportError),
+    Transport(#[source] ipcprims_transport::TransportError),
 
     /// Frame-level error.
+    ///
+    /// [`FrameError::ConnectionClosed`](ipcprims_frame::FrameError::ConnectionClosed)
+    /// converts to [`PeerError::Disconnected`],
+    /// [`FrameError::Timeout`](ipcprims_frame::FrameError::Timeout) to
+    /// [`PeerError::Timeout`] and
+    /// [`FrameError::Json`](ipcprims_frame::FrameError::Json) to
+    /// [`PeerError::Json`] instead.
     #[error("frame error: {0}")]
-    Frame(#[from] ipcprims_frame::FrameError),
+    Frame(#[source] ipcprims_frame::FrameError),
 
     /// Handshake failed.
     #[error("handshake failed: {0}")]
@@ -34,13 +91,118 @@ pub enum PeerError {
     #[error("schema validation error: {0}")]
     Schema(#[from] ipcprims_schema::SchemaError),
 
-    /// Request timed out.
-    #[error("request timed out after {0:?}")]
-    Timeout(std::time::Duration),
+    /// An operation timed out.
+    #[error("{operation} timed out after {waited:?}")]
+    Timeout {
+        /// What was being waited on.
+        operation: TimeoutOp,
+        /// How long it was waited on.
+        waited: Duration,
+    },
 
     /// Graceful shutdown failed.
     #[error("shutdown failed: {0}")]
     ShutdownFailed(String),
+
+    /// A frame of a [`Peer::send_transaction`](crate::Peer::send_transaction)
+    /// failed. `index` is its position in the group; frames before it may
+    /// already have been written.
+    #[error("transaction failed at frame {index}: {source}")]
+    TransactionFailed {
+        /// Index of the failing frame.
+        index: usize,
+        /// Why it failed.
+        #[source]
+        source: Box<PeerError>,
+    },
+
+    /// The remote rejected a refreshed auth token, or it was invalid locally.
+    #[error("auth refresh rejected: {0}")]
+    AuthRejected(String),
+
+    /// An earlier framing error left the inbound stream misaligned.
+    ///
+    /// Returned by operations that read from a poisoned [`Peer`](crate::Peer) until
+    /// [`Peer::resync`](crate::Peer::resync) succeeds. Holds the original
+    /// error text.
+    #[error("stream desynchronized after {0}; resync or reconnect")]
+    Desynchronized(String),
+}
+
+impl From<ipcprims_transport::TransportError> for PeerError {
+    fn from(err: ipcprims_transport::TransportError) -> Self {
+        match err {
+            ipcprims_transport::TransportError::ConnectionReset
+            | ipcprims_transport::TransportError::BrokenPipe => {
+                PeerError::Disconnected(err.to_string())
+            }
+            err => PeerError::Transport(err),
+        }
+    }
+}
+
+impl From<ipcprims_frame::FrameError> for PeerError {
+    fn from(err: ipcprims_frame::FrameError) -> Self {
+        match err {
+            ipcprims_frame::FrameError::ConnectionClosed => {
+                PeerError::Disconnected("connection closed".to_string())
+            }
+            ipcprims_frame::FrameError::Timeout(waited) => PeerError::Timeout {
+                operation: TimeoutOp::Send { channel: None },
+                waited,
+            },
+            ipcprims_frame::FrameError::Json(err) => PeerError::Json(err),
+            err => PeerError::Frame(err),
+        }
+    }
+}
+
+impl PeerError {
+    /// Attribute a timeout to `operation`; other errors pass through unchanged.
+    pub(crate) fn during(self, operation: TimeoutOp) -> Self {
+        match self {
+            PeerError::Timeout { waited, .. } => PeerError::Timeout { operation, waited },
+            err => err,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, PeerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_names_the_operation() {
+        let err = PeerError::Timeout {
+            operation: TimeoutOp::Recv { channel: Some(1) },
+            waited: Duration::from_secs(5),
+        };
+        assert_eq!(err.to_string(), "recv on channel 1 timed out after 5s");
+
+        let err = PeerError::from(ipcprims_frame::FrameError::Timeout(Duration::from_millis(
+            250,
+        )))
+        .during(TimeoutOp::HandshakeWrite);
+        assert_eq!(err.to_string(), "handshake write timed out after 250ms");
+    }
+
+    #[test]
+    fn transport_hang_ups_convert_to_disconnected() {
+        use ipcprims_transport::TransportError;
+
+        let err = PeerError::from(TransportError::ConnectionReset);
+        assert!(
+            matches!(&err, PeerError::Disconnected(msg) if msg == "connection reset by peer"),
+            "{err:?}"
+        );
+        let err = PeerError::from(TransportError::BrokenPipe);
+        assert!(matches!(err, PeerError::Disconnected(_)), "{err:?}");
+        let err = PeerError::from(TransportError::Shutdown);
+        assert!(
+            matches!(err, PeerError::Transport(TransportError::Shutdown)),
+            "{err:?}"
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use std::collections::HashSet;
 use std::fmt;
 use std::io::{ErrorKind, Read, Write};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 #[cfg(feature = "async")]
 use bytes::BytesMut;
-use ipcprims_frame::{FrameError, FrameReader, FrameWriter, CONTROL};
+use ipcprims_frame::{Frame, FrameError, FrameReader, FrameWriter, WireVersion, CONTROL};
 use serde::{Deserialize, Serialize};
 
-use crate::error::{PeerError, Result};
+use crate::error::{PeerError, Result, TimeoutOp};
+use crate::trace;
 
 #[cfg(feature = "async")]
 use ipcprims_frame::HEADER_SIZE;
@@ -17,7 +19,8 @@ const MAX_HANDSHAKE_CHANNELS: usize = 256;
 const MAX_PROTOCOL_LEN: usize = 32;
 const MAX_VERSION_LEN: usize = 16;
 const MAX_PEER_ID_LEN: usize = 128;
-const MAX_AUTH_TOKEN_LEN: usize = 4096;
+pub(crate) const MAX_AUTH_TOKEN_LEN: usize = 4096;
+const MAX_LIBRARY_VERSION_LEN: usize = 64;
 
 /// Client handshake request sent on CONTROL channel.
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -32,6 +35,24 @@ pub struct HandshakeRequest {
     /// Treated as opaque credential material and redacted in debug output.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth_token: Option<String>,
+    /// ipcprims version of the client, for diagnostics only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_version: Option<String>,
+    /// Client can decode frame priorities. Only used with `wire_version` 2
+    /// or later, whose versioned header carries them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frame_priority: bool,
+    /// Client can verify frame checksums. Only used with `wire_version` 2
+    /// or later, whose versioned header carries them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frame_checksum: bool,
+    /// Newest frame header version the client decodes (see
+    /// [`WireVersion`]). Absent means 1.
+    #[serde(
+        default = "wire_version_v1",
+        skip_serializing_if = "is_wire_version_v1"
+    )]
+    pub wire_version: u8,
 }
 
 /// Server handshake response sent on CONTROL channel.
@@ -45,6 +66,24 @@ pub struct HandshakeResponse {
     pub channels: Vec<u16>,
     /// Opaque server-assigned peer identifier.
     pub peer_id: String,
+    /// ipcprims version of the server, for diagnostics only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_version: Option<String>,
+    /// Both sides can decode frame priorities.
+    /// Only set when the request advertised it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frame_priority: bool,
+    /// Both sides can verify frame checksums. Only set when the request
+    /// advertised it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frame_checksum: bool,
+    /// Frame header version both sides decode: the newest the server knows
+    /// that is not newer than the request's. Absent means 1.
+    #[serde(
+        default = "wire_version_v1",
+        skip_serializing_if = "is_wire_version_v1"
+    )]
+    pub wire_version: u8,
 }
 
 /// Result of a successful handshake.
@@ -58,14 +97,59 @@ pub struct HandshakeResult {
     pub negotiated_channels: Vec<u16>,
     /// Client auth token observed by the server side.
     pub client_auth_token: Option<String>,
+    /// ipcprims version the remote side reported, if it sent one.
+    pub remote_library_version: Option<String>,
+    /// Both sides decode frame priorities in the versioned frame header, so
+    /// nonzero priorities may be sent. Otherwise every frame goes out at priority 0.
+    pub priority_frames: bool,
+    /// Both sides verify frame checksums in the versioned frame header, so
+    /// [`PeerConfig::checksum_frames`](crate::PeerConfig::checksum_frames)
+    /// can take effect.
+    pub checksum_frames: bool,
+    /// Newest frame header version both sides decode, so
+    /// [`PeerConfig::wire_version`](crate::PeerConfig::wire_version) is
+    /// capped at it.
+    pub wire_version: WireVersion,
 }
 
+/// Whether a remote advertising `wire_version` decodes the versioned frame
+/// header that priorities and checksums travel in.
+fn versioned_headers(wire_version: u8) -> bool {
+    WireVersion::at_most(wire_version) >= WireVersion::V2
+}
+
+fn wire_version_v1() -> u8 {
+    WireVersion::V1.as_u8()
+}
+
+fn is_wire_version_v1(version: &u8) -> bool {
+    *version == WireVersion::V1.as_u8()
+}
+
+/// Server-side hook deciding whether a client's auth token is accepted.
+///
+/// Receives the token presented by the client (`None` when it sent none).
+pub type AuthValidator = Arc<dyn Fn(Option<&str>) -> bool + Send + Sync>;
+
 /// Configuration for handshake negotiation.
 #[derive(Clone)]
 pub struct HandshakeConfig {
     /// Timeout for each blocking handshake operation.
     pub timeout: Duration,
-    /// Expected protocol name.
+    /// Bound on connecting to a Unix socket path in `connect_with_config`,
+    /// which otherwise blocks while the listener's backlog is full. `None`
+    /// uses [`timeout`](Self::timeout).
+    pub connect_timeout: Option<Duration>,
+    /// Client side: refuse a server that does not run as this uid, checked
+    /// once connected and before anything (such as the auth token) is
+    /// sent. A socket path must also be owned by the uid, in a directory
+    /// owned by it or root that others cannot write to without the sticky
+    /// bit. Transports without peer credentials (TCP, vsock, Windows named
+    /// pipes) always fail the check. Violations fail with
+    /// `TransportError::UntrustedEndpoint`.
+    pub require_server_uid: Option<u32>,
+    /// Expected protocol name: 1-32 bytes of lowercase ASCII letters,
+    /// digits, `-` and `_`.
     pub protocol_name: String,
     /// Local protocol version.
     pub protocol_version: String,
@@ -76,21 +160,41 @@ pub struct HandshakeConfig {
     /// Optional auth token sent by the client.
     /// This is transported as plaintext within local IPC and should not be logged.
     pub auth_token: Option<String>,
+    /// Optional server-side auth check. Clients it rejects fail the handshake
+    /// before any response is sent.
+    pub auth_validator: Option<AuthValidator>,
+    /// Report this crate's version to the remote side (default `true`).
+    pub send_library_version: bool,
 }
 
 impl Default for HandshakeConfig {
     fn default() -> Self {
         Self {
             timeout: Duration::from_secs(5),
+            connect_timeout: None,
+            require_server_uid: None,
             protocol_name: "ipcprims".to_string(),
             protocol_version: "1.0".to_string(),
             require_channel_overlap: true,
             max_handshake_payload: 16 * 1024,
             auth_token: None,
+            auth_validator: None,
+            send_library_version: true,
         }
     }
 }
 
+impl HandshakeConfig {
+    /// Set [`protocol_name`](Self::protocol_name), rejecting names outside
+    /// the allowed charset up front rather than at the first handshake.
+    pub fn with_protocol_name(mut self, name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        validate_protocol_name(&name)?;
+        self.protocol_name = name;
+        Ok(self)
+    }
+}
+
 impl fmt::Debug for HandshakeRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut dbg = f.debug_struct("HandshakeRequest");
@@ -105,6 +209,10 @@ impl fmt::Debug for HandshakeRequest {
         } else {
             dbg.field("auth_token", &Option::<String>::None);
         }
+        dbg.field("library_version", &self.library_version)
+            .field("frame_priority", &self.frame_priority)
+            .field("frame_checksum", &self.frame_checksum)
+            .field("wire_version", &self.wire_version);
         dbg.finish()
     }
 }
@@ -123,6 +231,10 @@ impl fmt::Debug for HandshakeResult {
         } else {
             dbg.field("client_auth_token", &Option::<String>::None);
         }
+        dbg.field("remote_library_version", &self.remote_library_version)
+            .field("priority_frames", &self.priority_frames)
+            .field("checksum_frames", &self.checksum_frames)
+            .field("wire_version", &self.wire_version);
         dbg.finish()
     }
 }
@@ -131,6 +243,8 @@ impl fmt::Debug for HandshakeConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut dbg = f.debug_struct("HandshakeConfig");
         dbg.field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("require_server_uid", &self.require_server_uid)
             .field("protocol_name", &self.protocol_name)
             .field("protocol_version", &self.protocol_version)
             .field("require_channel_overlap", &self.require_channel_overlap)
@@ -143,6 +257,11 @@ impl fmt::Debug for HandshakeConfig {
         } else {
             dbg.field("auth_token", &Option::<String>::None);
         }
+        dbg.field(
+            "auth_validator",
+            &self.auth_validator.as_ref().map(|_| "<fn>"),
+        );
+        dbg.field("send_library_version", &self.send_library_version);
         dbg.finish()
     }
 }
@@ -167,6 +286,18 @@ pub fn handshake_client_with_config<R: Read, W: Write>(
     writer: &mut FrameWriter<W>,
     requested_channels: &[u16],
     config: &HandshakeConfig,
+) -> Result<HandshakeResult> {
+    trace::handshake_started("client", requested_channels, config.auth_token.as_deref());
+    let result = client_handshake(reader, writer, requested_channels, config);
+    trace::handshake_finished("client", &result);
+    result
+}
+
+fn client_handshake<R: Read, W: Write>(
+    reader: &mut FrameReader<R>,
+    writer: &mut FrameWriter<W>,
+    requested_channels: &[u16],
+    config: &HandshakeConfig,
 ) -> Result<HandshakeResult> {
     validate_protocol_name(&config.protocol_name)?;
     validate_version(&config.protocol_version)?;
@@ -179,22 +310,27 @@ pub fn handshake_client_with_config<R: Read, W: Write>(
         version: config.protocol_version.clone(),
         channels: requested.clone(),
         auth_token: config.auth_token.clone(),
+        library_version: local_library_version(config),
+        frame_priority: true,
+        frame_checksum: true,
+        wire_version: WireVersion::LATEST.as_u8(),
     };
 
     send_control_json(writer, &req)?;
 
     let deadline = Instant::now() + config.timeout;
-    let payload = recv_control_payload(
+    let resp: HandshakeResponse = recv_control_frame(
         reader,
         deadline,
         config.timeout,
         config.max_handshake_payload,
-    )?;
-    let resp: HandshakeResponse = serde_json::from_slice(&payload)?;
+    )?
+    .parse_json()?;
 
     validate_protocol_name(&resp.protocol)?;
     validate_version(&resp.version)?;
     validate_peer_id(&resp.peer_id)?;
+    validate_library_version(resp.library_version.as_deref())?;
 
     if resp.protocol != config.protocol_name {
         return Err(PeerError::HandshakeFailed(format!(
@@ -232,6 +368,10 @@ pub fn handshake_client_with_config<R: Read, W: Write>(
         protocol_version: resp.version,
         negotiated_channels: negotiated,
         client_auth_token: None,
+        remote_library_version: resp.library_version,
+        priority_frames: resp.frame_priority && versioned_headers(resp.wire_version),
+        checksum_frames: resp.frame_checksum && versioned_headers(resp.wire_version),
+        wire_version: WireVersion::at_most(resp.wire_version),
     })
 }
 
@@ -258,6 +398,19 @@ pub fn handshake_server_with_config<R: Read, W: Write>(
     supported_channels: &[u16],
     peer_id: &str,
     config: &HandshakeConfig,
+) -> Result<HandshakeResult> {
+    trace::handshake_started("server", supported_channels, None);
+    let result = server_handshake(reader, writer, supported_channels, peer_id, config);
+    trace::handshake_finished("server", &result);
+    result
+}
+
+fn server_handshake<R: Read, W: Write>(
+    reader: &mut FrameReader<R>,
+    writer: &mut FrameWriter<W>,
+    supported_channels: &[u16],
+    peer_id: &str,
+    config: &HandshakeConfig,
 ) -> Result<HandshakeResult> {
     validate_protocol_name(&config.protocol_name)?;
     validate_version(&config.protocol_version)?;
@@ -266,17 +419,19 @@ pub fn handshake_server_with_config<R: Read, W: Write>(
     let supported = normalize_channels(supported_channels)?;
 
     let deadline = Instant::now() + config.timeout;
-    let payload = recv_control_payload(
+    let req: HandshakeRequest = recv_control_frame(
         reader,
         deadline,
         config.timeout,
         config.max_handshake_payload,
-    )?;
-    let req: HandshakeRequest = serde_json::from_slice(&payload)?;
+    )?
+    .parse_json()?;
 
     validate_protocol_name(&req.protocol)?;
     validate_version(&req.version)?;
     validate_auth_token(req.auth_token.as_deref())?;
+    validate_library_version(req.library_version.as_deref())?;
+    check_client_auth(config, req.auth_token.as_deref())?;
 
     if req.protocol != config.protocol_name {
         return Err(PeerError::HandshakeFailed(format!(
@@ -306,6 +461,10 @@ pub fn handshake_server_with_config<R: Read, W: Write>(
         version: config.protocol_version.clone(),
         channels: negotiated.clone(),
         peer_id: peer_id.to_string(),
+        library_version: local_library_version(config),
+        frame_priority: req.frame_priority,
+        frame_checksum: req.frame_checksum,
+        wire_version: WireVersion::at_most(req.wire_version).as_u8(),
     };
     send_control_json(writer, &resp)?;
 
@@ -314,15 +473,24 @@ pub fn handshake_server_with_config<R: Read, W: Write>(
         protocol_version: config.protocol_version.clone(),
         negotiated_channels: negotiated,
         client_auth_token: req.auth_token,
+        remote_library_version: req.library_version,
+        priority_frames: req.frame_priority && versioned_headers(req.wire_version),
+        checksum_frames: req.frame_checksum && versioned_headers(req.wire_version),
+        wire_version: WireVersion::at_most(req.wire_version),
     })
 }
 
 fn send_control_json<T: Serialize, W: Write>(writer: &mut FrameWriter<W>, value: &T) -> Result<()> {
-    let payload = serde_json::to_vec(value)?;
-    writer.send(CONTROL, &payload)?;
+    writer
+        .send_json(CONTROL, value)
+        .map_err(|err| PeerError::from(err).during(TimeoutOp::HandshakeWrite))?;
     Ok(())
 }
 
+fn timed_out(operation: TimeoutOp, waited: Duration) -> PeerError {
+    PeerError::Timeout { operation, waited }
+}
+
 #[cfg(feature = "async")]
 async fn send_control_json_async<W: tokio::io::AsyncWrite + Unpin>(
     writer: &mut W,
@@ -332,7 +500,7 @@ async fn send_control_json_async<W: tokio::io::AsyncWrite + Unpin>(
 ) -> Result<()> {
     let now = Instant::now();
     if now >= deadline {
-        return Err(PeerError::Timeout(timeout));
+        return Err(timed_out(TimeoutOp::HandshakeWrite, timeout));
     }
 
     let payload = serde_json::to_vec(value)?;
@@ -342,17 +510,17 @@ async fn send_control_json_async<W: tokio::io::AsyncWrite + Unpin>(
     let remaining = deadline.saturating_duration_since(now);
     tokio::time::timeout(remaining, tokio::io::AsyncWriteExt::write_all(writer, &buf))
         .await
-        .map_err(|_| PeerError::Timeout(timeout))?
+        .map_err(|_| timed_out(TimeoutOp::HandshakeWrite, timeout))?
         .map_err(|e| PeerError::Frame(FrameError::Io(e)))?;
 
     let now = Instant::now();
     if now >= deadline {
-        return Err(PeerError::Timeout(timeout));
+        return Err(timed_out(TimeoutOp::HandshakeWrite, timeout));
     }
     let remaining = deadline.saturating_duration_since(now);
     tokio::time::timeout(remaining, tokio::io::AsyncWriteExt::flush(writer))
         .await
-        .map_err(|_| PeerError::Timeout(timeout))?
+        .map_err(|_| timed_out(TimeoutOp::HandshakeWrite, timeout))?
         .map_err(|e| PeerError::Frame(FrameError::Io(e)))?;
     Ok(())
 }
@@ -394,23 +562,33 @@ mod async_write_timeout_tests {
             version: "1.0".to_string(),
             channels: vec![1],
             auth_token: None,
+            library_version: None,
+            frame_priority: false,
+            frame_checksum: false,
+            wire_version: 1,
         };
         let err = send_control_json_async(&mut w, &req, deadline, timeout)
             .await
             .unwrap_err();
-        assert!(matches!(err, PeerError::Timeout(_)));
+        assert!(matches!(
+            err,
+            PeerError::Timeout {
+                operation: TimeoutOp::HandshakeWrite,
+                ..
+            }
+        ));
     }
 }
 
-fn recv_control_payload<R: Read>(
+fn recv_control_frame<R: Read>(
     reader: &mut FrameReader<R>,
     deadline: Instant,
     timeout: Duration,
     max_handshake_payload: usize,
-) -> Result<Vec<u8>> {
+) -> Result<Frame> {
     loop {
         if Instant::now() >= deadline {
-            return Err(PeerError::Timeout(timeout));
+            return Err(timed_out(TimeoutOp::HandshakeRead, timeout));
         }
 
         match reader.read_frame() {
@@ -428,7 +606,7 @@ fn recv_control_payload<R: Read>(
                         max_handshake_payload
                     )));
                 }
-                return Ok(frame.payload.to_vec());
+                return Ok(frame);
             }
             Err(FrameError::Io(err))
                 if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
@@ -454,7 +632,7 @@ async fn recv_control_payload_async<R: tokio::io::AsyncRead + Unpin>(
 ) -> Result<Vec<u8>> {
     let now = Instant::now();
     if now >= deadline {
-        return Err(PeerError::Timeout(timeout));
+        return Err(timed_out(TimeoutOp::HandshakeRead, timeout));
     }
 
     // Read exactly one frame (header then payload), without over-reading. This prevents
@@ -466,7 +644,7 @@ async fn recv_control_payload_async<R: tokio::io::AsyncRead + Unpin>(
         tokio::io::AsyncReadExt::read_exact(reader, &mut header),
     )
     .await
-    .map_err(|_| PeerError::Timeout(timeout))?
+    .map_err(|_| timed_out(TimeoutOp::HandshakeRead, timeout))?
     .map_err(|e| {
         if e.kind() == std::io::ErrorKind::UnexpectedEof {
             PeerError::Disconnected("connection closed during handshake".to_string())
@@ -498,7 +676,7 @@ async fn recv_control_payload_async<R: tokio::io::AsyncRead + Unpin>(
 
     let now = Instant::now();
     if now >= deadline {
-        return Err(PeerError::Timeout(timeout));
+        return Err(timed_out(TimeoutOp::HandshakeRead, timeout));
     }
     let remaining = deadline - now;
 
@@ -508,7 +686,7 @@ async fn recv_control_payload_async<R: tokio::io::AsyncRead + Unpin>(
         tokio::io::AsyncReadExt::read_exact(reader, &mut payload),
     )
     .await
-    .map_err(|_| PeerError::Timeout(timeout))?
+    .map_err(|_| timed_out(TimeoutOp::HandshakeRead, timeout))?
     .map_err(|e| {
         if e.kind() == std::io::ErrorKind::UnexpectedEof {
             PeerError::Disconnected("connection closed during handshake".to_string())
@@ -528,6 +706,23 @@ pub async fn async_handshake_client_with_config<R, W>(
     requested_channels: &[u16],
     config: &HandshakeConfig,
 ) -> Result<HandshakeResult>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    trace::handshake_started("client", requested_channels, config.auth_token.as_deref());
+    let result = async_client_handshake(reader, writer, requested_channels, config).await;
+    trace::handshake_finished("client", &result);
+    result
+}
+
+#[cfg(feature = "async")]
+async fn async_client_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    requested_channels: &[u16],
+    config: &HandshakeConfig,
+) -> Result<HandshakeResult>
 where
     R: tokio::io::AsyncRead + Unpin,
     W: tokio::io::AsyncWrite + Unpin,
@@ -542,6 +737,10 @@ where
         version: config.protocol_version.clone(),
         channels: requested.clone(),
         auth_token: config.auth_token.clone(),
+        library_version: local_library_version(config),
+        frame_priority: true,
+        frame_checksum: true,
+        wire_version: WireVersion::LATEST.as_u8(),
     };
 
     let deadline = Instant::now() + config.timeout;
@@ -558,6 +757,7 @@ where
     validate_protocol_name(&resp.protocol)?;
     validate_version(&resp.version)?;
     validate_peer_id(&resp.peer_id)?;
+    validate_library_version(resp.library_version.as_deref())?;
 
     if resp.protocol != config.protocol_name {
         return Err(PeerError::HandshakeFailed(format!(
@@ -595,6 +795,10 @@ where
         protocol_version: resp.version,
         negotiated_channels: negotiated,
         client_auth_token: None,
+        remote_library_version: resp.library_version,
+        priority_frames: resp.frame_priority && versioned_headers(resp.wire_version),
+        checksum_frames: resp.frame_checksum && versioned_headers(resp.wire_version),
+        wire_version: WireVersion::at_most(resp.wire_version),
     })
 }
 
@@ -607,6 +811,24 @@ pub async fn async_handshake_server_with_config<R, W>(
     peer_id: &str,
     config: &HandshakeConfig,
 ) -> Result<HandshakeResult>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    trace::handshake_started("server", supported_channels, None);
+    let result = async_server_handshake(reader, writer, supported_channels, peer_id, config).await;
+    trace::handshake_finished("server", &result);
+    result
+}
+
+#[cfg(feature = "async")]
+async fn async_server_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    supported_channels: &[u16],
+    peer_id: &str,
+    config: &HandshakeConfig,
+) -> Result<HandshakeResult>
 where
     R: tokio::io::AsyncRead + Unpin,
     W: tokio::io::AsyncWrite + Unpin,
@@ -630,6 +852,8 @@ where
     validate_protocol_name(&req.protocol)?;
     validate_version(&req.version)?;
     validate_auth_token(req.auth_token.as_deref())?;
+    validate_library_version(req.library_version.as_deref())?;
+    check_client_auth(config, req.auth_token.as_deref())?;
 
     if req.protocol != config.protocol_name {
         return Err(PeerError::HandshakeFailed(format!(
@@ -659,6 +883,10 @@ where
         version: config.protocol_version.clone(),
         channels: negotiated.clone(),
         peer_id: peer_id.to_string(),
+        library_version: local_library_version(config),
+        frame_priority: req.frame_priority,
+        frame_checksum: req.frame_checksum,
+        wire_version: WireVersion::at_most(req.wire_version).as_u8(),
     };
     send_control_json_async(writer, &resp, deadline, config.timeout).await?;
 
@@ -667,6 +895,10 @@ where
         protocol_version: config.protocol_version.clone(),
         negotiated_channels: negotiated,
         client_auth_token: req.auth_token,
+        remote_library_version: req.library_version,
+        priority_frames: req.frame_priority && versioned_headers(req.wire_version),
+        checksum_frames: req.frame_checksum && versioned_headers(req.wire_version),
+        wire_version: WireVersion::at_most(req.wire_version),
     })
 }
 
@@ -712,6 +944,14 @@ fn validate_protocol_name(protocol: &str) -> Result<()> {
             protocol.len()
         )));
     }
+    // Names end up in error messages and logs, so keep them to a plain charset.
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+    if let Some((index, c)) = protocol.char_indices().find(|&(_, c)| !allowed(c)) {
+        return Err(PeerError::HandshakeFailed(format!(
+            "invalid character '{}' in protocol name at byte {index}",
+            c.escape_default()
+        )));
+    }
     Ok(())
 }
 
@@ -736,6 +976,24 @@ fn validate_peer_id(peer_id: &str) -> Result<()> {
     Ok(())
 }
 
+fn validate_library_version(library_version: Option<&str>) -> Result<()> {
+    if let Some(version) = library_version {
+        if version.is_empty() || version.len() > MAX_LIBRARY_VERSION_LEN {
+            return Err(PeerError::HandshakeFailed(format!(
+                "invalid library_version length: {}",
+                version.len()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn local_library_version(config: &HandshakeConfig) -> Option<String> {
+    config
+        .send_library_version
+        .then(|| env!("CARGO_PKG_VERSION").to_string())
+}
+
 fn validate_auth_token(auth_token: Option<&str>) -> Result<()> {
     if let Some(token) = auth_token {
         if token.is_empty() || token.len() > MAX_AUTH_TOKEN_LEN {
@@ -748,7 +1006,19 @@ fn validate_auth_token(auth_token: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-fn is_version_compatible(client_version: &str, server_version: &str) -> Result<bool> {
+fn check_client_auth(config: &HandshakeConfig, auth_token: Option<&str>) -> Result<()> {
+    match &config.auth_validator {
+        Some(validator) if !validator(auth_token) => Err(PeerError::HandshakeFailed(
+            "auth token rejected".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns whether a client speaking `client_version` can talk to a server speaking
+/// `server_version` (`"<major>.<minor>"`): majors must match and the client minor must be
+/// at least the server minor. This is the rule both handshake sides enforce.
+pub fn is_version_compatible(client_version: &str, server_version: &str) -> Result<bool> {
     let (client_major, client_minor) = parse_version(client_version)?;
     let (server_major, server_minor) = parse_version(server_version)?;
 
@@ -813,6 +1083,100 @@ mod tests {
         assert!(client_result.client_auth_token.is_none());
         assert_eq!(server_result.negotiated_channels, vec![1, 2, 3]);
         assert!(server_result.client_auth_token.is_none());
+        let local = Some(env!("CARGO_PKG_VERSION"));
+        assert_eq!(client_result.remote_library_version.as_deref(), local);
+        assert_eq!(server_result.remote_library_version.as_deref(), local);
+        assert_eq!(client_result.wire_version, WireVersion::LATEST);
+        assert_eq!(server_result.wire_version, WireVersion::LATEST);
+    }
+
+    #[test]
+    fn wire_version_downgrades_for_older_clients() {
+        // Peers that predate the field omit it and only decode v1 headers,
+        // so priorities and checksums cannot reach them either.
+        let legacy: HandshakeRequest = serde_json::from_str(
+            r#"{"protocol":"ipcprims","version":"1.0","channels":[1],"frame_priority":true,"frame_checksum":true}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.wire_version, 1);
+        assert!(!serde_json::to_string(&legacy)
+            .unwrap()
+            .contains("wire_version"));
+
+        let (left, right) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            let mut reader = FrameReader::new(left.try_clone().unwrap());
+            let mut writer = FrameWriter::new(left);
+            handshake_server(&mut reader, &mut writer, &[1], "peer-1").unwrap()
+        });
+
+        let mut reader = FrameReader::new(right.try_clone().unwrap());
+        let mut writer = FrameWriter::new(right);
+        send_control_json(&mut writer, &legacy).unwrap();
+        let frame = reader.read_frame().unwrap();
+        let response: HandshakeResponse = serde_json::from_slice(&frame.payload).unwrap();
+        assert_eq!(response.wire_version, 1);
+        let result = server.join().unwrap();
+        assert_eq!(result.wire_version, WireVersion::V1);
+        assert!(!result.priority_frames);
+        assert!(!result.checksum_frames);
+    }
+
+    #[test]
+    fn library_version_is_optional_on_the_wire() {
+        // Peers that predate the field omit it; both sides still agree.
+        let legacy: HandshakeRequest =
+            serde_json::from_str(r#"{"protocol":"ipcprims","version":"1.0","channels":[1]}"#)
+                .unwrap();
+        assert!(legacy.library_version.is_none());
+
+        let (left, right) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            let mut reader = FrameReader::new(left.try_clone().unwrap());
+            let mut writer = FrameWriter::new(left);
+            handshake_server(&mut reader, &mut writer, &[1], "peer-1").unwrap()
+        });
+
+        let mut reader = FrameReader::new(right.try_clone().unwrap());
+        let mut writer = FrameWriter::new(right);
+        let config = HandshakeConfig {
+            send_library_version: false,
+            ..HandshakeConfig::default()
+        };
+        let client_result =
+            handshake_client_with_config(&mut reader, &mut writer, &[1], &config).unwrap();
+        let server_result = server.join().unwrap();
+
+        assert!(server_result.remote_library_version.is_none());
+        assert!(client_result.remote_library_version.is_some());
+    }
+
+    #[test]
+    fn overlong_library_version_rejected() {
+        let (left, right) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            let mut reader = FrameReader::new(left.try_clone().unwrap());
+            let mut writer = FrameWriter::new(left);
+            handshake_server(&mut reader, &mut writer, &[1], "peer-1")
+        });
+
+        let mut writer = FrameWriter::new(right);
+        let request = HandshakeRequest {
+            protocol: "ipcprims".to_string(),
+            version: "1.0".to_string(),
+            channels: vec![1],
+            auth_token: None,
+            library_version: Some("x".repeat(MAX_LIBRARY_VERSION_LEN + 1)),
+            frame_priority: false,
+            frame_checksum: false,
+            wire_version: 1,
+        };
+        send_control_json(&mut writer, &request).unwrap();
+
+        assert!(matches!(
+            server.join().unwrap(),
+            Err(PeerError::HandshakeFailed(msg)) if msg.contains("library_version")
+        ));
     }
 
     #[test]
@@ -878,6 +1242,56 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn protocol_name_charset_enforced_in_config() {
+        assert!(HandshakeConfig::default()
+            .with_protocol_name("my-proto_2")
+            .is_ok());
+
+        for (name, shown) in [
+            ("ipc\0prims", "'\\u{0}'"),
+            ("ipc prims", "' '"),
+            ("ipc\nprims", "'\\n'"),
+            ("IpcPrims", "'I'"),
+        ] {
+            let err = HandshakeConfig::default()
+                .with_protocol_name(name)
+                .expect_err("name should be rejected");
+            assert!(
+                matches!(&err, PeerError::HandshakeFailed(msg) if msg.contains(shown)),
+                "{name:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn received_protocol_name_with_nul_rejected() {
+        let (left, right) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            let mut reader = FrameReader::new(left.try_clone().unwrap());
+            let mut writer = FrameWriter::new(left);
+            handshake_server(&mut reader, &mut writer, &[1], "peer-1")
+        });
+
+        let mut writer = FrameWriter::new(right);
+        let request = HandshakeRequest {
+            protocol: "ipcprims\0".to_string(),
+            version: "1.0".to_string(),
+            channels: vec![1],
+            auth_token: None,
+            library_version: None,
+            frame_priority: false,
+            frame_checksum: false,
+            wire_version: 1,
+        };
+        send_control_json(&mut writer, &request).unwrap();
+
+        assert!(matches!(
+            server.join().unwrap(),
+            Err(PeerError::HandshakeFailed(msg)) if msg.contains("'\\u{0}' in protocol name at byte 8")
+        ));
+    }
+
     #[test]
     fn invalid_json_rejected() {
         let (left, right) = UnixStream::pair().unwrap();
@@ -901,7 +1315,13 @@ mod tests {
         };
 
         let result = handshake_client_with_config(&mut reader, &mut writer, &[1], &cfg);
-        assert!(matches!(result, Err(PeerError::Timeout(_))));
+        assert!(matches!(
+            result,
+            Err(PeerError::Timeout {
+                operation: TimeoutOp::HandshakeRead,
+                waited,
+            }) if waited == Duration::from_millis(25)
+        ));
     }
 
     #[test]
@@ -929,6 +1349,15 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn version_compatibility_rule() {
+        assert!(is_version_compatible("1.0", "1.0").unwrap());
+        assert!(is_version_compatible("1.2", "1.1").unwrap());
+        assert!(!is_version_compatible("1.0", "1.1").unwrap());
+        assert!(!is_version_compatible("2.0", "1.0").unwrap());
+        assert!(is_version_compatible("1", "1.0").is_err());
+    }
+
     #[test]
     fn uds_integration_handshake_roundtrip() {
         let dir = std::env::temp_dir().join(format!(
@@ -1000,6 +1429,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn auth_validator_rejects_before_response() {
+        let (left, right) = UnixStream::pair().unwrap();
+
+        let server = thread::spawn(move || {
+            let mut reader = FrameReader::new(left.try_clone().unwrap());
+            let mut writer = FrameWriter::new(left);
+            let cfg = HandshakeConfig {
+                auth_validator: Some(Arc::new(|token| token == Some("expected"))),
+                ..HandshakeConfig::default()
+            };
+            handshake_server_with_config(&mut reader, &mut writer, &[1], "peer-auth", &cfg)
+        });
+
+        let mut reader = FrameReader::new(right.try_clone().unwrap());
+        let mut writer = FrameWriter::new(right);
+        let cfg = HandshakeConfig {
+            auth_token: Some("wrong".to_string()),
+            ..HandshakeConfig::default()
+        };
+        let client_result = handshake_client_with_config(&mut reader, &mut writer, &[1], &cfg);
+        assert!(matches!(client_result, Err(PeerError::Disconnected(_))));
+        assert!(matches!(
+            server.join().unwrap(),
+            Err(PeerError::HandshakeFailed(reason)) if reason == "auth token rejected"
+        ));
+    }
+
     #[test]
     fn rejects_oversized_auth_token() {
         let mut reader = FrameReader::new(Cursor::new(Vec::<u8>::new()));
@@ -1047,6 +1504,10 @@ mod tests {
             version: "1.0".to_string(),
             channels: vec![1, 2],
             auth_token: Some("super-secret".to_string()),
+            library_version: None,
+            frame_priority: false,
+            frame_checksum: false,
+            wire_version: 1,
         };
         let request_debug = format!("{request:?}");
         assert!(request_debug.contains("<redacted:12 bytes>"));
@@ -1065,6 +1526,10 @@ mod tests {
             protocol_version: "1.0".to_string(),
             negotiated_channels: vec![1],
             client_auth_token: Some("token-123".to_string()),
+            remote_library_version: None,
+            priority_frames: false,
+            checksum_frames: false,
+            wire_version: WireVersion::V1,
         };
         let result_debug = format!("{result:?}");
         assert!(result_debug.contains("<redacted:9 bytes>"));
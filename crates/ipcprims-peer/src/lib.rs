@@ -8,7 +8,11 @@ pub mod control;
 pub mod error;
 pub mod handshake;
 pub mod listener;
+pub mod metrics;
 pub mod peer;
+pub mod split;
+
+mod trace;
 
 #[cfg(feature = "async")]
 pub mod async_connector;
@@ -17,18 +21,27 @@ pub mod async_listener;
 #[cfg(feature = "async")]
 pub mod async_peer;
 
-pub use connector::{connect, connect_with_config};
+pub use connector::{
+    connect, connect_named, connect_over, connect_with_config, connect_with_retry,
+};
 pub use control::{
-    ControlMessage, CONTROL_PING, CONTROL_PONG, CONTROL_SHUTDOWN_ACK, CONTROL_SHUTDOWN_FORCE,
-    CONTROL_SHUTDOWN_REQUEST,
+    ControlMessage, CONTROL_AUTH_REFRESH, CONTROL_AUTH_RESULT, CONTROL_FDS, CONTROL_PING,
+    CONTROL_PONG, CONTROL_SHUTDOWN_ACK, CONTROL_SHUTDOWN_FORCE, CONTROL_SHUTDOWN_REQUEST,
+    FORCED_SHUTDOWN_REASON,
 };
-pub use error::{PeerError, Result};
+pub use error::{PeerError, Result, TimeoutOp};
 pub use handshake::{
     handshake_client, handshake_client_with_config, handshake_server, handshake_server_with_config,
-    HandshakeConfig, HandshakeRequest, HandshakeResponse, HandshakeResult,
+    is_version_compatible, AuthValidator, HandshakeConfig, HandshakeRequest, HandshakeResponse,
+    HandshakeResult,
 };
-pub use listener::PeerListener;
-pub use peer::{Peer, PeerConfig};
+#[cfg(unix)]
+pub use ipcprims_transport::{BindOptions, PathWatchdog};
+pub use ipcprims_transport::{IpcAddr, Listener, PeerCredentials, RetryPolicy, TransportStream};
+pub use listener::{EndpointListener, PeerListener};
+pub use metrics::ListenerMetrics;
+pub use peer::{Peer, PeerConfig, PeerRole, ShutdownOutcome, ShutdownPolicy};
+pub use split::{ChannelHandle, PeerReceiver, PeerSender};
 
 #[cfg(feature = "async")]
 pub use async_connector::{async_connect, async_connect_with_config};
@@ -1,65 +1,310 @@
+#[cfg(unix)]
+use std::os::fd::OwnedFd;
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
-#[cfg_attr(not(unix), allow(unused_imports))]
 use ipcprims_frame::{
-    FrameConfig, FrameReader, FrameWriter, COMMAND, DATA, DEFAULT_MAX_PAYLOAD, ERROR, TELEMETRY,
+    ChannelMap, FrameConfig, FrameReader, FrameWriter, COMMAND, DATA, ERROR, TELEMETRY,
 };
 #[cfg(windows)]
 use ipcprims_transport::NamedPipeListener;
+#[cfg(feature = "tcp")]
+use ipcprims_transport::TcpTransport;
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+use ipcprims_transport::VsockTransport;
 #[cfg(unix)]
-use ipcprims_transport::UnixDomainSocket;
+use ipcprims_transport::{BindOptions, PathWatchdog, UnixDomainSocket};
+use ipcprims_transport::{IpcStream, Listener, TransportError, TransportStream};
 
-use crate::error::Result;
-#[cfg_attr(not(unix), allow(unused_imports))]
-use crate::handshake::handshake_server_with_config;
-use crate::handshake::HandshakeConfig;
+use crate::error::{PeerError, Result};
+use crate::handshake::{handshake_server_with_config, HandshakeConfig};
+use crate::metrics::{ListenerCounters, ListenerMetrics};
 use crate::peer::{Peer, PeerConfig, SchemaRegistryHandle};
+use crate::trace;
 
-/// Listens for and accepts peer connections.
-pub struct PeerListener {
+/// The listener behind [`PeerListener::bind`]: whichever transport the
+/// endpoint string names.
+pub struct EndpointListener {
+    socket: ListenerSocket,
+}
+
+impl EndpointListener {
+    /// Bind the endpoint `path` names; see [`PeerListener::bind`].
+    pub fn bind(path: impl AsRef<Path>) -> ipcprims_transport::Result<Self> {
+        ListenerSocket::bind(path.as_ref()).map(|socket| Self { socket })
+    }
+
+    /// Bind like [`Self::bind`], setting up a filesystem socket path as
+    /// `options` asks; see [`PeerListener::bind_with`].
+    #[cfg(unix)]
+    pub fn bind_with(
+        path: impl AsRef<Path>,
+        options: BindOptions,
+    ) -> ipcprims_transport::Result<Self> {
+        let path = path.as_ref();
+        #[cfg(feature = "tcp")]
+        if TcpTransport::endpoint_addr(path).is_some() {
+            return Self::bind(path);
+        }
+        #[cfg(all(target_os = "linux", feature = "vsock"))]
+        if VsockTransport::endpoint_addr(path).is_some() {
+            return Self::bind(path);
+        }
+        if UnixDomainSocket::abstract_name(path).is_some() {
+            return Self::bind(path);
+        }
+        let socket = UnixDomainSocket::bind_with(path, options)?;
+        Ok(Self {
+            socket: ListenerSocket::Unix(socket),
+        })
+    }
+
+    /// Adopt a listening Unix socket; see [`PeerListener::from_listener_fd`].
     #[cfg(unix)]
-    socket: UnixDomainSocket,
+    pub fn from_listener_fd(fd: OwnedFd) -> ipcprims_transport::Result<Self> {
+        let socket = UnixDomainSocket::from_listener_fd(fd)?;
+        Ok(Self {
+            socket: ListenerSocket::Unix(socket),
+        })
+    }
+
+    /// Export the listening socket; see [`PeerListener::into_listener_fd`].
+    #[cfg(unix)]
+    pub fn into_listener_fd(self) -> ipcprims_transport::Result<OwnedFd> {
+        match self.socket {
+            ListenerSocket::Unix(socket) => socket.into_listener_fd(),
+            #[cfg(any(feature = "tcp", all(target_os = "linux", feature = "vsock")))]
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "only unix socket listeners can be handed over",
+            )
+            .into()),
+        }
+    }
+
+    /// Watch a filesystem socket path; see [`PeerListener::with_path_watchdog`].
+    #[cfg(unix)]
+    pub fn with_path_watchdog(self, watchdog: impl Into<PathWatchdog>) -> Self {
+        let socket = match self.socket {
+            ListenerSocket::Unix(socket) => {
+                ListenerSocket::Unix(socket.with_path_watchdog(watchdog))
+            }
+            #[cfg(any(feature = "tcp", all(target_os = "linux", feature = "vsock")))]
+            socket => socket,
+        };
+        Self { socket }
+    }
+}
+
+impl Listener for EndpointListener {
+    type Stream = IpcStream;
+
+    fn accept(&self) -> ipcprims_transport::Result<IpcStream> {
+        self.socket.accept()
+    }
+
+    fn accept_timeout(&self, timeout: Duration) -> ipcprims_transport::Result<Option<IpcStream>> {
+        self.socket.accept_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.socket.shutdown();
+    }
+
+    fn path(&self) -> &Path {
+        self.socket.path()
+    }
+}
+
+/// The transport an [`EndpointListener`] accepts on.
+enum ListenerSocket {
+    #[cfg(unix)]
+    Unix(UnixDomainSocket),
     #[cfg(windows)]
-    socket: NamedPipeListener,
+    NamedPipe(NamedPipeListener),
+    #[cfg(feature = "tcp")]
+    Tcp(TcpTransport),
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    Vsock(VsockTransport),
+}
+
+impl ListenerSocket {
+    fn bind(path: &Path) -> ipcprims_transport::Result<Self> {
+        #[cfg(feature = "tcp")]
+        if let Some(addr) = TcpTransport::endpoint_addr(path) {
+            return TcpTransport::bind(addr).map(Self::Tcp);
+        }
+
+        #[cfg(all(target_os = "linux", feature = "vsock"))]
+        if let Some((cid, port)) = VsockTransport::endpoint_addr(path) {
+            return VsockTransport::bind(cid, port).map(Self::Vsock);
+        }
+
+        #[cfg(unix)]
+        {
+            match UnixDomainSocket::abstract_name(path) {
+                Some(name) => UnixDomainSocket::bind_abstract(name).map(Self::Unix),
+                None => UnixDomainSocket::bind(path).map(Self::Unix),
+            }
+        }
+
+        #[cfg(windows)]
+        {
+            NamedPipeListener::bind(path).map(Self::NamedPipe)
+        }
+    }
+
+    fn accept(&self) -> ipcprims_transport::Result<IpcStream> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(socket) => socket.accept(),
+            #[cfg(windows)]
+            Self::NamedPipe(socket) => socket.accept(),
+            #[cfg(feature = "tcp")]
+            Self::Tcp(socket) => socket.accept(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            Self::Vsock(socket) => socket.accept(),
+        }
+    }
+
+    fn accept_timeout(&self, timeout: Duration) -> ipcprims_transport::Result<Option<IpcStream>> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(socket) => socket.accept_timeout(timeout),
+            #[cfg(windows)]
+            Self::NamedPipe(socket) => socket.accept_timeout(timeout),
+            #[cfg(feature = "tcp")]
+            Self::Tcp(socket) => socket.accept_timeout(timeout),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            Self::Vsock(socket) => socket.accept_timeout(timeout),
+        }
+    }
+
+    fn shutdown(&self) {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(socket) => socket.shutdown(),
+            #[cfg(windows)]
+            Self::NamedPipe(socket) => socket.shutdown(),
+            #[cfg(feature = "tcp")]
+            Self::Tcp(socket) => socket.shutdown(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            Self::Vsock(socket) => socket.shutdown(),
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(socket) => socket.path(),
+            #[cfg(windows)]
+            Self::NamedPipe(socket) => socket.path(),
+            #[cfg(feature = "tcp")]
+            Self::Tcp(socket) => socket.path(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            Self::Vsock(socket) => socket.path(),
+        }
+    }
+}
+
+/// Listens for and accepts peer connections.
+///
+/// [`bind`](Self::bind) picks the transport from the endpoint string. For
+/// any other transport, wrap a [`Listener`] with
+/// [`from_listener`](Self::from_listener).
+pub struct PeerListener<L = EndpointListener> {
+    socket: L,
     supported_channels: Vec<u16>,
     handshake_config: HandshakeConfig,
-    #[cfg_attr(not(unix), allow(dead_code))]
     schema_registry: Option<SchemaRegistryHandle>,
     peer_config: PeerConfig,
     next_peer_id: AtomicU64,
+    counters: ListenerCounters,
 }
 
 impl PeerListener {
     /// Bind to a Unix domain socket path.
+    ///
+    /// On Linux, `@name` binds `name` in the abstract socket namespace
+    /// instead (see `UnixDomainSocket::bind_abstract`). With the `tcp`
+    /// feature, `tcp://127.0.0.1:9000` listens on loopback TCP (see
+    /// `TcpTransport::bind`); port 0 picks a free port, which
+    /// [`path`](Self::path) reports. With the `vsock` feature (Linux),
+    /// `vsock://4294967295:5000` listens on port 5000 of every CID (see
+    /// `VsockTransport::bind`).
     pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
-        #[cfg(unix)]
-        {
-            let socket = UnixDomainSocket::bind(path)?;
-            Ok(Self {
-                socket,
-                supported_channels: vec![COMMAND, DATA, TELEMETRY, ERROR],
-                handshake_config: HandshakeConfig::default(),
-                schema_registry: None,
-                peer_config: PeerConfig::default(),
-                next_peer_id: AtomicU64::new(1),
-            })
-        }
+        Ok(Self::from_listener(EndpointListener::bind(path)?))
+    }
 
-        #[cfg(windows)]
-        {
-            let socket = NamedPipeListener::bind(path)?;
-            Ok(Self {
-                socket,
-                supported_channels: vec![COMMAND, DATA, TELEMETRY, ERROR],
-                handshake_config: HandshakeConfig::default(),
-                schema_registry: None,
-                peer_config: PeerConfig::default(),
-                next_peer_id: AtomicU64::new(1),
-            })
+    /// Bind like [`Self::bind`], setting up a filesystem socket path as
+    /// `options` asks (mode, backlog, owning group); see
+    /// `UnixDomainSocket::bind_with`.
+    ///
+    /// Abstract (`@name`), TCP and vsock endpoints have no path to set up
+    /// and ignore `options`.
+    #[cfg(unix)]
+    pub fn bind_with(path: impl AsRef<Path>, options: BindOptions) -> Result<Self> {
+        Ok(Self::from_listener(EndpointListener::bind_with(
+            path, options,
+        )?))
+    }
+
+    /// Watch the socket path while accepting.
+    ///
+    /// Once the path is deleted or replaced, `accept` fails with
+    /// `TransportError::PathLost` unless the watchdog rebinds it. Ignored
+    /// for TCP and vsock endpoints.
+    #[cfg(unix)]
+    pub fn with_path_watchdog(mut self, watchdog: impl Into<PathWatchdog>) -> Self {
+        self.socket = self.socket.with_path_watchdog(watchdog);
+        self
+    }
+
+    /// Take over a listening Unix socket from a previous instance, for a
+    /// restart that drops no connections; see
+    /// `UnixDomainSocket::from_listener_fd`.
+    ///
+    /// Peers the old instance already accepted keep running there; clients
+    /// that connect during the handoff are accepted here.
+    #[cfg(unix)]
+    pub fn from_listener_fd(fd: OwnedFd) -> Result<Self> {
+        Ok(Self::from_listener(EndpointListener::from_listener_fd(fd)?))
+    }
+
+    /// Stop accepting and hand the listening socket to a successor, which
+    /// adopts it with [`from_listener_fd`](Self::from_listener_fd); see
+    /// `UnixDomainSocket::into_listener_fd`.
+    ///
+    /// Accepted peers are unaffected. TCP and vsock listeners fail with
+    /// `Unsupported`.
+    #[cfg(unix)]
+    pub fn into_listener_fd(self) -> Result<OwnedFd> {
+        Ok(self.socket.into_listener_fd()?)
+    }
+}
+
+impl<L: Listener> PeerListener<L> {
+    /// Serve peers over an already bound `listener`, such as a
+    /// `UnixDomainSocket` set up by hand or a transport from outside this
+    /// crate.
+    pub fn from_listener(listener: L) -> Self {
+        Self {
+            socket: listener,
+            supported_channels: vec![COMMAND, DATA, TELEMETRY, ERROR],
+            handshake_config: HandshakeConfig::default(),
+            schema_registry: None,
+            peer_config: PeerConfig::default(),
+            next_peer_id: AtomicU64::new(1),
+            counters: ListenerCounters::default(),
         }
     }
 
+    /// The listener peers are accepted from.
+    pub fn get_ref(&self) -> &L {
+        &self.socket
+    }
+
     /// Override the supported channel set.
     ///
     /// This is the authorization boundary for channel negotiation.
@@ -68,6 +313,17 @@ impl PeerListener {
         self
     }
 
+    /// Also support every channel named in `map`, on top of the current
+    /// set.
+    pub fn with_channel_map(mut self, map: &ChannelMap) -> Self {
+        for id in map.ids() {
+            if !self.supported_channels.contains(&id) {
+                self.supported_channels.push(id);
+            }
+        }
+        self
+    }
+
     /// Override handshake config.
     pub fn with_handshake_config(mut self, config: HandshakeConfig) -> Self {
         self.handshake_config = config;
@@ -84,6 +340,18 @@ impl PeerListener {
         self
     }
 
+    /// Accept only clients whose auth token satisfies `validator`.
+    ///
+    /// Rejected clients fail the handshake. Call after `with_handshake_config`,
+    /// which replaces the whole handshake config.
+    pub fn with_auth_validator(
+        mut self,
+        validator: impl Fn(Option<&str>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.handshake_config.auth_validator = Some(std::sync::Arc::new(validator));
+        self
+    }
+
     /// Override peer behavior config.
     pub fn with_peer_config(mut self, config: PeerConfig) -> Self {
         self.peer_config = config;
@@ -98,100 +366,138 @@ impl PeerListener {
 
     /// Accept next connection and use explicit peer id.
     pub fn accept_with_id(&self, peer_id: &str) -> Result<Peer> {
-        #[cfg(unix)]
-        {
-            let stream = self.socket.accept()?;
-            let reader_stream = stream.try_clone()?;
-
-            let frame_config = FrameConfig {
-                max_payload_size: self.handshake_config.max_handshake_payload,
-                read_timeout: Some(self.handshake_config.timeout),
-                write_timeout: Some(self.handshake_config.timeout),
-            };
-
-            let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
-            let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
-
-            let handshake = handshake_server_with_config(
-                &mut reader,
-                &mut writer,
-                &self.supported_channels,
-                peer_id,
-                &self.handshake_config,
-            )?;
-            // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
-            reader.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-            writer.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-
-            Ok(Peer::from_parts(
-                peer_id.to_string(),
-                reader,
-                writer,
-                handshake,
-                self.schema_registry.clone(),
-                self.peer_config.clone(),
-            ))
-        }
+        let stream = self.socket.accept()?;
+        self.finish_accept(stream.into_ipc_stream(), peer_id)
+    }
 
-        #[cfg(windows)]
-        {
-            let stream = self.socket.accept()?;
-            let reader_stream = stream.try_clone()?;
-
-            let frame_config = FrameConfig {
-                max_payload_size: self.handshake_config.max_handshake_payload,
-                read_timeout: Some(self.handshake_config.timeout),
-                write_timeout: Some(self.handshake_config.timeout),
-            };
-
-            let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
-            let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
-
-            let handshake = handshake_server_with_config(
-                &mut reader,
-                &mut writer,
-                &self.supported_channels,
-                peer_id,
-                &self.handshake_config,
-            )?;
-            // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
-            reader.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-            writer.set_max_payload_size(DEFAULT_MAX_PAYLOAD);
-
-            Ok(Peer::from_parts(
-                peer_id.to_string(),
-                reader,
-                writer,
-                handshake,
-                self.schema_registry.clone(),
-                self.peer_config.clone(),
-            ))
-        }
+    /// Iterate over accepted peers, handshaking each connection in turn.
+    ///
+    /// A client that fails the handshake yields its error and the next
+    /// item waits for the next client, so one bad client does not end an
+    /// accept loop. Other accept errors are yielded too; stop on ones that
+    /// persist, such as `TransportError::PathLost`. The iterator ends once
+    /// [`Self::shutdown`] has been called.
+    pub fn incoming(&self) -> impl Iterator<Item = Result<Peer>> + '_ {
+        std::iter::from_fn(move || match self.accept() {
+            Err(PeerError::Transport(TransportError::Shutdown)) => None,
+            result => Some(result),
+        })
     }
 
-    /// Bound socket path.
-    pub fn path(&self) -> &Path {
-        #[cfg(unix)]
-        {
-            self.socket.path()
-        }
+    /// Wait at most `timeout` for a client, then handshake as [`Self::accept`].
+    ///
+    /// Returns `Ok(None)` if no client connected in time, so an accept loop
+    /// can check a stop flag between attempts. The timeout covers only the
+    /// wait; the handshake is bounded by the handshake config's timeout. A
+    /// peer id is assigned only once a client arrives.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<Option<Peer>> {
+        let Some(stream) = self.socket.accept_timeout(timeout)? else {
+            return Ok(None);
+        };
+        let id = self.next_peer_id.fetch_add(1, Ordering::Relaxed);
+        self.finish_accept(stream.into_ipc_stream(), &format!("peer-{id}"))
+            .map(Some)
+    }
 
-        #[cfg(windows)]
-        {
-            self.socket.path()
+    fn finish_accept(&self, stream: IpcStream, peer_id: &str) -> Result<Peer> {
+        let result = self.establish(stream, peer_id);
+        match &result {
+            Ok(_) => self.counters.record_accept(),
+            Err(err) => self.counters.record_failure(err),
         }
+        result
+    }
+
+    /// Stop accepting connections.
+    ///
+    /// A blocked [`Self::accept`] or [`Self::accept_timeout`] (on another
+    /// thread) and every later one fail
+    /// with `TransportError::Shutdown`. Already accepted peers are unaffected;
+    /// the socket path is still removed when the listener is dropped.
+    pub fn shutdown(&self) {
+        self.socket.shutdown();
+    }
+
+    /// Accept and handshake counters since bind.
+    pub fn metrics(&self) -> ListenerMetrics {
+        self.counters.snapshot()
+    }
+
+    /// Run the server handshake on a freshly accepted stream.
+    fn establish(&self, stream: IpcStream, peer_id: &str) -> Result<Peer> {
+        establish_server(
+            stream,
+            peer_id,
+            &self.supported_channels,
+            &self.handshake_config,
+            self.schema_registry.clone(),
+            self.peer_config.clone(),
+        )
+    }
+
+    /// Bound socket path, or `tcp://host:port` / `vsock://cid:port` for a
+    /// TCP or vsock endpoint.
+    pub fn path(&self) -> &Path {
+        self.socket.path()
     }
 }
 
+/// Run the server handshake on an accepted (or otherwise connected) stream.
+pub(crate) fn establish_server(
+    stream: IpcStream,
+    peer_id: &str,
+    supported_channels: &[u16],
+    handshake_config: &HandshakeConfig,
+    schema_registry: Option<SchemaRegistryHandle>,
+    peer_config: PeerConfig,
+) -> Result<Peer> {
+    peer_config.apply_buffer_sizes(&stream)?;
+    let reader_stream = stream.try_clone()?;
+
+    let frame_config = FrameConfig {
+        max_payload_size: handshake_config.max_handshake_payload,
+        read_timeout: Some(handshake_config.timeout),
+        write_timeout: Some(handshake_config.timeout),
+        ..FrameConfig::default()
+    };
+
+    let mut reader = FrameReader::with_config_ipc(reader_stream, frame_config.clone())?;
+    let mut writer = FrameWriter::with_config_ipc(stream, frame_config)?;
+
+    let span = trace::connection_span(Some(peer_id));
+    let _entered = span.clone().entered();
+    let handshake = handshake_server_with_config(
+        &mut reader,
+        &mut writer,
+        supported_channels,
+        peer_id,
+        handshake_config,
+    )?;
+    // Handshake uses a tighter pre-auth payload budget; restore runtime defaults after auth.
+    reader.set_max_payload_size(peer_config.max_payload_size);
+    writer.set_max_payload_size(peer_config.max_payload_size);
+
+    Ok(Peer::from_parts(
+        peer_id.to_string(),
+        reader,
+        writer,
+        handshake,
+        schema_registry,
+        peer_config,
+        span,
+    ))
+}
+
 #[cfg(all(test, unix))]
 mod tests {
     use std::path::PathBuf;
     use std::thread;
 
     use ipcprims_frame::{COMMAND, DATA};
+    use ipcprims_transport::{IpcAddr, SetTimeouts, TryClone};
 
     use super::*;
-    use crate::connector::connect;
+    use crate::connector::{connect, connect_named, connect_over};
 
     fn make_sock_path(tag: &str) -> PathBuf {
         let dir = std::path::PathBuf::from(format!(
@@ -248,6 +554,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn channel_map_names_negotiate_user_channels() {
+        let sock_path = make_sock_path("channel-map");
+        let mut map = ChannelMap::new();
+        map.insert("metrics", 256).unwrap();
+        map.insert("audit", 300).unwrap();
+        let listener = PeerListener::bind(&sock_path)
+            .expect("listener should bind")
+            .with_channel_map(&map);
+
+        let server = thread::spawn(move || {
+            let peer = listener.accept().expect("listener should accept");
+            assert_eq!(peer.channels(), &[COMMAND, 256]);
+        });
+
+        let client = connect_named(&sock_path, &map, &["command", "metrics"])
+            .expect("client should connect");
+        assert_eq!(client.channels(), &[COMMAND, 256]);
+        server.join().expect("server thread should finish");
+
+        assert!(connect_named(&sock_path, &map, &["missing"]).is_err());
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
     #[test]
     fn accepts_multiple_sequential_connections() {
         let sock_path = make_sock_path("multi");
@@ -268,6 +601,493 @@ mod tests {
             let _ = std::fs::remove_dir_all(parent);
         }
     }
+
+    #[test]
+    fn metrics_count_accepts_and_handshake_failures() {
+        let sock_path = make_sock_path("metrics");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+        assert_eq!(listener.metrics(), ListenerMetrics::default());
+
+        let server = thread::spawn(move || {
+            let _peer = listener.accept().expect("first accept should succeed");
+            assert!(
+                listener.accept().is_err(),
+                "client hung up before the handshake"
+            );
+            listener.metrics()
+        });
+
+        let _client = connect(&sock_path, &[COMMAND]).expect("client should connect");
+        drop(UnixDomainSocket::connect(&sock_path).expect("raw connect should succeed"));
+        let metrics = server.join().expect("server thread should finish");
+
+        assert_eq!(metrics.accepted, 1);
+        assert_eq!(metrics.handshake_failures, 1);
+        assert_eq!(metrics.failures_by_kind.get("disconnected"), Some(&1));
+        assert!(metrics.last_accept_at.is_some());
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn shutdown_unblocks_accept_without_counting_a_failure() {
+        let sock_path = make_sock_path("shutdown");
+        let listener =
+            std::sync::Arc::new(PeerListener::bind(&sock_path).expect("listener should bind"));
+
+        let acceptor = listener.clone();
+        let server = thread::spawn(move || acceptor.accept().map(|peer| peer.id().to_string()));
+        thread::sleep(std::time::Duration::from_millis(50));
+        listener.shutdown();
+
+        assert!(matches!(
+            server.join().expect("server thread should finish"),
+            Err(crate::PeerError::Transport(
+                ipcprims_transport::TransportError::Shutdown
+            ))
+        ));
+        assert_eq!(listener.metrics(), ListenerMetrics::default());
+
+        drop(listener);
+        assert!(!sock_path.exists(), "socket path should be cleaned up");
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn incoming_keeps_yielding_after_a_garbage_client() {
+        use std::io::{Read, Write};
+
+        let sock_path = make_sock_path("incoming");
+        let listener =
+            std::sync::Arc::new(PeerListener::bind(&sock_path).expect("listener should bind"));
+
+        let acceptor = listener.clone();
+        let server = thread::spawn(move || {
+            acceptor
+                .incoming()
+                .take(3)
+                .map(|peer| peer.map(|peer| peer.id().to_string()))
+                .collect::<Vec<_>>()
+        });
+
+        drop(connect(&sock_path, &[COMMAND]).expect("first client should connect"));
+        let mut garbage =
+            UnixDomainSocket::connect(&sock_path).expect("raw connect should succeed");
+        garbage
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("timeout should apply");
+        garbage
+            .write_all(b"GARBAGE! not a handshake")
+            .expect("garbage should send");
+        // The server hangs up once the handshake fails.
+        let _ = garbage.read_to_end(&mut Vec::new());
+        drop(connect(&sock_path, &[COMMAND]).expect("third client should connect"));
+
+        let results = server.join().expect("server thread should finish");
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_deref().ok(), Some("peer-1"));
+        assert!(
+            results[1].is_err(),
+            "garbage client should fail the handshake"
+        );
+        assert_eq!(results[2].as_deref().ok(), Some("peer-3"));
+
+        listener.shutdown();
+        assert!(listener.incoming().next().is_none());
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn accept_timeout_returns_none_then_accepts_a_late_client() {
+        let sock_path = make_sock_path("accept-timeout");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+
+        let timed_out = listener
+            .accept_timeout(Duration::from_millis(50))
+            .expect("waiting without a client is not an error");
+        assert!(timed_out.is_none());
+        assert_eq!(listener.metrics(), ListenerMetrics::default());
+
+        let client_path = sock_path.clone();
+        let client = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            connect(&client_path, &[COMMAND]).map(|_| ())
+        });
+        let peer = listener
+            .accept_timeout(Duration::from_secs(5))
+            .expect("listener should accept")
+            .expect("client connects mid-wait");
+        // The timed-out attempt did not use up an id.
+        assert_eq!(peer.id(), "peer-1");
+        assert_eq!(listener.metrics().accepted, 1);
+        client
+            .join()
+            .expect("client thread should finish")
+            .expect("client should connect");
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn bind_with_applies_socket_options() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let sock_path = make_sock_path("bind-with");
+        let listener = PeerListener::bind_with(&sock_path, BindOptions::new().mode(0o640))
+            .expect("listener should bind");
+        let mode = std::fs::metadata(&sock_path)
+            .expect("socket path should exist")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        let server = thread::spawn(move || listener.accept().map(|peer| peer.id().to_string()));
+        let _client = connect(&sock_path, &[COMMAND]).expect("client should connect");
+        assert_eq!(server.join().unwrap().unwrap(), "peer-1");
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn accept_applies_peer_config_buffer_sizes() {
+        const SIZE: usize = 48 * 1024;
+
+        let sock_path = make_sock_path("bufsize");
+        let listener = PeerListener::bind(&sock_path)
+            .expect("listener should bind")
+            .with_peer_config(PeerConfig {
+                send_buffer_size: Some(SIZE),
+                recv_buffer_size: Some(SIZE),
+                ..PeerConfig::default()
+            });
+
+        let server = thread::spawn(move || {
+            let peer = listener.accept().expect("accept should succeed");
+            (
+                peer.send_buffer_size().unwrap(),
+                peer.recv_buffer_size().unwrap(),
+            )
+        });
+        let _client = connect(&sock_path, &[COMMAND]).expect("client should connect");
+        let (sent, received) = server.join().expect("server thread should finish");
+        assert!(sent >= SIZE && received >= SIZE, "{sent} {received}");
+        // Linux doubles the request; 48 KiB stays clear of both the
+        // defaults and `wmem_max`.
+        #[cfg(target_os = "linux")]
+        assert_eq!((sent, received), (2 * SIZE, 2 * SIZE));
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    /// A stream type from outside the transport crate.
+    struct Wrapped(IpcStream);
+
+    impl std::io::Read for Wrapped {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl std::io::Write for Wrapped {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl TryClone for Wrapped {
+        fn try_clone(&self) -> ipcprims_transport::Result<Self> {
+            Ok(Self(self.0.try_clone()?))
+        }
+    }
+
+    impl SetTimeouts for Wrapped {
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> ipcprims_transport::Result<()> {
+            self.0.set_read_timeout(timeout)
+        }
+
+        fn set_write_timeout(&self, timeout: Option<Duration>) -> ipcprims_transport::Result<()> {
+            self.0.set_write_timeout(timeout)
+        }
+    }
+
+    impl std::os::fd::AsFd for Wrapped {
+        fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+            self.0.as_fd()
+        }
+    }
+
+    impl TransportStream for Wrapped {}
+
+    /// A listener handing out [`Wrapped`] streams.
+    struct WrappedListener(UnixDomainSocket);
+
+    impl Listener for WrappedListener {
+        type Stream = Wrapped;
+
+        fn accept(&self) -> ipcprims_transport::Result<Wrapped> {
+            self.0.accept().map(Wrapped)
+        }
+
+        fn accept_timeout(&self, timeout: Duration) -> ipcprims_transport::Result<Option<Wrapped>> {
+            Ok(self.0.accept_timeout(timeout)?.map(Wrapped))
+        }
+
+        fn shutdown(&self) {
+            self.0.shutdown();
+        }
+
+        fn path(&self) -> &Path {
+            self.0.path()
+        }
+    }
+
+    #[test]
+    fn custom_listener_and_stream_carry_peers() {
+        use std::os::unix::fs::MetadataExt;
+
+        let sock_path = make_sock_path("custom");
+        let socket = UnixDomainSocket::bind(&sock_path).expect("socket should bind");
+        let listener = PeerListener::from_listener(WrappedListener(socket));
+        assert_eq!(listener.path(), sock_path.as_path());
+
+        let server = thread::spawn(move || {
+            for _ in 0..2 {
+                let mut peer = listener.accept().expect("accept should succeed");
+                // Credentials do not survive the trait object.
+                assert!(peer.peer_credentials().is_none());
+                let frame = peer.recv().expect("server should receive");
+                peer.send(frame.channel, &frame.payload)
+                    .expect("server should echo");
+            }
+        });
+
+        let stream = UnixDomainSocket::connect(&sock_path).expect("client should connect");
+        let mut client = connect_over(
+            Wrapped(stream),
+            &[COMMAND],
+            &HandshakeConfig::default(),
+            None,
+            None,
+        )
+        .expect("custom stream should handshake");
+        client.send(COMMAND, b"custom").unwrap();
+        assert_eq!(client.recv().unwrap().payload.as_ref(), b"custom");
+
+        // A plain UnixStream keeps its credentials, so the uid check works.
+        let uid = std::fs::metadata(&sock_path).unwrap().uid();
+        let stream = std::os::unix::net::UnixStream::connect(&sock_path).unwrap();
+        let config = HandshakeConfig {
+            require_server_uid: Some(uid),
+            ..HandshakeConfig::default()
+        };
+        let mut client = connect_over(stream, &[COMMAND], &config, None, None)
+            .expect("unix stream should handshake");
+        client.send(COMMAND, b"plain").unwrap();
+        assert_eq!(client.recv().unwrap().payload.as_ref(), b"plain");
+
+        server.join().expect("server thread should finish");
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn client_remote_addr_names_the_listener_path() {
+        let sock_path = make_sock_path("addr");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+
+        let server = thread::spawn(move || {
+            let peer = listener.accept().expect("accept should succeed");
+            peer.remote_addr()
+        });
+        let client = connect(&sock_path, &[COMMAND]).expect("client should connect");
+        assert_eq!(client.remote_addr(), Some(IpcAddr::Path(sock_path.clone())));
+        let server_view = server.join().expect("server thread should finish");
+        assert_eq!(server_view, Some(IpcAddr::Unnamed));
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn listener_handoff_keeps_established_peers() {
+        use std::os::fd::AsFd;
+
+        let sock_path = make_sock_path("handoff");
+        let old = PeerListener::bind(&sock_path).expect("listener should bind");
+        let server = thread::spawn(move || {
+            let peer = old.accept().expect("old listener should accept");
+            let fd = old.into_listener_fd().expect("listener should export");
+            (fd, peer)
+        });
+        let mut first = connect(&sock_path, &[COMMAND]).expect("first client should connect");
+        let (fd, mut first_server) = server.join().expect("server thread should finish");
+
+        // Hand the socket over the way a restarting daemon would.
+        let (mut tx, mut rx) = IpcStream::pair().expect("pair should open");
+        tx.send_with_fds(b"L", &[fd.as_fd()])
+            .expect("fd should send");
+        drop(fd);
+        let mut fds = Vec::new();
+        rx.recv_with_fds(&mut [0u8; 1], &mut fds)
+            .expect("fd should arrive");
+        let new = PeerListener::from_listener_fd(fds.pop().expect("one fd"))
+            .expect("listener should adopt");
+        assert_eq!(new.path(), sock_path);
+
+        let server = thread::spawn(move || {
+            let mut peer = new.accept().expect("new listener should accept");
+            let frame = peer.recv_on(COMMAND).expect("should receive command frame");
+            peer.send(COMMAND, frame.payload.as_ref())
+                .expect("should echo command");
+            new
+        });
+        let mut second = connect(&sock_path, &[COMMAND]).expect("second client should connect");
+        let response = second.request(b"new").expect("request should succeed");
+        assert_eq!(response.payload.as_ref(), b"new");
+        let new = server.join().expect("server thread should finish");
+
+        // The peer accepted before the handoff still reaches the old owner.
+        first
+            .send(COMMAND, b"old")
+            .expect("first client should send");
+        let frame = first_server
+            .recv_on(COMMAND)
+            .expect("old owner should receive");
+        assert_eq!(frame.payload.as_ref(), b"old");
+
+        drop(new);
+        assert!(!sock_path.exists(), "new owner should clean up the path");
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn tcp_endpoint_accepts_peers_on_loopback() {
+        let listener = PeerListener::bind("tcp://127.0.0.1:0").expect("listener should bind");
+        let endpoint = listener.path().to_path_buf();
+        let address = endpoint.to_str().unwrap();
+        assert!(address.starts_with("tcp://127.0.0.1:"));
+        assert!(!address.ends_with(":0"));
+
+        let server = thread::spawn(move || {
+            let mut peer = listener.accept().expect("listener should accept");
+            assert!(peer.peer_credentials().is_none());
+            let frame = peer.recv_on(COMMAND).expect("should receive command frame");
+            peer.send(COMMAND, frame.payload.as_ref())
+                .expect("should echo command");
+        });
+
+        let mut client = connect(&endpoint, &[COMMAND]).expect("client should connect");
+        let response = client.request(b"over-tcp").expect("request should succeed");
+        assert_eq!(response.payload.as_ref(), b"over-tcp");
+        server.join().expect("server thread should finish");
+
+        assert!(matches!(
+            PeerListener::bind("tcp://0.0.0.0:0"),
+            Err(crate::PeerError::Transport(
+                ipcprims_transport::TransportError::NonLoopback { .. }
+            ))
+        ));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    #[test]
+    fn vsock_endpoint_binds_and_reports_its_port() {
+        // CID_ANY and PORT_ANY: the kernel picks the port.
+        let listener = match PeerListener::bind("vsock://4294967295:4294967295") {
+            Ok(listener) => listener,
+            // This kernel has no vsock support.
+            Err(crate::PeerError::Transport(TransportError::Bind { .. })) => return,
+            Err(err) => panic!("vsock listener should bind: {err}"),
+        };
+        let endpoint = listener.path().to_str().unwrap().to_string();
+        assert!(endpoint.starts_with("vsock://4294967295:"));
+        assert!(!endpoint.ends_with(":4294967295"));
+        assert!(listener
+            .accept_timeout(Duration::from_millis(30))
+            .expect("accept_timeout should succeed")
+            .is_none());
+    }
+
+    #[test]
+    fn path_watchdog_fails_accept_after_path_is_deleted() {
+        let sock_path = make_sock_path("watchdog");
+        let listener = PeerListener::bind(&sock_path)
+            .expect("listener should bind")
+            .with_path_watchdog(std::time::Duration::from_millis(50));
+
+        std::fs::remove_file(&sock_path).expect("socket path should be removable");
+        assert!(matches!(
+            listener.accept(),
+            Err(crate::PeerError::Transport(
+                ipcprims_transport::TransportError::PathLost { .. }
+            ))
+        ));
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    #[test]
+    fn shared_buffer_pool_is_reused_across_peers() {
+        let sock_path = make_sock_path("pool");
+        let pool = ipcprims_frame::BufferPool::default();
+        let listener = PeerListener::bind(&sock_path)
+            .expect("listener should bind")
+            .with_peer_config(PeerConfig {
+                buffer_pool: Some(pool.clone()),
+                ..PeerConfig::default()
+            });
+
+        let server = thread::spawn(move || {
+            for _ in 0..3 {
+                let mut peer = listener.accept().expect("accept should succeed");
+                let frame = peer.recv().expect("server should receive");
+                peer.send(COMMAND, &frame.payload)
+                    .expect("server should reply");
+            }
+        });
+
+        for _ in 0..3 {
+            let mut client = connect(&sock_path, &[COMMAND]).expect("client should connect");
+            let reply = client.request(b"ping").expect("request should succeed");
+            assert_eq!(reply.payload.as_ref(), b"ping");
+        }
+        server.join().expect("server thread should finish");
+
+        // Each server peer borrows a reader and a writer buffer; later peers
+        // reuse the ones returned by earlier peers.
+        let stats = pool.stats();
+        assert_eq!(stats.hits + stats.misses, 6);
+        assert!(stats.hits >= 4, "expected reuse, got {stats:?}");
+        assert_eq!(stats.pooled, 2);
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
 }
 
 #[cfg(all(test, windows))]
@@ -319,6 +1139,31 @@ mod windows_tests {
         server.join().expect("server thread should complete");
     }
 
+    #[test]
+    fn accept_timeout_on_named_pipe() {
+        let pipe = make_pipe_name("accept-timeout");
+        let listener = PeerListener::bind(&pipe).expect("listener should bind named pipe");
+        assert!(listener
+            .accept_timeout(std::time::Duration::from_millis(50))
+            .expect("waiting without a client is not an error")
+            .is_none());
+
+        let client_pipe = pipe.clone();
+        let client = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            connect(&client_pipe, &[COMMAND]).map(|_| ())
+        });
+        let peer = listener
+            .accept_timeout(std::time::Duration::from_secs(5))
+            .expect("listener should accept")
+            .expect("client connects mid-wait");
+        assert_eq!(peer.id(), "peer-1");
+        client
+            .join()
+            .expect("client thread should finish")
+            .expect("client should connect");
+    }
+
     /// Verify that a named pipe listener can accept two sequential clients.
     ///
     /// Windows named pipes require a new pipe instance after each client
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Serializer};
+
+use crate::error::PeerError;
+
+/// Snapshot of a [`PeerListener`](crate::PeerListener)'s accept counters.
+///
+/// Serializes with `last_accept_at` as Unix milliseconds (or `null`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ListenerMetrics {
+    /// Connections that completed the handshake.
+    pub accepted: u64,
+    /// Connections that were accepted by the socket but failed the handshake.
+    pub handshake_failures: u64,
+    /// `handshake_failures` by cause: `"rejected"` (version, channel or auth
+    /// checks), `"timeout"`, `"disconnected"`, `"frame"`, `"transport"`,
+    /// `"json"` or `"other"`.
+    pub failures_by_kind: BTreeMap<String, u64>,
+    /// When the most recent handshake completed.
+    #[serde(serialize_with = "serialize_unix_millis")]
+    pub last_accept_at: Option<SystemTime>,
+}
+
+/// Live counters behind [`ListenerMetrics`], shared by concurrent accepts.
+#[derive(Debug, Default)]
+pub(crate) struct ListenerCounters {
+    accepted: AtomicU64,
+    handshake_failures: AtomicU64,
+    /// Nanoseconds since the Unix epoch; 0 means no accept yet.
+    last_accept_nanos: AtomicU64,
+    failures_by_kind: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl ListenerCounters {
+    pub(crate) fn record_accept(&self) {
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| {
+                u64::try_from(since.as_nanos()).unwrap_or(u64::MAX)
+            });
+        self.last_accept_nanos.store(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_failure(&self, err: &PeerError) {
+        self.handshake_failures.fetch_add(1, Ordering::Relaxed);
+        let mut by_kind = self
+            .failures_by_kind
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *by_kind.entry(failure_kind(err)).or_default() += 1;
+    }
+
+    pub(crate) fn snapshot(&self) -> ListenerMetrics {
+        let failures_by_kind = self
+            .failures_by_kind
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(kind, count)| ((*kind).to_string(), *count))
+            .collect();
+        let last_accept_at = match self.last_accept_nanos.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(UNIX_EPOCH + Duration::from_nanos(nanos)),
+        };
+        ListenerMetrics {
+            accepted: self.accepted.load(Ordering::Relaxed),
+            handshake_failures: self.handshake_failures.load(Ordering::Relaxed),
+            failures_by_kind,
+            last_accept_at,
+        }
+    }
+}
+
+/// Bucket for [`ListenerMetrics::failures_by_kind`].
+fn failure_kind(err: &PeerError) -> &'static str {
+    match err {
+        PeerError::HandshakeFailed(_) | PeerError::AuthRejected(_) => "rejected",
+        PeerError::Timeout { .. } => "timeout",
+        PeerError::Disconnected(_) => "disconnected",
+        PeerError::Frame(_) | PeerError::Desynchronized(_) => "frame",
+        PeerError::Transport(_) => "transport",
+        PeerError::Json(_) => "json",
+        _ => "other",
+    }
+}
+
+fn serialize_unix_millis<S: Serializer>(
+    at: &Option<SystemTime>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match at.and_then(|at| at.duration_since(UNIX_EPOCH).ok()) {
+        Some(since) => {
+            serializer.serialize_u64(u64::try_from(since.as_millis()).unwrap_or(u64::MAX))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_accepts_and_failures_by_kind() {
+        let counters = ListenerCounters::default();
+        assert_eq!(counters.snapshot(), ListenerMetrics::default());
+
+        counters.record_accept();
+        counters.record_failure(&PeerError::HandshakeFailed("bad token".to_string()));
+        counters.record_failure(&PeerError::HandshakeFailed("bad version".to_string()));
+        counters.record_failure(&PeerError::Disconnected("closed".to_string()));
+
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.accepted, 1);
+        assert_eq!(metrics.handshake_failures, 3);
+        assert_eq!(metrics.failures_by_kind["rejected"], 2);
+        assert_eq!(metrics.failures_by_kind["disconnected"], 1);
+        assert!(metrics.last_accept_at.is_some());
+    }
+
+    #[test]
+    fn serializes_last_accept_as_unix_millis() {
+        let metrics = ListenerMetrics {
+            accepted: 2,
+            last_accept_at: Some(UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)),
+            ..ListenerMetrics::default()
+        };
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["accepted"], 2);
+        assert_eq!(json["last_accept_at"], 1_700_000_000_123u64);
+        assert_eq!(json["failures_by_kind"], serde_json::json!({}));
+
+        let json = serde_json::to_value(ListenerMetrics::default()).unwrap();
+        assert!(json["last_accept_at"].is_null());
+    }
+}
@@ -1,30 +1,40 @@
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::io::ErrorKind;
+#[cfg(unix)]
+use std::os::fd::{BorrowedFd, OwnedFd};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use ipcprims_frame::{Frame, FrameError, FrameReader, FrameWriter, COMMAND, CONTROL};
-use ipcprims_transport::IpcStream;
+use ipcprims_frame::{
+    BatchError, BufferPool, Frame, FrameError, FrameObserver, FrameReader, FrameStats, FrameWriter,
+    PayloadCodec, Priority, WireVersion, COMMAND, CONTROL, DATA, DEFAULT_MAX_PAYLOAD, ERROR,
+    TELEMETRY,
+};
+use ipcprims_transport::{IpcAddr, IpcStream, PeerCredentials};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::connector::establish_client;
 use crate::control::{
-    ControlMessage, CONTROL_PING, CONTROL_PONG, CONTROL_SHUTDOWN_ACK, CONTROL_SHUTDOWN_FORCE,
-    CONTROL_SHUTDOWN_REQUEST,
+    ControlMessage, CONTROL_AUTH_REFRESH, CONTROL_AUTH_RESULT, CONTROL_FDS, CONTROL_PING,
+    CONTROL_PONG, CONTROL_SHUTDOWN_ACK, CONTROL_SHUTDOWN_FORCE, CONTROL_SHUTDOWN_REQUEST,
+    FORCED_SHUTDOWN_REASON,
 };
-use crate::error::{PeerError, Result};
-use crate::handshake::HandshakeResult;
+use crate::error::{PeerError, Result, TimeoutOp};
+use crate::handshake::{AuthValidator, HandshakeConfig, HandshakeResult, MAX_AUTH_TOKEN_LEN};
+use crate::listener::establish_server;
+use crate::trace::{self, Span};
 
 #[cfg(feature = "schema")]
 use ipcprims_schema::SchemaRegistry;
 #[cfg(feature = "schema")]
-use std::sync::Arc;
-#[cfg(feature = "schema")]
 pub(crate) type SchemaRegistryHandle = Arc<SchemaRegistry>;
 #[cfg(not(feature = "schema"))]
-pub(crate) type SchemaRegistryHandle = std::sync::Arc<()>;
+pub(crate) type SchemaRegistryHandle = Arc<()>;
 
 /// Peer behavior configuration.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PeerConfig {
     /// Max buffered frames per channel for `recv_on`.
     /// Together with `max_total_buffered_bytes`, this bounds off-channel buffering memory.
@@ -36,10 +46,60 @@ pub struct PeerConfig {
     pub shutdown_timeout: Duration,
     /// Maximum CONTROL frames processed in a single receive/wait loop before disconnecting.
     pub max_control_frames_per_loop: usize,
+    /// Escalation used by [`Peer::shutdown`] when the remote does not ack.
+    pub shutdown_policy: ShutdownPolicy,
     /// Whether remote SHUTDOWN_FORCE messages are honored.
     pub allow_shutdown_force: bool,
     /// Whether unknown CONTROL message types are passed through instead of rejected.
     pub allow_unknown_control_messages: bool,
+    /// Checks tokens the remote presents with [`Peer::refresh_auth`]. `None`
+    /// rejects every refresh.
+    pub auth_refresh_validator: Option<AuthValidator>,
+    /// Close the connection when an auth refresh is rejected (default
+    /// `false`: reply with the rejection and keep the old token).
+    pub disconnect_on_auth_reject: bool,
+    /// Maximum frame payload size accepted or sent after the handshake.
+    pub max_payload_size: usize,
+    /// Default send priority per channel. Channels not listed send at
+    /// [`Priority::NORMAL`]. See [`Peer::send_with_priority`].
+    pub channel_priorities: HashMap<u16, Priority>,
+    /// Shared pool for frame read/write buffers. Clone one pool into the
+    /// configs of many peers to bound and reuse their buffer memory.
+    /// Ignored by async peers.
+    pub buffer_pool: Option<BufferPool>,
+    /// Send every frame with a CRC-32 of its payload, once the handshake
+    /// shows the remote verifies them (default `false`). Received checksums
+    /// are always verified. Ignored by async peers.
+    pub checksum_frames: bool,
+    /// Frame header version to send, capped at what the handshake shows the
+    /// remote decodes, so older peers get [`WireVersion::V1`] (the default).
+    /// Received frames of either version are always accepted. Ignored by
+    /// async peers.
+    pub wire_version: WireVersion,
+    /// Count frames and payload bytes per channel after the handshake,
+    /// reported by [`Peer::stats`] (default `false`). Ignored by async peers.
+    pub collect_stats: bool,
+    /// Told about every frame sent and received after the handshake, in
+    /// both directions. Ignored by async peers.
+    pub observer: Option<Arc<dyn FrameObserver>>,
+    /// Seals payloads sent and opens payloads received after the handshake
+    /// (e.g. `ChaCha20Poly1305Codec`, `crypto` feature). Both ends need the
+    /// same codec; CONTROL stays in the clear unless the codec says
+    /// otherwise. Async peers refuse a config that sets one rather than
+    /// send in the clear.
+    pub payload_codec: Option<Arc<dyn PayloadCodec>>,
+    /// Keep file descriptors the remote sends with [`Peer::send_fds`], for
+    /// [`Peer::take_fds`] (default `false`: they are closed on arrival).
+    /// Unix stream sockets only. Ignored by async peers.
+    pub receive_fds: bool,
+    /// `SO_SNDBUF` to request on the stream before the handshake, instead
+    /// of the system default (`None`). The kernel may adjust it; see
+    /// [`Peer::send_buffer_size`]. Socket-backed streams on Unix only;
+    /// ignored elsewhere and by async peers.
+    pub send_buffer_size: Option<usize>,
+    /// `SO_RCVBUF` to request on the stream before the handshake, as for
+    /// `send_buffer_size`.
+    pub recv_buffer_size: Option<usize>,
 
     /// Async-only: whether the arrival-ordered `any_rx` delivery path starts enabled.
     ///
@@ -55,13 +115,124 @@ impl Default for PeerConfig {
             max_total_buffered_bytes: 16 * 1024 * 1024,
             shutdown_timeout: Duration::from_secs(5),
             max_control_frames_per_loop: 256,
+            shutdown_policy: ShutdownPolicy::default(),
             allow_shutdown_force: false,
             allow_unknown_control_messages: false,
+            auth_refresh_validator: None,
+            disconnect_on_auth_reject: false,
+            max_payload_size: DEFAULT_MAX_PAYLOAD,
+            channel_priorities: HashMap::new(),
+            buffer_pool: None,
+            checksum_frames: false,
+            wire_version: WireVersion::V1,
+            collect_stats: false,
+            observer: None,
+            payload_codec: None,
+            receive_fds: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
             enable_any_delivery: true,
         }
     }
 }
 
+impl PeerConfig {
+    /// Request the socket buffer sizes this config asks for on a new stream.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub(crate) fn apply_buffer_sizes(&self, stream: &IpcStream) -> Result<()> {
+        #[cfg(unix)]
+        {
+            if let Some(size) = self.send_buffer_size {
+                stream.set_send_buffer_size(size)?;
+            }
+            if let Some(size) = self.recv_buffer_size {
+                stream.set_recv_buffer_size(size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PeerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerConfig")
+            .field("max_buffer_per_channel", &self.max_buffer_per_channel)
+            .field("max_total_buffered_bytes", &self.max_total_buffered_bytes)
+            .field("shutdown_timeout", &self.shutdown_timeout)
+            .field(
+                "max_control_frames_per_loop",
+                &self.max_control_frames_per_loop,
+            )
+            .field("shutdown_policy", &self.shutdown_policy)
+            .field("allow_shutdown_force", &self.allow_shutdown_force)
+            .field(
+                "allow_unknown_control_messages",
+                &self.allow_unknown_control_messages,
+            )
+            .field(
+                "auth_refresh_validator",
+                &self.auth_refresh_validator.as_ref().map(|_| "<fn>"),
+            )
+            .field("disconnect_on_auth_reject", &self.disconnect_on_auth_reject)
+            .field("max_payload_size", &self.max_payload_size)
+            .field("channel_priorities", &self.channel_priorities)
+            .field("buffer_pool", &self.buffer_pool)
+            .field("checksum_frames", &self.checksum_frames)
+            .field("wire_version", &self.wire_version)
+            .field("collect_stats", &self.collect_stats)
+            .field("observer", &self.observer.as_ref().map(|_| "<observer>"))
+            .field(
+                "payload_codec",
+                &self.payload_codec.as_ref().map(|_| "<payload codec>"),
+            )
+            .field("receive_fds", &self.receive_fds)
+            .field("send_buffer_size", &self.send_buffer_size)
+            .field("recv_buffer_size", &self.recv_buffer_size)
+            .field("enable_any_delivery", &self.enable_any_delivery)
+            .finish()
+    }
+}
+
+/// Escalation steps for [`Peer::shutdown`].
+///
+/// The default waits `shutdown_timeout` for an ack and then fails, without
+/// sending SHUTDOWN_FORCE.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownPolicy {
+    /// How long to wait for SHUTDOWN_ACK. `None` uses
+    /// [`PeerConfig::shutdown_timeout`].
+    pub ack_timeout: Option<Duration>,
+    /// If set, send SHUTDOWN_FORCE when the ack times out and wait this long
+    /// for the remote to close. `None` skips the force phase.
+    pub force_timeout: Option<Duration>,
+    /// Close locally and report [`ShutdownOutcome::HardClosed`] when the
+    /// remote is still open after the force phase, instead of failing.
+    pub hard_close: bool,
+}
+
+/// Which phase of [`Peer::shutdown`] completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    /// The remote acknowledged the shutdown request in time.
+    Acknowledged,
+    /// The ack timed out; after SHUTDOWN_FORCE the remote closed the
+    /// connection (or acked late) within `force_timeout`.
+    Forced,
+    /// The remote stayed silent through both phases and the connection was
+    /// closed locally.
+    HardClosed,
+}
+
+/// Which side of the handshake [`Peer::from_stream`] plays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerRole {
+    /// Send the handshake request, asking for `channels`.
+    Client { channels: Vec<u16> },
+    /// Answer the request, granting the requested channels that are also in
+    /// `channels`, and call the remote `peer_id`.
+    Server { channels: Vec<u16>, peer_id: String },
+}
+
 /// A connected, handshaken peer.
 pub struct Peer {
     id: String,
@@ -71,22 +242,104 @@ pub struct Peer {
     client_auth_token: Option<String>,
     #[cfg_attr(not(unix), allow(dead_code))]
     schema_registry: Option<SchemaRegistryHandle>,
-    channel_buffers: HashMap<u16, VecDeque<Frame>>,
+    /// Frames buffered by `recv_on`, tagged with an arrival sequence number.
+    channel_buffers: HashMap<u16, VecDeque<(u64, Frame)>>,
+    next_buffer_seq: u64,
     buffered_total_bytes: usize,
     config: PeerConfig,
+    /// The stream read timeout `recv_timeout` restores afterwards.
+    recv_timeout: Duration,
+    /// When the current `recv_timeout` call gives up; each read waits at
+    /// most until then.
+    read_deadline: Option<Instant>,
     shutdown_requested: bool,
+    /// Set by [`Peer::finish_sending`].
+    sending_finished: bool,
+    /// Why the inbound stream lost frame alignment, until [`Peer::resync`].
+    desynchronized: Option<String>,
+    /// [`Peer::send_final_error`] has used up its reply since poisoning.
+    final_error_sent: bool,
+    /// Descriptors a CONTROL_FDS announcement claimed for the next frame
+    /// on each channel.
+    #[cfg(unix)]
+    announced_fds: HashMap<u16, Vec<OwnedFd>>,
+    /// Descriptors of buffered frames, by buffer sequence number.
+    #[cfg(unix)]
+    buffered_fds: HashMap<u64, Vec<OwnedFd>>,
+    /// Descriptors of the frame last read or returned, for `take_fds`.
+    #[cfg(unix)]
+    received_fds: Vec<OwnedFd>,
+    span: Span,
 }
 
 impl Peer {
+    /// Handshake over an already connected stream, such as one end of
+    /// `IpcStream::pair` or a descriptor inherited from a parent process.
+    ///
+    /// One end must be [`PeerRole::Client`] and the other
+    /// [`PeerRole::Server`]; the handshake uses the default
+    /// [`HandshakeConfig`].
+    pub fn from_stream(stream: IpcStream, role: PeerRole, config: PeerConfig) -> Result<Self> {
+        Self::from_stream_with_handshake(stream, role, config, &HandshakeConfig::default())
+    }
+
+    /// [`Peer::from_stream`] with an explicit handshake config (auth token,
+    /// validator, timeout).
+    pub fn from_stream_with_handshake(
+        stream: IpcStream,
+        role: PeerRole,
+        config: PeerConfig,
+        handshake_config: &HandshakeConfig,
+    ) -> Result<Self> {
+        match role {
+            PeerRole::Client { channels } => {
+                establish_client(stream, &channels, handshake_config, None, config)
+            }
+            PeerRole::Server { channels, peer_id } => {
+                establish_server(stream, &peer_id, &channels, handshake_config, None, config)
+            }
+        }
+    }
+
+    /// A client and a server peer joined by an in-memory stream
+    /// (`IpcStream::memory_pair`), for tests that need a real handshake
+    /// without a socket.
+    ///
+    /// The server grants the listener defaults (COMMAND, DATA, TELEMETRY,
+    /// ERROR) to a client asking for all of them, and calls the client
+    /// `"memory"`. Returns `(client, server)`.
+    pub fn connected_pair(config: PeerConfig) -> Result<(Self, Self)> {
+        let (client_stream, server_stream) = IpcStream::memory_pair()?;
+        let channels = vec![COMMAND, DATA, TELEMETRY, ERROR];
+        let server_config = config.clone();
+        let server_channels = channels.clone();
+        std::thread::scope(|scope| {
+            let server = scope.spawn(move || {
+                Self::from_stream(
+                    server_stream,
+                    PeerRole::Server {
+                        channels: server_channels,
+                        peer_id: "memory".to_string(),
+                    },
+                    server_config,
+                )
+            });
+            let client = Self::from_stream(client_stream, PeerRole::Client { channels }, config);
+            let server = server.join().expect("server handshake thread panicked");
+            Ok((client?, server?))
+        })
+    }
+
     /// Create a peer from connected components.
     #[cfg_attr(not(unix), allow(dead_code))]
     pub(crate) fn from_parts(
         id: String,
-        reader: FrameReader<IpcStream>,
-        writer: FrameWriter<IpcStream>,
+        mut reader: FrameReader<IpcStream>,
+        mut writer: FrameWriter<IpcStream>,
         mut handshake_result: HandshakeResult,
         #[cfg_attr(not(unix), allow(dead_code))] schema_registry: Option<SchemaRegistryHandle>,
         config: PeerConfig,
+        span: Span,
     ) -> Self {
         let _ = reader
             .get_ref()
@@ -94,6 +347,29 @@ impl Peer {
         let _ = writer
             .get_ref()
             .set_write_timeout(Some(config.shutdown_timeout));
+        writer.set_write_timeout(Some(config.shutdown_timeout));
+
+        if let Some(pool) = &config.buffer_pool {
+            reader.set_buffer_pool(pool.clone());
+            writer.set_buffer_pool(pool.clone());
+        }
+        writer.set_checksum(config.checksum_frames && handshake_result.checksum_frames);
+        writer.set_wire_version(config.wire_version.min(handshake_result.wire_version));
+        reader.set_collect_stats(config.collect_stats);
+        writer.set_collect_stats(config.collect_stats);
+        if let Some(observer) = &config.observer {
+            reader.set_observer(Arc::clone(observer));
+            writer.set_observer(Arc::clone(observer));
+        }
+        if let Some(codec) = &config.payload_codec {
+            reader.set_payload_codec(Arc::clone(codec));
+            writer.set_payload_codec(Arc::clone(codec));
+        }
+        reader.absorb_heartbeats(true);
+        #[cfg(unix)]
+        if config.receive_fds {
+            let _ = reader.get_mut().set_receive_fds(true);
+        }
 
         let client_auth_token = handshake_result.client_auth_token.take();
 
@@ -105,20 +381,98 @@ impl Peer {
             client_auth_token,
             schema_registry,
             channel_buffers: HashMap::new(),
+            next_buffer_seq: 0,
             buffered_total_bytes: 0,
+            recv_timeout: config.shutdown_timeout,
+            read_deadline: None,
             config,
             shutdown_requested: false,
+            sending_finished: false,
+            desynchronized: None,
+            final_error_sent: false,
+            #[cfg(unix)]
+            announced_fds: HashMap::new(),
+            #[cfg(unix)]
+            buffered_fds: HashMap::new(),
+            #[cfg(unix)]
+            received_fds: Vec::new(),
+            span,
         }
     }
 
     /// Send bytes on a negotiated channel.
+    ///
+    /// Uses the channel's default from [`PeerConfig::channel_priorities`].
     pub fn send(&mut self, channel: u16, payload: &[u8]) -> Result<()> {
+        let priority = self
+            .config
+            .channel_priorities
+            .get(&channel)
+            .copied()
+            .unwrap_or_default();
+        self.send_with_priority(channel, payload, priority)
+    }
+
+    /// Send bytes on a negotiated channel with an explicit priority.
+    ///
+    /// The priority travels in the extended frame header, which is only used
+    /// when the handshake established that the remote side decodes it;
+    /// otherwise the frame is sent at [`Priority::NORMAL`].
+    pub fn send_with_priority(
+        &mut self,
+        channel: u16,
+        payload: &[u8],
+        priority: Priority,
+    ) -> Result<()> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        self.write_payload(channel, payload, priority)
+    }
+
+    /// Send one last reply on [`ERROR`] before closing a poisoned peer.
+    ///
+    /// Sends fail on a poisoned peer (see [`Self::is_poisoned`]), but a
+    /// corrupt inbound frame leaves the outbound stream intact, so a server
+    /// can still say why it is hanging up. Allowed once per poisoning;
+    /// later calls fail with [`PeerError::Desynchronized`] like any send.
+    /// On a peer that is not poisoned this is a plain [`Self::send`] on
+    /// ERROR.
+    pub fn send_final_error(&mut self, payload: &[u8]) -> Result<()> {
+        let _span = self.span.clone().entered();
+        if self.desynchronized.is_some() {
+            if self.final_error_sent {
+                return self.ensure_synchronized();
+            }
+            self.final_error_sent = true;
+        }
+        let priority = self
+            .config
+            .channel_priorities
+            .get(&ERROR)
+            .copied()
+            .unwrap_or_default();
+        self.write_payload(ERROR, payload, priority)
+    }
+
+    fn write_payload(&mut self, channel: u16, payload: &[u8], priority: Priority) -> Result<()> {
         if channel != CONTROL && !self.supports_channel(channel) {
             return Err(PeerError::UnsupportedChannel(channel));
         }
 
         self.validate_send(channel, payload)?;
-        self.writer.send(channel, payload)?;
+        let priority = if self.handshake_result.priority_frames {
+            priority
+        } else {
+            Priority::NORMAL
+        };
+        self.writer
+            .send_with_priority(channel, payload, priority)
+            .map_err(|err| {
+                PeerError::from(err).during(TimeoutOp::Send {
+                    channel: Some(channel),
+                })
+            })?;
+        trace::frame_sent(channel, payload.len());
         Ok(())
     }
 
@@ -128,37 +482,234 @@ impl Peer {
         self.send(channel, &payload)
     }
 
+    /// Send bytes on a negotiated channel together with an open file
+    /// descriptor (Unix stream sockets only). See [`Peer::send_fds`].
+    #[cfg(unix)]
+    pub fn send_fd(&mut self, channel: u16, payload: &[u8], fd: BorrowedFd<'_>) -> Result<()> {
+        self.send_fds(channel, payload, &[fd])
+    }
+
+    /// Send bytes on a negotiated channel together with up to
+    /// [`MAX_FDS_PER_MESSAGE`](ipcprims_transport::MAX_FDS_PER_MESSAGE)
+    /// open file descriptors (Unix stream sockets only).
+    ///
+    /// The descriptors ride on a [`CONTROL_FDS`] frame sent just before the
+    /// data frame; a remote [`Peer`] with [`PeerConfig::receive_fds`] hands
+    /// them out with [`Peer::take_fds`] after receiving that frame, and
+    /// one without closes them. Split and async peers do not understand the
+    /// announcement. The caller keeps its own copies.
+    #[cfg(unix)]
+    pub fn send_fds(&mut self, channel: u16, payload: &[u8], fds: &[BorrowedFd<'_>]) -> Result<()> {
+        if fds.is_empty() {
+            return self.send(channel, payload);
+        }
+        self.ensure_synchronized()?;
+        if !self.supports_channel(channel) {
+            return Err(PeerError::UnsupportedChannel(channel));
+        }
+        self.validate_send(channel, payload)?;
+
+        self.writer.get_mut().attach_fds(fds)?;
+        if let Err(err) = self.send_control(ControlMessage::fds(channel, fds.len())) {
+            self.writer.get_mut().detach_fds();
+            return Err(err);
+        }
+        self.send(channel, payload)
+    }
+
+    /// File descriptors that arrived with the frame most recently returned
+    /// by a receive ([`recv`](Self::recv), [`recv_on`](Self::recv_on),
+    /// [`request`](Self::request)), in the order they were sent.
+    ///
+    /// Empty unless [`PeerConfig::receive_fds`] is set and the remote used
+    /// [`Peer::send_fds`]. The next receive replaces them, closing any not
+    /// taken.
+    #[cfg(unix)]
+    pub fn take_fds(&mut self) -> Vec<OwnedFd> {
+        std::mem::take(&mut self.received_fds)
+    }
+
+    /// Send a group of frames back to back, as one unit.
+    ///
+    /// Channels, payload sizes and outgoing schemas are checked for every
+    /// frame before any byte is written, then the group is written with a
+    /// single flush at normal priority, so it arrives in order. Failures return
+    /// [`PeerError::TransactionFailed`] with the frame's index; a write that
+    /// fails partway through poisons the writer (later sends fail with
+    /// [`FrameError::Poisoned`]).
+    pub fn send_transaction(&mut self, frames: &[(u16, &[u8])]) -> Result<()> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        for (index, &(channel, payload)) in frames.iter().enumerate() {
+            if channel != CONTROL && !self.supports_channel(channel) {
+                return Err(transaction_failed(
+                    index,
+                    PeerError::UnsupportedChannel(channel),
+                ));
+            }
+            self.validate_send(channel, payload)
+                .map_err(|err| transaction_failed(index, err))?;
+        }
+
+        self.writer
+            .send_batch(frames)
+            .map_err(|err| batch_failed(frames, err))?;
+        for &(channel, payload) in frames {
+            trace::frame_sent(channel, payload.len());
+        }
+        Ok(())
+    }
+
     /// Receive next non-internal frame from any channel.
+    ///
+    /// Frames that [`recv_on`](Self::recv_on) buffered for other channels
+    /// are returned first, highest priority first and in arrival order
+    /// within a priority.
     pub fn recv(&mut self) -> Result<Frame> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
         if self.shutdown_requested {
             return Err(PeerError::Disconnected("shutdown requested".to_string()));
         }
+        if let Some(frame) = self.pop_highest_priority() {
+            return Ok(frame);
+        }
+        self.recv_unbuffered()
+    }
 
+    /// Read the next non-internal frame from the stream, bypassing buffers.
+    fn recv_unbuffered(&mut self) -> Result<Frame> {
         let mut control_frames_seen = 0usize;
         loop {
             let frame = self.read_frame_once()?;
-            if frame.channel != CONTROL {
-                self.ensure_inbound_channel(frame.channel)?;
-                self.validate_recv(&frame)?;
+            if let Some(frame) = self.deliverable(frame, &mut control_frames_seen)? {
                 return Ok(frame);
             }
-            control_frames_seen = control_frames_seen.saturating_add(1);
-            if control_frames_seen > self.config.max_control_frames_per_loop {
-                return Err(PeerError::Disconnected(
-                    "control frame flood detected".to_string(),
-                ));
+        }
+    }
+
+    /// Like [`recv_unbuffered`](Self::recv_unbuffered), but `Ok(None)` once
+    /// the non-blocking stream has no complete frame left.
+    #[cfg(unix)]
+    fn try_recv_unbuffered(&mut self) -> Result<Option<Frame>> {
+        let mut control_frames_seen = 0usize;
+        while let Some(frame) = self.try_read_frame_once()? {
+            if let Some(frame) = self.deliverable(frame, &mut control_frames_seen)? {
+                return Ok(Some(frame));
             }
+        }
+        Ok(None)
+    }
 
-            match self.handle_control_frame(frame)? {
-                ControlDisposition::Continue => continue,
-                ControlDisposition::Return(frame) => {
-                    self.validate_recv(&frame)?;
-                    return Ok(frame);
-                }
-                ControlDisposition::Disconnected(reason) => {
-                    return Err(PeerError::Disconnected(reason));
+    /// Validate a frame just read, handling CONTROL frames internally.
+    /// `None` means it was consumed and the caller should read on.
+    fn deliverable(
+        &mut self,
+        frame: Frame,
+        control_frames_seen: &mut usize,
+    ) -> Result<Option<Frame>> {
+        if frame.channel != CONTROL {
+            self.ensure_inbound_channel(frame.channel)?;
+            self.validate_recv(&frame)?;
+            self.claim_announced_fds(frame.channel);
+            return Ok(Some(frame));
+        }
+        *control_frames_seen = control_frames_seen.saturating_add(1);
+        if *control_frames_seen > self.config.max_control_frames_per_loop {
+            return Err(PeerError::Disconnected(
+                "control frame flood detected".to_string(),
+            ));
+        }
+
+        match self.handle_control_frame(frame)? {
+            ControlDisposition::Continue => Ok(None),
+            ControlDisposition::Return(frame) => {
+                self.validate_recv(&frame)?;
+                self.claim_announced_fds(CONTROL);
+                Ok(Some(frame))
+            }
+            ControlDisposition::Disconnected(reason) => Err(PeerError::Disconnected(reason)),
+        }
+    }
+
+    /// Return the next non-internal frame from any channel without
+    /// blocking, or `Ok(None)` if none is ready.
+    ///
+    /// Buffered frames come first, as for [`recv`](Self::recv). Otherwise
+    /// the stream is switched to non-blocking mode (see
+    /// `IpcStream::set_nonblocking`) for the duration of the call and read
+    /// until a frame completes or it would block; a partial frame is kept
+    /// for the next call. CONTROL frames read meanwhile are handled as by
+    /// `recv`, including replies, which wait out a full socket buffer up to
+    /// the write timeout. Custom transports without a non-blocking mode
+    /// fail with a transport error.
+    ///
+    /// Unix only: Windows named pipes have no non-blocking mode here, so
+    /// the method does not exist there.
+    #[cfg(unix)]
+    pub fn try_recv(&mut self) -> Result<Option<Frame>> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        if let Some(frame) = self.pop_highest_priority() {
+            return Ok(Some(frame));
+        }
+        self.without_blocking(Self::try_recv_unbuffered)
+    }
+
+    /// Return the next frame on `channel` without blocking, or `Ok(None)`
+    /// if none is ready; frames for other channels are buffered as by
+    /// [`recv_on`](Self::recv_on). See [`try_recv`](Self::try_recv).
+    ///
+    /// Unix only, like `try_recv`.
+    #[cfg(unix)]
+    pub fn try_recv_on(&mut self, channel: u16) -> Result<Option<Frame>> {
+        if channel != CONTROL && !self.supports_channel(channel) {
+            return Err(PeerError::UnsupportedChannel(channel));
+        }
+        self.ensure_synchronized()?;
+
+        if let Some(frame) = self.pop_buffered(channel) {
+            return Ok(Some(frame));
+        }
+
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        let _span = self.span.clone().entered();
+        self.without_blocking(|peer| {
+            while let Some(frame) = peer.try_recv_unbuffered()? {
+                if frame.channel == channel {
+                    return Ok(Some(frame));
                 }
+                peer.buffer_frame(frame)?;
+            }
+            Ok(None)
+        })
+    }
+
+    /// Run `poll` with the stream in non-blocking mode, restoring blocking
+    /// mode afterwards.
+    ///
+    /// A polled frame outlives a failed restore; the restore error is only
+    /// returned when the poll found nothing.
+    #[cfg(unix)]
+    fn without_blocking(
+        &mut self,
+        poll: impl FnOnce(&mut Self) -> Result<Option<Frame>>,
+    ) -> Result<Option<Frame>> {
+        self.reader.get_ref().set_nonblocking(true)?;
+        let result = poll(self);
+        let restored = self.reader.get_ref().set_nonblocking(false);
+        match (result, restored) {
+            (Ok(None), Err(err)) => Err(err.into()),
+            (result, Err(err)) => {
+                trace::stream_restore_failed("blocking mode", &err);
+                result
             }
+            (result, Ok(())) => result,
         }
     }
 
@@ -167,13 +718,22 @@ impl Peer {
         if channel != CONTROL && !self.supports_channel(channel) {
             return Err(PeerError::UnsupportedChannel(channel));
         }
+        self.ensure_synchronized()?;
 
         if let Some(frame) = self.pop_buffered(channel) {
             return Ok(frame);
         }
 
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        let _span = self.span.clone().entered();
         loop {
-            let frame = self.recv()?;
+            let frame = self.recv_unbuffered().map_err(|err| {
+                err.during(TimeoutOp::Recv {
+                    channel: Some(channel),
+                })
+            })?;
             if frame.channel == channel {
                 return Ok(frame);
             }
@@ -181,12 +741,81 @@ impl Peer {
         }
     }
 
+    /// Like [`recv`](Self::recv), but block at most `timeout` for this call
+    /// only, returning [`PeerError::Timeout`] with `timeout` as the wait.
+    ///
+    /// The deadline covers the whole call: each read waits only for what is
+    /// left of it, so CONTROL traffic handled along the way cannot extend it.
+    /// Buffered frames are returned without touching the stream; with
+    /// nothing buffered, a zero `timeout` times out at once. The timeout set
+    /// by [`set_recv_timeout`](Self::set_recv_timeout) applies again
+    /// afterwards.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Frame> {
+        if !self.channel_buffers.values().any(|queue| !queue.is_empty()) {
+            return self.with_read_deadline(timeout, TimeoutOp::Recv { channel: None }, Self::recv);
+        }
+        self.recv()
+    }
+
+    /// Like [`recv_on`](Self::recv_on), but block at most `timeout` for this
+    /// call only; see [`recv_timeout`](Self::recv_timeout). Frames buffered
+    /// for other channels meanwhile do not extend the deadline either.
+    pub fn recv_on_timeout(&mut self, channel: u16, timeout: Duration) -> Result<Frame> {
+        if channel != CONTROL && !self.supports_channel(channel) {
+            return Err(PeerError::UnsupportedChannel(channel));
+        }
+        if self
+            .channel_buffers
+            .get(&channel)
+            .is_some_and(|queue| !queue.is_empty())
+        {
+            return self.recv_on(channel);
+        }
+        let operation = TimeoutOp::Recv {
+            channel: Some(channel),
+        };
+        self.with_read_deadline(timeout, operation, |peer| peer.recv_on(channel))
+    }
+
+    /// Run `recv` with every read bounded by a deadline `timeout` from now,
+    /// restoring the peer's receive timeout afterwards.
+    fn with_read_deadline(
+        &mut self,
+        timeout: Duration,
+        operation: TimeoutOp,
+        recv: impl FnOnce(&mut Self) -> Result<Frame>,
+    ) -> Result<Frame> {
+        self.ensure_synchronized()?;
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        self.read_deadline = Some(Instant::now() + timeout);
+        let result = recv(self);
+        self.read_deadline = None;
+        // A frame already received outlives a failed restore.
+        if let Err(err) = self
+            .reader
+            .get_ref()
+            .set_read_timeout(Some(self.recv_timeout))
+        {
+            trace::stream_restore_failed("read timeout", &err);
+        }
+        result.map_err(|err| match err {
+            PeerError::Timeout { .. } => PeerError::Timeout {
+                operation,
+                waited: timeout,
+            },
+            err => err,
+        })
+    }
+
     /// Send a COMMAND request and wait for COMMAND response.
     ///
     /// This helper is intended for a single in-flight request/response flow.
     /// Replay protection, idempotency, and correlation matching are consumer
     /// policy and should be implemented in message payloads.
     pub fn request(&mut self, payload: &[u8]) -> Result<Frame> {
+        self.ensure_synchronized()?;
         self.send(COMMAND, payload)?;
         self.recv_on(COMMAND)
     }
@@ -195,42 +824,190 @@ impl Peer {
     ///
     /// The same ordering/replay considerations as [`Self::request`] apply.
     pub fn request_json<T: Serialize, R: DeserializeOwned>(&mut self, value: &T) -> Result<R> {
+        self.ensure_synchronized()?;
         self.send_json(COMMAND, value)?;
         let frame = self.recv_on(COMMAND)?;
         Ok(serde_json::from_slice(frame.payload.as_ref())?)
     }
 
+    /// Shut down the write half of the connection: the remote reads EOF
+    /// (`ConnectionClosed`) after the frames already sent, while this peer
+    /// keeps receiving until the remote closes.
+    ///
+    /// For a client that streams a batch and then signals "no more input".
+    /// Later sends fail; CONTROL requests from the remote go unanswered;
+    /// [`Peer::shutdown`] fails, so drop the peer once done receiving.
+    /// Named pipes cannot half-close and fail with a transport error.
+    pub fn finish_sending(&mut self) -> Result<()> {
+        if self.sending_finished {
+            return Ok(());
+        }
+        self.writer.flush()?;
+        self.writer.get_ref().shutdown(std::net::Shutdown::Write)?;
+        self.sending_finished = true;
+        Ok(())
+    }
+
     /// Send ping and wait for pong.
     pub fn ping(&mut self) -> Result<Duration> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        self.with_control_timeout(|peer| {
+            let start = Instant::now();
+            peer.send_control(ControlMessage::ping())
+                .map_err(|err| err.during(TimeoutOp::Ping))?;
+
+            let deadline = start + peer.config.shutdown_timeout;
+            peer.wait_for_control_message(CONTROL_PONG, TimeoutOp::Ping, deadline)?;
+            Ok(start.elapsed())
+        })
+    }
+
+    /// Run a CONTROL exchange with reads bounded by `shutdown_timeout`,
+    /// restoring the peer's receive timeout afterwards.
+    fn with_control_timeout<R>(
+        &mut self,
+        exchange: impl FnOnce(&mut Self) -> Result<R>,
+    ) -> Result<R> {
         self.reader
             .get_ref()
             .set_read_timeout(Some(self.config.shutdown_timeout))?;
+        let result = exchange(self);
+        // The exchange's outcome outlives a failed restore.
+        if let Err(err) = self
+            .reader
+            .get_ref()
+            .set_read_timeout(Some(self.recv_timeout))
+        {
+            trace::stream_restore_failed("read timeout", &err);
+        }
+        result
+    }
 
-        let start = Instant::now();
-        self.send_control(ControlMessage::ping())?;
+    /// Graceful shutdown, escalating as configured by
+    /// [`PeerConfig::shutdown_policy`].
+    ///
+    /// Sends SHUTDOWN_REQUEST and waits for the ack. If that times out and a
+    /// force phase is configured, sends SHUTDOWN_FORCE and waits for the
+    /// remote to close; if it still has not, either fails or (with
+    /// `hard_close`) closes locally. The connection is closed on return
+    /// either way.
+    pub fn shutdown(mut self) -> Result<ShutdownOutcome> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        if self.sending_finished {
+            return Err(PeerError::ShutdownFailed(
+                "sending already finished; drop the peer to close".to_string(),
+            ));
+        }
+        let policy = self.config.shutdown_policy;
+        let ack_timeout = policy.ack_timeout.unwrap_or(self.config.shutdown_timeout);
+        self.reader.get_ref().set_read_timeout(Some(ack_timeout))?;
 
-        let deadline = start + self.config.shutdown_timeout;
-        self.wait_for_control_message(CONTROL_PONG, deadline)?;
-        Ok(start.elapsed())
+        self.send_control(ControlMessage::shutdown_request(None))?;
+        trace::shutdown_phase("requested");
+        let deadline = Instant::now() + ack_timeout;
+        let result = match self.wait_for_control_message(
+            CONTROL_SHUTDOWN_ACK,
+            TimeoutOp::ShutdownAck,
+            deadline,
+        ) {
+            Ok(_) => Ok(ShutdownOutcome::Acknowledged),
+            Err(PeerError::Timeout { .. }) => match policy.force_timeout {
+                Some(force_timeout) => self.force_shutdown(force_timeout, policy.hard_close),
+                None => Err(PeerError::ShutdownFailed(
+                    "timed out waiting for shutdown acknowledgement".to_string(),
+                )),
+            },
+            Err(err) => Err(PeerError::ShutdownFailed(err.to_string())),
+        };
+        trace::shutdown_phase(match &result {
+            Ok(ShutdownOutcome::Acknowledged) => "acknowledged",
+            Ok(ShutdownOutcome::Forced) => "forced",
+            Ok(ShutdownOutcome::HardClosed) => "hard_closed",
+            Err(_) => "failed",
+        });
+        result
     }
 
-    /// Graceful shutdown.
-    pub fn shutdown(mut self) -> Result<()> {
+    /// Second shutdown phase: send SHUTDOWN_FORCE and wait for the remote to
+    /// go away.
+    fn force_shutdown(
+        &mut self,
+        force_timeout: Duration,
+        hard_close: bool,
+    ) -> Result<ShutdownOutcome> {
+        trace::shutdown_phase("forcing");
+        match self.send_control(ControlMessage::shutdown_force()) {
+            Ok(()) => {}
+            // Already gone; the force had nothing left to do.
+            Err(PeerError::Disconnected(_)) => return Ok(ShutdownOutcome::Forced),
+            Err(err) => return Err(PeerError::ShutdownFailed(err.to_string())),
+        }
         self.reader
             .get_ref()
-            .set_read_timeout(Some(self.config.shutdown_timeout))?;
+            .set_read_timeout(Some(force_timeout))?;
 
-        self.send_control(ControlMessage::shutdown_request(None))?;
-        let deadline = Instant::now() + self.config.shutdown_timeout;
-        match self.wait_for_control_message(CONTROL_SHUTDOWN_ACK, deadline) {
-            Ok(()) => Ok(()),
-            Err(PeerError::Timeout(_)) => Err(PeerError::ShutdownFailed(
-                "timed out waiting for shutdown acknowledgement".to_string(),
+        let deadline = Instant::now() + force_timeout;
+        match self.wait_for_control_message(CONTROL_SHUTDOWN_ACK, TimeoutOp::ShutdownAck, deadline)
+        {
+            Ok(_) | Err(PeerError::Disconnected(_)) => Ok(ShutdownOutcome::Forced),
+            Err(PeerError::Timeout { .. }) if hard_close => Ok(ShutdownOutcome::HardClosed),
+            Err(PeerError::Timeout { .. }) => Err(PeerError::ShutdownFailed(
+                "remote did not close after force shutdown".to_string(),
             )),
             Err(err) => Err(PeerError::ShutdownFailed(err.to_string())),
         }
     }
 
+    /// True once a corrupt, unsupported or oversized frame header has been
+    /// read.
+    ///
+    /// A poisoned peer fails every send and receive, as well as request,
+    /// ping and shutdown, with [`PeerError::Desynchronized`] until
+    /// [`Self::resync`] succeeds. [`Self::send_final_error`] can still send
+    /// one error reply before closing.
+    pub fn is_poisoned(&self) -> bool {
+        self.desynchronized.is_some()
+    }
+
+    /// Skip to the next frame boundary after a framing error and clear the
+    /// poisoned state.
+    ///
+    /// Discards inbound bytes up to the next plausible frame header (see
+    /// [`FrameReader::resync`]) and returns how many were dropped; frames
+    /// lost in them are not reported. Returns `Ok(0)` without reading if the
+    /// peer is not poisoned. On timeout the peer stays poisoned and the call
+    /// can be retried.
+    pub fn resync(&mut self) -> Result<usize> {
+        let _span = self.span.clone().entered();
+        if self.desynchronized.is_none() {
+            return Ok(0);
+        }
+        let discarded = self
+            .reader
+            .resync()
+            .map_err(|err| classify_frame_error(err, self.config.shutdown_timeout))?;
+        self.desynchronized = None;
+        self.final_error_sent = false;
+        trace::resynchronized(discarded);
+        Ok(discarded)
+    }
+
+    /// Frames and payload bytes sent and received since the handshake,
+    /// CONTROL traffic included, if [`PeerConfig::collect_stats`] is set.
+    pub fn stats(&self) -> Option<FrameStats> {
+        let mut stats = self.reader.stats()?.clone();
+        stats.merge(self.writer.stats()?);
+        Some(stats)
+    }
+
+    /// Zero the counters reported by [`Self::stats`].
+    pub fn reset_stats(&mut self) {
+        self.reader.reset_stats();
+        self.writer.reset_stats();
+    }
+
     /// Peer identifier.
     pub fn id(&self) -> &str {
         &self.id
@@ -261,21 +1038,175 @@ impl Peer {
         self.client_auth_token.take()
     }
 
+    /// Present a new auth token without reconnecting.
+    ///
+    /// Sends it over CONTROL and waits up to `shutdown_timeout` for the
+    /// remote's verdict, which comes from its
+    /// [`PeerConfig::auth_refresh_validator`]. A rejection returns
+    /// [`PeerError::AuthRejected`]; the remote may also close the connection
+    /// if it sets `disconnect_on_auth_reject`. Only a blocking [`Peer`] on the
+    /// other end handles refreshes.
+    pub fn refresh_auth(&mut self, token: &str) -> Result<()> {
+        let _span = self.span.clone().entered();
+        self.ensure_synchronized()?;
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        if let Err(reason) = check_auth_token_len(token) {
+            trace::auth_refreshed("client", Some(token), Some(&reason));
+            return Err(PeerError::AuthRejected(reason));
+        }
+        let verdict = self.with_control_timeout(|peer| {
+            peer.send_control(ControlMessage::auth_refresh(token))
+                .map_err(|err| err.during(TimeoutOp::AuthRefresh))?;
+            let deadline = Instant::now() + peer.config.shutdown_timeout;
+            peer.wait_for_control_message(CONTROL_AUTH_RESULT, TimeoutOp::AuthRefresh, deadline)
+        })?;
+
+        let payload = verdict.payload.unwrap_or_default();
+        if payload.get("accepted").and_then(serde_json::Value::as_bool) == Some(true) {
+            trace::auth_refreshed("client", Some(token), None);
+            return Ok(());
+        }
+        let reason = payload
+            .get("reason")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("rejected by remote")
+            .to_string();
+        trace::auth_refreshed("client", Some(token), Some(&reason));
+        Err(PeerError::AuthRejected(reason))
+    }
+
+    /// The stream's kernel send buffer size, as the kernel reports it.
+    #[cfg(unix)]
+    pub fn send_buffer_size(&self) -> Result<usize> {
+        Ok(self.writer.get_ref().send_buffer_size()?)
+    }
+
+    /// The stream's kernel receive buffer size, as the kernel reports it.
+    #[cfg(unix)]
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        Ok(self.reader.get_ref().recv_buffer_size()?)
+    }
+
+    /// Address of the other end of the connection, as the transport
+    /// reports it; `None` if the socket can no longer be queried.
+    pub fn remote_addr(&self) -> Option<IpcAddr> {
+        self.reader.get_ref().peer_addr().ok()
+    }
+
     /// Connected peer credentials, when available on this platform.
-    pub fn peer_credentials(&self) -> Option<(u32, u32, u32)> {
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
         self.reader.get_ref().peer_credentials()
     }
 
+    /// Set how long [`Self::recv`] and [`Self::recv_on`] block before
+    /// returning [`PeerError::Timeout`].
+    ///
+    /// Defaults to `PeerConfig::shutdown_timeout`. [`ping`](Self::ping) and
+    /// [`refresh_auth`](Self::refresh_auth) wait up to `shutdown_timeout`
+    /// for their reply and then restore this timeout; [`shutdown`](Self::shutdown)
+    /// uses the shutdown policy's timeouts.
+    pub fn set_recv_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.reader.get_ref().set_read_timeout(Some(timeout))?;
+        self.recv_timeout = timeout;
+        Ok(())
+    }
+
+    /// Decompose for [`crate::split`], failing if a shutdown was already requested.
+    pub(crate) fn into_parts(self) -> Result<PeerParts> {
+        if self.shutdown_requested {
+            return Err(PeerError::Disconnected("shutdown requested".to_string()));
+        }
+        self.ensure_synchronized()?;
+        Ok(PeerParts {
+            span: self.span,
+            id: self.id,
+            reader: self.reader,
+            writer: self.writer,
+            handshake_result: self.handshake_result,
+            schema_registry: self.schema_registry,
+            channel_buffers: self
+                .channel_buffers
+                .into_iter()
+                .map(|(channel, queue)| (channel, queue.into_iter().map(|(_, f)| f).collect()))
+                .collect(),
+            config: self.config,
+        })
+    }
+
     fn send_control(&mut self, message: ControlMessage) -> Result<()> {
+        if self.sending_finished {
+            // The remote has read EOF; replies to its CONTROL requests
+            // (pong, shutdown ack, auth verdict) can no longer be sent.
+            return Ok(());
+        }
         let payload = serde_json::to_vec(&message)?;
-        self.writer.send(CONTROL, &payload)?;
+        self.writer.send(CONTROL, &payload).map_err(|err| {
+            PeerError::from(err).during(TimeoutOp::Send {
+                channel: Some(CONTROL),
+            })
+        })?;
         Ok(())
     }
 
     fn read_frame_once(&mut self) -> Result<Frame> {
+        if let Some(deadline) = self.read_deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(PeerError::Timeout {
+                    operation: TimeoutOp::Recv { channel: None },
+                    waited: remaining,
+                });
+            }
+            self.reader.get_ref().set_read_timeout(Some(remaining))?;
+        }
         match self.reader.read_frame() {
-            Ok(frame) => Ok(frame),
-            Err(err) => Err(classify_frame_error(err, self.config.shutdown_timeout)),
+            Ok(frame) => {
+                trace::frame_received(frame.channel, frame.payload.len());
+                Ok(frame)
+            }
+            Err(err) => {
+                self.note_desync(&err);
+                Err(classify_frame_error(err, self.config.shutdown_timeout))
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn try_read_frame_once(&mut self) -> Result<Option<Frame>> {
+        match self.reader.try_read_frame() {
+            Ok(Some(frame)) => {
+                trace::frame_received(frame.channel, frame.payload.len());
+                Ok(Some(frame))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                self.note_desync(&err);
+                Err(classify_frame_error(err, self.config.shutdown_timeout))
+            }
+        }
+    }
+
+    /// Poison the peer if `err` means the reader lost frame alignment.
+    fn note_desync(&mut self, err: &FrameError) {
+        if matches!(
+            err,
+            FrameError::InvalidMagic
+                | FrameError::UnsupportedVersion(_)
+                | FrameError::UnsupportedFlags(_)
+                | FrameError::PayloadTooLarge { .. }
+                | FrameError::BufferLimitExceeded { .. }
+        ) {
+            trace::desynchronized(err);
+            self.desynchronized = Some(err.to_string());
+        }
+    }
+
+    fn ensure_synchronized(&self) -> Result<()> {
+        match &self.desynchronized {
+            Some(reason) => Err(PeerError::Desynchronized(reason.clone())),
+            None => Ok(()),
         }
     }
 
@@ -283,30 +1214,47 @@ impl Peer {
         let message = match serde_json::from_slice::<ControlMessage>(frame.payload.as_ref()) {
             Ok(message) => message,
             Err(_) => {
+                trace::control_rejected(None, "invalid CONTROL JSON payload");
                 return Ok(ControlDisposition::Disconnected(
                     "invalid CONTROL JSON payload".to_string(),
                 ));
             }
         };
+        trace::control_received(&message.msg_type);
 
         match message.msg_type.as_str() {
             CONTROL_PING => {
                 self.send_control(ControlMessage::pong())?;
                 Ok(ControlDisposition::Continue)
             }
-            CONTROL_PONG => Ok(ControlDisposition::Continue),
+            // A verdict that arrives after `refresh_auth` gave up waiting.
+            CONTROL_PONG | CONTROL_AUTH_RESULT => Ok(ControlDisposition::Continue),
+            CONTROL_AUTH_REFRESH => Ok(match self.handle_auth_refresh(&message)? {
+                Some(reason) => ControlDisposition::Disconnected(reason),
+                None => ControlDisposition::Continue,
+            }),
+            CONTROL_FDS => Ok(match self.handle_fds_announcement(&message) {
+                Some(reason) => ControlDisposition::Disconnected(reason),
+                None => ControlDisposition::Continue,
+            }),
             CONTROL_SHUTDOWN_REQUEST => {
                 self.send_control(ControlMessage::shutdown_ack())?;
+                trace::shutdown_phase("remote_requested");
                 self.shutdown_requested = true;
                 Ok(ControlDisposition::Continue)
             }
             CONTROL_SHUTDOWN_FORCE => {
                 if self.config.allow_shutdown_force {
+                    trace::shutdown_phase("remote_forced");
                     self.shutdown_requested = true;
                     Ok(ControlDisposition::Disconnected(
-                        "force shutdown".to_string(),
+                        FORCED_SHUTDOWN_REASON.to_string(),
                     ))
                 } else {
+                    trace::control_rejected(
+                        Some(CONTROL_SHUTDOWN_FORCE),
+                        "received disallowed SHUTDOWN_FORCE",
+                    );
                     Ok(ControlDisposition::Disconnected(
                         "received disallowed SHUTDOWN_FORCE".to_string(),
                     ))
@@ -315,21 +1263,35 @@ impl Peer {
             _ if self.config.allow_unknown_control_messages => {
                 Ok(ControlDisposition::Return(frame))
             }
-            _ => Ok(ControlDisposition::Disconnected(
-                "unknown CONTROL message type".to_string(),
-            )),
+            msg_type => {
+                trace::control_rejected(Some(msg_type), "unknown CONTROL message type");
+                Ok(ControlDisposition::Disconnected(
+                    "unknown CONTROL message type".to_string(),
+                ))
+            }
         }
     }
 
-    fn wait_for_control_message(&mut self, expected: &str, deadline: Instant) -> Result<()> {
+    fn wait_for_control_message(
+        &mut self,
+        expected: &str,
+        operation: TimeoutOp,
+        deadline: Instant,
+    ) -> Result<ControlMessage> {
         let mut control_frames_seen = 0usize;
         loop {
             if Instant::now() >= deadline {
-                return Err(PeerError::Timeout(self.config.shutdown_timeout));
+                return Err(PeerError::Timeout {
+                    operation,
+                    waited: self.config.shutdown_timeout,
+                });
             }
 
             let frame = match self.reader.read_frame() {
-                Ok(frame) => frame,
+                Ok(frame) => {
+                    trace::frame_received(frame.channel, frame.payload.len());
+                    frame
+                }
                 Err(FrameError::Io(err))
                     if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
                 {
@@ -338,12 +1300,16 @@ impl Peer {
                 Err(FrameError::ConnectionClosed) => {
                     return Err(PeerError::Disconnected("connection closed".to_string()));
                 }
-                Err(err) => return Err(PeerError::Frame(err)),
+                Err(err) => {
+                    self.note_desync(&err);
+                    return Err(PeerError::Frame(err));
+                }
             };
 
             if frame.channel != CONTROL {
                 self.ensure_inbound_channel(frame.channel)?;
                 self.validate_recv(&frame)?;
+                self.claim_announced_fds(frame.channel);
                 self.buffer_frame(frame)?;
                 continue;
             }
@@ -358,27 +1324,45 @@ impl Peer {
             let message = match parsed {
                 Ok(message) => message,
                 Err(_) => {
+                    trace::control_rejected(None, "invalid CONTROL JSON payload");
                     return Err(PeerError::Disconnected(
                         "invalid CONTROL JSON payload".to_string(),
                     ));
                 }
             };
+            trace::control_received(&message.msg_type);
 
             match message.msg_type.as_str() {
-                msg if msg == expected => return Ok(()),
+                msg if msg == expected => return Ok(message),
                 CONTROL_PING => {
                     self.send_control(ControlMessage::pong())?;
                 }
+                CONTROL_AUTH_REFRESH => {
+                    if let Some(reason) = self.handle_auth_refresh(&message)? {
+                        return Err(PeerError::Disconnected(reason));
+                    }
+                }
+                CONTROL_FDS => {
+                    if let Some(reason) = self.handle_fds_announcement(&message) {
+                        return Err(PeerError::Disconnected(reason));
+                    }
+                }
                 CONTROL_SHUTDOWN_REQUEST => {
                     self.send_control(ControlMessage::shutdown_ack())?;
+                    trace::shutdown_phase("remote_requested");
                     self.shutdown_requested = true;
                     return Err(PeerError::Disconnected("shutdown requested".to_string()));
                 }
                 CONTROL_SHUTDOWN_FORCE => {
                     if self.config.allow_shutdown_force {
+                        trace::shutdown_phase("remote_forced");
                         self.shutdown_requested = true;
-                        return Err(PeerError::Disconnected("force shutdown".to_string()));
+                        return Err(PeerError::Disconnected(FORCED_SHUTDOWN_REASON.to_string()));
                     }
+                    trace::control_rejected(
+                        Some(CONTROL_SHUTDOWN_FORCE),
+                        "received disallowed SHUTDOWN_FORCE",
+                    );
                     return Err(PeerError::Disconnected(
                         "received disallowed SHUTDOWN_FORCE".to_string(),
                     ));
@@ -386,7 +1370,8 @@ impl Peer {
                 _ if self.config.allow_unknown_control_messages => {
                     self.buffer_frame(frame)?;
                 }
-                _ => {
+                msg_type => {
+                    trace::control_rejected(Some(msg_type), "unknown CONTROL message type");
                     return Err(PeerError::Disconnected(
                         "unknown CONTROL message type".to_string(),
                     ));
@@ -395,6 +1380,80 @@ impl Peer {
         }
     }
 
+    /// Validate a token the remote sent with [`Peer::refresh_auth`] and reply
+    /// with the verdict. Returns a disconnect reason when a rejection should
+    /// close the connection.
+    fn handle_auth_refresh(&mut self, message: &ControlMessage) -> Result<Option<String>> {
+        let token = message.auth_token();
+        let verdict = match token {
+            None => Err("missing auth token".to_string()),
+            Some(token) => check_auth_token_len(token).and_then(|()| {
+                match &self.config.auth_refresh_validator {
+                    Some(validator) if validator(Some(token)) => Ok(token),
+                    Some(_) => Err("auth token rejected".to_string()),
+                    None => Err("auth refresh not supported".to_string()),
+                }
+            }),
+        };
+
+        match verdict {
+            Ok(token) => {
+                self.send_control(ControlMessage::auth_result(true, None))?;
+                trace::auth_refreshed("server", Some(token), None);
+                self.client_auth_token = Some(token.to_string());
+                Ok(None)
+            }
+            Err(reason) => {
+                self.send_control(ControlMessage::auth_result(false, Some(&reason)))?;
+                trace::auth_refreshed("server", token, Some(&reason));
+                Ok(self
+                    .config
+                    .disconnect_on_auth_reject
+                    .then(|| format!("auth refresh rejected: {reason}")))
+            }
+        }
+    }
+
+    /// Claim the descriptors a [`CONTROL_FDS`] announcement says came with
+    /// it, for the next frame on its channel. Returns a disconnect reason.
+    #[cfg(unix)]
+    fn handle_fds_announcement(&mut self, message: &ControlMessage) -> Option<String> {
+        let announcement = message
+            .fds_announcement()
+            .filter(|&(_, count)| (1..=ipcprims_transport::MAX_FDS_PER_MESSAGE).contains(&count));
+        let Some((channel, count)) = announcement else {
+            trace::control_rejected(Some(CONTROL_FDS), "invalid fds announcement");
+            return Some("invalid fds announcement".to_string());
+        };
+        if !self.config.receive_fds {
+            // The reader discarded them; the frame arrives without.
+            return None;
+        }
+        match self.reader.get_mut().take_received_fds(count) {
+            Some(fds) => {
+                self.announced_fds.insert(channel, fds);
+                None
+            }
+            None => Some("announced file descriptors did not arrive".to_string()),
+        }
+    }
+
+    /// Descriptors cannot be passed here; frames arrive without them.
+    #[cfg(not(unix))]
+    fn handle_fds_announcement(&mut self, _message: &ControlMessage) -> Option<String> {
+        None
+    }
+
+    /// Make the descriptors announced for `channel` those of the frame
+    /// just read from it.
+    #[cfg(unix)]
+    fn claim_announced_fds(&mut self, channel: u16) {
+        self.received_fds = self.announced_fds.remove(&channel).unwrap_or_default();
+    }
+
+    #[cfg(not(unix))]
+    fn claim_announced_fds(&mut self, _channel: u16) {}
+
     fn buffer_frame(&mut self, frame: Frame) -> Result<()> {
         let frame_bytes = frame.wire_size();
         if self.buffered_total_bytes.saturating_add(frame_bytes)
@@ -408,20 +1467,51 @@ impl Peer {
             return Err(PeerError::BufferFull(frame.channel));
         }
         self.buffered_total_bytes = self.buffered_total_bytes.saturating_add(frame_bytes);
-        queue.push_back(frame);
+        queue.push_back((self.next_buffer_seq, frame));
+        #[cfg(unix)]
+        if !self.received_fds.is_empty() {
+            let fds = std::mem::take(&mut self.received_fds);
+            self.buffered_fds.insert(self.next_buffer_seq, fds);
+        }
+        self.next_buffer_seq += 1;
         Ok(())
     }
 
     fn pop_buffered(&mut self, channel: u16) -> Option<Frame> {
+        self.take_buffered(channel, 0)
+    }
+
+    /// Remove the buffered frame with the highest priority, oldest first
+    /// among equals, across all channels.
+    fn pop_highest_priority(&mut self) -> Option<Frame> {
+        let (channel, index) = self
+            .channel_buffers
+            .iter()
+            .flat_map(|(&channel, queue)| {
+                queue
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, (seq, frame))| (frame.priority, *seq, channel, index))
+            })
+            .min_by_key(|&(priority, seq, _, _)| (std::cmp::Reverse(priority), seq))
+            .map(|(_, _, channel, index)| (channel, index))?;
+        self.take_buffered(channel, index)
+    }
+
+    fn take_buffered(&mut self, channel: u16, index: usize) -> Option<Frame> {
         let queue = self.channel_buffers.get_mut(&channel)?;
-        let frame = queue.pop_front();
-        if let Some(frame) = &frame {
-            self.buffered_total_bytes = self.buffered_total_bytes.saturating_sub(frame.wire_size());
-        }
+        let (seq, frame) = queue.remove(index)?;
+        self.buffered_total_bytes = self.buffered_total_bytes.saturating_sub(frame.wire_size());
         if queue.is_empty() {
             self.channel_buffers.remove(&channel);
         }
-        frame
+        #[cfg(unix)]
+        {
+            self.received_fds = self.buffered_fds.remove(&seq).unwrap_or_default();
+        }
+        #[cfg(not(unix))]
+        let _ = seq;
+        Some(frame)
     }
 
     fn ensure_inbound_channel(&self, channel: u16) -> Result<()> {
@@ -436,7 +1526,9 @@ impl Peer {
     #[cfg(feature = "schema")]
     fn validate_send(&self, channel: u16, payload: &[u8]) -> Result<()> {
         if let Some(registry) = &self.schema_registry {
-            registry.validate(channel, payload)?;
+            registry
+                .validate(channel, payload)
+                .inspect_err(|err| trace::schema_rejected("send", channel, err))?;
         }
         Ok(())
     }
@@ -450,7 +1542,9 @@ impl Peer {
     #[cfg(feature = "schema")]
     fn validate_recv(&self, frame: &Frame) -> Result<()> {
         if let Some(registry) = &self.schema_registry {
-            registry.validate_frame(frame)?;
+            registry
+                .validate_frame(frame)
+                .inspect_err(|err| trace::schema_rejected("recv", frame.channel, err))?;
         }
         Ok(())
     }
@@ -462,22 +1556,68 @@ impl Peer {
     }
 }
 
+/// Owned pieces of a [`Peer`], handed to the split halves.
+pub(crate) struct PeerParts {
+    pub(crate) span: Span,
+    pub(crate) id: String,
+    pub(crate) reader: FrameReader<IpcStream>,
+    pub(crate) writer: FrameWriter<IpcStream>,
+    pub(crate) handshake_result: HandshakeResult,
+    pub(crate) schema_registry: Option<SchemaRegistryHandle>,
+    /// Frames `recv_on` buffered before the split, in arrival order per channel.
+    pub(crate) channel_buffers: HashMap<u16, VecDeque<Frame>>,
+    pub(crate) config: PeerConfig,
+}
+
+pub(crate) fn transaction_failed(index: usize, source: PeerError) -> PeerError {
+    PeerError::TransactionFailed {
+        index,
+        source: Box::new(source),
+    }
+}
+
+/// Attribute a [`FrameWriter::send_batch`] failure to the frame it hit.
+pub(crate) fn batch_failed(frames: &[(u16, &[u8])], err: BatchError) -> PeerError {
+    let channel = frames.get(err.index).map(|&(channel, _)| channel);
+    transaction_failed(
+        err.index,
+        PeerError::from(err.source).during(TimeoutOp::Send { channel }),
+    )
+}
+
+/// Same length bounds as the handshake's `auth_token`.
+fn check_auth_token_len(token: &str) -> std::result::Result<(), String> {
+    if token.is_empty() || token.len() > MAX_AUTH_TOKEN_LEN {
+        return Err(format!("invalid auth_token length: {}", token.len()));
+    }
+    Ok(())
+}
+
 /// Map a [`FrameError`] from the frame reader into the appropriate [`PeerError`].
 ///
 /// Extracted as a free function so it can be unit-tested without a live transport.
-fn classify_frame_error(err: FrameError, shutdown_timeout: std::time::Duration) -> PeerError {
+pub(crate) fn classify_frame_error(
+    err: FrameError,
+    shutdown_timeout: std::time::Duration,
+) -> PeerError {
     match err {
         FrameError::ConnectionClosed => PeerError::Disconnected("connection closed".to_string()),
+        err @ FrameError::Timeout(_) => err.into(),
         FrameError::Io(ref io_err)
             if io_err.kind() == ErrorKind::WouldBlock || io_err.kind() == ErrorKind::TimedOut =>
         {
-            PeerError::Timeout(shutdown_timeout)
+            PeerError::Timeout {
+                operation: TimeoutOp::Recv { channel: None },
+                waited: shutdown_timeout,
+            }
         }
-        // On Windows, named pipe closure surfaces as BrokenPipe or
-        // ConnectionReset rather than EOF (ConnectionClosed).
+        // The frame layer already maps these to ConnectionClosed; kept for
+        // errors constructed outside FrameReader/FrameWriter.
         FrameError::Io(ref io_err)
-            if io_err.kind() == ErrorKind::BrokenPipe
-                || io_err.kind() == ErrorKind::ConnectionReset =>
+            if matches!(
+                io_err.kind(),
+                ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+            ) =>
         {
             PeerError::Disconnected(format!("connection closed: {}", io_err))
         }
@@ -529,8 +1669,14 @@ mod classify_frame_error_tests {
         let io_err = std::io::Error::new(ErrorKind::WouldBlock, "would block");
         let err = classify_frame_error(FrameError::Io(io_err), Duration::from_secs(3));
         assert!(
-            matches!(err, PeerError::Timeout(d) if d == Duration::from_secs(3)),
-            "expected Timeout(3s), got {:?}",
+            matches!(
+                err,
+                PeerError::Timeout {
+                    operation: TimeoutOp::Recv { channel: None },
+                    waited,
+                } if waited == Duration::from_secs(3)
+            ),
+            "expected recv Timeout(3s), got {:?}",
             err,
         );
     }
@@ -586,111 +1732,582 @@ mod tests {
     fn peer_pair(config: PeerConfig) -> (Peer, Peer) {
         let (left, right) = make_connected_ipc_pair();
 
-        let left_reader = FrameReader::with_config_ipc(
-            left.try_clone().unwrap(),
-            ipcprims_frame::FrameConfig {
-                read_timeout: Some(config.shutdown_timeout),
-                write_timeout: Some(config.shutdown_timeout),
-                ..Default::default()
-            },
-        )
-        .unwrap();
-        let left_writer = FrameWriter::with_config_ipc(
-            left,
-            ipcprims_frame::FrameConfig {
-                read_timeout: Some(config.shutdown_timeout),
-                write_timeout: Some(config.shutdown_timeout),
-                ..Default::default()
-            },
-        )
-        .unwrap();
+        let left_reader = FrameReader::with_config_ipc(
+            left.try_clone().unwrap(),
+            ipcprims_frame::FrameConfig {
+                read_timeout: Some(config.shutdown_timeout),
+                write_timeout: Some(config.shutdown_timeout),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let left_writer = FrameWriter::with_config_ipc(
+            left,
+            ipcprims_frame::FrameConfig {
+                read_timeout: Some(config.shutdown_timeout),
+                write_timeout: Some(config.shutdown_timeout),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let right_reader = FrameReader::with_config_ipc(
+            right.try_clone().unwrap(),
+            ipcprims_frame::FrameConfig {
+                read_timeout: Some(config.shutdown_timeout),
+                write_timeout: Some(config.shutdown_timeout),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let right_writer = FrameWriter::with_config_ipc(
+            right,
+            ipcprims_frame::FrameConfig {
+                read_timeout: Some(config.shutdown_timeout),
+                write_timeout: Some(config.shutdown_timeout),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let handshake = HandshakeResult {
+            peer_id: "peer".to_string(),
+            protocol_version: "1.0".to_string(),
+            negotiated_channels: vec![1, 2, 3, 4],
+            client_auth_token: None,
+            remote_library_version: None,
+            priority_frames: true,
+            checksum_frames: true,
+            wire_version: WireVersion::V1,
+        };
+
+        let a = Peer::from_parts(
+            "left".to_string(),
+            left_reader,
+            left_writer,
+            handshake.clone(),
+            None,
+            config.clone(),
+            trace::connection_span(Some("left")),
+        );
+        let b = Peer::from_parts(
+            "right".to_string(),
+            right_reader,
+            right_writer,
+            handshake,
+            None,
+            config,
+            trace::connection_span(Some("right")),
+        );
+
+        (a, b)
+    }
+
+    #[test]
+    fn send_recv() {
+        let config = PeerConfig::default();
+        let (mut a, mut b) = peer_pair(config);
+
+        a.send(1, b"hello").unwrap();
+        let frame = b.recv().unwrap();
+        assert_eq!(frame.channel, 1);
+        assert_eq!(frame.payload.as_ref(), b"hello");
+    }
+
+    #[test]
+    fn send_and_recv_after_remote_drop_report_disconnected() {
+        let (mut a, b) = peer_pair(PeerConfig::default());
+        drop(b);
+
+        assert!(matches!(
+            a.send(1, b"late"),
+            Err(PeerError::Disconnected(_))
+        ));
+        assert!(matches!(a.recv(), Err(PeerError::Disconnected(_))));
+    }
+
+    #[test]
+    fn garbage_mid_stream_poisons_until_resync() {
+        use std::io::Write;
+
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        let garbage = b"\x00\xffjunk\x13";
+
+        b.send(1, b"one").unwrap();
+        b.writer.get_mut().write_all(garbage).unwrap();
+        b.send(2, b"two").unwrap();
+        b.send(1, b"three").unwrap();
+
+        assert_eq!(a.recv().unwrap().payload.as_ref(), b"one");
+        assert!(!a.is_poisoned());
+        assert!(matches!(
+            a.recv(),
+            Err(PeerError::Frame(FrameError::InvalidMagic))
+        ));
+        assert!(a.is_poisoned());
+        assert!(matches!(a.recv(), Err(PeerError::Desynchronized(_))));
+        assert!(matches!(a.recv_on(1), Err(PeerError::Desynchronized(_))));
+        assert!(matches!(
+            a.request(b"query"),
+            Err(PeerError::Desynchronized(_))
+        ));
+        assert!(matches!(
+            a.send(1, b"early"),
+            Err(PeerError::Desynchronized(_))
+        ));
+        assert!(matches!(
+            a.send_transaction(&[(1, b"early")]),
+            Err(PeerError::Desynchronized(_))
+        ));
+        a.send_final_error(b"bad frame").unwrap();
+        assert!(matches!(
+            a.send_final_error(b"again"),
+            Err(PeerError::Desynchronized(_))
+        ));
+
+        assert_eq!(a.resync().unwrap(), garbage.len());
+        assert!(!a.is_poisoned());
+        let two = a.recv().unwrap();
+        assert_eq!((two.channel, two.payload.as_ref()), (2, b"two".as_ref()));
+        assert_eq!(a.recv_on(1).unwrap().payload.as_ref(), b"three");
+
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"bad frame");
+        a.send(1, b"reply").unwrap();
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"reply");
+    }
+
+    #[test]
+    fn oversized_header_poisons_and_resync_recovers() {
+        use std::io::Write;
+
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        let mut bogus = b"IP".to_vec();
+        bogus.extend_from_slice(&u32::MAX.to_le_bytes());
+        bogus.extend_from_slice(&1u16.to_le_bytes());
+
+        b.writer.get_mut().write_all(&bogus).unwrap();
+        b.send(1, b"after").unwrap();
+
+        let err = a.recv().unwrap_err();
+        assert!(matches!(
+            err,
+            PeerError::Frame(FrameError::PayloadTooLarge { .. })
+        ));
+        let err = a.ping().unwrap_err();
+        assert!(
+            err.to_string().contains("payload too large"),
+            "expected the original error in {err}"
+        );
+
+        assert_eq!(a.resync().unwrap(), bogus.len());
+        assert_eq!(a.recv().unwrap().payload.as_ref(), b"after");
+    }
+
+    #[test]
+    fn resync_is_a_no_op_when_not_poisoned() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        assert_eq!(a.resync().unwrap(), 0);
+
+        b.send(1, b"intact").unwrap();
+        assert_eq!(a.recv().unwrap().payload.as_ref(), b"intact");
+    }
+
+    #[test]
+    fn send_json_and_parse() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Msg {
+            n: u32,
+        }
+
+        let config = PeerConfig::default();
+        let (mut a, mut b) = peer_pair(config);
+
+        a.send_json(1, &Msg { n: 7 }).unwrap();
+        let frame = b.recv().unwrap();
+        let parsed: Msg = serde_json::from_slice(frame.payload.as_ref()).unwrap();
+        assert_eq!(parsed.n, 7);
+    }
+
+    #[test]
+    fn recv_on_buffers_other_channels() {
+        let config = PeerConfig::default();
+        let (mut a, mut b) = peer_pair(config);
+
+        a.send(1, b"one").unwrap();
+        a.send(2, b"two").unwrap();
+
+        let two = b.recv_on(2).unwrap();
+        assert_eq!(two.payload.as_ref(), b"two");
+
+        let one = b.recv_on(1).unwrap();
+        assert_eq!(one.payload.as_ref(), b"one");
+    }
+
+    #[test]
+    fn recv_timeout_reports_the_per_call_deadline() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        let deadline = Duration::from_millis(30);
+
+        let start = Instant::now();
+        match b.recv_timeout(deadline) {
+            Err(PeerError::Timeout { operation, waited }) => {
+                assert_eq!(operation, TimeoutOp::Recv { channel: None });
+                assert_eq!(waited, deadline);
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        assert!(start.elapsed() < PeerConfig::default().shutdown_timeout);
+        match b.recv_on_timeout(2, deadline) {
+            Err(PeerError::Timeout { operation, waited }) => {
+                assert_eq!(operation, TimeoutOp::Recv { channel: Some(2) });
+                assert_eq!(waited, deadline);
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+
+        a.send(1, b"one").unwrap();
+        let one = b.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(one.payload.as_ref(), b"one");
+        assert!(matches!(
+            b.recv_on_timeout(9, deadline),
+            Err(PeerError::UnsupportedChannel(9))
+        ));
+    }
+
+    #[test]
+    fn recv_on_timeout_holds_its_deadline_while_other_channels_stream() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let streaming = Arc::clone(&stop);
+        let sender = std::thread::spawn(move || {
+            while !streaming.load(std::sync::atomic::Ordering::Relaxed) {
+                a.send(2, b"tick").unwrap();
+                a.send_control(ControlMessage::ping()).unwrap();
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            a
+        });
+
+        let deadline = Duration::from_millis(100);
+        let start = Instant::now();
+        let err = b.recv_on_timeout(1, deadline).unwrap_err();
+        let elapsed = start.elapsed();
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        let _a = sender.join().unwrap();
+
+        assert!(
+            matches!(err, PeerError::Timeout { waited, .. } if waited == deadline),
+            "expected a timeout, got {err:?}"
+        );
+        assert!(elapsed < Duration::from_secs(1), "took {elapsed:?}");
+        assert!(b.pop_buffered(2).is_some());
+    }
+
+    #[test]
+    fn recv_timeout_returns_buffered_frames_immediately() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        a.send(1, b"one").unwrap();
+        a.send(2, b"two").unwrap();
+        let two = b.recv_on(2).unwrap();
+        assert_eq!(two.payload.as_ref(), b"two");
+
+        // A zero deadline never reads, so only the buffer can answer.
+        let one = b.recv_on_timeout(1, Duration::ZERO).unwrap();
+        assert_eq!(one.payload.as_ref(), b"one");
+        assert!(matches!(
+            b.recv_timeout(Duration::ZERO),
+            Err(PeerError::Timeout { .. })
+        ));
+
+        a.send(1, b"buffered").unwrap();
+        a.send(2, b"later").unwrap();
+        let _ = b.recv_on(2).unwrap();
+        let buffered = b.recv_timeout(Duration::ZERO).unwrap();
+        assert_eq!(buffered.payload.as_ref(), b"buffered");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn try_recv_polls_without_blocking() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        assert!(b.try_recv().unwrap().is_none());
+        assert!(b.try_recv_on(1).unwrap().is_none());
+
+        a.send(2, b"two").unwrap();
+        a.send(1, b"one").unwrap();
+        let one = b.try_recv_on(1).unwrap().expect("frame on channel 1");
+        assert_eq!(one.payload.as_ref(), b"one");
+        let two = b.try_recv().unwrap().expect("buffered frame");
+        assert_eq!(two.payload.as_ref(), b"two");
+        assert!(b.try_recv().unwrap().is_none());
+
+        // The stream is blocking again for ordinary receives.
+        a.send(1, b"blocking").unwrap();
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"blocking");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn try_recv_answers_control_frames() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        a.send_control(ControlMessage::ping()).unwrap();
+        assert!(b.try_recv().unwrap().is_none());
+
+        let pong = a.reader.read_frame().unwrap();
+        assert_eq!(pong.channel, CONTROL);
+        let message: ControlMessage = serde_json::from_slice(&pong.payload).unwrap();
+        assert_eq!(message.msg_type, CONTROL_PONG);
+    }
+
+    #[test]
+    fn recv_returns_buffered_frames_by_priority() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+
+        a.send_with_priority(1, b"low-1", Priority::NORMAL).unwrap();
+        a.send_with_priority(2, b"high-2", Priority(5)).unwrap();
+        a.send_with_priority(1, b"low-2", Priority::NORMAL).unwrap();
+        a.send_with_priority(3, b"high-3", Priority(5)).unwrap();
+        a.send_with_priority(1, b"urgent", Priority(9)).unwrap();
+        a.send(4, b"target").unwrap();
+
+        assert_eq!(b.recv_on(4).unwrap().payload.as_ref(), b"target");
+
+        let order: Vec<_> = (0..5)
+            .map(|_| {
+                let frame = b.recv().unwrap();
+                (frame.priority, frame.payload.to_vec())
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (Priority(9), b"urgent".to_vec()),
+                (Priority(5), b"high-2".to_vec()),
+                (Priority(5), b"high-3".to_vec()),
+                (Priority::NORMAL, b"low-1".to_vec()),
+                (Priority::NORMAL, b"low-2".to_vec()),
+            ]
+        );
+        assert_eq!(b.buffered_total_bytes, 0);
+    }
+
+    #[test]
+    fn send_uses_channel_default_priority_when_negotiated() {
+        let config = PeerConfig {
+            channel_priorities: HashMap::from([(2, Priority(3))]),
+            ..PeerConfig::default()
+        };
+        let (mut a, mut b) = peer_pair(config);
+
+        a.send(2, b"defaulted").unwrap();
+        a.send(1, b"unlisted").unwrap();
+        assert_eq!(b.recv().unwrap().priority, Priority(3));
+        assert_eq!(b.recv().unwrap().priority, Priority::NORMAL);
 
-        let right_reader = FrameReader::with_config_ipc(
-            right.try_clone().unwrap(),
-            ipcprims_frame::FrameConfig {
-                read_timeout: Some(config.shutdown_timeout),
-                write_timeout: Some(config.shutdown_timeout),
-                ..Default::default()
-            },
-        )
-        .unwrap();
-        let right_writer = FrameWriter::with_config_ipc(
-            right,
-            ipcprims_frame::FrameConfig {
-                read_timeout: Some(config.shutdown_timeout),
-                write_timeout: Some(config.shutdown_timeout),
-                ..Default::default()
-            },
-        )
-        .unwrap();
+        // A remote without frame priorities gets everything at priority 0.
+        a.handshake_result.priority_frames = false;
+        a.send_with_priority(1, b"downgraded", Priority(7)).unwrap();
+        let frame = b.recv().unwrap();
+        assert_eq!(frame.priority, Priority::NORMAL);
+        assert_eq!(frame.payload.as_ref(), b"downgraded");
+    }
 
-        let handshake = HandshakeResult {
-            peer_id: "peer".to_string(),
-            protocol_version: "1.0".to_string(),
-            negotiated_channels: vec![1, 2, 3, 4],
-            client_auth_token: None,
+    #[test]
+    fn checksum_frames_are_sent_only_when_negotiated() {
+        let config = PeerConfig {
+            checksum_frames: true,
+            ..PeerConfig::default()
         };
+        let (mut a, mut b) = peer_pair(config);
+        assert!(a.writer.config().checksum);
 
-        let a = Peer::from_parts(
+        a.send(1, b"checked").unwrap();
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"checked");
+
+        let (a, _b) = peer_pair(PeerConfig::default());
+        assert!(!a.writer.config().checksum);
+
+        let (left, right) = make_connected_ipc_pair();
+        let mut handshake = a.handshake_result.clone();
+        handshake.checksum_frames = false;
+        let peer = Peer::from_parts(
             "left".to_string(),
-            left_reader,
-            left_writer,
-            handshake.clone(),
-            None,
-            config.clone(),
-        );
-        let b = Peer::from_parts(
-            "right".to_string(),
-            right_reader,
-            right_writer,
+            FrameReader::new(left.try_clone().unwrap()),
+            FrameWriter::new(left),
             handshake,
             None,
-            config,
+            PeerConfig {
+                checksum_frames: true,
+                ..PeerConfig::default()
+            },
+            trace::connection_span(Some("left")),
         );
-
-        (a, b)
+        assert!(!peer.writer.config().checksum);
+        drop(right);
     }
 
     #[test]
-    fn send_recv() {
-        let config = PeerConfig::default();
+    fn stats_cover_both_directions() {
+        let config = PeerConfig {
+            collect_stats: true,
+            ..PeerConfig::default()
+        };
         let (mut a, mut b) = peer_pair(config);
+        a.send(1, b"one").unwrap();
+        a.send(1, b"two").unwrap();
+        a.send(2, b"command").unwrap();
+        for _ in 0..3 {
+            b.recv().unwrap();
+        }
+        b.send(1, b"reply").unwrap();
+        a.recv().unwrap();
 
-        a.send(1, b"hello").unwrap();
-        let frame = b.recv().unwrap();
-        assert_eq!(frame.channel, 1);
-        assert_eq!(frame.payload.as_ref(), b"hello");
+        let stats = a.stats().unwrap();
+        assert_eq!((stats.frames_sent, stats.bytes_sent), (3, 13));
+        assert_eq!((stats.frames_received, stats.bytes_received), (1, 5));
+        assert_eq!(stats.channels[&1].frames_sent, 2);
+        assert_eq!(stats.channels[&1].frames_received, 1);
+        assert_eq!(stats.channels[&2].bytes_sent, 7);
+
+        let stats = b.stats().unwrap();
+        assert_eq!((stats.frames_sent, stats.frames_received), (1, 3));
+
+        a.reset_stats();
+        assert_eq!(a.stats().unwrap(), FrameStats::default());
+
+        let (plain, _other) = peer_pair(PeerConfig::default());
+        assert!(plain.stats().is_none());
     }
 
     #[test]
-    fn send_json_and_parse() {
-        #[derive(serde::Serialize, serde::Deserialize)]
-        struct Msg {
-            n: u32,
+    fn observer_sees_both_directions() {
+        #[derive(Default)]
+        struct Events(std::sync::Mutex<Vec<(&'static str, u16, usize)>>);
+        impl FrameObserver for Events {
+            fn on_send(&self, channel: u16, len: usize) {
+                self.0.lock().unwrap().push(("send", channel, len));
+            }
+            fn on_recv(&self, channel: u16, len: usize) {
+                self.0.lock().unwrap().push(("recv", channel, len));
+            }
         }
 
-        let config = PeerConfig::default();
+        let events = Arc::new(Events::default());
+        let config = PeerConfig {
+            observer: Some(events.clone()),
+            ..PeerConfig::default()
+        };
         let (mut a, mut b) = peer_pair(config);
-
-        a.send_json(1, &Msg { n: 7 }).unwrap();
-        let frame = b.recv().unwrap();
-        let parsed: Msg = serde_json::from_slice(frame.payload.as_ref()).unwrap();
-        assert_eq!(parsed.n, 7);
+        events.0.lock().unwrap().clear();
+        a.send(1, b"one").unwrap();
+        b.recv().unwrap();
+        b.send(2, b"reply").unwrap();
+        a.recv().unwrap();
+
+        assert_eq!(
+            events.0.lock().unwrap()[..],
+            [
+                ("send", 1, 3),
+                ("recv", 1, 3),
+                ("send", 2, 5),
+                ("recv", 2, 5)
+            ]
+        );
     }
 
     #[test]
-    fn recv_on_buffers_other_channels() {
-        let config = PeerConfig::default();
+    fn payload_codec_seals_application_frames() {
+        /// XORs every byte, counting the payloads it seals.
+        #[derive(Default)]
+        struct Xor(std::sync::atomic::AtomicUsize);
+        impl PayloadCodec for Xor {
+            fn seal(&self, _: u16, payload: &[u8]) -> ipcprims_frame::Result<Vec<u8>> {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(payload.iter().map(|byte| byte ^ 0x5A).collect())
+            }
+            fn open(&self, _: u16, sealed: &[u8]) -> ipcprims_frame::Result<Vec<u8>> {
+                Ok(sealed.iter().map(|byte| byte ^ 0x5A).collect())
+            }
+        }
+
+        let codec = Arc::new(Xor::default());
+        let config = PeerConfig {
+            payload_codec: Some(codec.clone()),
+            ..PeerConfig::default()
+        };
         let (mut a, mut b) = peer_pair(config);
+        a.send(1, b"secret").unwrap();
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"secret");
+        b.send(2, b"reply").unwrap();
+        assert_eq!(a.recv().unwrap().payload.as_ref(), b"reply");
+        assert_eq!(codec.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 
-        a.send(1, b"one").unwrap();
-        a.send(2, b"two").unwrap();
+    #[test]
+    fn wire_version_is_capped_by_the_handshake() {
+        let config = PeerConfig {
+            wire_version: WireVersion::V2,
+            ..PeerConfig::default()
+        };
+        // peer_pair's handshake reports a v1 remote.
+        let (a, _b) = peer_pair(config.clone());
+        assert_eq!(a.writer.config().wire_version, WireVersion::V1);
 
-        let two = b.recv_on(2).unwrap();
-        assert_eq!(two.payload.as_ref(), b"two");
+        let (left, right) = make_connected_ipc_pair();
+        let mut handshake = a.handshake_result.clone();
+        handshake.wire_version = WireVersion::V2;
+        let mut v2 = Peer::from_parts(
+            "left".to_string(),
+            FrameReader::new(left.try_clone().unwrap()),
+            FrameWriter::new(left),
+            handshake.clone(),
+            None,
+            config,
+            trace::connection_span(Some("left")),
+        );
+        let mut v1 = Peer::from_parts(
+            "right".to_string(),
+            FrameReader::new(right.try_clone().unwrap()),
+            FrameWriter::new(right),
+            handshake,
+            None,
+            PeerConfig::default(),
+            trace::connection_span(Some("right")),
+        );
+        assert_eq!(v2.writer.config().wire_version, WireVersion::V2);
+        assert_eq!(v1.writer.config().wire_version, WireVersion::V1);
 
-        let one = b.recv_on(1).unwrap();
-        assert_eq!(one.payload.as_ref(), b"one");
+        v2.send(1, b"versioned").unwrap();
+        v1.send(1, b"plain").unwrap();
+        assert_eq!(v1.recv().unwrap().payload.as_ref(), b"versioned");
+        assert_eq!(v2.recv().unwrap().payload.as_ref(), b"plain");
+    }
+
+    #[test]
+    fn send_transaction_validates_every_frame_before_writing() {
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+
+        let err = a
+            .send_transaction(&[(1, b"cmd".as_ref()), (9, b"bad channel"), (2, b"data")])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PeerError::TransactionFailed { index: 1, ref source }
+                if matches!(**source, PeerError::UnsupportedChannel(9))
+        ));
+
+        let oversized = vec![0u8; DEFAULT_MAX_PAYLOAD + 1];
+        let err = a
+            .send_transaction(&[(1, b"cmd".as_ref()), (2, &oversized)])
+            .unwrap_err();
+        assert!(matches!(err, PeerError::TransactionFailed { index: 1, .. }));
+
+        // Nothing from either rejected group reached the wire.
+        a.send_transaction(&[(1, b"cmd".as_ref()), (2, b"d1"), (2, b"d2")])
+            .unwrap();
+        for expected in [b"cmd".as_ref(), b"d1", b"d2"] {
+            assert_eq!(b.recv().unwrap().payload.as_ref(), expected);
+        }
     }
 
     #[test]
@@ -700,9 +2317,23 @@ mod tests {
             max_total_buffered_bytes: 16 * 1024 * 1024,
             shutdown_timeout: Duration::from_millis(200),
             max_control_frames_per_loop: 256,
+            shutdown_policy: ShutdownPolicy::default(),
             allow_shutdown_force: false,
             allow_unknown_control_messages: false,
+            auth_refresh_validator: None,
+            disconnect_on_auth_reject: false,
             enable_any_delivery: true,
+            max_payload_size: DEFAULT_MAX_PAYLOAD,
+            channel_priorities: HashMap::new(),
+            buffer_pool: None,
+            checksum_frames: false,
+            wire_version: WireVersion::V1,
+            collect_stats: false,
+            observer: None,
+            payload_codec: None,
+            receive_fds: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
         };
         let (mut a, mut b) = peer_pair(config);
 
@@ -775,9 +2406,23 @@ mod tests {
             max_total_buffered_bytes: 16 * 1024 * 1024,
             shutdown_timeout: Duration::from_millis(50),
             max_control_frames_per_loop: 256,
+            shutdown_policy: ShutdownPolicy::default(),
             allow_shutdown_force: false,
             allow_unknown_control_messages: false,
+            auth_refresh_validator: None,
+            disconnect_on_auth_reject: false,
             enable_any_delivery: true,
+            max_payload_size: DEFAULT_MAX_PAYLOAD,
+            channel_priorities: HashMap::new(),
+            buffer_pool: None,
+            checksum_frames: false,
+            wire_version: WireVersion::V1,
+            collect_stats: false,
+            observer: None,
+            payload_codec: None,
+            receive_fds: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
         };
         let (left, right) = peer_pair(config);
 
@@ -791,6 +2436,177 @@ mod tests {
         closer.join().unwrap();
     }
 
+    fn escalating_config(hard_close: bool) -> PeerConfig {
+        PeerConfig {
+            shutdown_policy: ShutdownPolicy {
+                ack_timeout: Some(Duration::from_millis(50)),
+                force_timeout: Some(Duration::from_millis(500)),
+                hard_close,
+            },
+            allow_shutdown_force: true,
+            ..PeerConfig::default()
+        }
+    }
+
+    #[test]
+    fn escalating_shutdown_with_cooperative_remote_is_acknowledged() {
+        let (left, mut right) = peer_pair(escalating_config(true));
+
+        let right_thread = thread::spawn(move || right.recv().unwrap_err());
+
+        assert_eq!(left.shutdown().unwrap(), ShutdownOutcome::Acknowledged);
+        assert!(matches!(
+            right_thread.join().unwrap(),
+            PeerError::Disconnected(reason) if reason != FORCED_SHUTDOWN_REASON
+        ));
+    }
+
+    #[test]
+    fn escalating_shutdown_with_slow_remote_is_forced() {
+        let (left, mut right) = peer_pair(escalating_config(false));
+
+        let right_thread = thread::spawn(move || {
+            // Miss the ack window; by the time we read, the force is queued
+            // right behind the request.
+            thread::sleep(Duration::from_millis(120));
+            right.recv().unwrap_err()
+        });
+
+        assert_eq!(left.shutdown().unwrap(), ShutdownOutcome::Forced);
+        assert!(matches!(
+            right_thread.join().unwrap(),
+            PeerError::Disconnected(reason) if reason == FORCED_SHUTDOWN_REASON
+        ));
+    }
+
+    #[test]
+    fn escalating_shutdown_with_silent_remote() {
+        let (left, right) = peer_pair(escalating_config(true));
+        assert_eq!(left.shutdown().unwrap(), ShutdownOutcome::HardClosed);
+        drop(right);
+
+        let (left, right) = peer_pair(escalating_config(false));
+        let err = left.shutdown().unwrap_err();
+        assert!(
+            matches!(&err, PeerError::ShutdownFailed(reason) if reason.contains("force shutdown")),
+            "unexpected error: {err}"
+        );
+        drop(right);
+    }
+
+    fn refresh_config(disconnect_on_auth_reject: bool) -> PeerConfig {
+        PeerConfig {
+            auth_refresh_validator: Some(std::sync::Arc::new(|token: Option<&str>| {
+                token == Some("fresh-token")
+            })),
+            disconnect_on_auth_reject,
+            ..PeerConfig::default()
+        }
+    }
+
+    #[test]
+    fn refresh_auth_accepted_updates_server_token() {
+        let (mut client, mut server) = peer_pair(refresh_config(false));
+        server.client_auth_token = Some("old-token".to_string());
+
+        let server_thread = thread::spawn(move || {
+            let frame = server.recv().unwrap();
+            (server, frame)
+        });
+
+        client.refresh_auth("fresh-token").unwrap();
+        client.send(1, b"after").unwrap();
+
+        let (server, frame) = server_thread.join().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"after");
+        assert_eq!(server.client_auth_token(), Some("fresh-token"));
+    }
+
+    #[test]
+    fn refresh_auth_keeps_the_callers_recv_timeout() {
+        let (mut client, mut server) = peer_pair(refresh_config(false));
+        client.set_recv_timeout(Duration::from_millis(50)).unwrap();
+
+        let server_thread = thread::spawn(move || {
+            let frame = server.recv().unwrap();
+            (server, frame)
+        });
+
+        client.refresh_auth("fresh-token").unwrap();
+        client.send(1, b"after").unwrap();
+        let (_server, frame) = server_thread.join().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"after");
+
+        // Still the short timeout, not the 5s `shutdown_timeout`.
+        let start = Instant::now();
+        assert!(matches!(client.recv(), Err(PeerError::Timeout { .. })));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn refresh_auth_rejected_keeps_connection_by_default() {
+        let (mut client, mut server) = peer_pair(refresh_config(false));
+        server.client_auth_token = Some("old-token".to_string());
+
+        let server_thread = thread::spawn(move || {
+            let frame = server.recv().unwrap();
+            (server, frame)
+        });
+
+        let err = client.refresh_auth("stale-token").unwrap_err();
+        assert!(matches!(&err, PeerError::AuthRejected(reason) if reason == "auth token rejected"));
+        client.send(1, b"still open").unwrap();
+
+        let (server, frame) = server_thread.join().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"still open");
+        assert_eq!(server.client_auth_token(), Some("old-token"));
+    }
+
+    #[test]
+    fn refresh_auth_rejected_can_disconnect() {
+        let (mut client, mut server) = peer_pair(refresh_config(true));
+
+        let server_thread = thread::spawn(move || server.recv().unwrap_err());
+
+        let err = client.refresh_auth("stale-token").unwrap_err();
+        assert!(matches!(err, PeerError::AuthRejected(_)));
+        assert!(matches!(
+            server_thread.join().unwrap(),
+            PeerError::Disconnected(reason) if reason.starts_with("auth refresh rejected")
+        ));
+    }
+
+    #[test]
+    fn refresh_auth_oversized_token_rejected_on_both_sides() {
+        let (mut client, mut server) = peer_pair(refresh_config(false));
+        let oversized = "x".repeat(MAX_AUTH_TOKEN_LEN + 1);
+
+        // Checked locally before anything is sent.
+        let err = client.refresh_auth(&oversized).unwrap_err();
+        assert!(matches!(&err, PeerError::AuthRejected(reason) if reason.contains("length")));
+
+        let server_thread = thread::spawn(move || {
+            let frame = server.recv().unwrap();
+            (server, frame)
+        });
+
+        // A remote that skips the local check still gets a rejection.
+        client
+            .send_control(ControlMessage::auth_refresh(&oversized))
+            .unwrap();
+        let verdict = client.reader.read_frame().unwrap();
+        let verdict: ControlMessage = serde_json::from_slice(verdict.payload.as_ref()).unwrap();
+        let payload = verdict.payload.unwrap();
+        assert_eq!(verdict.msg_type, CONTROL_AUTH_RESULT);
+        assert_eq!(payload["accepted"], false);
+        assert!(payload["reason"].as_str().unwrap().contains("length"));
+        client.send(1, b"after").unwrap();
+
+        let (server, frame) = server_thread.join().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"after");
+        assert_eq!(server.client_auth_token(), None);
+    }
+
     #[test]
     fn take_client_auth_token_clears_stored_token() {
         let (mut peer, _) = peer_pair(PeerConfig::default());
@@ -835,17 +2651,21 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "linux")]
-    fn peer_credentials_linux() {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn peer_credentials_linux_and_macos() {
         let config = PeerConfig::default();
         let (peer, _other) = peer_pair(config);
-        let creds = peer.reader.get_ref().peer_credentials();
-        assert!(creds.is_some());
+        let creds = peer
+            .peer_credentials()
+            .expect("peer credentials should be available");
+        // The uid matches this process's geteuid().
+        assert!(creds.same_user());
+        assert_eq!(creds.pid, Some(std::process::id()));
     }
 
     #[test]
-    #[cfg(not(target_os = "linux"))]
-    fn peer_credentials_non_linux() {
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn peer_credentials_other_platforms() {
         let config = PeerConfig::default();
         let (peer, _other) = peer_pair(config);
         let creds = peer.reader.get_ref().peer_credentials();
@@ -898,9 +2718,23 @@ mod tests {
             max_total_buffered_bytes: 40,
             shutdown_timeout: Duration::from_millis(200),
             max_control_frames_per_loop: 256,
+            shutdown_policy: ShutdownPolicy::default(),
             allow_shutdown_force: false,
             allow_unknown_control_messages: false,
+            auth_refresh_validator: None,
+            disconnect_on_auth_reject: false,
             enable_any_delivery: true,
+            max_payload_size: DEFAULT_MAX_PAYLOAD,
+            channel_priorities: HashMap::new(),
+            buffer_pool: None,
+            checksum_frames: false,
+            wire_version: WireVersion::V1,
+            collect_stats: false,
+            observer: None,
+            payload_codec: None,
+            receive_fds: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
         };
         let (mut left, mut right) = peer_pair(config);
 
@@ -991,9 +2825,14 @@ mod tests {
                 protocol_version: "1.0".into(),
                 negotiated_channels: vec![1],
                 client_auth_token: None,
+                remote_library_version: None,
+                priority_frames: false,
+                checksum_frames: false,
+                wire_version: WireVersion::V1,
             },
             Some(Arc::clone(&registry)),
             config.clone(),
+            trace::connection_span(Some("left")),
         );
 
         let mut right_peer = Peer::from_parts(
@@ -1005,9 +2844,14 @@ mod tests {
                 protocol_version: "1.0".into(),
                 negotiated_channels: vec![1],
                 client_auth_token: None,
+                remote_library_version: None,
+                priority_frames: false,
+                checksum_frames: false,
+                wire_version: WireVersion::V1,
             },
             Some(registry),
             config,
+            trace::connection_span(Some("right")),
         );
 
         let mut left_peer = left_peer;
@@ -1022,4 +2866,142 @@ mod tests {
         let frame = right_peer.recv().unwrap();
         assert_eq!(frame.channel, 1);
     }
+
+    /// Read everything written into `sink` through a passed descriptor.
+    fn drain(mut sink: std::os::unix::net::UnixStream) -> String {
+        use std::io::Read;
+
+        let mut text = String::new();
+        sink.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn finish_sending_half_closes_and_keeps_receiving() {
+        let (mut client, mut server) = peer_pair(PeerConfig::default());
+        client.send(1, b"one").unwrap();
+        client.send(1, b"two").unwrap();
+        client.finish_sending().unwrap();
+        client.finish_sending().unwrap();
+        assert!(client.send(1, b"late").is_err());
+
+        assert_eq!(server.recv().unwrap().payload.as_ref(), b"one");
+        assert_eq!(server.recv().unwrap().payload.as_ref(), b"two");
+        assert!(matches!(
+            server.reader.read_frame(),
+            Err(FrameError::ConnectionClosed)
+        ));
+
+        server.send(1, b"done").unwrap();
+        assert_eq!(client.recv().unwrap().payload.as_ref(), b"done");
+        drop(server);
+        assert!(matches!(client.recv(), Err(PeerError::Disconnected(_))));
+        assert!(matches!(
+            client.shutdown(),
+            Err(PeerError::ShutdownFailed(_))
+        ));
+    }
+
+    #[test]
+    fn send_fd_hands_descriptors_to_their_frame() {
+        use std::io::Write;
+        use std::os::fd::AsFd;
+
+        let config = PeerConfig {
+            receive_fds: true,
+            ..PeerConfig::default()
+        };
+        let (mut a, mut b) = peer_pair(config);
+        let (log, log_sink) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (other, other_sink) = std::os::unix::net::UnixStream::pair().unwrap();
+
+        a.send_fd(2, b"buffered", other.as_fd()).unwrap();
+        a.send(3, b"plain").unwrap();
+        a.send_fd(1, b"rotate", log.as_fd()).unwrap();
+        drop((log, other));
+
+        let frame = b.recv_on(1).unwrap();
+        assert_eq!(frame.payload.as_ref(), b"rotate");
+        let mut fds = b.take_fds();
+        assert_eq!(fds.len(), 1);
+        assert!(b.take_fds().is_empty());
+        let mut log = std::os::unix::net::UnixStream::from(fds.pop().unwrap());
+        log.write_all(b"reopened\n").unwrap();
+        drop(log);
+        assert_eq!(drain(log_sink), "reopened\n");
+
+        let frame = b.recv_on(2).unwrap();
+        assert_eq!(frame.payload.as_ref(), b"buffered");
+        let fds = b.take_fds();
+        assert_eq!(fds.len(), 1);
+        let frame = b.recv().unwrap();
+        assert_eq!(frame.payload.as_ref(), b"plain");
+        assert!(b.take_fds().is_empty());
+
+        drop(fds);
+        assert_eq!(drain(other_sink), "");
+    }
+
+    #[test]
+    fn passed_fds_are_closed_unless_receiving() {
+        use std::os::fd::AsFd;
+
+        let (mut a, mut b) = peer_pair(PeerConfig::default());
+        let (log, log_sink) = std::os::unix::net::UnixStream::pair().unwrap();
+        a.send_fd(1, b"rotate", log.as_fd()).unwrap();
+        drop(log);
+
+        assert_eq!(b.recv().unwrap().payload.as_ref(), b"rotate");
+        assert!(b.take_fds().is_empty());
+        // EOF: no copy of the passed end survived.
+        assert_eq!(drain(log_sink), "");
+    }
+
+    #[test]
+    fn send_fds_checks_channel_and_count() {
+        use std::os::fd::AsFd;
+
+        let (mut a, _b) = peer_pair(PeerConfig::default());
+        let (log, _sink) = std::os::unix::net::UnixStream::pair().unwrap();
+        assert!(matches!(
+            a.send_fd(9, b"x", log.as_fd()),
+            Err(PeerError::UnsupportedChannel(9))
+        ));
+        let fds = vec![log.as_fd(); ipcprims_transport::MAX_FDS_PER_MESSAGE + 1];
+        assert!(matches!(
+            a.send_fds(1, b"x", &fds),
+            Err(PeerError::Transport(
+                ipcprims_transport::TransportError::TooManyFds { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn connected_pair_handshakes_in_memory() {
+        let (mut client, mut server) =
+            Peer::connected_pair(PeerConfig::default()).expect("memory handshake should succeed");
+        assert_eq!(client.handshake_result().peer_id, "memory");
+        assert_eq!(
+            server.handshake_result().negotiated_channels,
+            vec![COMMAND, DATA, TELEMETRY, ERROR]
+        );
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        assert!(client
+            .peer_credentials()
+            .is_some_and(|creds| creds.same_user()));
+
+        let echo = thread::spawn(move || {
+            let frame = server.recv().expect("server should receive");
+            server
+                .send(frame.channel, &frame.payload)
+                .expect("server should echo");
+            server
+        });
+        client.send(DATA, b"hello").unwrap();
+        assert_eq!(client.recv().unwrap().payload.as_ref(), b"hello");
+
+        let mut server = echo.join().expect("echo thread should finish");
+        drop(client);
+        assert!(matches!(server.recv(), Err(PeerError::Disconnected(_))));
+    }
 }
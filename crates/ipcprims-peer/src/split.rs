@@ -0,0 +1,891 @@
+//! Split a blocking [`Peer`] into a sender and a demultiplexing receiver.
+//!
+//! After [`Peer::split`], a background thread owns the read half and routes
+//! each inbound frame into a bounded queue for its channel. Every channel has
+//! its own lock and condvar, so a consumer draining one channel never waits
+//! behind another channel's backlog. Limits come from [`PeerConfig`]:
+//! `max_buffer_per_channel` and `max_total_buffered_bytes` are checked as each
+//! frame is enqueued, and exceeding either stops the demux thread with
+//! [`PeerError::BufferFull`] (frames already queued stay receivable). The
+//! socket stays open until the [`PeerSender`] is dropped too.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+use ipcprims_frame::{Frame, FrameReader, FrameWriter, CONTROL};
+use ipcprims_transport::IpcStream;
+use serde::Serialize;
+
+use crate::control::{
+    ControlMessage, CONTROL_PING, CONTROL_PONG, CONTROL_SHUTDOWN_FORCE, CONTROL_SHUTDOWN_REQUEST,
+    FORCED_SHUTDOWN_REASON,
+};
+use crate::error::{PeerError, Result, TimeoutOp};
+use crate::peer::{
+    batch_failed, classify_frame_error, transaction_failed, Peer, PeerConfig, PeerParts,
+    SchemaRegistryHandle,
+};
+use crate::trace::{self, Span};
+
+/// How often the demux thread wakes from an idle read to check whether any
+/// receiving handle is still alive.
+const DEMUX_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Peer {
+    /// Split into a cloneable [`PeerSender`] and a [`PeerReceiver`] whose
+    /// background thread demultiplexes inbound frames into per-channel queues.
+    ///
+    /// Frames already buffered by [`Peer::recv_on`] are carried over. CONTROL
+    /// pings and shutdown requests are answered by the demux thread.
+    pub fn split(self) -> Result<(PeerSender, PeerReceiver)> {
+        let PeerParts {
+            span,
+            id,
+            reader,
+            writer,
+            handshake_result,
+            schema_registry,
+            mut channel_buffers,
+            config,
+        } = self.into_parts()?;
+
+        reader
+            .get_ref()
+            .set_read_timeout(Some(DEMUX_POLL_INTERVAL))?;
+
+        let channels = handshake_result.negotiated_channels.clone();
+        let mut queue_channels = channels.clone();
+        if config.allow_unknown_control_messages {
+            queue_channels.push(CONTROL);
+        }
+
+        let shared = Arc::new(Shared {
+            id: id.clone(),
+            channels: channels.clone(),
+            queues: queue_channels
+                .iter()
+                .map(|&channel| {
+                    let backlog = channel_buffers.remove(&channel).unwrap_or_default();
+                    (channel, Arc::new(ChannelQueue::new(channel, backlog)))
+                })
+                .collect(),
+            buffered_bytes: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            config,
+        });
+        let carried: usize = shared
+            .queues
+            .values()
+            .map(|queue| {
+                queue
+                    .lock()
+                    .frames
+                    .iter()
+                    .map(Frame::wire_size)
+                    .sum::<usize>()
+            })
+            .sum();
+        shared.buffered_bytes.store(carried, Ordering::Relaxed);
+
+        let writer = Arc::new(Mutex::new(writer));
+        let alive = Arc::new(());
+
+        let demux = Demux {
+            shared: Arc::clone(&shared),
+            reader,
+            writer: Arc::clone(&writer),
+            schema_registry: schema_registry.clone(),
+            alive: Arc::downgrade(&alive),
+            control_frames_seen: 0,
+        };
+        let demux_span = span.clone();
+        std::thread::Builder::new()
+            .name(format!("ipcprims-demux-{id}"))
+            .spawn(move || {
+                let _span = demux_span.entered();
+                demux.run()
+            })
+            .map_err(|err| PeerError::Transport(err.into()))?;
+
+        let sender = PeerSender {
+            id: id.clone(),
+            channels,
+            writer,
+            schema_registry,
+            span,
+        };
+        let receiver = PeerReceiver {
+            shared,
+            _alive: alive,
+        };
+        Ok((sender, receiver))
+    }
+}
+
+/// Sending half of a split [`Peer`]. Clones share one writer.
+#[derive(Clone)]
+pub struct PeerSender {
+    id: String,
+    channels: Vec<u16>,
+    writer: Arc<Mutex<FrameWriter<IpcStream>>>,
+    #[cfg_attr(not(feature = "schema"), allow(dead_code))]
+    schema_registry: Option<SchemaRegistryHandle>,
+    span: Span,
+}
+
+impl PeerSender {
+    /// Send bytes on a negotiated channel.
+    pub fn send(&self, channel: u16, payload: &[u8]) -> Result<()> {
+        let _span = self.span.clone().entered();
+        if channel != CONTROL && !self.supports_channel(channel) {
+            return Err(PeerError::UnsupportedChannel(channel));
+        }
+
+        self.validate_send(channel, payload)?;
+        lock(&self.writer).send(channel, payload).map_err(|err| {
+            PeerError::from(err).during(TimeoutOp::Send {
+                channel: Some(channel),
+            })
+        })?;
+        trace::frame_sent(channel, payload.len());
+        Ok(())
+    }
+
+    /// Send JSON value on a channel.
+    pub fn send_json<T: Serialize>(&self, channel: u16, value: &T) -> Result<()> {
+        let payload = serde_json::to_vec(value)?;
+        self.send(channel, &payload)
+    }
+
+    /// [`Peer::send_transaction`] for a split peer. The writer lock is held
+    /// for the whole group, so other clones cannot interleave frames in it.
+    pub fn send_transaction(&self, frames: &[(u16, &[u8])]) -> Result<()> {
+        let _span = self.span.clone().entered();
+        for (index, &(channel, payload)) in frames.iter().enumerate() {
+            if channel != CONTROL && !self.supports_channel(channel) {
+                return Err(transaction_failed(
+                    index,
+                    PeerError::UnsupportedChannel(channel),
+                ));
+            }
+            self.validate_send(channel, payload)
+                .map_err(|err| transaction_failed(index, err))?;
+        }
+
+        lock(&self.writer)
+            .send_batch(frames)
+            .map_err(|err| batch_failed(frames, err))?;
+        for &(channel, payload) in frames {
+            trace::frame_sent(channel, payload.len());
+        }
+        Ok(())
+    }
+
+    /// Peer identifier.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Negotiated channels.
+    pub fn channels(&self) -> &[u16] {
+        &self.channels
+    }
+
+    /// True if the channel was negotiated.
+    pub fn supports_channel(&self, channel: u16) -> bool {
+        self.channels.contains(&channel)
+    }
+
+    #[cfg(feature = "schema")]
+    fn validate_send(&self, channel: u16, payload: &[u8]) -> Result<()> {
+        if let Some(registry) = &self.schema_registry {
+            registry
+                .validate(channel, payload)
+                .inspect_err(|err| trace::schema_rejected("send", channel, err))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "schema"))]
+    fn validate_send(&self, _channel: u16, _payload: &[u8]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Receiving half of a split [`Peer`].
+///
+/// Hand out one [`ChannelHandle`] per consumer. The demux thread stops once
+/// this receiver and every handle taken from it have been dropped.
+pub struct PeerReceiver {
+    shared: Arc<Shared>,
+    _alive: Arc<()>,
+}
+
+impl PeerReceiver {
+    /// Handle for a negotiated channel, or `None` if it was not negotiated.
+    ///
+    /// Handles are cheap to clone; clones share the queue, so each frame is
+    /// delivered to exactly one of them.
+    pub fn channel(&self, channel: u16) -> Option<ChannelHandle> {
+        let queue = self.shared.queues.get(&channel)?;
+        Some(ChannelHandle {
+            queue: Arc::clone(queue),
+            shared: Arc::clone(&self.shared),
+            _alive: Arc::clone(&self._alive),
+        })
+    }
+
+    /// Peer identifier.
+    pub fn id(&self) -> &str {
+        &self.shared.id
+    }
+
+    /// Negotiated channels.
+    pub fn channels(&self) -> &[u16] {
+        &self.shared.channels
+    }
+
+    /// True once the connection ended; queued frames may still be pending.
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+}
+
+/// Consumer handle for one channel of a split peer.
+#[derive(Clone)]
+pub struct ChannelHandle {
+    queue: Arc<ChannelQueue>,
+    shared: Arc<Shared>,
+    _alive: Arc<()>,
+}
+
+impl ChannelHandle {
+    /// Channel this handle drains.
+    pub fn channel(&self) -> u16 {
+        self.queue.channel
+    }
+
+    /// Frames currently queued on this channel.
+    pub fn len(&self) -> usize {
+        self.queue.lock().frames.len()
+    }
+
+    /// True if no frames are queued on this channel.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Block until a frame arrives or the connection ends.
+    pub fn recv(&self) -> Result<Frame> {
+        let mut state = self.queue.lock();
+        loop {
+            if let Some(frame) = self.take(&mut state) {
+                return Ok(frame);
+            }
+            if let Some(reason) = &state.closed {
+                return Err(reason.to_error());
+            }
+            state = self
+                .queue
+                .ready
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Take a queued frame without blocking. `Ok(None)` means the queue is
+    /// empty but the connection is still open.
+    pub fn try_recv(&self) -> Result<Option<Frame>> {
+        let mut state = self.queue.lock();
+        if let Some(frame) = self.take(&mut state) {
+            return Ok(Some(frame));
+        }
+        match &state.closed {
+            Some(reason) => Err(reason.to_error()),
+            None => Ok(None),
+        }
+    }
+
+    /// Like [`Self::recv`], but gives up with [`PeerError::Timeout`].
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Frame> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.queue.lock();
+        loop {
+            if let Some(frame) = self.take(&mut state) {
+                return Ok(frame);
+            }
+            if let Some(reason) = &state.closed {
+                return Err(reason.to_error());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PeerError::Timeout {
+                    operation: TimeoutOp::Recv {
+                        channel: Some(self.queue.channel),
+                    },
+                    waited: timeout,
+                });
+            }
+            state = self
+                .queue
+                .ready
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    fn take(&self, state: &mut QueueState) -> Option<Frame> {
+        let frame = state.frames.pop_front()?;
+        self.shared
+            .buffered_bytes
+            .fetch_sub(frame.wire_size(), Ordering::Relaxed);
+        Some(frame)
+    }
+}
+
+struct Shared {
+    id: String,
+    channels: Vec<u16>,
+    queues: HashMap<u16, Arc<ChannelQueue>>,
+    buffered_bytes: AtomicUsize,
+    closed: AtomicBool,
+    config: PeerConfig,
+}
+
+impl Shared {
+    fn close_all(&self, reason: &CloseReason) {
+        self.closed.store(true, Ordering::Release);
+        for queue in self.queues.values() {
+            let mut state = queue.lock();
+            if state.closed.is_none() {
+                state.closed = Some(reason.clone());
+            }
+            drop(state);
+            queue.ready.notify_all();
+        }
+    }
+}
+
+struct ChannelQueue {
+    channel: u16,
+    state: Mutex<QueueState>,
+    ready: Condvar,
+}
+
+struct QueueState {
+    frames: VecDeque<Frame>,
+    closed: Option<CloseReason>,
+}
+
+impl ChannelQueue {
+    fn new(channel: u16, backlog: VecDeque<Frame>) -> Self {
+        Self {
+            channel,
+            state: Mutex::new(QueueState {
+                frames: backlog,
+                closed: None,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        // Queue state stays consistent even if a consumer panicked mid-call.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Why the demux loop stopped; replayed to every receiver as a [`PeerError`].
+#[derive(Debug, Clone)]
+enum CloseReason {
+    Disconnected(String),
+    BufferFull(u16),
+}
+
+impl CloseReason {
+    fn to_error(&self) -> PeerError {
+        match self {
+            CloseReason::Disconnected(reason) => PeerError::Disconnected(reason.clone()),
+            CloseReason::BufferFull(channel) => PeerError::BufferFull(*channel),
+        }
+    }
+}
+
+struct Demux {
+    shared: Arc<Shared>,
+    reader: FrameReader<IpcStream>,
+    writer: Arc<Mutex<FrameWriter<IpcStream>>>,
+    schema_registry: Option<SchemaRegistryHandle>,
+    alive: Weak<()>,
+    control_frames_seen: usize,
+}
+
+impl Demux {
+    fn run(mut self) {
+        let reason = loop {
+            if self.alive.strong_count() == 0 {
+                break CloseReason::Disconnected("receiver dropped".to_string());
+            }
+
+            let frame = match self.reader.read_frame() {
+                Ok(frame) => {
+                    trace::frame_received(frame.channel, frame.payload.len());
+                    frame
+                }
+                Err(err) => match classify_frame_error(err, DEMUX_POLL_INTERVAL) {
+                    PeerError::Timeout { .. } => continue,
+                    err => break CloseReason::Disconnected(err.to_string()),
+                },
+            };
+
+            let result = if frame.channel == CONTROL {
+                self.handle_control(frame)
+            } else {
+                self.control_frames_seen = 0;
+                self.route(frame)
+            };
+            if let Err(reason) = result {
+                break reason;
+            }
+        };
+        trace::debug!(error = %reason.to_error(), "demux stopped");
+        self.shared.close_all(&reason);
+    }
+
+    fn route(&self, frame: Frame) -> std::result::Result<(), CloseReason> {
+        let Some(queue) = self.shared.queues.get(&frame.channel) else {
+            return Err(CloseReason::Disconnected(format!(
+                "received frame on unnegotiated channel {}",
+                frame.channel
+            )));
+        };
+        self.validate_recv(&frame)?;
+
+        let config = &self.shared.config;
+        let frame_bytes = frame.wire_size();
+        let total = self
+            .shared
+            .buffered_bytes
+            .fetch_add(frame_bytes, Ordering::Relaxed)
+            .saturating_add(frame_bytes);
+        if total > config.max_total_buffered_bytes {
+            self.shared
+                .buffered_bytes
+                .fetch_sub(frame_bytes, Ordering::Relaxed);
+            return Err(CloseReason::BufferFull(frame.channel));
+        }
+
+        let mut state = queue.lock();
+        if state.frames.len() >= config.max_buffer_per_channel {
+            drop(state);
+            self.shared
+                .buffered_bytes
+                .fetch_sub(frame_bytes, Ordering::Relaxed);
+            return Err(CloseReason::BufferFull(frame.channel));
+        }
+        state.frames.push_back(frame);
+        drop(state);
+        queue.ready.notify_one();
+        Ok(())
+    }
+
+    fn handle_control(&mut self, frame: Frame) -> std::result::Result<(), CloseReason> {
+        self.control_frames_seen = self.control_frames_seen.saturating_add(1);
+        if self.control_frames_seen > self.shared.config.max_control_frames_per_loop {
+            return Err(CloseReason::Disconnected(
+                "control frame flood detected".to_string(),
+            ));
+        }
+
+        let Ok(message) = serde_json::from_slice::<ControlMessage>(frame.payload.as_ref()) else {
+            trace::control_rejected(None, "invalid CONTROL JSON payload");
+            return Err(CloseReason::Disconnected(
+                "invalid CONTROL JSON payload".to_string(),
+            ));
+        };
+        trace::control_received(&message.msg_type);
+
+        match message.msg_type.as_str() {
+            CONTROL_PING => self.send_control(ControlMessage::pong()),
+            CONTROL_PONG => Ok(()),
+            CONTROL_SHUTDOWN_REQUEST => {
+                self.send_control(ControlMessage::shutdown_ack())?;
+                trace::shutdown_phase("remote_requested");
+                Err(CloseReason::Disconnected("shutdown requested".to_string()))
+            }
+            CONTROL_SHUTDOWN_FORCE => Err(CloseReason::Disconnected(
+                if self.shared.config.allow_shutdown_force {
+                    FORCED_SHUTDOWN_REASON
+                } else {
+                    "received disallowed SHUTDOWN_FORCE"
+                }
+                .to_string(),
+            )),
+            _ if self.shared.config.allow_unknown_control_messages => self.route(frame),
+            msg_type => {
+                trace::control_rejected(Some(msg_type), "unknown CONTROL message type");
+                Err(CloseReason::Disconnected(
+                    "unknown CONTROL message type".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn send_control(&self, message: ControlMessage) -> std::result::Result<(), CloseReason> {
+        let result = serde_json::to_vec(&message)
+            .map_err(PeerError::from)
+            .and_then(|payload| Ok(lock(&self.writer).send(CONTROL, &payload)?));
+        result.map_err(|err| CloseReason::Disconnected(err.to_string()))
+    }
+
+    /// There is no caller to hand a per-frame error to, so (as with async
+    /// peers) an invalid inbound frame ends the connection.
+    #[cfg(feature = "schema")]
+    fn validate_recv(&self, frame: &Frame) -> std::result::Result<(), CloseReason> {
+        if let Some(registry) = &self.schema_registry {
+            registry
+                .validate_frame(frame)
+                .inspect_err(|err| trace::schema_rejected("recv", frame.channel, err))
+                .map_err(|err| CloseReason::Disconnected(PeerError::from(err).to_string()))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "schema"))]
+    fn validate_recv(&self, _frame: &Frame) -> std::result::Result<(), CloseReason> {
+        let _ = &self.schema_registry;
+        Ok(())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use ipcprims_frame::{COMMAND, DATA, TELEMETRY};
+
+    use super::*;
+    use crate::connector::{connect, connect_with_config};
+    use crate::handshake::HandshakeConfig;
+    use crate::listener::PeerListener;
+
+    fn make_sock_path(tag: &str) -> PathBuf {
+        let dir = PathBuf::from(format!(
+            "/tmp/ipcp-split-{}-{}-{}",
+            tag,
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        dir.join("split.sock")
+    }
+
+    fn cleanup(sock_path: &std::path::Path) {
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+
+    /// Bind, run `server` on the accepted peer, and connect a client with `config`.
+    fn connected<F>(
+        tag: &str,
+        config: PeerConfig,
+        server: F,
+    ) -> (Peer, thread::JoinHandle<()>, PathBuf)
+    where
+        F: FnOnce(Peer) + Send + 'static,
+    {
+        let sock_path = make_sock_path(tag);
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+        let handle = thread::spawn(move || {
+            server(listener.accept().expect("listener should accept"));
+        });
+        let client = connect_with_config(
+            &sock_path,
+            &[COMMAND, DATA],
+            &HandshakeConfig::default(),
+            None,
+            Some(config),
+        )
+        .expect("client should connect");
+        (client, handle, sock_path)
+    }
+
+    #[test]
+    fn routes_frames_to_channel_handles() {
+        let (client, server, sock_path) = connected("route", PeerConfig::default(), |mut peer| {
+            peer.send(COMMAND, b"a").unwrap();
+            peer.send(DATA, b"b").unwrap();
+            peer.send(COMMAND, b"c").unwrap();
+            // Keep the connection open until the client has drained.
+            let _ = peer.recv();
+        });
+
+        let (sender, receiver) = client.split().expect("split should succeed");
+        assert!(receiver.channel(TELEMETRY).is_none());
+        let command = receiver.channel(COMMAND).expect("COMMAND negotiated");
+        let data = receiver.channel(DATA).expect("DATA negotiated");
+
+        assert_eq!(command.recv().unwrap().payload.as_ref(), b"a");
+        assert_eq!(command.recv().unwrap().payload.as_ref(), b"c");
+        assert_eq!(data.recv().unwrap().payload.as_ref(), b"b");
+        assert!(data.try_recv().unwrap().is_none());
+        let err = data.recv_timeout(Duration::from_millis(20)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("recv on channel {DATA} timed out after 20ms")
+        );
+
+        sender.send(COMMAND, b"done").unwrap();
+        server.join().unwrap();
+        assert!(matches!(command.recv(), Err(PeerError::Disconnected(_))));
+        assert!(receiver.is_closed());
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn transactions_are_not_interleaved_by_concurrent_senders() {
+        const GROUPS: usize = 200;
+        const NOISE: usize = 600;
+        let (frames_tx, frames_rx) = std::sync::mpsc::channel();
+        let (client, server, sock_path) =
+            connected("txn", PeerConfig::default(), move |mut peer| {
+                for _ in 0..GROUPS * 3 + NOISE {
+                    let frame = peer.recv().unwrap();
+                    frames_tx.send(frame.payload.to_vec()).unwrap();
+                }
+            });
+
+        let (sender, _receiver) = client.split().unwrap();
+        let noisy = sender.clone();
+        let noise = thread::spawn(move || {
+            for _ in 0..NOISE {
+                noisy.send(DATA, b"noise").unwrap();
+            }
+        });
+        for i in 0..GROUPS {
+            let head = format!("tx{i}");
+            let (first, second) = (format!("tx{i}-a"), format!("tx{i}-b"));
+            sender
+                .send_transaction(&[
+                    (COMMAND, head.as_bytes()),
+                    (DATA, first.as_bytes()),
+                    (DATA, second.as_bytes()),
+                ])
+                .unwrap();
+        }
+        noise.join().unwrap();
+        server.join().unwrap();
+
+        let received: Vec<Vec<u8>> = frames_rx.iter().collect();
+        assert_eq!(received.len(), GROUPS * 3 + NOISE);
+        let mut groups = 0;
+        for (pos, payload) in received.iter().enumerate() {
+            if payload == b"noise" || payload.ends_with(b"-a") || payload.ends_with(b"-b") {
+                continue;
+            }
+            let head = String::from_utf8(payload.clone()).unwrap();
+            assert_eq!(received[pos + 1], format!("{head}-a").into_bytes());
+            assert_eq!(received[pos + 2], format!("{head}-b").into_bytes());
+            groups += 1;
+        }
+        assert_eq!(groups, GROUPS);
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn carries_over_frames_buffered_before_split() {
+        let (mut client, server, sock_path) =
+            connected("carry", PeerConfig::default(), |mut peer| {
+                peer.send(DATA, b"early").unwrap();
+                peer.send(COMMAND, b"reply").unwrap();
+                let _ = peer.recv();
+            });
+
+        assert_eq!(client.recv_on(COMMAND).unwrap().payload.as_ref(), b"reply");
+        let (sender, receiver) = client.split().unwrap();
+        let data = receiver.channel(DATA).unwrap();
+        assert_eq!(data.try_recv().unwrap().unwrap().payload.as_ref(), b"early");
+
+        sender.send(COMMAND, b"done").unwrap();
+        server.join().unwrap();
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn overflow_closes_every_channel_with_buffer_full() {
+        let config = PeerConfig {
+            max_buffer_per_channel: 4,
+            ..PeerConfig::default()
+        };
+        let (client, server, sock_path) = connected("overflow", config, |mut peer| {
+            for i in 0..10u8 {
+                if peer.send(DATA, &[i]).is_err() {
+                    break;
+                }
+            }
+            let _ = peer.recv();
+        });
+
+        let (_sender, receiver) = client.split().unwrap();
+        let data = receiver.channel(DATA).unwrap();
+        let command = receiver.channel(COMMAND).unwrap();
+
+        assert!(matches!(command.recv(), Err(PeerError::BufferFull(DATA))));
+        for i in 0..4u8 {
+            assert_eq!(data.recv().unwrap().payload.as_ref(), &[i]);
+        }
+        assert!(matches!(data.recv(), Err(PeerError::BufferFull(DATA))));
+
+        drop((data, command, receiver, _sender));
+        server.join().unwrap();
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn concurrent_consumers_receive_each_frame_once() {
+        const FRAMES: u32 = 2000;
+        let config = PeerConfig {
+            max_buffer_per_channel: FRAMES as usize,
+            ..PeerConfig::default()
+        };
+        let (client, server, sock_path) = connected("fanin", config, |mut peer| {
+            for i in 0..FRAMES {
+                peer.send(DATA, &i.to_le_bytes()).unwrap();
+            }
+        });
+
+        let (_sender, receiver) = client.split().unwrap();
+        let data = receiver.channel(DATA).unwrap();
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let data = data.clone();
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while let Ok(frame) = data.recv() {
+                        seen.push(u32::from_le_bytes(
+                            frame.payload.as_ref().try_into().unwrap(),
+                        ));
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        server.join().unwrap();
+        let mut all = HashSet::new();
+        for consumer in consumers {
+            for value in consumer.join().unwrap() {
+                assert!(all.insert(value), "frame {value} delivered twice");
+            }
+        }
+        assert_eq!(all.len(), FRAMES as usize);
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn command_round_trips_are_not_blocked_by_data_flood() {
+        const FLOOD: usize = 5000;
+        const REQUESTS: usize = 50;
+        let config = PeerConfig {
+            max_buffer_per_channel: FLOOD,
+            max_total_buffered_bytes: 64 * 1024 * 1024,
+            ..PeerConfig::default()
+        };
+
+        let sock_path = make_sock_path("flood");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+        let server = thread::spawn(move || {
+            let peer = listener.accept().expect("listener should accept");
+            let (sender, receiver) = peer.split().unwrap();
+            let flooder = {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for _ in 0..FLOOD {
+                        sender.send(DATA, &[0xAB; 1024]).unwrap();
+                    }
+                })
+            };
+            let command = receiver.channel(COMMAND).unwrap();
+            for _ in 0..REQUESTS {
+                let frame = command.recv().unwrap();
+                sender.send(COMMAND, &frame.payload).unwrap();
+            }
+            flooder.join().unwrap();
+        });
+
+        let client = connect_with_config(
+            &sock_path,
+            &[COMMAND, DATA],
+            &HandshakeConfig::default(),
+            None,
+            Some(config),
+        )
+        .unwrap();
+        let (sender, receiver) = client.split().unwrap();
+        let data = receiver.channel(DATA).unwrap();
+        let command = receiver.channel(COMMAND).unwrap();
+
+        // DATA is deliberately left undrained until every request completed.
+        let (done_tx, done_rx) = mpsc::channel();
+        let requester = thread::spawn(move || {
+            let mut slowest = Duration::ZERO;
+            for i in 0..REQUESTS {
+                let start = Instant::now();
+                sender.send(COMMAND, &i.to_le_bytes()).unwrap();
+                let reply = command.recv_timeout(Duration::from_secs(5)).unwrap();
+                assert_eq!(reply.payload.as_ref(), &i.to_le_bytes());
+                slowest = slowest.max(start.elapsed());
+            }
+            done_tx.send(slowest).unwrap();
+        });
+
+        let slowest = done_rx.recv().unwrap();
+        requester.join().unwrap();
+        assert!(
+            slowest < Duration::from_secs(1),
+            "COMMAND round trip took {slowest:?} behind a DATA flood"
+        );
+
+        let mut received = 0;
+        while received < FLOOD {
+            data.recv_timeout(Duration::from_secs(5)).unwrap();
+            received += 1;
+        }
+        server.join().unwrap();
+        cleanup(&sock_path);
+    }
+
+    #[test]
+    fn answers_ping_after_split() {
+        let sock_path = make_sock_path("ping");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+        let server = thread::spawn(move || {
+            let mut peer = listener.accept().unwrap();
+            peer.ping().expect("split client should answer ping");
+        });
+
+        let client = connect(&sock_path, &[COMMAND]).unwrap();
+        let (_sender, receiver) = client.split().unwrap();
+        server.join().unwrap();
+        drop(receiver);
+        cleanup(&sock_path);
+    }
+}
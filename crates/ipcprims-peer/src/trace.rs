@@ -0,0 +1,405 @@
+//! Instrumentation shims for the optional `tracing` feature.
+//!
+//! With the feature enabled the macros forward to the `tracing` macros of the
+//! same name and [`Span`] is `tracing::Span`; without it everything compiles to
+//! nothing. Every event uses the same field names so log pipelines can index
+//! them:
+//!
+//! | Field | Meaning |
+//! |-------|---------|
+//! | `peer_id` | Peer identifier (on the `ipc_peer` connection span) |
+//! | `transport` | `"uds"` or `"named_pipe"` (on the connection span) |
+//! | `role` | Handshake or auth refresh side: `"client"` or `"server"` |
+//! | `channels` | Requested or negotiated channel list |
+//! | `auth_token` | Always redacted: `<redacted:N bytes>` or `<none>` |
+//! | `channel` | Frame channel |
+//! | `size` | Frame payload length in bytes |
+//! | `msg_type` | CONTROL message type |
+//! | `direction` | `"send"` or `"recv"` for schema validation |
+//! | `phase` | Shutdown phase |
+//! | `discarded` | Bytes skipped by a resync |
+//! | `error` | Error display text |
+
+// Event arguments go unused when the macros expand to nothing.
+#![cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+
+use std::fmt;
+
+use crate::error::Result;
+use crate::handshake::HandshakeResult;
+
+macro_rules! trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!($($arg)*);
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!($($arg)*);
+    }};
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::info!($($arg)*);
+    }};
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!($($arg)*);
+    }};
+}
+
+pub(crate) use debug;
+
+/// Transport recorded on connection spans.
+#[cfg(all(feature = "tracing", unix))]
+const TRANSPORT: &str = "uds";
+#[cfg(all(feature = "tracing", windows))]
+const TRANSPORT: &str = "named_pipe";
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+/// Stand-in for `tracing::Span` when the feature is off.
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub(crate) fn entered(self) -> Self {
+        self
+    }
+
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+}
+
+/// Span covering one connection, from handshake to drop.
+///
+/// Pass `None` when the peer id is not known until the handshake completes and
+/// fill it in with [`record_peer_id`].
+pub(crate) fn connection_span(peer_id: Option<&str>) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::info_span!(
+            "ipc_peer",
+            peer_id = tracing::field::Empty,
+            transport = TRANSPORT
+        );
+        if let Some(peer_id) = peer_id {
+            span.record("peer_id", peer_id);
+        }
+        span
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = peer_id;
+        Span
+    }
+}
+
+pub(crate) fn record_peer_id(span: &Span, peer_id: &str) {
+    #[cfg(feature = "tracing")]
+    span.record("peer_id", peer_id);
+    #[cfg(not(feature = "tracing"))]
+    let _ = (span, peer_id);
+}
+
+/// Run `future` inside `span` each time it is polled.
+#[cfg(feature = "async")]
+pub(crate) fn instrument<F: std::future::Future>(
+    future: F,
+    span: Span,
+) -> impl std::future::Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::Instrument::instrument(future, span)
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = span;
+        future
+    }
+}
+
+pub(crate) fn frame_sent(channel: u16, size: usize) {
+    trace!(channel, size, "frame sent");
+}
+
+pub(crate) fn frame_received(channel: u16, size: usize) {
+    trace!(channel, size, "frame received");
+}
+
+pub(crate) fn control_received(msg_type: &str) {
+    debug!(msg_type, "control message received");
+}
+
+/// A CONTROL frame that was malformed or of an unhandled type.
+pub(crate) fn control_rejected(msg_type: Option<&str>, reason: &str) {
+    warn!(msg_type, error = reason, "control message rejected");
+}
+
+/// A payload failed schema validation; `direction` is `"send"` or `"recv"`.
+#[cfg(feature = "schema")]
+pub(crate) fn schema_rejected(direction: &str, channel: u16, error: &dyn fmt::Display) {
+    warn!(direction, channel, error = %error, "schema validation failed");
+}
+
+pub(crate) fn shutdown_phase(phase: &str) {
+    debug!(phase, "shutdown");
+}
+
+/// The inbound stream lost frame alignment; the peer is now poisoned.
+pub(crate) fn desynchronized(error: &dyn fmt::Display) {
+    warn!(error = %error, "frame stream desynchronized");
+}
+
+/// Putting a stream setting back after a single call failed; the call's
+/// own result is kept.
+pub(crate) fn stream_restore_failed(setting: &str, error: &dyn fmt::Display) {
+    warn!(setting, error = %error, "failed to restore stream setting");
+}
+
+pub(crate) fn resynchronized(discarded: usize) {
+    info!(discarded, "frame stream resynchronized");
+}
+
+/// Display form of an auth token that never reveals its contents.
+pub(crate) struct Redacted<'a>(pub(crate) Option<&'a str>);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(token) => write!(f, "<redacted:{} bytes>", token.len()),
+            None => f.write_str("<none>"),
+        }
+    }
+}
+
+pub(crate) fn handshake_started(role: &str, channels: &[u16], auth_token: Option<&str>) {
+    debug!(role, ?channels, auth_token = %Redacted(auth_token), "handshake started");
+}
+
+pub(crate) fn handshake_finished(role: &str, result: &Result<HandshakeResult>) {
+    match result {
+        Ok(handshake) => info!(
+            role,
+            peer_id = %handshake.peer_id,
+            channels = ?handshake.negotiated_channels,
+            auth_token = %Redacted(handshake.client_auth_token.as_deref()),
+            "handshake completed"
+        ),
+        Err(err) => warn!(role, error = %err, "handshake failed"),
+    }
+}
+
+/// An auth refresh finished; `rejected` holds the reason when it failed.
+pub(crate) fn auth_refreshed(role: &str, auth_token: Option<&str>, rejected: Option<&str>) {
+    match rejected {
+        None => info!(role, auth_token = %Redacted(auth_token), "auth token refreshed"),
+        Some(reason) => warn!(
+            role,
+            auth_token = %Redacted(auth_token),
+            error = reason,
+            "auth refresh rejected"
+        ),
+    }
+}
+
+#[cfg(all(test, unix, feature = "tracing"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use ipcprims_frame::DATA;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Dispatch, Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    use crate::connector::connect_with_config;
+    use crate::handshake::HandshakeConfig;
+    use crate::listener::PeerListener;
+
+    type Fields = HashMap<String, String>;
+
+    /// An event's own fields plus those of every span it was emitted in.
+    #[derive(Debug)]
+    struct Captured {
+        fields: Fields,
+        span_fields: Fields,
+    }
+
+    impl Captured {
+        fn message(&self) -> &str {
+            self.fields.get("message").map_or("", String::as_str)
+        }
+
+        fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<Captured>>>);
+
+    impl Capture {
+        fn find(&self, message: &str, matches: impl Fn(&Captured) -> bool) -> bool {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|event| event.message() == message && matches(event))
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let mut extensions = span.extensions_mut();
+            if let Some(fields) = extensions.get_mut::<Fields>() {
+                values.record(&mut FieldVisitor(fields));
+            }
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            let mut span_fields = Fields::new();
+            if let Some(scope) = ctx.event_scope(event) {
+                for span in scope.from_root() {
+                    if let Some(values) = span.extensions().get::<Fields>() {
+                        span_fields.extend(values.clone());
+                    }
+                }
+            }
+            self.0.lock().unwrap().push(Captured {
+                fields,
+                span_fields,
+            });
+        }
+    }
+
+    fn make_sock_path(tag: &str) -> std::path::PathBuf {
+        let dir = std::path::PathBuf::from(format!(
+            "/tmp/ipcp-{}-{}-{}",
+            tag,
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        dir.join("trace.sock")
+    }
+
+    #[test]
+    fn connection_lifecycle_emits_structured_events() {
+        let capture = Capture::default();
+        let dispatch = Dispatch::new(tracing_subscriber::registry().with(capture.clone()));
+
+        let sock_path = make_sock_path("trace");
+        let listener = PeerListener::bind(&sock_path).expect("listener should bind");
+
+        let server_dispatch = dispatch.clone();
+        let server = thread::spawn(move || {
+            tracing::dispatcher::with_default(&server_dispatch, || {
+                let mut peer = listener
+                    .accept_with_id("traced-peer")
+                    .expect("listener should accept");
+                let frame = peer.recv().expect("server should receive data");
+                assert_eq!(frame.payload.as_ref(), b"hello");
+                // Acks the shutdown request, then sees the client hang up.
+                assert!(peer.recv().is_err());
+            });
+        });
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            let config = HandshakeConfig {
+                auth_token: Some("s3cret-token".to_string()),
+                ..HandshakeConfig::default()
+            };
+            let mut client = connect_with_config(&sock_path, &[DATA], &config, None, None)
+                .expect("client should connect");
+            client.send(DATA, b"hello").expect("client should send");
+            client.shutdown().expect("shutdown should be acknowledged");
+        });
+        server.join().expect("server thread should finish");
+
+        let in_peer_span = |event: &Captured| {
+            event.span_fields.get("peer_id").map(String::as_str) == Some("traced-peer")
+                && event.span_fields.get("transport").map(String::as_str) == Some("uds")
+        };
+
+        assert!(capture.find("handshake started", |event| {
+            event.field("role") == Some("client")
+                && event.field("auth_token") == Some("<redacted:12 bytes>")
+        }));
+        assert!(capture.find("handshake completed", |event| {
+            event.field("role") == Some("server")
+                && event.field("peer_id") == Some("traced-peer")
+                && event.field("auth_token") == Some("<redacted:12 bytes>")
+                && in_peer_span(event)
+        }));
+        assert!(capture.find("frame sent", |event| {
+            event.field("channel") == Some("2")
+                && event.field("size") == Some("5")
+                && in_peer_span(event)
+        }));
+        assert!(capture.find("frame received", |event| {
+            event.field("channel") == Some("2")
+                && event.field("size") == Some("5")
+                && in_peer_span(event)
+        }));
+        assert!(capture.find("control message received", |event| {
+            event.field("msg_type") == Some("shutdown_request") && in_peer_span(event)
+        }));
+        for phase in ["requested", "acknowledged", "remote_requested"] {
+            assert!(
+                capture.find("shutdown", |event| event.field("phase") == Some(phase)
+                    && in_peer_span(event)),
+                "missing shutdown phase {phase}"
+            );
+        }
+
+        let events = capture.0.lock().unwrap();
+        assert!(!format!("{events:?}").contains("s3cret-token"));
+        drop(events);
+
+        if let Some(parent) = sock_path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+#![cfg(all(unix, feature = "async"))]
+
+//! An async echo server and client talking through `AsyncPeer` alone.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ipcprims_frame::{FrameError, PayloadCodec, COMMAND, TELEMETRY};
+use ipcprims_peer::{
+    async_connect, async_connect_with_config, AsyncPeer, AsyncPeerListener, HandshakeConfig,
+    PeerConfig, PeerError,
+};
+
+fn sock_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "ipcprims-async-echo-{}-{name}.sock",
+        std::process::id()
+    ))
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+/// Echo every COMMAND frame back until the client hangs up.
+async fn echo(mut peer: AsyncPeer) {
+    loop {
+        match peer.recv_on(COMMAND).await {
+            Ok(frame) => peer.send(COMMAND, &frame.payload).await.unwrap(),
+            Err(PeerError::Disconnected(_)) => return,
+            Err(e) => panic!("echo server failed: {e}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn async_peer_talks_to_an_echo_server() {
+    let path = sock_path("echo");
+    let listener = AsyncPeerListener::bind(&path)
+        .unwrap()
+        .with_channels(&[COMMAND, TELEMETRY]);
+    let server = tokio::spawn(async move {
+        let peer = listener.accept().await.unwrap();
+        echo(peer).await;
+    });
+
+    let mut client = async_connect(&path, &[COMMAND, TELEMETRY]).await.unwrap();
+    assert_send(&client);
+    assert_send(&client.recv());
+
+    let reply = client.request(b"hello").await.unwrap();
+    assert_eq!(reply.channel, COMMAND);
+    assert_eq!(reply.payload.as_ref(), b"hello");
+
+    client.send(COMMAND, b"again").await.unwrap();
+    let reply = client.recv_on(COMMAND).await.unwrap();
+    assert_eq!(reply.payload.as_ref(), b"again");
+
+    client.ping().await.unwrap();
+    client.shutdown().await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server should stop after shutdown")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn dropped_recv_futures_lose_no_frames() {
+    let path = sock_path("cancel");
+    let listener = AsyncPeerListener::bind(&path)
+        .unwrap()
+        .with_channels(&[COMMAND, TELEMETRY]);
+    let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+    let mut client = async_connect(&path, &[COMMAND, TELEMETRY]).await.unwrap();
+    let server = server.await.unwrap();
+
+    // Nothing has been sent yet, so both of these give up mid-wait.
+    let idle = Duration::from_millis(20);
+    assert!(tokio::time::timeout(idle, client.recv()).await.is_err());
+    assert!(tokio::time::timeout(idle, client.recv_on(COMMAND))
+        .await
+        .is_err());
+
+    server.send(TELEMETRY, b"t1").await.unwrap();
+    server.send(COMMAND, b"c1").await.unwrap();
+
+    // recv_on sets the telemetry frame aside; recv hands it back next.
+    let command = client.recv_on(COMMAND).await.unwrap();
+    assert_eq!(command.payload.as_ref(), b"c1");
+    let telemetry = client.recv().await.unwrap();
+    assert_eq!(telemetry.channel, TELEMETRY);
+    assert_eq!(telemetry.payload.as_ref(), b"t1");
+
+    assert!(matches!(
+        client.recv_on(99).await,
+        Err(PeerError::UnsupportedChannel(99))
+    ));
+}
+
+/// Passes payloads through unchanged; only its presence matters here.
+struct Plain;
+
+impl PayloadCodec for Plain {
+    fn seal(&self, _: u16, payload: &[u8]) -> ipcprims_frame::Result<Vec<u8>> {
+        Ok(payload.to_vec())
+    }
+    fn open(&self, _: u16, sealed: &[u8]) -> ipcprims_frame::Result<Vec<u8>> {
+        Ok(sealed.to_vec())
+    }
+}
+
+#[tokio::test]
+async fn payload_codec_is_refused_rather_than_ignored() {
+    let config = PeerConfig {
+        payload_codec: Some(Arc::new(Plain)),
+        ..PeerConfig::default()
+    };
+    let path = sock_path("codec");
+    let listener = AsyncPeerListener::bind(&path)
+        .unwrap()
+        .with_peer_config(config.clone());
+    assert!(matches!(
+        listener.accept().await,
+        Err(PeerError::Frame(FrameError::InvalidConfig(_)))
+    ));
+
+    assert!(matches!(
+        async_connect_with_config(
+            &path,
+            &[COMMAND],
+            &HandshakeConfig::default(),
+            None,
+            Some(config),
+            None,
+        )
+        .await,
+        Err(PeerError::Frame(FrameError::InvalidConfig(_)))
+    ));
+}
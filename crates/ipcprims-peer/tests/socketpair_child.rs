@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+//! A supervisor hands one end of a socketpair to a child process, and the
+//! two run the regular handshake over it without any socket path.
+
+use std::process::{Command, Stdio};
+
+use ipcprims_frame::COMMAND;
+use ipcprims_peer::{Peer, PeerConfig, PeerRole};
+use ipcprims_transport::IpcStream;
+
+/// Set in the re-executed test binary to make it play the child.
+const CHILD_MARKER: &str = "IPCPRIMS_TEST_SOCKETPAIR_CHILD";
+const TEST_NAME: &str = "child_handshakes_over_inherited_socketpair";
+
+#[test]
+fn child_handshakes_over_inherited_socketpair() {
+    if std::env::var_os(CHILD_MARKER).is_some() {
+        run_child();
+        return;
+    }
+
+    let (parent_end, child_end) = IpcStream::pair().expect("socketpair should be created");
+    let mut command = Command::new(std::env::current_exe().expect("test binary path"));
+    command
+        .args(["--exact", TEST_NAME, "--quiet"])
+        .env(CHILD_MARKER, "1")
+        .stdout(Stdio::null());
+    child_end.pass_to_child(&mut command);
+    let mut child = command.spawn().expect("child should spawn");
+    // The parent's copy must go, or the parent never sees the child exit.
+    drop(child_end);
+
+    let mut peer = Peer::from_stream(
+        parent_end,
+        PeerRole::Server {
+            channels: vec![COMMAND],
+            peer_id: "child".to_string(),
+        },
+        PeerConfig::default(),
+    )
+    .expect("server handshake should complete");
+    assert_eq!(peer.id(), "child");
+    assert_eq!(peer.channels(), &[COMMAND]);
+
+    let request = peer.recv_on(COMMAND).expect("child should send a request");
+    assert_eq!(request.payload.as_ref(), b"hello from child");
+    peer.send(COMMAND, b"hello from parent")
+        .expect("reply should send");
+
+    let status = child.wait().expect("child should exit");
+    assert!(status.success(), "child failed: {status}");
+}
+
+fn run_child() {
+    let fd = IpcStream::inherited_fd().expect("parent should pass a descriptor");
+    // SAFETY: the descriptor was inherited for this purpose and nothing else
+    // in the child owns it.
+    let stream = unsafe { IpcStream::from_inherited_fd(fd) }.expect("fd should be a unix socket");
+
+    let mut peer = Peer::from_stream(
+        stream,
+        PeerRole::Client {
+            channels: vec![COMMAND],
+        },
+        PeerConfig::default(),
+    )
+    .expect("client handshake should complete");
+    assert_eq!(peer.channels(), &[COMMAND]);
+
+    let response = peer
+        .request(b"hello from child")
+        .expect("parent should reply");
+    assert_eq!(response.payload.as_ref(), b"hello from parent");
+}
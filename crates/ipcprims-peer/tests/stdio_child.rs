@@ -0,0 +1,66 @@
+//! A parent spawns a plugin child with piped stdin/stdout, and the two run
+//! the regular handshake over the pipes.
+//!
+//! Built with `harness = false`: libtest prints to stdout, which in the
+//! child is the protocol stream.
+
+use std::process::Command;
+
+use ipcprims_frame::COMMAND;
+use ipcprims_peer::{Peer, PeerConfig, PeerRole};
+use ipcprims_transport::{ChildStdioTransport, IpcStream};
+
+/// Set in the re-executed test binary to make it play the child.
+const CHILD_MARKER: &str = "IPCPRIMS_TEST_STDIO_CHILD";
+
+fn main() {
+    if std::env::var_os(CHILD_MARKER).is_some() {
+        run_child();
+    } else {
+        child_handshakes_over_stdio();
+        eprintln!("test child_handshakes_over_stdio ... ok");
+    }
+}
+
+fn child_handshakes_over_stdio() {
+    let mut command = Command::new(std::env::current_exe().expect("test binary path"));
+    command.env(CHILD_MARKER, "1");
+    let (mut child, stream) = ChildStdioTransport::spawn(&mut command).expect("child should spawn");
+
+    let mut peer = Peer::from_stream(
+        stream,
+        PeerRole::Server {
+            channels: vec![COMMAND],
+            peer_id: "plugin".to_string(),
+        },
+        PeerConfig::default(),
+    )
+    .expect("server handshake should complete");
+    assert_eq!(peer.id(), "plugin");
+    assert!(peer.peer_credentials().is_none());
+
+    let request = peer.recv_on(COMMAND).expect("child should send a request");
+    assert_eq!(request.payload.as_ref(), b"hello from plugin");
+    peer.send(COMMAND, b"hello from host")
+        .expect("reply should send");
+
+    let status = child.wait().expect("child should exit");
+    assert!(status.success(), "child failed: {status}");
+}
+
+fn run_child() {
+    let stream = IpcStream::from_stdio().expect("stdio should wrap");
+    let mut peer = Peer::from_stream(
+        stream,
+        PeerRole::Client {
+            channels: vec![COMMAND],
+        },
+        PeerConfig::default(),
+    )
+    .expect("client handshake should complete");
+
+    let response = peer
+        .request(b"hello from plugin")
+        .expect("parent should reply");
+    assert_eq!(response.payload.as_ref(), b"hello from host");
+}
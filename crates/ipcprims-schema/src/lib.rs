@@ -14,3 +14,4 @@ pub mod validator;
 pub use config::RegistryConfig;
 pub use error::{Result, SchemaError};
 pub use registry::SchemaRegistry;
+pub use validator::ValidationIssue;
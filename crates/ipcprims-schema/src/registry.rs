@@ -8,11 +8,12 @@ use serde_json::{Map, Value};
 
 use crate::config::RegistryConfig;
 use crate::error::{Result, SchemaError};
-use crate::validator::validate_payload;
+use crate::validator::{collect_issues, validate_payload, ValidationIssue};
 
 /// Channel-keyed registry of compiled JSON Schema validators.
 pub struct SchemaRegistry {
     validators: HashMap<u16, Validator>,
+    titles: HashMap<u16, String>,
     config: RegistryConfig,
 }
 
@@ -26,6 +27,7 @@ impl SchemaRegistry {
     pub fn with_config(config: RegistryConfig) -> Self {
         Self {
             validators: HashMap::new(),
+            titles: HashMap::new(),
             config,
         }
     }
@@ -33,20 +35,44 @@ impl SchemaRegistry {
     /// Register a schema for a channel from a JSON string.
     pub fn register(&mut self, channel: u16, schema_json: &str) -> Result<()> {
         let schema: Value = serde_json::from_str(schema_json)?;
-        self.register_value(channel, &schema)
+        self.register_owned(channel, schema)
     }
 
     /// Register a schema for a channel from JSON value.
+    ///
+    /// The caller's value is never modified. Strict mode compiles a private
+    /// copy; prefer [`register_owned`](Self::register_owned) when the value is
+    /// not needed afterwards.
     pub fn register_value(&mut self, channel: u16, schema: &Value) -> Result<()> {
-        let mut schema_to_compile = schema.clone();
         if self.config.strict_mode {
-            apply_strict_mode(&mut schema_to_compile);
+            return self.register_owned(channel, schema.clone());
         }
+        self.insert_compiled(channel, schema)
+    }
 
-        let compiled = jsonschema::validator_for(&schema_to_compile)
+    /// Register a schema for a channel, taking ownership of the JSON value.
+    ///
+    /// Strict mode rewrites the value in place instead of copying it.
+    pub fn register_owned(&mut self, channel: u16, mut schema: Value) -> Result<()> {
+        if self.config.strict_mode {
+            apply_strict_mode(&mut schema);
+        }
+        self.insert_compiled(channel, &schema)
+    }
+
+    fn insert_compiled(&mut self, channel: u16, schema: &Value) -> Result<()> {
+        let compiled = jsonschema::validator_for(schema)
             .map_err(|err| SchemaError::CompileFailed(err.to_string()))?;
 
         self.validators.insert(channel, compiled);
+        match schema.get("title").and_then(Value::as_str) {
+            Some(title) => {
+                self.titles.insert(channel, title.to_string());
+            }
+            None => {
+                self.titles.remove(&channel);
+            }
+        }
         Ok(())
     }
 
@@ -172,6 +198,18 @@ impl SchemaRegistry {
         }
     }
 
+    /// Validate channel payload and return every violation found.
+    ///
+    /// An empty list means the payload is valid (or the channel has no schema
+    /// and missing schemas are allowed).
+    pub fn issues(&self, channel: u16, payload: &[u8]) -> Result<Vec<ValidationIssue>> {
+        match self.validators.get(&channel) {
+            Some(validator) => collect_issues(payload, validator),
+            None if self.config.fail_on_missing_schema => Err(SchemaError::NoSchema(channel)),
+            None => Ok(Vec::new()),
+        }
+    }
+
     /// Validate a frame payload against its channel schema.
     pub fn validate_frame(&self, frame: &Frame) -> Result<()> {
         self.validate(frame.channel, frame.payload.as_ref())
@@ -189,6 +227,11 @@ impl SchemaRegistry {
         channels
     }
 
+    /// Get the `title` of the schema registered for a channel, if any.
+    pub fn title(&self, channel: u16) -> Option<&str> {
+        self.titles.get(&channel).map(String::as_str)
+    }
+
     /// Get registry configuration.
     pub fn config(&self) -> &RegistryConfig {
         &self.config
@@ -432,6 +475,28 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn issues_report_json_pointers() {
+        let mut registry = SchemaRegistry::new();
+        registry.register(1, OBJECT_SCHEMA).unwrap();
+
+        assert!(registry
+            .issues(1, br#"{"id":1,"name":"ok"}"#)
+            .unwrap()
+            .is_empty());
+
+        let issues = registry.issues(1, br#"{"id":"x","name":"ok"}"#).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].instance_path, "/id");
+        assert_eq!(issues[0].schema_path, "/properties/id/type");
+
+        assert!(matches!(
+            registry.issues(1, b"not-json"),
+            Err(SchemaError::InvalidJson(_))
+        ));
+        assert!(registry.issues(9, b"anything").unwrap().is_empty());
+    }
+
     #[test]
     fn invalid_json_payload_fails() {
         let mut registry = SchemaRegistry::new();
@@ -501,6 +566,22 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn schema_titles_are_recorded() {
+        let mut registry = SchemaRegistry::new();
+        registry
+            .register(300, r#"{"title":"Job Update","type":"object"}"#)
+            .unwrap();
+        registry.register(301, OBJECT_SCHEMA).unwrap();
+
+        assert_eq!(registry.title(300), Some("Job Update"));
+        assert_eq!(registry.title(301), None);
+        assert_eq!(registry.title(302), None);
+
+        registry.register(300, OBJECT_SCHEMA).unwrap();
+        assert_eq!(registry.title(300), None);
+    }
+
     fn make_temp_schema_dir(tag: &str) -> PathBuf {
         let dir = std::env::temp_dir().join(format!(
             "ipcprims-schema-{tag}-{}-{}",
@@ -589,6 +670,46 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn register_value_does_not_mutate_caller_schema() {
+        let schema: Value = serde_json::from_str(OBJECT_SCHEMA).unwrap();
+        let original = schema.clone();
+
+        let mut strict = SchemaRegistry::with_config(RegistryConfig {
+            strict_mode: true,
+            ..RegistryConfig::default()
+        });
+        strict.register_value(1, &schema).unwrap();
+        assert_eq!(schema, original);
+        assert!(schema.get("additionalProperties").is_none());
+        assert!(matches!(
+            strict.validate(1, br#"{"id":1,"name":"ok","extra":true}"#),
+            Err(SchemaError::ValidationFailed { .. })
+        ));
+
+        let mut lenient = SchemaRegistry::new();
+        lenient.register_value(1, &schema).unwrap();
+        assert_eq!(schema, original);
+        assert!(lenient
+            .validate(1, br#"{"id":1,"name":"ok","extra":true}"#)
+            .is_ok());
+    }
+
+    #[test]
+    fn register_owned_applies_strict_mode() {
+        let schema: Value = serde_json::from_str(OBJECT_SCHEMA).unwrap();
+        let mut strict = SchemaRegistry::with_config(RegistryConfig {
+            strict_mode: true,
+            ..RegistryConfig::default()
+        });
+        strict.register_owned(1, schema).unwrap();
+
+        assert!(strict.validate(1, br#"{"id":1,"name":"ok"}"#).is_ok());
+        assert!(strict
+            .validate(1, br#"{"id":1,"name":"ok","extra":true}"#)
+            .is_err());
+    }
+
     #[test]
     fn only_recognized_extensions_are_loaded() {
         let dir = make_temp_schema_dir("extensions");
@@ -2,6 +2,17 @@ use jsonschema::Validator;
 
 use crate::error::{Result, SchemaError};
 
+/// A single schema violation, located by JSON pointers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// JSON pointer to the offending location in the payload.
+    pub instance_path: String,
+    /// JSON pointer to the schema keyword that rejected it.
+    pub schema_path: String,
+    /// Human-readable description of the violation.
+    pub message: String,
+}
+
 pub(crate) fn validate_payload(channel: u16, payload: &[u8], validator: &Validator) -> Result<()> {
     let value: serde_json::Value = serde_json::from_slice(payload)?;
 
@@ -17,3 +28,19 @@ pub(crate) fn validate_payload(channel: u16, payload: &[u8], validator: &Validat
 
     Ok(())
 }
+
+pub(crate) fn collect_issues(
+    payload: &[u8],
+    validator: &Validator,
+) -> Result<Vec<ValidationIssue>> {
+    let value: serde_json::Value = serde_json::from_slice(payload)?;
+
+    Ok(validator
+        .iter_errors(&value)
+        .map(|err| ValidationIssue {
+            instance_path: err.instance_path().to_string(),
+            schema_path: err.schema_path().to_string(),
+            message: err.to_string(),
+        })
+        .collect())
+}
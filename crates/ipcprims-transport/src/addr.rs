@@ -0,0 +1,68 @@
+//! What an [`IpcStream`](crate::IpcStream) is connected to, for logs and
+//! diagnostics.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Serialize, Serializer};
+
+/// One end of a connection, as [`IpcStream::local_addr`] and
+/// [`IpcStream::peer_addr`] report it.
+///
+/// Displays (and serializes) in the form endpoints are written in:
+/// a path, `@name`, `tcp://host:port` or `vsock://cid:port`.
+///
+/// [`IpcStream::local_addr`]: crate::IpcStream::local_addr
+/// [`IpcStream::peer_addr`]: crate::IpcStream::peer_addr
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IpcAddr {
+    /// A filesystem Unix socket path.
+    Path(PathBuf),
+    /// A name in the Linux abstract socket namespace, without the leading
+    /// NUL.
+    Abstract(Vec<u8>),
+    /// No address: a socket from `socketpair`, a client socket that never
+    /// bound one, or a stream with no socket behind it (stdio, memory,
+    /// named pipes).
+    Unnamed,
+    /// A loopback TCP address.
+    Tcp(std::net::SocketAddr),
+    /// A vsock context id and port.
+    Vsock { cid: u32, port: u32 },
+}
+
+#[cfg(unix)]
+impl From<std::os::unix::net::SocketAddr> for IpcAddr {
+    fn from(addr: std::os::unix::net::SocketAddr) -> Self {
+        if let Some(path) = addr.as_pathname() {
+            return Self::Path(path.to_path_buf());
+        }
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            if let Some(name) = addr.as_abstract_name() {
+                return Self::Abstract(name.to_vec());
+            }
+        }
+        Self::Unnamed
+    }
+}
+
+impl fmt::Display for IpcAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Abstract(name) => write!(f, "@{}", String::from_utf8_lossy(name)),
+            Self::Unnamed => f.write_str("unnamed"),
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            Self::Vsock { cid, port } => write!(f, "vsock://{cid}:{port}"),
+        }
+    }
+}
+
+impl Serialize for IpcAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
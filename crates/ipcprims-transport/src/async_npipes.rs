@@ -10,9 +10,9 @@ use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::windows::named_pipe::{
     ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions,
 };
-use tracing::debug;
 
 use crate::error::{Result, TransportError};
+use crate::trace::debug;
 
 fn normalize_pipe_name(path: &Path) -> String {
     let raw = path.as_os_str().to_string_lossy().to_string();
@@ -126,7 +126,7 @@ impl AsyncNamedPipeSocket {
             .map_err(TransportError::Io)?;
 
         connect_server(&server).await?;
-        debug!(pipe = %name, "accepted connection on named pipe (async)");
+        debug!(transport = "named_pipe", path = %name, "accepted connection on named pipe (async)");
         Ok(AsyncIpcStream::from_server(server))
     }
 
@@ -137,7 +137,7 @@ impl AsyncNamedPipeSocket {
         loop {
             match ClientOptions::new().open(&name) {
                 Ok(client) => {
-                    debug!(pipe = %name, "connected to named pipe (async)");
+                    debug!(transport = "named_pipe", path = %name, "connected to named pipe (async)");
                     return Ok(AsyncIpcStream::new(client));
                 }
                 Err(e) if retries < 200 => {
@@ -149,16 +149,10 @@ impl AsyncNamedPipeSocket {
                         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
                         continue;
                     }
-                    return Err(TransportError::Connect {
-                        path: PathBuf::from(name),
-                        source: e,
-                    });
+                    return Err(TransportError::connect(name, e));
                 }
                 Err(e) => {
-                    return Err(TransportError::Connect {
-                        path: PathBuf::from(name),
-                        source: e,
-                    });
+                    return Err(TransportError::connect(name, e));
                 }
             }
         }
@@ -9,9 +9,11 @@ use std::task::{Context, Poll};
 
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::UnixListener;
-use tracing::{debug, info};
 
+use crate::credentials::PeerCredentials;
 use crate::error::{Result, TransportError};
+use crate::trace::{debug, info};
+use crate::uds::{fits_sun_path, remove_stale_socket, with_sun_path, LockFile, UnixDomainSocket};
 
 /// An async IPC stream (Tokio).
 ///
@@ -51,45 +53,31 @@ impl AsyncIpcStream {
         self.inner
     }
 
-    /// Get the credentials of the connected peer (Linux only).
+    /// Convert back to a blocking [`IpcStream`](crate::IpcStream), with
+    /// no timeouts set; the reverse of
+    /// [`IpcStream::into_async`](crate::IpcStream::into_async).
+    pub fn into_sync(self) -> Result<crate::IpcStream> {
+        let stream = self.inner.into_std()?;
+        stream.set_nonblocking(false)?;
+        Ok(crate::IpcStream::from_unix(stream))
+    }
+
+    /// Get the credentials of the connected peer (Linux and macOS).
     ///
-    /// Returns `(uid, gid, pid)` via `SO_PEERCRED`, or `None` if unavailable.
-    #[cfg(target_os = "linux")]
-    pub fn peer_credentials(&self) -> Option<(u32, u32, u32)> {
+    /// Read via `SO_PEERCRED` on Linux, and via `getpeereid` plus
+    /// `LOCAL_PEERPID` on macOS; `None` if unavailable.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "ios"))]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
         use std::os::fd::AsRawFd;
 
-        let fd = self.inner.as_raw_fd();
-        let mut cred = libc::ucred {
-            pid: 0,
-            uid: 0,
-            gid: 0,
-        };
-        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
-
-        // SAFETY: `cred` and `len` are valid writable pointers for the provided sizes,
-        // and `fd` is an open Unix socket descriptor owned by this process.
-        let rc = unsafe {
-            libc::getsockopt(
-                fd,
-                libc::SOL_SOCKET,
-                libc::SO_PEERCRED,
-                (&mut cred as *mut libc::ucred).cast::<libc::c_void>(),
-                &mut len,
-            )
-        };
-
-        if rc == 0 && len as usize == std::mem::size_of::<libc::ucred>() {
-            Some((cred.uid, cred.gid, cred.pid as u32))
-        } else {
-            None
-        }
+        crate::credentials::from_socket(self.inner.as_raw_fd())
     }
 
     /// Get the credentials of the connected peer.
     ///
     /// Returns `None` on platforms that do not expose peer credentials.
-    #[cfg(not(target_os = "linux"))]
-    pub fn peer_credentials(&self) -> Option<(u32, u32, u32)> {
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "ios")))]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
         None
     }
 }
@@ -136,20 +124,18 @@ pub struct AsyncUnixDomainSocket {
     path: PathBuf,
     created_inode: Option<(u64, u64)>,
     cleanup_on_drop: bool,
+    /// Held until drop, which runs after the socket path is cleaned up.
+    lock: Option<LockFile>,
 }
 
 impl AsyncUnixDomainSocket {
     /// Default permission mode for created socket paths.
     pub const DEFAULT_SOCKET_MODE: u32 = 0o600;
-    /// Maximum socket path length.
-    #[cfg(target_os = "linux")]
-    const MAX_PATH_LEN: usize = 108;
-    #[cfg(target_os = "macos")]
-    const MAX_PATH_LEN: usize = 104;
-    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-    const MAX_PATH_LEN: usize = 104;
 
     /// Bind and listen on a filesystem-path Unix domain socket.
+    ///
+    /// Takes the same `<path>.lock` file and live-socket check as
+    /// [`UnixDomainSocket::bind`](crate::UnixDomainSocket::bind).
     pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
         Self::bind_with_mode(path, Self::DEFAULT_SOCKET_MODE)
     }
@@ -157,43 +143,18 @@ impl AsyncUnixDomainSocket {
     /// Bind and listen on a filesystem-path Unix domain socket with explicit mode.
     pub fn bind_with_mode(path: impl AsRef<Path>, mode: u32) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
+        UnixDomainSocket::check_path_len(&path)?;
 
-        let path_bytes = path.as_os_str().len();
-        if path_bytes >= Self::MAX_PATH_LEN {
-            return Err(TransportError::PathTooLong {
-                path,
-                len: path_bytes,
-                max: Self::MAX_PATH_LEN,
-            });
-        }
+        let lock = LockFile::acquire(&path, false)?;
+        remove_stale_socket(&path, false)?;
 
-        if path.exists() {
-            let metadata = std::fs::symlink_metadata(&path).map_err(|e| TransportError::Bind {
-                path: path.clone(),
-                source: e,
-            })?;
-            if metadata.file_type().is_socket() {
-                debug!(?path, "removing stale socket");
-                std::fs::remove_file(&path).map_err(|e| TransportError::Bind {
+        let std_listener =
+            with_sun_path(&path, |path| std::os::unix::net::UnixListener::bind(path)).map_err(
+                |e| TransportError::Bind {
                     path: path.clone(),
                     source: e,
-                })?;
-            } else {
-                return Err(TransportError::Bind {
-                    path: path.clone(),
-                    source: std::io::Error::new(
-                        std::io::ErrorKind::AlreadyExists,
-                        "existing path is not a unix socket",
-                    ),
-                });
-            }
-        }
-
-        let std_listener =
-            std::os::unix::net::UnixListener::bind(&path).map_err(|e| TransportError::Bind {
-                path: path.clone(),
-                source: e,
-            })?;
+                },
+            )?;
         std_listener
             .set_nonblocking(true)
             .map_err(|e| TransportError::Bind {
@@ -215,13 +176,18 @@ impl AsyncUnixDomainSocket {
         let created_inode = Some((created_metadata.dev(), created_metadata.ino()));
 
         let listener = UnixListener::from_std(std_listener).map_err(TransportError::Io)?;
-        info!(?path, "listening on unix domain socket (async)");
+        info!(
+            transport = "uds",
+            ?path,
+            "listening on unix domain socket (async)"
+        );
 
         Ok(Self {
             listener,
             path,
             created_inode,
             cleanup_on_drop: true,
+            lock,
         })
     }
 
@@ -232,21 +198,32 @@ impl AsyncUnixDomainSocket {
             .accept()
             .await
             .map_err(TransportError::Accept)?;
-        debug!("accepted connection");
+        debug!(transport = "uds", "accepted connection");
         Ok(AsyncIpcStream::new(stream))
     }
 
     /// Connect to a listening Unix domain socket (async).
     pub async fn connect(path: impl AsRef<Path>) -> Result<AsyncIpcStream> {
         let path = path.as_ref().to_path_buf();
-        let stream =
-            tokio::net::UnixStream::connect(&path)
-                .await
-                .map_err(|e| TransportError::Connect {
-                    path: path.clone(),
-                    source: e,
-                })?;
-        debug!(?path, "connected to unix domain socket (async)");
+        UnixDomainSocket::check_path_len(&path)?;
+        let stream = if fits_sun_path(&path) {
+            tokio::net::UnixStream::connect(&path).await
+        } else {
+            // Tokio cannot connect through the directory alias; a Unix
+            // connect only blocks while the listener's backlog is full.
+            with_sun_path(&path, |path| std::os::unix::net::UnixStream::connect(path)).and_then(
+                |stream| {
+                    stream.set_nonblocking(true)?;
+                    tokio::net::UnixStream::from_std(stream)
+                },
+            )
+        }
+        .map_err(|e| TransportError::connect(&path, e))?;
+        debug!(
+            transport = "uds",
+            ?path,
+            "connected to unix domain socket (async)"
+        );
         Ok(AsyncIpcStream::new(stream))
     }
 
@@ -255,6 +232,11 @@ impl AsyncUnixDomainSocket {
         &self.path
     }
 
+    /// The `<path>.lock` file this listener holds, if any.
+    pub fn lock_path(&self) -> Option<&Path> {
+        self.lock.as_ref().map(LockFile::path)
+    }
+
     /// Transport name for diagnostics.
     pub fn transport_name(&self) -> &'static str {
         "unix-domain-socket"
@@ -323,6 +305,16 @@ mod tests {
         assert!(matches!(result, Err(TransportError::PathTooLong { .. })));
     }
 
+    #[tokio::test]
+    async fn connect_path_too_long() {
+        let long_path = "/tmp/".to_string() + &"a".repeat(200) + ".sock";
+        let result = AsyncUnixDomainSocket::connect(&long_path).await;
+        assert!(matches!(
+            result,
+            Err(TransportError::PathTooLong { len, .. }) if len == long_path.len()
+        ));
+    }
+
     #[tokio::test]
     async fn bind_default_permissions_hardened() {
         let dir = std::env::temp_dir().join(format!("ipcprims-async-perms-{}", std::process::id()));
@@ -409,4 +401,81 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[tokio::test]
+    async fn into_async_and_back_keep_the_connection() {
+        use std::io::{Read, Write};
+
+        let (sync_a, mut b) = crate::IpcStream::pair().unwrap();
+        let mut a = sync_a.into_async().unwrap();
+
+        b.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        a.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        a.write_all(b"pong").await.unwrap();
+
+        let mut a = a.into_sync().unwrap();
+        a.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+        b.write_all(b"sync").unwrap();
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"sync");
+    }
+
+    #[tokio::test]
+    async fn into_async_rejects_streams_without_a_unix_socket() {
+        let (a, _b) = crate::IpcStream::memory_pair().unwrap();
+        let err = a.into_async().err().unwrap();
+        assert!(matches!(
+            err,
+            TransportError::Io(ref e) if e.kind() == std::io::ErrorKind::Unsupported
+        ));
+    }
+
+    #[tokio::test]
+    async fn bind_refuses_a_path_another_listener_holds() {
+        let dir =
+            std::env::temp_dir().join(format!("ipcprims-async-in-use-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("held.sock");
+
+        let listener = AsyncUnixDomainSocket::bind(&sock_path).unwrap();
+        let result = AsyncUnixDomainSocket::bind(&sock_path);
+        assert!(matches!(result, Err(TransportError::AddressInUse { .. })));
+        assert!(sock_path.exists());
+
+        drop(listener);
+        let listener = AsyncUnixDomainSocket::bind(&sock_path).unwrap();
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[tokio::test]
+    async fn long_paths_bind_and_connect_through_their_directory() {
+        let root = std::env::temp_dir().join(format!("ipcprims-async-long-{}", std::process::id()));
+        let dir = root.join("a".repeat(60)).join("b".repeat(60));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("daemon.sock");
+        assert!(sock_path.as_os_str().len() > 150);
+
+        let listener = AsyncUnixDomainSocket::bind(&sock_path).unwrap();
+        let server = tokio::spawn(async move {
+            let mut server = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"long");
+            listener
+        });
+        let mut client = AsyncUnixDomainSocket::connect(&sock_path).await.unwrap();
+        client.write_all(b"long").await.unwrap();
+        let listener = server.await.unwrap();
+
+        drop(listener);
+        assert!(!sock_path.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,325 @@
+//! Runtime probes for optional platform transport features.
+//!
+//! Each probe exercises the feature directly (binds a socket, queries an
+//! option, passes a descriptor) instead of inferring support from `cfg!`.
+//! Probes never touch the filesystem and close every descriptor they open
+//! before returning.
+
+/// Optional transport feature that can be probed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Linux abstract-namespace Unix sockets (no filesystem entry).
+    AbstractNamespace,
+    /// Connected peer credentials (`SO_PEERCRED`) as reported by [`crate::IpcStream`].
+    PeerCredentials,
+    /// Descriptor passing over Unix sockets (`SCM_RIGHTS`).
+    ScmRights,
+    /// `AF_VSOCK` sockets for guest/host communication.
+    Vsock,
+    /// `SOCK_SEQPACKET` Unix sockets.
+    Seqpacket,
+    /// Windows named pipes.
+    NamedPipes,
+}
+
+impl Capability {
+    /// Every probeable capability, in report order.
+    pub const ALL: [Capability; 6] = [
+        Capability::AbstractNamespace,
+        Capability::PeerCredentials,
+        Capability::ScmRights,
+        Capability::Vsock,
+        Capability::Seqpacket,
+        Capability::NamedPipes,
+    ];
+
+    /// Stable snake_case identifier.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::AbstractNamespace => "abstract_namespace",
+            Capability::PeerCredentials => "peer_credentials",
+            Capability::ScmRights => "scm_rights",
+            Capability::Vsock => "vsock",
+            Capability::Seqpacket => "seqpacket",
+            Capability::NamedPipes => "named_pipes",
+        }
+    }
+}
+
+/// Outcome of a capability probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeStatus {
+    /// The probe exercised the feature successfully.
+    Available,
+    /// The platform has the feature but the probe failed (kernel module
+    /// missing, sandbox restrictions, unsupported by this build, ...).
+    Unavailable,
+    /// The feature does not exist on this platform.
+    NotApplicable,
+}
+
+/// Result of probing one [`Capability`].
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub capability: Capability,
+    pub status: ProbeStatus,
+    pub detail: String,
+}
+
+/// Probe a single capability.
+///
+/// Probes only make non-blocking syscalls, but callers that need a hard
+/// bound (e.g. `doctor`) should still run them off-thread with a timeout.
+pub fn probe(capability: Capability) -> ProbeResult {
+    let (status, detail) = match capability {
+        Capability::AbstractNamespace => probe_abstract_namespace(),
+        Capability::PeerCredentials => probe_peer_credentials(),
+        Capability::ScmRights => probe_scm_rights(),
+        Capability::Vsock => probe_vsock(),
+        Capability::Seqpacket => probe_seqpacket(),
+        Capability::NamedPipes => probe_named_pipes(),
+    };
+    ProbeResult {
+        capability,
+        status,
+        detail,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn probe_abstract_namespace() -> (ProbeStatus, String) {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixListener};
+
+    let name = format!("ipcprims-probe-{}-{}", std::process::id(), unique_suffix());
+    match SocketAddr::from_abstract_name(name.as_bytes())
+        .and_then(|addr| UnixListener::bind_addr(&addr))
+    {
+        Ok(_listener) => (
+            ProbeStatus::Available,
+            "bound and released an abstract socket".to_string(),
+        ),
+        Err(err) => (
+            ProbeStatus::Unavailable,
+            format!("abstract socket bind failed: {err}"),
+        ),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_abstract_namespace() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "abstract namespace sockets are Linux-only".to_string(),
+    )
+}
+
+#[cfg(unix)]
+fn probe_peer_credentials() -> (ProbeStatus, String) {
+    let (local, _remote) = match std::os::unix::net::UnixStream::pair() {
+        Ok(pair) => pair,
+        Err(err) => {
+            return (
+                ProbeStatus::Unavailable,
+                format!("socketpair failed: {err}"),
+            )
+        }
+    };
+
+    match crate::IpcStream::from_unix(local).peer_credentials() {
+        Some(creds) => (
+            ProbeStatus::Available,
+            format!("read {creds} from a socketpair"),
+        ),
+        None => (
+            ProbeStatus::Unavailable,
+            "peer credential lookup not supported by this build on this OS".to_string(),
+        ),
+    }
+}
+
+#[cfg(not(unix))]
+fn probe_peer_credentials() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "peer credentials require Unix domain sockets".to_string(),
+    )
+}
+
+#[cfg(unix)]
+fn probe_scm_rights() -> (ProbeStatus, String) {
+    match scm_rights_roundtrip() {
+        Ok(()) => (
+            ProbeStatus::Available,
+            "passed a descriptor over a socketpair".to_string(),
+        ),
+        Err(err) => (
+            ProbeStatus::Unavailable,
+            format!("SCM_RIGHTS round-trip failed: {err}"),
+        ),
+    }
+}
+
+/// Send one end of a socketpair's own descriptor across it and close the copy.
+#[cfg(unix)]
+fn scm_rights_roundtrip() -> std::io::Result<()> {
+    use std::io::IoSlice;
+    use std::os::fd::{AsFd, AsRawFd};
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair()?;
+    crate::fdpass::send(tx.as_raw_fd(), &[IoSlice::new(&[0])], &[tx.as_fd()])?;
+
+    let mut byte = [0u8; 1];
+    let mut received = Vec::new();
+    crate::fdpass::recv(rx.as_raw_fd(), &mut byte, &mut received)?;
+    if received.is_empty() {
+        return Err(std::io::Error::other("no descriptor received"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn probe_scm_rights() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "SCM_RIGHTS requires Unix domain sockets".to_string(),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn probe_vsock() -> (ProbeStatus, String) {
+    probe_socket(libc::AF_VSOCK, libc::SOCK_STREAM, "AF_VSOCK")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_vsock() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "AF_VSOCK is Linux-only".to_string(),
+    )
+}
+
+#[cfg(unix)]
+fn probe_seqpacket() -> (ProbeStatus, String) {
+    probe_socket(
+        libc::AF_UNIX,
+        libc::SOCK_SEQPACKET,
+        "AF_UNIX/SOCK_SEQPACKET",
+    )
+}
+
+#[cfg(not(unix))]
+fn probe_seqpacket() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "SOCK_SEQPACKET requires Unix domain sockets".to_string(),
+    )
+}
+
+/// Create and immediately close a socket of the given family and type.
+#[cfg(unix)]
+fn probe_socket(domain: libc::c_int, ty: libc::c_int, label: &str) -> (ProbeStatus, String) {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    #[cfg(target_os = "linux")]
+    let ty = ty | libc::SOCK_CLOEXEC;
+
+    // SAFETY: socket() takes no pointers; a non-negative return is a new
+    // descriptor owned by this function.
+    let fd = unsafe { libc::socket(domain, ty, 0) };
+    if fd < 0 {
+        let err = std::io::Error::last_os_error();
+        return (
+            ProbeStatus::Unavailable,
+            format!("{label} socket failed: {err}"),
+        );
+    }
+    // SAFETY: fd was just returned by socket() and is not shared.
+    drop(unsafe { OwnedFd::from_raw_fd(fd) });
+    (
+        ProbeStatus::Available,
+        format!("created and closed an {label} socket"),
+    )
+}
+
+#[cfg(windows)]
+fn probe_named_pipes() -> (ProbeStatus, String) {
+    let name = format!(
+        r"\\.\pipe\ipcprims-probe-{}-{}",
+        std::process::id(),
+        unique_suffix()
+    );
+    match crate::npipes::probe_pipe_instance(&name) {
+        Ok(()) => (
+            ProbeStatus::Available,
+            "created and closed a named-pipe instance".to_string(),
+        ),
+        Err(err) => (
+            ProbeStatus::Unavailable,
+            format!("named-pipe creation failed: {err}"),
+        ),
+    }
+}
+
+#[cfg(not(windows))]
+fn probe_named_pipes() -> (ProbeStatus, String) {
+    (
+        ProbeStatus::NotApplicable,
+        "named pipes are Windows-only".to_string(),
+    )
+}
+
+#[cfg(any(target_os = "linux", windows))]
+fn unique_suffix() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_capability_reports_a_detail() {
+        for capability in Capability::ALL {
+            let result = probe(capability);
+            assert_eq!(result.capability, capability);
+            assert!(!result.detail.is_empty(), "{}", capability.name());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_probes_succeed() {
+        assert_eq!(probe(Capability::ScmRights).status, ProbeStatus::Available);
+        assert_eq!(
+            probe(Capability::NamedPipes).status,
+            ProbeStatus::NotApplicable
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_probes_succeed() {
+        assert_eq!(
+            probe(Capability::AbstractNamespace).status,
+            ProbeStatus::Available
+        );
+        assert_eq!(
+            probe(Capability::PeerCredentials).status,
+            ProbeStatus::Available
+        );
+        assert_eq!(probe(Capability::Seqpacket).status, ProbeStatus::Available);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn macos_probes_succeed() {
+        assert_eq!(
+            probe(Capability::PeerCredentials).status,
+            ProbeStatus::Available
+        );
+    }
+}
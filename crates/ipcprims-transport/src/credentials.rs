@@ -0,0 +1,162 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Credentials of the process on the other end of a connection.
+///
+/// `pid` is `None` on platforms that report the peer's user and group but not
+/// its process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PeerCredentials {
+    /// Effective user ID of the peer.
+    pub uid: u32,
+    /// Effective group ID of the peer.
+    pub gid: u32,
+    /// Process ID of the peer, if the platform reports it.
+    pub pid: Option<u32>,
+}
+
+impl PeerCredentials {
+    /// True if the peer runs as this process's effective user.
+    #[cfg(unix)]
+    pub fn same_user(&self) -> bool {
+        // SAFETY: `geteuid` has no preconditions and cannot fail.
+        self.uid == unsafe { libc::geteuid() }
+    }
+
+    /// True if the peer runs as this process's effective user.
+    ///
+    /// Always `false` on platforms without Unix user IDs.
+    #[cfg(not(unix))]
+    pub fn same_user(&self) -> bool {
+        false
+    }
+
+    /// This process's own credentials, as seen by a peer in the same process.
+    #[cfg(unix)]
+    pub(crate) fn current_process() -> Self {
+        // SAFETY: `geteuid` and `getegid` have no preconditions and cannot fail.
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        Self {
+            uid,
+            gid,
+            pid: Some(std::process::id()),
+        }
+    }
+}
+
+impl fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uid={} gid={}", self.uid, self.gid)?;
+        match self.pid {
+            Some(pid) => write!(f, " pid={pid}"),
+            None => f.write_str(" pid=unknown"),
+        }
+    }
+}
+
+/// Read `SO_PEERCRED` from a connected Unix socket.
+#[cfg(target_os = "linux")]
+pub(crate) fn from_socket(fd: std::os::fd::RawFd) -> Option<PeerCredentials> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: `cred` and `len` are valid writable pointers for the provided sizes,
+    // and `fd` is an open Unix socket descriptor owned by the caller.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast::<libc::c_void>(),
+            &mut len,
+        )
+    };
+
+    if rc == 0 && len as usize == std::mem::size_of::<libc::ucred>() {
+        Some(PeerCredentials {
+            uid: cred.uid,
+            gid: cred.gid,
+            // The kernel reports 0 when the peer's pid is not visible here.
+            pid: u32::try_from(cred.pid).ok().filter(|&pid| pid != 0),
+        })
+    } else {
+        None
+    }
+}
+
+/// Read `getpeereid` and `LOCAL_PEERPID` from a connected Unix socket.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn from_socket(fd: std::os::fd::RawFd) -> Option<PeerCredentials> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    // SAFETY: `uid` and `gid` are valid writable pointers, and `fd` is an
+    // open Unix socket descriptor owned by the caller.
+    if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+        return None;
+    }
+
+    let mut pid: libc::pid_t = 0;
+    let mut len = std::mem::size_of::<libc::pid_t>() as libc::socklen_t;
+    // SAFETY: `pid` and `len` are valid writable pointers for the provided
+    // size.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_LOCAL,
+            libc::LOCAL_PEERPID,
+            (&raw mut pid).cast::<libc::c_void>(),
+            &mut len,
+        )
+    };
+
+    Some(PeerCredentials {
+        uid,
+        gid,
+        // Older kernels lack LOCAL_PEERPID; report the pid as unknown.
+        pid: (rc == 0)
+            .then(|| u32::try_from(pid).ok())
+            .flatten()
+            .filter(|&pid| pid != 0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names_every_field() {
+        let creds = PeerCredentials {
+            uid: 1000,
+            gid: 100,
+            pid: Some(42),
+        };
+        assert_eq!(creds.to_string(), "uid=1000 gid=100 pid=42");
+
+        let creds = PeerCredentials { pid: None, ..creds };
+        assert_eq!(creds.to_string(), "uid=1000 gid=100 pid=unknown");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_user_compares_effective_uid() {
+        // SAFETY: `geteuid` has no preconditions and cannot fail.
+        let euid = unsafe { libc::geteuid() };
+        let mine = PeerCredentials {
+            uid: euid,
+            gid: 0,
+            pid: None,
+        };
+        assert!(mine.same_user());
+        assert!(!PeerCredentials {
+            uid: euid.wrapping_add(1),
+            ..mine
+        }
+        .same_user());
+    }
+}
@@ -10,6 +10,20 @@ pub enum TransportError {
         source: std::io::Error,
     },
 
+    /// A socket path could not be given the requested owning group.
+    #[error("failed to set group {group} on {path}: {source}")]
+    Chown {
+        path: PathBuf,
+        group: String,
+        source: std::io::Error,
+    },
+
+    /// Another listener is alive on the socket path, so bind left it alone.
+    /// Pass [`BindOptions::force`](crate::BindOptions::force) to take the
+    /// path over regardless. Unix only.
+    #[error("address in use: a listener is still accepting on {path}")]
+    AddressInUse { path: PathBuf },
+
     /// Failed to connect to the specified address.
     #[error("failed to connect to {path}: {source}")]
     Connect {
@@ -17,15 +31,55 @@ pub enum TransportError {
         source: std::io::Error,
     },
 
+    /// Nothing exists at the endpoint path: no listener has bound it, or
+    /// a directory on the way is missing.
+    #[error("no such endpoint: {path}")]
+    NotFound { path: PathBuf },
+
+    /// The endpoint, or a directory on the way to it, is not accessible
+    /// to this user.
+    #[error("permission denied: {path}")]
+    PermissionDenied { path: PathBuf },
+
+    /// [`connect_with_retry`](crate::connect_with_retry) ran out of time;
+    /// `source` is the last attempt's error.
+    #[error("connect to {path} timed out after {waited:?}: {source}")]
+    ConnectTimeout {
+        path: PathBuf,
+        waited: std::time::Duration,
+        source: std::io::Error,
+    },
+
+    /// The endpoint failed a [`ConnectOptions`](crate::ConnectOptions)
+    /// ownership, mode or peer credential requirement. Nothing was sent.
+    #[error("refusing to connect to {path}: {detail}")]
+    UntrustedEndpoint { path: PathBuf, detail: String },
+
     /// Failed to accept an incoming connection.
     #[error("failed to accept connection: {0}")]
     Accept(std::io::Error),
 
+    /// The peer reset or aborted the connection.
+    #[error("connection reset by peer")]
+    ConnectionReset,
+
+    /// A write found the peer's end of the connection closed.
+    #[error("broken pipe: the peer closed the connection")]
+    BrokenPipe,
+
     /// An I/O error occurred on the transport stream.
+    ///
+    /// Resets and broken pipes convert to [`ConnectionReset`] and
+    /// [`BrokenPipe`] instead.
+    ///
+    /// [`ConnectionReset`]: Self::ConnectionReset
+    /// [`BrokenPipe`]: Self::BrokenPipe
     #[error("transport I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[source] std::io::Error),
 
-    /// The socket path is too long for the platform.
+    /// The socket path is too long for the platform. On Linux and macOS,
+    /// longer paths are reached through their directory, so only a file
+    /// name too long for that fails.
     #[error("socket path too long ({len} bytes, max {max}): {path}")]
     PathTooLong {
         path: PathBuf,
@@ -33,9 +87,64 @@ pub enum TransportError {
         max: usize,
     },
 
+    /// The listener's socket path was removed or replaced while it was bound.
+    #[error("socket path lost: {path} no longer points at this listener")]
+    PathLost { path: PathBuf },
+
+    /// A message-oriented (SEQPACKET) stream could not send a message this
+    /// large in one packet. Nothing was sent.
+    #[error("message of {len} bytes is too large to send as one packet")]
+    MessageTooLarge { len: usize },
+
+    /// A TCP listener was asked to bind an address other than loopback
+    /// without opting out of the check.
+    #[error("refusing to bind non-loopback address {addr}: ipcprims transports are local-only")]
+    NonLoopback { addr: std::net::SocketAddr },
+
+    /// A descriptor handed to the transport is not a Unix stream or
+    /// seqpacket socket. Unix only.
+    #[error("fd {fd} is not a unix stream or seqpacket socket")]
+    NotUnixSocket { fd: i32 },
+
+    /// More file descriptors than one message may carry, or than a stream
+    /// holds unclaimed.
+    #[error("too many file descriptors ({count}, max {max})")]
+    TooManyFds { count: usize, max: usize },
+
+    /// Received ancillary data was cut short, so some passed file
+    /// descriptors were lost. The ones that arrived were closed.
+    #[error("file descriptor ancillary data truncated")]
+    FdsTruncated,
+
     /// The transport has been shut down.
     #[error("transport shut down")]
     Shutdown,
 }
 
+impl TransportError {
+    /// A failed connect to `path`: [`NotFound`](Self::NotFound) and
+    /// [`PermissionDenied`](Self::PermissionDenied) by kind, otherwise
+    /// [`Connect`](Self::Connect).
+    pub(crate) fn connect(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound { path },
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::Connect { path, source },
+        }
+    }
+}
+
+impl From<std::io::Error> for TransportError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted => {
+                Self::ConnectionReset
+            }
+            std::io::ErrorKind::BrokenPipe => Self::BrokenPipe,
+            _ => Self::Io(err),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, TransportError>;
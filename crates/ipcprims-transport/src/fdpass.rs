@@ -0,0 +1,252 @@
+//! Passing file descriptors over Unix stream sockets (`SCM_RIGHTS`).
+//!
+//! Descriptors travel as ancillary data on `sendmsg`/`recvmsg` and arrive
+//! with the first byte they were sent with. The receiving process gets new
+//! descriptors for the same open files.
+
+use std::collections::VecDeque;
+use std::io::{self, IoSlice};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use crate::error::{Result, TransportError};
+
+/// Most file descriptors sent or received with one message.
+///
+/// The receive buffer for ancillary data holds this many; a sender that
+/// packs more into one message is cut off with [`TransportError::FdsTruncated`].
+pub const MAX_FDS_PER_MESSAGE: usize = 16;
+
+/// Received descriptors a stream holds before they must be claimed.
+const MAX_QUEUED_FDS: usize = 4 * MAX_FDS_PER_MESSAGE;
+
+/// Descriptor-passing state of one [`IpcStream`](crate::IpcStream) handle.
+#[derive(Default)]
+pub(crate) struct FdState {
+    /// Sent with the next write.
+    outgoing: Vec<OwnedFd>,
+    /// Received by reads, oldest first; `None` while receiving is off and
+    /// reads use plain `read`, which makes the kernel close passed fds.
+    incoming: Option<VecDeque<OwnedFd>>,
+}
+
+impl FdState {
+    pub(crate) fn attach(&mut self, fds: &[BorrowedFd<'_>]) -> Result<()> {
+        check_count(self.outgoing.len() + fds.len())?;
+        for fd in fds {
+            self.outgoing.push(fd.try_clone_to_owned()?);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn detach(&mut self) -> Vec<OwnedFd> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    pub(crate) fn set_receiving(&mut self, enabled: bool) {
+        match (enabled, &self.incoming) {
+            (true, None) => self.incoming = Some(VecDeque::new()),
+            (false, _) => self.incoming = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    pub(crate) fn take_received(&mut self, count: usize) -> Option<Vec<OwnedFd>> {
+        let queue = self.incoming.as_mut()?;
+        if queue.len() < count {
+            return None;
+        }
+        Some(queue.drain(..count).collect())
+    }
+
+    /// Write `bufs`, carrying the attached descriptors with the first byte.
+    pub(crate) fn write(&mut self, fd: RawFd, bufs: &[IoSlice<'_>]) -> Option<io::Result<usize>> {
+        if self.outgoing.is_empty() {
+            return None;
+        }
+        let fds: Vec<BorrowedFd<'_>> = self.outgoing.iter().map(AsFd::as_fd).collect();
+        let result = send(fd, bufs, &fds);
+        if matches!(result, Ok(len) if len > 0) {
+            self.outgoing.clear();
+        }
+        Some(result)
+    }
+
+    /// Read into `buf`, queueing any descriptors that arrive with it.
+    pub(crate) fn read(&mut self, fd: RawFd, buf: &mut [u8]) -> Option<io::Result<usize>> {
+        let queue = self.incoming.as_mut()?;
+        let mut fds = Vec::new();
+        let result = recv(fd, buf, &mut fds);
+        queue.extend(fds);
+        if queue.len() > MAX_QUEUED_FDS {
+            let count = queue.len();
+            queue.clear();
+            return Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                TransportError::TooManyFds {
+                    count,
+                    max: MAX_QUEUED_FDS,
+                },
+            )));
+        }
+        Some(result)
+    }
+}
+
+pub(crate) fn check_count(count: usize) -> Result<()> {
+    if count > MAX_FDS_PER_MESSAGE {
+        return Err(TransportError::TooManyFds {
+            count,
+            max: MAX_FDS_PER_MESSAGE,
+        });
+    }
+    Ok(())
+}
+
+/// `sendmsg` with `fds` as `SCM_RIGHTS` ancillary data.
+pub(crate) fn send(fd: RawFd, bufs: &[IoSlice<'_>], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+    let fds_len = std::mem::size_of_val(fds) as libc::c_uint;
+    // SAFETY: CMSG_SPACE only computes a size.
+    let space = unsafe { libc::CMSG_SPACE(fds_len) } as usize;
+    // u64 words keep the buffer aligned for `cmsghdr`.
+    let mut control = vec![0u64; space.div_ceil(8)];
+
+    // SAFETY: all-zero is a valid `msghdr`.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    // `IoSlice` is ABI-compatible with `iovec`, and sendmsg does not write
+    // through `msg_iov`.
+    msg.msg_iov = bufs.as_ptr().cast_mut().cast();
+    msg.msg_iovlen = bufs.len() as _;
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = space as _;
+        // SAFETY: `control` holds CMSG_SPACE(fds_len) bytes, room for one
+        // header and the descriptors.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len) as _;
+            let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+            for (i, fd) in fds.iter().enumerate() {
+                data.add(i).write_unaligned(fd.as_raw_fd());
+            }
+        }
+    }
+
+    loop {
+        // SAFETY: `msg` points at live buffers for the whole call.
+        let rc = unsafe { libc::sendmsg(fd, &msg, SEND_FLAGS) };
+        if rc >= 0 {
+            return Ok(rc as usize);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// `recvmsg` into `buf`, appending any `SCM_RIGHTS` descriptors to `fds`.
+///
+/// If the sender packed more than [`MAX_FDS_PER_MESSAGE`] descriptors the
+/// kernel truncates the ancillary data; the descriptors that did arrive are
+/// closed and the read fails with [`TransportError::FdsTruncated`]. The
+/// bytes that came with them are lost, so the stream should be dropped.
+pub(crate) fn recv(fd: RawFd, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+    let max_len = (MAX_FDS_PER_MESSAGE * std::mem::size_of::<RawFd>()) as libc::c_uint;
+    // SAFETY: CMSG_SPACE only computes a size.
+    let space = unsafe { libc::CMSG_SPACE(max_len) } as usize;
+    let mut control = vec![0u64; space.div_ceil(8)];
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // SAFETY: all-zero is a valid `msghdr`.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &raw mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+
+    let len = loop {
+        // SAFETY: `msg` points at live, writable buffers for the whole call.
+        let rc = unsafe { libc::recvmsg(fd, &mut msg, RECV_FLAGS) };
+        if rc >= 0 {
+            break rc as usize;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    };
+
+    // Own every descriptor first, so none leaks whatever happens next.
+    let start = fds.len();
+    // SAFETY: the kernel filled `msg_control` with well-formed headers, and
+    // the descriptors in them are new and ours.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                let data_len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                for i in 0..data_len / std::mem::size_of::<RawFd>() {
+                    fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        fds.truncate(start);
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            TransportError::FdsTruncated,
+        ));
+    }
+    // Without MSG_CMSG_CLOEXEC there is a window in which a concurrent
+    // fork/exec can inherit these.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    for fd in &fds[start..] {
+        set_cloexec(fd.as_raw_fd())?;
+    }
+    Ok(len)
+}
+
+/// Unwrap a [`TransportError`] that an `io::Error` from this module carries.
+pub(crate) fn into_transport_error(err: io::Error) -> TransportError {
+    match err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<TransportError>())
+    {
+        Some(TransportError::FdsTruncated) => TransportError::FdsTruncated,
+        Some(&TransportError::TooManyFds { count, max }) => {
+            TransportError::TooManyFds { count, max }
+        }
+        _ => TransportError::Io(err),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_cloexec(fd: RawFd) -> io::Result<()> {
+    // SAFETY: F_GETFD/F_SETFD on a descriptor we own.
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const SEND_FLAGS: libc::c_int = 0;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: libc::c_int = 0;
@@ -3,25 +3,60 @@
 //! Provides a unified interface over different local transport mechanisms:
 //! - Unix domain sockets (Linux/macOS)
 //! - Named pipes (Windows)
+//! - Loopback TCP (`tcp` feature), where no filesystem is shared
+//! - Vsock between a VM and its host (`vsock` feature, Linux)
+//! - A child process's stdin/stdout pipes
+//! - An in-process memory pair, for tests
 //!
 //! This is the lowest layer of ipcprims. Everything else builds on top of
 //! the [`IpcStream`] type provided here.
 
+pub mod addr;
+pub mod capabilities;
+pub mod credentials;
 pub mod error;
+pub mod memory;
+pub mod retry;
 pub mod traits;
 
+#[cfg(unix)]
+mod fdpass;
+#[cfg(unix)]
+mod seqpacket;
+pub mod stdio;
+#[cfg(feature = "tcp")]
+pub mod tcp;
+mod trace;
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+pub mod vsock;
+
 #[cfg(windows)]
 pub mod npipes;
 #[cfg(unix)]
 pub mod uds;
 
+pub use addr::IpcAddr;
+pub use credentials::PeerCredentials;
 pub use error::{Result, TransportError};
-pub use traits::IpcStream;
+#[cfg(unix)]
+pub use fdpass::MAX_FDS_PER_MESSAGE;
+pub use memory::MemoryPairOptions;
+pub use retry::{connect_with_retry, RetryPolicy};
+pub use stdio::ChildStdioTransport;
+#[cfg(feature = "tcp")]
+pub use tcp::TcpTransport;
+#[cfg(unix)]
+pub use traits::INHERITED_FD_ENV;
+pub use traits::{IpcStream, Listener, SetTimeouts, TransportStream, TryClone};
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+pub use vsock::VsockTransport;
 
 #[cfg(windows)]
-pub use npipes::{NamedPipeListener, NamedPipeStream};
+pub use npipes::{NamedPipeListener, NamedPipeStream, PipeSecurity};
 #[cfg(unix)]
-pub use uds::UnixDomainSocket;
+pub use uds::{
+    BindOptions, ConnectOptions, Incoming, PathLostCallback, PathWatchdog, UnixDomainSocket,
+};
 
 #[cfg(all(windows, feature = "async"))]
 pub mod async_npipes;
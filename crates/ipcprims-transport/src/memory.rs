@@ -0,0 +1,480 @@
+//! An in-process duplex channel, for testing code that takes an
+//! [`IpcStream`] or a peer without binding sockets.
+//!
+//! Each direction is a byte queue behind a mutex and condvar. A stream and
+//! its clones count as one reader and one writer of their queues, so the
+//! other end reads EOF once every clone of this end is dropped (or after
+//! `shutdown(Write)`), and writes fail with `BrokenPipe` once nobody can
+//! read them. On Unix each end also owns a self-pipe that is readable while
+//! a read would not block, so `as_fd` can be polled like a socket.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::traits::IpcStream;
+
+/// Simulated conditions for [`IpcStream::memory_pair_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryPairOptions {
+    /// Delay before written bytes become readable on the other end.
+    pub latency: Duration,
+    /// Fail every Nth read and every Nth write call with `WouldBlock`
+    /// before doing anything, to exercise non-blocking callers. `0` never
+    /// does.
+    pub would_block_every: u32,
+}
+
+/// One direction of a memory pair.
+struct Queue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    #[cfg(unix)]
+    ready: ReadySignal,
+}
+
+#[derive(Default)]
+struct QueueState {
+    chunks: VecDeque<Chunk>,
+    /// Stream handles that can read from this queue.
+    readers: usize,
+    /// Stream handles that can write to this queue.
+    writers: usize,
+    /// `shutdown(Write)` on the writing end.
+    write_shut: bool,
+    /// `shutdown(Read)` on the reading end.
+    read_shut: bool,
+}
+
+struct Chunk {
+    data: Vec<u8>,
+    pos: usize,
+    ready_at: Instant,
+}
+
+impl QueueState {
+    fn at_eof(&self) -> bool {
+        self.chunks.is_empty() && (self.writers == 0 || self.write_shut)
+    }
+
+    /// Whether a read would return without waiting for a writer.
+    #[cfg(unix)]
+    fn readable(&self) -> bool {
+        !self.chunks.is_empty() || self.at_eof() || self.read_shut
+    }
+}
+
+impl Queue {
+    fn new() -> io::Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            state: Mutex::new(QueueState {
+                readers: 1,
+                writers: 1,
+                ..QueueState::default()
+            }),
+            changed: Condvar::new(),
+            #[cfg(unix)]
+            ready: ReadySignal::new()?,
+        }))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wake waiters after `state` changed.
+    fn notify(&self, state: &QueueState) {
+        #[cfg(unix)]
+        self.ready.set(state.readable());
+        #[cfg(not(unix))]
+        let _ = state;
+        self.changed.notify_all();
+    }
+}
+
+/// Our end of a memory pair: reads `rx`, writes `tx`.
+pub(crate) struct MemoryStream {
+    rx: Arc<Queue>,
+    tx: Arc<Queue>,
+    options: MemoryPairOptions,
+    /// Shared by clones, like a socket's `O_NONBLOCK`.
+    nonblocking: Arc<AtomicBool>,
+    read_timeout: Mutex<Option<Duration>>,
+    calls: AtomicU32,
+}
+
+impl MemoryStream {
+    pub(crate) fn pair(options: MemoryPairOptions) -> io::Result<(Self, Self)> {
+        let (a_to_b, b_to_a) = (Queue::new()?, Queue::new()?);
+        let end = |rx: &Arc<Queue>, tx: &Arc<Queue>| Self {
+            rx: Arc::clone(rx),
+            tx: Arc::clone(tx),
+            options: options.clone(),
+            nonblocking: Arc::new(AtomicBool::new(false)),
+            read_timeout: Mutex::new(None),
+            calls: AtomicU32::new(0),
+        };
+        Ok((end(&b_to_a, &a_to_b), end(&a_to_b, &b_to_a)))
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        self.rx.lock().readers += 1;
+        self.tx.lock().writers += 1;
+        Ok(Self {
+            rx: Arc::clone(&self.rx),
+            tx: Arc::clone(&self.tx),
+            options: self.options.clone(),
+            nonblocking: Arc::clone(&self.nonblocking),
+            read_timeout: Mutex::new(*self.read_timeout.lock().unwrap_or_else(|e| e.into_inner())),
+            calls: AtomicU32::new(0),
+        })
+    }
+
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        *self.read_timeout.lock().unwrap_or_else(|e| e.into_inner()) = timeout;
+        Ok(())
+    }
+
+    /// Writes never wait: the queue is unbounded.
+    pub(crate) fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    pub(crate) fn shutdown(&self, how: std::net::Shutdown) {
+        use std::net::Shutdown;
+
+        if matches!(how, Shutdown::Read | Shutdown::Both) {
+            let mut state = self.rx.lock();
+            state.read_shut = true;
+            state.chunks.clear();
+            self.rx.notify(&state);
+        }
+        if matches!(how, Shutdown::Write | Shutdown::Both) {
+            let mut state = self.tx.lock();
+            state.write_shut = true;
+            self.tx.notify(&state);
+        }
+    }
+
+    /// The self-pipe that is readable while a read would not block.
+    #[cfg(unix)]
+    pub(crate) fn ready_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        use std::os::fd::AsFd;
+
+        self.rx.ready.read.as_fd()
+    }
+
+    fn simulate_would_block(&self) -> io::Result<()> {
+        let every = self.options.would_block_every;
+        if every > 0 && self.calls.fetch_add(1, Ordering::Relaxed) % every == every - 1 {
+            return Err(io::Error::from(io::ErrorKind::WouldBlock));
+        }
+        Ok(())
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.simulate_would_block()?;
+        let timeout = *self.read_timeout.lock().unwrap_or_else(|e| e.into_inner());
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.rx.lock();
+        loop {
+            if state.read_shut || buf.is_empty() {
+                return Ok(0);
+            }
+            let now = Instant::now();
+            // Wake for the next delayed chunk, or for the read deadline.
+            let mut wake_at = deadline;
+            if let Some(chunk) = state.chunks.front_mut() {
+                if chunk.ready_at <= now {
+                    let n = buf.len().min(chunk.data.len() - chunk.pos);
+                    buf[..n].copy_from_slice(&chunk.data[chunk.pos..chunk.pos + n]);
+                    chunk.pos += n;
+                    if chunk.pos == chunk.data.len() {
+                        state.chunks.pop_front();
+                    }
+                    self.rx.notify(&state);
+                    return Ok(n);
+                }
+                wake_at = Some(wake_at.map_or(chunk.ready_at, |at| at.min(chunk.ready_at)));
+            } else if state.at_eof() {
+                return Ok(0);
+            }
+
+            if self.nonblocking.load(Ordering::Relaxed) {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            if deadline.is_some_and(|deadline| deadline <= now) {
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+            state = match wake_at {
+                Some(at) => {
+                    self.rx
+                        .changed
+                        .wait_timeout(state, at - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self
+                    .rx
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.simulate_would_block()?;
+        let mut state = self.tx.lock();
+        if state.write_shut || state.read_shut || state.readers == 0 {
+            return Err(io::Error::from(io::ErrorKind::BrokenPipe));
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        state.chunks.push_back(Chunk {
+            data: buf.to_vec(),
+            pos: 0,
+            ready_at: Instant::now() + self.options.latency,
+        });
+        self.tx.notify(&state);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MemoryStream {
+    fn drop(&mut self) {
+        let mut state = self.rx.lock();
+        state.readers -= 1;
+        self.rx.notify(&state);
+        drop(state);
+
+        let mut state = self.tx.lock();
+        state.writers -= 1;
+        self.tx.notify(&state);
+    }
+}
+
+/// A non-blocking pipe holding one byte while its queue is readable, so
+/// `poll` on the read end reports what a read would do.
+#[cfg(unix)]
+struct ReadySignal {
+    read: std::os::fd::OwnedFd,
+    write: std::os::fd::OwnedFd,
+    set: AtomicBool,
+}
+
+#[cfg(unix)]
+impl ReadySignal {
+    fn new() -> io::Result<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let mut fds = [0; 2];
+        // SAFETY: `fds` is a writable array of two descriptors.
+        crate::seqpacket::cvt(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+        // SAFETY: `pipe` just returned both descriptors; nothing else owns them.
+        let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        for fd in [read.as_raw_fd(), write.as_raw_fd()] {
+            // SAFETY: `fd` is open and owned here.
+            crate::seqpacket::cvt(unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) })?;
+            // SAFETY: as above.
+            crate::seqpacket::cvt(unsafe { libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) })?;
+        }
+        Ok(Self {
+            read,
+            write,
+            set: AtomicBool::new(false),
+        })
+    }
+
+    /// Called with the queue locked, so set and clear do not interleave.
+    fn set(&self, readable: bool) {
+        use std::os::fd::AsRawFd;
+
+        if self.set.swap(readable, Ordering::Relaxed) == readable {
+            return;
+        }
+        let mut byte = 0u8;
+        // SAFETY: both descriptors are open; the one-byte buffer is valid.
+        // A full or empty non-blocking pipe only fails with EAGAIN, which
+        // leaves it in the state asked for anyway.
+        unsafe {
+            if readable {
+                libc::write(self.write.as_raw_fd(), (&raw const byte).cast(), 1);
+            } else {
+                libc::read(self.read.as_raw_fd(), (&raw mut byte).cast(), 1);
+            }
+        }
+    }
+}
+
+impl IpcStream {
+    /// A connected pair of in-process streams, for tests that need an
+    /// [`IpcStream`] without binding a socket.
+    ///
+    /// Reads block until the other end writes (or the read timeout
+    /// passes, failing with `TimedOut`); dropping every clone of one end
+    /// gives the other EOF, and `shutdown` works as on a socket. Writes
+    /// never block. There are no descriptors to pass, and on Unix `as_fd`
+    /// is a pipe that polls readable while a read would not block.
+    pub fn memory_pair() -> crate::Result<(Self, Self)> {
+        Self::memory_pair_with(MemoryPairOptions::default())
+    }
+
+    /// [`memory_pair`](Self::memory_pair) with simulated latency or
+    /// spurious `WouldBlock`s. With latency, `as_fd` polls readable as soon
+    /// as bytes are queued, before they are due.
+    pub fn memory_pair_with(options: MemoryPairOptions) -> crate::Result<(Self, Self)> {
+        let (a, b) = MemoryStream::pair(options)?;
+        Ok((Self::from_memory(a), Self::from_memory(b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Shutdown;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn dropping_every_clone_of_one_end_gives_the_other_eof() {
+        let (mut a, b) = IpcStream::memory_pair().unwrap();
+        let b_clone = b.try_clone().unwrap();
+        let mut b_writer = b_clone.try_clone().unwrap();
+        b_writer.write_all(b"last").unwrap();
+        drop(b);
+        drop(b_clone);
+
+        let mut buf = [0u8; 4];
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"last");
+        drop(b_writer);
+        assert_eq!(a.read(&mut buf).unwrap(), 0);
+        assert_eq!(a.write(b"x").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn ends_work_across_threads() {
+        let (mut a, mut b) = IpcStream::memory_pair().unwrap();
+        let echo = thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = b.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                b.write_all(&buf[..n]).unwrap();
+            }
+        });
+
+        let mut reader = a.try_clone().unwrap();
+        reader
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let payload: Vec<u8> = (0..=255).cycle().take(64 * 1024).collect();
+        a.write_all(&payload).unwrap();
+        a.shutdown(Shutdown::Write).unwrap();
+        let mut echoed = Vec::new();
+        reader.read_to_end(&mut echoed).unwrap();
+        assert_eq!(echoed, payload);
+        echo.join().unwrap();
+    }
+
+    #[test]
+    fn read_timeout_latency_and_simulated_would_block() {
+        let (mut a, mut b) = IpcStream::memory_pair_with(MemoryPairOptions {
+            latency: Duration::from_millis(50),
+            would_block_every: 0,
+        })
+        .unwrap();
+        a.set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+        let mut buf = [0u8; 2];
+        assert_eq!(
+            a.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+
+        let start = Instant::now();
+        b.write_all(b"hi").unwrap();
+        a.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        a.read_exact(&mut buf).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let (mut a, mut b) = IpcStream::memory_pair_with(MemoryPairOptions {
+            would_block_every: 2,
+            ..MemoryPairOptions::default()
+        })
+        .unwrap();
+        b.write_all(b"ok").unwrap();
+        assert_eq!(a.read(&mut buf[..1]).unwrap(), 1);
+        assert_eq!(
+            a.read(&mut buf[1..]).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        assert_eq!(a.read(&mut buf[1..]).unwrap(), 1);
+        assert_eq!(&buf, b"ok");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn nonblocking_reads_and_the_ready_fd_track_the_queue() {
+        use std::os::fd::AsRawFd;
+
+        let poll_readable = |stream: &IpcStream| {
+            let mut pollfd = libc::pollfd {
+                fd: stream.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` is a valid array of length 1.
+            let ready = unsafe { libc::poll(&mut pollfd, 1, 0) };
+            ready == 1
+        };
+
+        let (mut a, mut b) = IpcStream::memory_pair().unwrap();
+        a.set_nonblocking(true).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            a.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        assert!(!poll_readable(&a));
+
+        b.write_all(b"ping").unwrap();
+        assert!(poll_readable(&a));
+        assert_eq!(a.read(&mut buf).unwrap(), 4);
+        assert!(!poll_readable(&a));
+
+        drop(b);
+        assert!(poll_readable(&a));
+        assert_eq!(a.read(&mut buf).unwrap(), 0);
+    }
+}
@@ -7,22 +7,27 @@ use std::os::windows::io::{AsRawHandle, FromRawHandle, RawHandle};
 #[cfg(windows)]
 use std::path::{Path, PathBuf};
 #[cfg(windows)]
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 #[cfg(windows)]
-use tracing::debug;
+use crate::trace::debug;
 #[cfg(windows)]
 use windows_sys::core::BOOL;
 #[cfg(windows)]
 use windows_sys::Win32::Foundation::{
-    CloseHandle, GetLastError, ERROR_FILE_NOT_FOUND, ERROR_IO_PENDING, ERROR_PIPE_BUSY,
-    ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE, WAIT_OBJECT_0, WAIT_TIMEOUT,
+    CloseHandle, GetLastError, LocalFree, ERROR_FILE_NOT_FOUND, ERROR_IO_PENDING,
+    ERROR_OPERATION_ABORTED, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE,
+    WAIT_OBJECT_0, WAIT_TIMEOUT,
+};
+#[cfg(windows)]
+use windows_sys::Win32::Security::Authorization::{
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
 };
 #[cfg(windows)]
 use windows_sys::Win32::Security::{
     AddAccessAllowedAce, GetLengthSid, InitializeAcl, InitializeSecurityDescriptor,
-    SetSecurityDescriptorDacl, ACL, ACL_REVISION, SECURITY_ATTRIBUTES, SECURITY_DESCRIPTOR,
-    TOKEN_QUERY, TOKEN_USER,
+    SetSecurityDescriptorDacl, ACL, ACL_REVISION, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES,
+    SECURITY_DESCRIPTOR, TOKEN_QUERY, TOKEN_USER,
 };
 #[cfg(windows)]
 use windows_sys::Win32::Storage::FileSystem::{
@@ -208,6 +213,65 @@ fn write_file_with_timeout(handle: HANDLE, buf: &[u8], timeout_ms: u32) -> std::
     let transferred = wait_for_overlapped_result(handle, &mut overlapped, event.raw(), timeout_ms)?;
     Ok(transferred as usize)
 }
+/// Wait up to `timeout_ms` for a client to open the pipe instance `handle`.
+///
+/// Returns `Ok(false)` on timeout. A client that connects while the wait is
+/// being cancelled is kept.
+#[cfg(windows)]
+fn connect_pipe_instance(handle: HANDLE, timeout_ms: u32) -> std::io::Result<bool> {
+    let event = EventHandle::create_manual_reset()?;
+    // SAFETY: zero-initialized OVERLAPPED is valid; hEvent set to owned event.
+    let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+    overlapped.hEvent = event.raw();
+
+    // SAFETY: handle is a valid pipe instance; overlapped and its event
+    // outlive the operation, which is waited for or cancelled below.
+    let connected = unsafe { ConnectNamedPipe(handle, &mut overlapped) };
+    if connected != 0 {
+        return Ok(true);
+    }
+    // SAFETY: GetLastError has no preconditions.
+    match unsafe { GetLastError() } {
+        ERROR_PIPE_CONNECTED => return Ok(true),
+        ERROR_IO_PENDING => {}
+        code => return Err(std::io::Error::from_raw_os_error(code as i32)),
+    }
+
+    // SAFETY: event is owned and valid for the duration of the wait.
+    let wait = unsafe { WaitForSingleObject(event.raw(), timeout_ms) };
+    if wait == WAIT_TIMEOUT {
+        // SAFETY: handle and overlapped refer to this in-flight operation.
+        unsafe {
+            CancelIoEx(handle, &overlapped);
+        }
+    } else if wait != WAIT_OBJECT_0 {
+        // SAFETY: GetLastError has no preconditions.
+        let code = unsafe { GetLastError() };
+        // SAFETY: as above; the operation must finish before overlapped is dropped.
+        unsafe {
+            CancelIoEx(handle, &overlapped);
+        }
+        let mut _discarded = 0u32;
+        // SAFETY: waits for the cancelled operation to complete.
+        let _ = unsafe { GetOverlappedResult(handle, &overlapped, &mut _discarded, 1) };
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    let mut _transferred = 0u32;
+    // SAFETY: overlapped corresponds to this operation; bWait=1 blocks until
+    // a cancellation has completed.
+    let ok = unsafe { GetOverlappedResult(handle, &overlapped, &mut _transferred, 1) };
+    if ok != 0 {
+        return Ok(true);
+    }
+    // SAFETY: GetLastError has no preconditions.
+    let code = unsafe { GetLastError() };
+    if wait == WAIT_TIMEOUT && code == ERROR_OPERATION_ABORTED {
+        return Ok(false);
+    }
+    Err(std::io::Error::from_raw_os_error(code as i32))
+}
+
 #[cfg(windows)]
 use windows_sys::Win32::System::Pipes::{
     CreateNamedPipeW, WaitNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
@@ -224,7 +288,7 @@ unsafe extern "system" {
 }
 
 use crate::error::{Result, TransportError};
-use crate::traits::IpcStream;
+use crate::traits::{IpcStream, Listener};
 
 #[cfg(windows)]
 fn to_wide_null(s: &str) -> Vec<u16> {
@@ -363,6 +427,88 @@ impl OwnerOnlySecurityDescriptor {
     }
 }
 
+/// Owns a security descriptor parsed from SDDL, freed with `LocalFree`.
+#[cfg(windows)]
+struct SddlSecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+#[cfg(windows)]
+impl SddlSecurityDescriptor {
+    fn new(sddl: &str) -> std::io::Result<Self> {
+        let wide = to_wide_null(sddl);
+        let mut sd: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+        // SAFETY: wide is NUL-terminated; sd receives a LocalAlloc'd
+        // descriptor owned by the returned value.
+        let ok = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                wide.as_ptr(),
+                SDDL_REVISION_1,
+                &mut sd,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self(sd))
+    }
+}
+
+#[cfg(windows)]
+impl Drop for SddlSecurityDescriptor {
+    fn drop(&mut self) {
+        // SAFETY: self.0 was allocated by ConvertStringSecurityDescriptorToSecurityDescriptorW.
+        unsafe {
+            LocalFree(self.0);
+        }
+    }
+}
+
+/// Who may open the pipe instances a [`NamedPipeListener`] creates.
+///
+/// The Windows counterpart of the socket mode passed to
+/// `UnixDomainSocket::bind_with_mode`.
+#[cfg(windows)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PipeSecurity {
+    /// Only the user running the listener (the default), like Unix `0o600`.
+    #[default]
+    OwnerOnly,
+    /// A security descriptor in SDDL form, e.g. `"D:(A;;GA;;;AU)"` to admit
+    /// every authenticated user. Checked when the listener is bound.
+    Sddl(String),
+}
+
+/// A security descriptor built from a [`PipeSecurity`].
+#[cfg(windows)]
+enum PipeSecurityDescriptor {
+    OwnerOnly(OwnerOnlySecurityDescriptor),
+    Sddl(SddlSecurityDescriptor),
+}
+
+#[cfg(windows)]
+impl PipeSecurityDescriptor {
+    fn new(security: &PipeSecurity) -> std::io::Result<Self> {
+        match security {
+            PipeSecurity::OwnerOnly => OwnerOnlySecurityDescriptor::new().map(Self::OwnerOnly),
+            PipeSecurity::Sddl(sddl) => SddlSecurityDescriptor::new(sddl).map(Self::Sddl),
+        }
+    }
+
+    /// Build a `SECURITY_ATTRIBUTES` referencing this descriptor, which must
+    /// outlive any use of it.
+    fn security_attributes(&mut self) -> SECURITY_ATTRIBUTES {
+        match self {
+            Self::OwnerOnly(sd) => sd.security_attributes(),
+            Self::Sddl(sd) => SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: sd.0,
+                bInheritHandle: 0,
+            },
+        }
+    }
+}
+
 /// Windows named-pipe stream.
 #[cfg(windows)]
 pub struct NamedPipeStream {
@@ -394,7 +540,7 @@ impl NamedPipeStream {
             };
 
             if handle != INVALID_HANDLE_VALUE {
-                debug!(pipe = %pipe_name, "connected to named pipe");
+                debug!(transport = "named_pipe", path = %pipe_name, "connected to named pipe");
                 // SAFETY: handle was returned by CreateFileW and is owned here.
                 let file = unsafe { std::fs::File::from_raw_handle(handle as RawHandle) };
                 return Ok(Self {
@@ -412,10 +558,10 @@ impl NamedPipeStream {
             }
 
             if code != ERROR_PIPE_BUSY {
-                return Err(TransportError::Connect {
-                    path: PathBuf::from(pipe_name),
-                    source: std::io::Error::from_raw_os_error(code as i32),
-                });
+                return Err(TransportError::connect(
+                    pipe_name,
+                    std::io::Error::from_raw_os_error(code as i32),
+                ));
             }
 
             // SAFETY: wide is NUL-terminated; timeout is bounded.
@@ -499,24 +645,75 @@ impl Write for NamedPipeStream {
 #[cfg(windows)]
 pub struct NamedPipeListener {
     pipe_name: String,
+    security: PipeSecurity,
+    shut_down: AtomicBool,
 }
 
 #[cfg(windows)]
 impl NamedPipeListener {
+    /// Listen on a named pipe that only the current user can open.
     pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_with_security(path, PipeSecurity::default())
+    }
+
+    /// Listen on a named pipe whose instances carry `security`.
+    ///
+    /// An SDDL string Windows cannot parse fails here with
+    /// [`TransportError::Bind`] rather than on the first `accept`.
+    pub fn bind_with_security(path: impl AsRef<Path>, security: PipeSecurity) -> Result<Self> {
         let pipe_name = normalize_pipe_name(path.as_ref());
-        Ok(Self { pipe_name })
+        PipeSecurityDescriptor::new(&security).map_err(|err| TransportError::Bind {
+            path: PathBuf::from(&pipe_name),
+            source: err,
+        })?;
+        Ok(Self {
+            pipe_name,
+            security,
+            shut_down: AtomicBool::new(false),
+        })
+    }
+
+    /// Stop accepting: a blocked `accept` returns
+    /// [`TransportError::Shutdown`], as does every later one.
+    pub fn shutdown(&self) {
+        if self.shut_down.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        debug!(transport = "named_pipe", path = %self.pipe_name, "listener shut down");
+        // Wake a blocked accept with a throwaway connection.
+        let _ = NamedPipeStream::connect(&self.pipe_name);
     }
 
     pub fn accept(&self) -> Result<IpcStream> {
+        loop {
+            if let Some(stream) = self.accept_within(INFINITE)? {
+                return Ok(stream);
+            }
+        }
+    }
+
+    /// Accept an incoming connection, waiting at most `timeout`.
+    ///
+    /// Returns `Ok(None)` if no client connected in time. The pipe instance
+    /// offered for the wait is closed again, so a client racing the
+    /// deadline retries against the next one.
+    pub fn accept_timeout(&self, timeout: std::time::Duration) -> Result<Option<IpcStream>> {
+        self.accept_within(duration_to_timeout_ms(Some(timeout)))
+    }
+
+    fn accept_within(&self, timeout_ms: u32) -> Result<Option<IpcStream>> {
+        if self.shut_down.load(Ordering::Acquire) {
+            return Err(TransportError::Shutdown);
+        }
         let wide = to_wide_null(&self.pipe_name);
 
-        // Build an owner-only DACL so only the creating user can open the pipe.
-        // This is the Windows equivalent of Unix 0o600 permissions.
-        let mut sd = OwnerOnlySecurityDescriptor::new().map_err(|err| TransportError::Bind {
-            path: PathBuf::from(&self.pipe_name),
-            source: err,
-        })?;
+        // By default an owner-only DACL, so only the creating user can open
+        // the pipe: the Windows equivalent of Unix 0o600 permissions.
+        let mut sd =
+            PipeSecurityDescriptor::new(&self.security).map_err(|err| TransportError::Bind {
+                path: PathBuf::from(&self.pipe_name),
+                source: err,
+            })?;
         let sa = sd.security_attributes();
 
         // SAFETY: wide is NUL-terminated and valid for call duration.
@@ -543,30 +740,23 @@ impl NamedPipeListener {
             });
         }
 
-        // SAFETY: handle is valid if we reached here.
-        let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) };
-        if connected == 0 {
-            // SAFETY: GetLastError has no preconditions.
-            let code = unsafe { GetLastError() };
-            if code != ERROR_PIPE_CONNECTED {
-                // SAFETY: handle is valid and owned here.
-                unsafe {
-                    CloseHandle(handle);
-                }
-                return Err(TransportError::Accept(std::io::Error::from_raw_os_error(
-                    code as i32,
-                )));
-            }
+        // SAFETY: handle was returned by CreateNamedPipeW and is owned here;
+        // the File closes it on every early return below.
+        let file = unsafe { std::fs::File::from_raw_handle(handle as RawHandle) };
+        if !connect_pipe_instance(handle, timeout_ms).map_err(TransportError::Accept)? {
+            return Ok(None);
         }
 
-        // SAFETY: handle is connected and ownership is transferred to File.
-        let file = unsafe { std::fs::File::from_raw_handle(handle as RawHandle) };
         let stream = NamedPipeStream {
             file,
             read_timeout_ms: AtomicU32::new(INFINITE),
             write_timeout_ms: AtomicU32::new(INFINITE),
         };
-        Ok(IpcStream::from_named_pipe(stream))
+        // The connection may be the wake-up from `shutdown`.
+        if self.shut_down.load(Ordering::Acquire) {
+            return Err(TransportError::Shutdown);
+        }
+        Ok(Some(IpcStream::from_named_pipe(stream)))
     }
 
     pub fn path(&self) -> &Path {
@@ -574,6 +764,62 @@ impl NamedPipeListener {
     }
 }
 
+impl Listener for NamedPipeListener {
+    type Stream = IpcStream;
+
+    fn accept(&self) -> Result<IpcStream> {
+        NamedPipeListener::accept(self)
+    }
+
+    fn accept_timeout(&self, timeout: std::time::Duration) -> Result<Option<IpcStream>> {
+        NamedPipeListener::accept_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) {
+        NamedPipeListener::shutdown(self);
+    }
+
+    fn path(&self) -> &Path {
+        NamedPipeListener::path(self)
+    }
+}
+
+/// Create one owner-only pipe instance and close it straight away.
+///
+/// Used by capability probes: nothing is left behind once this returns.
+#[cfg(windows)]
+pub(crate) fn probe_pipe_instance(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let wide = to_wide_null(&normalize_pipe_name(path.as_ref()));
+    let mut sd = OwnerOnlySecurityDescriptor::new()?;
+    let sa = sd.security_attributes();
+
+    // SAFETY: wide is NUL-terminated and valid for call duration.
+    // sa borrows sd which is alive for the duration of this call.
+    let handle: HANDLE = unsafe {
+        CreateNamedPipeW(
+            wide.as_ptr(),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_OVERLAPPED,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            0,
+            0,
+            0,
+            &sa,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        // SAFETY: GetLastError has no preconditions.
+        let code = unsafe { GetLastError() };
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    // SAFETY: handle was returned by CreateNamedPipeW and is owned here.
+    unsafe {
+        CloseHandle(handle);
+    }
+    Ok(())
+}
+
 #[cfg(all(test, windows))]
 mod tests {
     use std::sync::atomic::Ordering;
@@ -762,4 +1008,40 @@ mod tests {
             "ACE should grant GENERIC_ALL"
         );
     }
+
+    #[test]
+    fn bind_with_security_rejects_invalid_sddl() {
+        let pipe = make_pipe_name("bad-sddl");
+        let err = NamedPipeListener::bind_with_security(
+            &pipe,
+            PipeSecurity::Sddl("not an sddl string".to_string()),
+        )
+        .err()
+        .expect("invalid SDDL should fail to bind");
+        assert!(matches!(err, TransportError::Bind { .. }), "{err}");
+    }
+
+    #[test]
+    fn sddl_security_accepts_connections() {
+        let pipe = make_pipe_name("sddl");
+        let listener = NamedPipeListener::bind_with_security(
+            &pipe,
+            PipeSecurity::Sddl("D:(A;;GA;;;AU)".to_string()),
+        )
+        .expect("listener should bind");
+
+        let server = thread::spawn(move || {
+            let mut stream = listener.accept().expect("listener should accept");
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).expect("server should read");
+            stream.write_all(&buf).expect("server should echo");
+        });
+
+        let mut stream = NamedPipeStream::connect(&pipe).expect("client should connect");
+        stream.write_all(b"ping").expect("client should write");
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).expect("client should read");
+        assert_eq!(&buf, b"ping");
+        server.join().expect("server thread should complete");
+    }
 }
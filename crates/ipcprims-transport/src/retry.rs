@@ -0,0 +1,190 @@
+//! Retrying a connect while the listener comes up.
+
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, TransportError};
+#[cfg(windows)]
+use crate::npipes::NamedPipeStream;
+#[cfg(feature = "tcp")]
+use crate::tcp::TcpTransport;
+use crate::trace::debug;
+use crate::traits::IpcStream;
+#[cfg(unix)]
+use crate::uds::UnixDomainSocket;
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+use crate::vsock::VsockTransport;
+
+/// How [`connect_with_retry`] retries a connect that failed because the
+/// listener is not up yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total time to keep trying, including the connect attempts themselves.
+    pub max_duration: Duration,
+    /// Sleep after the first failed attempt; doubled after each further one.
+    pub initial_backoff: Duration,
+    /// Upper bound on the sleep between attempts.
+    pub max_backoff: Duration,
+    /// Connect failures worth retrying. Anything else fails at once.
+    pub retryable: Vec<io::ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::from_secs(5),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(250),
+            retryable: vec![io::ErrorKind::NotFound, io::ErrorKind::ConnectionRefused],
+        }
+    }
+}
+
+/// Connect to `path`, retrying per `policy` until a listener accepts.
+///
+/// `path` is resolved like a peer endpoint: a socket path (or `@name` on
+/// Linux) on Unix, a pipe name on Windows, `tcp://host:port` with the
+/// `tcp` feature and `vsock://cid:port` with the `vsock` feature. A Unix socket path attempt is bounded by the time left,
+/// so a listener with a full backlog cannot hold it past `max_duration`.
+///
+/// A failure whose kind is not in `policy.retryable` is returned as is.
+/// Running out of time fails with [`TransportError::ConnectTimeout`]
+/// carrying the last attempt's error.
+pub fn connect_with_retry(path: impl AsRef<Path>, policy: &RetryPolicy) -> Result<IpcStream> {
+    let path = path.as_ref();
+    let start = Instant::now();
+    let mut backoff = policy.initial_backoff;
+    loop {
+        let remaining = policy.max_duration.saturating_sub(start.elapsed());
+        let (endpoint, source) = match connect_once(path, remaining) {
+            Ok(stream) => return Ok(stream),
+            Err(TransportError::Connect { path, source }) => (path, source),
+            Err(TransportError::NotFound { path }) => (path, io::ErrorKind::NotFound.into()),
+            Err(TransportError::PermissionDenied { path }) => {
+                (path, io::ErrorKind::PermissionDenied.into())
+            }
+            Err(err) => return Err(err),
+        };
+
+        let retryable = policy.retryable.contains(&source.kind());
+        if !retryable && source.kind() != io::ErrorKind::TimedOut {
+            return Err(TransportError::connect(endpoint, source));
+        }
+        let elapsed = start.elapsed();
+        if !retryable || elapsed >= policy.max_duration {
+            return Err(TransportError::ConnectTimeout {
+                path: endpoint,
+                waited: policy.max_duration,
+                source,
+            });
+        }
+
+        debug!(?path, ?backoff, error = %source, "connect failed, retrying");
+        std::thread::sleep(backoff.min(policy.max_duration - elapsed));
+        backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+    }
+}
+
+/// One connect attempt, bounded by `remaining` where the transport allows.
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn connect_once(path: &Path, remaining: Duration) -> Result<IpcStream> {
+    #[cfg(feature = "tcp")]
+    if let Some(addr) = TcpTransport::endpoint_addr(path) {
+        return TcpTransport::connect(addr);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    if let Some((cid, port)) = VsockTransport::endpoint_addr(path) {
+        return VsockTransport::connect(cid, port);
+    }
+
+    #[cfg(unix)]
+    {
+        match UnixDomainSocket::abstract_name(path) {
+            Some(name) => UnixDomainSocket::connect_abstract(name),
+            None => {
+                UnixDomainSocket::connect_timeout(path, remaining.max(Duration::from_millis(1)))
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        NamedPipeStream::connect(path)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn temp_sock(tag: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ipcprims-retry-{tag}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        dir.join("retry.sock")
+    }
+
+    #[test]
+    fn connect_with_retry_waits_for_a_late_listener() {
+        let path = temp_sock("late");
+        let bind_path = path.clone();
+        let server = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let socket = UnixDomainSocket::bind(&bind_path).expect("bind should succeed");
+            socket.accept().expect("accept should succeed")
+        });
+
+        let policy = RetryPolicy {
+            max_duration: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        };
+        let client = connect_with_retry(&path, &policy).expect("connect should succeed");
+        let _server_end = server.join().expect("server thread should finish");
+        drop(client);
+
+        let _ = std::fs::remove_dir_all(path.parent().expect("socket has a parent"));
+    }
+
+    #[test]
+    fn connect_with_retry_wraps_the_last_error_when_time_runs_out() {
+        let path = temp_sock("missing");
+        let policy = RetryPolicy {
+            max_duration: Duration::from_millis(100),
+            ..RetryPolicy::default()
+        };
+
+        let start = Instant::now();
+        let err = connect_with_retry(&path, &policy).expect_err("missing socket should time out");
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        match err {
+            TransportError::ConnectTimeout { waited, source, .. } => {
+                assert_eq!(waited, Duration::from_millis(100));
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("expected ConnectTimeout, got {other:?}"),
+        }
+
+        // Not retryable under this policy: fails on the first attempt.
+        let policy = RetryPolicy {
+            retryable: vec![io::ErrorKind::ConnectionRefused],
+            ..policy
+        };
+        let err = connect_with_retry(&path, &policy).expect_err("missing socket should fail");
+        assert!(
+            matches!(&err, TransportError::NotFound { path: missing } if *missing == path),
+            "{err:?}"
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().expect("socket has a parent"));
+    }
+}
@@ -0,0 +1,220 @@
+//! `SOCK_SEQPACKET` Unix domain sockets: connection-oriented like a stream,
+//! but the kernel keeps every write a separate message.
+//!
+//! std has no SEQPACKET constructors, so sockets are created here with libc
+//! and then handed to `UnixListener`/`UnixStream`, whose accept, timeout and
+//! credential calls work the same on any Unix socket type.
+
+use std::io::{self, IoSlice, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+
+use crate::error::TransportError;
+
+/// Pending connections the kernel queues before `accept`.
+const BACKLOG: libc::c_int = 128;
+
+/// A connected SEQPACKET socket read as a byte stream.
+///
+/// A read shorter than the next message would make the kernel discard the
+/// rest of it, so such a message is received whole into `pending` and
+/// handed out over the following reads. Reads never span two messages.
+pub(crate) struct SeqPacketStream {
+    stream: UnixStream,
+    pending: Vec<u8>,
+    pos: usize,
+}
+
+impl SeqPacketStream {
+    pub(crate) fn new(stream: UnixStream) -> Self {
+        Self {
+            stream,
+            pending: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &UnixStream {
+        &self.stream
+    }
+
+    /// A second handle on the socket. Bytes of a message this handle has
+    /// already received stay with it.
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        self.stream.try_clone().map(Self::new)
+    }
+
+    /// Length of the next message, without consuming it.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn next_message_len(&self) -> io::Result<usize> {
+        // With MSG_TRUNC, Linux reports the full message length even though
+        // the buffer is empty.
+        recv(
+            self.stream.as_raw_fd(),
+            &mut [],
+            libc::MSG_PEEK | libc::MSG_TRUNC,
+        )
+    }
+
+    /// Length of the next message, without consuming it.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn next_message_len(&self) -> io::Result<usize> {
+        let mut probe = vec![0; 4096];
+        loop {
+            let len = recv(self.stream.as_raw_fd(), &mut probe, libc::MSG_PEEK)?;
+            if len < probe.len() {
+                return Ok(len);
+            }
+            probe.resize(probe.len() * 2, 0);
+        }
+    }
+}
+
+impl Read for SeqPacketStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.pending.len() {
+            let len = buf.len().min(self.pending.len() - self.pos);
+            buf[..len].copy_from_slice(&self.pending[self.pos..self.pos + len]);
+            self.pos += len;
+            return Ok(len);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let fd = self.stream.as_raw_fd();
+        let message_len = self.next_message_len()?;
+        if message_len <= buf.len() {
+            return recv(fd, buf, 0);
+        }
+        self.pending.clear();
+        self.pending.resize(message_len, 0);
+        let received = recv(fd, &mut self.pending, 0)?;
+        self.pending.truncate(received);
+        self.pos = 0;
+        self.read(buf)
+    }
+}
+
+impl Write for SeqPacketStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream
+            .write(buf)
+            .map_err(|err| message_error(err, buf.len()))
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        self.stream
+            .write_vectored(bufs)
+            .map_err(|err| message_error(err, len))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Turn `EMSGSIZE` into [`TransportError::MessageTooLarge`], kept inside
+/// the `io::Error` that `Write` has to return.
+fn message_error(err: io::Error, len: usize) -> io::Error {
+    if err.raw_os_error() == Some(libc::EMSGSIZE) {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            TransportError::MessageTooLarge { len },
+        )
+    } else {
+        err
+    }
+}
+
+/// Bind a listening SEQPACKET socket at `path`.
+pub(crate) fn listen(path: &Path) -> io::Result<UnixListener> {
+    let fd = socket(libc::SOCK_SEQPACKET)?;
+    let (addr, len) = sockaddr(path)?;
+    // SAFETY: `addr` is an initialized `sockaddr_un` of which `len` bytes
+    // are in use, and `fd` is an open socket.
+    cvt(unsafe {
+        libc::bind(
+            fd.as_raw_fd(),
+            (&raw const addr).cast::<libc::sockaddr>(),
+            len,
+        )
+    })?;
+    // SAFETY: `fd` is an open, bound socket.
+    cvt(unsafe { libc::listen(fd.as_raw_fd(), BACKLOG) })?;
+    Ok(UnixListener::from(fd))
+}
+
+/// Connect a SEQPACKET socket to the listener at `path`.
+pub(crate) fn connect(path: &Path) -> io::Result<UnixStream> {
+    let fd = socket(libc::SOCK_SEQPACKET)?;
+    let (addr, len) = sockaddr(path)?;
+    loop {
+        // SAFETY: as for `bind` in `listen`.
+        let rc = unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                (&raw const addr).cast::<libc::sockaddr>(),
+                len,
+            )
+        };
+        match cvt(rc) {
+            Ok(()) => return Ok(UnixStream::from(fd)),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// A new close-on-exec `AF_UNIX` socket of `socket_type`.
+pub(crate) fn socket(socket_type: libc::c_int) -> io::Result<OwnedFd> {
+    // SAFETY: plain syscall; the result is checked before use.
+    let fd = unsafe { libc::socket(libc::AF_UNIX, socket_type, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just returned by `socket` and is owned by nothing else.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    // SAFETY: `fd` is open; FD_CLOEXEC is the only descriptor flag.
+    cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) })?;
+    Ok(fd)
+}
+
+/// The `sockaddr_un` for a filesystem `path`, and its length in use.
+pub(crate) fn sockaddr(path: &Path) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    // SAFETY: all-zero is a valid `sockaddr_un`.
+    let mut addr: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    let bytes = path.as_os_str().as_bytes();
+    if bytes.contains(&0) || bytes.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "socket path must be shorter than sun_path and contain no NUL",
+        ));
+    }
+    for (dst, src) in addr.sun_path.iter_mut().zip(bytes) {
+        *dst = *src as libc::c_char;
+    }
+    let len = std::mem::offset_of!(libc::sockaddr_un, sun_path) + bytes.len() + 1;
+    Ok((addr, len as libc::socklen_t))
+}
+
+fn recv(fd: RawFd, buf: &mut [u8], flags: libc::c_int) -> io::Result<usize> {
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes and `fd` is an
+    // open socket.
+    let len = unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), flags) };
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(len as usize)
+}
+
+pub(crate) fn cvt(rc: libc::c_int) -> io::Result<()> {
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! Stdin/stdout pipes as one duplex stream, for plugin processes that
+//! speak a protocol over their standard streams (the LSP pattern).
+//!
+//! Pipes ignore `SO_RCVTIMEO`, so on Unix read and write timeouts are
+//! enforced with `poll(2)` before each call. Windows anonymous pipes have no
+//! way to bound a blocking read or write: timeouts are accepted there but
+//! not enforced.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+use crate::error::{Result, TransportError};
+use crate::trace::debug;
+use crate::traits::IpcStream;
+
+/// `poll` timeout meaning "wait indefinitely".
+const NO_TIMEOUT: i32 = -1;
+
+/// A read pipe and a write pipe used as one stream.
+pub(crate) struct StdioStream {
+    reader: File,
+    writer: File,
+    read_timeout_ms: AtomicI32,
+    write_timeout_ms: AtomicI32,
+}
+
+impl StdioStream {
+    pub(crate) fn new(reader: File, writer: File) -> Self {
+        Self {
+            reader,
+            writer,
+            read_timeout_ms: AtomicI32::new(NO_TIMEOUT),
+            write_timeout_ms: AtomicI32::new(NO_TIMEOUT),
+        }
+    }
+
+    /// This process's stdin and stdout, as duplicated handles.
+    pub(crate) fn current_process() -> io::Result<Self> {
+        #[cfg(unix)]
+        let (reader, writer) = {
+            use std::os::fd::AsFd;
+            (
+                io::stdin().as_fd().try_clone_to_owned()?,
+                io::stdout().as_fd().try_clone_to_owned()?,
+            )
+        };
+        #[cfg(windows)]
+        let (reader, writer) = {
+            use std::os::windows::io::AsHandle;
+            (
+                io::stdin().as_handle().try_clone_to_owned()?,
+                io::stdout().as_handle().try_clone_to_owned()?,
+            )
+        };
+        Ok(Self::new(File::from(reader), File::from(writer)))
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            reader: self.reader.try_clone()?,
+            writer: self.writer.try_clone()?,
+            read_timeout_ms: AtomicI32::new(self.read_timeout_ms.load(Ordering::Relaxed)),
+            write_timeout_ms: AtomicI32::new(self.write_timeout_ms.load(Ordering::Relaxed)),
+        })
+    }
+
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read_timeout_ms
+            .store(timeout_ms(timeout)?, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.write_timeout_ms
+            .store(timeout_ms(timeout)?, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        for fd in [self.reader.as_raw_fd(), self.writer.as_raw_fd()] {
+            // SAFETY: `fd` is open and owned by this stream.
+            let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+            if flags < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let flags = if nonblocking {
+                flags | libc::O_NONBLOCK
+            } else {
+                flags & !libc::O_NONBLOCK
+            };
+            // SAFETY: as above; only the status flags change.
+            if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn reader(&self) -> &File {
+        &self.reader
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn writer(&self) -> &File {
+        &self.writer
+    }
+}
+
+impl Read for StdioStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        wait_ready(
+            &self.reader,
+            libc::POLLIN,
+            self.read_timeout_ms.load(Ordering::Relaxed),
+        )?;
+        self.reader.read(buf)
+    }
+}
+
+impl Write for StdioStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        let buf = {
+            let timeout_ms = self.write_timeout_ms.load(Ordering::Relaxed);
+            wait_ready(&self.writer, libc::POLLOUT, timeout_ms)?;
+            // A writable pipe has room for at least PIPE_BUF bytes; more
+            // could block past the timeout.
+            if timeout_ms == NO_TIMEOUT {
+                buf
+            } else {
+                &buf[..buf.len().min(libc::PIPE_BUF)]
+            }
+        };
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Milliseconds for `poll`, rounded up; like sockets, a zero timeout is
+/// rejected.
+fn timeout_ms(timeout: Option<Duration>) -> io::Result<i32> {
+    match timeout {
+        None => Ok(NO_TIMEOUT),
+        Some(timeout) if timeout.is_zero() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot set a 0 duration timeout",
+        )),
+        Some(timeout) => {
+            Ok(i32::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(i32::MAX))
+        }
+    }
+}
+
+/// Wait until `file` is ready for `events`, failing with `TimedOut` after
+/// `timeout_ms`. Errors and hang-ups count as ready so the next call
+/// reports them.
+#[cfg(unix)]
+fn wait_ready(file: &File, events: libc::c_short, timeout_ms: i32) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    if timeout_ms == NO_TIMEOUT {
+        return Ok(());
+    }
+    let mut pollfd = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events,
+        revents: 0,
+    };
+    loop {
+        // SAFETY: `pollfd` is a valid, writable array of length 1 and the fd
+        // is open for the lifetime of `file`.
+        match unsafe { libc::poll(&mut pollfd, 1, timeout_ms) } {
+            0 => return Err(io::Error::from(io::ErrorKind::TimedOut)),
+            rc if rc > 0 => return Ok(()),
+            _ => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// A child process spawned with its stdin and stdout piped to an
+/// [`IpcStream`] held by the parent.
+///
+/// The child uses [`IpcStream::from_stdio`] for its end, and either side can
+/// then run the handshake with `Peer::from_stream`. Dropping this does not
+/// kill or wait for the child, as with [`Child`].
+pub struct ChildStdioTransport {
+    child: Child,
+}
+
+impl ChildStdioTransport {
+    /// Spawn `command` with stdin and stdout piped; stderr is left as the
+    /// command has it. Returns the child and the parent's end of the pipes.
+    ///
+    /// The child sees EOF on stdin once every clone of the returned stream
+    /// is dropped.
+    pub fn spawn(command: &mut Command) -> Result<(Self, IpcStream)> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TransportError::Io(io::Error::other(
+                "child stdio was not piped",
+            )));
+        };
+        debug!(transport = "stdio", pid = child.id(), "spawned child");
+
+        #[cfg(unix)]
+        let (reader, writer) = (
+            std::os::fd::OwnedFd::from(stdout),
+            std::os::fd::OwnedFd::from(stdin),
+        );
+        #[cfg(windows)]
+        let (reader, writer) = (
+            std::os::windows::io::OwnedHandle::from(stdout),
+            std::os::windows::io::OwnedHandle::from(stdin),
+        );
+        let stream = StdioStream::new(File::from(reader), File::from(writer));
+        Ok((Self { child }, IpcStream::from_stdio_stream(stream)))
+    }
+
+    /// The child's process id.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// The child process, to kill or poll it.
+    pub fn child_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Wait for the child to exit.
+    pub fn wait(&mut self) -> Result<ExitStatus> {
+        Ok(self.child.wait()?)
+    }
+
+    /// Give up the helper and keep the [`Child`].
+    pub fn into_child(self) -> Child {
+        self.child
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn child_stdio_round_trips_through_cat() {
+        let (mut child, mut stream) =
+            ChildStdioTransport::spawn(&mut Command::new("cat")).expect("cat should spawn");
+        assert!(format!("{stream:?}").contains("stdio"));
+        assert!(stream.peer_credentials().is_none());
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let mut reader = stream.try_clone().unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        // Closing every copy of the write side ends cat.
+        drop(stream);
+        drop(reader);
+        assert!(child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn read_timeout_applies_to_pipes() {
+        let (mut child, mut stream) =
+            ChildStdioTransport::spawn(&mut Command::new("cat")).expect("cat should spawn");
+        stream
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+
+        let start = Instant::now();
+        let err = stream.read(&mut [0u8; 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(stream.set_read_timeout(Some(Duration::ZERO)).is_err());
+
+        drop(stream);
+        child.wait().unwrap();
+    }
+}
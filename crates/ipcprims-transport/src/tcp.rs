@@ -0,0 +1,337 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, TransportError};
+use crate::trace::{debug, info};
+use crate::traits::{IpcStream, Listener};
+
+/// Prefix that marks a TCP endpoint where a socket path is expected.
+const SCHEME: &str = "tcp://";
+
+/// How often [`TcpTransport::accept_timeout`] retries a non-blocking accept.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Loopback TCP transport (`tcp` feature).
+///
+/// A fallback for deployments where the two sides share no filesystem, such
+/// as a sidecar container without a shared volume. TCP has no file
+/// permissions or peer credentials, so [`bind`](Self::bind) only accepts
+/// loopback addresses and anything on the host can connect; pair it with a
+/// handshake auth token.
+pub struct TcpTransport {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    /// `tcp://<local_addr>`, with the port the OS picked for port 0.
+    endpoint: PathBuf,
+    shut_down: AtomicBool,
+}
+
+impl TcpTransport {
+    /// Bind and listen on a loopback address such as `127.0.0.1:0`.
+    ///
+    /// Any other address fails with [`TransportError::NonLoopback`]; use
+    /// [`bind_non_loopback`](Self::bind_non_loopback) to listen beyond the
+    /// host on purpose.
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::bind_inner(addr, false)
+    }
+
+    /// Bind without the loopback check.
+    ///
+    /// ipcprims assumes both ends run on one host; only use this behind
+    /// something else that keeps remote clients out, and require an auth
+    /// token.
+    pub fn bind_non_loopback(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::bind_inner(addr, true)
+    }
+
+    fn bind_inner(addr: impl ToSocketAddrs, allow_non_loopback: bool) -> Result<Self> {
+        let addrs = resolve(addr)?;
+        if !allow_non_loopback {
+            if let Some(addr) = addrs.iter().find(|addr| !addr.ip().is_loopback()) {
+                return Err(TransportError::NonLoopback { addr: *addr });
+            }
+        }
+
+        let listener = TcpListener::bind(&addrs[..]).map_err(|e| TransportError::Bind {
+            path: endpoint(addrs[0]),
+            source: e,
+        })?;
+        let local_addr = listener.local_addr().map_err(|e| TransportError::Bind {
+            path: endpoint(addrs[0]),
+            source: e,
+        })?;
+
+        info!(transport = "tcp", %local_addr, "listening on tcp");
+
+        Ok(Self {
+            listener,
+            local_addr,
+            endpoint: endpoint(local_addr),
+            shut_down: AtomicBool::new(false),
+        })
+    }
+
+    /// Accept an incoming connection (blocking).
+    ///
+    /// Fails with [`TransportError::Shutdown`] once [`Self::shutdown`] has
+    /// been called.
+    pub fn accept(&self) -> Result<IpcStream> {
+        loop {
+            self.ensure_running()?;
+            // `accept_timeout` may have left the listener non-blocking.
+            self.listener
+                .set_nonblocking(false)
+                .map_err(TransportError::Accept)?;
+            match self.listener.accept() {
+                Ok((stream, _)) => return self.wrap(stream),
+                // A concurrent `accept_timeout` switched the mode back.
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(TransportError::Accept(err)),
+            }
+        }
+    }
+
+    /// Accept an incoming connection, waiting at most `timeout`.
+    ///
+    /// Returns `Ok(None)` if no client connected in time. The listener is
+    /// checked every few milliseconds while waiting.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.ensure_running()?;
+            self.listener
+                .set_nonblocking(true)
+                .map_err(TransportError::Accept)?;
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // Accepted sockets inherit non-blocking mode on Windows.
+                    stream
+                        .set_nonblocking(false)
+                        .map_err(TransportError::Accept)?;
+                    return self.wrap(stream).map(Some);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(None);
+                    }
+                    std::thread::sleep(remaining.min(ACCEPT_POLL_INTERVAL));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(TransportError::Accept(err)),
+            }
+        }
+    }
+
+    fn wrap(&self, stream: TcpStream) -> Result<IpcStream> {
+        // The connection may be the wake-up from `shutdown`.
+        self.ensure_running()?;
+        stream.set_nodelay(true).map_err(TransportError::Accept)?;
+        debug!(transport = "tcp", "accepted connection");
+        Ok(IpcStream::from_tcp(stream))
+    }
+
+    /// Stop accepting: a blocked [`Self::accept`] returns
+    /// [`TransportError::Shutdown`], as does every later one.
+    pub fn shutdown(&self) {
+        if self.shut_down.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        debug!(transport = "tcp", local_addr = %self.local_addr, "listener shut down");
+        // Wake a blocked accept with a throwaway connection.
+        let _ = Self::connect(wake_addr(self.local_addr));
+    }
+
+    fn ensure_running(&self) -> Result<()> {
+        if self.shut_down.load(Ordering::Acquire) {
+            return Err(TransportError::Shutdown);
+        }
+        Ok(())
+    }
+
+    /// Connect to a listening TCP transport (blocking).
+    ///
+    /// Not restricted to loopback; the restriction is on what listens.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<IpcStream> {
+        let addrs = resolve(addr)?;
+        let stream = TcpStream::connect(&addrs[..]).map_err(|e| TransportError::Connect {
+            path: endpoint(addrs[0]),
+            source: e,
+        })?;
+        stream.set_nodelay(true)?;
+        debug!(transport = "tcp", addr = %addrs[0], "connected over tcp");
+        Ok(IpcStream::from_tcp(stream))
+    }
+
+    /// The address in a `tcp://host:port` endpoint, or `None` for anything
+    /// else.
+    ///
+    /// Callers that take a socket path, such as the peer listener and
+    /// connector, use this to route TCP endpoints here.
+    pub fn endpoint_addr(path: &Path) -> Option<&str> {
+        path.to_str()?
+            .strip_prefix(SCHEME)
+            .filter(|addr| !addr.is_empty())
+    }
+
+    /// The address actually bound, with the port the OS picked for port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The bound address as a `tcp://host:port` endpoint.
+    pub fn path(&self) -> &Path {
+        &self.endpoint
+    }
+
+    /// Transport name for diagnostics.
+    pub fn transport_name(&self) -> &'static str {
+        "tcp"
+    }
+}
+
+impl Listener for TcpTransport {
+    type Stream = IpcStream;
+
+    fn accept(&self) -> Result<IpcStream> {
+        TcpTransport::accept(self)
+    }
+
+    fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        TcpTransport::accept_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) {
+        TcpTransport::shutdown(self);
+    }
+
+    fn path(&self) -> &Path {
+        TcpTransport::path(self)
+    }
+}
+
+fn resolve(addr: impl ToSocketAddrs) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    if addrs.is_empty() {
+        return Err(TransportError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "address resolved to nothing",
+        )));
+    }
+    Ok(addrs)
+}
+
+fn endpoint(addr: SocketAddr) -> PathBuf {
+    PathBuf::from(format!("{SCHEME}{addr}"))
+}
+
+/// Where to connect to reach a listener on `addr`; an unspecified address
+/// is reached through loopback.
+fn wake_addr(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => {
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port())
+        }
+        IpAddr::V6(ip) if ip.is_unspecified() => {
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port())
+        }
+        _ => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn bind_accept_connect_over_loopback() {
+        let listener = TcpTransport::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr();
+        assert_ne!(addr.port(), 0);
+        assert_eq!(listener.path(), Path::new(&format!("tcp://{addr}")));
+        assert_eq!(listener.transport_name(), "tcp");
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        });
+
+        let mut client = TcpTransport::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut reader = client.try_clone().unwrap();
+        client.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        assert!(format!("{client:?}").contains("tcp"));
+        assert!(client.peer_credentials().is_none());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn accept_timeout_returns_none_then_takes_a_late_client() {
+        let listener = TcpTransport::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr();
+        assert!(listener
+            .accept_timeout(Duration::from_millis(30))
+            .unwrap()
+            .is_none());
+
+        let client = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let mut stream = TcpTransport::connect(addr).unwrap();
+            stream.write_all(b"late").unwrap();
+        });
+        let mut server = listener
+            .accept_timeout(Duration::from_secs(5))
+            .unwrap()
+            .expect("client connects mid-wait");
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"late");
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn bind_rejects_non_loopback_unless_opted_out() {
+        let err = TcpTransport::bind("0.0.0.0:0").err().unwrap();
+        assert!(
+            matches!(err, TransportError::NonLoopback { addr } if addr.ip().is_unspecified()),
+            "{err}"
+        );
+
+        let listener = Arc::new(TcpTransport::bind_non_loopback("0.0.0.0:0").unwrap());
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || acceptor.accept().map(|_| ()));
+        std::thread::sleep(Duration::from_millis(50));
+        listener.shutdown();
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(TransportError::Shutdown)
+        ));
+    }
+
+    #[test]
+    fn endpoint_addr_parses_tcp_scheme() {
+        assert_eq!(
+            TcpTransport::endpoint_addr(Path::new("tcp://127.0.0.1:9000")),
+            Some("127.0.0.1:9000")
+        );
+        assert_eq!(TcpTransport::endpoint_addr(Path::new("tcp://")), None);
+        assert_eq!(TcpTransport::endpoint_addr(Path::new("/tmp/x.sock")), None);
+    }
+}
@@ -0,0 +1,25 @@
+//! Logging shims for the optional `tracing` feature.
+//!
+//! With the feature enabled these forward to the `tracing` macros of the same
+//! name; without it they expand to nothing. Events use the `transport`
+//! (`"uds"` or `"named_pipe"`) and `path` fields.
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!($($arg)*);
+    }};
+}
+
+// Only the Unix transports (and `tcp`) log at info level.
+#[cfg_attr(not(unix), allow(unused_macros))]
+macro_rules! info {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::info!($($arg)*);
+    }};
+}
+
+pub(crate) use debug;
+#[cfg_attr(not(unix), allow(unused_imports))]
+pub(crate) use info;
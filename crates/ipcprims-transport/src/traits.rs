@@ -1,24 +1,55 @@
-use std::io::{Read, Write};
+use std::io::{IoSlice, Read, Write};
+use std::path::Path;
+use std::time::Duration;
 
+use crate::addr::IpcAddr;
+use crate::credentials::PeerCredentials;
 use crate::error::Result;
+#[cfg(unix)]
+use crate::fdpass::{self, FdState};
+use crate::memory::MemoryStream;
 #[cfg(windows)]
 use crate::npipes::NamedPipeStream;
+#[cfg(unix)]
+use crate::seqpacket::SeqPacketStream;
+use crate::stdio::StdioStream;
+
+/// Environment variable through which [`IpcStream::pass_to_child`] tells a
+/// child process which inherited file descriptor is its stream.
+#[cfg(unix)]
+pub const INHERITED_FD_ENV: &str = "IPCPRIMS_FD";
 
 /// A connected IPC stream — implements Read + Write.
 ///
 /// This is the fundamental I/O type returned by transport operations.
 /// On Unix, this wraps a Unix domain socket stream.
-/// On Windows, this wraps a named pipe handle.
+/// On Windows, this wraps a named pipe handle. With the `tcp` feature it
+/// can also wrap a loopback TCP stream, and with the `vsock` feature (Linux)
+/// a VM-to-host vsock stream.
 pub struct IpcStream {
     inner: IpcStreamInner,
+    /// File descriptors to send or received (Unix variant only).
+    #[cfg(unix)]
+    fds: FdState,
 }
 
 #[cfg_attr(not(unix), allow(dead_code))]
 enum IpcStreamInner {
     #[cfg(unix)]
     Unix(std::os::unix::net::UnixStream),
+    #[cfg(unix)]
+    SeqPacket(SeqPacketStream),
     #[cfg(windows)]
     NamedPipe(NamedPipeStream),
+    #[cfg(feature = "tcp")]
+    Tcp(std::net::TcpStream),
+    /// An `AF_VSOCK` socket; see the `vsock` module for why `UnixStream`.
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    Vsock(std::os::unix::net::UnixStream),
+    Stdio(StdioStream),
+    Memory(MemoryStream),
+    /// A [`TransportStream`] from outside this crate.
+    Custom(Box<dyn DynStream>),
 }
 
 #[cfg_attr(not(unix), allow(unused_variables))]
@@ -26,9 +57,24 @@ impl Read for IpcStream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match &mut self.inner {
             #[cfg(unix)]
-            IpcStreamInner::Unix(stream) => stream.read(buf),
+            IpcStreamInner::Unix(stream) => {
+                use std::os::fd::AsRawFd;
+                match self.fds.read(stream.as_raw_fd(), buf) {
+                    Some(result) => result,
+                    None => stream.read(buf),
+                }
+            }
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream.read(buf),
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => stream.read(buf),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.read(buf),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.read(buf),
+            IpcStreamInner::Stdio(stream) => stream.read(buf),
+            IpcStreamInner::Memory(stream) => stream.read(buf),
+            IpcStreamInner::Custom(stream) => stream.read(buf),
         }
     }
 }
@@ -38,9 +84,48 @@ impl Write for IpcStream {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         match &mut self.inner {
             #[cfg(unix)]
-            IpcStreamInner::Unix(stream) => stream.write(buf),
+            IpcStreamInner::Unix(stream) => {
+                use std::os::fd::AsRawFd;
+                match self.fds.write(stream.as_raw_fd(), &[IoSlice::new(buf)]) {
+                    Some(result) => result,
+                    None => stream.write(buf),
+                }
+            }
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream.write(buf),
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => stream.write(buf),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.write(buf),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.write(buf),
+            IpcStreamInner::Stdio(stream) => stream.write(buf),
+            IpcStreamInner::Memory(stream) => stream.write(buf),
+            IpcStreamInner::Custom(stream) => stream.write(buf),
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            #[cfg(unix)]
+            IpcStreamInner::Unix(stream) => {
+                use std::os::fd::AsRawFd;
+                match self.fds.write(stream.as_raw_fd(), bufs) {
+                    Some(result) => result,
+                    None => stream.write_vectored(bufs),
+                }
+            }
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream.write_vectored(bufs),
+            #[cfg(windows)]
+            IpcStreamInner::NamedPipe(stream) => stream.write_vectored(bufs),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.write_vectored(bufs),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.write_vectored(bufs),
+            IpcStreamInner::Stdio(stream) => stream.write_vectored(bufs),
+            IpcStreamInner::Memory(stream) => stream.write_vectored(bufs),
+            IpcStreamInner::Custom(stream) => stream.write_vectored(bufs),
         }
     }
 
@@ -48,27 +133,211 @@ impl Write for IpcStream {
         match &mut self.inner {
             #[cfg(unix)]
             IpcStreamInner::Unix(stream) => stream.flush(),
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream.flush(),
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => stream.flush(),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.flush(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.flush(),
+            IpcStreamInner::Stdio(stream) => stream.flush(),
+            IpcStreamInner::Memory(stream) => stream.flush(),
+            IpcStreamInner::Custom(stream) => stream.flush(),
         }
     }
 }
 
 #[cfg_attr(not(unix), allow(unused_variables))]
 impl IpcStream {
+    fn new(inner: IpcStreamInner) -> Self {
+        Self {
+            inner,
+            #[cfg(unix)]
+            fds: FdState::default(),
+        }
+    }
+
     /// Create an IpcStream from a Unix domain socket stream.
     #[cfg(unix)]
     pub(crate) fn from_unix(stream: std::os::unix::net::UnixStream) -> Self {
-        Self {
-            inner: IpcStreamInner::Unix(stream),
-        }
+        Self::new(IpcStreamInner::Unix(stream))
+    }
+
+    /// Create an IpcStream from a connected `SOCK_SEQPACKET` Unix socket.
+    #[cfg(unix)]
+    pub(crate) fn from_unix_seqpacket(stream: std::os::unix::net::UnixStream) -> Self {
+        Self::new(IpcStreamInner::SeqPacket(SeqPacketStream::new(stream)))
     }
 
     /// Create an IpcStream from a Windows named pipe stream.
     #[cfg(windows)]
     pub(crate) fn from_named_pipe(stream: NamedPipeStream) -> Self {
-        Self {
-            inner: IpcStreamInner::NamedPipe(stream),
+        Self::new(IpcStreamInner::NamedPipe(stream))
+    }
+
+    /// Create an IpcStream from a loopback TCP stream.
+    #[cfg(feature = "tcp")]
+    pub(crate) fn from_tcp(stream: std::net::TcpStream) -> Self {
+        Self::new(IpcStreamInner::Tcp(stream))
+    }
+
+    /// Create an IpcStream from a connected `AF_VSOCK` socket.
+    #[cfg(all(target_os = "linux", feature = "vsock"))]
+    pub(crate) fn from_vsock(stream: std::os::unix::net::UnixStream) -> Self {
+        Self::new(IpcStreamInner::Vsock(stream))
+    }
+
+    /// Create an IpcStream from a pair of pipes.
+    pub(crate) fn from_stdio_stream(stream: StdioStream) -> Self {
+        Self::new(IpcStreamInner::Stdio(stream))
+    }
+
+    /// Create an IpcStream from one end of a memory pair.
+    pub(crate) fn from_memory(stream: MemoryStream) -> Self {
+        Self::new(IpcStreamInner::Memory(stream))
+    }
+
+    /// Wrap a stream from another transport; see
+    /// [`TransportStream::into_ipc_stream`].
+    fn from_custom(stream: impl TransportStream) -> Self {
+        Self::new(IpcStreamInner::Custom(Box::new(stream)))
+    }
+
+    /// This process's stdin and stdout as one duplex stream, for a plugin
+    /// spawned with [`ChildStdioTransport`](crate::ChildStdioTransport) or
+    /// any parent that pipes both.
+    ///
+    /// The stream reads and writes duplicates of the standard handles, so
+    /// anything else the process prints to stdout corrupts it: log to
+    /// stderr. On Unix, timeouts are enforced with `poll(2)`; on Windows
+    /// they are accepted but reads and writes block regardless. As for
+    /// named pipes, [`shutdown`](Self::shutdown) is unsupported.
+    pub fn from_stdio() -> Result<Self> {
+        Ok(Self::from_stdio_stream(StdioStream::current_process()?))
+    }
+
+    /// A connected pair of Unix stream sockets (`socketpair(2)`).
+    ///
+    /// For a supervisor and the child it spawns there is no path to bind,
+    /// clean up or race on: keep one end and hand the other to the child
+    /// with [`pass_to_child`](Self::pass_to_child).
+    #[cfg(unix)]
+    pub fn pair() -> Result<(Self, Self)> {
+        let (a, b) = std::os::unix::net::UnixStream::pair()?;
+        Ok((Self::from_unix(a), Self::from_unix(b)))
+    }
+
+    /// Let the child that `command` spawns inherit this stream.
+    ///
+    /// Sets [`INHERITED_FD_ENV`] to the descriptor number and clears
+    /// `FD_CLOEXEC` on it in the child only, between fork and exec, so
+    /// processes spawned from other threads meanwhile do not get it. Drop
+    /// this copy after spawning, so the parent's end sees EOF once the
+    /// child exits. Returns the descriptor number.
+    #[cfg(unix)]
+    pub fn pass_to_child(&self, command: &mut std::process::Command) -> std::os::fd::RawFd {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let fd = self.as_raw_fd();
+        command.env(INHERITED_FD_ENV, fd.to_string());
+        // SAFETY: the hook only calls `fcntl`, which is async-signal-safe,
+        // and reads errno; it allocates nothing.
+        unsafe {
+            command.pre_exec(move || {
+                let flags = libc::fcntl(fd, libc::F_GETFD);
+                if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        fd
+    }
+
+    /// The descriptor named by [`INHERITED_FD_ENV`], if this process was
+    /// spawned with [`pass_to_child`](Self::pass_to_child).
+    #[cfg(unix)]
+    pub fn inherited_fd() -> Option<std::os::fd::RawFd> {
+        std::env::var(INHERITED_FD_ENV).ok()?.parse().ok()
+    }
+
+    /// Take ownership of an inherited Unix socket descriptor.
+    ///
+    /// The descriptor must be a connected `AF_UNIX` stream or seqpacket
+    /// socket; anything else fails with [`TransportError::NotUnixSocket`]
+    /// and is left open. `FD_CLOEXEC` is set again so the stream is not
+    /// passed on to this process's own children.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be open and owned by nothing else in this process; the
+    /// stream closes it on drop.
+    ///
+    /// [`TransportError::NotUnixSocket`]: crate::TransportError::NotUnixSocket
+    #[cfg(unix)]
+    pub unsafe fn from_inherited_fd(fd: std::os::fd::RawFd) -> Result<Self> {
+        use std::os::fd::FromRawFd;
+
+        unix_socket_type(fd)?;
+        // SAFETY: `fd` is open, per this function's contract.
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // SAFETY: the caller hands over sole ownership of `fd`.
+        Self::from_owned_fd(unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// Wrap a Unix socket created elsewhere, such as one passed in by
+    /// systemd socket activation or made with `socketpair(2)`.
+    ///
+    /// The descriptor must be a connected `AF_UNIX` stream or seqpacket
+    /// socket (checked with `getsockname` and `SO_TYPE`); anything else
+    /// fails with [`TransportError::NotUnixSocket`], closing it.
+    ///
+    /// The stream owns the descriptor and closes it on drop. To hand a
+    /// connection to code that wants its own `OwnedFd`, pass
+    /// `stream.as_fd().try_clone_to_owned()`: like [`try_clone`], that is
+    /// a second descriptor for the same socket, so the connection stays
+    /// open until both are closed.
+    ///
+    /// [`TransportError::NotUnixSocket`]: crate::TransportError::NotUnixSocket
+    /// [`try_clone`]: Self::try_clone
+    #[cfg(unix)]
+    pub fn from_owned_fd(fd: std::os::fd::OwnedFd) -> Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let socket_type = unix_socket_type(fd.as_raw_fd())?;
+        let stream = std::os::unix::net::UnixStream::from(fd);
+        if socket_type == libc::SOCK_SEQPACKET {
+            Ok(Self::from_unix_seqpacket(stream))
+        } else {
+            Ok(Self::from_unix(stream))
+        }
+    }
+
+    /// Hand this connection to Tokio, for a handshake done blocking and a
+    /// session driven async.
+    ///
+    /// Only Unix stream sockets convert; every other kind fails with
+    /// `Unsupported`. The socket is switched to non-blocking mode, and
+    /// descriptors attached but not yet sent, or received but not yet
+    /// claimed, are closed. Must be called within a Tokio runtime.
+    /// [`AsyncIpcStream::into_sync`](crate::AsyncIpcStream::into_sync)
+    /// converts back.
+    #[cfg(all(unix, feature = "async"))]
+    pub fn into_async(self) -> Result<crate::AsyncIpcStream> {
+        match self.inner {
+            IpcStreamInner::Unix(stream) => {
+                stream.set_nonblocking(true)?;
+                crate::AsyncIpcStream::from_std(stream).map_err(Into::into)
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "only unix stream sockets convert to async streams",
+            )
+            .into()),
         }
     }
 
@@ -77,8 +346,20 @@ impl IpcStream {
         match &self.inner {
             #[cfg(unix)]
             IpcStreamInner::Unix(stream) => stream.set_read_timeout(timeout).map_err(Into::into),
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream
+                .get_ref()
+                .set_read_timeout(timeout)
+                .map_err(Into::into),
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => stream.set_read_timeout(timeout),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.set_read_timeout(timeout).map_err(Into::into),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.set_read_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Stdio(stream) => stream.set_read_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Memory(stream) => stream.set_read_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Custom(stream) => stream.set_read_timeout(timeout),
         }
     }
 
@@ -87,12 +368,315 @@ impl IpcStream {
         match &self.inner {
             #[cfg(unix)]
             IpcStreamInner::Unix(stream) => stream.set_write_timeout(timeout).map_err(Into::into),
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream
+                .get_ref()
+                .set_write_timeout(timeout)
+                .map_err(Into::into),
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => stream.set_write_timeout(timeout),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.set_write_timeout(timeout).map_err(Into::into),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.set_write_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Stdio(stream) => stream.set_write_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Memory(stream) => stream.set_write_timeout(timeout).map_err(Into::into),
+            IpcStreamInner::Custom(stream) => stream.set_write_timeout(timeout),
+        }
+    }
+
+    /// Switch the stream into or out of non-blocking mode.
+    ///
+    /// In non-blocking mode reads and writes that cannot make progress fail
+    /// with `WouldBlock`, for use with an external readiness loop (epoll,
+    /// kqueue, mio) on [`as_raw_fd`](std::os::fd::AsRawFd::as_raw_fd).
+    /// A frame reader's `read_frame` then fails with `Io(WouldBlock)`
+    /// when no complete frame is available, keeping any partial frame;
+    /// `try_read_frame` reports that as `Ok(None)`. A frame writer waits
+    /// out `WouldBlock` up to its write timeout. The flag is shared by
+    /// every clone of the stream.
+    #[cfg(unix)]
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match &self.inner {
+            IpcStreamInner::Unix(stream) => stream.set_nonblocking(nonblocking).map_err(Into::into),
+            IpcStreamInner::SeqPacket(stream) => stream
+                .get_ref()
+                .set_nonblocking(nonblocking)
+                .map_err(Into::into),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.set_nonblocking(nonblocking).map_err(Into::into),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => {
+                stream.set_nonblocking(nonblocking).map_err(Into::into)
+            }
+            IpcStreamInner::Stdio(stream) => {
+                stream.set_nonblocking(nonblocking).map_err(Into::into)
+            }
+            IpcStreamInner::Memory(stream) => {
+                stream.set_nonblocking(nonblocking);
+                Ok(())
+            }
+            IpcStreamInner::Custom(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "custom transport streams have no non-blocking mode",
+            )
+            .into()),
+        }
+    }
+
+    /// Shut down the read half, the write half or both (`shutdown(2)`).
+    ///
+    /// After `Shutdown::Write` the other end reads EOF once it has drained
+    /// what was sent, while this end can still read its replies. Applies to
+    /// the socket, so every clone of the stream sees it. Named pipes have no
+    /// half-close and fail with `Unsupported`.
+    pub fn shutdown(&self, how: std::net::Shutdown) -> Result<()> {
+        match &self.inner {
+            #[cfg(unix)]
+            IpcStreamInner::Unix(stream) => stream.shutdown(how).map_err(Into::into),
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => stream.get_ref().shutdown(how).map_err(Into::into),
+            #[cfg(windows)]
+            IpcStreamInner::NamedPipe(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "named pipes cannot shut down one direction",
+            )
+            .into()),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.shutdown(how).map_err(Into::into),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.shutdown(how).map_err(Into::into),
+            IpcStreamInner::Stdio(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "stdio pipes cannot shut down one direction; drop every clone to close them",
+            )
+            .into()),
+            IpcStreamInner::Memory(stream) => {
+                stream.shutdown(how);
+                Ok(())
+            }
+            IpcStreamInner::Custom(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "custom transport streams cannot shut down one direction",
+            )
+            .into()),
+        }
+    }
+
+    /// Whether the transport keeps message boundaries (`SOCK_SEQPACKET`).
+    ///
+    /// Each write is then delivered as one message and a corrupt message
+    /// cannot bleed into the next, so a framing layer on top has no stream
+    /// to resynchronize. Reads still behave as on a byte stream: a message
+    /// larger than the read buffer is handed out over several reads.
+    pub fn is_message_oriented(&self) -> bool {
+        match &self.inner {
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Ask for a `size`-byte kernel send buffer (`SO_SNDBUF`).
+    ///
+    /// The kernel may adjust the request: Linux doubles it for bookkeeping
+    /// and caps it at `net.core.wmem_max`, so read the result back with
+    /// [`send_buffer_size`](Self::send_buffer_size). Stdio and memory
+    /// streams have no socket buffer and fail with `Unsupported`.
+    #[cfg(unix)]
+    pub fn set_send_buffer_size(&self, size: usize) -> Result<()> {
+        set_socket_buffer_size(self.socket_fd()?, libc::SO_SNDBUF, size).map_err(Into::into)
+    }
+
+    /// Ask for a `size`-byte kernel receive buffer (`SO_RCVBUF`); see
+    /// [`set_send_buffer_size`](Self::set_send_buffer_size).
+    #[cfg(unix)]
+    pub fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        set_socket_buffer_size(self.socket_fd()?, libc::SO_RCVBUF, size).map_err(Into::into)
+    }
+
+    /// The kernel send buffer size in bytes, as the kernel reports it.
+    #[cfg(unix)]
+    pub fn send_buffer_size(&self) -> Result<usize> {
+        socket_buffer_size(self.socket_fd()?, libc::SO_SNDBUF).map_err(Into::into)
+    }
+
+    /// The kernel receive buffer size in bytes, as the kernel reports it.
+    #[cfg(unix)]
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        socket_buffer_size(self.socket_fd()?, libc::SO_RCVBUF).map_err(Into::into)
+    }
+
+    #[cfg(unix)]
+    fn socket_fd(&self) -> std::io::Result<std::os::fd::RawFd> {
+        use std::os::fd::AsRawFd;
+
+        match &self.inner {
+            IpcStreamInner::Stdio(_) | IpcStreamInner::Memory(_) | IpcStreamInner::Custom(_) => {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "socket options need a socket-backed stream",
+                ))
+            }
+            _ => Ok(self.as_raw_fd()),
+        }
+    }
+
+    /// Block until the stream can accept more bytes or `timeout` elapses.
+    ///
+    /// Uses `poll(2)`. Returns `Ok(false)` on timeout; `None` waits
+    /// indefinitely. Hang-ups and socket errors count as writable so that the
+    /// next write reports them.
+    #[cfg(unix)]
+    pub fn wait_writable(&self, timeout: Option<std::time::Duration>) -> std::io::Result<bool> {
+        use std::os::fd::AsRawFd;
+
+        let fd = match &self.inner {
+            IpcStreamInner::Stdio(stream) => stream.writer().as_raw_fd(),
+            // The queue is unbounded.
+            IpcStreamInner::Memory(_) => return Ok(true),
+            // Only readability is known; let the write itself find out.
+            IpcStreamInner::Custom(_) => return Ok(true),
+            _ => self.as_raw_fd(),
+        };
+        // Round up so a sub-millisecond remainder does not become a 0 ms spin.
+        let timeout_ms = match timeout {
+            Some(timeout) => {
+                let ms = timeout.as_nanos().div_ceil(1_000_000);
+                libc::c_int::try_from(ms).unwrap_or(libc::c_int::MAX)
+            }
+            None => -1,
+        };
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+
+        // SAFETY: `pollfd` is a valid, writable array of length 1 and `fd` is
+        // an open socket descriptor owned by this stream.
+        let rc = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        match rc {
+            0 => Ok(false),
+            rc if rc > 0 => Ok(true),
+            _ => {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    // Let the caller retry the write and re-check its deadline.
+                    Ok(true)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    /// Send `data` with `fds` attached as `SCM_RIGHTS` ancillary data
+    /// (Unix stream sockets only).
+    ///
+    /// The descriptors arrive with the first byte sent; the receiver gets
+    /// its own descriptors for the same open files. Returns the number of
+    /// bytes written, which may be fewer than `data.len()`; write the rest
+    /// without descriptors. At most [`MAX_FDS_PER_MESSAGE`] per call,
+    /// otherwise [`TransportError::TooManyFds`].
+    ///
+    /// [`MAX_FDS_PER_MESSAGE`]: crate::MAX_FDS_PER_MESSAGE
+    /// [`TransportError::TooManyFds`]: crate::TransportError::TooManyFds
+    #[cfg(unix)]
+    pub fn send_with_fds(
+        &mut self,
+        data: &[u8],
+        fds: &[std::os::fd::BorrowedFd<'_>],
+    ) -> Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        fdpass::check_count(fds.len())?;
+        let fd = self.fd_passing_socket()?.as_raw_fd();
+        fdpass::send(fd, &[IoSlice::new(data)], fds).map_err(Into::into)
+    }
+
+    /// Receive into `buf`, appending any descriptors that arrive with the
+    /// bytes to `fds` (Unix stream sockets only).
+    ///
+    /// Received descriptors are close-on-exec. A sender that packs more
+    /// than [`MAX_FDS_PER_MESSAGE`] into one message truncates the
+    /// ancillary data: the descriptors that arrived are closed and this
+    /// fails with [`TransportError::FdsTruncated`]. The bytes that came
+    /// with them are consumed, so treat the stream as broken.
+    ///
+    /// [`MAX_FDS_PER_MESSAGE`]: crate::MAX_FDS_PER_MESSAGE
+    /// [`TransportError::FdsTruncated`]: crate::TransportError::FdsTruncated
+    #[cfg(unix)]
+    pub fn recv_with_fds(
+        &mut self,
+        buf: &mut [u8],
+        fds: &mut Vec<std::os::fd::OwnedFd>,
+    ) -> Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        let fd = self.fd_passing_socket()?.as_raw_fd();
+        fdpass::recv(fd, buf, fds).map_err(fdpass::into_transport_error)
+    }
+
+    /// Duplicate `fds` and send them with the next `write`, for writers
+    /// such as a frame writer that only see [`Write`].
+    ///
+    /// Adds to descriptors already attached, up to [`MAX_FDS_PER_MESSAGE`]
+    /// in total. They stay attached until a write sends at least one byte
+    /// or [`detach_fds`](Self::detach_fds) is called.
+    ///
+    /// [`MAX_FDS_PER_MESSAGE`]: crate::MAX_FDS_PER_MESSAGE
+    #[cfg(unix)]
+    pub fn attach_fds(&mut self, fds: &[std::os::fd::BorrowedFd<'_>]) -> Result<()> {
+        self.fd_passing_socket()?;
+        self.fds.attach(fds)
+    }
+
+    /// Take back descriptors attached with [`attach_fds`](Self::attach_fds)
+    /// that no write has sent yet.
+    #[cfg(unix)]
+    pub fn detach_fds(&mut self) -> Vec<std::os::fd::OwnedFd> {
+        self.fds.detach()
+    }
+
+    /// Keep descriptors that arrive with ordinary reads, for readers such
+    /// as a frame reader that only see [`Read`] (default off).
+    ///
+    /// While off, reads discard passed descriptors. While on, they queue,
+    /// oldest first, until claimed with
+    /// [`take_received_fds`](Self::take_received_fds); a read that would
+    /// leave more than four messages' worth unclaimed closes them all and
+    /// fails. Turning it off closes any still queued.
+    #[cfg(unix)]
+    pub fn set_receive_fds(&mut self, enabled: bool) -> Result<()> {
+        self.fd_passing_socket()?;
+        self.fds.set_receiving(enabled);
+        Ok(())
+    }
+
+    /// The `count` oldest descriptors queued by reads since
+    /// [`set_receive_fds`](Self::set_receive_fds), or `None` if fewer than
+    /// `count` have arrived.
+    #[cfg(unix)]
+    pub fn take_received_fds(&mut self, count: usize) -> Option<Vec<std::os::fd::OwnedFd>> {
+        self.fds.take_received(count)
+    }
+
+    #[cfg(unix)]
+    fn fd_passing_socket(&self) -> Result<&std::os::unix::net::UnixStream> {
+        match &self.inner {
+            IpcStreamInner::Unix(stream) => Ok(stream),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "file descriptor passing needs a unix stream socket",
+            )
+            .into()),
         }
     }
 
     /// Try to clone this stream (creates a new file descriptor).
+    ///
+    /// The clone starts with no attached or received file descriptors.
     pub fn try_clone(&self) -> Result<Self> {
         match &self.inner {
             #[cfg(unix)]
@@ -100,71 +684,760 @@ impl IpcStream {
                 let cloned = stream.try_clone()?;
                 Ok(Self::from_unix(cloned))
             }
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => {
+                Ok(Self::new(IpcStreamInner::SeqPacket(stream.try_clone()?)))
+            }
             #[cfg(windows)]
             IpcStreamInner::NamedPipe(stream) => {
                 let cloned = stream.try_clone()?;
                 Ok(Self::from_named_pipe(cloned))
             }
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => Ok(Self::from_tcp(stream.try_clone()?)),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => Ok(Self::from_vsock(stream.try_clone()?)),
+            IpcStreamInner::Stdio(stream) => Ok(Self::from_stdio_stream(stream.try_clone()?)),
+            IpcStreamInner::Memory(stream) => Ok(Self::from_memory(stream.try_clone()?)),
+            IpcStreamInner::Custom(stream) => {
+                Ok(Self::new(IpcStreamInner::Custom(stream.try_clone_boxed()?)))
+            }
         }
     }
 
-    /// Get the credentials of the connected peer (Linux only).
+    /// The address this end of the stream is bound to.
+    ///
+    /// For a stream accepted from a Unix socket listener this is the
+    /// listener's path (or `@name`); client sockets, socket pairs and
+    /// streams with no socket behind them are [`IpcAddr::Unnamed`].
+    pub fn local_addr(&self) -> Result<IpcAddr> {
+        self.addr(false)
+    }
+
+    /// The address of the other end of the stream.
     ///
-    /// Returns `(uid, gid, pid)` via `SO_PEERCRED`, or `None` if unavailable.
-    #[cfg(target_os = "linux")]
-    pub fn peer_credentials(&self) -> Option<(u32, u32, u32)> {
+    /// A client connected to a Unix socket listener sees the listener's
+    /// path (or `@name`); the listener's end sees
+    /// [`IpcAddr::Unnamed`] unless the client bound its socket.
+    pub fn peer_addr(&self) -> Result<IpcAddr> {
+        self.addr(true)
+    }
+
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn addr(&self, peer: bool) -> Result<IpcAddr> {
+        #[cfg(unix)]
+        let unix_addr = |stream: &std::os::unix::net::UnixStream| -> Result<IpcAddr> {
+            let addr = if peer {
+                stream.peer_addr()?
+            } else {
+                stream.local_addr()?
+            };
+            Ok(addr.into())
+        };
+        match &self.inner {
+            #[cfg(unix)]
+            IpcStreamInner::Unix(stream) => unix_addr(stream),
+            #[cfg(unix)]
+            IpcStreamInner::SeqPacket(stream) => unix_addr(stream.get_ref()),
+            #[cfg(windows)]
+            IpcStreamInner::NamedPipe(_) => Ok(IpcAddr::Unnamed),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => {
+                let addr = if peer {
+                    stream.peer_addr()?
+                } else {
+                    stream.local_addr()?
+                };
+                Ok(IpcAddr::Tcp(addr))
+            }
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => {
+                use std::os::fd::AsRawFd;
+                let (cid, port) = crate::vsock::socket_addr(stream.as_raw_fd(), peer)?;
+                Ok(IpcAddr::Vsock { cid, port })
+            }
+            IpcStreamInner::Stdio(_) | IpcStreamInner::Memory(_) | IpcStreamInner::Custom(_) => {
+                Ok(IpcAddr::Unnamed)
+            }
+        }
+    }
+
+    /// Get the credentials of the connected peer (Linux and macOS).
+    ///
+    /// Read via `SO_PEERCRED` on Linux, and via `getpeereid` plus
+    /// `LOCAL_PEERPID` on macOS; `None` if unavailable.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "ios"))]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
         use std::os::fd::AsRawFd;
 
         let fd = match &self.inner {
             IpcStreamInner::Unix(stream) => stream.as_raw_fd(),
+            IpcStreamInner::SeqPacket(stream) => stream.get_ref().as_raw_fd(),
+            // TCP carries no credentials, even over loopback.
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(_) => return None,
+            // Nor does vsock; the CID is all the kernel knows of the peer.
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(_) => return None,
+            // A pipe has no peer credentials.
+            IpcStreamInner::Stdio(_) => return None,
+            // Both ends are this process.
+            IpcStreamInner::Memory(_) => return Some(PeerCredentials::current_process()),
+            // The transport is opaque here.
+            IpcStreamInner::Custom(_) => return None,
         };
-
-        let mut cred = libc::ucred {
-            pid: 0,
-            uid: 0,
-            gid: 0,
-        };
-        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
-
-        // SAFETY: `cred` and `len` are valid writable pointers for the provided sizes,
-        // and `fd` is an open Unix socket descriptor owned by this process.
-        let rc = unsafe {
-            libc::getsockopt(
-                fd,
-                libc::SOL_SOCKET,
-                libc::SO_PEERCRED,
-                (&mut cred as *mut libc::ucred).cast::<libc::c_void>(),
-                &mut len,
-            )
-        };
-
-        if rc == 0 && len as usize == std::mem::size_of::<libc::ucred>() {
-            Some((cred.uid, cred.gid, cred.pid as u32))
-        } else {
-            None
-        }
+        crate::credentials::from_socket(fd)
     }
 
     /// Get the credentials of the connected peer.
     ///
     /// Returns `None` on platforms that do not expose peer credentials.
-    #[cfg(not(target_os = "linux"))]
-    pub fn peer_credentials(&self) -> Option<(u32, u32, u32)> {
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "ios")))]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
         None
     }
 }
 
+#[cfg(unix)]
+impl std::os::fd::AsRawFd for IpcStream {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        match &self.inner {
+            IpcStreamInner::Unix(stream) => stream.as_raw_fd(),
+            IpcStreamInner::SeqPacket(stream) => stream.get_ref().as_raw_fd(),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.as_raw_fd(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.as_raw_fd(),
+            IpcStreamInner::Stdio(stream) => stream.reader().as_raw_fd(),
+            IpcStreamInner::Memory(stream) => stream.ready_fd().as_raw_fd(),
+            IpcStreamInner::Custom(stream) => stream.poll_fd().as_raw_fd(),
+        }
+    }
+}
+
+/// Borrows the stream's socket (for a stdio stream, its read pipe; for a
+/// memory pair, its readiness pipe; for a custom transport, whatever its
+/// own `AsFd` returns); see
+/// [`IpcStream::from_owned_fd`] for handing out an owned copy.
+#[cfg(unix)]
+impl std::os::fd::AsFd for IpcStream {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        match &self.inner {
+            IpcStreamInner::Unix(stream) => stream.as_fd(),
+            IpcStreamInner::SeqPacket(stream) => stream.get_ref().as_fd(),
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(stream) => stream.as_fd(),
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(stream) => stream.as_fd(),
+            IpcStreamInner::Stdio(stream) => stream.reader().as_fd(),
+            IpcStreamInner::Memory(stream) => stream.ready_fd(),
+            IpcStreamInner::Custom(stream) => stream.poll_fd(),
+        }
+    }
+}
+
+/// Duplicate a stream handle, so the peer layer can read on one copy while
+/// it writes on the other.
+pub trait TryClone: Sized {
+    /// Another handle to the same connection.
+    fn try_clone(&self) -> Result<Self>;
+}
+
+/// Read and write timeouts, which bound handshakes and blocking receives.
+pub trait SetTimeouts {
+    /// Fail reads with `WouldBlock` or `TimedOut` after `timeout`; `None`
+    /// blocks indefinitely.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()>;
+
+    /// Fail writes with `WouldBlock` or `TimedOut` after `timeout`; `None`
+    /// blocks indefinitely.
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()>;
+}
+
+/// A connected byte stream the frame and peer layers can run over.
+///
+/// Streams from this crate's transports already are [`IpcStream`]s. Any
+/// other implementor becomes one through
+/// [`into_ipc_stream`](Self::into_ipc_stream), which by default wraps it:
+/// reads, writes, clones and timeouts go through these traits, while
+/// credentials are `None`, addresses [`IpcAddr::Unnamed`], and shutdown,
+/// non-blocking mode, buffer sizes and fd passing fail with `Unsupported`.
+/// On Unix the stream must also lend a descriptor that polls readable when
+/// a read would make progress.
+#[cfg(unix)]
+pub trait TransportStream:
+    Read + Write + TryClone + SetTimeouts + std::os::fd::AsFd + Send + Sync + 'static
+{
+    /// Convert into the stream type the frame layer reads and writes.
+    fn into_ipc_stream(self) -> IpcStream {
+        IpcStream::from_custom(self)
+    }
+}
+
+/// A connected byte stream the frame and peer layers can run over.
+///
+/// Streams from this crate's transports already are [`IpcStream`]s. Any
+/// other implementor becomes one through
+/// [`into_ipc_stream`](Self::into_ipc_stream), which by default wraps it:
+/// reads, writes, clones and timeouts go through these traits, while
+/// credentials are `None`, addresses [`IpcAddr::Unnamed`], and shutdown
+/// fails with `Unsupported`.
+#[cfg(not(unix))]
+pub trait TransportStream: Read + Write + TryClone + SetTimeouts + Send + Sync + 'static {
+    /// Convert into the stream type the frame layer reads and writes.
+    fn into_ipc_stream(self) -> IpcStream {
+        IpcStream::from_custom(self)
+    }
+}
+
+/// Accepts connections on some transport; the extension point for serving
+/// peers over one this crate does not ship.
+pub trait Listener {
+    /// The stream each accepted connection comes as.
+    type Stream: TransportStream;
+
+    /// Block until the next client connects.
+    fn accept(&self) -> Result<Self::Stream>;
+
+    /// Wait at most `timeout` for a client; `Ok(None)` if none came.
+    fn accept_timeout(&self, timeout: Duration) -> Result<Option<Self::Stream>>;
+
+    /// Make a blocked accept, and every later one, fail with
+    /// [`TransportError::Shutdown`](crate::TransportError::Shutdown).
+    fn shutdown(&self);
+
+    /// The endpoint, for logs: a socket path, or `scheme://address`.
+    fn path(&self) -> &Path;
+}
+
+impl TryClone for IpcStream {
+    fn try_clone(&self) -> Result<Self> {
+        IpcStream::try_clone(self)
+    }
+}
+
+impl SetTimeouts for IpcStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        IpcStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        IpcStream::set_write_timeout(self, timeout)
+    }
+}
+
+impl TransportStream for IpcStream {
+    fn into_ipc_stream(self) -> IpcStream {
+        self
+    }
+}
+
+#[cfg(unix)]
+impl TryClone for std::os::unix::net::UnixStream {
+    fn try_clone(&self) -> Result<Self> {
+        std::os::unix::net::UnixStream::try_clone(self).map_err(Into::into)
+    }
+}
+
+#[cfg(unix)]
+impl SetTimeouts for std::os::unix::net::UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout).map_err(Into::into)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        std::os::unix::net::UnixStream::set_write_timeout(self, timeout).map_err(Into::into)
+    }
+}
+
+/// Keeps credentials, addresses and fd passing, as for a stream from
+/// [`UnixDomainSocket`](crate::UnixDomainSocket).
+#[cfg(unix)]
+impl TransportStream for std::os::unix::net::UnixStream {
+    fn into_ipc_stream(self) -> IpcStream {
+        IpcStream::from_unix(self)
+    }
+}
+
+/// The object-safe view of a [`TransportStream`] that
+/// [`IpcStreamInner::Custom`] holds.
+trait DynStream: Read + Write + Send + Sync {
+    fn try_clone_boxed(&self) -> Result<Box<dyn DynStream>>;
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()>;
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()>;
+    #[cfg(unix)]
+    fn poll_fd(&self) -> std::os::fd::BorrowedFd<'_>;
+}
+
+impl<T: TransportStream> DynStream for T {
+    fn try_clone_boxed(&self) -> Result<Box<dyn DynStream>> {
+        Ok(Box::new(TryClone::try_clone(self)?))
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        SetTimeouts::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        SetTimeouts::set_write_timeout(self, timeout)
+    }
+
+    #[cfg(unix)]
+    fn poll_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.as_fd()
+    }
+}
+
 #[cfg_attr(not(unix), allow(unused_variables))]
 impl std::fmt::Debug for IpcStream {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.inner {
+        let kind = match &self.inner {
+            #[cfg(unix)]
+            IpcStreamInner::Unix(_) => "unix",
             #[cfg(unix)]
-            IpcStreamInner::Unix(_) => f.debug_struct("IpcStream").field("type", &"unix").finish(),
+            IpcStreamInner::SeqPacket(_) => "unix-seqpacket",
             #[cfg(windows)]
-            IpcStreamInner::NamedPipe(_) => f
-                .debug_struct("IpcStream")
-                .field("type", &"named-pipe")
-                .finish(),
+            IpcStreamInner::NamedPipe(_) => "named-pipe",
+            #[cfg(feature = "tcp")]
+            IpcStreamInner::Tcp(_) => "tcp",
+            #[cfg(all(target_os = "linux", feature = "vsock"))]
+            IpcStreamInner::Vsock(_) => "vsock",
+            IpcStreamInner::Stdio(_) => "stdio",
+            IpcStreamInner::Memory(_) => "memory",
+            IpcStreamInner::Custom(_) => "custom",
+        };
+        let mut dbg = f.debug_struct("IpcStream");
+        dbg.field("type", &kind);
+        if let Ok(addr) = self.local_addr() {
+            dbg.field("local", &format_args!("{addr}"));
+        }
+        if let Ok(addr) = self.peer_addr() {
+            dbg.field("peer", &format_args!("{addr}"));
+        }
+        dbg.finish()
+    }
+}
+
+/// Set `SO_SNDBUF` or `SO_RCVBUF` on socket `fd`.
+#[cfg(unix)]
+pub(crate) fn set_socket_buffer_size(
+    fd: std::os::fd::RawFd,
+    option: libc::c_int,
+    size: usize,
+) -> std::io::Result<()> {
+    let size = libc::c_int::try_from(size).unwrap_or(libc::c_int::MAX);
+    // SAFETY: `size` is readable for the given length; a bad `fd` only fails.
+    let rc = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            option,
+            (&raw const size).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if rc < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Read `SO_SNDBUF` or `SO_RCVBUF` from socket `fd`.
+#[cfg(unix)]
+fn socket_buffer_size(fd: std::os::fd::RawFd, option: libc::c_int) -> std::io::Result<usize> {
+    let mut size: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: `size` is writable for `len` bytes; a bad `fd` only fails.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            option,
+            (&raw mut size).cast(),
+            &mut len,
+        )
+    };
+    if rc < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(usize::try_from(size).unwrap_or(0))
+}
+
+/// `SOCK_STREAM` or `SOCK_SEQPACKET` for an `AF_UNIX` socket `fd`; an
+/// error for anything else.
+#[cfg(unix)]
+pub(crate) fn unix_socket_type(fd: std::os::fd::RawFd) -> Result<libc::c_int> {
+    let invalid = || crate::TransportError::NotUnixSocket { fd };
+
+    // SAFETY: all-zero is a valid `sockaddr_storage`.
+    let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    // SAFETY: `addr` is writable for `len` bytes; a bad `fd` only fails.
+    if unsafe { libc::getsockname(fd, (&raw mut addr).cast(), &mut len) } < 0 {
+        return Err(invalid());
+    }
+    if libc::c_int::from(addr.ss_family) != libc::AF_UNIX {
+        return Err(invalid());
+    }
+
+    let mut socket_type: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: `socket_type` is writable for `len` bytes.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_TYPE,
+            (&raw mut socket_type).cast(),
+            &mut len,
+        )
+    };
+    if rc < 0 || !matches!(socket_type, libc::SOCK_STREAM | libc::SOCK_SEQPACKET) {
+        return Err(invalid());
+    }
+    Ok(socket_type)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::fd::{AsFd, AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+
+    use super::*;
+    use crate::error::TransportError;
+    use crate::fdpass::MAX_FDS_PER_MESSAGE;
+
+    /// `(read end, write end)` of a new pipe.
+    fn pipe() -> (std::fs::File, std::fs::File) {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for the two descriptors.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        // SAFETY: both descriptors are new and owned by nothing else.
+        unsafe {
+            (
+                std::fs::File::from_raw_fd(fds[0]),
+                std::fs::File::from_raw_fd(fds[1]),
+            )
+        }
+    }
+
+    fn read_pipe(mut reader: std::fs::File) -> String {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn pair_is_connected_both_ways() {
+        let (mut a, mut b) = IpcStream::pair().unwrap();
+        a.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        b.write_all(b"pong").unwrap();
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+    }
+
+    #[test]
+    fn pair_ends_have_unnamed_addresses() {
+        let (a, b) = IpcStream::pair().unwrap();
+        assert_eq!(a.local_addr().unwrap(), IpcAddr::Unnamed);
+        assert_eq!(a.peer_addr().unwrap(), IpcAddr::Unnamed);
+        assert_eq!(b.peer_addr().unwrap(), IpcAddr::Unnamed);
+        let debug = format!("{a:?}");
+        assert!(debug.contains("peer: unnamed"), "{debug}");
+
+        let (c, _d) = IpcStream::memory_pair().unwrap();
+        assert_eq!(c.peer_addr().unwrap(), IpcAddr::Unnamed);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn pair_reports_this_process_as_peer() {
+        let (a, _b) = IpcStream::pair().unwrap();
+        let creds = a.peer_credentials().unwrap();
+        // SAFETY: `geteuid`/`getegid` have no preconditions and cannot fail.
+        assert_eq!(creds.uid, unsafe { libc::geteuid() });
+        assert_eq!(creds.gid, unsafe { libc::getegid() });
+        assert_eq!(creds.pid, Some(std::process::id()));
+    }
+
+    #[test]
+    fn nonblocking_reads_would_block_until_poll_reports_data() {
+        let (mut a, mut b) = IpcStream::pair().unwrap();
+        b.set_nonblocking(true).unwrap();
+        let mut buf = [0u8; 4];
+        let err = b.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        let mut pollfd = libc::pollfd {
+            fd: b.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: one valid `pollfd`.
+        assert_eq!(unsafe { libc::poll(&mut pollfd, 1, 0) }, 0);
+        a.write_all(b"ping").unwrap();
+        // SAFETY: as above.
+        assert_eq!(unsafe { libc::poll(&mut pollfd, 1, 5000) }, 1);
+        assert_ne!(pollfd.revents & libc::POLLIN, 0);
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        b.set_nonblocking(false).unwrap();
+        b.set_read_timeout(Some(std::time::Duration::from_millis(20)))
+            .unwrap();
+        let err = b.read(&mut buf).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn write_shutdown_leaves_the_read_half_open() {
+        let (mut client, mut server) = IpcStream::pair().unwrap();
+        client.write_all(b"last").unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        assert!(client.write_all(b"more").is_err());
+
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).unwrap();
+        assert_eq!(received, b"last");
+        server.write_all(b"reply").unwrap();
+        let mut buf = [0u8; 5];
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"reply");
+    }
+
+    #[test]
+    fn from_inherited_fd_adopts_unix_sockets_only() {
+        let (mut a, b) = IpcStream::pair().unwrap();
+        let IpcStreamInner::Unix(stream) = b.inner else {
+            unreachable!("pair() returns stream sockets")
+        };
+        let raw = stream.into_raw_fd();
+        // SAFETY: `raw` was just released by the stream that owned it.
+        let mut adopted = unsafe { IpcStream::from_inherited_fd(raw) }.unwrap();
+        // SAFETY: `raw` is open and owned by `adopted`.
+        let flags = unsafe { libc::fcntl(adopted.as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+        adopted.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        let file = std::fs::File::open("/dev/null").unwrap();
+        // SAFETY: the call fails without taking ownership of a non-socket.
+        let err = unsafe { IpcStream::from_inherited_fd(file.as_raw_fd()) }.unwrap_err();
+        assert!(err.to_string().contains("not a unix"), "{err}");
+        drop(file);
+
+        let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        // SAFETY: as above.
+        assert!(unsafe { IpcStream::from_inherited_fd(tcp.as_raw_fd()) }.is_err());
+    }
+
+    #[test]
+    fn from_owned_fd_checks_the_socket_type() {
+        let (mut a, b) = IpcStream::pair().unwrap();
+        let owned = b.as_fd().try_clone_to_owned().unwrap();
+        drop(b);
+        let mut rewrapped = IpcStream::from_owned_fd(owned).unwrap();
+        assert!(!rewrapped.is_message_oriented());
+        rewrapped.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        #[cfg(target_os = "linux")]
+        {
+            let mut fds = [0; 2];
+            // SAFETY: `fds` has room for the two descriptors.
+            let rc = unsafe {
+                libc::socketpair(libc::AF_UNIX, libc::SOCK_SEQPACKET, 0, fds.as_mut_ptr())
+            };
+            assert_eq!(rc, 0);
+            // SAFETY: both descriptors are new and owned by nothing else.
+            let (left, _right) =
+                unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+            let stream = IpcStream::from_owned_fd(left).unwrap();
+            assert!(stream.is_message_oriented());
+        }
+
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let fd = file.as_raw_fd();
+        match IpcStream::from_owned_fd(OwnedFd::from(file)) {
+            Err(TransportError::NotUnixSocket { fd: reported }) => assert_eq!(reported, fd),
+            other => panic!("expected NotUnixSocket, got {other:?}"),
+        }
+        let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        assert!(matches!(
+            IpcStream::from_owned_fd(OwnedFd::from(tcp)),
+            Err(TransportError::NotUnixSocket { .. })
+        ));
+    }
+
+    #[test]
+    fn passed_pipe_fd_writes_into_the_senders_pipe() {
+        let (mut server, mut client) = IpcStream::pair().unwrap();
+        let (reader, writer) = pipe();
+        assert_eq!(server.send_with_fds(b"log", &[writer.as_fd()]).unwrap(), 3);
+        drop(writer);
+
+        let mut buf = [0u8; 8];
+        let mut fds = Vec::new();
+        let len = client.recv_with_fds(&mut buf, &mut fds).unwrap();
+        assert_eq!(&buf[..len], b"log");
+        assert_eq!(fds.len(), 1);
+        // SAFETY: F_GETFD on a descriptor we own.
+        let flags = unsafe { libc::fcntl(fds[0].as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+        let mut passed = std::fs::File::from(fds.pop().unwrap());
+        passed.write_all(b"rotated\n").unwrap();
+        drop(passed);
+        assert_eq!(read_pipe(reader), "rotated\n");
+    }
+
+    #[test]
+    fn attached_fds_ride_on_the_next_write() {
+        let (mut server, mut client) = IpcStream::pair().unwrap();
+        client.set_receive_fds(true).unwrap();
+        let (reader, writer) = pipe();
+
+        server.attach_fds(&[writer.as_fd()]).unwrap();
+        drop(writer);
+        server.write_all(b"one").unwrap();
+        server.write_all(b"two").unwrap();
+        assert!(server.detach_fds().is_empty());
+
+        let mut buf = [0u8; 6];
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"onetwo");
+        assert!(client.take_received_fds(2).is_none());
+        let fds = client.take_received_fds(1).unwrap();
+        assert!(client.take_received_fds(1).is_none());
+
+        std::fs::File::from(fds.into_iter().next().unwrap())
+            .write_all(b"via read")
+            .unwrap();
+        assert_eq!(read_pipe(reader), "via read");
+    }
+
+    #[test]
+    fn fd_count_is_limited_per_message() {
+        let (mut server, mut client) = IpcStream::pair().unwrap();
+        let (_reader, writer) = pipe();
+        let fds = vec![writer.as_fd(); MAX_FDS_PER_MESSAGE + 1];
+
+        let err = server.send_with_fds(b"x", &fds).unwrap_err();
+        assert!(
+            matches!(err, TransportError::TooManyFds { count, max }
+                if count == MAX_FDS_PER_MESSAGE + 1 && max == MAX_FDS_PER_MESSAGE),
+            "{err}"
+        );
+        assert!(server.attach_fds(&fds).is_err());
+        server.attach_fds(&fds[..MAX_FDS_PER_MESSAGE]).unwrap();
+        assert!(server.attach_fds(&fds[..1]).is_err());
+        assert_eq!(server.detach_fds().len(), MAX_FDS_PER_MESSAGE);
+
+        // A sender that skips the check overflows the receive buffer.
+        crate::fdpass::send(server.as_raw_fd(), &[IoSlice::new(b"x")], &fds).unwrap();
+        let mut received = Vec::new();
+        let err = client
+            .recv_with_fds(&mut [0u8; 1], &mut received)
+            .unwrap_err();
+        assert!(matches!(err, TransportError::FdsTruncated), "{err}");
+        assert!(received.is_empty());
+    }
+
+    #[test]
+    fn fd_passing_needs_a_unix_stream_socket() {
+        let (a, _b) = std::os::unix::net::UnixStream::pair().unwrap();
+        let mut seqpacket = IpcStream::from_unix_seqpacket(a);
+        let file = OwnedFd::from(std::fs::File::open("/dev/null").unwrap());
+        let err = seqpacket.send_with_fds(b"x", &[file.as_fd()]).unwrap_err();
+        assert!(err.to_string().contains("unix stream socket"), "{err}");
+        assert!(seqpacket.set_receive_fds(true).is_err());
+    }
+
+    /// A stream type from outside the crate, as a custom transport would
+    /// bring.
+    struct Wrapped(std::os::unix::net::UnixStream);
+
+    impl Read for Wrapped {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
         }
     }
+
+    impl Write for Wrapped {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl TryClone for Wrapped {
+        fn try_clone(&self) -> Result<Self> {
+            Ok(Self(self.0.try_clone()?))
+        }
+    }
+
+    impl SetTimeouts for Wrapped {
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+            Ok(self.0.set_read_timeout(timeout)?)
+        }
+
+        fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+            Ok(self.0.set_write_timeout(timeout)?)
+        }
+    }
+
+    impl AsFd for Wrapped {
+        fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+            self.0.as_fd()
+        }
+    }
+
+    impl TransportStream for Wrapped {}
+
+    #[test]
+    fn custom_streams_run_through_the_transport_traits() {
+        let (a, b) = std::os::unix::net::UnixStream::pair().unwrap();
+        let fd = a.as_raw_fd();
+        let mut a = Wrapped(a).into_ipc_stream();
+        let mut b = Wrapped(b).into_ipc_stream();
+        assert!(format!("{a:?}").contains("custom"));
+        assert_eq!(a.as_raw_fd(), fd);
+
+        let mut a2 = a.try_clone().unwrap();
+        a2.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        a.set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+        let err = a.read(&mut buf).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ));
+
+        // Socket-level features are out of reach behind the trait.
+        assert!(a.peer_credentials().is_none());
+        assert_eq!(a.peer_addr().unwrap(), IpcAddr::Unnamed);
+        assert!(a.shutdown(std::net::Shutdown::Write).is_err());
+        assert!(a.send_buffer_size().is_err());
+
+        // A plain UnixStream keeps them.
+        let (c, _d) = std::os::unix::net::UnixStream::pair().unwrap();
+        let c = c.into_ipc_stream();
+        assert!(format!("{c:?}").contains("\"unix\""));
+        assert!(c.shutdown(std::net::Shutdown::Write).is_ok());
+    }
 }
@@ -1,23 +1,377 @@
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
 use std::os::unix::net::UnixListener;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use tracing::{debug, info};
-
+use crate::addr::IpcAddr;
 use crate::error::{Result, TransportError};
-use crate::traits::IpcStream;
+use crate::trace::{debug, info};
+use crate::traits::{IpcStream, Listener};
+
+/// How often [`UnixDomainSocket::connect_timeout`] retries while the
+/// listener's backlog is full.
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Callback invoked with the socket path when a [`PathWatchdog`] finds it
+/// missing or replaced.
+pub type PathLostCallback = Arc<dyn Fn(&Path) + Send + Sync>;
+
+/// Periodic check that a listener's socket path still names its socket.
+///
+/// While [`UnixDomainSocket::accept`] waits, the path is stat'ed every
+/// `interval` and its dev/ino compared with the socket created at bind time.
+/// On a mismatch `on_lost` is called once, then the listener either rebinds
+/// (`rebind: true`) or fails every accept with [`TransportError::PathLost`].
+#[derive(Clone)]
+pub struct PathWatchdog {
+    /// How often the path is checked while accepting.
+    pub interval: Duration,
+    /// Re-create the socket at the path and keep accepting.
+    ///
+    /// The new socket is bound beside the path and renamed over it, so
+    /// clients never see a half-created path. A non-socket file that took
+    /// the path's place is never replaced.
+    pub rebind: bool,
+    /// Called once each time the path is found missing or replaced.
+    pub on_lost: Option<PathLostCallback>,
+}
+
+impl PathWatchdog {
+    /// Check every `interval` and fail accepts once the path is lost.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            rebind: false,
+            on_lost: None,
+        }
+    }
+}
+
+impl From<Duration> for PathWatchdog {
+    fn from(interval: Duration) -> Self {
+        Self::new(interval)
+    }
+}
+
+impl std::fmt::Debug for PathWatchdog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PathWatchdog")
+            .field("interval", &self.interval)
+            .field("rebind", &self.rebind)
+            .field("on_lost", &self.on_lost.as_ref().map(|_| "<callback>"))
+            .finish()
+    }
+}
+
+/// Iterator over a listener's connections, from
+/// [`UnixDomainSocket::incoming`].
+pub struct Incoming<'a> {
+    listener: &'a UnixDomainSocket,
+}
+
+impl Iterator for Incoming<'_> {
+    type Item = Result<IpcStream>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.listener.accept() {
+            Err(TransportError::Shutdown) => None,
+            result => Some(result),
+        }
+    }
+}
+
+/// How [`UnixDomainSocket::bind_with`] sets up a filesystem socket.
+///
+/// ```no_run
+/// # use ipcprims_transport::{BindOptions, UnixDomainSocket};
+/// let listener = UnixDomainSocket::bind_with(
+///     "/run/myapp/myapp.sock",
+///     BindOptions::new().mode(0o660).backlog(256).owner_group("myapp-sock"),
+/// )?;
+/// # Ok::<(), ipcprims_transport::TransportError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindOptions {
+    mode: u32,
+    backlog: Option<u32>,
+    owner_group: Option<String>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    force: bool,
+    lock_file: bool,
+}
+
+impl BindOptions {
+    /// Mode `0o600`, the platform's default backlog and the caller's group.
+    pub fn new() -> Self {
+        Self {
+            mode: UnixDomainSocket::DEFAULT_SOCKET_MODE,
+            backlog: None,
+            owner_group: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            force: false,
+            lock_file: true,
+        }
+    }
+
+    /// Permission bits for the socket path.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Queue length for connections not yet accepted, passed to
+    /// `listen(2)`. The kernel may cap it (`net.core.somaxconn` on Linux).
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = Some(backlog);
+        self
+    }
+
+    /// Group to `chown` the socket path to, by name or numeric gid.
+    ///
+    /// Combine with a mode such as `0o660` to let the group's members
+    /// connect. Unless privileged, the caller must belong to the group.
+    pub fn owner_group(mut self, group: impl Into<String>) -> Self {
+        self.owner_group = Some(group.into());
+        self
+    }
+
+    /// `SO_SNDBUF` for the listener and every accepted stream, instead of
+    /// the system default. The kernel may adjust it; see
+    /// [`IpcStream::set_send_buffer_size`].
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// `SO_RCVBUF` for the listener and every accepted stream, instead of
+    /// the system default.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Replace an existing socket at the path even if a listener is still
+    /// accepting on it (default off).
+    ///
+    /// Without it, bind probes an existing socket with a connect and fails
+    /// with [`TransportError::AddressInUse`] if anything answers, or if
+    /// another listener holds the lock file. With it, a held lock file is
+    /// passed over and the new listener goes without one.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Hold an exclusive `flock` on a companion `<path>.lock` file for the
+    /// listener's lifetime (default on).
+    ///
+    /// The lock is taken before the stale-socket check, so two processes
+    /// cannot both judge a socket stale and race to replace it; the loser
+    /// fails with [`TransportError::AddressInUse`]. Drop removes the lock
+    /// file if the path still names the file it locked. Turn it off where
+    /// the socket directory cannot take another file.
+    pub fn lock_file(mut self, enabled: bool) -> Self {
+        self.lock_file = enabled;
+        self
+    }
+}
+
+impl Default for BindOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How [`UnixDomainSocket::connect_with`] connects to a filesystem socket.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use ipcprims_transport::{ConnectOptions, UnixDomainSocket};
+/// let stream = UnixDomainSocket::connect_with(
+///     "/run/myapp/myapp.sock",
+///     ConnectOptions::new()
+///         .timeout(Duration::from_secs(2))
+///         .send_buffer_size(1 << 20),
+/// )?;
+/// # Ok::<(), ipcprims_transport::TransportError>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectOptions {
+    timeout: Option<Duration>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    owner_uid: Option<u32>,
+    mode_max: Option<u32>,
+    peer_uid: Option<u32>,
+}
+
+impl ConnectOptions {
+    /// A blocking connect with the system's buffer sizes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give up after `timeout`, as [`UnixDomainSocket::connect_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// `SO_SNDBUF` for the connected stream, instead of the system default.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// `SO_RCVBUF` for the connected stream, instead of the system default.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Refuse a socket not owned by `uid`, or in a directory that is
+    /// neither owned by `uid` nor by root, or that others may write to
+    /// without the sticky bit. Guards against another local user planting
+    /// a socket in a shared directory.
+    pub fn require_owner_uid(mut self, uid: u32) -> Self {
+        self.owner_uid = Some(uid);
+        self
+    }
+
+    /// Refuse a socket whose permission bits go beyond `mode`, e.g. `0o600`
+    /// to insist that only the owner can connect.
+    pub fn require_mode_max(mut self, mode: u32) -> Self {
+        self.mode_max = Some(mode);
+        self
+    }
+
+    /// Once connected, refuse a server whose peer credentials do not report
+    /// `uid`. Unlike the path checks this cannot race a swapped socket, but
+    /// needs a platform with peer credentials (Linux, macOS).
+    pub fn require_peer_uid(mut self, uid: u32) -> Self {
+        self.peer_uid = Some(uid);
+        self
+    }
+
+    /// Check the owner and mode requirements against the socket at `path`
+    /// and its directory, failing with [`TransportError::UntrustedEndpoint`].
+    ///
+    /// [`UnixDomainSocket::connect_with`] does this before connecting;
+    /// callers that connect some other way can run it themselves. A path
+    /// that cannot be inspected fails with [`TransportError::NotFound`],
+    /// [`TransportError::PermissionDenied`] or [`TransportError::Connect`].
+    pub fn check_path(&self, path: &Path) -> Result<()> {
+        if self.owner_uid.is_none() && self.mode_max.is_none() {
+            return Ok(());
+        }
+        let untrusted = |detail: String| TransportError::UntrustedEndpoint {
+            path: path.to_path_buf(),
+            detail,
+        };
+        let stat =
+            |path: &Path| std::fs::metadata(path).map_err(|e| TransportError::connect(path, e));
+
+        let socket = stat(path)?;
+        if !socket.file_type().is_socket() {
+            return Err(untrusted("not a socket".to_string()));
+        }
+        if let Some(uid) = self.owner_uid {
+            if socket.uid() != uid {
+                return Err(untrusted(format!(
+                    "socket is owned by uid {}, expected {uid}",
+                    socket.uid()
+                )));
+            }
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            // `S_ISVTX`, spelled out since `mode_t` differs by platform.
+            const STICKY: u32 = 0o1000;
+            let dir_meta = stat(dir)?;
+            if dir_meta.uid() != uid && dir_meta.uid() != 0 {
+                return Err(untrusted(format!(
+                    "directory {} is owned by uid {}, expected {uid} or root",
+                    dir.display(),
+                    dir_meta.uid()
+                )));
+            }
+            if dir_meta.mode() & 0o022 != 0 && dir_meta.mode() & STICKY == 0 {
+                return Err(untrusted(format!(
+                    "directory {} is writable by others without the sticky bit",
+                    dir.display()
+                )));
+            }
+        }
+        if let Some(max) = self.mode_max {
+            let mode = socket.mode() & 0o777;
+            if mode & !max != 0 {
+                return Err(untrusted(format!(
+                    "socket mode {mode:o} allows more than {max:o}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the peer uid requirement against a stream connected to
+    /// `path`, failing with [`TransportError::UntrustedEndpoint`], also
+    /// when the platform or transport reports no credentials.
+    pub fn check_peer(&self, path: &Path, stream: &IpcStream) -> Result<()> {
+        let Some(uid) = self.peer_uid else {
+            return Ok(());
+        };
+        let detail = match stream.peer_credentials() {
+            Some(creds) if creds.uid == uid => return Ok(()),
+            Some(creds) => format!("server runs as uid {}, expected {uid}", creds.uid),
+            None => "server credentials are unavailable".to_string(),
+        };
+        Err(TransportError::UntrustedEndpoint {
+            path: path.to_path_buf(),
+            detail,
+        })
+    }
+}
+
+/// The listening socket and the identity of the path it created.
+struct Bound {
+    listener: UnixListener,
+    created_inode: Option<(u64, u64)>,
+}
 
 /// Unix domain socket transport.
 ///
-/// Provides bind/accept/connect over filesystem-path UDS on Linux and macOS.
-/// On Linux, abstract namespace sockets are preferred (no filesystem cleanup).
-/// On macOS, filesystem paths are used with automatic cleanup via `Drop`.
+/// Provides bind/accept/connect over filesystem-path UDS on Linux and macOS,
+/// with the path removed again on `Drop`. On Linux,
+/// [`bind_abstract`](Self::bind_abstract) and
+/// [`connect_abstract`](Self::connect_abstract) use the abstract namespace
+/// instead: the name lives only in the kernel, so there is nothing to clean
+/// up, but also no file permissions guarding it.
+/// [`bind_seqpacket`](Self::bind_seqpacket) and
+/// [`connect_seqpacket`](Self::connect_seqpacket) create `SOCK_SEQPACKET`
+/// sockets that keep message boundaries.
 pub struct UnixDomainSocket {
-    listener: UnixListener,
+    /// Swapped out only when a [`PathWatchdog`] rebinds.
+    bound: RwLock<Bound>,
     path: PathBuf,
-    created_inode: Option<(u64, u64)>,
+    options: BindOptions,
     /// Whether the path should be removed on drop (filesystem sockets only).
     cleanup_on_drop: bool,
+    watchdog: Option<PathWatchdog>,
+    /// Set once the watchdog has reported the path lost; cleared by a rebind.
+    path_lost: AtomicBool,
+    shut_down: AtomicBool,
+    /// Bound in the abstract namespace; `path` is the `@name` form.
+    is_abstract: bool,
+    /// `SOCK_SEQPACKET` rather than `SOCK_STREAM`.
+    seqpacket: bool,
+    /// Held until drop, which runs after the socket path is cleaned up.
+    lock: Option<LockFile>,
 }
 
 impl UnixDomainSocket {
@@ -34,174 +388,1218 @@ impl UnixDomainSocket {
 
     /// Bind and listen on a filesystem-path Unix domain socket.
     ///
-    /// The socket file is created at `path`. If the file already exists and is
-    /// a socket, it is removed first (stale socket cleanup).
+    /// The socket file is created at `path`. If a socket file already
+    /// exists there, bind connects to it first: a live listener fails the
+    /// bind with [`TransportError::AddressInUse`], while a stale file
+    /// (connection refused) is removed. The live listener sees that probe
+    /// as a client that hangs up at once. Other files are never removed.
     pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
-        Self::bind_with_mode(path, Self::DEFAULT_SOCKET_MODE)
+        Self::bind_with(path, BindOptions::new())
     }
 
     /// Bind and listen on a filesystem-path Unix domain socket with explicit mode.
     pub fn bind_with_mode(path: impl AsRef<Path>, mode: u32) -> Result<Self> {
-        let path = path.as_ref().to_path_buf();
+        Self::bind_with(path, BindOptions::new().mode(mode))
+    }
 
-        // Validate path length
-        let path_bytes = path.as_os_str().len();
-        if path_bytes >= Self::MAX_PATH_LEN {
-            return Err(TransportError::PathTooLong {
-                path,
-                len: path_bytes,
-                max: Self::MAX_PATH_LEN,
-            });
-        }
+    /// Bind and listen on a filesystem-path Unix domain socket set up as
+    /// `options` asks: mode, `listen(2)` backlog and owning group.
+    ///
+    /// A group that does not exist or cannot be set fails with
+    /// [`TransportError::Chown`], and the socket path is removed again.
+    pub fn bind_with(path: impl AsRef<Path>, options: BindOptions) -> Result<Self> {
+        Self::bind_path(path.as_ref(), options, false)
+    }
+
+    /// Bind and listen on a filesystem-path `SOCK_SEQPACKET` socket.
+    ///
+    /// Like [`bind`](Self::bind), but accepted streams keep message
+    /// boundaries: each write arrives as one message, see
+    /// [`IpcStream::is_message_oriented`]. Clients must connect with
+    /// [`connect_seqpacket`](Self::connect_seqpacket). Supported on Linux
+    /// and the BSDs, not macOS.
+    pub fn bind_seqpacket(path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_path(path.as_ref(), BindOptions::new(), true)
+    }
+
+    fn bind_path(path: &Path, options: BindOptions, seqpacket: bool) -> Result<Self> {
+        let path = path.to_path_buf();
+        Self::check_path_len(&path)?;
+        let lock = if options.lock_file {
+            LockFile::acquire(&path, options.force)?
+        } else {
+            None
+        };
+        let bound = Self::bind_listener(&path, &options, seqpacket)?;
+
+        info!(
+            transport = if seqpacket { "unix-seqpacket" } else { "uds" },
+            ?path,
+            "listening on unix domain socket"
+        );
 
-        // Remove stale socket if it exists, but never remove non-socket files.
-        if path.exists() {
-            let metadata = std::fs::symlink_metadata(&path).map_err(|e| TransportError::Bind {
+        Ok(Self {
+            bound: RwLock::new(bound),
+            path,
+            options,
+            cleanup_on_drop: true,
+            watchdog: None,
+            path_lost: AtomicBool::new(false),
+            shut_down: AtomicBool::new(false),
+            is_abstract: false,
+            seqpacket,
+            lock,
+        })
+    }
+
+    /// Bind and listen on `name` in the Linux abstract socket namespace.
+    ///
+    /// No filesystem entry is created, so there is no path length beyond the
+    /// 107 bytes `sun_path` leaves after the leading NUL, no mode and nothing
+    /// to remove on drop. Any local process in the same network namespace
+    /// can connect; check [`IpcStream::peer_credentials`] if that matters.
+    /// Elsewhere this fails with [`std::io::ErrorKind::Unsupported`].
+    pub fn bind_abstract(name: &str) -> Result<Self> {
+        let path = PathBuf::from(format!("@{name}"));
+        Self::check_abstract_name_len(name, &path)?;
+        let listener = abstract_addr(name)
+            .and_then(|addr| UnixListener::bind_addr(&addr))
+            .map_err(|e| TransportError::Bind {
                 path: path.clone(),
                 source: e,
             })?;
-            if metadata.file_type().is_socket() {
-                debug!(?path, "removing stale socket");
-                std::fs::remove_file(&path).map_err(|e| TransportError::Bind {
-                    path: path.clone(),
-                    source: e,
-                })?;
-            } else {
-                return Err(TransportError::Bind {
-                    path: path.clone(),
-                    source: std::io::Error::new(
-                        std::io::ErrorKind::AlreadyExists,
-                        "existing path is not a unix socket",
-                    ),
-                });
-            }
+
+        info!(
+            transport = "unix-abstract",
+            ?path,
+            "listening on abstract unix socket"
+        );
+
+        Ok(Self {
+            bound: RwLock::new(Bound {
+                listener,
+                created_inode: None,
+            }),
+            path,
+            options: BindOptions::new(),
+            cleanup_on_drop: false,
+            watchdog: None,
+            path_lost: AtomicBool::new(false),
+            shut_down: AtomicBool::new(false),
+            is_abstract: true,
+            seqpacket: false,
+            lock: None,
+        })
+    }
+
+    /// The abstract socket name in an `@name` address, or `None` for a
+    /// filesystem path.
+    ///
+    /// Callers that take a path, such as the peer listener and connector,
+    /// use this to route `@name` to the `*_abstract` functions.
+    pub fn abstract_name(path: &Path) -> Option<&str> {
+        path.to_str()?
+            .strip_prefix('@')
+            .filter(|name| !name.is_empty())
+    }
+
+    /// The `<path>.lock` file this listener holds, if any; see
+    /// [`BindOptions::lock_file`].
+    pub fn lock_path(&self) -> Option<&Path> {
+        self.lock.as_ref().map(LockFile::path)
+    }
+
+    /// Adopt a listening socket from a previous instance, for a restart
+    /// that drops no connections.
+    ///
+    /// The old instance exports it with
+    /// [`into_listener_fd`](Self::into_listener_fd) and hands it over with
+    /// [`IpcStream::send_with_fds`] or across `exec`. The descriptor must
+    /// be a listening `AF_UNIX` stream or seqpacket socket; anything else
+    /// fails with [`TransportError::NotUnixSocket`], closing it. The path
+    /// is read from the socket's address. A filesystem path is removed on
+    /// drop as if this listener had bound it, and its `<path>.lock` is
+    /// taken unless another process still holds it. Clients queued during
+    /// the handoff are accepted here; a rebinding [`PathWatchdog`] uses
+    /// the default [`BindOptions`].
+    pub fn from_listener_fd(fd: OwnedFd) -> Result<Self> {
+        let raw = fd.as_raw_fd();
+        let socket_type = crate::traits::unix_socket_type(raw)?;
+        if !is_listening(raw) {
+            return Err(TransportError::NotUnixSocket { fd: raw });
         }
+        let listener = UnixListener::from(fd);
+        let seqpacket = socket_type == libc::SOCK_SEQPACKET;
 
-        let listener = UnixListener::bind(&path).map_err(|e| TransportError::Bind {
-            path: path.clone(),
-            source: e,
+        let (path, created_inode, is_abstract, lock) = match listener.local_addr()?.into() {
+            IpcAddr::Path(path) => {
+                let created_inode = std::fs::symlink_metadata(&path)
+                    .ok()
+                    .filter(|metadata| metadata.file_type().is_socket())
+                    .map(|metadata| (metadata.dev(), metadata.ino()));
+                let lock = LockFile::acquire(&path, true)?;
+                (path, created_inode, false, lock)
+            }
+            IpcAddr::Abstract(name) => {
+                let path = PathBuf::from(format!("@{}", String::from_utf8_lossy(&name)));
+                (path, None, true, None)
+            }
+            _ => return Err(TransportError::NotUnixSocket { fd: raw }),
+        };
+
+        info!(
+            transport = if seqpacket { "unix-seqpacket" } else { "uds" },
+            ?path,
+            "adopted listening unix domain socket"
+        );
+
+        Ok(Self {
+            bound: RwLock::new(Bound {
+                listener,
+                created_inode,
+            }),
+            path,
+            options: BindOptions::new(),
+            cleanup_on_drop: !is_abstract,
+            watchdog: None,
+            path_lost: AtomicBool::new(false),
+            shut_down: AtomicBool::new(false),
+            is_abstract,
+            seqpacket,
+            lock,
+        })
+    }
+
+    /// Give up the listening socket for another process to adopt with
+    /// [`from_listener_fd`](Self::from_listener_fd).
+    ///
+    /// The socket keeps listening, so clients that connect meanwhile queue
+    /// for the new owner, and the path is left in place. The lock file is
+    /// released. Streams already accepted are unaffected.
+    pub fn into_listener_fd(mut self) -> Result<OwnedFd> {
+        self.cleanup_on_drop = false;
+        let bound = self.bound.get_mut().unwrap_or_else(|e| e.into_inner());
+        let fd = bound.listener.as_fd().try_clone_to_owned()?;
+        debug!(transport = "uds", path = ?self.path, "exported listening socket");
+        Ok(fd)
+    }
+
+    /// Watch the bound path while accepting; see [`PathWatchdog`].
+    ///
+    /// Pass a `Duration` for the default behavior (fail with
+    /// [`TransportError::PathLost`]) or a full `PathWatchdog`. Ignored for
+    /// abstract sockets, which have no path to lose.
+    pub fn with_path_watchdog(mut self, watchdog: impl Into<PathWatchdog>) -> Self {
+        self.watchdog = Some(watchdog.into());
+        self
+    }
+
+    fn bind_listener(path: &Path, options: &BindOptions, seqpacket: bool) -> Result<Bound> {
+        Self::check_path_len(path)?;
+
+        remove_stale_socket(path, options.force)?;
+
+        let listener = if seqpacket {
+            with_sun_path(path, crate::seqpacket::listen)
+        } else {
+            with_sun_path(path, |path| UnixListener::bind(path))
+        };
+        let listener = listener.map_err(|e| match e.kind() {
+            // Another bind won the race since the stale check.
+            std::io::ErrorKind::AddrInUse => TransportError::AddressInUse {
+                path: path.to_path_buf(),
+            },
+            _ => TransportError::Bind {
+                path: path.to_path_buf(),
+                source: e,
+            },
         })?;
 
-        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).map_err(|e| {
-            TransportError::Bind {
-                path: path.clone(),
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(options.mode)).map_err(
+            |e| TransportError::Bind {
+                path: path.to_path_buf(),
                 source: e,
-            }
+            },
+        )?;
+        set_buffer_sizes(
+            listener.as_raw_fd(),
+            options.send_buffer_size,
+            options.recv_buffer_size,
+        )
+        .map_err(|e| TransportError::Bind {
+            path: path.to_path_buf(),
+            source: e,
         })?;
+        if let Some(backlog) = options.backlog {
+            let backlog = libc::c_int::try_from(backlog).unwrap_or(libc::c_int::MAX);
+            // SAFETY: the fd is the bound socket owned by `listener`; calling
+            // listen again on a listening socket only updates its backlog.
+            if unsafe { libc::listen(listener.as_raw_fd(), backlog) } != 0 {
+                return Err(TransportError::Bind {
+                    path: path.to_path_buf(),
+                    source: std::io::Error::last_os_error(),
+                });
+            }
+        }
+        if let Some(group) = &options.owner_group {
+            if let Err(e) =
+                group_id(group).and_then(|gid| std::os::unix::fs::chown(path, None, Some(gid)))
+            {
+                let _ = std::fs::remove_file(path);
+                return Err(TransportError::Chown {
+                    path: path.to_path_buf(),
+                    group: group.clone(),
+                    source: e,
+                });
+            }
+        }
         let created_metadata =
-            std::fs::symlink_metadata(&path).map_err(|e| TransportError::Bind {
-                path: path.clone(),
+            std::fs::symlink_metadata(path).map_err(|e| TransportError::Bind {
+                path: path.to_path_buf(),
                 source: e,
             })?;
         let created_inode = Some((created_metadata.dev(), created_metadata.ino()));
 
-        info!(?path, "listening on unix domain socket");
-
-        Ok(Self {
+        Ok(Bound {
             listener,
-            path,
             created_inode,
-            cleanup_on_drop: true,
         })
     }
 
     /// Accept an incoming connection (blocking).
+    ///
+    /// With a [`PathWatchdog`], the path is checked before each wait and at
+    /// least once per interval while no client connects. Fails with
+    /// [`TransportError::Shutdown`] once [`Self::shutdown`] has been called.
     pub fn accept(&self) -> Result<IpcStream> {
-        let (stream, _addr) = self.listener.accept().map_err(TransportError::Accept)?;
-        debug!("accepted connection");
-        Ok(IpcStream::from_unix(stream))
-    }
-
-    /// Connect to a listening Unix domain socket (blocking).
-    pub fn connect(path: impl AsRef<Path>) -> Result<IpcStream> {
-        let path = path.as_ref();
-        let stream =
-            std::os::unix::net::UnixStream::connect(path).map_err(|e| TransportError::Connect {
-                path: path.to_path_buf(),
-                source: e,
-            })?;
-        debug!(?path, "connected to unix domain socket");
-        Ok(IpcStream::from_unix(stream))
+        loop {
+            if let Some(stream) = self.accept_until(None)? {
+                return Ok(stream);
+            }
+        }
     }
 
-    /// The path this socket is bound to.
-    pub fn path(&self) -> &Path {
-        &self.path
+    /// Accept an incoming connection, waiting at most `timeout`.
+    ///
+    /// Returns `Ok(None)` if no client connected in time, so a caller can
+    /// check its own stop condition between attempts. Otherwise behaves
+    /// like [`Self::accept`].
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        self.accept_until(Some(Instant::now() + timeout))
     }
 
-    /// Transport name for diagnostics.
-    pub fn transport_name(&self) -> &'static str {
-        "unix-domain-socket"
-    }
-}
+    fn accept_until(&self, deadline: Option<Instant>) -> Result<Option<IpcStream>> {
+        let watchdog = self.watchdog.as_ref().filter(|_| !self.is_abstract);
+        loop {
+            self.ensure_running()?;
+            if let Some(watchdog) = watchdog {
+                self.check_path(watchdog)?;
+            }
 
-impl Drop for UnixDomainSocket {
-    fn drop(&mut self) {
-        if self.cleanup_on_drop {
-            if let Some((expected_dev, expected_ino)) = self.created_inode {
-                if let Ok(metadata) = std::fs::symlink_metadata(&self.path) {
-                    if metadata.file_type().is_socket()
-                        && metadata.dev() == expected_dev
-                        && metadata.ino() == expected_ino
-                    {
-                        debug!(path = ?self.path, "cleaning up socket file");
-                        let _ = std::fs::remove_file(&self.path);
-                    } else {
-                        debug!(
-                            path = ?self.path,
-                            "socket path identity changed; skipping cleanup"
-                        );
+            let bound = self.bound.read().unwrap_or_else(|e| e.into_inner());
+            // Non-blocking so a client taken by a concurrent accept cannot
+            // park this one past the next path check or the deadline.
+            bound
+                .listener
+                .set_nonblocking(true)
+                .map_err(TransportError::Accept)?;
+            match bound.listener.accept() {
+                Ok((stream, _addr)) => {
+                    // The connection may be the wake-up from `shutdown`.
+                    self.ensure_running()?;
+                    // Accepted sockets inherit O_NONBLOCK on BSD-derived systems.
+                    stream
+                        .set_nonblocking(false)
+                        .map_err(TransportError::Accept)?;
+                    set_buffer_sizes(
+                        stream.as_raw_fd(),
+                        self.options.send_buffer_size,
+                        self.options.recv_buffer_size,
+                    )
+                    .map_err(TransportError::Accept)?;
+                    debug!(transport = "uds", "accepted connection");
+                    return Ok(Some(self.wrap(stream)));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    let remaining =
+                        deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                    if remaining == Some(Duration::ZERO) {
+                        return Ok(None);
                     }
+                    let wait = match (remaining, watchdog) {
+                        (Some(remaining), Some(watchdog)) => Some(remaining.min(watchdog.interval)),
+                        (remaining, watchdog) => remaining.or(watchdog.map(|w| w.interval)),
+                    };
+                    wait_readable(&bound.listener, wait).map_err(TransportError::Accept)?;
                 }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(TransportError::Accept(err)),
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::{Read, Write};
+    /// Iterate over incoming connections, as [`Self::accept`] would return
+    /// them.
+    ///
+    /// Accept errors are yielded rather than ending the iteration, so a
+    /// caller should stop on ones that will not go away, such as
+    /// [`TransportError::PathLost`]. The iterator ends once
+    /// [`Self::shutdown`] has been called.
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
 
-    #[test]
-    fn test_bind_accept_connect() {
-        let dir = std::env::temp_dir().join(format!("ipcprims-test-{}", std::process::id()));
-        std::fs::create_dir_all(&dir).unwrap();
-        let sock_path = dir.join("test.sock");
+    fn wrap(&self, stream: std::os::unix::net::UnixStream) -> IpcStream {
+        if self.seqpacket {
+            IpcStream::from_unix_seqpacket(stream)
+        } else {
+            IpcStream::from_unix(stream)
+        }
+    }
 
-        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
-        assert!(sock_path.exists());
+    /// Stop accepting: a blocked [`Self::accept`] returns
+    /// [`TransportError::Shutdown`], as does every later one.
+    ///
+    /// Safe to call from another thread. The socket path is still removed on
+    /// drop.
+    pub fn shutdown(&self) {
+        if self.shut_down.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        debug!(transport = "uds", path = ?self.path, "listener shut down");
+        // Wake a blocked accept with a throwaway connection.
+        let _ = match Self::abstract_name(&self.path).filter(|_| self.is_abstract) {
+            Some(name) => Self::connect_abstract(name),
+            None if self.seqpacket => Self::connect_seqpacket(&self.path),
+            None => Self::connect(&self.path),
+        };
+    }
 
-        // Connect from another thread
-        let path_clone = sock_path.clone();
-        let handle = std::thread::spawn(move || {
-            let mut client = UnixDomainSocket::connect(&path_clone).unwrap();
-            client.write_all(b"hello").unwrap();
-        });
+    fn ensure_running(&self) -> Result<()> {
+        if self.shut_down.load(Ordering::Acquire) {
+            return Err(TransportError::Shutdown);
+        }
+        Ok(())
+    }
 
-        let mut server = listener.accept().unwrap();
-        let mut buf = [0u8; 5];
-        server.read_exact(&mut buf).unwrap();
-        assert_eq!(&buf, b"hello");
+    fn check_path(&self, watchdog: &PathWatchdog) -> Result<()> {
+        let created_inode = self
+            .bound
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .created_inode;
+        if path_matches(&self.path, created_inode) {
+            return Ok(());
+        }
 
-        handle.join().unwrap();
+        if !self.path_lost.swap(true, Ordering::AcqRel) {
+            info!(transport = "uds", path = ?self.path, "socket path lost");
+            if let Some(on_lost) = &watchdog.on_lost {
+                on_lost(&self.path);
+            }
+        }
 
-        // Cleanup
-        drop(listener);
-        assert!(
-            !sock_path.exists(),
-            "socket file should be cleaned up on drop"
-        );
-        let _ = std::fs::remove_dir_all(&dir);
+        if !watchdog.rebind {
+            return Err(TransportError::PathLost {
+                path: self.path.clone(),
+            });
+        }
+
+        self.rebind()?;
+        self.path_lost.store(false, Ordering::Release);
+        info!(transport = "uds", path = ?self.path, "rebound socket path");
+        Ok(())
     }
 
-    #[test]
-    fn test_path_too_long() {
-        let long_path = "/tmp/".to_string() + &"a".repeat(200) + ".sock";
+    /// Bind a fresh socket beside the path and rename it into place.
+    fn rebind(&self) -> Result<()> {
+        let mut current = self.bound.write().unwrap_or_else(|e| e.into_inner());
+        // A concurrent accept may have rebound while this one waited.
+        if path_matches(&self.path, current.created_inode) {
+            return Ok(());
+        }
+        if let Ok(metadata) = std::fs::symlink_metadata(&self.path) {
+            if !metadata.file_type().is_socket() {
+                return Err(TransportError::PathLost {
+                    path: self.path.clone(),
+                });
+            }
+        }
+
+        let mut staging_name = std::ffi::OsString::from(".");
+        staging_name.push(self.path.file_name().unwrap_or_default());
+        staging_name.push(".rebind");
+        let staging = self.path.with_file_name(staging_name);
+
+        let bound = Self::bind_listener(&staging, &self.options, self.seqpacket)?;
+        if let Err(e) = std::fs::rename(&staging, &self.path) {
+            let _ = std::fs::remove_file(&staging);
+            return Err(TransportError::Bind {
+                path: self.path.clone(),
+                source: e,
+            });
+        }
+
+        // The rename keeps the inode, so `created_inode` still identifies it.
+        *current = bound;
+        Ok(())
+    }
+
+    /// Connect to a listening Unix domain socket (blocking).
+    pub fn connect(path: impl AsRef<Path>) -> Result<IpcStream> {
+        let path = path.as_ref();
+        Self::check_path_len(path)?;
+        let stream = with_sun_path(path, |path| std::os::unix::net::UnixStream::connect(path))
+            .map_err(|e| TransportError::connect(path, e))?;
+        debug!(transport = "uds", ?path, "connected to unix domain socket");
+        Ok(IpcStream::from_unix(stream))
+    }
+
+    /// Connect to a listening Unix domain socket, giving up after `timeout`.
+    ///
+    /// A blocking connect can wait indefinitely while the listener's
+    /// backlog is full. Here the socket connects in non-blocking mode and
+    /// is retried (or polled for writability) until the deadline; running
+    /// out of time fails with [`TransportError::Connect`] of kind
+    /// [`std::io::ErrorKind::TimedOut`]. The returned stream blocks as
+    /// usual.
+    pub fn connect_timeout(path: impl AsRef<Path>, timeout: Duration) -> Result<IpcStream> {
+        let path = path.as_ref();
+        Self::check_path_len(path)?;
+        let deadline = Instant::now() + timeout;
+        let stream = with_sun_path(path, |path| connect_before(path, deadline))
+            .map_err(|e| TransportError::connect(path, e))?;
+        debug!(transport = "uds", ?path, "connected to unix domain socket");
+        Ok(IpcStream::from_unix(stream))
+    }
+
+    /// Connect to a listening Unix domain socket set up as `options` asks:
+    /// an optional timeout, kernel buffer sizes and trust requirements.
+    ///
+    /// Owner and mode requirements are checked before connecting, the peer
+    /// uid right after; a failed one returns
+    /// [`TransportError::UntrustedEndpoint`] without sending anything. A
+    /// buffer size the kernel rejects fails with
+    /// [`TransportError::Connect`], like the connect itself.
+    pub fn connect_with(path: impl AsRef<Path>, options: ConnectOptions) -> Result<IpcStream> {
+        let path = path.as_ref();
+        options.check_path(path)?;
+        let stream = match options.timeout {
+            Some(timeout) => Self::connect_timeout(path, timeout)?,
+            None => Self::connect(path)?,
+        };
+        set_buffer_sizes(
+            stream.as_raw_fd(),
+            options.send_buffer_size,
+            options.recv_buffer_size,
+        )
+        .map_err(|e| TransportError::Connect {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        options.check_peer(path, &stream)?;
+        Ok(stream)
+    }
+
+    /// Connect to a socket bound with [`bind_seqpacket`](Self::bind_seqpacket).
+    pub fn connect_seqpacket(path: impl AsRef<Path>) -> Result<IpcStream> {
+        let path = path.as_ref();
+        Self::check_path_len(path)?;
+        let stream = with_sun_path(path, crate::seqpacket::connect)
+            .map_err(|e| TransportError::connect(path, e))?;
+        debug!(
+            transport = "unix-seqpacket",
+            ?path,
+            "connected to seqpacket unix socket"
+        );
+        Ok(IpcStream::from_unix_seqpacket(stream))
+    }
+
+    /// Connect to a socket bound with [`bind_abstract`](Self::bind_abstract).
+    pub fn connect_abstract(name: &str) -> Result<IpcStream> {
+        let path = PathBuf::from(format!("@{name}"));
+        Self::check_abstract_name_len(name, &path)?;
+        let stream = abstract_addr(name)
+            .and_then(|addr| std::os::unix::net::UnixStream::connect_addr(&addr))
+            .map_err(|e| TransportError::connect(&path, e))?;
+        debug!(
+            transport = "unix-abstract",
+            ?path,
+            "connected to abstract unix socket"
+        );
+        Ok(IpcStream::from_unix(stream))
+    }
+
+    /// The path this socket is bound to; `@name` for an abstract socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this socket was bound with [`bind_abstract`](Self::bind_abstract).
+    pub fn is_abstract(&self) -> bool {
+        self.is_abstract
+    }
+
+    /// Reject paths that do not fit in `sockaddr_un.sun_path` (including the
+    /// trailing NUL) before the OS reports an opaque `EINVAL`.
+    ///
+    /// On Linux and macOS a longer path is reached through its directory
+    /// (see [`with_sun_path`]), so only a file name too long for that is
+    /// rejected.
+    pub(crate) fn check_path_len(path: &Path) -> Result<()> {
+        let len = path.as_os_str().len();
+        if len >= Self::MAX_PATH_LEN && !alias_fits(path) {
+            return Err(TransportError::PathTooLong {
+                path: path.to_path_buf(),
+                len,
+                max: Self::MAX_PATH_LEN,
+            });
+        }
+        Ok(())
+    }
+
+    /// An abstract name fills `sun_path` after a leading NUL, so it has the
+    /// same room as a path before its trailing NUL.
+    fn check_abstract_name_len(name: &str, path: &Path) -> Result<()> {
+        if name.len() >= Self::MAX_PATH_LEN {
+            return Err(TransportError::PathTooLong {
+                path: path.to_path_buf(),
+                len: name.len(),
+                max: Self::MAX_PATH_LEN,
+            });
+        }
+        Ok(())
+    }
+
+    /// Transport name for diagnostics.
+    pub fn transport_name(&self) -> &'static str {
+        if self.is_abstract {
+            "unix-abstract"
+        } else if self.seqpacket {
+            "unix-seqpacket"
+        } else {
+            "unix-domain-socket"
+        }
+    }
+}
+
+/// Room `/proc/self/fd/<dirfd>/` takes in front of a file name.
+#[cfg(target_os = "linux")]
+const PROC_FD_ALIAS_LEN: usize = "/proc/self/fd/".len() + 10 + 1;
+
+/// Whether `path`'s file name fits in `sun_path` once reached through its
+/// directory.
+fn alias_fits(path: &Path) -> bool {
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    #[cfg(target_os = "linux")]
+    {
+        name.len() + PROC_FD_ALIAS_LEN < UnixDomainSocket::MAX_PATH_LEN
+    }
+    #[cfg(target_os = "macos")]
+    {
+        name.len() < UnixDomainSocket::MAX_PATH_LEN
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = name;
+        false
+    }
+}
+
+/// Whether `path` fits in `sun_path` as it is.
+pub(crate) fn fits_sun_path(path: &Path) -> bool {
+    path.as_os_str().len() < UnixDomainSocket::MAX_PATH_LEN
+}
+
+/// Run `op` on a path naming the same socket as `path` that fits in
+/// `sun_path`.
+///
+/// Short paths pass through. A longer one is reached through a descriptor
+/// for its directory, without changing the process's working directory:
+/// on Linux as `/proc/self/fd/<dirfd>/<name>`; on macOS as `<name>`, from
+/// a scoped thread whose own working directory is set with
+/// `pthread_fchdir_np`. [`UnixDomainSocket::check_path_len`] has already
+/// rejected file names too long for either. Addresses read back from the
+/// socket show the alias.
+pub(crate) fn with_sun_path<T: Send>(
+    path: &Path,
+    op: impl FnOnce(&Path) -> std::io::Result<T> + Send,
+) -> std::io::Result<T> {
+    if fits_sun_path(path) {
+        return op(path);
+    }
+    let (Some(name), Some(dir)) = (path.file_name(), path.parent()) else {
+        return op(path);
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    sun_path_alias(dir, Path::new(name), op)
+}
+
+#[cfg(target_os = "linux")]
+fn sun_path_alias<T: Send>(
+    dir: &Path,
+    name: &Path,
+    op: impl FnOnce(&Path) -> std::io::Result<T> + Send,
+) -> std::io::Result<T> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // `O_PATH` needs no read permission on the directory, only search.
+    let dir = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_PATH | libc::O_DIRECTORY)
+        .open(dir)?;
+    let alias = PathBuf::from(format!("/proc/self/fd/{}", dir.as_raw_fd())).join(name);
+    op(&alias)
+}
+
+#[cfg(target_os = "macos")]
+fn sun_path_alias<T: Send>(
+    dir: &Path,
+    name: &Path,
+    op: impl FnOnce(&Path) -> std::io::Result<T> + Send,
+) -> std::io::Result<T> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    extern "C" {
+        /// Set the calling thread's working directory (macOS 10.12+).
+        fn pthread_fchdir_np(fd: libc::c_int) -> libc::c_int;
+    }
+
+    let dir = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY)
+        .open(dir)?;
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                // SAFETY: `dir` is an open directory; only this thread's
+                // working directory changes, and the thread ends after `op`.
+                if unsafe { pthread_fchdir_np(dir.as_raw_fd()) } != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                op(name)
+            })
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn sun_path_alias<T: Send>(
+    dir: &Path,
+    name: &Path,
+    op: impl FnOnce(&Path) -> std::io::Result<T> + Send,
+) -> std::io::Result<T> {
+    op(&dir.join(name))
+}
+
+/// Whether `fd` is a socket in the listening state (`SO_ACCEPTCONN`).
+fn is_listening(fd: std::os::fd::RawFd) -> bool {
+    let mut accepting: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: `accepting` is writable for `len` bytes; a bad `fd` only fails.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ACCEPTCONN,
+            (&raw mut accepting).cast(),
+            &mut len,
+        )
+    };
+    rc == 0 && accepting != 0
+}
+
+/// The socket address for `name` in the abstract namespace.
+#[cfg(target_os = "linux")]
+fn abstract_addr(name: &str) -> std::io::Result<std::os::unix::net::SocketAddr> {
+    use std::os::linux::net::SocketAddrExt;
+    std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn abstract_addr(_name: &str) -> std::io::Result<std::os::unix::net::SocketAddr> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "abstract unix sockets are only available on Linux",
+    ))
+}
+
+/// Apply the `SO_SNDBUF` and `SO_RCVBUF` sizes that were asked for.
+fn set_buffer_sizes(
+    fd: std::os::fd::RawFd,
+    send: Option<usize>,
+    recv: Option<usize>,
+) -> std::io::Result<()> {
+    if let Some(size) = send {
+        crate::traits::set_socket_buffer_size(fd, libc::SO_SNDBUF, size)?;
+    }
+    if let Some(size) = recv {
+        crate::traits::set_socket_buffer_size(fd, libc::SO_RCVBUF, size)?;
+    }
+    Ok(())
+}
+
+/// An exclusive `flock` on `<socket path>.lock`, held for a listener's
+/// lifetime.
+pub(crate) struct LockFile {
+    path: PathBuf,
+    file: std::fs::File,
+}
+
+impl LockFile {
+    /// Lock the companion file of `socket_path`, creating it if needed.
+    ///
+    /// A lock held elsewhere fails with [`TransportError::AddressInUse`],
+    /// or with `force` yields `None`.
+    pub(crate) fn acquire(socket_path: &Path, force: bool) -> Result<Option<Self>> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut name = socket_path.as_os_str().to_owned();
+        name.push(".lock");
+        let path = PathBuf::from(name);
+        let bind_error = |source| TransportError::Bind {
+            path: path.clone(),
+            source,
+        };
+
+        loop {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .mode(0o600)
+                .open(&path)
+                .map_err(bind_error)?;
+            // SAFETY: `file` owns an open descriptor for the whole call.
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+                let err = std::io::Error::last_os_error();
+                return match err.kind() {
+                    std::io::ErrorKind::WouldBlock if force => Ok(None),
+                    std::io::ErrorKind::WouldBlock => Err(TransportError::AddressInUse {
+                        path: socket_path.to_path_buf(),
+                    }),
+                    std::io::ErrorKind::Interrupted => continue,
+                    _ => Err(bind_error(err)),
+                };
+            }
+            // The previous holder may have removed the file between our open
+            // and our lock; a lock on an unlinked file guards nothing.
+            if names_file(&path, &file) {
+                return Ok(Some(Self { path, file }));
+            }
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Whether `path` names the open `file`.
+fn names_file(path: &Path, file: &std::fs::File) -> bool {
+    match (file.metadata(), std::fs::symlink_metadata(path)) {
+        (Ok(held), Ok(named)) => held.dev() == named.dev() && held.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // Remove while still holding the lock, and only our own file.
+        if names_file(&self.path, &self.file) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// How long bind waits on an existing socket to tell a live listener from
+/// a stale file.
+const STALE_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Clear the way to bind `path`: remove a socket file nothing listens on,
+/// refuse one something does (unless `force`), and never remove a file
+/// that is not a socket.
+pub(crate) fn remove_stale_socket(path: &Path, force: bool) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(TransportError::Bind {
+                path: path.to_path_buf(),
+                source: e,
+            })
+        }
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(TransportError::Bind {
+            path: path.to_path_buf(),
+            source: std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "existing path is not a unix socket",
+            ),
+        });
+    }
+
+    if !force {
+        let deadline = Instant::now() + STALE_PROBE_TIMEOUT;
+        let in_use = match with_sun_path(path, |path| connect_before(path, deadline)) {
+            Ok(_) => true,
+            // A full backlog, or a listener of the other socket type, is
+            // still a listener.
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => true,
+            Err(e) if e.raw_os_error() == Some(libc::EPROTOTYPE) => true,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound
+                ) =>
+            {
+                false
+            }
+            Err(e) => {
+                return Err(TransportError::Bind {
+                    path: path.to_path_buf(),
+                    source: e,
+                })
+            }
+        };
+        if in_use {
+            return Err(TransportError::AddressInUse {
+                path: path.to_path_buf(),
+            });
+        }
+    }
+
+    debug!(?path, "removing stale socket");
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(TransportError::Bind {
+            path: path.to_path_buf(),
+            source: e,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Connect a stream socket to `path`, failing with `TimedOut` at `deadline`.
+fn connect_before(
+    path: &Path,
+    deadline: Instant,
+) -> std::io::Result<std::os::unix::net::UnixStream> {
+    use crate::seqpacket::{cvt, sockaddr, socket};
+
+    let fd = socket(libc::SOCK_STREAM)?;
+    let (addr, len) = sockaddr(path)?;
+    let set_nonblocking = |nonblocking: bool| {
+        // SAFETY: `fd` is an open socket; only O_NONBLOCK is changed.
+        let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+        cvt(flags)?;
+        let flags = if nonblocking {
+            flags | libc::O_NONBLOCK
+        } else {
+            flags & !libc::O_NONBLOCK
+        };
+        // SAFETY: as above.
+        cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags) })
+    };
+    let timed_out = || std::io::Error::new(std::io::ErrorKind::TimedOut, "connect timed out");
+
+    set_nonblocking(true)?;
+    loop {
+        // SAFETY: `addr` is an initialized `sockaddr_un` of which `len`
+        // bytes are in use, and `fd` is an open socket.
+        let rc = unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                (&raw const addr).cast::<libc::sockaddr>(),
+                len,
+            )
+        };
+        if rc == 0 {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match err.raw_os_error() {
+            Some(libc::EINTR) => {}
+            // Linux refuses rather than queues a non-blocking connect while
+            // the backlog is full, so retry until the deadline.
+            Some(libc::EAGAIN) => {
+                if remaining.is_zero() {
+                    return Err(timed_out());
+                }
+                std::thread::sleep(remaining.min(CONNECT_RETRY_INTERVAL));
+            }
+            Some(libc::EINPROGRESS) => {
+                if !wait_writable(fd.as_raw_fd(), remaining)? {
+                    return Err(timed_out());
+                }
+                let mut error: libc::c_int = 0;
+                let mut error_len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+                // SAFETY: `error` is writable for `error_len` bytes.
+                cvt(unsafe {
+                    libc::getsockopt(
+                        fd.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_ERROR,
+                        (&raw mut error).cast(),
+                        &mut error_len,
+                    )
+                })?;
+                if error != 0 {
+                    return Err(std::io::Error::from_raw_os_error(error));
+                }
+                break;
+            }
+            _ => return Err(err),
+        }
+    }
+    set_nonblocking(false)?;
+    Ok(std::os::unix::net::UnixStream::from(fd))
+}
+
+/// Wait up to `timeout` for `fd` to become writable; false on timeout.
+fn wait_writable(fd: std::os::fd::RawFd, timeout: Duration) -> std::io::Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let timeout_ms = libc::c_int::try_from(remaining.as_nanos().div_ceil(1_000_000))
+            .unwrap_or(libc::c_int::MAX);
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+        // SAFETY: `pollfd` is a valid, writable array of length 1.
+        let rc = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        if rc > 0 {
+            return Ok(true);
+        }
+        if rc == 0 {
+            return Ok(false);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// Resolve a group name, or a numeric gid as is.
+fn group_id(group: &str) -> std::io::Result<libc::gid_t> {
+    if let Ok(gid) = group.parse::<libc::gid_t>() {
+        return Ok(gid);
+    }
+    let name = std::ffi::CString::new(group).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "group name contains a NUL byte",
+        )
+    })?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: a zeroed `group` is a valid out-parameter.
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut found = std::ptr::null_mut();
+        // SAFETY: `name` is NUL-terminated, and `entry`, `buf` and `found`
+        // are valid for writes for the duration of the call.
+        let rc = unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                &mut entry,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            )
+        };
+        match rc {
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            0 if found.is_null() => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no group named {group:?}"),
+                ))
+            }
+            0 => return Ok(entry.gr_gid),
+            rc => return Err(std::io::Error::from_raw_os_error(rc)),
+        }
+    }
+}
+
+/// True while `path` still names the socket identified by `created_inode`.
+fn path_matches(path: &Path, created_inode: Option<(u64, u64)>) -> bool {
+    match (created_inode, std::fs::symlink_metadata(path)) {
+        (Some((dev, ino)), Ok(metadata)) => {
+            metadata.file_type().is_socket() && metadata.dev() == dev && metadata.ino() == ino
+        }
+        _ => false,
+    }
+}
+
+/// Wait up to `timeout` (forever if `None`) for a pending connection on
+/// `listener`.
+fn wait_readable(listener: &UnixListener, timeout: Option<Duration>) -> std::io::Result<()> {
+    // Round up so a sub-millisecond interval does not become a 0 ms spin.
+    let timeout_ms = timeout.map_or(-1, |timeout| {
+        libc::c_int::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(libc::c_int::MAX)
+    });
+    let mut pollfd = libc::pollfd {
+        fd: listener.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    // SAFETY: `pollfd` is a valid, writable array of length 1 and the fd is
+    // an open listening socket owned by `listener`.
+    let rc = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+    if rc < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+impl Listener for UnixDomainSocket {
+    type Stream = IpcStream;
+
+    fn accept(&self) -> Result<IpcStream> {
+        UnixDomainSocket::accept(self)
+    }
+
+    fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        UnixDomainSocket::accept_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) {
+        UnixDomainSocket::shutdown(self);
+    }
+
+    fn path(&self) -> &Path {
+        UnixDomainSocket::path(self)
+    }
+}
+
+impl Drop for UnixDomainSocket {
+    fn drop(&mut self) {
+        if self.cleanup_on_drop {
+            let created_inode = self
+                .bound
+                .get_mut()
+                .unwrap_or_else(|e| e.into_inner())
+                .created_inode;
+            if let Some((expected_dev, expected_ino)) = created_inode {
+                if let Ok(metadata) = std::fs::symlink_metadata(&self.path) {
+                    if metadata.file_type().is_socket()
+                        && metadata.dev() == expected_dev
+                        && metadata.ino() == expected_ino
+                    {
+                        debug!(path = ?self.path, "cleaning up socket file");
+                        let _ = std::fs::remove_file(&self.path);
+                    } else {
+                        debug!(
+                            path = ?self.path,
+                            "socket path identity changed; skipping cleanup"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_bind_accept_connect() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("test.sock");
+
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+        assert!(sock_path.exists());
+
+        // Connect from another thread
+        let path_clone = sock_path.clone();
+        let handle = std::thread::spawn(move || {
+            let mut client = UnixDomainSocket::connect(&path_clone).unwrap();
+            client.write_all(b"hello").unwrap();
+        });
+
+        let mut server = listener.accept().unwrap();
+        assert!(!server.is_message_oriented());
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+
+        handle.join().unwrap();
+
+        // Cleanup
+        drop(listener);
+        assert!(
+            !sock_path.exists(),
+            "socket file should be cleaned up on drop"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_addresses_name_the_listener_path() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-addr-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("addr.sock");
+
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+        let client = UnixDomainSocket::connect(&sock_path).unwrap();
+        let server = listener.accept().unwrap();
+
+        let named = IpcAddr::Path(sock_path.clone());
+        assert_eq!(client.peer_addr().unwrap(), named);
+        assert_eq!(client.local_addr().unwrap(), IpcAddr::Unnamed);
+        assert_eq!(server.local_addr().unwrap(), named);
+        assert_eq!(server.peer_addr().unwrap(), IpcAddr::Unnamed);
+        assert_eq!(named.to_string(), sock_path.display().to_string());
+        let debug = format!("{client:?}");
+        assert!(
+            debug.contains(&format!("peer: {}", sock_path.display())),
+            "{debug}"
+        );
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_path_too_long() {
+        let long_path = "/tmp/".to_string() + &"a".repeat(200) + ".sock";
         let result = UnixDomainSocket::bind(&long_path);
         assert!(matches!(result, Err(TransportError::PathTooLong { .. })));
     }
 
+    #[test]
+    fn test_connect_path_too_long() {
+        let long_path = "/tmp/".to_string() + &"a".repeat(200) + ".sock";
+        match UnixDomainSocket::connect(&long_path) {
+            Err(TransportError::PathTooLong { path, len, max }) => {
+                assert_eq!(path, std::path::PathBuf::from(&long_path));
+                assert_eq!(len, long_path.len());
+                assert_eq!(max, UnixDomainSocket::MAX_PATH_LEN);
+            }
+            other => panic!("expected PathTooLong, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_bind_default_permissions_hardened() {
         let dir = std::env::temp_dir().join(format!("ipcprims-perms-{}", std::process::id()));
@@ -217,16 +1615,197 @@ mod tests {
     }
 
     #[test]
-    fn test_bind_rejects_existing_non_socket_file() {
-        let dir = std::env::temp_dir().join(format!("ipcprims-bind-file-{}", std::process::id()));
+    fn test_bind_with_sets_mode_backlog_and_group() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-bindopts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("opts.sock");
+
+        // SAFETY: getegid has no preconditions.
+        let gid = unsafe { libc::getegid() };
+        let listener = UnixDomainSocket::bind_with(
+            &sock_path,
+            BindOptions::new()
+                .mode(0o660)
+                .backlog(4)
+                .owner_group(gid.to_string()),
+        )
+        .unwrap();
+        let metadata = std::fs::metadata(&sock_path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o660);
+        assert_eq!(metadata.gid(), gid);
+
+        let client_path = sock_path.clone();
+        let client =
+            std::thread::spawn(move || UnixDomainSocket::connect(&client_path).map(|_| ()));
+        listener.accept().unwrap();
+        client.join().unwrap().unwrap();
+        drop(listener);
+
+        // SAFETY: getgrgid returns null or a pointer to a static entry,
+        // read before any other call could overwrite it.
+        let name = unsafe {
+            let entry = libc::getgrgid(gid);
+            (!entry.is_null()).then(|| {
+                std::ffi::CStr::from_ptr((*entry).gr_name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        };
+        if let Some(name) = name {
+            let listener =
+                UnixDomainSocket::bind_with(&sock_path, BindOptions::new().owner_group(name))
+                    .unwrap();
+            assert_eq!(std::fs::metadata(&sock_path).unwrap().gid(), gid);
+            drop(listener);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bind_with_unknown_group_fails_and_removes_the_path() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-nogroup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("nogroup.sock");
+
+        let err = UnixDomainSocket::bind_with(
+            &sock_path,
+            BindOptions::new().owner_group("ipcprims-no-such-group"),
+        )
+        .err()
+        .unwrap();
+        match err {
+            TransportError::Chown {
+                path,
+                group,
+                source,
+            } => {
+                assert_eq!(path, sock_path);
+                assert_eq!(group, "ipcprims-no-such-group");
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            other => panic!("expected Chown, got {other:?}"),
+        }
+        assert!(!sock_path.exists(), "socket path should be removed");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bind_rejects_existing_non_socket_file() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-bind-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("not-a-socket.sock");
+        std::fs::write(&sock_path, b"regular-file").unwrap();
+
+        let result = UnixDomainSocket::bind(&sock_path);
+        assert!(matches!(result, Err(TransportError::Bind { .. })));
+
+        let _ = std::fs::remove_file(&sock_path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bind_refuses_a_live_socket_and_replaces_a_dead_one() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-live-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("live.sock");
+
+        // Two binds racing for one path: exactly one wins.
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let racers: Vec<_> = (0..2)
+            .map(|_| {
+                let (barrier, path) = (barrier.clone(), sock_path.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    UnixDomainSocket::bind(&path)
+                })
+            })
+            .collect();
+        let mut results: Vec<_> = racers.into_iter().map(|r| r.join().unwrap()).collect();
+        results.sort_by_key(|result| result.is_err());
+        let listener = results.remove(0).expect("one bind should win");
+        assert!(
+            matches!(&results[0], Err(TransportError::AddressInUse { path }) if *path == sock_path),
+            "{:?}",
+            results[0].as_ref().err()
+        );
+
+        assert!(matches!(
+            UnixDomainSocket::bind(&sock_path),
+            Err(TransportError::AddressInUse { .. })
+        ));
+
+        // The losers left the winner's path alone; their probes queue
+        // ahead of the real client and hang up without a byte.
+        let mut client = UnixDomainSocket::connect(&sock_path).unwrap();
+        client.write_all(b"ok").unwrap();
+        let mut buf = [0u8; 2];
+        loop {
+            let mut server = listener.accept().unwrap();
+            if server.read_exact(&mut buf).is_ok() {
+                break;
+            }
+        }
+        assert_eq!(&buf, b"ok");
+
+        // `force` takes the path over regardless.
+        let forced =
+            UnixDomainSocket::bind_with(&sock_path, BindOptions::new().force(true)).unwrap();
+        drop(listener);
+        drop(forced);
+
+        // A socket file nobody listens on is stale and gets replaced.
+        drop(std::os::unix::net::UnixListener::bind(&sock_path).unwrap());
+        assert!(sock_path.exists());
+        let listener = UnixDomainSocket::bind(&sock_path).expect("stale socket should be replaced");
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lock_file_is_held_for_the_listeners_lifetime() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-lock-{}", std::process::id()));
         std::fs::create_dir_all(&dir).unwrap();
-        let sock_path = dir.join("not-a-socket.sock");
-        std::fs::write(&sock_path, b"regular-file").unwrap();
+        let sock_path = dir.join("locked.sock");
+        let lock_path = dir.join("locked.sock.lock");
 
-        let result = UnixDomainSocket::bind(&sock_path);
-        assert!(matches!(result, Err(TransportError::Bind { .. })));
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+        assert_eq!(listener.lock_path(), Some(lock_path.as_path()));
+        drop(listener);
+        assert!(!sock_path.exists() && !lock_path.exists());
+
+        // A holder of the lock keeps others off the path, even with no
+        // socket there to probe.
+        let held = std::fs::File::create(&lock_path).unwrap();
+        // SAFETY: `held` owns an open descriptor.
+        assert_eq!(
+            unsafe { libc::flock(held.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) },
+            0
+        );
+        assert!(matches!(
+            UnixDomainSocket::bind(&sock_path),
+            Err(TransportError::AddressInUse { .. })
+        ));
+        assert!(!sock_path.exists());
+
+        // Opting out, or forcing, binds anyway without taking the lock.
+        let unlocked =
+            UnixDomainSocket::bind_with(&sock_path, BindOptions::new().lock_file(false)).unwrap();
+        assert!(unlocked.lock_path().is_none());
+        drop(unlocked);
+        let forced =
+            UnixDomainSocket::bind_with(&sock_path, BindOptions::new().force(true)).unwrap();
+        assert!(forced.lock_path().is_none());
+        drop(forced);
+        assert!(lock_path.exists(), "someone else's lock file was removed");
+        drop(held);
+
+        // A lock file replaced under the listener is not its to remove.
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+        std::fs::remove_file(&lock_path).unwrap();
+        std::fs::write(&lock_path, b"").unwrap();
+        drop(listener);
+        assert!(lock_path.exists());
 
-        let _ = std::fs::remove_file(&sock_path);
         let _ = std::fs::remove_dir_all(&dir);
     }
 
@@ -252,4 +1831,730 @@ mod tests {
         let _ = std::fs::remove_file(&sock_path);
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn test_shutdown_wakes_blocked_accept() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-shutdown-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("shutdown.sock");
+
+        let listener = Arc::new(UnixDomainSocket::bind(&sock_path).unwrap());
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || acceptor.accept().map(|_| ()));
+
+        std::thread::sleep(Duration::from_millis(50));
+        listener.shutdown();
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(TransportError::Shutdown)
+        ));
+        assert!(matches!(listener.accept(), Err(TransportError::Shutdown)));
+
+        drop(listener);
+        assert!(
+            !sock_path.exists(),
+            "socket file should be cleaned up after shutdown"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_listener_fd_handoff_keeps_accepted_streams() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-handoff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("handoff.sock");
+
+        let old = UnixDomainSocket::bind(&sock_path).unwrap();
+        let lock_path = old.lock_path().unwrap().to_path_buf();
+        let mut first = UnixDomainSocket::connect(&sock_path).unwrap();
+        let mut first_server = old.accept().unwrap();
+
+        let fd = old.into_listener_fd().unwrap();
+        assert!(sock_path.exists(), "export must leave the path in place");
+        // Clients connecting mid-handoff queue on the socket.
+        let mut queued = UnixDomainSocket::connect(&sock_path).unwrap();
+
+        let new = UnixDomainSocket::from_listener_fd(fd).unwrap();
+        assert_eq!(new.path(), sock_path);
+        assert_eq!(new.lock_path(), Some(lock_path.as_path()));
+        assert!(!new.is_abstract());
+        let mut queued_server = new.accept().unwrap();
+        queued.write_all(b"new").unwrap();
+        let mut buf = [0u8; 3];
+        queued_server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"new");
+
+        first.write_all(b"old").unwrap();
+        first_server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"old");
+
+        drop(new);
+        assert!(!sock_path.exists());
+        assert!(!lock_path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_from_listener_fd_rejects_connected_sockets() {
+        let (a, _b) = std::os::unix::net::UnixStream::pair().unwrap();
+        let result = UnixDomainSocket::from_listener_fd(a.into());
+        assert!(matches!(result, Err(TransportError::NotUnixSocket { .. })));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_long_paths_bind_and_connect_through_their_directory() {
+        let root = std::env::temp_dir().join(format!("ipcprims-long-{}", std::process::id()));
+        let dir = root.join("a".repeat(60)).join("b".repeat(60));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("daemon.sock");
+        assert!(sock_path.as_os_str().len() > 150);
+
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+        assert!(sock_path.exists());
+        let mut client = UnixDomainSocket::connect(&sock_path).unwrap();
+        let mut server = listener.accept().unwrap();
+        client.write_all(b"long").unwrap();
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"long");
+
+        let _client =
+            UnixDomainSocket::connect_timeout(&sock_path, Duration::from_secs(5)).unwrap();
+        let _server = listener.accept().unwrap();
+        // The live socket check also reaches the socket through the alias.
+        let options = BindOptions::new().lock_file(false);
+        assert!(matches!(
+            UnixDomainSocket::bind_with(&sock_path, options),
+            Err(TransportError::AddressInUse { .. })
+        ));
+
+        drop(listener);
+        assert!(!sock_path.exists(), "drop should clean up the long path");
+
+        #[cfg(target_os = "linux")]
+        {
+            let listener = UnixDomainSocket::bind_seqpacket(&sock_path).unwrap();
+            let _client = UnixDomainSocket::connect_seqpacket(&sock_path).unwrap();
+            let _server = listener.accept().unwrap();
+        }
+
+        // Only a file name too long to reach through the directory fails.
+        let long_name = dir.join("c".repeat(100));
+        assert!(matches!(
+            UnixDomainSocket::bind(&long_name),
+            Err(TransportError::PathTooLong { .. })
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_connect_failures_name_the_path() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-conn-err-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = dir.join("missing.sock");
+        match UnixDomainSocket::connect(&missing) {
+            Err(TransportError::NotFound { path }) => assert_eq!(path, missing),
+            other => panic!("expected NotFound, got {other:?}"),
+        }
+
+        let locked = dir.join("locked");
+        std::fs::create_dir_all(&locked).unwrap();
+        let listener = UnixDomainSocket::bind(locked.join("inner.sock")).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Root ignores directory permissions, so only check as another user.
+        // SAFETY: `geteuid` has no preconditions and cannot fail.
+        if unsafe { libc::geteuid() } != 0 {
+            let inner = locked.join("inner.sock");
+            match UnixDomainSocket::connect(&inner) {
+                Err(TransportError::PermissionDenied { path }) => assert_eq!(path, inner),
+                other => panic!("expected PermissionDenied, got {other:?}"),
+            }
+            let options = ConnectOptions::new().require_owner_uid(0);
+            match UnixDomainSocket::connect_with(&inner, options) {
+                Err(TransportError::PermissionDenied { path }) => assert_eq!(path, inner),
+                other => panic!("expected PermissionDenied, got {other:?}"),
+            }
+        }
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o700)).unwrap();
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_closed_peer_errors_convert_to_hang_ups() {
+        let (mut a, b) = IpcStream::pair().unwrap();
+        drop(b);
+        let err = TransportError::from(a.write(b"x").unwrap_err());
+        assert!(matches!(err, TransportError::BrokenPipe), "{err:?}");
+
+        // Linux resets a connection closed with data left unread.
+        #[cfg(target_os = "linux")]
+        {
+            let (mut a, b) = IpcStream::pair().unwrap();
+            a.write_all(b"unread").unwrap();
+            drop(b);
+            let mut buf = [0u8; 1];
+            let err = TransportError::from(a.read(&mut buf).unwrap_err());
+            assert!(matches!(err, TransportError::ConnectionReset), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn test_connect_timeout_connects_to_a_listener() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-conn-to-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("connect-timeout.sock");
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+
+        let mut client =
+            UnixDomainSocket::connect_timeout(&sock_path, Duration::from_secs(5)).unwrap();
+        let mut server = listener.accept().unwrap();
+        client.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        let missing = dir.join("missing.sock");
+        match UnixDomainSocket::connect_timeout(&missing, Duration::from_secs(5)) {
+            Err(TransportError::NotFound { path }) => assert_eq!(path, missing),
+            other => panic!("expected NotFound, got {other:?}"),
+        }
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_buffer_sizes_apply_to_accepted_and_connected_streams() {
+        // Below the usual defaults, so the change is visible; Linux reports
+        // double what was asked for.
+        const SNDBUF: usize = 24 * 1024;
+        const RCVBUF: usize = 40 * 1024;
+
+        let dir = std::env::temp_dir().join(format!("ipcprims-bufsize-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("bufsize.sock");
+        let listener = UnixDomainSocket::bind_with(
+            &sock_path,
+            BindOptions::new()
+                .send_buffer_size(SNDBUF)
+                .recv_buffer_size(RCVBUF),
+        )
+        .unwrap();
+
+        let client = UnixDomainSocket::connect_with(
+            &sock_path,
+            ConnectOptions::new()
+                .timeout(Duration::from_secs(5))
+                .send_buffer_size(RCVBUF)
+                .recv_buffer_size(SNDBUF),
+        )
+        .unwrap();
+        let server = listener.accept().unwrap();
+        for (stream, sndbuf, rcvbuf) in [(&server, SNDBUF, RCVBUF), (&client, RCVBUF, SNDBUF)] {
+            let (sent, received) = (
+                stream.send_buffer_size().unwrap(),
+                stream.recv_buffer_size().unwrap(),
+            );
+            assert!(sent >= sndbuf, "SO_SNDBUF {sent} < {sndbuf}");
+            assert!(received >= rcvbuf, "SO_RCVBUF {received} < {rcvbuf}");
+            #[cfg(target_os = "linux")]
+            assert_eq!((sent, received), (2 * sndbuf, 2 * rcvbuf));
+        }
+
+        // Streams can be resized after the fact; memory streams have no
+        // socket to resize.
+        server.set_send_buffer_size(64 * 1024).unwrap();
+        assert!(server.send_buffer_size().unwrap() >= 64 * 1024);
+
+        let (memory, _other) = IpcStream::memory_pair().unwrap();
+        match memory.set_recv_buffer_size(64 * 1024) {
+            Err(TransportError::Io(err)) => {
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported)
+            }
+            other => panic!("expected Unsupported, got {other:?}"),
+        }
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_connect_with_checks_owner_mode_and_peer_uid() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("ipcprims-trust-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let sock_path = dir.join("trust.sock");
+        let listener = UnixDomainSocket::bind_with_mode(&sock_path, 0o660).unwrap();
+        // SAFETY: `geteuid` has no preconditions and cannot fail.
+        let me = unsafe { libc::geteuid() };
+        let other = me.wrapping_add(1);
+
+        let connect = |options: ConnectOptions| {
+            let result = UnixDomainSocket::connect_with(&sock_path, options);
+            if result.is_ok() {
+                listener.accept().unwrap();
+            }
+            result
+        };
+        let untrusted = |result: Result<IpcStream>| match result {
+            Err(TransportError::UntrustedEndpoint { path, detail }) => {
+                assert_eq!(path, sock_path);
+                detail
+            }
+            other => panic!("expected UntrustedEndpoint, got {other:?}"),
+        };
+
+        connect(
+            ConnectOptions::new()
+                .require_owner_uid(me)
+                .require_mode_max(0o660)
+                .require_peer_uid(me),
+        )
+        .unwrap();
+        let detail = untrusted(connect(ConnectOptions::new().require_owner_uid(other)));
+        assert!(detail.contains("owned by uid"), "{detail}");
+        let detail = untrusted(connect(ConnectOptions::new().require_mode_max(0o600)));
+        assert!(detail.contains("mode 660"), "{detail}");
+        let detail = untrusted(connect(ConnectOptions::new().require_peer_uid(other)));
+        assert!(detail.contains(&format!("uid {me}")), "{detail}");
+
+        // A directory anyone can write to could hold anyone's socket.
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+        let detail = untrusted(connect(ConnectOptions::new().require_owner_uid(me)));
+        assert!(detail.contains("sticky"), "{detail}");
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o1777)).unwrap();
+        connect(ConnectOptions::new().require_owner_uid(me)).unwrap();
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_connect_timeout_gives_up_on_a_full_backlog() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-backlog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("backlog.sock");
+        // Never accepts, so the queue fills after a connection or two.
+        let listener =
+            UnixDomainSocket::bind_with(&sock_path, BindOptions::new().backlog(0)).unwrap();
+
+        let mut queued = Vec::new();
+        let timeout = Duration::from_millis(100);
+        let err = loop {
+            assert!(queued.len() < 16, "backlog never filled");
+            let started = std::time::Instant::now();
+            match UnixDomainSocket::connect_timeout(&sock_path, timeout) {
+                Ok(stream) => queued.push(stream),
+                Err(err) => {
+                    assert!(started.elapsed() >= timeout);
+                    break err;
+                }
+            }
+        };
+        match err {
+            TransportError::Connect { source, .. } => {
+                assert_eq!(source.kind(), std::io::ErrorKind::TimedOut)
+            }
+            other => panic!("expected Connect, got {other:?}"),
+        }
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_incoming_yields_connections_until_shutdown() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-incoming-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("incoming.sock");
+        let listener = Arc::new(UnixDomainSocket::bind(&sock_path).unwrap());
+
+        let acceptor = Arc::clone(&listener);
+        let server = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for stream in acceptor.incoming() {
+                let mut buf = [0u8; 1];
+                stream.unwrap().read_exact(&mut buf).unwrap();
+                received.push(buf[0]);
+            }
+            received
+        });
+
+        for byte in [b'a', b'b'] {
+            let mut client = UnixDomainSocket::connect(&sock_path).unwrap();
+            client.write_all(&[byte]).unwrap();
+        }
+        // Let the server take both clients before the shutdown wake-up.
+        std::thread::sleep(Duration::from_millis(50));
+        listener.shutdown();
+        assert_eq!(server.join().unwrap(), b"ab");
+        assert!(listener.incoming().next().is_none());
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_accept_timeout_returns_none_then_takes_a_late_client() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-accept-to-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("accept-timeout.sock");
+        let listener = UnixDomainSocket::bind(&sock_path).unwrap();
+
+        let started = std::time::Instant::now();
+        assert!(listener
+            .accept_timeout(Duration::from_millis(50))
+            .unwrap()
+            .is_none());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let client_path = sock_path.clone();
+        let client = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let mut stream = UnixDomainSocket::connect(&client_path).unwrap();
+            stream.write_all(b"late").unwrap();
+        });
+        let mut server = listener
+            .accept_timeout(Duration::from_secs(5))
+            .unwrap()
+            .expect("client connects mid-wait");
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"late");
+        client.join().unwrap();
+
+        // A plain accept still blocks normally afterwards.
+        let client_path = sock_path.clone();
+        let client =
+            std::thread::spawn(move || UnixDomainSocket::connect(&client_path).map(|_| ()));
+        listener.accept().unwrap();
+        client.join().unwrap().unwrap();
+
+        listener.shutdown();
+        assert!(matches!(
+            listener.accept_timeout(Duration::from_millis(10)),
+            Err(TransportError::Shutdown)
+        ));
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_seqpacket_preserves_message_boundaries() {
+        let dir = std::env::temp_dir().join(format!("ipcprims-seqpacket-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("seqpacket.sock");
+        let listener = UnixDomainSocket::bind_seqpacket(&sock_path).unwrap();
+        assert_eq!(listener.transport_name(), "unix-seqpacket");
+
+        let large = vec![0x5A; 1000];
+        let expected = large.clone();
+        let handle = std::thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            assert!(stream.is_message_oriented());
+            let mut buf = [0u8; 64];
+            let first = stream.read(&mut buf).unwrap();
+            assert_eq!(&buf[..first], b"one");
+            let second = stream.read(&mut buf).unwrap();
+            assert_eq!(&buf[..second], b"two-two");
+
+            // A message larger than the buffer comes out over several reads
+            // and none of them runs into the next message.
+            let mut received = Vec::new();
+            while received.len() < expected.len() {
+                let len = stream.read(&mut buf).unwrap();
+                assert!(len > 0);
+                received.extend_from_slice(&buf[..len]);
+            }
+            assert_eq!(received, expected);
+            let last = stream.read(&mut buf).unwrap();
+            assert_eq!(&buf[..last], b"end");
+            stream.write_all(b"ack").unwrap();
+        });
+
+        let mut client = UnixDomainSocket::connect_seqpacket(&sock_path).unwrap();
+        assert!(client.is_message_oriented());
+        assert!(format!("{client:?}").contains("unix-seqpacket"));
+        client.write_all(b"one").unwrap();
+        client.write_all(b"two-two").unwrap();
+        client.write_all(&large).unwrap();
+        client.write_all(b"end").unwrap();
+        let mut buf = [0u8; 16];
+        let len = client.read(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ack");
+        handle.join().unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_seqpacket_rejects_oversized_message() {
+        let dir = std::env::temp_dir().join(format!(
+            "ipcprims-seqpacket-oversized-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock_path = dir.join("seqpacket.sock");
+        let listener = UnixDomainSocket::bind_seqpacket(&sock_path).unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            let mut buf = [0u8; 16];
+            let len = stream.read(&mut buf).unwrap();
+            buf[..len].to_vec()
+        });
+
+        let mut client = UnixDomainSocket::connect_seqpacket(&sock_path).unwrap();
+        let oversized = vec![0u8; 16 * 1024 * 1024];
+        let err = client.write(&oversized).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let source = err
+            .get_ref()
+            .and_then(|source| source.downcast_ref::<TransportError>());
+        assert!(
+            matches!(
+                source,
+                Some(TransportError::MessageTooLarge { len }) if *len == oversized.len()
+            ),
+            "{err}"
+        );
+
+        // Nothing was sent, so the stream is still usable.
+        client.write_all(b"after").unwrap();
+        assert_eq!(handle.join().unwrap(), b"after");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn abstract_test_name(tag: &str) -> String {
+        format!("ipcprims-test-{tag}-{}", std::process::id())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_abstract_bind_accept_connect_without_a_file() {
+        let name = abstract_test_name("roundtrip");
+
+        let listener = UnixDomainSocket::bind_abstract(&name).unwrap();
+        assert!(listener.is_abstract());
+        assert_eq!(listener.transport_name(), "unix-abstract");
+        assert_eq!(listener.path(), Path::new(&format!("@{name}")));
+        assert!(!listener.path().exists());
+        let listed = std::fs::read_to_string("/proc/net/unix").unwrap();
+        assert!(listed.contains(&format!("@{name}")));
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+            listener
+        });
+        let mut client = UnixDomainSocket::connect_abstract(&name).unwrap();
+        client.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        drop(handle.join().unwrap());
+        assert!(matches!(
+            UnixDomainSocket::connect_abstract(&name),
+            Err(TransportError::Connect { .. })
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_abstract_addresses_keep_the_name() {
+        let name = abstract_test_name("addr");
+        let listener = UnixDomainSocket::bind_abstract(&name).unwrap();
+        let client = UnixDomainSocket::connect_abstract(&name).unwrap();
+        let server = listener.accept().unwrap();
+
+        let addr = IpcAddr::Abstract(name.as_bytes().to_vec());
+        assert_eq!(client.peer_addr().unwrap(), addr);
+        assert_eq!(server.local_addr().unwrap(), addr);
+        assert_eq!(addr.to_string(), format!("@{name}"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_abstract_shutdown_and_name_limits() {
+        let name = abstract_test_name("shutdown");
+        let listener = Arc::new(
+            UnixDomainSocket::bind_abstract(&name)
+                .unwrap()
+                .with_path_watchdog(Duration::from_millis(10)),
+        );
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || acceptor.accept().map(|_| ()));
+        std::thread::sleep(Duration::from_millis(50));
+        listener.shutdown();
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(TransportError::Shutdown)
+        ));
+
+        assert!(matches!(
+            UnixDomainSocket::bind_abstract(&name),
+            Err(TransportError::Bind { .. })
+        ));
+        assert!(matches!(
+            UnixDomainSocket::bind_abstract(&"x".repeat(108)),
+            Err(TransportError::PathTooLong { .. })
+        ));
+        assert!(UnixDomainSocket::bind_abstract(&"y".repeat(107)).is_ok());
+    }
+
+    #[test]
+    fn test_abstract_name_parses_at_prefix() {
+        assert_eq!(
+            UnixDomainSocket::abstract_name(Path::new("@myapp")),
+            Some("myapp")
+        );
+        assert_eq!(UnixDomainSocket::abstract_name(Path::new("@")), None);
+        assert_eq!(
+            UnixDomainSocket::abstract_name(Path::new("/tmp/@myapp")),
+            None
+        );
+    }
+
+    const WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
+    /// Scheduling slack on top of one watchdog interval.
+    const WATCHDOG_GRACE: Duration = Duration::from_millis(400);
+
+    fn watchdog_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ipcprims-watchdog-{tag}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_watchdog_reports_replaced_path_within_interval() {
+        let dir = watchdog_dir("lost");
+        let sock_path = dir.join("w.sock");
+        let listener = Arc::new(
+            UnixDomainSocket::bind(&sock_path)
+                .unwrap()
+                .with_path_watchdog(WATCHDOG_INTERVAL),
+        );
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || {
+            let _ = tx.send(acceptor.accept().map(|_| ()));
+        });
+
+        std::thread::sleep(WATCHDOG_INTERVAL / 2);
+        std::fs::remove_file(&sock_path).unwrap();
+        std::fs::write(&sock_path, b"replacement-file").unwrap();
+
+        match rx.recv_timeout(WATCHDOG_INTERVAL + WATCHDOG_GRACE) {
+            Ok(Err(TransportError::PathLost { path })) => assert_eq!(path, sock_path),
+            other => panic!("expected PathLost within one interval, got {other:?}"),
+        }
+        handle.join().unwrap();
+
+        // Stays lost for later accepts.
+        assert!(matches!(
+            listener.accept(),
+            Err(TransportError::PathLost { .. })
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watchdog_rebinds_deleted_path_and_resumes() {
+        let dir = watchdog_dir("rebind");
+        let sock_path = dir.join("w.sock");
+        let (lost_tx, lost_rx) = std::sync::mpsc::channel();
+        let watchdog = PathWatchdog {
+            rebind: true,
+            on_lost: Some(Arc::new(move |path: &Path| {
+                let _ = lost_tx.send(path.to_path_buf());
+            })),
+            ..PathWatchdog::new(WATCHDOG_INTERVAL)
+        };
+        let listener = Arc::new(
+            UnixDomainSocket::bind(&sock_path)
+                .unwrap()
+                .with_path_watchdog(watchdog),
+        );
+
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || {
+            let mut server = acceptor.accept().unwrap();
+            let mut buf = [0u8; 5];
+            server.read_exact(&mut buf).unwrap();
+            buf
+        });
+
+        std::thread::sleep(WATCHDOG_INTERVAL / 2);
+        std::fs::remove_file(&sock_path).unwrap();
+
+        let lost = lost_rx
+            .recv_timeout(WATCHDOG_INTERVAL + WATCHDOG_GRACE)
+            .expect("watchdog should report the deleted path within one interval");
+        assert_eq!(lost, sock_path);
+
+        let deadline = std::time::Instant::now() + WATCHDOG_GRACE;
+        let mut client = loop {
+            match UnixDomainSocket::connect(&sock_path) {
+                Ok(client) => break client,
+                Err(err) if std::time::Instant::now() >= deadline => {
+                    panic!("rebound path should accept connections: {err}")
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        client.write_all(b"hello").unwrap();
+        assert_eq!(&handle.join().unwrap(), b"hello");
+        assert!(lost_rx.try_recv().is_err(), "callback should fire once");
+
+        drop(listener);
+        assert!(
+            !sock_path.exists(),
+            "rebound socket should be cleaned up on drop"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watchdog_rebind_never_replaces_non_socket_file() {
+        let dir = watchdog_dir("keep");
+        let sock_path = dir.join("w.sock");
+        let watchdog = PathWatchdog {
+            rebind: true,
+            ..PathWatchdog::new(WATCHDOG_INTERVAL)
+        };
+        let listener = UnixDomainSocket::bind(&sock_path)
+            .unwrap()
+            .with_path_watchdog(watchdog);
+
+        std::fs::remove_file(&sock_path).unwrap();
+        std::fs::write(&sock_path, b"replacement-file").unwrap();
+
+        assert!(matches!(
+            listener.accept(),
+            Err(TransportError::PathLost { .. })
+        ));
+        assert_eq!(std::fs::read(&sock_path).unwrap(), b"replacement-file");
+
+        drop(listener);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
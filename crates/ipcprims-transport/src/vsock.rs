@@ -0,0 +1,418 @@
+//! `AF_VSOCK` sockets between a virtual machine and its host (Linux,
+//! `vsock` feature).
+//!
+//! std has no vsock types, so sockets are created here with libc and the
+//! connected ones handed to `UnixStream`, whose read/write, timeout, clone
+//! and shutdown calls work the same on any socket family.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, TransportError};
+use crate::seqpacket::cvt;
+use crate::trace::{debug, info};
+use crate::traits::{IpcStream, Listener};
+
+/// Prefix that marks a vsock endpoint where a socket path is expected.
+const SCHEME: &str = "vsock://";
+
+/// Pending connections the kernel queues before `accept`.
+const BACKLOG: libc::c_int = 128;
+
+/// How long a blocked [`VsockTransport::accept`] waits before checking
+/// whether the listener was shut down.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Vsock transport between a guest VM and its host (`vsock` feature, Linux).
+///
+/// Addresses are a context id (CID) and a port: the host is
+/// [`CID_HOST`](Self::CID_HOST), and a listener usually binds
+/// [`CID_ANY`](Self::CID_ANY). Vsock has no file permissions or peer
+/// credentials, so anything in a VM that can reach the port can connect;
+/// pair it with a handshake auth token.
+pub struct VsockTransport {
+    listener: OwnedFd,
+    cid: u32,
+    port: u32,
+    /// `vsock://<cid>:<port>`, with the port the kernel picked for
+    /// [`PORT_ANY`](Self::PORT_ANY).
+    endpoint: PathBuf,
+    shut_down: AtomicBool,
+}
+
+impl VsockTransport {
+    /// Listen on every CID this machine has.
+    pub const CID_ANY: u32 = libc::VMADDR_CID_ANY;
+    /// The hypervisor.
+    pub const CID_HYPERVISOR: u32 = libc::VMADDR_CID_HYPERVISOR;
+    /// This machine, over the `vsock_loopback` transport.
+    pub const CID_LOCAL: u32 = libc::VMADDR_CID_LOCAL;
+    /// The host, as seen from a guest.
+    pub const CID_HOST: u32 = libc::VMADDR_CID_HOST;
+    /// Let the kernel pick a free port.
+    pub const PORT_ANY: u32 = libc::VMADDR_PORT_ANY;
+
+    /// Bind and listen on `cid`:`port`.
+    pub fn bind(cid: u32, port: u32) -> Result<Self> {
+        let bind_err = |source| TransportError::Bind {
+            path: endpoint(cid, port),
+            source,
+        };
+        // Non-blocking, so a connection another thread took first leaves
+        // `accept4` with EAGAIN instead of blocking past a deadline.
+        let listener = socket(libc::SOCK_NONBLOCK).map_err(bind_err)?;
+        let addr = sockaddr(cid, port);
+        // SAFETY: `addr` is an initialized `sockaddr_vm` and `listener` is
+        // an open socket.
+        cvt(unsafe {
+            libc::bind(
+                listener.as_raw_fd(),
+                (&raw const addr).cast::<libc::sockaddr>(),
+                std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t,
+            )
+        })
+        .map_err(bind_err)?;
+        // SAFETY: `listener` is an open, bound socket.
+        cvt(unsafe { libc::listen(listener.as_raw_fd(), BACKLOG) }).map_err(bind_err)?;
+        let (cid, port) = local_addr(&listener).map_err(bind_err)?;
+
+        info!(transport = "vsock", cid, port, "listening on vsock");
+
+        Ok(Self {
+            listener,
+            cid,
+            port,
+            endpoint: endpoint(cid, port),
+            shut_down: AtomicBool::new(false),
+        })
+    }
+
+    /// Accept an incoming connection (blocking).
+    ///
+    /// Fails with [`TransportError::Shutdown`] once [`Self::shutdown`] has
+    /// been called.
+    pub fn accept(&self) -> Result<IpcStream> {
+        loop {
+            if let Some(stream) = self.accept_until(Instant::now() + SHUTDOWN_POLL_INTERVAL)? {
+                return Ok(stream);
+            }
+        }
+    }
+
+    /// Accept an incoming connection, waiting at most `timeout`.
+    ///
+    /// Returns `Ok(None)` if no client connected in time.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let slice = (Instant::now() + SHUTDOWN_POLL_INTERVAL).min(deadline);
+            if let Some(stream) = self.accept_until(slice)? {
+                return Ok(Some(stream));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn accept_until(&self, deadline: Instant) -> Result<Option<IpcStream>> {
+        self.ensure_running()?;
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if !wait_readable(&self.listener, timeout).map_err(TransportError::Accept)? {
+            return Ok(None);
+        }
+        self.ensure_running()?;
+
+        // SAFETY: `listener` is an open listening socket; the peer address
+        // is not asked for.
+        let fd = unsafe {
+            libc::accept4(
+                self.listener.as_raw_fd(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                libc::SOCK_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            let err = io::Error::last_os_error();
+            // Another thread took the connection, or a signal arrived.
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+            ) {
+                return Ok(None);
+            }
+            return Err(TransportError::Accept(err));
+        }
+        // SAFETY: `fd` was just returned by `accept4` and is owned by
+        // nothing else.
+        let stream = UnixStream::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        debug!(transport = "vsock", "accepted connection");
+        Ok(Some(IpcStream::from_vsock(stream)))
+    }
+
+    /// Stop accepting: a blocked [`Self::accept`] returns
+    /// [`TransportError::Shutdown`] within a fraction of a second, as does
+    /// every later one.
+    pub fn shutdown(&self) {
+        if !self.shut_down.swap(true, Ordering::AcqRel) {
+            debug!(transport = "vsock", endpoint = ?self.endpoint, "listener shut down");
+        }
+    }
+
+    fn ensure_running(&self) -> Result<()> {
+        if self.shut_down.load(Ordering::Acquire) {
+            return Err(TransportError::Shutdown);
+        }
+        Ok(())
+    }
+
+    /// Connect to a vsock listener at `cid`:`port` (blocking).
+    pub fn connect(cid: u32, port: u32) -> Result<IpcStream> {
+        let connect_err = |source| TransportError::Connect {
+            path: endpoint(cid, port),
+            source,
+        };
+        let fd = socket(0).map_err(connect_err)?;
+        let addr = sockaddr(cid, port);
+        loop {
+            // SAFETY: as for `bind`.
+            let rc = unsafe {
+                libc::connect(
+                    fd.as_raw_fd(),
+                    (&raw const addr).cast::<libc::sockaddr>(),
+                    std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t,
+                )
+            };
+            match cvt(rc) {
+                Ok(()) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(connect_err(err)),
+            }
+        }
+        debug!(transport = "vsock", cid, port, "connected over vsock");
+        Ok(IpcStream::from_vsock(UnixStream::from(fd)))
+    }
+
+    /// The CID and port in a `vsock://cid:port` endpoint, or `None` for
+    /// anything else.
+    ///
+    /// Callers that take a socket path, such as the peer listener and
+    /// connector, use this to route vsock endpoints here.
+    pub fn endpoint_addr(path: &Path) -> Option<(u32, u32)> {
+        let (cid, port) = path.to_str()?.strip_prefix(SCHEME)?.split_once(':')?;
+        Some((cid.parse().ok()?, port.parse().ok()?))
+    }
+
+    /// The CID bound.
+    pub fn cid(&self) -> u32 {
+        self.cid
+    }
+
+    /// The port bound, with the one the kernel picked for
+    /// [`PORT_ANY`](Self::PORT_ANY).
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    /// The bound address as a `vsock://cid:port` endpoint.
+    pub fn path(&self) -> &Path {
+        &self.endpoint
+    }
+
+    /// Transport name for diagnostics.
+    pub fn transport_name(&self) -> &'static str {
+        "vsock"
+    }
+}
+
+impl Listener for VsockTransport {
+    type Stream = IpcStream;
+
+    fn accept(&self) -> Result<IpcStream> {
+        VsockTransport::accept(self)
+    }
+
+    fn accept_timeout(&self, timeout: Duration) -> Result<Option<IpcStream>> {
+        VsockTransport::accept_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) {
+        VsockTransport::shutdown(self);
+    }
+
+    fn path(&self) -> &Path {
+        VsockTransport::path(self)
+    }
+}
+
+fn socket(flags: libc::c_int) -> io::Result<OwnedFd> {
+    // SAFETY: plain syscall; the result is checked before use.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_VSOCK,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC | flags,
+            0,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just returned by `socket` and is owned by nothing else.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn sockaddr(cid: u32, port: u32) -> libc::sockaddr_vm {
+    // SAFETY: all-zero is a valid `sockaddr_vm`.
+    let mut addr: libc::sockaddr_vm = unsafe { std::mem::zeroed() };
+    addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+    addr.svm_cid = cid;
+    addr.svm_port = port;
+    addr
+}
+
+fn local_addr(fd: &OwnedFd) -> io::Result<(u32, u32)> {
+    socket_addr(fd.as_raw_fd(), false)
+}
+
+/// The `(cid, port)` a vsock socket is bound to, or with `peer` the one it
+/// is connected to.
+pub(crate) fn socket_addr(fd: std::os::fd::RawFd, peer: bool) -> io::Result<(u32, u32)> {
+    let mut addr = sockaddr(0, 0);
+    let mut len = std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t;
+    // SAFETY: `addr` is writable for `len` bytes; a bad `fd` only fails.
+    cvt(unsafe {
+        if peer {
+            libc::getpeername(fd, (&raw mut addr).cast(), &mut len)
+        } else {
+            libc::getsockname(fd, (&raw mut addr).cast(), &mut len)
+        }
+    })?;
+    Ok((addr.svm_cid, addr.svm_port))
+}
+
+/// Wait up to `timeout` for a connection to accept; `false` if none came.
+fn wait_readable(fd: &OwnedFd, timeout: Duration) -> io::Result<bool> {
+    // Round up so a sub-millisecond wait does not become a 0 ms spin.
+    let timeout_ms =
+        libc::c_int::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(libc::c_int::MAX);
+    let mut pollfd = libc::pollfd {
+        fd: fd.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `pollfd` is a valid, writable array of length 1.
+    let rc = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+    if rc < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+            return Ok(false);
+        }
+        return Err(err);
+    }
+    Ok(rc > 0)
+}
+
+fn endpoint(cid: u32, port: u32) -> PathBuf {
+    PathBuf::from(format!("{SCHEME}{cid}:{port}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Bind on the loopback CID, or `None` where this kernel has no vsock.
+    fn bind_local() -> Option<VsockTransport> {
+        match VsockTransport::bind(VsockTransport::CID_ANY, VsockTransport::PORT_ANY) {
+            Ok(listener) => Some(listener),
+            Err(TransportError::Bind { source, .. })
+                if matches!(
+                    source.raw_os_error(),
+                    Some(libc::EAFNOSUPPORT | libc::ENODEV | libc::EADDRNOTAVAIL)
+                ) =>
+            {
+                None
+            }
+            Err(err) => panic!("vsock bind failed: {err}"),
+        }
+    }
+
+    #[test]
+    fn bind_reports_the_endpoint_and_shuts_down() {
+        let Some(listener) = bind_local() else {
+            return;
+        };
+        assert_ne!(listener.port(), VsockTransport::PORT_ANY);
+        assert_eq!(
+            listener.path(),
+            Path::new(&format!("vsock://{}:{}", listener.cid(), listener.port()))
+        );
+        assert_eq!(listener.transport_name(), "vsock");
+        assert!(listener
+            .accept_timeout(Duration::from_millis(30))
+            .unwrap()
+            .is_none());
+
+        let listener = Arc::new(listener);
+        let acceptor = Arc::clone(&listener);
+        let handle = std::thread::spawn(move || acceptor.accept().map(|_| ()));
+        std::thread::sleep(Duration::from_millis(50));
+        listener.shutdown();
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(TransportError::Shutdown)
+        ));
+    }
+
+    #[test]
+    #[ignore = "needs the vsock_loopback kernel module"]
+    fn bind_accept_connect_over_loopback() {
+        let listener = bind_local().expect("kernel should support vsock");
+        let port = listener.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        });
+
+        let mut client = VsockTransport::connect(VsockTransport::CID_LOCAL, port).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut reader = client.try_clone().unwrap();
+        client.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        assert!(format!("{client:?}").contains("vsock"));
+        assert!(client.peer_credentials().is_none());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn endpoint_addr_parses_vsock_scheme() {
+        assert_eq!(
+            VsockTransport::endpoint_addr(Path::new("vsock://2:5000")),
+            Some((2, 5000))
+        );
+        assert_eq!(
+            VsockTransport::endpoint_addr(Path::new("vsock://4294967295:1")),
+            Some((VsockTransport::CID_ANY, 1))
+        );
+        assert_eq!(VsockTransport::endpoint_addr(Path::new("vsock://2")), None);
+        assert_eq!(
+            VsockTransport::endpoint_addr(Path::new("vsock://x:1")),
+            None
+        );
+        assert_eq!(VsockTransport::endpoint_addr(Path::new("tcp://2:1")), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Auth token flags: presenting a token (`send`, `info`) and requiring one
+//! (`echo`, `listen`). Token values are never logged or printed.
+
+use std::path::Path;
+
+use ipcprims_peer::{PeerError, PeerListener};
+
+use crate::exit::{io_error, CliError, CliResult, USAGE};
+
+/// Resolve a token given inline or via a file. `flag` names the flag pair in errors.
+pub(crate) fn resolve_token(
+    inline: Option<&str>,
+    file: Option<&Path>,
+    flag: &str,
+) -> CliResult<Option<String>> {
+    let token = match (inline, file) {
+        (Some(token), _) => token.to_string(),
+        (None, Some(path)) => {
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                io_error(
+                    &format!("failed reading {flag}-file {}", path.display()),
+                    err,
+                )
+            })?;
+            // Tolerate the trailing newline editors and `echo` leave behind.
+            contents.trim_end_matches(['\r', '\n']).to_string()
+        }
+        (None, None) => return Ok(None),
+    };
+
+    if token.is_empty() {
+        return Err(CliError::new(USAGE, format!("{flag} must not be empty")));
+    }
+    Ok(Some(token))
+}
+
+/// Make `listener` reject clients that do not present `expected`.
+pub(crate) fn require_token(listener: PeerListener, expected: Option<String>) -> PeerListener {
+    let Some(expected) = expected else {
+        return listener;
+    };
+
+    listener.with_auth_validator(move |presented| match presented {
+        Some(token) if tokens_match(token.as_bytes(), expected.as_bytes()) => true,
+        Some(_) => {
+            tracing::warn!("rejected client: auth token mismatch");
+            false
+        }
+        None => {
+            tracing::warn!("rejected client: no auth token presented");
+            false
+        }
+    })
+}
+
+/// Whether an accept error only concerns the one client (e.g. a rejected
+/// token) so the server should keep accepting.
+pub(crate) fn is_client_rejection(err: &PeerError) -> bool {
+    matches!(err, PeerError::HandshakeFailed(_))
+}
+
+/// Compare without short-circuiting on the first differing byte.
+fn tokens_match(presented: &[u8], expected: &[u8]) -> bool {
+    if presented.len() != expected.len() {
+        return false;
+    }
+    presented
+        .iter()
+        .zip(expected)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_token_wins_and_empty_is_rejected() {
+        assert_eq!(
+            resolve_token(Some("abc"), None, "--auth-token").unwrap(),
+            Some("abc".to_string())
+        );
+        assert_eq!(resolve_token(None, None, "--auth-token").unwrap(), None);
+
+        let err = resolve_token(Some(""), None, "--auth-token").expect_err("empty token");
+        assert_eq!(err.code, USAGE);
+    }
+
+    #[test]
+    fn token_file_strips_trailing_newline() {
+        let path = std::env::temp_dir().join(format!(
+            "ipcprims-token-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::write(&path, "s3cret\n").unwrap();
+        assert_eq!(
+            resolve_token(None, Some(&path), "--auth-token").unwrap(),
+            Some("s3cret".to_string())
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn tokens_match_requires_exact_bytes() {
+        assert!(tokens_match(b"token", b"token"));
+        assert!(!tokens_match(b"token", b"tokeN"));
+        assert!(!tokens_match(b"token", b"token2"));
+    }
+}
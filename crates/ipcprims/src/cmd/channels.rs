@@ -0,0 +1,190 @@
+use ipcprims_frame::channel::{builtin_channel, channel_name, BUILTIN_CHANNELS};
+#[cfg(feature = "schema")]
+use ipcprims_schema::SchemaRegistry;
+use serde::Serialize;
+
+use crate::cmd::ChannelsArgs;
+#[cfg(feature = "schema")]
+use crate::exit::CliError;
+use crate::exit::{CliResult, SUCCESS};
+use crate::output::OutputFormat;
+
+#[derive(Debug, Serialize)]
+struct ChannelEntry {
+    id: u16,
+    name: &'static str,
+    kind: &'static str,
+    purpose: Option<&'static str>,
+    has_schema: bool,
+    schema_title: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChannelsOutput {
+    schema_id: &'static str,
+    channels: Vec<ChannelEntry>,
+}
+
+pub fn run(args: ChannelsArgs, format: OutputFormat) -> CliResult<i32> {
+    let mut channels = builtin_entries();
+
+    #[cfg(feature = "schema")]
+    if let Some(dir) = &args.schema_dir {
+        let registry = SchemaRegistry::from_directory(dir).map_err(|err| {
+            CliError::new(
+                crate::exit::DATA_INVALID,
+                format!("schema load failed: {err}"),
+            )
+        })?;
+        apply_schema_registry(&mut channels, &registry);
+    }
+    #[cfg(not(feature = "schema"))]
+    let _ = &args;
+
+    let output = ChannelsOutput {
+        schema_id: "https://schemas.3leaps.dev/ipcprims/cli/v1/channel-list.schema.json",
+        channels,
+    };
+
+    print_channels(&output, format);
+    Ok(SUCCESS)
+}
+
+fn builtin_entries() -> Vec<ChannelEntry> {
+    BUILTIN_CHANNELS
+        .iter()
+        .map(|info| ChannelEntry {
+            id: info.id,
+            name: info.name,
+            kind: "builtin",
+            purpose: Some(info.purpose),
+            has_schema: false,
+            schema_title: None,
+        })
+        .collect()
+}
+
+#[cfg(feature = "schema")]
+fn apply_schema_registry(channels: &mut Vec<ChannelEntry>, registry: &SchemaRegistry) {
+    for id in registry.channels() {
+        let title = registry.title(id).map(str::to_string);
+        if let Some(entry) = channels.iter_mut().find(|entry| entry.id == id) {
+            entry.has_schema = true;
+            entry.schema_title = title;
+            continue;
+        }
+
+        channels.push(ChannelEntry {
+            id,
+            name: channel_name(id),
+            kind: if builtin_channel(id).is_some() {
+                "builtin"
+            } else {
+                "user"
+            },
+            purpose: None,
+            has_schema: true,
+            schema_title: title,
+        });
+    }
+    channels.sort_by_key(|entry| entry.id);
+}
+
+fn print_channels(output: &ChannelsOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string(output).unwrap_or_else(|_| "{}".to_string())
+        ),
+        OutputFormat::Table | OutputFormat::Pretty => {
+            let mut table = comfy_table::Table::new();
+            table
+                .load_preset(comfy_table::presets::UTF8_FULL)
+                .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+                .set_header(vec!["ID", "NAME", "KIND", "PURPOSE", "SCHEMA"]);
+            for entry in &output.channels {
+                let schema = match (&entry.schema_title, entry.has_schema) {
+                    (Some(title), _) => title.clone(),
+                    (None, true) => "(untitled)".to_string(),
+                    (None, false) => "-".to_string(),
+                };
+                table.add_row(vec![
+                    entry.id.to_string(),
+                    entry.name.to_string(),
+                    entry.kind.to_string(),
+                    entry.purpose.unwrap_or("-").to_string(),
+                    schema,
+                ]);
+            }
+            println!("{table}");
+        }
+        OutputFormat::Raw => {
+            for entry in &output.channels {
+                println!("{}\t{}", entry.id, entry.name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ipcprims_frame::{COMMAND, HEARTBEAT};
+
+    use super::*;
+
+    #[test]
+    fn builtin_entries_match_library_registry() {
+        let entries = builtin_entries();
+        assert_eq!(entries.len(), BUILTIN_CHANNELS.len());
+        for (entry, info) in entries.iter().zip(BUILTIN_CHANNELS) {
+            assert_eq!(
+                (entry.id, entry.name, entry.purpose),
+                (info.id, info.name, Some(info.purpose))
+            );
+            assert_eq!(entry.kind, "builtin");
+        }
+        assert!(entries.iter().any(|e| e.id == HEARTBEAT));
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn schema_registry_adds_user_channels_with_titles() {
+        let registry = SchemaRegistry::from_embedded(&[
+            (COMMAND, r#"{"title":"Command","type":"object"}"#),
+            (300, r#"{"title":"Job Update","type":"object"}"#),
+            (301, r#"{"type":"object"}"#),
+        ])
+        .unwrap();
+
+        let mut entries = builtin_entries();
+        apply_schema_registry(&mut entries, &registry);
+
+        let command = entries.iter().find(|e| e.id == COMMAND).unwrap();
+        assert!(command.has_schema);
+        assert_eq!(command.schema_title.as_deref(), Some("Command"));
+
+        let user = entries.iter().find(|e| e.id == 300).unwrap();
+        assert_eq!(user.kind, "user");
+        assert_eq!(user.schema_title.as_deref(), Some("Job Update"));
+
+        let untitled = entries.iter().find(|e| e.id == 301).unwrap();
+        assert!(untitled.has_schema);
+        assert!(untitled.schema_title.is_none());
+
+        let ids: Vec<u16> = entries.iter().map(|e| e.id).collect();
+        let mut sorted = ids.clone();
+        sorted.sort_unstable();
+        assert_eq!(ids, sorted);
+    }
+
+    #[test]
+    fn channels_json_has_schema_id() {
+        let out = ChannelsOutput {
+            schema_id: "x",
+            channels: builtin_entries(),
+        };
+        let json = serde_json::to_string(&out).expect("channels output should serialize");
+        assert!(json.contains("\"schema_id\""));
+        assert!(json.contains("\"TELEMETRY\""));
+    }
+}
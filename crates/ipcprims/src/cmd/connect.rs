@@ -0,0 +1,159 @@
+//! Connection helpers shared by client commands (`send`, `info`).
+
+use std::path::Path;
+use std::time::Duration;
+
+use ipcprims_peer::{
+    connect_with_retry, HandshakeConfig, Peer, PeerConfig, PeerError, RetryPolicy, TimeoutOp,
+};
+use ipcprims_transport::TransportError;
+
+use crate::exit::{peer_error, CliError, CliResult, FAILURE, TIMEOUT, USAGE};
+
+/// Parse a duration flag such as `5s`, `500ms`, or `3` (seconds).
+pub(crate) fn parse_duration(input: &str) -> CliResult<Duration> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(CliError::new(USAGE, "duration must not be empty"));
+    }
+
+    let (number, unit) = if let Some(num) = input.strip_suffix("ms") {
+        (num, "ms")
+    } else if let Some(num) = input.strip_suffix('s') {
+        (num, "s")
+    } else {
+        (input, "s")
+    };
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| CliError::new(USAGE, format!("invalid duration value: {input}")))?;
+
+    if value == 0 {
+        return Err(CliError::new(USAGE, "duration must be greater than zero"));
+    }
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" => Ok(Duration::from_secs(value)),
+        _ => Err(CliError::new(
+            USAGE,
+            format!("unsupported duration unit: {unit}"),
+        )),
+    }
+}
+
+/// Connect and handshake, bounded by `timeout`.
+///
+/// The connect phase goes through [`connect_with_retry`], which retries
+/// missing or refusing sockets until `timeout` elapses. The handshake itself
+/// is bounded by `handshake_config.timeout`. Either phase running out of
+/// time is reported as `TIMEOUT` naming the phase.
+pub(crate) fn connect_with_timeout(
+    path: &Path,
+    channels: &[u16],
+    handshake_config: &HandshakeConfig,
+    peer_config: Option<PeerConfig>,
+    timeout: Duration,
+) -> CliResult<Peer> {
+    let policy = RetryPolicy {
+        max_duration: timeout,
+        ..RetryPolicy::default()
+    };
+    connect_with_retry(path, channels, handshake_config, &policy, peer_config).map_err(|err| {
+        match err {
+            PeerError::Transport(TransportError::ConnectTimeout { source, .. }) => {
+                let timed_out = PeerError::Timeout {
+                    operation: TimeoutOp::Connect,
+                    waited: timeout,
+                };
+                CliError::new(TIMEOUT, format!("{timed_out}: {source}"))
+            }
+            err @ PeerError::Timeout { .. } => CliError::new(
+                TIMEOUT,
+                format!("{err} (server accepted but did not respond)"),
+            ),
+            // The server hangs up without a response when it rejects the handshake.
+            err @ PeerError::Disconnected(_) => CliError::new(
+                FAILURE,
+                format!(
+                    "connect failed: server closed the connection during handshake \
+                     (auth token or channels rejected?): {err}"
+                ),
+            ),
+            err => peer_error("connect failed", err),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_seconds_and_millis() {
+        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("150ms").unwrap(), Duration::from_millis(150));
+        assert_eq!(parse_duration("3").unwrap(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_values() {
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("bad").is_err());
+        assert!(parse_duration(" ").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn missing_socket_times_out_in_connect_phase() {
+        let path = std::path::PathBuf::from(format!(
+            "/tmp/ipcprims-connect-missing-{}.sock",
+            std::process::id()
+        ));
+        let err = connect_with_timeout(
+            &path,
+            &[1],
+            &HandshakeConfig::default(),
+            None,
+            Duration::from_millis(100),
+        )
+        .err()
+        .expect("missing socket should time out");
+        assert_eq!(err.code, TIMEOUT);
+        assert!(err.message.starts_with("connect timed out"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn silent_server_times_out_in_handshake_phase() {
+        let dir = std::path::PathBuf::from(format!(
+            "/tmp/ipcprims-connect-wedged-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wedged.sock");
+        // Bound but never accepted: the kernel completes connect, nobody answers.
+        let socket = ipcprims_transport::UnixDomainSocket::bind(&path).unwrap();
+
+        let err = connect_with_timeout(
+            &path,
+            &[1],
+            &HandshakeConfig {
+                timeout: Duration::from_millis(100),
+                ..HandshakeConfig::default()
+            },
+            None,
+            Duration::from_millis(100),
+        )
+        .err()
+        .expect("silent server should time out");
+        assert_eq!(err.code, TIMEOUT);
+        assert!(err
+            .message
+            .starts_with("handshake read timed out after 100ms"));
+
+        drop(socket);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
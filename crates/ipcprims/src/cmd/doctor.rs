@@ -1,14 +1,21 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
+use ipcprims_transport::capabilities::{self, Capability, ProbeStatus};
 use serde::Serialize;
 
 use crate::cmd::DoctorArgs;
 use crate::exit::{CliResult, HEALTH_CHECK_FAILED, SUCCESS};
 use crate::output::OutputFormat;
 
+/// Upper bound for each transport capability probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "lowercase")]
-enum CheckStatus {
+pub(crate) enum CheckStatus {
     Pass,
     Fail,
     Warn,
@@ -17,20 +24,40 @@ enum CheckStatus {
 }
 
 #[derive(Debug, Serialize)]
-struct CheckResult {
-    name: String,
-    status: CheckStatus,
-    detail: String,
+pub(crate) struct CheckResult {
+    pub(crate) name: String,
+    pub(crate) status: CheckStatus,
+    pub(crate) detail: String,
 }
 
+/// Check report shared by `doctor` and `selftest`.
 #[derive(Debug, Serialize)]
-struct DoctorOutput {
+pub(crate) struct CheckReport {
     schema_id: &'static str,
     checks: Vec<CheckResult>,
     overall: &'static str,
 }
 
-pub fn run(_args: DoctorArgs, format: OutputFormat) -> CliResult<i32> {
+impl CheckReport {
+    pub(crate) fn new(schema_id: &'static str, checks: Vec<CheckResult>) -> Self {
+        let has_fail = checks.iter().any(|c| matches!(c.status, CheckStatus::Fail));
+        Self {
+            schema_id,
+            checks,
+            overall: if has_fail { "fail" } else { "pass" },
+        }
+    }
+
+    pub(crate) fn exit_code(&self) -> i32 {
+        if self.overall == "pass" {
+            SUCCESS
+        } else {
+            HEALTH_CHECK_FAILED
+        }
+    }
+}
+
+pub fn run(args: DoctorArgs, format: OutputFormat) -> CliResult<i32> {
     let mut checks = vec![
         platform_transport_check(),
         temp_dir_writable_check(),
@@ -39,23 +66,18 @@ pub fn run(_args: DoctorArgs, format: OutputFormat) -> CliResult<i32> {
     ];
 
     checks.push(schema_dir_check());
+    checks.extend(transport_capabilities_checks());
+    if let Some(path) = &args.socket {
+        checks.push(socket_check(path));
+    }
 
-    let has_fail = checks.iter().any(|c| matches!(c.status, CheckStatus::Fail));
-    let overall = if has_fail { "fail" } else { "pass" };
-
-    let output = DoctorOutput {
-        schema_id: "https://schemas.3leaps.dev/ipcprims/cli/v1/doctor-report.schema.json",
+    let output = CheckReport::new(
+        "https://schemas.3leaps.dev/ipcprims/cli/v1/doctor-report.schema.json",
         checks,
-        overall,
-    };
+    );
 
-    print_doctor(&output, format);
-
-    if has_fail {
-        Ok(HEALTH_CHECK_FAILED)
-    } else {
-        Ok(SUCCESS)
-    }
+    print_report("ipcprims doctor", &output, format);
+    Ok(output.exit_code())
 }
 
 fn rsfulmen_alignment_check() -> CheckResult {
@@ -66,7 +88,7 @@ fn rsfulmen_alignment_check() -> CheckResult {
     }
 }
 
-fn print_doctor(output: &DoctorOutput, format: OutputFormat) {
+pub(crate) fn print_report(title: &str, output: &CheckReport, format: OutputFormat) {
     match format {
         OutputFormat::Json => {
             println!(
@@ -75,7 +97,7 @@ fn print_doctor(output: &DoctorOutput, format: OutputFormat) {
             );
         }
         OutputFormat::Table | OutputFormat::Pretty => {
-            println!("ipcprims doctor\n");
+            println!("{title}\n");
             for c in &output.checks {
                 println!(
                     "  [{:>4}] {:<22} {}",
@@ -203,6 +225,150 @@ fn temp_dir_writable_check() -> CheckResult {
     }
 }
 
+/// One check per optional transport feature, probed at runtime.
+///
+/// Unavailable features are INFO rather than FAIL: the JSON report doubles
+/// as a capabilities matrix, and none of these are required.
+fn transport_capabilities_checks() -> Vec<CheckResult> {
+    Capability::ALL
+        .into_iter()
+        .map(|capability| {
+            let name = format!("transport_capabilities.{}", capability.name());
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let _ = tx.send(capabilities::probe(capability));
+            });
+
+            match rx.recv_timeout(PROBE_TIMEOUT) {
+                Ok(result) => CheckResult {
+                    name,
+                    status: match result.status {
+                        ProbeStatus::Available => CheckStatus::Pass,
+                        ProbeStatus::Unavailable => CheckStatus::Info,
+                        ProbeStatus::NotApplicable => CheckStatus::Skip,
+                    },
+                    detail: result.detail,
+                },
+                Err(_) => CheckResult {
+                    name,
+                    status: CheckStatus::Info,
+                    detail: format!("probe did not finish within {PROBE_TIMEOUT:?}"),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Connect to a listener socket path and explain why it is unreachable.
+///
+/// On Linux a listener still bound to `path` in `/proc/net/unix` while the
+/// path itself is gone, foreign or refuses connections is reported as a
+/// listener inode mismatch: the file at the path is no longer the listener's.
+fn socket_check(path: &Path) -> CheckResult {
+    let result = |status, detail: String| CheckResult {
+        name: "socket".to_string(),
+        status,
+        detail,
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let mismatch = |what: &str| {
+            result(
+                CheckStatus::Fail,
+                format!("listener inode mismatch: {} {what}", path.display()),
+            )
+        };
+        let bound = listener_bound_at(path);
+
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) if bound == Some(true) => {
+                return mismatch("was removed while its listener is still bound")
+            }
+            Err(err) => {
+                return result(
+                    CheckStatus::Fail,
+                    format!("{} is not accessible: {err}", path.display()),
+                )
+            }
+        };
+        if !metadata.file_type().is_socket() {
+            if bound == Some(true) {
+                return mismatch("was replaced by a non-socket file");
+            }
+            return result(
+                CheckStatus::Fail,
+                format!("{} is not a socket", path.display()),
+            );
+        }
+
+        match ipcprims_transport::UnixDomainSocket::connect(path) {
+            Ok(_) => result(
+                CheckStatus::Pass,
+                format!("{} is accepting connections", path.display()),
+            ),
+            Err(_) if bound == Some(true) => {
+                mismatch("was replaced; the bound listener is unreachable")
+            }
+            Err(err) => result(
+                CheckStatus::Fail,
+                format!("{} has no live listener: {err}", path.display()),
+            ),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        result(
+            CheckStatus::Skip,
+            format!(
+                "{}: socket path check is only available on Unix",
+                path.display()
+            ),
+        )
+    }
+}
+
+/// Whether some process holds a listening socket bound to `path`.
+///
+/// `None` where the platform does not expose bound socket names.
+#[cfg(unix)]
+fn listener_bound_at(path: &Path) -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        // Columns: Num RefCount Protocol Flags Type St Inode Path.
+        const SO_ACCEPTCON: u32 = 0x0001_0000;
+
+        let table = std::fs::read_to_string("/proc/net/unix").ok()?;
+        let mut names = vec![path.to_path_buf()];
+        if path.is_relative() {
+            if let Ok(cwd) = std::env::current_dir() {
+                names.push(cwd.join(path));
+            }
+        }
+        Some(table.lines().skip(1).any(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let listening = fields
+                .get(3)
+                .and_then(|flags| u32::from_str_radix(flags, 16).ok())
+                .is_some_and(|flags| flags & SO_ACCEPTCON != 0);
+            listening
+                && fields
+                    .get(7)
+                    .is_some_and(|name| names.iter().any(|p| p.as_os_str() == *name))
+        }))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
 fn compiled_features_check() -> CheckResult {
     let mut features = Vec::new();
     if cfg!(feature = "peer") {
@@ -290,12 +456,98 @@ mod tests {
             status: CheckStatus::Pass,
             detail: "ok".to_string(),
         }];
-        let output = DoctorOutput {
-            schema_id: "x",
-            checks,
-            overall: "pass",
-        };
+        let output = CheckReport::new("x", checks);
         let json = serde_json::to_string(&output).expect("doctor output should serialize");
         assert!(json.contains("\"overall\":\"pass\""));
+        assert_eq!(output.exit_code(), SUCCESS);
+    }
+
+    #[test]
+    fn transport_capabilities_never_fail_the_report() {
+        let checks = transport_capabilities_checks();
+        assert_eq!(checks.len(), Capability::ALL.len());
+        assert!(checks
+            .iter()
+            .all(|c| c.name.starts_with("transport_capabilities.")));
+        assert!(checks
+            .iter()
+            .all(|c| !matches!(c.status, CheckStatus::Fail)));
+    }
+
+    #[cfg(unix)]
+    fn socket_dir(tag: &str) -> PathBuf {
+        let dir = PathBuf::from(format!(
+            "/tmp/ipcprims-doctor-{tag}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("time should be after epoch")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_check_passes_for_live_listener() {
+        let dir = socket_dir("live");
+        let sock = dir.join("live.sock");
+        let _listener =
+            ipcprims_transport::UnixDomainSocket::bind(&sock).expect("socket should bind");
+
+        let check = socket_check(&sock);
+        assert!(matches!(check.status, CheckStatus::Pass), "{check:?}");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn socket_check_reports_listener_inode_mismatch() {
+        let dir = socket_dir("mismatch");
+        let sock = dir.join("mismatch.sock");
+        let _listener =
+            ipcprims_transport::UnixDomainSocket::bind(&sock).expect("socket should bind");
+
+        std::fs::remove_file(&sock).expect("socket path should be removable");
+        let check = socket_check(&sock);
+        assert!(matches!(check.status, CheckStatus::Fail));
+        assert!(
+            check.detail.contains("listener inode mismatch"),
+            "{check:?}"
+        );
+
+        std::fs::write(&sock, b"replacement-file").expect("path should be writable");
+        let check = socket_check(&sock);
+        assert!(
+            check.detail.contains("listener inode mismatch"),
+            "{check:?}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_check_fails_for_missing_path() {
+        let dir = socket_dir("missing");
+        let check = socket_check(&dir.join("missing.sock"));
+        assert!(matches!(check.status, CheckStatus::Fail));
+        assert!(!check.detail.contains("listener inode mismatch"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_check_fails_report() {
+        let checks = vec![CheckResult {
+            name: "x".to_string(),
+            status: CheckStatus::Fail,
+            detail: "broken".to_string(),
+        }];
+        let output = CheckReport::new("x", checks);
+        assert_eq!(output.overall, "fail");
+        assert_eq!(output.exit_code(), HEALTH_CHECK_FAILED);
     }
 }
@@ -1,24 +1,132 @@
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use ipcprims_frame::ERROR;
-use ipcprims_peer::PeerListener;
+use ipcprims_frame::channel::channel_name;
+use ipcprims_frame::{FrameError, ERROR};
+use ipcprims_peer::{PeerConfig, PeerError};
 #[cfg(feature = "schema")]
 use ipcprims_schema::{RegistryConfig, SchemaRegistry};
 
-use crate::cmd::EchoArgs;
-use crate::exit::{peer_error, CliError, CliResult, SUCCESS};
-use crate::output::{channel_name, OutputFormat};
+use crate::cmd::auth::{is_client_rejection, require_token, resolve_token};
+use crate::cmd::connect::parse_duration;
+use crate::cmd::signal::{drain_bounded, install_handler, release_listener, SIGNAL_POLL_INTERVAL};
+use crate::cmd::{bind_listener, require_path, EchoArgs};
+use crate::exit::{peer_error, CliError, CliResult, SUCCESS, USAGE};
+use crate::output::{print_listener_metrics, OutputFormat};
+use crate::units::parse_size;
+
+/// Granularity at which a pending delay checks for a termination signal.
+const DELAY_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Fault-injection settings shared by all connections.
+#[derive(Debug, Default)]
+struct EchoShaping {
+    delay: Option<Duration>,
+    channel_delays: HashMap<u16, Duration>,
+    drop_every: Option<u64>,
+}
+
+impl EchoShaping {
+    fn from_args(args: &EchoArgs) -> CliResult<Self> {
+        let delay = args.delay.as_deref().map(parse_duration).transpose()?;
+
+        let mut channel_delays = HashMap::new();
+        for spec in &args.delay_channel {
+            let (channel, duration) = spec.split_once('=').ok_or_else(|| {
+                CliError::new(
+                    USAGE,
+                    format!("invalid --delay-channel '{spec}': expected CHAN=DURATION"),
+                )
+            })?;
+            let channel: u16 = channel.trim().parse().map_err(|_| {
+                CliError::new(
+                    USAGE,
+                    format!("invalid --delay-channel '{spec}': bad channel id"),
+                )
+            })?;
+            channel_delays.insert(channel, parse_duration(duration)?);
+        }
+
+        Ok(Self {
+            delay,
+            channel_delays,
+            drop_every: args.drop_every,
+        })
+    }
+
+    fn delay_for(&self, channel: u16) -> Option<Duration> {
+        self.channel_delays.get(&channel).copied().or(self.delay)
+    }
+}
+
+/// Per-connection shaping state, so each client is delayed/dropped independently.
+struct ConnectionShaper<'a> {
+    shaping: &'a EchoShaping,
+    frames_seen: u64,
+}
+
+impl<'a> ConnectionShaper<'a> {
+    fn new(shaping: &'a EchoShaping) -> Self {
+        Self {
+            shaping,
+            frames_seen: 0,
+        }
+    }
+
+    /// Returns true if this frame should be swallowed without a reply.
+    fn should_drop(&mut self) -> bool {
+        self.frames_seen = self.frames_seen.wrapping_add(1);
+        self.shaping
+            .drop_every
+            .is_some_and(|n| self.frames_seen % n == 0)
+    }
+
+    /// Sleep for the configured delay. Returns false if interrupted by shutdown.
+    fn wait(&self, channel: u16, running: &AtomicBool) -> bool {
+        match self.shaping.delay_for(channel) {
+            Some(delay) => sleep_while_running(delay, running),
+            None => true,
+        }
+    }
+}
+
+fn sleep_while_running(duration: Duration, running: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep(DELAY_POLL_INTERVAL.min(deadline - now));
+    }
+    false
+}
 
 enum RecvErrorDisposition {
     Break,
     ContinueWithError(Vec<u8>),
+    /// Reply on ERROR, then close: the stream cannot be resynchronized.
+    CloseWithError(Vec<u8>),
     Fatal(CliError),
 }
 
-pub fn run(args: EchoArgs, _format: OutputFormat) -> CliResult<i32> {
-    let mut listener =
-        PeerListener::bind(&args.path).map_err(|err| peer_error("bind failed", err))?;
+pub fn run(args: EchoArgs, format: OutputFormat) -> CliResult<i32> {
+    let path = require_path(args.path.as_deref())?;
+    let required_token = resolve_token(
+        args.require_token.as_deref(),
+        args.require_token_file.as_deref(),
+        "--require-token",
+    )?;
+    let max_payload = args.max_payload.as_deref().map(parse_size).transpose()?;
+    let mut listener = bind_listener(path, args.socket_mode, args.socket_group.as_deref())?;
+    listener = require_token(listener, required_token);
+    let mut peer_config = PeerConfig::default();
+    if let Some(max_payload_size) = max_payload {
+        peer_config.max_payload_size = max_payload_size;
+    }
+    listener = listener.with_peer_config(drain_bounded(peer_config));
 
     if let Some(channels) = &args.channels {
         listener = listener.with_channels(channels);
@@ -43,18 +151,44 @@ pub fn run(args: EchoArgs, _format: OutputFormat) -> CliResult<i32> {
         listener = listener.with_schema_registry(std::sync::Arc::new(registry));
     }
 
+    let shaping = EchoShaping::from_args(&args)?;
+
     let running = Arc::new(AtomicBool::new(true));
-    install_ctrlc_handler(running.clone())?;
+    let listener = Arc::new(listener);
+    let signalled = install_handler(running.clone(), Arc::downgrade(&listener))?;
 
+    let mut listener = Some(listener);
+    // Counters as of the last unbind, for `--stats` after `--once`.
+    let mut unbound_metrics = None;
     while running.load(Ordering::SeqCst) {
-        let mut peer = match listener.accept() {
-            Ok(peer) => peer,
+        let Some(active) = listener.as_ref() else {
+            break;
+        };
+        let mut peer = match active.accept_timeout(SIGNAL_POLL_INTERVAL) {
+            Ok(Some(peer)) => peer,
+            // Re-check `running` even if the signal's wake-up was missed.
+            Ok(None) => continue,
+            // Woken by the signal handler's listener shutdown.
+            Err(_) if !running.load(Ordering::SeqCst) => break,
+            Err(err) if is_client_rejection(&err) => {
+                tracing::warn!(error = %err, "client handshake failed");
+                continue;
+            }
             Err(err) => return Err(peer_error("accept failed", err)),
         };
+        if args.once {
+            // Unbind immediately so no second client can queue behind this one.
+            unbound_metrics = listener.take().map(|listener| listener.metrics());
+        }
+        peer.set_recv_timeout(SIGNAL_POLL_INTERVAL)
+            .map_err(|err| peer_error("accept failed", err))?;
 
+        let mut shaper = ConnectionShaper::new(&shaping);
         while running.load(Ordering::SeqCst) {
             let frame = match peer.recv() {
                 Ok(frame) => frame,
+                // Idle read timeout; keep waiting (and re-check the signal flag).
+                Err(PeerError::Timeout { .. }) => continue,
                 Err(err) => match classify_recv_error(err) {
                     RecvErrorDisposition::Break => break,
                     RecvErrorDisposition::ContinueWithError(payload) => {
@@ -63,6 +197,16 @@ pub fn run(args: EchoArgs, _format: OutputFormat) -> CliResult<i32> {
                         }
                         continue;
                     }
+                    RecvErrorDisposition::CloseWithError(payload) => {
+                        tracing::warn!(
+                            peer_id = peer.id(),
+                            "frame exceeds --max-payload; closing connection"
+                        );
+                        if let Err(send_err) = peer.send_final_error(&payload) {
+                            tracing::warn!(error = %send_err, "failed sending payload error response");
+                        }
+                        break;
+                    }
                     RecvErrorDisposition::Fatal(cli_err) => return Err(cli_err),
                 },
             };
@@ -73,6 +217,19 @@ pub fn run(args: EchoArgs, _format: OutputFormat) -> CliResult<i32> {
                 }
             }
 
+            if shaper.should_drop() {
+                tracing::info!(
+                    channel = frame.channel,
+                    channel_name = channel_name(frame.channel),
+                    size = frame.payload.len(),
+                    "dropping frame (--drop-every)"
+                );
+                continue;
+            }
+            if !shaper.wait(frame.channel, &running) {
+                break;
+            }
+
             tracing::info!(
                 channel = frame.channel,
                 channel_name = channel_name(frame.channel),
@@ -80,26 +237,37 @@ pub fn run(args: EchoArgs, _format: OutputFormat) -> CliResult<i32> {
                 "echoing frame"
             );
 
-            peer.send(frame.channel, frame.payload.as_ref())
-                .map_err(|err| peer_error("echo send failed", err))?;
+            match peer.send(frame.channel, frame.payload.as_ref()) {
+                Ok(()) => {}
+                // The client left before reading its echo; that ends this
+                // connection, not the server.
+                Err(PeerError::Disconnected(_)) => break,
+                Err(err) => return Err(peer_error("echo send failed", err)),
+            }
+        }
+
+        if signalled.load(Ordering::SeqCst) {
+            args.on_signal.close(peer);
+            break;
+        }
+
+        if args.exit_on_disconnect {
+            break;
         }
     }
 
+    let metrics = listener.as_ref().map(|l| l.metrics()).or(unbound_metrics);
+    if let Some(listener) = listener {
+        release_listener(listener);
+    }
+    if args.stats {
+        if let Some(metrics) = metrics {
+            print_listener_metrics(&metrics, format);
+        }
+    }
     Ok(SUCCESS)
 }
 
-fn install_ctrlc_handler(running: Arc<AtomicBool>) -> CliResult<()> {
-    ctrlc::set_handler(move || {
-        running.store(false, Ordering::SeqCst);
-    })
-    .map_err(|err| {
-        CliError::new(
-            crate::exit::INTERNAL,
-            format!("signal handler setup failed: {err}"),
-        )
-    })
-}
-
 fn classify_recv_error(err: ipcprims_peer::PeerError) -> RecvErrorDisposition {
     if matches!(err, ipcprims_peer::PeerError::Disconnected(_)) {
         return RecvErrorDisposition::Break;
@@ -108,9 +276,21 @@ fn classify_recv_error(err: ipcprims_peer::PeerError) -> RecvErrorDisposition {
     if let ipcprims_peer::PeerError::Schema(schema_err) = err {
         return RecvErrorDisposition::ContinueWithError(schema_error_payload(schema_err));
     }
+    if let ipcprims_peer::PeerError::Frame(FrameError::PayloadTooLarge { size, max }) = err {
+        return RecvErrorDisposition::CloseWithError(payload_too_large_payload(size, max));
+    }
     RecvErrorDisposition::Fatal(peer_error("receive failed", err))
 }
 
+fn payload_too_large_payload(size: usize, max: usize) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "error": "payload too large",
+        "size": size,
+        "max": max,
+    }))
+    .unwrap_or_else(|_| b"{\"error\":\"payload too large\"}".to_vec())
+}
+
 #[cfg(feature = "schema")]
 fn schema_error_payload(err: ipcprims_schema::SchemaError) -> Vec<u8> {
     serde_json::to_vec(&serde_json::json!({
@@ -130,6 +310,24 @@ mod tests {
         assert!(matches!(disposition, RecvErrorDisposition::Break));
     }
 
+    #[test]
+    fn oversized_frame_replies_then_closes() {
+        let disposition = classify_recv_error(PeerError::Frame(FrameError::PayloadTooLarge {
+            size: 2048,
+            max: 1024,
+        }));
+
+        let payload = match disposition {
+            RecvErrorDisposition::CloseWithError(payload) => payload,
+            _ => panic!("expected close disposition"),
+        };
+        let value: serde_json::Value =
+            serde_json::from_slice(&payload).expect("payload should be valid json");
+        assert_eq!(value["error"], "payload too large");
+        assert_eq!(value["size"], 2048);
+        assert_eq!(value["max"], 1024);
+    }
+
     #[cfg(feature = "schema")]
     #[test]
     fn schema_error_produces_error_payload_and_continues() {
@@ -153,10 +351,69 @@ mod tests {
         );
     }
 
+    fn echo_args(delay: Option<&str>, delay_channel: &[&str], drop_every: Option<u64>) -> EchoArgs {
+        EchoArgs {
+            path: None,
+            channels: None,
+            validate: None,
+            once: false,
+            exit_on_disconnect: false,
+            delay: delay.map(str::to_string),
+            delay_channel: delay_channel.iter().map(|s| s.to_string()).collect(),
+            drop_every,
+            require_token: None,
+            require_token_file: None,
+            max_payload: None,
+            socket_mode: None,
+            socket_group: None,
+            stats: false,
+            on_signal: crate::cmd::signal::OnSignal::Graceful,
+        }
+    }
+
+    #[test]
+    fn channel_delay_overrides_default_delay() {
+        let shaping = EchoShaping::from_args(&echo_args(Some("1s"), &["2=250ms"], None)).unwrap();
+        assert_eq!(shaping.delay_for(1), Some(Duration::from_secs(1)));
+        assert_eq!(shaping.delay_for(2), Some(Duration::from_millis(250)));
+
+        let none = EchoShaping::from_args(&echo_args(None, &[], None)).unwrap();
+        assert_eq!(none.delay_for(1), None);
+    }
+
+    #[test]
+    fn invalid_delay_channel_is_usage_error() {
+        let err = EchoShaping::from_args(&echo_args(None, &["2:1s"], None)).unwrap_err();
+        assert_eq!(err.code, USAGE);
+        let err = EchoShaping::from_args(&echo_args(None, &["x=1s"], None)).unwrap_err();
+        assert_eq!(err.code, USAGE);
+    }
+
+    #[test]
+    fn drop_every_counts_per_connection() {
+        let shaping = EchoShaping::from_args(&echo_args(None, &[], Some(3))).unwrap();
+        let mut first = ConnectionShaper::new(&shaping);
+        let drops: Vec<bool> = (0..6).map(|_| first.should_drop()).collect();
+        assert_eq!(drops, vec![false, false, true, false, false, true]);
+
+        let mut second = ConnectionShaper::new(&shaping);
+        assert!(!second.should_drop());
+    }
+
+    #[test]
+    fn delay_is_interrupted_when_not_running() {
+        let running = AtomicBool::new(false);
+        let start = Instant::now();
+        assert!(!sleep_while_running(Duration::from_secs(5), &running));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
     #[test]
     fn non_schema_error_is_fatal() {
-        let disposition =
-            classify_recv_error(PeerError::Timeout(std::time::Duration::from_secs(1)));
+        let disposition = classify_recv_error(PeerError::Timeout {
+            operation: ipcprims_peer::TimeoutOp::Recv { channel: None },
+            waited: std::time::Duration::from_secs(1),
+        });
         assert!(matches!(disposition, RecvErrorDisposition::Fatal(_)));
     }
 }
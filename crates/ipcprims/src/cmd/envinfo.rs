@@ -40,6 +40,10 @@ pub fn run(_args: EnvinfoArgs, format: OutputFormat) -> CliResult<i32> {
         "IPCPRIMS_LOG_LEVEL".to_string(),
         std::env::var("IPCPRIMS_LOG_LEVEL").ok(),
     );
+    env.insert(
+        "IPCPRIMS_CONFIG".to_string(),
+        std::env::var("IPCPRIMS_CONFIG").ok(),
+    );
     env.insert("RUST_LOG".to_string(), std::env::var("RUST_LOG").ok());
 
     let output = EnvInfoOutput {
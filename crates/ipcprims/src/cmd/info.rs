@@ -1,12 +1,12 @@
-use std::time::Duration;
-
-use ipcprims_peer::PeerError;
-use ipcprims_peer::{connect_with_config, HandshakeConfig};
+use ipcprims_frame::channel::channel_name;
+use ipcprims_peer::{is_version_compatible, HandshakeConfig, IpcAddr, PeerCredentials};
 use serde::Serialize;
 
-use crate::cmd::InfoArgs;
-use crate::exit::{peer_error, CliError, CliResult, SUCCESS, USAGE};
-use crate::output::{channel_name, OutputFormat};
+use crate::cmd::auth::resolve_token;
+use crate::cmd::connect::{connect_with_timeout, parse_duration};
+use crate::cmd::{require_path, timeout_arg, InfoArgs};
+use crate::exit::{CliError, CliResult, DATA_INVALID, SUCCESS, USAGE};
+use crate::output::OutputFormat;
 
 #[derive(Serialize)]
 struct ChannelInfo {
@@ -14,35 +14,55 @@ struct ChannelInfo {
     name: &'static str,
 }
 
-#[derive(Serialize)]
-struct PeerCreds {
-    uid: u32,
-    gid: u32,
-    pid: u32,
-}
-
 #[derive(Serialize)]
 struct InfoOutput {
     schema_id: &'static str,
     peer_id: String,
     protocol_version: String,
+    remote_library_version: Option<String>,
     channels: Vec<ChannelInfo>,
     ping_latency_ms: Option<f64>,
-    peer_credentials: Option<PeerCreds>,
+    peer_credentials: Option<PeerCredentials>,
+    remote_addr: Option<IpcAddr>,
     connected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expectations: Option<Expectations>,
+}
+
+#[derive(Serialize)]
+struct Expectations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_ok: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channels_ok: Option<bool>,
 }
 
 pub fn run(args: InfoArgs, format: OutputFormat) -> CliResult<i32> {
-    let timeout = parse_timeout(&args.timeout)?;
+    let path = require_path(args.path.as_deref())?;
+    let timeout = parse_duration(timeout_arg(args.connect_timeout.as_deref()))?;
+    if let Some(expected) = &args.expect_version {
+        validate_expected_version(expected)?;
+    }
     let handshake_config = HandshakeConfig {
         timeout,
+        auth_token: resolve_token(
+            args.auth_token.as_deref(),
+            args.auth_token_file.as_deref(),
+            "--auth-token",
+        )?,
+        require_server_uid: args.require_owner,
         ..HandshakeConfig::default()
     };
 
-    // Request built-in channels; server returns negotiated intersection.
-    let requested_channels = [1, 2, 3, 4];
+    // Request built-in channels (plus any expected ones); server returns negotiated intersection.
+    let mut requested_channels = vec![1, 2, 3, 4];
+    for channel in args.expect_channels.iter().flatten() {
+        if !requested_channels.contains(channel) {
+            requested_channels.push(*channel);
+        }
+    }
     let mut peer =
-        connect_with_timeout(&args.path, &requested_channels, &handshake_config, timeout)?;
+        connect_with_timeout(path, &requested_channels, &handshake_config, None, timeout)?;
 
     let channels: Vec<ChannelInfo> = peer
         .channels()
@@ -59,57 +79,93 @@ pub fn run(args: InfoArgs, format: OutputFormat) -> CliResult<i32> {
         .ok()
         .map(|d| (d.as_secs_f64() * 1000.0 * 100.0).round() / 100.0);
 
-    let peer_credentials =
-        peer.peer_credentials()
-            .map(|(uid, gid, pid)| PeerCreds { uid, gid, pid });
+    let peer_credentials = peer.peer_credentials();
+
+    let protocol_version = peer.handshake_result().protocol_version.clone();
+    let expectations = check_expectations(
+        args.expect_version.as_deref(),
+        args.expect_channels.as_deref(),
+        &protocol_version,
+        peer.channels(),
+    );
 
     let out = InfoOutput {
         schema_id: "https://schemas.3leaps.dev/ipcprims/cli/v1/connection-info.schema.json",
         peer_id: peer.id().to_string(),
-        protocol_version: peer.handshake_result().protocol_version.clone(),
+        protocol_version,
+        remote_library_version: peer.handshake_result().remote_library_version.clone(),
         channels,
         ping_latency_ms,
         peer_credentials,
+        remote_addr: peer.remote_addr(),
         connected: true,
+        expectations,
     };
 
     print_info(&out, format);
-    Ok(SUCCESS)
+    expectation_result(&out, &args)
 }
 
-fn connect_with_timeout(
-    path: &std::path::Path,
-    channels: &[u16],
-    handshake_config: &HandshakeConfig,
-    timeout: Duration,
-) -> CliResult<ipcprims_peer::Peer> {
-    let start = std::time::Instant::now();
-    loop {
-        match connect_with_config(path, channels, handshake_config, None, None) {
-            Ok(peer) => return Ok(peer),
-            Err(err) => {
-                if !is_retryable_connect_error(&err) {
-                    return Err(peer_error("connect failed", err));
-                }
-                if start.elapsed() >= timeout {
-                    return Err(CliError::new(
-                        crate::exit::TIMEOUT,
-                        format!("connect timed out after {timeout:?}"),
-                    ));
-                }
-                std::thread::sleep(Duration::from_millis(50));
-            }
-        }
+fn validate_expected_version(version: &str) -> CliResult<()> {
+    let valid = version
+        .split_once('.')
+        .is_some_and(|(major, minor)| major.parse::<u16>().is_ok() && minor.parse::<u16>().is_ok());
+    if valid {
+        Ok(())
+    } else {
+        Err(CliError::new(
+            USAGE,
+            format!("invalid --expect-version '{version}': expected <major>.<minor>"),
+        ))
     }
 }
 
-fn is_retryable_connect_error(err: &PeerError) -> bool {
-    match err {
-        PeerError::Transport(ipcprims_transport::TransportError::Connect { source, .. }) => {
-            source.kind() == std::io::ErrorKind::NotFound
-                || source.kind() == std::io::ErrorKind::ConnectionRefused
-        }
-        _ => false,
+fn check_expectations(
+    expect_version: Option<&str>,
+    expect_channels: Option<&[u16]>,
+    protocol_version: &str,
+    negotiated: &[u16],
+) -> Option<Expectations> {
+    if expect_version.is_none() && expect_channels.is_none() {
+        return None;
+    }
+
+    Some(Expectations {
+        version_ok: expect_version
+            .map(|expected| is_version_compatible(expected, protocol_version).unwrap_or(false)),
+        channels_ok: expect_channels
+            .map(|expected| expected.iter().all(|channel| negotiated.contains(channel))),
+    })
+}
+
+fn expectation_result(out: &InfoOutput, args: &InfoArgs) -> CliResult<i32> {
+    let Some(expectations) = &out.expectations else {
+        return Ok(SUCCESS);
+    };
+
+    let mut failures = Vec::new();
+    if expectations.version_ok == Some(false) {
+        failures.push(format!(
+            "protocol version {} is not compatible with expected {}",
+            out.protocol_version,
+            args.expect_version.as_deref().unwrap_or_default()
+        ));
+    }
+    if expectations.channels_ok == Some(false) {
+        let negotiated: Vec<u16> = out.channels.iter().map(|c| c.id).collect();
+        failures.push(format!(
+            "negotiated channels {negotiated:?} do not include expected {:?}",
+            args.expect_channels.as_deref().unwrap_or_default()
+        ));
+    }
+
+    if failures.is_empty() {
+        Ok(SUCCESS)
+    } else {
+        Err(CliError::new(
+            DATA_INVALID,
+            format!("expectation not met: {}", failures.join("; ")),
+        ))
     }
 }
 
@@ -125,6 +181,10 @@ fn print_info(out: &InfoOutput, format: OutputFormat) {
             println!("Connection Info:");
             println!("  Peer ID:          {}", out.peer_id);
             println!("  Protocol:         ipcprims {}", out.protocol_version);
+            println!(
+                "  Remote library:   {}",
+                out.remote_library_version.as_deref().unwrap_or("unknown")
+            );
             let chans = out
                 .channels
                 .iter()
@@ -137,12 +197,21 @@ fn print_info(out: &InfoOutput, format: OutputFormat) {
                 None => println!("  Ping:             unavailable"),
             }
             match &out.peer_credentials {
-                Some(c) => println!(
-                    "  Peer credentials: uid={} gid={} pid={}",
-                    c.uid, c.gid, c.pid
-                ),
+                Some(creds) => println!("  Peer credentials: {creds}"),
                 None => println!("  Peer credentials: unavailable"),
             }
+            match &out.remote_addr {
+                Some(addr) => println!("  Remote address:   {addr}"),
+                None => println!("  Remote address:   unavailable"),
+            }
+            if let Some(e) = &out.expectations {
+                if let Some(ok) = e.version_ok {
+                    println!("  Version check:    {}", if ok { "ok" } else { "FAILED" });
+                }
+                if let Some(ok) = e.channels_ok {
+                    println!("  Channel check:    {}", if ok { "ok" } else { "FAILED" });
+                }
+            }
         }
         OutputFormat::Raw => {
             println!("{}", out.peer_id);
@@ -150,56 +219,39 @@ fn print_info(out: &InfoOutput, format: OutputFormat) {
     }
 }
 
-fn parse_timeout(input: &str) -> CliResult<Duration> {
-    let input = input.trim();
-    if input.is_empty() {
-        return Err(CliError::new(USAGE, "timeout must not be empty"));
-    }
-
-    let (number, unit) = if let Some(num) = input.strip_suffix("ms") {
-        (num, "ms")
-    } else if let Some(num) = input.strip_suffix('s') {
-        (num, "s")
-    } else {
-        (input, "s")
-    };
-
-    let value: u64 = number
-        .parse()
-        .map_err(|_| CliError::new(USAGE, format!("invalid timeout value: {input}")))?;
-
-    if value == 0 {
-        return Err(CliError::new(USAGE, "timeout must be greater than zero"));
-    }
-
-    match unit {
-        "ms" => Ok(Duration::from_millis(value)),
-        "s" => Ok(Duration::from_secs(value)),
-        _ => Err(CliError::new(
-            USAGE,
-            format!("unsupported timeout unit: {unit}"),
-        )),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn parse_timeout_seconds() {
-        assert_eq!(parse_timeout("5s").unwrap(), Duration::from_secs(5));
-        assert_eq!(parse_timeout("2").unwrap(), Duration::from_secs(2));
+    fn expected_version_must_be_major_minor() {
+        assert!(validate_expected_version("1.0").is_ok());
+        assert!(validate_expected_version("1").is_err());
+        assert!(validate_expected_version("1.x").is_err());
+        assert!(validate_expected_version("1.0.0").is_err());
+    }
+
+    #[test]
+    fn expectations_absent_without_flags() {
+        assert!(check_expectations(None, None, "1.0", &[1]).is_none());
     }
 
     #[test]
-    fn parse_timeout_millis() {
-        assert_eq!(parse_timeout("150ms").unwrap(), Duration::from_millis(150));
+    fn expectations_use_handshake_version_rule() {
+        let ok = check_expectations(Some("1.0"), None, "1.0", &[1]).unwrap();
+        assert_eq!(ok.version_ok, Some(true));
+        assert!(ok.channels_ok.is_none());
+
+        let bad = check_expectations(Some("2.0"), None, "1.0", &[1]).unwrap();
+        assert_eq!(bad.version_ok, Some(false));
     }
 
     #[test]
-    fn parse_timeout_invalid() {
-        assert!(parse_timeout("0s").is_err());
-        assert!(parse_timeout("bad").is_err());
+    fn expectations_check_negotiated_channels() {
+        let ok = check_expectations(None, Some(&[1, 2]), "1.0", &[1, 2, 3]).unwrap();
+        assert_eq!(ok.channels_ok, Some(true));
+
+        let bad = check_expectations(None, Some(&[1, 300]), "1.0", &[1, 2]).unwrap();
+        assert_eq!(bad.channels_ok, Some(false));
     }
 }
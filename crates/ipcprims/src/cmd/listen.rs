@@ -1,63 +1,450 @@
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use ipcprims_peer::PeerListener;
+use ipcprims_frame::{Frame, FrameError};
+use ipcprims_peer::{IpcAddr, Peer, PeerConfig, PeerCredentials, PeerError, PeerListener};
 
-use crate::cmd::ListenArgs;
-use crate::exit::{peer_error, CliError, CliResult, SUCCESS};
-use crate::output::{print_frame, OutputFormat};
+use crate::cmd::auth::{is_client_rejection, require_token, resolve_token};
+use crate::cmd::signal::{
+    drain_bounded, install_handler, release_listener, OnSignal, DRAIN_TIMEOUT, SIGNAL_POLL_INTERVAL,
+};
+use crate::cmd::{bind_listener, require_path, ListenArgs};
+use crate::exit::{frame_error_code, peer_error, CliError, CliResult, DATA_INVALID, SUCCESS};
+use crate::output::{print_frame, print_listener_metrics, FramePrintOptions, OutputFormat};
+use crate::units::parse_size;
+
+/// How often the main thread re-checks the signal flag while peers are serviced.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Outcome reported back to the main thread by the accept loop or a peer thread.
+enum ListenEvent {
+    CountReached,
+    /// A peer's connection ended, either cleanly or on a frame decode error.
+    PeerClosed {
+        decode_error: bool,
+    },
+    Fatal(CliError),
+}
+
+/// Output state shared by all peer threads.
+///
+/// The mutex doubles as the print lock: checking the limit, printing a frame
+/// and bumping the counter happen under one guard so `--count` is exact and
+/// each frame is written as a whole line.
+struct FrameSink {
+    printed: Mutex<usize>,
+    count: Option<usize>,
+    channels: Option<Vec<u16>>,
+    format: OutputFormat,
+    options: FramePrintOptions,
+}
+
+impl FrameSink {
+    /// Print a frame if it passes the filter. Returns `false` once the limit is hit.
+    fn emit(
+        &self,
+        frame: &Frame,
+        peer_id: &str,
+        credentials: Option<PeerCredentials>,
+        remote_addr: Option<&IpcAddr>,
+    ) -> bool {
+        if let Some(channels) = &self.channels {
+            if !channels.contains(&frame.channel) {
+                return true;
+            }
+        }
+
+        let mut printed = self.printed.lock().unwrap_or_else(|e| e.into_inner());
+        if self.count.is_some_and(|count| *printed >= count) {
+            return false;
+        }
+        print_frame(
+            frame,
+            peer_id,
+            credentials,
+            remote_addr,
+            self.format,
+            self.options,
+        );
+        *printed = printed.saturating_add(1);
+        self.count.is_none_or(|count| *printed < count)
+    }
+}
+
+/// `--allow-uid` filtering applied right after accept.
+struct CredentialPolicy {
+    allow_uid: Vec<u32>,
+    strict: bool,
+    warned_unavailable: bool,
+}
+
+impl CredentialPolicy {
+    /// Decide whether to keep a connection. `Err` carries the logged reason.
+    fn check(&mut self, credentials: Option<PeerCredentials>) -> Result<(), String> {
+        if self.allow_uid.is_empty() {
+            return Ok(());
+        }
+        match credentials {
+            Some(creds) if self.allow_uid.contains(&creds.uid) => Ok(()),
+            Some(creds) => Err(format!("uid {} not in --allow-uid", creds.uid)),
+            None => {
+                if !self.warned_unavailable {
+                    self.warned_unavailable = true;
+                    tracing::warn!(
+                        strict = self.strict,
+                        "peer credentials unavailable on this platform; --allow-uid cannot be enforced"
+                    );
+                }
+                if self.strict {
+                    Err("peer credentials unavailable (--strict-creds)".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
 
 pub fn run(args: ListenArgs, format: OutputFormat) -> CliResult<i32> {
-    let listener = PeerListener::bind(&args.path).map_err(|err| peer_error("bind failed", err))?;
+    let path = require_path(args.path.as_deref())?;
+    let required_token = resolve_token(
+        args.require_token.as_deref(),
+        args.require_token_file.as_deref(),
+        "--require-token",
+    )?;
+    let max_payload = args.max_payload.as_deref().map(parse_size).transpose()?;
+    let mut listener = bind_listener(path, args.socket_mode, args.socket_group.as_deref())?;
+    listener = require_token(listener, required_token);
+    let mut peer_config = PeerConfig::default();
+    if let Some(max_payload_size) = max_payload {
+        peer_config.max_payload_size = max_payload_size;
+    }
+    listener = listener.with_peer_config(drain_bounded(peer_config));
 
     let running = Arc::new(AtomicBool::new(true));
-    install_ctrlc_handler(running.clone())?;
+    let listener = Arc::new(listener);
+    let signalled = install_handler(running.clone(), Arc::downgrade(&listener))?;
 
-    let mut printed = 0usize;
+    let sink = Arc::new(FrameSink {
+        printed: Mutex::new(0),
+        count: args.count,
+        channels: args.channels,
+        format,
+        options: FramePrintOptions {
+            force_base64: args.force_base64,
+        },
+    });
 
-    while running.load(Ordering::SeqCst) {
-        let mut peer = match listener.accept() {
-            Ok(peer) => peer,
-            Err(err) => return Err(peer_error("accept failed", err)),
-        };
+    let policy = CredentialPolicy {
+        allow_uid: args.allow_uid,
+        strict: args.strict_creds,
+        warned_unavailable: false,
+    };
+
+    let exit_on_disconnect = args.exit_on_disconnect;
+    let stop = PeerStop {
+        running: running.clone(),
+        signalled: signalled.clone(),
+        on_signal: args.on_signal,
+    };
+    let (events, outcome) = mpsc::channel();
+    let acceptor = {
+        let listener = listener.clone();
+        thread::spawn(move || accept_loop(&listener, policy, sink, stop, events))
+    };
 
-        while running.load(Ordering::SeqCst) {
-            let frame = match peer.recv() {
-                Ok(frame) => frame,
-                Err(err) => match err {
-                    ipcprims_peer::PeerError::Disconnected(_) => break,
-                    _ => return Err(peer_error("receive failed", err)),
-                },
-            };
-
-            if let Some(channels) = &args.channels {
-                if !channels.contains(&frame.channel) {
-                    continue;
+    let mut decode_failures = 0usize;
+    let result = loop {
+        match outcome.recv_timeout(POLL_INTERVAL) {
+            Ok(ListenEvent::CountReached) => break Ok(SUCCESS),
+            Ok(ListenEvent::PeerClosed { decode_error }) => {
+                if decode_error {
+                    decode_failures += 1;
+                }
+                if exit_on_disconnect {
+                    break Ok(SUCCESS);
                 }
             }
+            Ok(ListenEvent::Fatal(err)) => break Err(err),
+            Err(RecvTimeoutError::Timeout) if running.load(Ordering::SeqCst) => {}
+            Err(_) => break Ok(SUCCESS),
+        }
+    };
 
-            print_frame(&frame, peer.id(), format);
-            printed = printed.saturating_add(1);
+    // Unblock `accept` so the listener is dropped and its socket cleaned up.
+    running.store(false, Ordering::SeqCst);
+    listener.shutdown();
+    let metrics = listener.metrics();
+    let _ = acceptor.join();
+    release_listener(listener);
+    // After a signal, give peer threads time to shut their clients down.
+    // Otherwise peer threads still reading are left to die with the process.
+    if signalled.load(Ordering::SeqCst) {
+        drain_peers(&outcome);
+    }
+    if args.stats {
+        print_listener_metrics(&metrics, format);
+    }
 
-            if let Some(count) = args.count {
-                if printed >= count {
-                    return Ok(SUCCESS);
-                }
+    match result {
+        Ok(_) if decode_failures > 0 => Err(CliError::new(
+            DATA_INVALID,
+            format!("{decode_failures} connection(s) ended with a frame decode error"),
+        )),
+        other => other,
+    }
+}
+
+/// Wait for every peer thread to finish, up to the drain timeout.
+///
+/// Each peer thread holds an event sender, so the channel disconnects once
+/// the last one exits.
+fn drain_peers(outcome: &Receiver<ListenEvent>) {
+    let deadline = Instant::now() + DRAIN_TIMEOUT + SIGNAL_POLL_INTERVAL;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match outcome.recv_timeout(remaining) {
+            Ok(_) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                tracing::warn!("peers still connected after drain timeout");
+                break;
+            }
+        }
+    }
+}
+
+/// When peer threads stop, and what they do with their client on a signal.
+#[derive(Clone)]
+struct PeerStop {
+    running: Arc<AtomicBool>,
+    signalled: Arc<AtomicBool>,
+    on_signal: OnSignal,
+}
+
+fn accept_loop(
+    listener: &PeerListener,
+    mut policy: CredentialPolicy,
+    sink: Arc<FrameSink>,
+    stop: PeerStop,
+    events: Sender<ListenEvent>,
+) {
+    while stop.running.load(Ordering::SeqCst) {
+        let peer = match listener.accept_timeout(SIGNAL_POLL_INTERVAL) {
+            Ok(Some(peer)) => peer,
+            Ok(None) => continue,
+            Err(_) if !stop.running.load(Ordering::SeqCst) => break,
+            Err(err) if is_decode_error(&err) => {
+                eprintln!("client handshake: frame decode failed: {err}");
+                let _ = events.send(ListenEvent::PeerClosed { decode_error: true });
+                continue;
             }
+            Err(err)
+                if is_client_rejection(&err)
+                    || matches!(err, PeerError::Disconnected(_) | PeerError::Timeout { .. }) =>
+            {
+                tracing::warn!(error = %err, "client handshake failed");
+                continue;
+            }
+            Err(err) => {
+                let _ = events.send(ListenEvent::Fatal(peer_error("accept failed", err)));
+                break;
+            }
+        };
+
+        let credentials = peer.peer_credentials();
+        if let Err(reason) = policy.check(credentials) {
+            tracing::warn!(
+                peer_id = peer.id(),
+                uid = credentials.map(|c| c.uid),
+                pid = credentials.map(|c| c.pid),
+                reason = %reason,
+                "dropping connection"
+            );
+            continue;
+        }
+
+        let sink = sink.clone();
+        let stop = stop.clone();
+        let events = events.clone();
+        thread::spawn(move || service_peer(peer, credentials, &sink, &stop, &events));
+    }
+}
+
+fn service_peer(
+    mut peer: Peer,
+    credentials: Option<PeerCredentials>,
+    sink: &FrameSink,
+    stop: &PeerStop,
+    events: &Sender<ListenEvent>,
+) {
+    tracing::info!(
+        peer_id = peer.id(),
+        uid = credentials.map(|c| c.uid),
+        gid = credentials.map(|c| c.gid),
+        pid = credentials.map(|c| c.pid),
+        "peer connected"
+    );
+    let remote_addr = peer.remote_addr();
+    if let Err(err) = peer.set_recv_timeout(SIGNAL_POLL_INTERVAL) {
+        tracing::warn!(peer_id = peer.id(), error = %err, "failed setting receive timeout");
+    }
+    let mut decode_error = false;
+    while stop.running.load(Ordering::SeqCst) {
+        let frame = match peer.recv() {
+            Ok(frame) => frame,
+            Err(PeerError::Disconnected(_)) => break,
+            // Idle read timeout; keep waiting (and re-check the signal flag).
+            Err(PeerError::Timeout { .. }) => continue,
+            // The frame after the gap is returned by the next recv.
+            Err(err @ PeerError::Frame(FrameError::SequenceGap { .. })) => {
+                eprintln!("{}: {err}", peer.id());
+                continue;
+            }
+            Err(err) if is_decode_error(&err) => {
+                eprintln!("{}: frame decode failed: {err}", peer.id());
+                decode_error = true;
+                break;
+            }
+            Err(err) => {
+                // One misbehaving client should not take down the others.
+                tracing::warn!(peer_id = peer.id(), error = %err, "receive failed; dropping peer");
+                break;
+            }
+        };
+
+        if !sink.emit(&frame, peer.id(), credentials, remote_addr.as_ref()) {
+            let _ = events.send(ListenEvent::CountReached);
+            return;
         }
     }
+    tracing::debug!(peer_id = peer.id(), "peer disconnected");
+    if stop.signalled.load(Ordering::SeqCst) {
+        stop.on_signal.close(peer);
+    }
+    let _ = events.send(ListenEvent::PeerClosed { decode_error });
+}
 
-    Ok(SUCCESS)
+/// Errors meaning the peer sent bytes that are not a valid frame stream,
+/// as classified for the exit code.
+fn is_decode_error(err: &PeerError) -> bool {
+    matches!(err, PeerError::Frame(err) if frame_error_code(err) == DATA_INVALID)
 }
 
-fn install_ctrlc_handler(running: Arc<AtomicBool>) -> CliResult<()> {
-    ctrlc::set_handler(move || {
-        running.store(false, Ordering::SeqCst);
-    })
-    .map_err(|err| {
-        CliError::new(
-            crate::exit::INTERNAL,
-            format!("signal handler setup failed: {err}"),
-        )
-    })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sink(count: Option<usize>, channels: Option<Vec<u16>>) -> FrameSink {
+        FrameSink {
+            printed: Mutex::new(0),
+            count,
+            channels,
+            format: OutputFormat::Raw,
+            options: FramePrintOptions::default(),
+        }
+    }
+
+    #[test]
+    fn count_limit_is_shared_across_threads() {
+        let sink = Arc::new(sink(Some(4), None));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let sink = sink.clone();
+                thread::spawn(move || {
+                    let frame = Frame::new(1, Vec::new());
+                    sink.emit(&frame, &format!("peer-{i}"), None, None)
+                })
+            })
+            .collect();
+        let continuing = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|keep_going| *keep_going)
+            .count();
+
+        assert_eq!(*sink.printed.lock().unwrap(), 4);
+        assert_eq!(
+            continuing, 3,
+            "only the first three emits stay under the limit"
+        );
+    }
+
+    #[test]
+    fn decode_errors_are_distinguished_from_disconnects() {
+        assert!(is_decode_error(&PeerError::Frame(FrameError::InvalidMagic)));
+        assert!(is_decode_error(&PeerError::Frame(FrameError::Truncated {
+            buffered: 3
+        })));
+        assert!(is_decode_error(&PeerError::Frame(
+            FrameError::UnsupportedVersion(3)
+        )));
+        assert!(is_decode_error(&PeerError::Frame(
+            FrameError::ChecksumMismatch {
+                expected: 1,
+                actual: 2
+            }
+        )));
+        assert!(is_decode_error(&PeerError::Frame(FrameError::Compression(
+            "bad data".to_string()
+        ))));
+        assert!(!is_decode_error(&PeerError::Disconnected(
+            "connection closed".to_string()
+        )));
+        assert!(!is_decode_error(&PeerError::Frame(
+            FrameError::ConnectionClosed
+        )));
+    }
+
+    #[test]
+    fn filtered_frames_do_not_count() {
+        let sink = sink(Some(1), Some(vec![2]));
+        assert!(sink.emit(&Frame::new(1, Vec::new()), "peer-0", None, None));
+        assert_eq!(*sink.printed.lock().unwrap(), 0);
+        assert!(!sink.emit(&Frame::new(2, Vec::new()), "peer-0", None, None));
+    }
+
+    fn policy(allow_uid: &[u32], strict: bool) -> CredentialPolicy {
+        CredentialPolicy {
+            allow_uid: allow_uid.to_vec(),
+            strict,
+            warned_unavailable: false,
+        }
+    }
+
+    fn creds(uid: u32) -> Option<PeerCredentials> {
+        Some(PeerCredentials {
+            uid,
+            gid: uid,
+            pid: Some(42),
+        })
+    }
+
+    #[test]
+    fn empty_allow_list_accepts_everyone() {
+        let mut policy = policy(&[], true);
+        assert!(policy.check(creds(0)).is_ok());
+        assert!(policy.check(None).is_ok());
+    }
+
+    #[test]
+    fn allow_list_filters_by_uid() {
+        let mut policy = policy(&[1000, 1001], false);
+        assert!(policy.check(creds(1001)).is_ok());
+        let reason = policy.check(creds(0)).expect_err("uid 0 is not allowed");
+        assert!(reason.contains("uid 0"));
+    }
+
+    #[test]
+    fn unavailable_credentials_follow_strict_flag() {
+        let mut open = policy(&[1000], false);
+        assert!(open.check(None).is_ok());
+        assert!(open.warned_unavailable);
+
+        let mut closed = policy(&[1000], true);
+        assert!(closed.check(None).is_err());
+    }
 }
@@ -1,15 +1,26 @@
 use clap::{Args, Subcommand};
-use std::path::PathBuf;
+use ipcprims_frame::ChannelMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
-use crate::exit::CliResult;
+use ipcprims_peer::PeerListener;
+
+use crate::config::{ConfigLayers, DEFAULT_TIMEOUT};
+use crate::exit::{peer_error, CliError, CliResult, USAGE};
 use crate::output::OutputFormat;
+use signal::OnSignal;
 
+pub mod auth;
+pub mod channels;
+pub mod connect;
 pub mod doctor;
 pub mod echo;
 pub mod envinfo;
 pub mod info;
 pub mod listen;
+pub mod selftest;
 pub mod send;
+pub mod signal;
 pub mod version;
 
 #[derive(Subcommand, Debug)]
@@ -28,6 +39,107 @@ pub enum Command {
     Doctor(DoctorArgs),
     /// Print build and environment diagnostics.
     Envinfo(EnvinfoArgs),
+    /// List well-known channels and schema-defined user channels.
+    Channels(ChannelsArgs),
+    /// Verify ipcprims works end to end on this machine.
+    Selftest(SelftestArgs),
+}
+
+impl Command {
+    /// Fill unset arguments from the environment/config file layers.
+    pub fn apply_config(&mut self, layers: &ConfigLayers) {
+        match self {
+            Command::Echo(args) => {
+                args.path = layers.socket_path(args.path.take());
+                args.validate = layers.schema_dir(args.validate.take());
+            }
+            Command::Send(args) => {
+                args.path = layers.socket_path(args.path.take());
+                args.connect_timeout = Some(layers.timeout(args.connect_timeout.take()));
+                args.wait_timeout = Some(layers.timeout(args.wait_timeout.take()));
+                args.validate_dir = layers.schema_dir(args.validate_dir.take());
+            }
+            Command::Listen(args) => {
+                args.path = layers.socket_path(args.path.take());
+            }
+            Command::Info(args) => {
+                args.path = layers.socket_path(args.path.take());
+                args.connect_timeout = Some(layers.timeout(args.connect_timeout.take()));
+            }
+            Command::Channels(args) => {
+                args.schema_dir = layers.schema_dir(args.schema_dir.take());
+            }
+            Command::Version(args) => {
+                if args.check_peer.is_some() {
+                    args.connect_timeout = Some(layers.timeout(args.connect_timeout.take()));
+                }
+            }
+            Command::Doctor(_) | Command::Envinfo(_) | Command::Selftest(_) => {}
+        }
+    }
+}
+
+/// Resolve the socket path argument, which may come from the config file.
+pub(crate) fn require_path(path: Option<&Path>) -> CliResult<&Path> {
+    path.ok_or_else(|| {
+        CliError::new(
+            USAGE,
+            "socket path required (pass PATH, set IPCPRIMS_SOCKET, or set socket_path in config)",
+        )
+    })
+}
+
+/// Bind the `echo`/`listen` listener, applying `--socket-mode` and
+/// `--socket-group` to a filesystem socket path.
+pub(crate) fn bind_listener(
+    path: &Path,
+    mode: Option<u32>,
+    group: Option<&str>,
+) -> CliResult<PeerListener> {
+    if mode.is_none() && group.is_none() {
+        return PeerListener::bind(path).map_err(|err| peer_error("bind failed", err));
+    }
+    #[cfg(unix)]
+    {
+        let mut options = ipcprims_peer::BindOptions::new();
+        if let Some(mode) = mode {
+            options = options.mode(mode);
+        }
+        if let Some(group) = group {
+            options = options.owner_group(group);
+        }
+        PeerListener::bind_with(path, options).map_err(|err| peer_error("bind failed", err))
+    }
+    #[cfg(not(unix))]
+    Err(CliError::new(
+        USAGE,
+        "--socket-mode and --socket-group only apply to Unix socket paths",
+    ))
+}
+
+/// Parse an octal permission mode such as `660`, `0660` or `0o660`.
+fn parse_socket_mode(value: &str) -> Result<u32, String> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| format!("'{value}' is not an octal permission mode (e.g. 660)"))
+}
+
+/// Resolved timeout argument, falling back to the built-in default.
+pub(crate) fn timeout_arg(timeout: Option<&str>) -> &str {
+    timeout.unwrap_or(DEFAULT_TIMEOUT)
+}
+
+/// Parse a channel argument: a numeric ID or a built-in channel name in any
+/// case (`command`, `DATA`, ...).
+fn parse_channel(value: &str) -> Result<u16, String> {
+    if let Ok(id) = value.parse::<u16>() {
+        return Ok(id);
+    }
+    ChannelMap::new()
+        .get(value)
+        .ok_or_else(|| format!("'{value}' is not a channel ID (0-65535) or built-in channel name"))
 }
 
 pub fn run(command: Command, format: OutputFormat) -> CliResult<i32> {
@@ -35,31 +147,69 @@ pub fn run(command: Command, format: OutputFormat) -> CliResult<i32> {
         Command::Echo(args) => echo::run(args, format),
         Command::Send(args) => send::run(args, format),
         Command::Listen(args) => listen::run(args, format),
-        Command::Version(args) => version::run(args),
+        Command::Version(args) => version::run(args, format),
         Command::Info(args) => info::run(args, format),
         Command::Doctor(args) => doctor::run(args, format),
         Command::Envinfo(args) => envinfo::run(args, format),
+        Command::Channels(args) => channels::run(args, format),
+        Command::Selftest(args) => selftest::run(args, format),
     }
 }
 
 #[derive(Args, Debug)]
 pub struct EchoArgs {
-    /// Socket path to bind.
-    pub path: PathBuf,
-    /// Channels to echo (comma-separated). Default: all negotiated channels.
-    #[arg(long, value_delimiter = ',')]
+    /// Socket path to bind (`@name` for a Linux abstract socket, `tcp://127.0.0.1:PORT` for loopback TCP, `vsock://CID:PORT` for vsock on Linux).
+    pub path: Option<PathBuf>,
+    /// Channels to echo (comma-separated IDs or built-in names). Default: all negotiated channels.
+    #[arg(long, value_delimiter = ',', value_parser = parse_channel)]
     pub channels: Option<Vec<u16>>,
     /// Schema directory for payload validation.
     #[arg(long, value_name = "DIR")]
     pub validate: Option<PathBuf>,
+    /// Serve a single client: stop listening after the first accept and exit on disconnect.
+    #[arg(long, conflicts_with = "exit_on_disconnect")]
+    pub once: bool,
+    /// Exit when a connected client disconnects instead of accepting the next one.
+    #[arg(long)]
+    pub exit_on_disconnect: bool,
+    /// Delay applied before every echo reply (e.g. 1s, 250ms).
+    #[arg(long, value_name = "DURATION")]
+    pub delay: Option<String>,
+    /// Per-channel delay override, repeatable (e.g. --delay-channel 2=500ms).
+    #[arg(long, value_name = "CHAN=DURATION")]
+    pub delay_channel: Vec<String>,
+    /// Swallow every Nth echoable frame without replying.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub drop_every: Option<u64>,
+    /// Reject clients that do not present this auth token.
+    #[arg(long, value_name = "TOKEN", conflicts_with = "require_token_file")]
+    pub require_token: Option<String>,
+    /// Read the required auth token from a file (keeps it out of process listings).
+    #[arg(long, value_name = "PATH")]
+    pub require_token_file: Option<PathBuf>,
+    /// Largest frame payload accepted from clients (e.g. 64k, 1m). Default: 16m.
+    #[arg(long, value_name = "SIZE")]
+    pub max_payload: Option<String>,
+    /// Permission bits for the socket path, in octal (e.g. 660). Default: 600.
+    #[arg(long, value_name = "MODE", value_parser = parse_socket_mode)]
+    pub socket_mode: Option<u32>,
+    /// Group to give the socket path, by name or gid (pair with --socket-mode 660).
+    #[arg(long, value_name = "GROUP")]
+    pub socket_group: Option<String>,
+    /// Print listener accept and handshake-failure counts to stderr on exit.
+    #[arg(long)]
+    pub stats: bool,
+    /// On SIGINT/SIGTERM/SIGHUP, ask clients to shut down (bounded wait) or just close them.
+    #[arg(long, value_name = "MODE", default_value = "graceful")]
+    pub on_signal: OnSignal,
 }
 
 #[derive(Args, Debug)]
 pub struct SendArgs {
-    /// Socket path to connect to.
-    pub path: PathBuf,
-    /// Channel to send on.
-    #[arg(long, short = 'c', default_value = "1")]
+    /// Socket path to connect to (`@name` for a Linux abstract socket, `tcp://127.0.0.1:PORT` for loopback TCP, `vsock://CID:PORT` for vsock on Linux).
+    pub path: Option<PathBuf>,
+    /// Channel to send on (ID or built-in name).
+    #[arg(long, short = 'c', default_value = "1", value_parser = parse_channel)]
     pub channel: u16,
     /// JSON payload.
     #[arg(long, conflicts_with_all = ["data", "file"])]
@@ -70,44 +220,140 @@ pub struct SendArgs {
     /// Read payload from file.
     #[arg(long, conflicts_with_all = ["json", "data"])]
     pub file: Option<PathBuf>,
+    /// Connect and handshake timeout (e.g. 5s, 500ms). Default: 5s.
+    #[arg(long, value_name = "DURATION")]
+    pub connect_timeout: Option<String>,
     /// Wait for one response frame and print it.
     #[arg(long)]
     pub wait: bool,
-    /// Maximum time to wait for response when --wait is set (e.g. 5s, 500ms).
-    #[arg(long, default_value = "5s")]
-    pub wait_timeout: String,
+    /// Maximum time to wait for response when --wait is set (e.g. 5s, 500ms). Default: 5s.
+    #[arg(long)]
+    pub wait_timeout: Option<String>,
+    /// Wait for N response frames (implies --wait), printing each as it arrives.
+    #[arg(long, value_name = "N")]
+    pub expect_responses: Option<NonZeroUsize>,
+    /// Base64-encode response payloads in JSON output even when they are valid UTF-8.
+    #[arg(long)]
+    pub force_base64: bool,
+    /// Validate the payload against this schema directory before connecting.
+    #[arg(long, value_name = "DIR")]
+    pub validate_dir: Option<PathBuf>,
+    /// With --validate-dir (or schema_dir from config), refuse to send on channels that have no schema.
+    #[arg(long)]
+    pub strict_schemas: bool,
+    /// Auth token to present during the handshake.
+    #[arg(long, value_name = "TOKEN", conflicts_with = "auth_token_file")]
+    pub auth_token: Option<String>,
+    /// Read the auth token from a file (keeps it out of process listings).
+    #[arg(long, value_name = "PATH")]
+    pub auth_token_file: Option<PathBuf>,
+    /// Refuse a server that does not run as this uid, or whose socket (or its directory) another user owns.
+    #[arg(long, value_name = "UID")]
+    pub require_owner: Option<u32>,
 }
 
 #[derive(Args, Debug)]
 pub struct ListenArgs {
-    /// Socket path to bind.
-    pub path: PathBuf,
-    /// Filter to specific channels (comma-separated).
-    #[arg(long, value_delimiter = ',')]
+    /// Socket path to bind (`@name` for a Linux abstract socket, `tcp://127.0.0.1:PORT` for loopback TCP, `vsock://CID:PORT` for vsock on Linux).
+    pub path: Option<PathBuf>,
+    /// Filter to specific channels (comma-separated IDs or built-in names).
+    #[arg(long, value_delimiter = ',', value_parser = parse_channel)]
     pub channels: Option<Vec<u16>>,
     /// Exit after receiving N frames.
     #[arg(long)]
     pub count: Option<usize>,
+    /// Base64-encode payloads in JSON output even when they are valid UTF-8.
+    #[arg(long)]
+    pub force_base64: bool,
+    /// Only accept connections from this uid (repeatable).
+    #[arg(long, value_name = "UID")]
+    pub allow_uid: Vec<u32>,
+    /// With --allow-uid, drop connections whose credentials are unavailable.
+    #[arg(long, requires = "allow_uid")]
+    pub strict_creds: bool,
+    /// Reject clients that do not present this auth token.
+    #[arg(long, value_name = "TOKEN", conflicts_with = "require_token_file")]
+    pub require_token: Option<String>,
+    /// Read the required auth token from a file (keeps it out of process listings).
+    #[arg(long, value_name = "PATH")]
+    pub require_token_file: Option<PathBuf>,
+    /// Largest frame payload accepted from clients (e.g. 64k, 1m). Default: 16m.
+    #[arg(long, value_name = "SIZE")]
+    pub max_payload: Option<String>,
+    /// Permission bits for the socket path, in octal (e.g. 660). Default: 600.
+    #[arg(long, value_name = "MODE", value_parser = parse_socket_mode)]
+    pub socket_mode: Option<u32>,
+    /// Group to give the socket path, by name or gid (pair with --socket-mode 660).
+    #[arg(long, value_name = "GROUP")]
+    pub socket_group: Option<String>,
+    /// Exit once the first client disconnects (exit 60 if it sent undecodable frames).
+    #[arg(long)]
+    pub exit_on_disconnect: bool,
+    /// Print listener accept and handshake-failure counts to stderr on exit.
+    #[arg(long)]
+    pub stats: bool,
+    /// On SIGINT/SIGTERM/SIGHUP, ask clients to shut down (bounded wait) or just close them.
+    #[arg(long, value_name = "MODE", default_value = "graceful")]
+    pub on_signal: OnSignal,
 }
 
 #[derive(Args, Debug)]
 pub struct VersionArgs {
     /// Show extended build provenance.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "check_peer")]
     pub extended: bool,
+    /// Handshake with the server at PATH and report protocol compatibility.
+    #[arg(long, value_name = "PATH")]
+    pub check_peer: Option<PathBuf>,
+    /// Connect and handshake timeout for --check-peer (e.g. 5s, 500ms). Default: 5s.
+    #[arg(long, value_name = "DURATION", requires = "check_peer")]
+    pub connect_timeout: Option<String>,
 }
 
 #[derive(Args, Debug)]
 pub struct InfoArgs {
-    /// Socket path to connect to.
-    pub path: PathBuf,
-    /// Connection timeout (e.g. 5s, 500ms).
-    #[arg(long, default_value = "5s")]
-    pub timeout: String,
+    /// Socket path to connect to (`@name` for a Linux abstract socket, `tcp://127.0.0.1:PORT` for loopback TCP, `vsock://CID:PORT` for vsock on Linux).
+    pub path: Option<PathBuf>,
+    /// Connect and handshake timeout (e.g. 5s, 500ms). Default: 5s.
+    #[arg(long, visible_alias = "timeout", value_name = "DURATION")]
+    pub connect_timeout: Option<String>,
+    /// Fail unless the peer's protocol version is compatible with this one (major.minor).
+    #[arg(long, value_name = "MAJOR.MINOR")]
+    pub expect_version: Option<String>,
+    /// Fail unless all of these channels are negotiated (comma-separated IDs or built-in names).
+    #[arg(long, value_delimiter = ',', value_parser = parse_channel)]
+    pub expect_channels: Option<Vec<u16>>,
+    /// Auth token to present during the handshake.
+    #[arg(long, value_name = "TOKEN", conflicts_with = "auth_token_file")]
+    pub auth_token: Option<String>,
+    /// Read the auth token from a file (keeps it out of process listings).
+    #[arg(long, value_name = "PATH")]
+    pub auth_token_file: Option<PathBuf>,
+    /// Refuse a server that does not run as this uid, or whose socket (or its directory) another user owns.
+    #[arg(long, value_name = "UID")]
+    pub require_owner: Option<u32>,
 }
 
 #[derive(Args, Debug, Default)]
-pub struct DoctorArgs {}
+pub struct DoctorArgs {
+    /// Also check that a listener socket path is live and still bound.
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+}
 
 #[derive(Args, Debug, Default)]
 pub struct EnvinfoArgs {}
+
+#[derive(Args, Debug, Default)]
+pub struct ChannelsArgs {
+    /// Schema directory describing application channels.
+    #[arg(long, value_name = "DIR")]
+    pub schema_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct SelftestArgs {
+    /// Skip the bundled schema validation step.
+    #[arg(long)]
+    pub skip_schema: bool,
+}
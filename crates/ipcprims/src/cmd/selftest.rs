@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use ipcprims_frame::channel::channel_name;
+use ipcprims_frame::{COMMAND, DATA, DEFAULT_MAX_PAYLOAD, ERROR, TELEMETRY};
+use ipcprims_peer::{connect, Peer, PeerError, PeerListener};
+
+use crate::cmd::doctor::{print_report, CheckReport, CheckResult, CheckStatus};
+use crate::cmd::SelftestArgs;
+use crate::exit::CliResult;
+use crate::output::OutputFormat;
+
+const ROUNDTRIP_CHANNELS: [u16; 4] = [COMMAND, DATA, TELEMETRY, ERROR];
+
+/// Large DATA payload size: just under the default frame limit.
+const LARGE_PAYLOAD_SIZE: usize = DEFAULT_MAX_PAYLOAD - 1024;
+
+#[cfg(feature = "schema")]
+const BUNDLED_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "id": { "type": "integer" },
+        "name": { "type": "string" }
+    },
+    "required": ["id"]
+}"#;
+
+pub fn run(args: SelftestArgs, format: OutputFormat) -> CliResult<i32> {
+    let mut checks = Vec::new();
+
+    match SelftestWorkspace::create() {
+        Ok(workspace) => {
+            checks.push(check(
+                "workspace",
+                CheckStatus::Pass,
+                format!("using {}", workspace.socket_path.display()),
+            ));
+            run_wire_checks(&workspace.socket_path, &mut checks);
+        }
+        Err(err) => checks.push(check(
+            "workspace",
+            CheckStatus::Fail,
+            format!("failed creating private temp directory: {err}"),
+        )),
+    }
+
+    if args.skip_schema {
+        checks.push(check(
+            "schema_validation",
+            CheckStatus::Skip,
+            "skipped by --skip-schema",
+        ));
+    } else {
+        checks.push(schema_validation_check());
+    }
+
+    let report = CheckReport::new(
+        "https://schemas.3leaps.dev/ipcprims/cli/v1/selftest-report.schema.json",
+        checks,
+    );
+    print_report("ipcprims selftest", &report, format);
+    Ok(report.exit_code())
+}
+
+fn run_wire_checks(path: &Path, checks: &mut Vec<CheckResult>) {
+    let listener = match PeerListener::bind(path) {
+        Ok(listener) => {
+            checks.push(check("bind", CheckStatus::Pass, "listener bound"));
+            listener
+        }
+        Err(err) => {
+            checks.push(check(
+                "bind",
+                CheckStatus::Fail,
+                format!("bind failed: {err}"),
+            ));
+            return;
+        }
+    };
+
+    let server = thread::spawn(move || echo_one_peer(&listener));
+
+    let mut client = match connect(path, &ROUNDTRIP_CHANNELS) {
+        Ok(peer) => {
+            checks.push(check(
+                "handshake",
+                CheckStatus::Pass,
+                format!(
+                    "protocol {} with channels {:?}",
+                    peer.handshake_result().protocol_version,
+                    peer.channels()
+                ),
+            ));
+            peer
+        }
+        Err(err) => {
+            checks.push(check(
+                "handshake",
+                CheckStatus::Fail,
+                format!("connect/handshake failed: {err}"),
+            ));
+            return;
+        }
+    };
+
+    for channel in ROUNDTRIP_CHANNELS {
+        let payload = format!("selftest:{}", channel_name(channel)).into_bytes();
+        let name = format!("roundtrip_{}", channel_name(channel).to_ascii_lowercase());
+        checks.push(roundtrip_check(&name, &mut client, channel, &payload));
+    }
+
+    let large: Vec<u8> = (0..LARGE_PAYLOAD_SIZE).map(|i| (i % 251) as u8).collect();
+    checks.push(roundtrip_check(
+        "roundtrip_data_large",
+        &mut client,
+        DATA,
+        &large,
+    ));
+
+    checks.push(match client.ping() {
+        Ok(rtt) => check("ping", CheckStatus::Pass, format!("rtt {rtt:?}")),
+        Err(err) => check("ping", CheckStatus::Fail, format!("ping failed: {err}")),
+    });
+
+    checks.push(match client.shutdown() {
+        Ok(_) => check(
+            "shutdown",
+            CheckStatus::Pass,
+            "graceful shutdown acknowledged",
+        ),
+        Err(err) => check(
+            "shutdown",
+            CheckStatus::Fail,
+            format!("shutdown failed: {err}"),
+        ),
+    });
+
+    checks.push(match server.join() {
+        Ok(Ok(echoed)) => check(
+            "server",
+            CheckStatus::Pass,
+            format!("echoed {echoed} frames and observed disconnect"),
+        ),
+        Ok(Err(err)) => check("server", CheckStatus::Fail, err),
+        Err(_) => check("server", CheckStatus::Fail, "server thread panicked"),
+    });
+}
+
+fn echo_one_peer(listener: &PeerListener) -> Result<usize, String> {
+    let mut peer = listener
+        .accept()
+        .map_err(|err| format!("accept failed: {err}"))?;
+    let mut echoed = 0usize;
+    loop {
+        match peer.recv() {
+            Ok(frame) => {
+                peer.send(frame.channel, frame.payload.as_ref())
+                    .map_err(|err| format!("server send failed: {err}"))?;
+                echoed += 1;
+            }
+            Err(PeerError::Disconnected(_)) => return Ok(echoed),
+            Err(err) => return Err(format!("server receive failed: {err}")),
+        }
+    }
+}
+
+fn roundtrip_check(name: &str, client: &mut Peer, channel: u16, payload: &[u8]) -> CheckResult {
+    if let Err(err) = client.send(channel, payload) {
+        return check(name, CheckStatus::Fail, format!("send failed: {err}"));
+    }
+
+    match client.recv_on(channel) {
+        Ok(frame) if frame.payload.as_ref() == payload => check(
+            name,
+            CheckStatus::Pass,
+            format!("{} bytes on channel {channel}", payload.len()),
+        ),
+        Ok(frame) => check(
+            name,
+            CheckStatus::Fail,
+            format!(
+                "payload mismatch on channel {channel}: sent {} bytes, received {} bytes",
+                payload.len(),
+                frame.payload.len()
+            ),
+        ),
+        Err(err) => check(name, CheckStatus::Fail, format!("receive failed: {err}")),
+    }
+}
+
+#[cfg(feature = "schema")]
+fn schema_validation_check() -> CheckResult {
+    let registry =
+        match ipcprims_schema::SchemaRegistry::from_embedded(&[(COMMAND, BUNDLED_SCHEMA)]) {
+            Ok(registry) => registry,
+            Err(err) => {
+                return check(
+                    "schema_validation",
+                    CheckStatus::Fail,
+                    format!("bundled schema failed to compile: {err}"),
+                )
+            }
+        };
+
+    let valid = registry.validate(COMMAND, br#"{"id":1,"name":"selftest"}"#);
+    let invalid = registry.validate(COMMAND, br#"{"id":"not-a-number"}"#);
+    match (valid, invalid) {
+        (Ok(()), Err(_)) => check(
+            "schema_validation",
+            CheckStatus::Pass,
+            "bundled schema accepted valid and rejected invalid payload",
+        ),
+        (Err(err), _) => check(
+            "schema_validation",
+            CheckStatus::Fail,
+            format!("valid payload rejected: {err}"),
+        ),
+        (Ok(()), Ok(())) => check(
+            "schema_validation",
+            CheckStatus::Fail,
+            "invalid payload was accepted",
+        ),
+    }
+}
+
+#[cfg(not(feature = "schema"))]
+fn schema_validation_check() -> CheckResult {
+    check(
+        "schema_validation",
+        CheckStatus::Skip,
+        "schema support not compiled in",
+    )
+}
+
+fn check(name: &str, status: CheckStatus, detail: impl Into<String>) -> CheckResult {
+    CheckResult {
+        name: name.to_string(),
+        status,
+        detail: detail.into(),
+    }
+}
+
+/// Private socket location removed when the selftest finishes.
+struct SelftestWorkspace {
+    #[cfg(unix)]
+    dir: PathBuf,
+    socket_path: PathBuf,
+}
+
+impl SelftestWorkspace {
+    #[cfg(unix)]
+    fn create() -> std::io::Result<Self> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        // Keep under /tmp to stay within `sun_path` limits on macOS.
+        let dir = PathBuf::from(format!(
+            "/tmp/ipcprims-selftest-{}-{}",
+            std::process::id(),
+            unique_suffix()
+        ));
+        std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+        let socket_path = dir.join("selftest.sock");
+        Ok(Self { dir, socket_path })
+    }
+
+    #[cfg(windows)]
+    fn create() -> std::io::Result<Self> {
+        let socket_path = PathBuf::from(format!(
+            r"\\.\pipe\ipcprims-selftest-{}-{}",
+            std::process::id(),
+            unique_suffix()
+        ));
+        Ok(Self { socket_path })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn create() -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "no native transport on this platform",
+        ))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SelftestWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn unique_suffix() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn bundled_schema_check_passes() {
+        let result = schema_validation_check();
+        assert!(matches!(result.status, CheckStatus::Pass));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wire_checks_pass_locally() {
+        let workspace = SelftestWorkspace::create().expect("workspace should be creatable");
+        let mut checks = Vec::new();
+        run_wire_checks(&workspace.socket_path, &mut checks);
+
+        let failures: Vec<_> = checks
+            .iter()
+            .filter(|c| !matches!(c.status, CheckStatus::Pass))
+            .map(|c| format!("{}: {}", c.name, c.detail))
+            .collect();
+        assert!(failures.is_empty(), "selftest failures: {failures:?}");
+        assert!(checks.iter().any(|c| c.name == "roundtrip_data_large"));
+    }
+}
@@ -1,40 +1,93 @@
 use std::fs;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ipcprims_frame::{Frame, ERROR};
-use ipcprims_peer::{connect_with_config, HandshakeConfig, PeerConfig};
+use ipcprims_peer::{HandshakeConfig, PeerError};
+#[cfg(feature = "schema")]
+use ipcprims_schema::{RegistryConfig, SchemaError, SchemaRegistry, ValidationIssue};
+#[cfg(feature = "schema")]
+use serde::Serialize;
 
-use crate::cmd::SendArgs;
-use crate::exit::{peer_error, CliError, CliResult, SUCCESS, USAGE};
-use crate::output::{print_frame, OutputFormat};
+use crate::cmd::auth::resolve_token;
+use crate::cmd::connect::{connect_with_timeout, parse_duration};
+use crate::cmd::{require_path, timeout_arg, SendArgs};
+#[cfg(feature = "schema")]
+use crate::exit::DATA_INVALID;
+use crate::exit::{peer_error, CliError, CliResult, FAILURE, SUCCESS, TIMEOUT, USAGE};
+use crate::output::{print_frame, FramePrintOptions, OutputFormat};
 
 pub fn run(args: SendArgs, format: OutputFormat) -> CliResult<i32> {
-    let wait_timeout = parse_duration(&args.wait_timeout)?;
-    let peer_config = PeerConfig {
-        shutdown_timeout: wait_timeout,
-        ..PeerConfig::default()
-    };
+    let path = require_path(args.path.as_deref())?;
+    let payload = resolve_payload(&args)?;
+    validate_outgoing(&args, &payload, format)?;
+
+    let connect_timeout = parse_duration(timeout_arg(args.connect_timeout.as_deref()))?;
+    let wait_timeout = parse_duration(timeout_arg(args.wait_timeout.as_deref()))?;
+    let wait = args.wait || args.expect_responses.is_some();
     let mut requested_channels = vec![args.channel];
-    if args.wait && args.channel != ERROR {
+    if wait && args.channel != ERROR {
         requested_channels.push(ERROR);
     }
-    let mut peer = connect_with_config(
-        &args.path,
+    let handshake_config = HandshakeConfig {
+        timeout: connect_timeout,
+        auth_token: resolve_token(
+            args.auth_token.as_deref(),
+            args.auth_token_file.as_deref(),
+            "--auth-token",
+        )?,
+        require_server_uid: args.require_owner,
+        ..HandshakeConfig::default()
+    };
+    let mut peer = connect_with_timeout(
+        path,
         &requested_channels,
-        &HandshakeConfig::default(),
+        &handshake_config,
         None,
-        Some(peer_config),
-    )
-    .map_err(|err| peer_error("connect failed", err))?;
+        connect_timeout,
+    )?;
 
-    let payload = resolve_payload(&args)?;
     peer.send(args.channel, &payload)
         .map_err(|err| peer_error("send failed", err))?;
 
-    if args.wait {
-        let frame = wait_for_response(&mut peer, args.channel)
+    let peer_id = peer.id().to_string();
+    let credentials = peer.peer_credentials();
+    let remote_addr = peer.remote_addr();
+    let options = FramePrintOptions {
+        force_base64: args.force_base64,
+    };
+
+    if let Some(expected) = args.expect_responses {
+        let expected = expected.get();
+        let received =
+            collect_responses(&mut peer, args.channel, expected, wait_timeout, |frame| {
+                print_frame(
+                    frame,
+                    &peer_id,
+                    credentials,
+                    remote_addr.as_ref(),
+                    format,
+                    options,
+                )
+            })?;
+        if received < expected {
+            return Err(CliError::new(
+                TIMEOUT,
+                format!(
+                    "received {received} of {expected} expected responses within {wait_timeout:?}"
+                ),
+            ));
+        }
+    } else if wait {
+        let frame = wait_for_response(&mut peer, args.channel, wait_timeout)
             .map_err(|err| peer_error("receive failed", err))?;
-        print_frame(&frame, peer.id(), format);
+        print_frame(
+            &frame,
+            &peer_id,
+            credentials,
+            remote_addr.as_ref(),
+            format,
+            options,
+        );
     }
 
     Ok(SUCCESS)
@@ -57,63 +110,232 @@ fn resolve_payload(args: &SendArgs) -> CliResult<Vec<u8>> {
     Ok(Vec::new())
 }
 
-fn parse_duration(input: &str) -> CliResult<Duration> {
-    let input = input.trim();
-    if input.is_empty() {
-        return Err(CliError::new(USAGE, "duration must not be empty"));
-    }
+#[cfg(feature = "schema")]
+#[derive(Serialize)]
+struct IssueOutput<'a> {
+    instance_path: &'a str,
+    schema_path: &'a str,
+    message: &'a str,
+}
 
-    let (number, unit) = if let Some(num) = input.strip_suffix("ms") {
-        (num, "ms")
-    } else if let Some(num) = input.strip_suffix('s') {
-        (num, "s")
-    } else {
-        (input, "s")
+#[cfg(feature = "schema")]
+#[derive(Serialize)]
+struct ValidationFailureOutput<'a> {
+    schema_id: &'a str,
+    channel: u16,
+    issues: Vec<IssueOutput<'a>>,
+}
+
+/// Check the payload against `--validate-dir` before anything is transmitted.
+#[cfg(feature = "schema")]
+fn validate_outgoing(args: &SendArgs, payload: &[u8], format: OutputFormat) -> CliResult<()> {
+    let Some(dir) = &args.validate_dir else {
+        return require_schema_dir_for_strict(args);
     };
 
-    let value: u64 = number
-        .parse()
-        .map_err(|_| CliError::new(USAGE, format!("invalid duration value: {input}")))?;
+    // --json was already parsed by resolve_payload; raw payloads must be JSON too.
+    if args.json.is_none() {
+        let source = if args.file.is_some() {
+            "--file"
+        } else {
+            "--data"
+        };
+        serde_json::from_slice::<serde_json::Value>(payload).map_err(|err| {
+            CliError::new(
+                DATA_INVALID,
+                format!(
+                    "--validate-dir requires a JSON payload, but {source} is not valid JSON: {err}"
+                ),
+            )
+        })?;
+    }
+
+    let registry = SchemaRegistry::from_directory_with_config(
+        dir,
+        RegistryConfig {
+            strict_mode: true,
+            fail_on_missing_schema: args.strict_schemas,
+            ..RegistryConfig::default()
+        },
+    )
+    .map_err(|err| CliError::new(DATA_INVALID, format!("schema load failed: {err}")))?;
 
-    if value == 0 {
-        return Err(CliError::new(USAGE, "duration must be greater than zero"));
+    if !registry.has_schema(args.channel) && !args.strict_schemas {
+        tracing::warn!(
+            channel = args.channel,
+            "no schema for channel; sending without validation"
+        );
+        return Ok(());
     }
 
-    match unit {
-        "ms" => Ok(Duration::from_millis(value)),
-        "s" => Ok(Duration::from_secs(value)),
-        _ => Err(CliError::new(
-            USAGE,
-            format!("unsupported duration unit: {unit}"),
+    match registry.issues(args.channel, payload) {
+        Ok(issues) if issues.is_empty() => Ok(()),
+        Ok(issues) => {
+            print_validation_issues(args.channel, &issues, format);
+            Err(CliError::new(
+                DATA_INVALID,
+                format!(
+                    "payload failed schema validation on channel {} ({} issue(s))",
+                    args.channel,
+                    issues.len()
+                ),
+            ))
+        }
+        Err(SchemaError::NoSchema(channel)) => Err(CliError::new(
+            DATA_INVALID,
+            format!(
+                "no schema for channel {channel} in {} (--strict-schemas)",
+                dir.display()
+            ),
+        )),
+        Err(err) => Err(CliError::new(
+            DATA_INVALID,
+            format!("schema validation failed: {err}"),
         )),
     }
 }
 
+#[cfg(not(feature = "schema"))]
+fn validate_outgoing(args: &SendArgs, _payload: &[u8], _format: OutputFormat) -> CliResult<()> {
+    if args.validate_dir.is_some() {
+        return Err(CliError::new(
+            USAGE,
+            "--validate-dir requires the schema feature",
+        ));
+    }
+    require_schema_dir_for_strict(args)
+}
+
+/// `--strict-schemas` needs a schema directory, from the flag or config.
+fn require_schema_dir_for_strict(args: &SendArgs) -> CliResult<()> {
+    if args.strict_schemas {
+        return Err(CliError::new(
+            USAGE,
+            "--strict-schemas requires --validate-dir (or schema_dir in config)",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "schema")]
+fn print_validation_issues(channel: u16, issues: &[ValidationIssue], format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            let out = ValidationFailureOutput {
+                schema_id:
+                    "https://schemas.3leaps.dev/ipcprims/cli/v1/validation-failure.schema.json",
+                channel,
+                issues: issues
+                    .iter()
+                    .map(|issue| IssueOutput {
+                        instance_path: &issue.instance_path,
+                        schema_path: &issue.schema_path,
+                        message: &issue.message,
+                    })
+                    .collect(),
+            };
+            eprintln!(
+                "{}",
+                serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string())
+            );
+        }
+        OutputFormat::Table | OutputFormat::Pretty | OutputFormat::Raw => {
+            for issue in issues {
+                let at = if issue.instance_path.is_empty() {
+                    "/"
+                } else {
+                    issue.instance_path.as_str()
+                };
+                eprintln!(
+                    "schema issue at {at} (schema {}): {}",
+                    issue.schema_path, issue.message
+                );
+            }
+        }
+    }
+}
+
 trait ResponseReceiver {
-    fn recv_on_channel(&mut self, channel: u16) -> Result<Frame, ipcprims_peer::PeerError>;
+    /// Next frame on `channel`, blocking at most `timeout`.
+    fn recv_on_channel(
+        &mut self,
+        channel: u16,
+        timeout: Duration,
+    ) -> Result<Frame, ipcprims_peer::PeerError>;
+
+    /// Next frame on any negotiated channel, blocking at most `timeout`.
+    fn recv_any(&mut self, timeout: Duration) -> Result<Frame, ipcprims_peer::PeerError>;
 }
 
 impl ResponseReceiver for ipcprims_peer::Peer {
-    fn recv_on_channel(&mut self, channel: u16) -> Result<Frame, ipcprims_peer::PeerError> {
-        self.recv_on(channel)
+    fn recv_on_channel(
+        &mut self,
+        channel: u16,
+        timeout: Duration,
+    ) -> Result<Frame, ipcprims_peer::PeerError> {
+        self.recv_on_timeout(channel, timeout)
+    }
+
+    fn recv_any(&mut self, timeout: Duration) -> Result<Frame, ipcprims_peer::PeerError> {
+        self.recv_timeout(timeout)
     }
 }
 
 fn wait_for_response<R: ResponseReceiver>(
     receiver: &mut R,
     channel: u16,
+    timeout: Duration,
 ) -> Result<Frame, ipcprims_peer::PeerError> {
-    match receiver.recv_on_channel(channel) {
+    match receiver.recv_on_channel(channel, timeout) {
         Ok(frame) => Ok(frame),
-        Err(ipcprims_peer::PeerError::Timeout(_)) if channel != ERROR => {
-            receiver.recv_on_channel(ERROR)
+        Err(ipcprims_peer::PeerError::Timeout { .. }) if channel != ERROR => {
+            receiver.recv_on_channel(ERROR, timeout)
         }
         Err(err) => Err(err),
     }
 }
 
+/// Receive frames on `channel` and ERROR until `expected` arrive or `timeout`
+/// elapses, handing each to `on_frame` as it arrives. Returns how many arrived.
+fn collect_responses<R: ResponseReceiver>(
+    receiver: &mut R,
+    channel: u16,
+    expected: usize,
+    timeout: Duration,
+    mut on_frame: impl FnMut(&Frame),
+) -> CliResult<usize> {
+    let deadline = Instant::now() + timeout;
+    let mut received = 0usize;
+    while received < expected {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        match receiver.recv_any(remaining) {
+            Ok(frame) if frame.channel == channel || frame.channel == ERROR => {
+                on_frame(&frame);
+                received += 1;
+            }
+            Ok(_) => {}
+            Err(PeerError::Timeout { .. }) => break,
+            Err(PeerError::Disconnected(reason)) => {
+                return Err(CliError::new(
+                    FAILURE,
+                    format!(
+                        "server disconnected after {received} of {expected} expected responses: {reason}"
+                    ),
+                ))
+            }
+            Err(err) => return Err(peer_error("receive failed", err)),
+        }
+    }
+    Ok(received)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
+
     use super::*;
 
     struct MockReceiver {
@@ -122,37 +344,107 @@ mod tests {
     }
 
     impl ResponseReceiver for MockReceiver {
-        fn recv_on_channel(&mut self, channel: u16) -> Result<Frame, ipcprims_peer::PeerError> {
+        fn recv_on_channel(
+            &mut self,
+            channel: u16,
+            _timeout: Duration,
+        ) -> Result<Frame, ipcprims_peer::PeerError> {
             self.calls += 1;
             self.called_channel = Some(channel);
             if self.calls == 1 {
-                return Err(ipcprims_peer::PeerError::Timeout(Duration::from_secs(1)));
+                return Err(ipcprims_peer::PeerError::Timeout {
+                    operation: ipcprims_peer::TimeoutOp::Recv {
+                        channel: Some(channel),
+                    },
+                    waited: Duration::from_secs(1),
+                });
             }
             Ok(Frame::new(channel, b"ok".to_vec()))
         }
+
+        fn recv_any(&mut self, _timeout: Duration) -> Result<Frame, ipcprims_peer::PeerError> {
+            unreachable!("wait_for_response only receives by channel")
+        }
+    }
+
+    /// Replays scripted frames, then times out.
+    struct ScriptedReceiver {
+        frames: VecDeque<Result<Frame, ipcprims_peer::PeerError>>,
+    }
+
+    impl ResponseReceiver for ScriptedReceiver {
+        fn recv_on_channel(
+            &mut self,
+            _channel: u16,
+            _timeout: Duration,
+        ) -> Result<Frame, ipcprims_peer::PeerError> {
+            unreachable!("collect_responses receives from any channel")
+        }
+
+        fn recv_any(&mut self, timeout: Duration) -> Result<Frame, ipcprims_peer::PeerError> {
+            self.frames
+                .pop_front()
+                .unwrap_or(Err(ipcprims_peer::PeerError::Timeout {
+                    operation: ipcprims_peer::TimeoutOp::Recv { channel: None },
+                    waited: timeout,
+                }))
+        }
     }
 
     #[test]
-    fn wait_for_response_falls_back_to_error_channel() {
-        let mut receiver = MockReceiver {
-            called_channel: None,
-            calls: 0,
+    fn collect_responses_counts_target_and_error_frames() {
+        let mut receiver = ScriptedReceiver {
+            frames: VecDeque::from([
+                Ok(Frame::new(7, b"result".to_vec())),
+                Ok(Frame::new(ERROR, b"warning".to_vec())),
+                Ok(Frame::new(7, b"done".to_vec())),
+                Ok(Frame::new(7, b"extra".to_vec())),
+            ]),
         };
-        let frame = wait_for_response(&mut receiver, 7).expect("wait should succeed");
-        assert_eq!(receiver.called_channel, Some(ERROR));
-        assert_eq!(frame.channel, ERROR);
+        let mut seen = Vec::new();
+        let received = collect_responses(&mut receiver, 7, 3, Duration::from_secs(1), |frame| {
+            seen.push(frame.payload.to_vec())
+        })
+        .expect("collect should succeed");
+        assert_eq!(received, 3);
+        assert_eq!(
+            seen,
+            [b"result".to_vec(), b"warning".to_vec(), b"done".to_vec()]
+        );
+        assert_eq!(receiver.frames.len(), 1, "frames past N are not consumed");
+    }
+
+    #[test]
+    fn collect_responses_reports_short_count_on_timeout() {
+        let mut receiver = ScriptedReceiver {
+            frames: VecDeque::from([Ok(Frame::new(7, b"only".to_vec()))]),
+        };
+        let received = collect_responses(&mut receiver, 7, 2, Duration::from_secs(1), |_| {})
+            .expect("timeout is not an error here");
+        assert_eq!(received, 1);
     }
 
     #[test]
-    fn parse_duration_seconds_and_millis() {
-        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
-        assert_eq!(parse_duration("150ms").unwrap(), Duration::from_millis(150));
-        assert_eq!(parse_duration("3").unwrap(), Duration::from_secs(3));
+    fn collect_responses_fails_when_server_disconnects() {
+        let mut receiver = ScriptedReceiver {
+            frames: VecDeque::from([Err(ipcprims_peer::PeerError::Disconnected(
+                "connection closed".to_string(),
+            ))]),
+        };
+        let err = collect_responses(&mut receiver, 7, 2, Duration::from_secs(1), |_| {})
+            .expect_err("disconnect should fail");
+        assert_eq!(err.code, FAILURE);
     }
 
     #[test]
-    fn parse_duration_rejects_invalid_values() {
-        assert!(parse_duration("0s").is_err());
-        assert!(parse_duration("bad").is_err());
+    fn wait_for_response_falls_back_to_error_channel() {
+        let mut receiver = MockReceiver {
+            called_channel: None,
+            calls: 0,
+        };
+        let frame = wait_for_response(&mut receiver, 7, Duration::from_secs(1))
+            .expect("wait should succeed");
+        assert_eq!(receiver.called_channel, Some(ERROR));
+        assert_eq!(frame.channel, ERROR);
     }
 }
@@ -0,0 +1,99 @@
+//! Termination signals for the long-running server commands (`echo`,
+//! `listen`): SIGINT, SIGTERM and SIGHUP on Unix, Ctrl-C and console close on
+//! Windows.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use ipcprims_peer::{Peer, PeerConfig, PeerListener, ShutdownPolicy};
+
+use crate::exit::{CliError, CliResult, INTERNAL};
+
+/// Upper bound on waiting for clients to acknowledge a signal-triggered
+/// shutdown.
+pub(crate) const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often blocked accepts and receives wake to re-check the signal flag.
+pub(crate) const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What `echo` and `listen` do with connected clients on a termination signal.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnSignal {
+    /// Send each client a shutdown request and wait (bounded) for the ack.
+    #[default]
+    Graceful,
+    /// Close client connections without a shutdown handshake.
+    Immediate,
+}
+
+impl OnSignal {
+    /// Shut `peer` down the way this mode asks; failures are only logged.
+    pub(crate) fn close(self, peer: Peer) {
+        if self == OnSignal::Immediate {
+            return;
+        }
+        let peer_id = peer.id().to_string();
+        match peer.shutdown() {
+            Ok(outcome) => tracing::debug!(peer_id, ?outcome, "peer shut down on signal"),
+            Err(err) => tracing::warn!(peer_id, error = %err, "graceful peer shutdown failed"),
+        }
+    }
+}
+
+/// Peer config whose shutdown handshake fits in [`DRAIN_TIMEOUT`].
+pub(crate) fn drain_bounded(config: PeerConfig) -> PeerConfig {
+    PeerConfig {
+        shutdown_policy: ShutdownPolicy {
+            ack_timeout: Some(DRAIN_TIMEOUT),
+            force_timeout: None,
+            hard_close: false,
+        },
+        ..config
+    }
+}
+
+/// Clear `running` and stop `listener` when a termination signal arrives.
+///
+/// Returns a flag that is set (before `running` is cleared) only by a signal,
+/// so callers can tell a signal from their own reasons to stop. The listener
+/// is held weakly so that dropping it elsewhere (e.g. `echo --once`) still
+/// unbinds and removes the socket path.
+pub(crate) fn install_handler(
+    running: Arc<AtomicBool>,
+    listener: Weak<PeerListener>,
+) -> CliResult<Arc<AtomicBool>> {
+    let signalled = Arc::new(AtomicBool::new(false));
+    let flag = signalled.clone();
+    ctrlc::set_handler(move || {
+        flag.store(true, Ordering::SeqCst);
+        running.store(false, Ordering::SeqCst);
+        if let Some(listener) = listener.upgrade() {
+            listener.shutdown();
+        }
+    })
+    .map_err(|err| CliError::new(INTERNAL, format!("signal handler setup failed: {err}")))?;
+    Ok(signalled)
+}
+
+/// Drop `listener` on this thread, so its socket path is removed before the
+/// process exits.
+///
+/// The signal handler upgrades its weak reference while it shuts the
+/// listener down; if the caller dropped its `Arc` first, the handler thread
+/// would do the cleanup and could lose the race with process exit. Waits
+/// (bounded) for the handler to let go.
+pub(crate) fn release_listener(mut listener: Arc<PeerListener>) {
+    let deadline = Instant::now() + SIGNAL_POLL_INTERVAL;
+    loop {
+        match Arc::try_unwrap(listener) {
+            Ok(listener) => return drop(listener),
+            Err(shared) if Instant::now() < deadline => {
+                listener = shared;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Err(_) => return,
+        }
+    }
+}
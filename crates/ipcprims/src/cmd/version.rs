@@ -1,7 +1,55 @@
-use crate::cmd::VersionArgs;
-use crate::exit::{CliResult, SUCCESS};
+use std::path::Path;
+
+use ipcprims_peer::{connect_with_config, HandshakeConfig, PeerError};
+use serde::Serialize;
+
+use crate::cmd::connect::parse_duration;
+use crate::cmd::{timeout_arg, VersionArgs};
+use crate::exit::{peer_error, CliError, CliResult, DATA_INVALID, SUCCESS, TIMEOUT};
+use crate::output::OutputFormat;
+
+/// Channels offered during a `--check-peer` probe; only the handshake matters.
+const PROBE_CHANNELS: [u16; 4] = [1, 2, 3, 4];
+
+#[derive(Serialize)]
+struct VersionOutput {
+    schema_id: &'static str,
+    name: &'static str,
+    version: &'static str,
+    protocol_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer_check: Option<PeerCheck>,
+}
+
+#[derive(Serialize)]
+struct PeerCheck {
+    path: String,
+    /// Remote protocol version; unknown when the server refused the handshake.
+    remote_protocol_version: Option<String>,
+    compatible: bool,
+    detail: String,
+}
+
+pub fn run(args: VersionArgs, format: OutputFormat) -> CliResult<i32> {
+    if let Some(path) = &args.check_peer {
+        let timeout = parse_duration(timeout_arg(args.connect_timeout.as_deref()))?;
+        let handshake_config = HandshakeConfig {
+            timeout,
+            require_channel_overlap: false,
+            ..HandshakeConfig::default()
+        };
+        let out = VersionOutput {
+            schema_id: "https://schemas.3leaps.dev/ipcprims/cli/v1/version.schema.json",
+            name: "ipcprims",
+            version: env!("CARGO_PKG_VERSION"),
+            protocol_version: handshake_config.protocol_version.clone(),
+            peer_check: Some(check_peer(path, &handshake_config)?),
+        };
+        print_version(&out, format);
+        let compatible = out.peer_check.as_ref().is_some_and(|c| c.compatible);
+        return Ok(if compatible { SUCCESS } else { DATA_INVALID });
+    }
 
-pub fn run(args: VersionArgs) -> CliResult<i32> {
     if !args.extended {
         println!("ipcprims {}", env!("CARGO_PKG_VERSION"));
         return Ok(SUCCESS);
@@ -26,3 +74,85 @@ pub fn run(args: VersionArgs) -> CliResult<i32> {
 
     Ok(SUCCESS)
 }
+
+/// Handshake-only probe. Incompatibility is a result, not an error; only
+/// failing to reach the server at all is.
+fn check_peer(path: &Path, config: &HandshakeConfig) -> CliResult<PeerCheck> {
+    let result = connect_with_config(path, &PROBE_CHANNELS, config, None, None);
+    let (remote_protocol_version, compatible, detail) = match result {
+        Ok(peer) => {
+            let remote = peer.handshake_result().protocol_version.clone();
+            let detail = format!(
+                "handshake succeeded (local {}, remote {remote})",
+                config.protocol_version
+            );
+            (Some(remote), true, detail)
+        }
+        // Servers refuse incompatible clients by hanging up without a response.
+        Err(PeerError::Disconnected(_)) => (
+            None,
+            false,
+            format!(
+                "server closed the connection during handshake \
+                 (protocol {} rejected, or an auth token is required)",
+                config.protocol_version
+            ),
+        ),
+        Err(PeerError::HandshakeFailed(reason)) => (None, false, reason),
+        Err(err @ PeerError::Timeout { .. }) => {
+            return Err(CliError::new(
+                TIMEOUT,
+                format!("{err} (server accepted but did not respond)"),
+            ))
+        }
+        Err(err) => return Err(peer_error("connect failed", err)),
+    };
+
+    Ok(PeerCheck {
+        path: path.display().to_string(),
+        remote_protocol_version,
+        compatible,
+        detail,
+    })
+}
+
+fn print_version(out: &VersionOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string(out).unwrap_or_else(|_| "{}".to_string())
+            );
+        }
+        OutputFormat::Table | OutputFormat::Pretty => {
+            println!("{} {}", out.name, out.version);
+            println!("  Local protocol:   {}", out.protocol_version);
+            if let Some(check) = &out.peer_check {
+                println!(
+                    "  Remote protocol:  {} ({})",
+                    check
+                        .remote_protocol_version
+                        .as_deref()
+                        .unwrap_or("unknown"),
+                    check.path
+                );
+                println!(
+                    "  Compatible:       {}",
+                    if check.compatible { "yes" } else { "NO" }
+                );
+                println!("  Detail:           {}", check.detail);
+            }
+        }
+        OutputFormat::Raw => {
+            let compatible = out.peer_check.as_ref().is_some_and(|c| c.compatible);
+            println!(
+                "{}",
+                if compatible {
+                    "compatible"
+                } else {
+                    "incompatible"
+                }
+            );
+        }
+    }
+}
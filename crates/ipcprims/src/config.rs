@@ -0,0 +1,324 @@
+//! CLI defaults resolution.
+//!
+//! Every defaultable setting is resolved here with a single precedence order:
+//! CLI flag > environment variable > config file > built-in default.
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::exit::{CliError, CliResult, USAGE};
+use crate::logging::{LogFormat, LogLevel};
+use crate::output::OutputFormat;
+
+/// Environment variable overriding the config file location.
+pub const CONFIG_PATH_ENV: &str = "IPCPRIMS_CONFIG";
+
+/// Built-in default for connect/wait timeouts.
+pub const DEFAULT_TIMEOUT: &str = "5s";
+
+const ENV_FORMAT: &str = "IPCPRIMS_FORMAT";
+const ENV_LOG_FORMAT: &str = "IPCPRIMS_LOG_FORMAT";
+const ENV_LOG_LEVEL: &str = "IPCPRIMS_LOG_LEVEL";
+const ENV_SCHEMA_DIR: &str = "IPCPRIMS_SCHEMA_DIR";
+const ENV_TIMEOUT: &str = "IPCPRIMS_TIMEOUT";
+const ENV_SOCKET: &str = "IPCPRIMS_SOCKET";
+
+/// Values supplied by a single configuration source.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigValues {
+    pub format: Option<OutputFormat>,
+    pub log_format: Option<LogFormat>,
+    pub log_level: Option<LogLevel>,
+    pub schema_dir: Option<PathBuf>,
+    pub timeout: Option<String>,
+    pub socket_path: Option<PathBuf>,
+}
+
+impl ConfigValues {
+    /// Read values from environment variables via `lookup`.
+    pub fn from_env_with(lookup: impl Fn(&str) -> Option<String>) -> CliResult<Self> {
+        let lookup = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+        let source = |key: &str| format!("environment variable {key}");
+
+        Ok(Self {
+            format: lookup(ENV_FORMAT)
+                .map(|v| parse_enum(&v, &source(ENV_FORMAT)))
+                .transpose()?,
+            log_format: lookup(ENV_LOG_FORMAT)
+                .map(|v| parse_enum(&v, &source(ENV_LOG_FORMAT)))
+                .transpose()?,
+            log_level: lookup(ENV_LOG_LEVEL)
+                .map(|v| parse_enum(&v, &source(ENV_LOG_LEVEL)))
+                .transpose()?,
+            schema_dir: lookup(ENV_SCHEMA_DIR).map(PathBuf::from),
+            timeout: lookup(ENV_TIMEOUT),
+            socket_path: lookup(ENV_SOCKET).map(PathBuf::from),
+        })
+    }
+
+    /// Parse values from config file contents. `origin` is used in error messages.
+    pub fn from_toml(contents: &str, origin: &Path) -> CliResult<Self> {
+        let table: toml::Table = contents.parse().map_err(|err| {
+            CliError::new(USAGE, format!("invalid config {}: {err}", origin.display()))
+        })?;
+
+        let mut values = Self::default();
+        for (key, value) in &table {
+            let source = format!("config key `{key}` in {}", origin.display());
+            let text = value.as_str().ok_or_else(|| {
+                CliError::new(USAGE, format!("invalid {source}: expected a string"))
+            })?;
+
+            match key.as_str() {
+                "format" => values.format = Some(parse_enum(text, &source)?),
+                "log_format" => values.log_format = Some(parse_enum(text, &source)?),
+                "log_level" => values.log_level = Some(parse_enum(text, &source)?),
+                "schema_dir" => values.schema_dir = Some(PathBuf::from(text)),
+                "timeout" => values.timeout = Some(text.to_string()),
+                "socket_path" => values.socket_path = Some(PathBuf::from(text)),
+                _ => {
+                    return Err(CliError::new(
+                        USAGE,
+                        format!("unknown config key `{key}` in {}", origin.display()),
+                    ))
+                }
+            }
+        }
+
+        Ok(values)
+    }
+}
+
+/// Environment and config file layers consulted when a CLI flag is absent.
+#[derive(Debug, Default)]
+pub struct ConfigLayers {
+    env: ConfigValues,
+    file: ConfigValues,
+}
+
+impl ConfigLayers {
+    /// Build layers from explicit sources.
+    pub fn new(env: ConfigValues, file: ConfigValues) -> Self {
+        Self { env, file }
+    }
+
+    /// Load layers from the process environment and, unless `no_config` is set,
+    /// the config file.
+    pub fn load(no_config: bool) -> CliResult<Self> {
+        let env = ConfigValues::from_env_with(|key| std::env::var(key).ok())?;
+        let file = if no_config {
+            ConfigValues::default()
+        } else {
+            load_config_file()?
+        };
+        Ok(Self::new(env, file))
+    }
+
+    pub fn format(&self, cli: Option<OutputFormat>) -> OutputFormat {
+        self.pick(cli, |v| v.format)
+            .unwrap_or_else(OutputFormat::default_for_stdout)
+    }
+
+    pub fn log_format(&self, cli: Option<LogFormat>) -> LogFormat {
+        self.pick(cli, |v| v.log_format).unwrap_or(LogFormat::Text)
+    }
+
+    pub fn log_level(&self, cli: Option<LogLevel>) -> LogLevel {
+        self.pick(cli, |v| v.log_level).unwrap_or(LogLevel::Info)
+    }
+
+    pub fn schema_dir(&self, cli: Option<PathBuf>) -> Option<PathBuf> {
+        self.pick(cli, |v| v.schema_dir.clone())
+    }
+
+    pub fn timeout(&self, cli: Option<String>) -> String {
+        self.pick(cli, |v| v.timeout.clone())
+            .unwrap_or_else(|| DEFAULT_TIMEOUT.to_string())
+    }
+
+    pub fn socket_path(&self, cli: Option<PathBuf>) -> Option<PathBuf> {
+        self.pick(cli, |v| v.socket_path.clone())
+    }
+
+    fn pick<T>(&self, cli: Option<T>, get: impl Fn(&ConfigValues) -> Option<T>) -> Option<T> {
+        cli.or_else(|| get(&self.env)).or_else(|| get(&self.file))
+    }
+}
+
+fn load_config_file() -> CliResult<ConfigValues> {
+    let (path, explicit) = match std::env::var_os(CONFIG_PATH_ENV) {
+        Some(path) if !path.is_empty() => (PathBuf::from(path), true),
+        _ => match default_config_path() {
+            Some(path) => (path, false),
+            None => return Ok(ConfigValues::default()),
+        },
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => ConfigValues::from_toml(&contents, &path),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && !explicit => {
+            Ok(ConfigValues::default())
+        }
+        Err(err) => Err(CliError::new(
+            USAGE,
+            format!("failed reading config {}: {err}", path.display()),
+        )),
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    #[cfg(not(windows))]
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    base.map(|dir| dir.join("ipcprims").join("config.toml"))
+}
+
+fn parse_enum<T: ValueEnum>(value: &str, source: &str) -> CliResult<T> {
+    T::from_str(value.trim(), true).map_err(|_| {
+        let allowed = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        CliError::new(
+            USAGE,
+            format!("invalid {source}: `{value}` (expected one of: {allowed})"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> ConfigValues {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ConfigValues::from_env_with(|key| map.get(key).cloned()).expect("env should parse")
+    }
+
+    fn file(contents: &str) -> ConfigValues {
+        ConfigValues::from_toml(contents, Path::new("config.toml")).expect("config should parse")
+    }
+
+    #[test]
+    fn builtin_defaults_apply_without_sources() {
+        let layers = ConfigLayers::default();
+        assert!(matches!(layers.log_level(None), LogLevel::Info));
+        assert!(matches!(layers.log_format(None), LogFormat::Text));
+        assert_eq!(layers.timeout(None), DEFAULT_TIMEOUT);
+        assert!(layers.schema_dir(None).is_none());
+        assert!(layers.socket_path(None).is_none());
+    }
+
+    #[test]
+    fn file_overrides_builtin_default() {
+        let layers = ConfigLayers::new(
+            ConfigValues::default(),
+            file("log_level = \"warn\"\ntimeout = \"2s\"\nformat = \"json\""),
+        );
+        assert!(matches!(layers.log_level(None), LogLevel::Warn));
+        assert_eq!(layers.timeout(None), "2s");
+        assert!(matches!(layers.format(None), OutputFormat::Json));
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let layers = ConfigLayers::new(
+            env(&[
+                ("IPCPRIMS_LOG_LEVEL", "debug"),
+                ("IPCPRIMS_SCHEMA_DIR", "/env/schemas"),
+            ]),
+            file("log_level = \"warn\"\nschema_dir = \"/file/schemas\""),
+        );
+        assert!(matches!(layers.log_level(None), LogLevel::Debug));
+        assert_eq!(layers.schema_dir(None), Some(PathBuf::from("/env/schemas")));
+    }
+
+    #[test]
+    fn env_overrides_builtin_default() {
+        let layers = ConfigLayers::new(
+            env(&[
+                ("IPCPRIMS_TIMEOUT", "750ms"),
+                ("IPCPRIMS_LOG_FORMAT", "json"),
+            ]),
+            ConfigValues::default(),
+        );
+        assert_eq!(layers.timeout(None), "750ms");
+        assert!(matches!(layers.log_format(None), LogFormat::Json));
+    }
+
+    #[test]
+    fn cli_overrides_env_and_file() {
+        let layers = ConfigLayers::new(
+            env(&[
+                ("IPCPRIMS_SOCKET", "/env.sock"),
+                ("IPCPRIMS_FORMAT", "table"),
+            ]),
+            file("socket_path = \"/file.sock\"\nformat = \"pretty\""),
+        );
+        assert_eq!(
+            layers.socket_path(Some(PathBuf::from("/cli.sock"))),
+            Some(PathBuf::from("/cli.sock"))
+        );
+        assert!(matches!(
+            layers.format(Some(OutputFormat::Raw)),
+            OutputFormat::Raw
+        ));
+    }
+
+    #[test]
+    fn cli_overrides_file_without_env() {
+        let layers = ConfigLayers::new(ConfigValues::default(), file("timeout = \"9s\""));
+        assert_eq!(layers.timeout(Some("1s".to_string())), "1s");
+    }
+
+    #[test]
+    fn unknown_config_key_names_the_key() {
+        let err = ConfigValues::from_toml("colour = \"red\"", Path::new("config.toml"))
+            .expect_err("unknown key should fail");
+        assert_eq!(err.code, USAGE);
+        assert!(err.message.contains("`colour`"));
+    }
+
+    #[test]
+    fn invalid_config_value_names_the_key() {
+        let err = ConfigValues::from_toml("log_level = \"loud\"", Path::new("config.toml"))
+            .expect_err("invalid value should fail");
+        assert_eq!(err.code, USAGE);
+        assert!(err.message.contains("`log_level`"));
+
+        let err = ConfigValues::from_toml("timeout = 5", Path::new("config.toml"))
+            .expect_err("non-string value should fail");
+        assert!(err.message.contains("`timeout`"));
+    }
+
+    #[test]
+    fn malformed_toml_is_usage_error() {
+        let err = ConfigValues::from_toml("format = ", Path::new("config.toml"))
+            .expect_err("malformed toml should fail");
+        assert_eq!(err.code, USAGE);
+    }
+
+    #[test]
+    fn invalid_env_value_names_the_variable() {
+        let err = ConfigValues::from_env_with(|key| {
+            (key == "IPCPRIMS_FORMAT").then(|| "yaml".to_string())
+        })
+        .expect_err("invalid env should fail");
+        assert_eq!(err.code, USAGE);
+        assert!(err.message.contains("IPCPRIMS_FORMAT"));
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::io;
 
-use ipcprims_frame::FrameError;
+use ipcprims_frame::{FrameError, FrameErrorCode};
 use ipcprims_peer::PeerError;
 use ipcprims_transport::TransportError;
 
@@ -9,11 +9,13 @@ use ipcprims_transport::TransportError;
 pub const SUCCESS: i32 = 0;
 pub const FAILURE: i32 = 1;
 pub const TRANSPORT_ERROR: i32 = 3;
-#[allow(dead_code)]
 pub const HEALTH_CHECK_FAILED: i32 = 30;
 pub const PERMISSION_DENIED: i32 = 50;
 pub const DATA_INVALID: i32 = 60;
 pub const USAGE: i32 = 64;
+/// Handshake refused: the server speaks an incompatible protocol or version,
+/// or negotiated channels we did not ask for (ipcprims-specific).
+pub const PROTOCOL_MISMATCH: i32 = 65;
 pub const TIMEOUT: i32 = 124;
 pub const INTERNAL: i32 = 125;
 
@@ -43,13 +45,20 @@ impl fmt::Display for CliError {
 impl std::error::Error for CliError {}
 
 pub fn io_error(context: &str, err: io::Error) -> CliError {
-    let code = match err.kind() {
+    CliError::new(io_error_code(&err), format!("{context}: {err}"))
+}
+
+fn io_error_code(err: &io::Error) -> i32 {
+    match err.kind() {
         io::ErrorKind::PermissionDenied => PERMISSION_DENIED,
         io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => TIMEOUT,
-        io::ErrorKind::ConnectionRefused => FAILURE,
+        // A remote hang-up is the same outcome as FrameError::ConnectionClosed.
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted => FAILURE,
         _ => INTERNAL,
-    };
-    CliError::new(code, format!("{context}: {err}"))
+    }
 }
 
 pub fn transport_error(context: &str, err: TransportError) -> CliError {
@@ -58,18 +67,65 @@ pub fn transport_error(context: &str, err: TransportError) -> CliError {
         | TransportError::Connect { source, .. }
         | TransportError::Accept(source)
         | TransportError::Io(source) => io_error(context, source),
+        TransportError::ConnectTimeout { .. } => {
+            CliError::new(TIMEOUT, format!("{context}: {err}"))
+        }
+        // A socket path or address that can never work is a caller mistake, not a transport fault.
+        TransportError::PathTooLong { .. } | TransportError::NonLoopback { .. } => {
+            CliError::new(USAGE, format!("{context}: {err}"))
+        }
+        // Nothing listening at the endpoint is a transport failure, not a bug.
+        TransportError::NotFound { .. } => {
+            CliError::new(TRANSPORT_ERROR, format!("{context}: {err}"))
+        }
+        // A remote hang-up is the same outcome as FrameError::ConnectionClosed.
+        TransportError::ConnectionReset | TransportError::BrokenPipe => {
+            CliError::new(FAILURE, format!("{context}: {err}"))
+        }
+        TransportError::PermissionDenied { .. } | TransportError::UntrustedEndpoint { .. } => {
+            CliError::new(PERMISSION_DENIED, format!("{context}: {err}"))
+        }
+        TransportError::Chown { ref source, .. } => {
+            let code = match source.kind() {
+                // No such group (or an unusable name) is a caller mistake.
+                io::ErrorKind::NotFound | io::ErrorKind::InvalidInput => USAGE,
+                io::ErrorKind::PermissionDenied => PERMISSION_DENIED,
+                _ => TRANSPORT_ERROR,
+            };
+            CliError::new(code, format!("{context}: {err}"))
+        }
         other => CliError::new(TRANSPORT_ERROR, format!("{context}: {other}")),
     }
 }
 
 pub fn frame_error(context: &str, err: FrameError) -> CliError {
-    match err {
-        FrameError::Io(source) => io_error(context, source),
-        FrameError::PayloadTooLarge { .. } => {
-            CliError::new(DATA_INVALID, format!("{context}: {err}"))
-        }
-        FrameError::ConnectionClosed => CliError::new(FAILURE, format!("{context}: {err}")),
-        other => CliError::new(INTERNAL, format!("{context}: {other}")),
+    CliError::new(frame_error_code(&err), format!("{context}: {err}"))
+}
+
+/// Exit code for a frame error; [`DATA_INVALID`] means the remote sent bytes
+/// that are not a valid frame stream.
+pub fn frame_error_code(err: &FrameError) -> i32 {
+    if let FrameError::Io(source) = err {
+        return io_error_code(source);
+    }
+    match err.code() {
+        FrameErrorCode::InvalidMagic
+        | FrameErrorCode::PayloadTooLarge
+        | FrameErrorCode::Truncated
+        | FrameErrorCode::ChecksumMismatch
+        | FrameErrorCode::Compression
+        | FrameErrorCode::Fragmentation
+        | FrameErrorCode::SequenceGap
+        | FrameErrorCode::UnsupportedFlags
+        | FrameErrorCode::UnsupportedVersion
+        | FrameErrorCode::BufferLimitExceeded
+        | FrameErrorCode::TrailingBytes
+        | FrameErrorCode::Json
+        | FrameErrorCode::PayloadTransform => DATA_INVALID,
+        FrameErrorCode::ConnectionClosed | FrameErrorCode::Poisoned => FAILURE,
+        FrameErrorCode::Timeout => TIMEOUT,
+        FrameErrorCode::InvalidConfig => USAGE,
+        _ => INTERNAL,
     }
 }
 
@@ -78,10 +134,17 @@ pub fn peer_error(context: &str, err: PeerError) -> CliError {
         PeerError::Transport(err) => transport_error(context, err),
         PeerError::Frame(err) => frame_error(context, err),
         PeerError::Schema(err) => CliError::new(DATA_INVALID, format!("{context}: {err}")),
-        PeerError::Timeout(_) => CliError::new(TIMEOUT, format!("{context}: {err}")),
+        PeerError::Timeout { .. } => CliError::new(TIMEOUT, format!("{context}: {err}")),
         PeerError::Json(err) => CliError::new(DATA_INVALID, format!("{context}: {err}")),
+        PeerError::Desynchronized(_) => CliError::new(DATA_INVALID, format!("{context}: {err}")),
         PeerError::UnsupportedChannel(_) => CliError::new(USAGE, format!("{context}: {err}")),
         PeerError::Disconnected(_) => CliError::new(FAILURE, format!("{context}: {err}")),
+        PeerError::TransactionFailed { index, source } => {
+            peer_error(&format!("{context}: transaction frame {index}"), *source)
+        }
+        PeerError::HandshakeFailed(_) | PeerError::AuthRejected(_) => {
+            CliError::new(PROTOCOL_MISMATCH, format!("{context}: {err}"))
+        }
         other => CliError::new(INTERNAL, format!("{context}: {other}")),
     }
 }
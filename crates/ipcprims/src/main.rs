@@ -1,11 +1,14 @@
 mod cmd;
+mod config;
 mod exit;
 mod logging;
 mod output;
+mod units;
 
 use clap::Parser;
 
 use crate::cmd::Command;
+use crate::config::ConfigLayers;
 use crate::logging::{init_logging, LogFormat, LogLevel};
 use crate::output::OutputFormat;
 
@@ -16,13 +19,17 @@ struct Cli {
     #[arg(long, value_name = "FORMAT", global = true)]
     format: Option<OutputFormat>,
 
-    /// Log output format (stderr).
-    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
-    log_format: LogFormat,
+    /// Log output format (stderr). Default: text.
+    #[arg(long, value_name = "FORMAT", global = true)]
+    log_format: Option<LogFormat>,
+
+    /// Minimum log level (stderr). Default: info.
+    #[arg(long, value_name = "LEVEL", global = true)]
+    log_level: Option<LogLevel>,
 
-    /// Minimum log level (stderr).
-    #[arg(long, value_name = "LEVEL", default_value = "info", global = true)]
-    log_level: LogLevel,
+    /// Ignore the config file (~/.config/ipcprims/config.toml or $IPCPRIMS_CONFIG).
+    #[arg(long, global = true)]
+    no_config: bool,
 
     #[command(subcommand)]
     command: Command,
@@ -30,10 +37,17 @@ struct Cli {
 
 fn main() {
     let cli = Cli::parse();
-    init_logging(cli.log_format, cli.log_level);
+    let result = ConfigLayers::load(cli.no_config).and_then(|layers| {
+        init_logging(
+            layers.log_format(cli.log_format),
+            layers.log_level(cli.log_level),
+        );
 
-    let format = cli.format.unwrap_or_else(OutputFormat::default_for_stdout);
-    let result = cmd::run(cli.command, format);
+        let format = layers.format(cli.format);
+        let mut command = cli.command;
+        command.apply_config(&layers);
+        cmd::run(command, format)
+    });
 
     match result {
         Ok(code) => std::process::exit(code),
@@ -86,4 +100,15 @@ mod tests {
             .expect("info args should parse");
         assert!(matches!(cli.command, Command::Info(_)));
     }
+
+    #[test]
+    fn socket_path_is_optional_for_config_defaults() {
+        let cli = Cli::try_parse_from(["ipcprims", "--no-config", "listen"])
+            .expect("listen without path should parse");
+        assert!(cli.no_config);
+        match cli.command {
+            Command::Listen(args) => assert!(args.path.is_none()),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
 }
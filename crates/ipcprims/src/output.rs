@@ -1,9 +1,13 @@
 use std::io::{IsTerminal, Write};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use base64::Engine;
 use clap::ValueEnum;
 use comfy_table::{presets::UTF8_FULL, ContentArrangement, Table};
-use ipcprims_frame::{Frame, COMMAND, CONTROL, DATA, ERROR, TELEMETRY};
+use ipcprims_frame::channel::channel_name;
+use ipcprims_frame::Frame;
+use ipcprims_peer::ListenerMetrics;
+use ipcprims_transport::{IpcAddr, PeerCredentials};
 use serde::Serialize;
 
 #[derive(Clone, Debug, Copy, ValueEnum)]
@@ -24,27 +28,56 @@ impl OutputFormat {
     }
 }
 
+pub const FRAME_RECEIVED_SCHEMA_ID: &str =
+    "https://schemas.3leaps.dev/ipcprims/cli/v2/frame-received.schema.json";
+
 #[derive(Serialize)]
 struct FrameOutput<'a> {
     schema_id: &'a str,
     channel: u16,
     channel_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequence: Option<u32>,
     payload_size: usize,
+    payload_encoding: &'static str,
     payload: String,
     peer_id: &'a str,
+    peer_credentials: Option<PeerCredentials>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote_addr: Option<&'a IpcAddr>,
     timestamp: String,
 }
 
-pub fn print_frame(frame: &Frame, peer_id: &str, format: OutputFormat) {
+/// Options controlling how frames are rendered.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FramePrintOptions {
+    /// Base64-encode JSON payloads even when they are valid UTF-8.
+    pub force_base64: bool,
+}
+
+pub fn print_frame(
+    frame: &Frame,
+    peer_id: &str,
+    peer_credentials: Option<PeerCredentials>,
+    remote_addr: Option<&IpcAddr>,
+    format: OutputFormat,
+    options: FramePrintOptions,
+) {
     match format {
         OutputFormat::Json => {
+            let (payload_encoding, payload) =
+                encode_payload(frame.payload.as_ref(), options.force_base64);
             let out = FrameOutput {
-                schema_id: "https://schemas.3leaps.dev/ipcprims/cli/v1/frame-received.schema.json",
+                schema_id: FRAME_RECEIVED_SCHEMA_ID,
                 channel: frame.channel,
                 channel_name: channel_name(frame.channel),
+                sequence: frame.sequence,
                 payload_size: frame.payload.len(),
-                payload: payload_preview(frame.payload.as_ref()),
+                payload_encoding,
+                payload,
                 peer_id,
+                peer_credentials,
+                remote_addr,
                 timestamp: now_unix_seconds(),
             };
             println!(
@@ -61,18 +94,23 @@ pub fn print_frame(frame: &Frame, peer_id: &str, format: OutputFormat) {
                 .add_row(vec![
                     channel_name(frame.channel).to_string(),
                     frame.payload.len().to_string(),
-                    peer_id.to_string(),
+                    peer_label(peer_id, peer_credentials),
                     payload_preview(frame.payload.as_ref()),
                 ]);
             println!("{table}");
         }
         OutputFormat::Pretty => {
+            let sequence = frame
+                .sequence
+                .map(|sequence| format!(" seq={sequence}"))
+                .unwrap_or_default();
             println!(
-                "channel={} ({}) size={} peer={} payload={}",
+                "channel={} ({}){} size={} peer={} payload={}",
                 frame.channel,
                 channel_name(frame.channel),
+                sequence,
                 frame.payload.len(),
-                peer_id,
+                peer_label(peer_id, peer_credentials),
                 payload_preview(frame.payload.as_ref())
             );
         }
@@ -82,20 +120,69 @@ pub fn print_frame(frame: &Frame, peer_id: &str, format: OutputFormat) {
     }
 }
 
+fn peer_label(peer_id: &str, credentials: Option<PeerCredentials>) -> String {
+    match credentials {
+        Some(creds) => format!("{peer_id} ({creds})"),
+        None => peer_id.to_string(),
+    }
+}
+
+/// Print `--stats` output to stderr, keeping stdout for frames.
+pub fn print_listener_metrics(metrics: &ListenerMetrics, format: OutputFormat) {
+    let mut err = std::io::stderr().lock();
+    if let OutputFormat::Json = format {
+        let _ = writeln!(
+            err,
+            "{}",
+            serde_json::to_string(metrics).unwrap_or_else(|_| "{}".to_string())
+        );
+        return;
+    }
+
+    let by_kind = metrics
+        .failures_by_kind
+        .iter()
+        .map(|(kind, count)| format!("{kind} {count}"))
+        .collect::<Vec<_>>();
+    let last_accept = match metrics.last_accept_at {
+        Some(at) => {
+            let ago = SystemTime::now().duration_since(at).unwrap_or_default();
+            format!("{:.1}s ago", ago.as_secs_f64())
+        }
+        None => "never".to_string(),
+    };
+    let _ = writeln!(err, "Listener stats:");
+    let _ = writeln!(err, "  Accepted:           {}", metrics.accepted);
+    if by_kind.is_empty() {
+        let _ = writeln!(err, "  Handshake failures: {}", metrics.handshake_failures);
+    } else {
+        let _ = writeln!(
+            err,
+            "  Handshake failures: {} ({})",
+            metrics.handshake_failures,
+            by_kind.join(", ")
+        );
+    }
+    let _ = writeln!(err, "  Last accept:        {last_accept}");
+}
+
 pub fn print_raw(data: &[u8]) {
     let mut out = std::io::stdout();
     let _ = out.write_all(data);
     let _ = out.flush();
 }
 
-pub fn channel_name(channel: u16) -> &'static str {
-    match channel {
-        CONTROL => "CONTROL",
-        COMMAND => "COMMAND",
-        DATA => "DATA",
-        TELEMETRY => "TELEMETRY",
-        ERROR => "ERROR",
-        _ => "USER",
+/// Encode a payload for JSON output, returning `(encoding, text)`.
+///
+/// Valid UTF-8 is emitted verbatim unless `force_base64` is set; anything else
+/// is base64 (standard alphabet, padded) so consumers can recover the bytes.
+fn encode_payload(payload: &[u8], force_base64: bool) -> (&'static str, String) {
+    match std::str::from_utf8(payload) {
+        Ok(text) if !force_base64 => ("utf8", text.to_string()),
+        _ => (
+            "base64",
+            base64::engine::general_purpose::STANDARD.encode(payload),
+        ),
     }
 }
 
@@ -112,3 +199,53 @@ fn now_unix_seconds() -> String {
         .map(|d| d.as_secs().to_string())
         .unwrap_or_else(|_| "0".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_payload_is_emitted_verbatim() {
+        assert_eq!(
+            encode_payload(b"hello", false),
+            ("utf8", "hello".to_string())
+        );
+    }
+
+    #[test]
+    fn binary_payload_is_base64() {
+        let payload = [0xff, 0xfe, 0x00, 0x41];
+        let (encoding, text) = encode_payload(&payload, false);
+        assert_eq!(encoding, "base64");
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .expect("payload should decode");
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn force_base64_encodes_text() {
+        assert_eq!(encode_payload(b"hi", true), ("base64", "aGk=".to_string()));
+    }
+
+    #[test]
+    fn peer_label_includes_credentials_when_known() {
+        assert_eq!(peer_label("peer-1", None), "peer-1");
+        assert_eq!(
+            peer_label(
+                "peer-1",
+                Some(PeerCredentials {
+                    uid: 1000,
+                    gid: 100,
+                    pid: Some(42),
+                })
+            ),
+            "peer-1 (uid=1000 gid=100 pid=42)"
+        );
+    }
+
+    #[test]
+    fn preview_keeps_binary_placeholder() {
+        assert_eq!(payload_preview(&[0xff, 0x00]), "<binary 2 bytes>");
+    }
+}
@@ -0,0 +1,79 @@
+//! Parsing for human-friendly size flags.
+
+use crate::exit::{CliError, CliResult, USAGE};
+
+/// Parse a byte size such as `4096`, `64k`, `1m`, or `2GiB`.
+///
+/// Suffixes are binary multiples (`k` = 1024) and case-insensitive; an
+/// optional trailing `b`/`ib` is accepted (`64kb`, `64KiB`).
+pub fn parse_size(input: &str) -> CliResult<usize> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(CliError::new(USAGE, "size must not be empty"));
+    }
+
+    let lower = trimmed.to_ascii_lowercase();
+    let unit_start = lower
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(unit_start);
+
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        _ => {
+            return Err(CliError::new(
+                USAGE,
+                format!("invalid size '{trimmed}': unknown unit '{unit}' (use k, m, or g)"),
+            ))
+        }
+    };
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| CliError::new(USAGE, format!("invalid size '{trimmed}'")))?;
+    if value == 0 {
+        return Err(CliError::new(USAGE, "size must be greater than zero"));
+    }
+
+    value
+        .checked_mul(multiplier)
+        .and_then(|bytes| usize::try_from(bytes).ok())
+        .ok_or_else(|| CliError::new(USAGE, format!("size '{trimmed}' is too large")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_bytes() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size(" 12b ").unwrap(), 12);
+    }
+
+    #[test]
+    fn binary_suffixes_are_case_insensitive() {
+        assert_eq!(parse_size("64k").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("64KB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("64KiB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("1m").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        for input in ["", "k", "0", "0k", "1.5m", "10x", "-1", "1 k"] {
+            let err = parse_size(input).expect_err(input);
+            assert_eq!(err.code, USAGE, "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(parse_size("18446744073709551616").is_err());
+        assert!(parse_size("17179869184g").is_err());
+    }
+}
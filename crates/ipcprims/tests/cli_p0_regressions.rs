@@ -2,12 +2,12 @@
 
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use ipcprims_frame::{COMMAND, ERROR};
-use ipcprims_peer::connect;
+use ipcprims_peer::{connect_with_retry, HandshakeConfig, RetryPolicy};
 
 #[cfg(unix)]
 fn unique_ipc_dir(tag: &str) -> PathBuf {
@@ -60,17 +60,26 @@ fn wait_for_connect(
     channels: &[u16],
     timeout: Duration,
 ) -> io::Result<ipcprims_peer::Peer> {
+    let policy = RetryPolicy {
+        max_duration: timeout,
+        ..RetryPolicy::default()
+    };
+    connect_with_retry(path, channels, &HandshakeConfig::default(), &policy, None)
+        .map_err(|err| io::Error::other(format!("connect timeout: {err}")))
+}
+
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> ExitStatus {
     let start = Instant::now();
     loop {
-        match connect(path, channels) {
-            Ok(peer) => return Ok(peer),
-            Err(err) => {
-                if start.elapsed() >= timeout {
-                    return Err(io::Error::other(format!("connect timeout: {err}")));
-                }
-                thread::sleep(Duration::from_millis(25));
-            }
+        if let Some(status) = child.try_wait().expect("child status should be readable") {
+            return status;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            panic!("child did not exit within {timeout:?}");
         }
+        thread::sleep(Duration::from_millis(25));
     }
 }
 
@@ -100,6 +109,7 @@ fn echo_validate_returns_error_and_continues_session() {
         .arg(&sock_path)
         .arg("--validate")
         .arg(&schema_dir)
+        .arg("--once")
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
         .spawn()
@@ -126,7 +136,866 @@ fn echo_validate_returns_error_and_continues_session() {
         .expect("echo should continue after invalid frame");
     assert_eq!(echoed.payload.as_ref(), br#"{"ok":true}"#);
 
+    drop(peer);
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn echo_exit_on_disconnect_exits_after_client_leaves() {
+    let dir = unique_ipc_dir("echo-exit-on-disconnect");
+    let sock_path = unique_ipc_path(&dir, "echo");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--exit-on-disconnect")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let mut peer = wait_for_connect(&sock_path, &[COMMAND], Duration::from_secs(5))
+        .expect("client should connect to echo server");
+    peer.send(COMMAND, b"hello").expect("frame should send");
+    let echoed = peer.recv_on(COMMAND).expect("frame should be echoed");
+    assert_eq!(echoed.payload.as_ref(), b"hello");
+    drop(peer);
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn echo_and_send_rendezvous_on_an_abstract_name() {
+    let address = format!("@ipcprims-cli-abstract-{}", std::process::id());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&address)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let start = Instant::now();
+    let output = loop {
+        let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("send")
+            .arg(&address)
+            .arg("--data")
+            .arg("over-abstract")
+            .arg("--wait")
+            .output()
+            .expect("send should run");
+        if output.status.success() || start.elapsed() >= Duration::from_secs(5) {
+            break output;
+        }
+        thread::sleep(Duration::from_millis(25));
+    };
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("over-abstract"));
+    assert!(!Path::new(&address).exists());
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn echo_and_send_rendezvous_over_loopback_tcp() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("a free loopback port should exist")
+        .port();
+    let address = format!("tcp://127.0.0.1:{port}");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&address)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let start = Instant::now();
+    let output = loop {
+        let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("send")
+            .arg(&address)
+            .arg("--data")
+            .arg("over-tcp")
+            .arg("--wait")
+            .output()
+            .expect("send should run");
+        if output.status.success() || start.elapsed() >= Duration::from_secs(5) {
+            break output;
+        }
+        thread::sleep(Duration::from_millis(25));
+    };
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("over-tcp"));
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn listen_refuses_a_non_loopback_tcp_address() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("listen")
+        .arg("tcp://0.0.0.0:0")
+        .output()
+        .expect("listen should run");
+    assert_eq!(output.status.code(), Some(64));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("non-loopback"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn send_wait_times_out_against_delayed_echo() {
+    let dir = unique_ipc_dir("echo-delay");
+    let sock_path = unique_ipc_path(&dir, "echo");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--delay")
+        .arg("1s")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    drop(
+        wait_for_connect(&sock_path, &[COMMAND], Duration::from_secs(5))
+            .expect("echo server should accept connections"),
+    );
+
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("send")
+        .arg(&sock_path)
+        .arg("--data")
+        .arg("slow")
+        .arg("--wait")
+        .arg("--wait-timeout")
+        .arg("200ms")
+        .output()
+        .expect("send should run");
+
+    assert_eq!(output.status.code(), Some(124));
+    assert!(start.elapsed() < Duration::from_secs(3));
+
     let _ = child.kill();
     let _ = child.wait();
     let _ = std::fs::remove_dir_all(&dir);
 }
+
+#[test]
+fn send_validate_dir_rejects_invalid_payload_before_connecting() {
+    let dir = unique_ipc_dir("send-validate");
+    // Never bound: validation must fail before any connect attempt.
+    let sock_path = unique_ipc_path(&dir, "absent");
+    let schema_dir = dir.join("schemas");
+    std::fs::create_dir_all(&schema_dir).expect("schema dir should be creatable");
+    std::fs::write(
+        schema_dir.join("command.schema.json"),
+        r#"{
+            "type": "object",
+            "properties": {
+                "ok": { "type": "boolean" }
+            },
+            "required": ["ok"]
+        }"#,
+    )
+    .expect("schema file should be writable");
+
+    let send = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("--format")
+            .arg("json")
+            .arg("send")
+            .arg(&sock_path)
+            .arg("--validate-dir")
+            .arg(&schema_dir)
+            .args(extra)
+            .output()
+            .expect("send should run")
+    };
+
+    let invalid = send(&["--json", r#"{"ok":"yes"}"#]);
+    assert_eq!(invalid.status.code(), Some(60));
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    let report: serde_json::Value = serde_json::from_str(
+        stderr
+            .lines()
+            .find(|line| line.starts_with('{'))
+            .expect("stderr should contain a json issue report"),
+    )
+    .expect("issue report should be json");
+    assert_eq!(report["issues"][0]["instance_path"], "/ok");
+
+    let not_json = send(&["--data", "plain text"]);
+    assert_eq!(not_json.status.code(), Some(60));
+    assert!(String::from_utf8_lossy(&not_json.stderr).contains("--data is not valid JSON"));
+
+    let missing = send(&["--channel", "2", "--json", "{}", "--strict-schemas"]);
+    assert_eq!(missing.status.code(), Some(60));
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("no schema for channel 2"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn send_validates_against_schema_dir_from_config() {
+    let dir = unique_ipc_dir("send-config-schemas");
+    // Never bound: validation must fail before any connect attempt.
+    let sock_path = unique_ipc_path(&dir, "absent");
+    let schema_dir = dir.join("schemas");
+    std::fs::create_dir_all(&schema_dir).expect("schema dir should be creatable");
+    std::fs::write(
+        schema_dir.join("command.schema.json"),
+        r#"{"type": "object", "required": ["ok"]}"#,
+    )
+    .expect("schema file should be writable");
+    let config_path = dir.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!("schema_dir = {:?}\n", schema_dir.display().to_string()),
+    )
+    .expect("config should be writable");
+
+    let send = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .env("IPCPRIMS_CONFIG", &config_path)
+            .env_remove("IPCPRIMS_SCHEMA_DIR")
+            .arg("send")
+            .arg(&sock_path)
+            .args(extra)
+            .output()
+            .expect("send should run")
+    };
+
+    let invalid = send(&["--json", "{}"]);
+    assert_eq!(invalid.status.code(), Some(60));
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("schema validation"));
+
+    let missing = send(&["--channel", "2", "--json", "{}", "--strict-schemas"]);
+    assert_eq!(missing.status.code(), Some(60));
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("no schema for channel 2"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn listen_interleaves_concurrent_clients_and_honors_count() {
+    use std::io::Read;
+
+    let dir = unique_ipc_dir("listen-concurrent");
+    let sock_path = unique_ipc_path(&dir, "listen");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("--format")
+        .arg("json")
+        .arg("listen")
+        .arg(&sock_path)
+        .arg("--count")
+        .arg("4")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("listen command should start");
+
+    // Both clients stay connected until listen exits, so a serial listener
+    // would never see the second client's frames.
+    let mut first = wait_for_connect(&sock_path, &[1], Duration::from_secs(5))
+        .expect("first client should connect");
+    let mut second = wait_for_connect(&sock_path, &[1], Duration::from_secs(5))
+        .expect("second client should connect");
+    for i in 0..2 {
+        first
+            .send(1, format!("{{\"client\":1,\"seq\":{i}}}").as_bytes())
+            .expect("first client send should succeed");
+        second
+            .send(1, format!("{{\"client\":2,\"seq\":{i}}}").as_bytes())
+            .expect("second client send should succeed");
+    }
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout should be piped")
+        .read_to_string(&mut stdout)
+        .expect("stdout should be readable");
+    let frames: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be one json frame"))
+        .collect();
+    assert_eq!(frames.len(), 4);
+
+    let mut peers: Vec<&str> = frames
+        .iter()
+        .map(|f| f["peer_id"].as_str().expect("peer_id should be a string"))
+        .collect();
+    peers.sort_unstable();
+    peers.dedup();
+    assert_eq!(
+        peers.len(),
+        2,
+        "frames should be attributed to both clients"
+    );
+
+    #[cfg(unix)]
+    assert!(!sock_path.exists(), "listener socket should be cleaned up");
+
+    drop((first, second));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn send_connect_timeout_names_handshake_phase() {
+    let dir = unique_ipc_dir("send-connect-timeout");
+    let sock_path = unique_ipc_path(&dir, "wedged");
+    // Bound but never accepted, like a wedged server.
+    let socket =
+        ipcprims_transport::UnixDomainSocket::bind(&sock_path).expect("socket should bind");
+
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("send")
+        .arg(&sock_path)
+        .arg("--data")
+        .arg("hello")
+        .arg("--connect-timeout")
+        .arg("300ms")
+        .output()
+        .expect("send should run");
+
+    assert_eq!(output.status.code(), Some(124));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("handshake read timed out"));
+
+    drop(socket);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn listen_allow_uid_filters_connections_and_reports_credentials() {
+    use std::io::Read;
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = unique_ipc_dir("listen-allow-uid");
+    let my_uid = std::fs::metadata(&dir)
+        .expect("temp dir should have metadata")
+        .uid();
+
+    let spawn_listen = |sock: &Path, allow_uid: u32| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("--log-level")
+            .arg("warn")
+            .arg("--format")
+            .arg("json")
+            .arg("listen")
+            .arg(sock)
+            .arg("--count")
+            .arg("1")
+            .arg("--allow-uid")
+            .arg(allow_uid.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("listen command should start")
+    };
+    let read_pipe = |pipe: Option<&mut dyn Read>| {
+        let mut out = String::new();
+        pipe.expect("pipe should exist")
+            .read_to_string(&mut out)
+            .expect("pipe should be readable");
+        out
+    };
+
+    // Allowed: the frame is printed with our credentials.
+    let allowed_sock = unique_ipc_path(&dir, "allowed");
+    let mut allowed = spawn_listen(&allowed_sock, my_uid);
+    let mut client = wait_for_connect(&allowed_sock, &[1], Duration::from_secs(5))
+        .expect("client should connect");
+    client.send(1, b"{}").expect("send should succeed");
+    assert_eq!(
+        wait_for_exit(&mut allowed, Duration::from_secs(5)).code(),
+        Some(0)
+    );
+    let stdout = read_pipe(allowed.stdout.as_mut().map(|p| p as &mut dyn Read));
+    let frame: serde_json::Value =
+        serde_json::from_str(stdout.trim()).expect("listen should print one json frame");
+    assert_eq!(frame["peer_credentials"]["uid"], my_uid);
+    assert_eq!(frame["peer_credentials"]["pid"], std::process::id());
+    // The client never bound its socket, so the listener sees no address.
+    assert_eq!(frame["remote_addr"], "unnamed");
+    drop(client);
+
+    // Not allowed: the connection is dropped and nothing is printed.
+    let denied_sock = unique_ipc_path(&dir, "denied");
+    let mut denied = spawn_listen(&denied_sock, my_uid.wrapping_add(1));
+    let mut client = wait_for_connect(&denied_sock, &[1], Duration::from_secs(5))
+        .expect("handshake completes before the uid check");
+    let _ = client.send(1, b"{}");
+    thread::sleep(Duration::from_millis(300));
+    let _ = denied.kill();
+    let _ = denied.wait();
+    let stdout = read_pipe(denied.stdout.as_mut().map(|p| p as &mut dyn Read));
+    let stderr = read_pipe(denied.stderr.as_mut().map(|p| p as &mut dyn Read));
+    assert!(
+        stdout.trim().is_empty(),
+        "denied frame was printed: {stdout}"
+    );
+    assert!(stderr.contains("not in --allow-uid"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn listen_exit_codes_distinguish_decode_errors_from_clean_disconnect() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let dir = unique_ipc_dir("listen-decode");
+    let spawn_listen = |sock: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("--log-level")
+            .arg("error")
+            .arg("listen")
+            .arg(sock)
+            .arg("--exit-on-disconnect")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("listen command should start")
+    };
+    let wait_for_socket = |sock: &Path| {
+        let start = Instant::now();
+        while !sock.exists() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "listener never bound"
+            );
+            thread::sleep(Duration::from_millis(25));
+        }
+    };
+    let stderr_of = |child: &mut Child| {
+        let mut out = String::new();
+        child
+            .stderr
+            .take()
+            .expect("stderr should be piped")
+            .read_to_string(&mut out)
+            .expect("stderr should be readable");
+        out
+    };
+
+    // Producer finished: clean disconnect exits 0.
+    let clean_sock = unique_ipc_path(&dir, "clean");
+    let mut clean = spawn_listen(&clean_sock);
+    let mut client =
+        wait_for_connect(&clean_sock, &[1], Duration::from_secs(5)).expect("client should connect");
+    client.send(1, b"{}").expect("send should succeed");
+    drop(client);
+    assert_eq!(
+        wait_for_exit(&mut clean, Duration::from_secs(5)).code(),
+        Some(0)
+    );
+
+    // Garbage bytes: invalid magic exits 60 with a per-connection line.
+    let garbage_sock = unique_ipc_path(&dir, "garbage");
+    let mut garbage = spawn_listen(&garbage_sock);
+    wait_for_socket(&garbage_sock);
+    let mut raw = UnixStream::connect(&garbage_sock).expect("raw connect should succeed");
+    raw.write_all(b"this is not an ipcprims frame")
+        .expect("garbage should write");
+    drop(raw);
+    assert_eq!(
+        wait_for_exit(&mut garbage, Duration::from_secs(5)).code(),
+        Some(60)
+    );
+    let stderr = stderr_of(&mut garbage);
+    assert!(stderr.contains("frame decode failed"), "stderr: {stderr}");
+    assert!(stderr.contains("invalid frame magic"), "stderr: {stderr}");
+
+    // Producer crashed mid-frame: a cut-off header/payload also exits 60.
+    let truncated_sock = unique_ipc_path(&dir, "truncated");
+    let mut truncated = spawn_listen(&truncated_sock);
+    wait_for_socket(&truncated_sock);
+    let mut raw = UnixStream::connect(&truncated_sock).expect("raw connect should succeed");
+    let mut partial = b"IP".to_vec();
+    partial.extend_from_slice(&64u32.to_le_bytes());
+    partial.extend_from_slice(&0u16.to_le_bytes());
+    partial.extend_from_slice(b"cut short");
+    raw.write_all(&partial).expect("partial frame should write");
+    drop(raw);
+    assert_eq!(
+        wait_for_exit(&mut truncated, Duration::from_secs(5)).code(),
+        Some(60)
+    );
+    assert!(stderr_of(&mut truncated).contains("mid-frame"));
+
+    // A checksummed frame whose CRC does not match its payload exits 60 too.
+    let corrupt_sock = unique_ipc_path(&dir, "corrupt");
+    let mut corrupt = spawn_listen(&corrupt_sock);
+    wait_for_socket(&corrupt_sock);
+    let mut raw = UnixStream::connect(&corrupt_sock).expect("raw connect should succeed");
+    let payload = b"checksummed";
+    let mut frame = ipcprims_frame::VERSIONED_MAGIC.to_vec();
+    frame.extend_from_slice(&[2, ipcprims_frame::FLAG_CHECKSUM]);
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&0u16.to_le_bytes());
+    frame.extend_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+    frame.extend_from_slice(payload);
+    raw.write_all(&frame).expect("corrupt frame should write");
+    drop(raw);
+    assert_eq!(
+        wait_for_exit(&mut corrupt, Duration::from_secs(5)).code(),
+        Some(60)
+    );
+    let stderr = stderr_of(&mut corrupt);
+    assert!(stderr.contains("checksum mismatch"), "stderr: {stderr}");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn send_expect_responses_streams_each_frame_and_times_out_when_short() {
+    let dir = unique_ipc_dir("send-expect-responses");
+    let sock_path = unique_ipc_path(&dir, "multi");
+    let listener = ipcprims_peer::PeerListener::bind(&sock_path).expect("listener should bind");
+
+    // Each request gets a result plus a completion marker, then the server
+    // waits for the client to hang up.
+    let server = thread::spawn(move || {
+        for _ in 0..2 {
+            let mut peer = listener.accept().expect("client should connect");
+            let request = peer.recv().expect("request should arrive");
+            peer.send(request.channel, br#"{"result":42}"#)
+                .expect("result should send");
+            peer.send(request.channel, br#"{"done":true}"#)
+                .expect("marker should send");
+            let _ = peer.recv();
+        }
+    });
+
+    let send = |expect: &str| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("--format")
+            .arg("json")
+            .arg("send")
+            .arg(&sock_path)
+            .arg("--data")
+            .arg("go")
+            .arg("--expect-responses")
+            .arg(expect)
+            .arg("--wait-timeout")
+            .arg("500ms")
+            .output()
+            .expect("send should run")
+    };
+
+    let enough = send("2");
+    assert_eq!(
+        enough.status.code(),
+        Some(0),
+        "stderr: {}",
+        String::from_utf8_lossy(&enough.stderr)
+    );
+    let frames: Vec<serde_json::Value> = String::from_utf8_lossy(&enough.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be one json frame"))
+        .collect();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0]["payload"], r#"{"result":42}"#);
+    assert_eq!(frames[1]["payload"], r#"{"done":true}"#);
+
+    let short = send("3");
+    assert_eq!(short.status.code(), Some(124));
+    assert_eq!(String::from_utf8_lossy(&short.stdout).lines().count(), 2);
+    assert!(String::from_utf8_lossy(&short.stderr).contains("received 2 of 3"));
+
+    server.join().expect("server thread should finish");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn handshake_rejected_by_newer_server_is_protocol_mismatch() {
+    use ipcprims_frame::{FrameReader, FrameWriter, CONTROL};
+    use ipcprims_peer::{HandshakeRequest, HandshakeResponse};
+
+    let dir = unique_ipc_dir("protocol-mismatch");
+    let sock_path = unique_ipc_path(&dir, "v9");
+    let socket =
+        ipcprims_transport::UnixDomainSocket::bind(&sock_path).expect("socket should bind");
+
+    // A server on protocol 9.0 answers every handshake with its own version.
+    let server = thread::spawn(move || {
+        for _ in 0..2 {
+            let stream = socket.accept().expect("client should connect");
+            let mut reader = FrameReader::new(stream.try_clone().expect("stream should clone"));
+            let mut writer = FrameWriter::new(stream);
+            let frame = reader
+                .read_frame()
+                .expect("handshake request should arrive");
+            let request: HandshakeRequest =
+                serde_json::from_slice(&frame.payload).expect("request should parse");
+            let response = HandshakeResponse {
+                protocol: request.protocol,
+                version: "9.0".to_string(),
+                channels: request.channels,
+                peer_id: "v9-server".to_string(),
+                library_version: None,
+                frame_priority: false,
+                frame_checksum: false,
+                wire_version: 1,
+            };
+            writer
+                .send(
+                    CONTROL,
+                    &serde_json::to_vec(&response).expect("response should encode"),
+                )
+                .expect("response should send");
+            let _ = reader.read_frame();
+        }
+    });
+
+    let send = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("send")
+        .arg(&sock_path)
+        .arg("--data")
+        .arg("hello")
+        .output()
+        .expect("send should run");
+    let stderr = String::from_utf8_lossy(&send.stderr);
+    assert_eq!(send.status.code(), Some(65), "stderr: {stderr}");
+    assert!(
+        stderr.contains("incompatible version '9.0'"),
+        "stderr: {stderr}"
+    );
+
+    let info = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("info")
+        .arg(&sock_path)
+        .output()
+        .expect("info should run");
+    assert_eq!(
+        info.status.code(),
+        Some(65),
+        "stderr: {}",
+        String::from_utf8_lossy(&info.stderr)
+    );
+
+    server.join().expect("server thread should finish");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+fn send_signal(child: &Child, signal: &str) {
+    let status = Command::new("kill")
+        .arg(format!("-{signal}"))
+        .arg(child.id().to_string())
+        .status()
+        .expect("kill should run");
+    assert!(status.success(), "kill -{signal} failed");
+}
+
+#[cfg(unix)]
+#[test]
+fn echo_sigterm_sends_graceful_shutdown_to_connected_client() {
+    let dir = unique_ipc_dir("echo-sigterm");
+    let sock_path = unique_ipc_path(&dir, "echo");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let mut peer = wait_for_connect(&sock_path, &[COMMAND], Duration::from_secs(5))
+        .expect("client should connect to echo server");
+    peer.send(COMMAND, b"hello").expect("frame should send");
+    let echoed = peer.recv_on(COMMAND).expect("frame should be echoed");
+    assert_eq!(echoed.payload.as_ref(), b"hello");
+
+    send_signal(&child, "TERM");
+
+    // The request is acked inside `recv`, which may then already see the
+    // server close; a shutdown request leaves the peer marked either way.
+    assert!(matches!(
+        peer.recv(),
+        Err(ipcprims_peer::PeerError::Disconnected(_))
+    ));
+    match peer.recv() {
+        Err(ipcprims_peer::PeerError::Disconnected(reason)) => {
+            assert_eq!(reason, "shutdown requested")
+        }
+        other => panic!("expected a graceful shutdown request, got {other:?}"),
+    }
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    assert!(!sock_path.exists(), "socket file should be removed");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn listen_sighup_while_idle_unbinds_and_exits() {
+    let dir = unique_ipc_dir("listen-sighup");
+    let sock_path = unique_ipc_path(&dir, "listen");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("listen")
+        .arg(&sock_path)
+        .arg("--on-signal")
+        .arg("immediate")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("listen command should start");
+
+    drop(
+        wait_for_connect(&sock_path, &[COMMAND], Duration::from_secs(5))
+            .expect("listener should accept connections"),
+    );
+    send_signal(&child, "HUP");
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    assert!(!sock_path.exists(), "socket file should be removed");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn echo_sigint_while_waiting_for_a_client_exits_promptly() {
+    let dir = unique_ipc_dir("echo-sigint");
+    let sock_path = unique_ipc_path(&dir, "echo");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !sock_path.exists() {
+        assert!(Instant::now() < deadline, "echo server never bound");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    send_signal(&child, "INT");
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(2));
+    assert_eq!(status.code(), Some(0));
+    assert!(!sock_path.exists(), "socket file should be removed");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn echo_socket_mode_and_group_flags_set_up_the_path() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let dir = unique_ipc_dir("echo-socket-mode");
+    let sock_path = unique_ipc_path(&dir, "echo");
+    let gid = std::fs::metadata(&dir).expect("dir should exist").gid();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--socket-mode")
+        .arg("660")
+        .arg("--socket-group")
+        .arg(gid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    drop(
+        wait_for_connect(&sock_path, &[COMMAND], Duration::from_secs(5))
+            .expect("client should connect to echo server"),
+    );
+    let metadata = std::fs::metadata(&sock_path).expect("socket path should exist");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o660);
+    assert_eq!(metadata.gid(), gid);
+    send_signal(&child, "INT");
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+
+    // Clap rejects a bad mode itself (exit 2); an unknown group fails the bind.
+    for (flag, value, code, expected) in [
+        ("--socket-mode", "988", 2, "not an octal permission mode"),
+        (
+            "--socket-group",
+            "ipcprims-no-such-group",
+            64,
+            "no group named",
+        ),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("echo")
+            .arg(&sock_path)
+            .arg(flag)
+            .arg(value)
+            .output()
+            .expect("echo should run");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(code), "{stderr}");
+        assert!(stderr.contains(expected), "{stderr}");
+    }
+    assert!(!sock_path.exists(), "failed binds should leave no socket");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,12 +1,10 @@
 #![cfg(feature = "cli")]
 
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use ipcprims_peer::connect;
-
 #[cfg(unix)]
 fn unique_ipc_path(tag: &str) -> PathBuf {
     let dir = PathBuf::from(format!(
@@ -57,14 +55,31 @@ fn nonexistent_ipc_path() -> PathBuf {
     ))
 }
 
-fn wait_for_connect(path: &Path, channels: &[u16], timeout: Duration) {
+fn wait_for_connect(path: &Path, channels: &[u16]) -> ipcprims::peer::Peer {
+    let policy = ipcprims::peer::RetryPolicy {
+        max_duration: Duration::from_secs(5),
+        ..ipcprims::peer::RetryPolicy::default()
+    };
+    ipcprims::peer::connect_with_retry(
+        path,
+        channels,
+        &ipcprims::peer::HandshakeConfig::default(),
+        &policy,
+        None,
+    )
+    .unwrap_or_else(|err| panic!("echo server did not come up: {err}"))
+}
+
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> ExitStatus {
     let start = Instant::now();
     loop {
-        if connect(path, channels).is_ok() {
-            return;
+        if let Some(status) = child.try_wait().expect("child status should be readable") {
+            return status;
         }
         if start.elapsed() >= timeout {
-            panic!("connect timeout");
+            let _ = child.kill();
+            let _ = child.wait();
+            panic!("child did not exit within {timeout:?}");
         }
         thread::sleep(Duration::from_millis(25));
     }
@@ -79,13 +94,13 @@ fn info_against_echo_server_outputs_connection_data() {
         .arg("error")
         .arg("echo")
         .arg(&sock_path)
+        .arg("--once")
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
         .spawn()
         .expect("echo command should start");
 
-    wait_for_connect(&sock_path, &[1], Duration::from_secs(5));
-
+    // `info` retries until the listener is up, so it is the only client `--once` serves.
     let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
         .arg("--log-level")
         .arg("error")
@@ -100,11 +115,163 @@ fn info_against_echo_server_outputs_connection_data() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("connection-info.schema.json"));
     assert!(stdout.contains("\"connected\":true"));
+    assert!(stdout.contains(&format!(
+        "\"remote_library_version\":\"{}\"",
+        env!("CARGO_PKG_VERSION")
+    )));
+    #[cfg(unix)]
+    {
+        let info: serde_json::Value =
+            serde_json::from_str(stdout.trim()).expect("info should print json");
+        assert_eq!(info["remote_addr"], sock_path.display().to_string());
+    }
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn info_expect_version_mismatch_returns_data_invalid() {
+    let sock_path = unique_ipc_path("info-expect");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("info")
+        .arg(&sock_path)
+        .arg("--expect-version")
+        .arg("9.0")
+        .arg("--expect-channels")
+        .arg("1,2")
+        .output()
+        .expect("info should run");
+
+    assert_eq!(output.status.code(), Some(60));
+    let payload: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .expect("info should emit json");
+    assert_eq!(payload["expectations"]["version_ok"], false);
+    assert_eq!(payload["expectations"]["channels_ok"], true);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expectation not met"));
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+}
+
+#[cfg(unix)]
+#[test]
+fn info_require_owner_refuses_a_server_running_as_another_uid() {
+    use std::os::unix::fs::MetadataExt;
+
+    let sock_path = unique_ipc_path("info-owner");
+    let me = std::fs::metadata(sock_path.parent().expect("socket path has a parent"))
+        .expect("socket dir should exist")
+        .uid();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let info = |uid: u32| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("info")
+            .arg(&sock_path)
+            .arg("--require-owner")
+            .arg(uid.to_string())
+            .output()
+            .expect("info should run")
+    };
+
+    assert!(info(me).status.success());
+    assert_eq!(
+        wait_for_exit(&mut child, Duration::from_secs(5)).code(),
+        Some(0)
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("echo command should start");
+    let refused = info(me.wrapping_add(1));
+    assert_eq!(refused.status.code(), Some(50));
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("refusing to connect"), "{stderr}");
 
     let _ = child.kill();
     let _ = child.wait();
 }
 
+#[test]
+fn channel_arguments_accept_builtin_names() {
+    let sock_path = unique_ipc_path("channel-names");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .arg("--channels")
+        .arg("command,DATA")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("info")
+        .arg(&sock_path)
+        .arg("--expect-channels")
+        .arg("Command,2")
+        .output()
+        .expect("info should run");
+
+    assert_eq!(output.status.code(), Some(0));
+    let payload: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .expect("info should emit json");
+    assert_eq!(payload["expectations"]["channels_ok"], true);
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("send")
+        .arg(nonexistent_ipc_path())
+        .arg("--channel")
+        .arg("metrics")
+        .arg("--data")
+        .arg("x")
+        .output()
+        .expect("send should run");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'metrics' is not a channel ID"));
+}
+
 #[test]
 fn info_timeout_returns_124() {
     let missing = nonexistent_ipc_path();
@@ -153,3 +320,418 @@ fn envinfo_reports_version() {
         Some(env!("CARGO_PKG_VERSION"))
     );
 }
+
+#[test]
+fn channels_lists_builtin_and_schema_channels() {
+    let schema_dir = std::env::temp_dir().join(format!(
+        "ipcprims-cli-channels-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time should be after epoch")
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&schema_dir).expect("temp dir should be creatable");
+    std::fs::write(
+        schema_dir.join("channel_300.schema.json"),
+        r#"{"title":"Job Update","type":"object"}"#,
+    )
+    .expect("schema should be writable");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("channels")
+        .arg("--schema-dir")
+        .arg(&schema_dir)
+        .output()
+        .expect("channels should run");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("channel-list.schema.json"));
+    let payload: serde_json::Value =
+        serde_json::from_str(&stdout).expect("channels should emit json");
+    let channels = payload
+        .get("channels")
+        .and_then(|v| v.as_array())
+        .expect("channels array");
+    // Six built-ins, HEARTBEAT included, plus the schema's user channel.
+    assert_eq!(channels.len(), 7);
+    assert!(channels
+        .iter()
+        .any(|c| c.get("name").and_then(|v| v.as_str()) == Some("HEARTBEAT")));
+    let user = channels
+        .iter()
+        .find(|c| c.get("id").and_then(|v| v.as_u64()) == Some(300))
+        .expect("user channel should be listed");
+    assert_eq!(
+        user.get("schema_title").and_then(|v| v.as_str()),
+        Some("Job Update")
+    );
+
+    let _ = std::fs::remove_dir_all(&schema_dir);
+}
+
+#[test]
+fn malformed_config_returns_usage_and_no_config_bypasses_it() {
+    let dir = std::env::temp_dir().join(format!(
+        "ipcprims-cli-config-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time should be after epoch")
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&dir).expect("temp dir should be creatable");
+    let config_path = dir.join("config.toml");
+    std::fs::write(&config_path, "log_level = \"loud\"\n").expect("config should be writable");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .env("IPCPRIMS_CONFIG", &config_path)
+        .arg("envinfo")
+        .output()
+        .expect("envinfo should run");
+    assert_eq!(output.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`log_level`"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .env("IPCPRIMS_CONFIG", &config_path)
+        .arg("--no-config")
+        .arg("--format")
+        .arg("json")
+        .arg("envinfo")
+        .output()
+        .expect("envinfo should run");
+    assert!(output.status.success());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn selftest_passes_on_clean_env() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("selftest")
+        .output()
+        .expect("selftest should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "selftest failed: {stdout}");
+    assert!(stdout.contains("selftest-report.schema.json"));
+    assert!(stdout.contains("\"overall\":\"pass\""));
+}
+
+#[test]
+fn send_wait_json_base64_roundtrips_binary_payload() {
+    use base64::Engine;
+
+    let sock_path = unique_ipc_path("base64");
+    let payload_path = std::env::temp_dir().join(format!(
+        "ipcprims-cli-binary-{}-{}.bin",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time should be after epoch")
+            .as_nanos()
+    ));
+    let payload: Vec<u8> = vec![0xff, 0xfe, 0x00, 0x80, b'o', b'k'];
+    std::fs::write(&payload_path, &payload).expect("payload should be writable");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    // `send` retries the connect until the listener is up.
+    let output = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("send")
+        .arg(&sock_path)
+        .arg("--connect-timeout")
+        .arg("5s")
+        .arg("--channel")
+        .arg("2")
+        .arg("--file")
+        .arg(&payload_path)
+        .arg("--wait")
+        .output()
+        .expect("send should run");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("send should emit json");
+    assert_eq!(json["payload_encoding"], "base64");
+    assert!(json["schema_id"]
+        .as_str()
+        .is_some_and(|id| id.ends_with("/v2/frame-received.schema.json")));
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(
+            json["payload"]
+                .as_str()
+                .expect("payload should be a string"),
+        )
+        .expect("payload should be base64");
+    assert_eq!(decoded, payload);
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    let _ = std::fs::remove_file(&payload_path);
+}
+
+#[test]
+fn echo_require_token_accepts_matching_and_rejects_others() {
+    use std::io::Read;
+
+    const SECRET: &str = "s3cret-token-value";
+    let sock_path = unique_ipc_path("auth");
+    let token_file = std::env::temp_dir().join(format!(
+        "ipcprims-cli-token-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time should be after epoch")
+            .as_nanos()
+    ));
+    std::fs::write(&token_file, format!("{SECRET}\n")).expect("token file should be writable");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("warn")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--require-token-file")
+        .arg(&token_file)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let send = |token: Option<&str>| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_ipcprims"));
+        cmd.arg("--format")
+            .arg("json")
+            .arg("send")
+            .arg(&sock_path)
+            .arg("--data")
+            .arg("hi")
+            .arg("--wait");
+        if let Some(token) = token {
+            cmd.arg("--auth-token").arg(token);
+        }
+        cmd.output().expect("send should run")
+    };
+
+    let accepted = send(Some(SECRET));
+    assert!(
+        accepted.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&accepted.stderr)
+    );
+
+    let wrong = send(Some("not-the-token"));
+    assert_eq!(wrong.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&wrong.stderr).contains("during handshake"));
+
+    let missing = send(None);
+    assert_eq!(missing.status.code(), Some(1));
+
+    let info = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--format")
+        .arg("json")
+        .arg("info")
+        .arg(&sock_path)
+        .arg("--auth-token-file")
+        .arg(&token_file)
+        .output()
+        .expect("info should run");
+    assert!(info.status.success());
+    assert!(!String::from_utf8_lossy(&info.stdout).contains(SECRET));
+
+    let _ = child.kill();
+    let _ = child.wait();
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .expect("stderr should be piped")
+        .read_to_string(&mut stderr)
+        .expect("stderr should be readable");
+    assert!(stderr.contains("auth token mismatch"));
+    assert!(stderr.contains("no auth token presented"));
+    assert!(!stderr.contains(SECRET));
+    assert!(!stderr.contains("not-the-token"));
+
+    let _ = std::fs::remove_file(&token_file);
+}
+
+#[test]
+fn echo_max_payload_rejects_oversized_frame_with_error_reply() {
+    use ipcprims::frame::{DATA, ERROR};
+
+    let sock_path = unique_ipc_path("maxpayload");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--max-payload")
+        .arg("8k")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("echo command should start");
+
+    let mut peer = wait_for_connect(&sock_path, &[DATA, ERROR]);
+
+    peer.send(DATA, b"small")
+        .expect("small payload should send");
+    let echoed = peer.recv_on(DATA).expect("small payload should echo");
+    assert_eq!(echoed.payload.as_ref(), b"small");
+
+    peer.send(DATA, &vec![b'x'; 64 * 1024])
+        .expect("oversized payload should send");
+    let reply = peer.recv_on(ERROR).expect("server should reply on ERROR");
+    let value: serde_json::Value =
+        serde_json::from_slice(reply.payload.as_ref()).expect("error reply should be json");
+    assert_eq!(value["error"], "payload too large");
+    assert_eq!(value["size"], 64 * 1024);
+    assert_eq!(value["max"], 8 * 1024);
+
+    // The server stays up for other clients.
+    let mut next =
+        ipcprims::peer::connect(&sock_path, &[DATA]).expect("server should still accept");
+    next.send(DATA, b"again").expect("send should succeed");
+    assert_eq!(next.recv_on(DATA).expect("echo").payload.as_ref(), b"again");
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[test]
+fn echo_stats_prints_listener_metrics_on_exit() {
+    use ipcprims::frame::DATA;
+
+    let sock_path = unique_ipc_path("stats");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("--log-level")
+        .arg("error")
+        .arg("--format")
+        .arg("json")
+        .arg("echo")
+        .arg(&sock_path)
+        .arg("--once")
+        .arg("--stats")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("echo command should start");
+
+    let mut peer = wait_for_connect(&sock_path, &[DATA]);
+    peer.send(DATA, b"hi").expect("send should succeed");
+    assert_eq!(peer.recv_on(DATA).expect("echo").payload.as_ref(), b"hi");
+    drop(peer);
+
+    let status = wait_for_exit(&mut child, Duration::from_secs(5));
+    assert_eq!(status.code(), Some(0));
+    let mut stderr = String::new();
+    std::io::Read::read_to_string(&mut child.stderr.take().unwrap(), &mut stderr).unwrap();
+    let stats: serde_json::Value =
+        serde_json::from_str(stderr.lines().last().expect("stats line should be printed"))
+            .expect("stats should be json");
+    assert_eq!(stats["accepted"], 1);
+    assert_eq!(stats["handshake_failures"], 0);
+    assert!(stats["last_accept_at"].as_u64().is_some());
+}
+
+#[test]
+fn version_check_peer_reports_compatibility() {
+    use ipcprims::peer::{HandshakeConfig, PeerListener};
+
+    let check = |sock: &PathBuf| {
+        Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+            .arg("--format")
+            .arg("json")
+            .arg("version")
+            .arg("--check-peer")
+            .arg(sock)
+            .output()
+            .expect("version should run")
+    };
+    let serve_one = |version: &str| {
+        let sock = unique_ipc_path(&format!("version-{version}"));
+        let listener = PeerListener::bind(&sock)
+            .expect("listener should bind")
+            .with_handshake_config(HandshakeConfig {
+                protocol_version: version.to_string(),
+                ..HandshakeConfig::default()
+            });
+        let server = thread::spawn(move || {
+            let _ = listener.accept();
+        });
+        (sock, server)
+    };
+
+    let (sock, server) = serve_one("1.0");
+    let output = check(&sock);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("version should emit json");
+    assert!(report["schema_id"]
+        .as_str()
+        .is_some_and(|id| id.ends_with("/version.schema.json")));
+    assert_eq!(report["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(report["peer_check"]["compatible"], true);
+    assert_eq!(report["peer_check"]["remote_protocol_version"], "1.0");
+    server.join().expect("server thread should finish");
+
+    let (sock, server) = serve_one("2.0");
+    let output = check(&sock);
+    assert_eq!(output.status.code(), Some(60));
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("version should emit json");
+    assert_eq!(report["peer_check"]["compatible"], false);
+    server.join().expect("server thread should finish");
+
+    let plain = Command::new(env!("CARGO_BIN_EXE_ipcprims"))
+        .arg("version")
+        .output()
+        .expect("version should run");
+    assert_eq!(
+        String::from_utf8_lossy(&plain.stdout).trim(),
+        format!("ipcprims {}", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn overlong_socket_path_is_usage_error() {
+    let long_path = format!("/tmp/{}.sock", "a".repeat(200));
+
+    for command in ["send", "info"] {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_ipcprims"));
+        cmd.arg(command).arg(&long_path);
+        if command == "send" {
+            cmd.arg("--channel").arg("1").arg("--data").arg("{}");
+        }
+        let output = cmd.output().expect("command should run");
+
+        assert_eq!(output.status.code(), Some(64), "{command}");
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("socket path too long"),
+            "{command}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
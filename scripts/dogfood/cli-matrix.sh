@@ -70,7 +70,7 @@ wait_for_ready() {
 		max_attempts=200
 	fi
 	for _ in $(seq 1 $max_attempts); do
-		if "$BIN" info "$sock" --timeout 1s >/dev/null 2>&1; then
+		if "$BIN" info "$sock" --connect-timeout 1s >/dev/null 2>&1; then
 			return 0
 		fi
 		sleep 0.1
@@ -124,7 +124,7 @@ else
 	MISSING_ADDR="$TMP_DIR/missing.sock"
 fi
 set +e
-"$BIN" info "$MISSING_ADDR" --timeout 1s >/dev/null 2>&1
+"$BIN" info "$MISSING_ADDR" --connect-timeout 1s >/dev/null 2>&1
 code=$?
 set -e
 if [[ "$code" -ne 124 ]]; then